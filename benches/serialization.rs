@@ -0,0 +1,71 @@
+//! Encode and decode time of a `get_users` response in each serialization format
+//!
+//! The app is a binary crate, so the serialization module is compiled into
+//! this bench from its source file. Event bus throughput depends on the rest
+//! of the app and is measured in-process by `/api/devtools/bench` instead.
+//!
+//!     cargo bench --bench serialization
+//!     cargo bench --bench serialization --features all-formats
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+// Its tests are compiled without their #[test] functions here
+#[allow(dead_code, unused_imports)]
+#[path = "../src/infrastructure/serialization/serialization.rs"]
+mod serialization;
+
+// `crate::core::domain::clock` for the module above, likewise without tests
+#[allow(dead_code, unused_imports)]
+#[path = "../src/core"]
+mod core {
+    pub mod domain {
+        pub mod clock;
+    }
+}
+
+use serialization::{sample_users_message, SerializationEngine, SerializationFormat};
+
+const USER_COUNTS: [usize; 3] = [10, 100, 1000];
+
+/// Formats this build can encode
+fn formats() -> Vec<SerializationFormat> {
+    let sample = sample_users_message(1);
+    [SerializationFormat::Json, SerializationFormat::MessagePack, SerializationFormat::Cbor]
+        .into_iter()
+        .filter(|format| SerializationEngine::new(*format).serialize(&sample).is_ok())
+        .collect()
+}
+
+fn encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_users");
+    for users in USER_COUNTS {
+        let message = sample_users_message(users);
+        group.throughput(Throughput::Elements(users as u64));
+        for format in formats() {
+            let engine = SerializationEngine::new(format);
+            group.bench_with_input(BenchmarkId::new(format.as_str(), users), &message, |b, message| {
+                b.iter(|| engine.serialize(black_box(message)).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_users");
+    for users in USER_COUNTS {
+        let message = sample_users_message(users);
+        group.throughput(Throughput::Elements(users as u64));
+        for format in formats() {
+            let engine = SerializationEngine::new(format);
+            let bytes = engine.serialize(&message).unwrap();
+            group.bench_with_input(BenchmarkId::new(format.as_str(), users), &bytes, |b, bytes| {
+                b.iter(|| engine.deserialize(black_box(bytes)).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode);
+criterion_main!(benches);
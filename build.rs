@@ -9,6 +9,10 @@ fn main() {
     // Generate build configuration
     generate_build_config(&project_dir);
 
+    // gRPC service code for `--features grpc`
+    #[cfg(feature = "grpc")]
+    compile_protos(&project_dir);
+
     // Path to the C library source
     let src_dir = format!("{}/thirdparty/webui-c-src/src", project_dir);
     let civetweb_dir = format!("{}/civetweb", src_dir);
@@ -75,10 +79,57 @@ fn main() {
     }
 }
 
-fn generate_build_config(_project_dir: &str) {
+/// Output of `git <args>` in the project, `None` outside a checkout or without git
+fn git(project_dir: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(project_dir)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn generate_build_config(project_dir: &str) {
     // Get package name from environment
     let package_name = env::var("CARGO_PKG_NAME").unwrap_or_else(|_| "rustwebui-app".to_string());
     let executable_name = package_name.clone(); // Use package name as executable name
+    let version = env::var("CARGO_PKG_VERSION").unwrap_or_else(|_| "1.0.0".to_string());
+
+    // Commit of the checkout; a new commit or checkout regenerates the file
+    let git_commit = git(project_dir, &["rev-parse", "HEAD"]).filter(|commit| !commit.is_empty());
+    let git_dirty = git(project_dir, &["status", "--porcelain", "--untracked-files=no"])
+        .is_some_and(|status| !status.is_empty());
+    if let Some(git_dir) = git(project_dir, &["rev-parse", "--absolute-git-dir"]) {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        if let Some(reference) = git(project_dir, &["symbolic-ref", "-q", "HEAD"]) {
+            println!("cargo:rerun-if-changed={}/{}", git_dir, reference);
+        }
+    }
+
+    // SOURCE_DATE_EPOCH keeps reproducible builds reproducible
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let build_timestamp = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .and_then(|epoch| chrono::DateTime::from_timestamp(epoch, 0))
+        .unwrap_or_else(chrono::Utc::now)
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+
+    // Features declared in Cargo.toml, without `default` and optional dependencies;
+    // CARGO_FEATURE_OUTBOX_WEBHOOK is outbox-webhook
+    let declared: Vec<String> = fs::read_to_string(format!("{}/Cargo.toml", env::var("CARGO_MANIFEST_DIR").unwrap()))
+        .ok()
+        .and_then(|manifest| manifest.parse::<toml::Table>().ok())
+        .and_then(|manifest| manifest.get("features")?.as_table().map(|features| features.keys().cloned().collect()))
+        .unwrap_or_default();
+    let mut features: Vec<String> = declared
+        .into_iter()
+        .filter(|feature| feature != "default")
+        .filter(|feature| env::var_os(format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"))).is_some())
+        .collect();
+    features.sort();
 
     // Generate the build config file
     let out_dir = env::var("OUT_DIR").unwrap();
@@ -95,10 +146,46 @@ pub const EXECUTABLE_NAME: &str = "{}";
 pub fn get_executable_name() -> &'static str {{
     EXECUTABLE_NAME
 }}
+
+/// What this binary was built from, served by `/api/version` and `devtools.info`
+#[derive(Debug, Clone, Copy, serde::Serialize, utoipa::ToSchema)]
+pub struct BuildInfo {{
+    pub name: &'static str,
+    pub version: &'static str,
+    /// Commit the binary was built from; `null` when built outside a git checkout
+    pub git_commit: Option<&'static str>,
+    /// Whether tracked files had uncommitted changes
+    pub git_dirty: bool,
+    /// RFC 3339, `SOURCE_DATE_EPOCH` when it is set
+    pub build_timestamp: &'static str,
+    /// Enabled cargo features
+    pub features: &'static [&'static str],
+    /// Target triple, e.g. `x86_64-unknown-linux-gnu`
+    pub target: &'static str,
+    /// `debug` or `release`
+    pub profile: &'static str,
+}}
+
+pub const BUILD_INFO: BuildInfo = BuildInfo {{
+    name: PACKAGE_NAME,
+    version: PACKAGE_VERSION,
+    git_commit: {:?},
+    git_dirty: {},
+    build_timestamp: {:?},
+    features: &{:?},
+    target: {:?},
+    profile: {:?},
+}};
 "#,
         package_name,
-        env::var("CARGO_PKG_VERSION").unwrap_or_else(|_| "1.0.0".to_string()),
-        executable_name
+        version,
+        executable_name,
+        git_commit,
+        git_dirty,
+        build_timestamp,
+        features,
+        env::var("TARGET").unwrap_or_default(),
+        env::var("PROFILE").unwrap_or_default(),
     );
 
     if let Err(e) = fs::write(&build_config_path, build_config) {
@@ -107,3 +194,16 @@ pub fn get_executable_name() -> &'static str {{
         println!("Generated build config at: {}", build_config_path);
     }
 }
+
+#[cfg(feature = "grpc")]
+fn compile_protos(project_dir: &str) {
+    // Use the bundled protoc so no system install is needed
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("No bundled protoc for this platform");
+    env::set_var("PROTOC", protoc);
+
+    let proto_dir = format!("{}/proto", project_dir);
+    tonic_build::configure()
+        .compile_protos(&[format!("{}/app.proto", proto_dir)], std::slice::from_ref(&proto_dir))
+        .expect("Failed to compile proto/app.proto");
+    println!("cargo:rerun-if-changed={}", proto_dir);
+}
@@ -34,6 +34,12 @@ export default defineConfig({
     open: true,
     hmr: true, // Enable hot module replacement
   },
+  dev: {
+    // Open the HMR socket to this server even when the page is proxied by the backend ([dev] proxy_url)
+    client: {
+      port: 3000,
+    },
+  },
   performance: {
     chunkSplit: false, // Disable for faster incremental builds
   },
@@ -27,6 +27,7 @@ export default defineConfig({
       css: '[name].[contenthash:8].css',
     },
     cleanDistPath: true,
+    manifest: true, // dist/manifest.json, read by the backend for /api/assets and cache headers
   },
   server: {
     port: 3000,
@@ -12,6 +12,7 @@ export interface User {
   status: 'active' | 'inactive' | 'pending' | 'suspended';
   created_at: string;
   updated_at?: string;
+  version: number;
 }
 
 export interface DatabaseStats {
@@ -30,13 +31,18 @@ export interface Counter {
 
 export interface SystemInfo {
   platform: string;
-  user_agent: string;
-  language: string;
-  screen_resolution: string;
-  memory_gb?: number;
-  cpu_cores?: number;
-  online: boolean;
-  timestamp: string;
+  arch: string;
+  os_name?: string;
+  os_version?: string;
+  kernel_version?: string;
+  hostname?: string;
+  cpu_model?: string;
+  cpu_cores: number;
+  physical_cores?: number;
+  total_memory_bytes: number;
+  used_memory_bytes: number;
+  memory_usage_percent: number;
+  uptime_secs: number;
 }
 
 export interface Todo {
@@ -22,6 +22,7 @@ describe('ErrorCode', () => {
     expect(ErrorCode.HANDLER_ERROR).toBe(3002);
     expect(ErrorCode.UI_ERROR).toBe(4000);
     expect(ErrorCode.COMMUNICATION_ERROR).toBe(4001);
+    expect(ErrorCode.SESSION_EXPIRED).toBe(4002);
     expect(ErrorCode.PLUGIN_ERROR).toBe(5000);
     expect(ErrorCode.PLUGIN_NOT_FOUND).toBe(5001);
     expect(ErrorCode.UNKNOWN).toBe(9999);
@@ -40,12 +40,14 @@ export enum ErrorCode {
   VALIDATION_FAILED = 1001,
   BUSINESS_RULE_VIOLATION = 1002,
   INVALID_STATE_TRANSITION = 1003,
+  CONFLICT_ERROR = 1004,
   
   // Infrastructure Errors (2000-2999)
   DATABASE_ERROR = 2000,
   CONNECTION_FAILED = 2001,
   TIMEOUT = 2002,
   SERIALIZATION_ERROR = 2003,
+  SERVICE_UNAVAILABLE = 2004,
   
   // Application Errors (3000-3999)
   COMMAND_FAILED = 3000,
@@ -55,11 +57,13 @@ export enum ErrorCode {
   // Presentation Errors (4000-4999)
   UI_ERROR = 4000,
   COMMUNICATION_ERROR = 4001,
+  SESSION_EXPIRED = 4002,
   
   // Plugin Errors (5000-5999)
   PLUGIN_ERROR = 5000,
   PLUGIN_NOT_FOUND = 5001,
   PLUGIN_CAPABILITY_NOT_FOUND = 5002,
+  ACCESS_DENIED = 5003,
   
   // Unknown
   UNKNOWN = 9999,
@@ -120,6 +124,25 @@ export class AppError extends Error {
     return new AppError({ code, message });
   }
 
+  /**
+   * Rebuild an error from a backend failure envelope
+   * (`{ success: false, error: { code_value, message, context, recovery, ... } }`)
+   */
+  static fromResponse(response: any): AppError {
+    const error = response?.error ?? {};
+    if (typeof error === 'string') {
+      return AppError.create(ErrorCode.UNKNOWN, error);
+    }
+    return new AppError({
+      id: error.id,
+      code: error.code_value in ErrorCode ? error.code_value : ErrorCode.UNKNOWN,
+      message: error.message ?? 'Unknown error',
+      context: error.context,
+      timestamp: error.timestamp,
+      recovery: error.recovery,
+    });
+  }
+
   /**
    * Add context to the error
    */
@@ -230,6 +253,8 @@ export class AppError extends Error {
         return `Validation failed: ${this.message}`;
       case ErrorCode.BUSINESS_RULE_VIOLATION:
         return this.message;
+      case ErrorCode.CONFLICT_ERROR:
+        return 'This item was changed elsewhere. Reload it and try again.';
       case ErrorCode.DATABASE_ERROR:
         return 'A database error occurred. Please try again.';
       case ErrorCode.CONNECTION_FAILED:
@@ -238,6 +263,8 @@ export class AppError extends Error {
         return 'The operation timed out. Please try again.';
       case ErrorCode.SERIALIZATION_ERROR:
         return 'Data format error. Please refresh.';
+      case ErrorCode.SERVICE_UNAVAILABLE:
+        return 'This service is temporarily unavailable. Please try again shortly.';
       case ErrorCode.COMMAND_FAILED:
       case ErrorCode.QUERY_FAILED:
       case ErrorCode.HANDLER_ERROR:
@@ -246,10 +273,14 @@ export class AppError extends Error {
         return 'Display error. Please refresh the page.';
       case ErrorCode.COMMUNICATION_ERROR:
         return 'Communication error. Please check connection.';
+      case ErrorCode.SESSION_EXPIRED:
+        return 'Your session has ended. Reconnect to start a new one.';
       case ErrorCode.PLUGIN_ERROR:
       case ErrorCode.PLUGIN_NOT_FOUND:
       case ErrorCode.PLUGIN_CAPABILITY_NOT_FOUND:
         return 'Feature unavailable. Please contact support.';
+      case ErrorCode.ACCESS_DENIED:
+        return 'This plugin is not allowed to do that.';
       default:
         return this.message;
     }
@@ -0,0 +1,1887 @@
+// Generated by `app --generate-client` from the backend command catalog.
+// Do not edit; run `bun run generate:client` after changing commands or events.
+
+import type { RecoveryAction } from '../core/error-handling/app-error';
+import { EventBus } from '../models/event-bus';
+import { requestFromBackend } from '../view-models/communication-bridge';
+
+export interface ProtocolOffer {
+  version: string;
+  protocol_versions: number[];
+  formats: string[];
+  compression: string[];
+  features: string[];
+  build_features: string[];
+  locales: string[];
+}
+
+export interface FileUpload {
+  upload_id: string;
+  path: string;
+  size: number;
+  chunk_size: number;
+}
+
+export interface UploadedFile {
+  path: string;
+  size: number;
+  sha256: string;
+}
+
+export interface User {
+  id: number;
+  name: string;
+  email: string;
+  role: string;
+  version: number;
+}
+
+export interface Counter {
+  id: string;
+  value: number;
+  label: string;
+  created_at: string;
+  updated_at: string;
+  version: number;
+}
+
+export interface CounterEvent {
+  sequence: number;
+  recorded_at: string;
+  type: 'CounterCreated' | 'CounterIncremented' | 'CounterDecremented' | 'CounterReset' | 'CounterSet';
+  label?: string;
+  value?: number;
+}
+
+export interface Task {
+  id: string;
+  name: string;
+  status: 'running' | 'completed' | 'failed' | 'cancelled';
+  progress: number;
+  message: string | null;
+  started_at: string;
+  finished_at: string | null;
+  result: unknown;
+  error: string | null;
+}
+
+export interface SystemInfo {
+  platform: string;
+  arch: string;
+  os_name: string | null;
+  os_version: string | null;
+  kernel_version: string | null;
+  hostname: string | null;
+  cpu_model: string | null;
+  cpu_cores: number;
+  physical_cores: number | null;
+  total_memory_bytes: number;
+  used_memory_bytes: number;
+  memory_usage_percent: number;
+  uptime_secs: number;
+}
+
+export interface MetricsSample {
+  timestamp: string;
+  cpu_percent: number;
+  per_core_percent: number[];
+  total_memory_bytes: number;
+  used_memory_bytes: number;
+  total_swap_bytes: number;
+  used_swap_bytes: number;
+  app_process: Record<string, unknown> | null;
+  top_processes: Record<string, unknown>[];
+}
+
+export interface JobRun {
+  job: string;
+  trigger: 'schedule' | 'manual';
+  started_at: string;
+  finished_at: string;
+  attempts: number;
+  success: boolean;
+  message: string | null;
+  error: string | null;
+}
+
+export interface DialogFilter {
+  name: string;
+  extensions: string[];
+}
+
+export interface Watch {
+  id: string;
+  path: string;
+}
+
+export interface PluginMetadata {
+  id: string;
+  name: string;
+  version: string;
+  description: string;
+  author: string;
+  dependencies: string[];
+}
+
+export interface ApiTokenClaims {
+  sub: string;
+  name: string;
+  role: 'admin' | 'user' | 'editor' | 'viewer';
+  iss: string;
+  iat: number;
+  exp: number;
+}
+
+export interface Setting {
+  namespace: string;
+  key: string;
+  value: unknown;
+  user_id: number | null;
+  updated_at: string;
+}
+
+export interface HistoryEntry {
+  id: number;
+  label: string;
+  at: string;
+  operation: { kind: string } & Record<string, unknown>;
+}
+
+export interface OutboxMessage {
+  id: string;
+  event: string;
+  payload: Record<string, unknown>;
+  created_at: string;
+  attempts: number;
+  last_error: string | null;
+}
+
+export interface Webhook {
+  id: string;
+  url: string;
+  events: string[];
+  created_at: string;
+}
+
+export interface WebhookDelivery {
+  id: string;
+  webhook_id: string;
+  event_id: string;
+  event: string;
+  payload: unknown;
+  status: 'pending' | 'delivered' | 'failed';
+  attempts: number;
+  response_status: number | null;
+  last_error: string | null;
+  created_at: string;
+  next_attempt_at: string | null;
+  delivered_at: string | null;
+}
+
+export interface Release {
+  version: string;
+  notes: string | null;
+  url: string | null;
+  published_at: string | null;
+}
+
+export interface UpdateStatus {
+  current_version: string;
+  latest: Release | null;
+  update_available: boolean;
+  last_checked: string | null;
+  last_error: string | null;
+  installed: string | null;
+}
+
+export interface UsageDay {
+  day: string;
+  total: number;
+  commands: Record<string, number>;
+  windows: Record<string, number>;
+}
+
+export interface UsageUpload {
+  opted_in: boolean;
+  available: boolean;
+  uploaded_through: string | null;
+}
+
+export interface SessionResume {
+  status: 'replayed' | 'refresh';
+  events?: number;
+  reason?: 'events_lost' | 'buffer_expired' | 'session_ended' | 'resume_disabled';
+}
+
+export interface UserSession {
+  id: string;
+  client: string | null;
+  created_at: string;
+  last_seen_at: string;
+  expires_at: string;
+  locale: string | null;
+}
+
+export interface JsonPatchOperation {
+  op: 'add' | 'remove' | 'replace' | 'move' | 'copy' | 'test';
+  path: string;
+  from?: string;
+  value?: unknown;
+}
+
+export interface ClientProfile {
+  id: string;
+  name: string | null;
+  kind: string;
+  metadata: unknown;
+  session: string | null;
+  address: string | null;
+  joined_at: string;
+}
+
+export interface HotkeyBinding {
+  accelerator: string;
+  action: string | null;
+  command: string | null;
+  payload: Record<string, unknown>;
+  source: 'config' | 'runtime';
+}
+
+export interface LaunchAtLogin {
+  enabled: boolean;
+  preference: boolean | null;
+  supported: boolean;
+  location: string | null;
+}
+
+/** Error envelope of a failed command */
+export interface CommandErrorInfo {
+  code: string;
+  code_value: number;
+  message: string;
+  user_message: string;
+  recovery: RecoveryAction;
+  id: string;
+  timestamp: string;
+  context: Record<string, unknown>;
+}
+
+export class CommandError extends Error {
+  constructor(readonly command: string, readonly info: CommandErrorInfo) {
+    super(info.message);
+    this.name = 'CommandError';
+  }
+}
+
+/**
+ * Send `name` and resolve with its response; failures reject with a `CommandError`.
+ * With a `mutationId`, the backend also reports it as `command.accepted`, then `command.applied` or `command.rejected`.
+ */
+export async function call<T>(name: string, payload: object = {}, mutationId?: string): Promise<T> {
+  const response = await requestFromBackend(name, payload, mutationId);
+  if (response?.success === false) {
+    throw new CommandError(name, response.error as CommandErrorInfo);
+  }
+  return response as T;
+}
+
+/** Listen to a backend event; returns the unsubscribe function */
+export function onEvent<K extends keyof EventPayloads>(
+  name: K,
+  handler: (payload: EventPayloads[K]) => void,
+): () => void {
+  return EventBus.subscribe(name, (event) => handler(event.payload as unknown as EventPayloads[K]));
+}
+
+// Commands
+
+export interface HelloRequest {
+  protocol_version: number;
+  min_protocol_version?: number;
+  formats?: string[];
+  compression?: string[];
+  features?: string[];
+  resume_token?: string;
+  resume_from?: string;
+  locale?: string;
+}
+
+export interface HelloResponse {
+  success: true;
+  protocol_version: number;
+  format: string;
+  compression: string | null;
+  features: string[];
+  ignored_features: string[];
+  server: ProtocolOffer;
+  locale: string;
+  session?: UserSession;
+  resume_token?: string;
+  resume?: SessionResume;
+}
+
+/** Negotiate the protocol version, format, compression and features of this connection */
+export const hello = (request: HelloRequest): Promise<HelloResponse> => call('hello', request);
+
+export interface SessionCurrentResponse {
+  success: true;
+  session: UserSession | null;
+}
+
+/** The session of this connection */
+export const sessionCurrent = (): Promise<SessionCurrentResponse> => call('session.current');
+
+export interface SessionSetLocaleRequest {
+  locale?: string;
+}
+
+export interface SessionSetLocaleResponse {
+  success: true;
+  locale: string;
+  session: UserSession;
+}
+
+/** Choose the locale of this session's messages; a missing or `null` locale restores the default */
+export const sessionSetLocale = (request: SessionSetLocaleRequest = {}): Promise<SessionSetLocaleResponse> => call('session.set_locale', request);
+
+export interface SettingsWatchRequest {
+  namespace: string;
+  user_id?: number;
+}
+
+export interface SettingsWatchResponse {
+  success: true;
+  values: Record<string, unknown>;
+}
+
+/** Current values of a settings namespace; its `settings.changed` events follow */
+export const settingsWatch = (request: SettingsWatchRequest): Promise<SettingsWatchResponse> => call('settings.watch', request);
+
+export interface SettingsUnwatchRequest {
+  namespace: string;
+}
+
+export interface SettingsUnwatchResponse {
+  success: true;
+  watching: boolean;
+}
+
+/** Stop receiving `settings.changed` events of a namespace */
+export const settingsUnwatch = (request: SettingsUnwatchRequest): Promise<SettingsUnwatchResponse> => call('settings.unwatch', request);
+
+export interface StateSubscribeRequest {
+  document: 'users' | 'counters' | 'system';
+}
+
+export interface StateSubscribeResponse {
+  success: true;
+  document: 'users' | 'counters' | 'system';
+  version: number;
+  state: unknown;
+}
+
+/** A state document and its version; its `state.patch` events follow */
+export const stateSubscribe = (request: StateSubscribeRequest): Promise<StateSubscribeResponse> => call('state.subscribe', request);
+
+export interface StateUnsubscribeRequest {
+  document: 'users' | 'counters' | 'system';
+}
+
+export interface StateUnsubscribeResponse {
+  success: true;
+  subscribed: boolean;
+}
+
+/** Stop receiving `state.patch` events of a document */
+export const stateUnsubscribe = (request: StateUnsubscribeRequest): Promise<StateUnsubscribeResponse> => call('state.unsubscribe', request);
+
+export interface PresenceRegisterRequest {
+  name?: string;
+  kind?: string;
+  metadata?: unknown;
+}
+
+export interface PresenceRegisterResponse {
+  success: true;
+  client: ClientProfile;
+}
+
+/** Make this connection present; announced as `presence.joined`, and `presence.left` once it closes */
+export const presenceRegister = (request: PresenceRegisterRequest = {}): Promise<PresenceRegisterResponse> => call('presence.register', request);
+
+export interface FileDownloadRequest {
+  path: string;
+}
+
+export interface FileDownloadResponse {
+  success: true;
+  download_id: string;
+  path: string;
+  size: number;
+  chunk_size: number;
+}
+
+/** Stream a file from `[file_transfer] allowed_dirs` as `file.download_chunk` binary frames */
+export const fileDownload = (request: FileDownloadRequest): Promise<FileDownloadResponse> => call('file.download', request);
+
+export interface WatchPathRequest {
+  path: string;
+}
+
+export interface WatchPathResponse {
+  success: true;
+  watch: Watch;
+}
+
+/** Watch a directory; its changes arrive as `fs.changed` events on this connection only */
+export const watchPath = (request: WatchPathRequest): Promise<WatchPathResponse> => call('watch_path', request);
+
+export interface UnwatchRequest {
+  id?: string;
+  path?: string;
+}
+
+export interface UnwatchResponse {
+  success: true;
+  watch: Watch;
+}
+
+/** Stop a watch of this connection by id or path */
+export const unwatch = (request: UnwatchRequest = {}): Promise<UnwatchResponse> => call('unwatch', request);
+
+export interface ListWatchesResponse {
+  success: true;
+  watches: Watch[];
+}
+
+/** Watches of this connection */
+export const listWatches = (): Promise<ListWatchesResponse> => call('list_watches');
+
+export interface GetUsersRequest {
+  role?: 'admin' | 'user' | 'editor' | 'viewer';
+  search?: string;
+  sort?: 'id' | 'name' | 'email' | 'role';
+  order?: 'asc' | 'desc';
+  limit?: number;
+  offset?: number;
+}
+
+export interface GetUsersResponse {
+  success: true;
+  data: User[];
+}
+
+/** Users, all of them by id unless filtered, sorted or paged */
+export const getUsers = (request: GetUsersRequest = {}): Promise<GetUsersResponse> => call('get_users', request);
+
+export interface UpdateUserRequest {
+  id: number;
+  version: number;
+  name?: string;
+  email?: string;
+  role?: 'admin' | 'user' | 'editor' | 'viewer';
+}
+
+export interface UpdateUserResponse {
+  success: true;
+  data: User;
+}
+
+/** Update a user; `version` must match the stored one */
+export const updateUser = (request: UpdateUserRequest): Promise<UpdateUserResponse> => call('update_user', request);
+
+export interface CreateUserRequest {
+  name: string;
+  email: string;
+  role?: 'admin' | 'user' | 'editor' | 'viewer';
+}
+
+export interface CreateUserResponse {
+  success: true;
+  data: User;
+}
+
+/** Add a user; the role defaults to `user` */
+export const createUser = (request: CreateUserRequest): Promise<CreateUserResponse> => call('create_user', request);
+
+export interface DeleteUserRequest {
+  id: number;
+  version: number;
+}
+
+export interface DeleteUserResponse {
+  success: true;
+  data: User;
+}
+
+/** Delete a user; `version` must match the stored one */
+export const deleteUser = (request: DeleteUserRequest): Promise<DeleteUserResponse> => call('delete_user', request);
+
+export interface GetDbStatsResponse {
+  success: true;
+  stats: { users: number; tables: string[] };
+}
+
+/** Database statistics */
+export const getDbStats = (): Promise<GetDbStatsResponse> => call('get_db_stats');
+
+export interface PresenceListResponse {
+  success: true;
+  clients: ClientProfile[];
+}
+
+/** Clients that registered with `presence.register`, longest present first */
+export const presenceList = (): Promise<PresenceListResponse> => call('presence.list');
+
+export interface UiReadyResponse {
+  success: true;
+  message: string;
+}
+
+/** Tell the backend the UI is up; answered by a `backend.connected` event */
+export const uiReady = (): Promise<UiReadyResponse> => call('ui.ready');
+
+export interface WindowStateChangeRequest {
+  id: string;
+  action: 'created' | 'focused' | 'blurred' | 'minimized' | 'restored' | 'maximized' | 'resized' | 'moved' | 'closed';
+  windowTitle?: string;
+  x?: number;
+  y?: number;
+  width?: number;
+  height?: number;
+  maximized?: boolean;
+}
+
+export interface WindowStateChangeResponse {
+  success: true;
+  message: string;
+}
+
+/** Report a change of a native window */
+export const windowStateChange = (request: WindowStateChangeRequest): Promise<WindowStateChangeResponse> => call('window.state.change', request);
+
+export interface GetSystemInfoResponse {
+  success: true;
+  data: SystemInfo;
+  app_version: string;
+}
+
+/** Operating system, CPU and memory of the host */
+export const getSystemInfo = (): Promise<GetSystemInfoResponse> => call('get_system_info');
+
+export interface ClipboardReadTextResponse {
+  success: true;
+  text: string;
+}
+
+/** Read the clipboard as text */
+export const clipboardReadText = (): Promise<ClipboardReadTextResponse> => call('clipboard.read_text');
+
+export interface ClipboardWriteTextRequest {
+  text: string;
+}
+
+export interface ClipboardWriteTextResponse {
+  success: true;
+  length: number;
+}
+
+/** Write text to the clipboard */
+export const clipboardWriteText = (request: ClipboardWriteTextRequest): Promise<ClipboardWriteTextResponse> => call('clipboard.write_text', request);
+
+export interface DialogOpenFileRequest {
+  title?: string;
+  directory?: string;
+  filters?: DialogFilter[];
+  multiple?: boolean;
+}
+
+export interface DialogOpenFileResponse {
+  success: true;
+  cancelled: boolean;
+  paths: string[];
+}
+
+/** Native file picker */
+export const dialogOpenFile = (request: DialogOpenFileRequest = {}): Promise<DialogOpenFileResponse> => call('dialog.open_file', request);
+
+export interface DialogOpenFolderRequest {
+  title?: string;
+  directory?: string;
+  filters?: DialogFilter[];
+  multiple?: boolean;
+}
+
+export interface DialogOpenFolderResponse {
+  success: true;
+  cancelled: boolean;
+  paths: string[];
+}
+
+/** Native folder picker */
+export const dialogOpenFolder = (request: DialogOpenFolderRequest = {}): Promise<DialogOpenFolderResponse> => call('dialog.open_folder', request);
+
+export interface DialogSaveFileRequest {
+  title?: string;
+  directory?: string;
+  filters?: DialogFilter[];
+  file_name?: string;
+}
+
+export interface DialogSaveFileResponse {
+  success: true;
+  cancelled: boolean;
+  paths: string[];
+}
+
+/** Native save dialog */
+export const dialogSaveFile = (request: DialogSaveFileRequest = {}): Promise<DialogSaveFileResponse> => call('dialog.save_file', request);
+
+export interface CounterCreateRequest {
+  label: string;
+  id?: string;
+}
+
+export interface CounterCreateResponse {
+  success: true;
+  counter: Counter;
+}
+
+/** Create a named counter */
+export const counterCreate = (request: CounterCreateRequest): Promise<CounterCreateResponse> => call('counter.create', request);
+
+export interface CounterListResponse {
+  success: true;
+  counters: Counter[];
+}
+
+/** All stored counters */
+export const counterList = (): Promise<CounterListResponse> => call('counter.list');
+
+export interface CounterEventsRequest {
+  id?: string;
+}
+
+export interface CounterEventsResponse {
+  success: true;
+  events: CounterEvent[];
+}
+
+/** Stored events of an event-sourced counter, oldest first */
+export const counterEvents = (request: CounterEventsRequest = {}): Promise<CounterEventsResponse> => call('counter.events', request);
+
+export interface CounterIncrementRequest {
+  id?: string;
+}
+
+export interface CounterIncrementResponse {
+  success: true;
+  counter: Counter;
+}
+
+/** Increment a counter */
+export const counterIncrement = (request: CounterIncrementRequest = {}): Promise<CounterIncrementResponse> => call('counter.increment', request);
+
+export interface CounterDecrementRequest {
+  id?: string;
+}
+
+export interface CounterDecrementResponse {
+  success: true;
+  counter: Counter;
+}
+
+/** Decrement a counter */
+export const counterDecrement = (request: CounterDecrementRequest = {}): Promise<CounterDecrementResponse> => call('counter.decrement', request);
+
+export interface CounterResetRequest {
+  id?: string;
+}
+
+export interface CounterResetResponse {
+  success: true;
+  counter: Counter;
+}
+
+/** Reset a counter to zero */
+export const counterReset = (request: CounterResetRequest = {}): Promise<CounterResetResponse> => call('counter.reset', request);
+
+export interface TaskListResponse {
+  success: true;
+  tasks: Task[];
+}
+
+/** Background tasks, newest first */
+export const taskList = (): Promise<TaskListResponse> => call('task.list');
+
+export interface TaskGetRequest {
+  id: string;
+}
+
+export interface TaskGetResponse {
+  success: true;
+  task: Task;
+}
+
+/** A background task by id */
+export const taskGet = (request: TaskGetRequest): Promise<TaskGetResponse> => call('task.get', request);
+
+export interface TaskCancelRequest {
+  id: string;
+}
+
+export interface TaskCancelResponse {
+  success: true;
+  id: string;
+}
+
+/** Cancel a running background task */
+export const taskCancel = (request: TaskCancelRequest): Promise<TaskCancelResponse> => call('task.cancel', request);
+
+export interface ImagesOrganizeRequest {
+  source: string;
+  destination?: string;
+  mode?: 'copy' | 'move';
+  dry_run?: boolean;
+  recursive?: boolean;
+  background?: boolean;
+}
+
+export interface ImagesOrganizeResponse {
+  success: true;
+  report?: Record<string, unknown>;
+  task_id?: string;
+}
+
+/** Sort images into YYYY/MM folders by capture date */
+export const imagesOrganize = (request: ImagesOrganizeRequest): Promise<ImagesOrganizeResponse> => call('images.organize', request);
+
+export interface MetricsStartRequest {
+  interval_secs?: number;
+}
+
+export interface MetricsStartResponse {
+  success: true;
+  started: boolean;
+  status: Record<string, unknown>;
+}
+
+/** Start the live metrics sampler */
+export const metricsStart = (request: MetricsStartRequest = {}): Promise<MetricsStartResponse> => call('metrics.start', request);
+
+export interface MetricsStopResponse {
+  success: true;
+  stopped: boolean;
+  status: Record<string, unknown>;
+}
+
+/** Stop the live metrics sampler */
+export const metricsStop = (): Promise<MetricsStopResponse> => call('metrics.stop');
+
+export interface MetricsStatusResponse {
+  success: true;
+  status: Record<string, unknown>;
+}
+
+/** State of the live metrics sampler */
+export const metricsStatus = (): Promise<MetricsStatusResponse> => call('metrics.status');
+
+export interface SchedulerListResponse {
+  success: true;
+  jobs: Record<string, unknown>[];
+}
+
+/** Registered background jobs */
+export const schedulerList = (): Promise<SchedulerListResponse> => call('scheduler.list');
+
+export interface SchedulerHistoryRequest {
+  job?: string;
+  limit?: number;
+}
+
+export interface SchedulerHistoryResponse {
+  success: true;
+  runs: JobRun[];
+}
+
+/** Recent job runs */
+export const schedulerHistory = (request: SchedulerHistoryRequest = {}): Promise<SchedulerHistoryResponse> => call('scheduler.history', request);
+
+export interface SchedulerRunNowRequest {
+  job: string;
+}
+
+export interface SchedulerRunNowResponse {
+  success: true;
+  run: JobRun;
+}
+
+/** Run a job immediately */
+export const schedulerRunNow = (request: SchedulerRunNowRequest): Promise<SchedulerRunNowResponse> => call('scheduler.run_now', request);
+
+export interface ShellOpenUrlRequest {
+  url: string;
+}
+
+export interface ShellOpenUrlResponse {
+  success: true;
+  target: string;
+}
+
+/** Open a URL in the default browser */
+export const shellOpenUrl = (request: ShellOpenUrlRequest): Promise<ShellOpenUrlResponse> => call('shell.open_url', request);
+
+export interface ShellOpenPathRequest {
+  path: string;
+}
+
+export interface ShellOpenPathResponse {
+  success: true;
+  target: string;
+}
+
+/** Open a file with its default application */
+export const shellOpenPath = (request: ShellOpenPathRequest): Promise<ShellOpenPathResponse> => call('shell.open_path', request);
+
+export interface ShellRevealRequest {
+  path: string;
+}
+
+export interface ShellRevealResponse {
+  success: true;
+  target: string;
+}
+
+/** Show a file in the system file manager */
+export const shellReveal = (request: ShellRevealRequest): Promise<ShellRevealResponse> => call('shell.reveal', request);
+
+export interface PeersListResponse {
+  success: true;
+  advertising: boolean;
+  instance_id: string;
+  peers: Record<string, unknown>[];
+}
+
+/** Other instances found on the LAN */
+export const peersList = (): Promise<PeersListResponse> => call('peers.list');
+
+export interface TelemetryStatusResponse {
+  success: true;
+  telemetry: Record<string, unknown>;
+}
+
+/** Error reporting state */
+export const telemetryStatus = (): Promise<TelemetryStatusResponse> => call('telemetry.status');
+
+export interface TelemetrySetConsentRequest {
+  granted: boolean;
+}
+
+export interface TelemetrySetConsentResponse {
+  success: true;
+  telemetry: Record<string, unknown>;
+}
+
+/** Grant or revoke consent to error reporting */
+export const telemetrySetConsent = (request: TelemetrySetConsentRequest): Promise<TelemetrySetConsentResponse> => call('telemetry.set_consent', request);
+
+export interface DbRekeyRequest {
+  admin_token?: string;
+}
+
+export interface DbRekeyResponse {
+  success: true;
+}
+
+/** Re-encrypt the database under a new keychain key */
+export const dbRekey = (request: DbRekeyRequest = {}): Promise<DbRekeyResponse> => call('db.rekey', request);
+
+export interface SessionListRequest {
+  admin_token?: string;
+}
+
+export interface SessionListResponse {
+  success: true;
+  sessions: UserSession[];
+}
+
+/** Unexpired sessions, most recently active first */
+export const sessionList = (request: SessionListRequest = {}): Promise<SessionListResponse> => call('session.list', request);
+
+export interface SessionRevokeRequest {
+  admin_token?: string;
+  id: string;
+}
+
+export interface SessionRevokeResponse {
+  success: true;
+}
+
+/** End a session and close its connection */
+export const sessionRevoke = (request: SessionRevokeRequest): Promise<SessionRevokeResponse> => call('session.revoke', request);
+
+export interface TokenIssueRequest {
+  admin_token?: string;
+  user_id: number;
+  ttl_mins?: number;
+}
+
+export interface TokenIssueResponse {
+  success: true;
+  token: string;
+  token_type: 'Bearer';
+  expires_at: string;
+  claims: ApiTokenClaims;
+}
+
+/** Sign an API token (JWT) for a user, for gRPC and the DevTools API */
+export const tokenIssue = (request: TokenIssueRequest): Promise<TokenIssueResponse> => call('token.issue', request);
+
+export interface AuthRequestPasswordResetRequest {
+  email: string;
+}
+
+export interface AuthRequestPasswordResetResponse {
+  success: true;
+}
+
+/** Mail a password reset token; succeeds for unknown addresses too */
+export const authRequestPasswordReset = (request: AuthRequestPasswordResetRequest): Promise<AuthRequestPasswordResetResponse> => call('auth.request_password_reset', request);
+
+export interface AuthResetPasswordRequest {
+  token: string;
+  password: string;
+}
+
+export interface AuthResetPasswordResponse {
+  success: true;
+  user_id: number;
+}
+
+/** Set a new password with a mailed reset token */
+export const authResetPassword = (request: AuthResetPasswordRequest): Promise<AuthResetPasswordResponse> => call('auth.reset_password', request);
+
+export interface AuthRequestEmailVerificationRequest {
+  user_id: number;
+}
+
+export interface AuthRequestEmailVerificationResponse {
+  success: true;
+}
+
+/** Mail an email verification token to a user */
+export const authRequestEmailVerification = (request: AuthRequestEmailVerificationRequest): Promise<AuthRequestEmailVerificationResponse> => call('auth.request_email_verification', request);
+
+export interface AuthVerifyEmailRequest {
+  token: string;
+}
+
+export interface AuthVerifyEmailResponse {
+  success: true;
+  user_id: number;
+}
+
+/** Mark a user's email verified with a mailed token */
+export const authVerifyEmail = (request: AuthVerifyEmailRequest): Promise<AuthVerifyEmailResponse> => call('auth.verify_email', request);
+
+export interface SettingsGetRequest {
+  namespace: string;
+  key?: string;
+  user_id?: number;
+}
+
+export interface SettingsGetResponse {
+  success: true;
+  value?: unknown;
+  setting?: Setting | null;
+  values?: Record<string, unknown>;
+}
+
+/** One setting, or every value of a namespace; a user's values fall back to global ones */
+export const settingsGet = (request: SettingsGetRequest): Promise<SettingsGetResponse> => call('settings.get', request);
+
+export interface SettingsSetRequest {
+  namespace: string;
+  key: string;
+  value?: unknown;
+  user_id?: number;
+}
+
+export interface SettingsSetResponse {
+  success: true;
+  setting: Setting | null;
+}
+
+/** Store a setting, globally or for a user; a missing or `null` value removes it */
+export const settingsSet = (request: SettingsSetRequest): Promise<SettingsSetResponse> => call('settings.set', request);
+
+export interface FlagsGetAllRequest {
+  user_id?: number;
+}
+
+export interface FlagsGetAllResponse {
+  success: true;
+  flags: Record<string, boolean>;
+  overrides: Record<string, boolean>;
+}
+
+/** Every feature flag and the runtime overrides behind them, for everyone or a user */
+export const flagsGetAll = (request: FlagsGetAllRequest = {}): Promise<FlagsGetAllResponse> => call('flags.get_all', request);
+
+export interface FlagsSetRequest {
+  name: string;
+  enabled?: boolean | null;
+  user_id?: number;
+}
+
+export interface FlagsSetResponse {
+  success: true;
+  name: string;
+  enabled: boolean;
+}
+
+/** Override a feature flag, globally or for a user; a missing or `null` value removes the override */
+export const flagsSet = (request: FlagsSetRequest): Promise<FlagsSetResponse> => call('flags.set', request);
+
+export interface UndoResponse {
+  success: true;
+  entry: HistoryEntry;
+  result: Record<string, unknown>;
+}
+
+/** Revert the newest user or counter change */
+export const undo = (): Promise<UndoResponse> => call('undo');
+
+export interface RedoResponse {
+  success: true;
+  entry: HistoryEntry;
+  result: Record<string, unknown>;
+}
+
+/** Apply the newest undone change again */
+export const redo = (): Promise<RedoResponse> => call('redo');
+
+export interface HistoryListResponse {
+  success: true;
+  undo: HistoryEntry[];
+  redo: HistoryEntry[];
+}
+
+/** Journaled changes, newest first, and those `redo` would apply */
+export const historyList = (): Promise<HistoryListResponse> => call('history.list');
+
+export interface OutboxStatusResponse {
+  success: true;
+  pending: number;
+  failing: number;
+  oldest: OutboxMessage | null;
+}
+
+/** Database events not yet published, and the oldest of them */
+export const outboxStatus = (): Promise<OutboxStatusResponse> => call('outbox.status');
+
+export interface AnalyticsSummaryRequest {
+  days?: number;
+}
+
+export interface AnalyticsSummaryResponse {
+  success: true;
+  enabled: boolean;
+  days: UsageDay[];
+  upload: UsageUpload;
+}
+
+/** This installation's own usage counts per day and whether they are uploaded */
+export const analyticsSummary = (request: AnalyticsSummaryRequest = {}): Promise<AnalyticsSummaryResponse> => call('analytics.summary', request);
+
+export interface BrokerStatusResponse {
+  success: true;
+  running: boolean;
+  kind: string | null;
+  url: string | null;
+  connected: boolean;
+  publish: string[] | null;
+  subscribe: string[] | null;
+  published: number;
+  received: number;
+  last_error: string | null;
+}
+
+/** State of the bridge to an external message broker */
+export const brokerStatus = (): Promise<BrokerStatusResponse> => call('broker.status');
+
+export interface WebhooksRegisterRequest {
+  admin_token?: string;
+  url: string;
+  events: string[];
+  secret?: string;
+}
+
+export interface WebhooksRegisterResponse {
+  success: true;
+  webhook: Webhook;
+  secret: string;
+}
+
+/** Send matching events to a URL as signed POST requests */
+export const webhooksRegister = (request: WebhooksRegisterRequest): Promise<WebhooksRegisterResponse> => call('webhooks.register', request);
+
+export interface WebhooksListRequest {
+  admin_token?: string;
+}
+
+export interface WebhooksListResponse {
+  success: true;
+  webhooks: Webhook[];
+}
+
+/** Registered webhooks */
+export const webhooksList = (request: WebhooksListRequest = {}): Promise<WebhooksListResponse> => call('webhooks.list', request);
+
+export interface WebhooksRemoveRequest {
+  admin_token?: string;
+  id: string;
+}
+
+export interface WebhooksRemoveResponse {
+  success: true;
+}
+
+/** Remove a webhook and its delivery log */
+export const webhooksRemove = (request: WebhooksRemoveRequest): Promise<WebhooksRemoveResponse> => call('webhooks.remove', request);
+
+export interface WebhooksDeliveriesRequest {
+  admin_token?: string;
+  webhook_id?: string;
+  status?: 'pending' | 'delivered' | 'failed';
+  limit?: number;
+}
+
+export interface WebhooksDeliveriesResponse {
+  success: true;
+  deliveries: WebhookDelivery[];
+}
+
+/** Webhook deliveries, newest first */
+export const webhooksDeliveries = (request: WebhooksDeliveriesRequest = {}): Promise<WebhooksDeliveriesResponse> => call('webhooks.deliveries', request);
+
+export interface CollabSyncRequest {
+  document: string;
+  state_vector?: string;
+}
+
+export interface CollabSyncResponse {
+  success: true;
+  document: string;
+  update: string;
+  state_vector: string;
+}
+
+/** What a shared document holds beyond a state vector, as a base64 Yjs update */
+export const collabSync = (request: CollabSyncRequest): Promise<CollabSyncResponse> => call('collab.sync', request);
+
+export interface CollabUpdateRequest {
+  document: string;
+  update: string;
+  origin?: string;
+}
+
+export interface CollabUpdateResponse {
+  success: true;
+  document: string;
+  state_vector: string;
+}
+
+/** Merge a base64 Yjs update into a shared document; announced as `collab.update` */
+export const collabUpdate = (request: CollabUpdateRequest): Promise<CollabUpdateResponse> => call('collab.update', request);
+
+export interface HotkeysListResponse {
+  success: true;
+  bindings: HotkeyBinding[];
+}
+
+/** Global keyboard shortcuts registered with the OS */
+export const hotkeysList = (): Promise<HotkeysListResponse> => call('hotkeys.list');
+
+export interface HotkeysRegisterRequest {
+  accelerator: string;
+  action?: string;
+  command?: string;
+  payload?: Record<string, unknown>;
+}
+
+export interface HotkeysRegisterResponse {
+  success: true;
+  binding: HotkeyBinding;
+}
+
+/** Register a global keyboard shortcut; each press emits `hotkey.pressed` */
+export const hotkeysRegister = (request: HotkeysRegisterRequest): Promise<HotkeysRegisterResponse> => call('hotkeys.register', request);
+
+export interface HotkeysUnregisterRequest {
+  accelerator: string;
+}
+
+export interface HotkeysUnregisterResponse {
+  success: true;
+  binding: HotkeyBinding;
+}
+
+/** Release a global keyboard shortcut */
+export const hotkeysUnregister = (request: HotkeysUnregisterRequest): Promise<HotkeysUnregisterResponse> => call('hotkeys.unregister', request);
+
+export interface CaptureWindowRequest {
+  title?: string;
+}
+
+export interface CaptureWindowResponse {
+  success: true;
+  request_id: string;
+  kind: 'window' | 'screen';
+  target: string;
+  expires_in_secs: number;
+}
+
+/** Ask the user to allow a screenshot of a window; announced as `capture.requested` */
+export const captureWindow = (request: CaptureWindowRequest = {}): Promise<CaptureWindowResponse> => call('capture.window', request);
+
+export interface CaptureScreenRequest {
+  monitor?: string;
+}
+
+export interface CaptureScreenResponse {
+  success: true;
+  request_id: string;
+  kind: 'window' | 'screen';
+  target: string;
+  expires_in_secs: number;
+}
+
+/** Ask the user to allow a screenshot of a monitor; announced as `capture.requested` */
+export const captureScreen = (request: CaptureScreenRequest = {}): Promise<CaptureScreenResponse> => call('capture.screen', request);
+
+export interface CaptureConfirmRequest {
+  request_id: string;
+  allow: boolean;
+}
+
+export interface CaptureConfirmResponse {
+  success: true;
+  request_id: string;
+  kind: 'window' | 'screen';
+  target: string;
+  allowed: boolean;
+  path?: string;
+  width?: number;
+  height?: number;
+  thumbnail?: string;
+}
+
+/** Allow or refuse a capture request; allowing saves the screenshot */
+export const captureConfirm = (request: CaptureConfirmRequest): Promise<CaptureConfirmResponse> => call('capture.confirm', request);
+
+export interface SoundPlayRequest {
+  name: 'click' | 'success' | 'error' | 'notification';
+  volume?: number;
+}
+
+export interface SoundPlayResponse {
+  success: true;
+  played: boolean;
+}
+
+/** Play a built-in UI sound at the stored volume, unless muted */
+export const soundPlay = (request: SoundPlayRequest): Promise<SoundPlayResponse> => call('sound.play', request);
+
+export interface SoundListResponse {
+  success: true;
+  sounds: string[];
+  volume: number;
+  muted: boolean;
+}
+
+/** Built-in UI sounds, the volume and whether they are muted */
+export const soundList = (): Promise<SoundListResponse> => call('sound.list');
+
+export interface SoundSetVolumeRequest {
+  volume: number;
+}
+
+export interface SoundSetVolumeResponse {
+  success: true;
+  sounds: string[];
+  volume: number;
+  muted: boolean;
+}
+
+/** Store the volume of UI sounds, from 0 to 1 */
+export const soundSetVolume = (request: SoundSetVolumeRequest): Promise<SoundSetVolumeResponse> => call('sound.set_volume', request);
+
+export interface SoundSetMutedRequest {
+  muted: boolean;
+}
+
+export interface SoundSetMutedResponse {
+  success: true;
+  sounds: string[];
+  volume: number;
+  muted: boolean;
+}
+
+/** Mute or unmute every UI sound */
+export const soundSetMuted = (request: SoundSetMutedRequest): Promise<SoundSetMutedResponse> => call('sound.set_muted', request);
+
+export interface OsIntegrationResponse {
+  success: true;
+  platform: string;
+  launch_at_login: LaunchAtLogin;
+}
+
+/** OS integration settings: whether the app launches at login */
+export const osIntegration = (): Promise<OsIntegrationResponse> => call('os.integration');
+
+export interface OsSetLaunchAtLoginRequest {
+  enabled: boolean;
+}
+
+export interface OsSetLaunchAtLoginResponse {
+  success: true;
+  platform: string;
+  launch_at_login: LaunchAtLogin;
+}
+
+/** Add or remove the login entry and remember the choice */
+export const osSetLaunchAtLogin = (request: OsSetLaunchAtLoginRequest): Promise<OsSetLaunchAtLoginResponse> => call('os.set_launch_at_login', request);
+
+export interface DocumentExportPdfRequest {
+  html: string;
+  base_url?: string;
+  file_name?: string;
+  title?: string;
+}
+
+export interface DocumentExportPdfResponse {
+  success: true;
+  cancelled: boolean;
+  export_id?: string;
+  path?: string;
+}
+
+/** Print HTML to a PDF the user picks a place for; announced as `document.exported` */
+export const documentExportPdf = (request: DocumentExportPdfRequest): Promise<DocumentExportPdfResponse> => call('document.export_pdf', request);
+
+export interface UpdateStatusResponse {
+  success: true;
+  status: UpdateStatus;
+}
+
+/** Result of the last update check */
+export const updateStatus = (): Promise<UpdateStatusResponse> => call('update.status');
+
+export interface UpdateCheckResponse {
+  success: true;
+  status: UpdateStatus;
+}
+
+/** Look for a newer release now */
+export const updateCheck = (): Promise<UpdateCheckResponse> => call('update.check');
+
+export interface UpdateInstallRequest {
+  admin_token?: string;
+}
+
+export interface UpdateInstallResponse {
+  success: true;
+  version: string;
+  restart_required: boolean;
+}
+
+/** Download, verify and install the newer release; it runs after a restart */
+export const updateInstall = (request: UpdateInstallRequest = {}): Promise<UpdateInstallResponse> => call('update.install', request);
+
+export interface PluginsListResponse {
+  success: true;
+  plugins: PluginMetadata[];
+  initialization_order: string[];
+  services: Record<string, string>;
+}
+
+/** Loaded plugins, their initialization order and services */
+export const pluginsList = (): Promise<PluginsListResponse> => call('plugins.list');
+
+export interface SchemasGetRequest {
+  name?: string;
+}
+
+export interface SchemasGetResponse {
+  success: true;
+  schemas?: Record<string, unknown>;
+  name?: string;
+  schema?: Record<string, unknown>;
+}
+
+/** JSON Schemas of the WebSocket envelope, event payloads and DTOs */
+export const schemasGet = (request: SchemasGetRequest = {}): Promise<SchemasGetResponse> => call('schemas.get', request);
+
+export interface FileUploadBeginRequest {
+  name: string;
+  size: number;
+  directory?: string;
+  overwrite?: boolean;
+  sha256?: string;
+}
+
+export interface FileUploadBeginResponse {
+  success: true;
+  upload: FileUpload;
+}
+
+/** Start an upload; the data follows as `file.upload_chunk` binary frames */
+export const fileUploadBegin = (request: FileUploadBeginRequest): Promise<FileUploadBeginResponse> => call('file.upload_begin', request);
+
+export interface FileUploadEndRequest {
+  upload_id: string;
+  overwrite?: boolean;
+}
+
+export interface FileUploadEndResponse {
+  success: true;
+  file: UploadedFile;
+}
+
+/** Verify a fully received upload and move it into place */
+export const fileUploadEnd = (request: FileUploadEndRequest): Promise<FileUploadEndResponse> => call('file.upload_end', request);
+
+export interface SysinfoGetResponse {
+  success: true;
+  data: SystemInfo;
+  app_version: string;
+}
+
+/** Host details from the system_info plugin */
+export const sysinfoGet = (): Promise<SysinfoGetResponse> => call('sysinfo.get');
+
+export interface WindowOpenRequest {
+  url?: string;
+  path?: string;
+  title?: string;
+}
+
+export interface WindowOpenResponse {
+  success: true;
+  id: number;
+  url: string;
+}
+
+/** Open a window showing a URL or a path of the app UI */
+export const windowOpen = (request: WindowOpenRequest = {}): Promise<WindowOpenResponse> => call('window.open', request);
+
+export interface WindowCloseRequest {
+  id: number;
+}
+
+export interface WindowCloseResponse {
+  success: true;
+  id: number;
+}
+
+/** Close a window by id */
+export const windowClose = (request: WindowCloseRequest): Promise<WindowCloseResponse> => call('window.close', request);
+
+export interface WindowSetTitleRequest {
+  id: number;
+  title: string;
+}
+
+export interface WindowSetTitleResponse {
+  success: true;
+  id: number;
+  title: string;
+}
+
+/** Change the title of a window */
+export const windowSetTitle = (request: WindowSetTitleRequest): Promise<WindowSetTitleResponse> => call('window.set_title', request);
+
+// Events
+
+export interface UserLoginEvent {
+  user_id?: number;
+}
+
+export interface UserLogoutEvent {
+  user_id?: number;
+}
+
+export interface DataChangedEvent {
+  operation: string;
+  table: string;
+  count?: number;
+  user?: User;
+}
+
+export interface CounterIncrementedEvent {
+  id: string;
+  value: number;
+}
+
+export interface CounterChangedEvent {
+  operation: 'increment' | 'decrement' | 'reset' | 'create' | 'set' | 'restore' | 'delete';
+  counter: Counter;
+}
+
+export interface DatabaseOperationEvent {
+  operation: string;
+  count?: number;
+}
+
+export interface DatabaseActivityEvent {
+  topic: string;
+  count: number;
+  counts: Record<string, number>;
+  from: string;
+  to: string;
+}
+
+export interface SystemHealthCheckEvent {
+  type?: string;
+  system?: SystemInfo;
+}
+
+export type FrontendConnectedEvent = Record<string, unknown>;
+
+export type FrontendDisconnectedEvent = Record<string, unknown>;
+
+export interface WindowStateChangedEvent {
+  id: string;
+  action: 'created' | 'focused' | 'blurred' | 'minimized' | 'restored' | 'maximized' | 'resized' | 'moved' | 'closed';
+  title?: string;
+}
+
+export interface ClipboardAccessedEvent {
+  operation: 'read' | 'write';
+  length: number;
+  success: boolean;
+  error: string | null;
+}
+
+export interface PluginAccessDeniedEvent {
+  plugin: string;
+  operation: string;
+  permission: string;
+  declared: boolean;
+}
+
+export type SystemMetricsEvent = MetricsSample;
+
+export type SchedulerJobCompletedEvent = JobRun;
+
+export interface FsChangedEvent {
+  watch_id: string;
+  path: string;
+  created: string[];
+  modified: string[];
+  removed: string[];
+}
+
+export interface ImagesOrganizeProgressEvent {
+  processed: number;
+  total: number;
+  current: string;
+  dry_run: boolean;
+}
+
+export interface FileTransferProgressEvent {
+  transfer_id: string;
+  direction: 'upload' | 'download';
+  name: string;
+  bytes: number;
+  total: number;
+}
+
+export interface FileUploadedEvent {
+  path: string;
+  name: string;
+  size: number;
+  sha256: string;
+  transport: 'websocket' | 'http';
+}
+
+export interface SettingsChangedEvent {
+  namespace: string;
+  key: string;
+  value: unknown;
+  user_id: number | null;
+}
+
+export interface TaskProgressEvent {
+  id: string;
+  name: string;
+  progress: number;
+  message: string | null;
+}
+
+export type TaskCompletedEvent = Task;
+
+export type TaskFailedEvent = Task;
+
+export type TaskCancelledEvent = Task;
+
+export interface UpdateAvailableEvent {
+  current_version: string;
+  version: string;
+  notes: string | null;
+  url: string | null;
+  published_at: string | null;
+  installable: boolean;
+}
+
+export interface UpdateInstalledEvent {
+  version: string;
+  restart_required: boolean;
+}
+
+export interface FlagsChangedEvent {
+  name: string;
+  enabled: boolean;
+  override: boolean | null;
+  user_id: number | null;
+}
+
+export interface ChaosInjectedEvent {
+  fault: 'db_delay' | 'ws_drop' | 'handler_error';
+  command?: string;
+  delay_ms?: number;
+  bytes?: number;
+}
+
+export interface StatePatchEvent {
+  document: 'users' | 'counters' | 'system';
+  version: number;
+  patch: JsonPatchOperation[];
+}
+
+export interface CommandAcceptedEvent {
+  mutation_id: string;
+  command: string;
+}
+
+export interface CommandAppliedEvent {
+  mutation_id: string;
+  command: string;
+  result: Record<string, unknown>;
+  document?: 'users' | 'counters' | 'system';
+  version?: number;
+  state?: unknown;
+}
+
+export interface CommandRejectedEvent {
+  mutation_id: string;
+  command: string;
+  error: CommandErrorInfo;
+  document?: 'users' | 'counters' | 'system';
+  version?: number;
+  state?: unknown;
+}
+
+export type PresenceJoinedEvent = ClientProfile;
+
+export type PresenceLeftEvent = ClientProfile;
+
+export interface CollabUpdateEvent {
+  document: string;
+  update: string;
+  origin?: string | null;
+}
+
+export interface HotkeyPressedEvent {
+  accelerator: string;
+  action: string | null;
+  command: string | null;
+  result: Record<string, unknown> | null;
+}
+
+export interface DocumentExportedEvent {
+  export_id: string;
+  path: string;
+  success: boolean;
+  bytes: number | null;
+  error: string | null;
+  duration_ms: number;
+}
+
+export interface CaptureRequestedEvent {
+  request_id: string;
+  kind: 'window' | 'screen';
+  target: string;
+  expires_in_secs: number;
+}
+
+export interface CaptureCompletedEvent {
+  request_id: string;
+  kind: 'window' | 'screen';
+  target: string;
+  allowed: boolean;
+  path?: string;
+  width?: number;
+  height?: number;
+  error?: string;
+}
+
+export interface EventPayloads {
+  'user.login': UserLoginEvent;
+  'user.logout': UserLogoutEvent;
+  'data.changed': DataChangedEvent;
+  'counter.incremented': CounterIncrementedEvent;
+  'counter.changed': CounterChangedEvent;
+  'database.operation': DatabaseOperationEvent;
+  'database.activity': DatabaseActivityEvent;
+  'system.health.check': SystemHealthCheckEvent;
+  'frontend.connected': FrontendConnectedEvent;
+  'frontend.disconnected': FrontendDisconnectedEvent;
+  'window.state.changed': WindowStateChangedEvent;
+  'clipboard.accessed': ClipboardAccessedEvent;
+  'plugin.access_denied': PluginAccessDeniedEvent;
+  'system.metrics': SystemMetricsEvent;
+  'scheduler.job.completed': SchedulerJobCompletedEvent;
+  'fs.changed': FsChangedEvent;
+  'images.organize.progress': ImagesOrganizeProgressEvent;
+  'file.transfer.progress': FileTransferProgressEvent;
+  'file.uploaded': FileUploadedEvent;
+  'settings.changed': SettingsChangedEvent;
+  'task.progress': TaskProgressEvent;
+  'task.completed': TaskCompletedEvent;
+  'task.failed': TaskFailedEvent;
+  'task.cancelled': TaskCancelledEvent;
+  'update.available': UpdateAvailableEvent;
+  'update.installed': UpdateInstalledEvent;
+  'flags.changed': FlagsChangedEvent;
+  'chaos.injected': ChaosInjectedEvent;
+  'state.patch': StatePatchEvent;
+  'command.accepted': CommandAcceptedEvent;
+  'command.applied': CommandAppliedEvent;
+  'command.rejected': CommandRejectedEvent;
+  'presence.joined': PresenceJoinedEvent;
+  'presence.left': PresenceLeftEvent;
+  'collab.update': CollabUpdateEvent;
+  'hotkey.pressed': HotkeyPressedEvent;
+  'document.exported': DocumentExportedEvent;
+  'capture.requested': CaptureRequestedEvent;
+  'capture.completed': CaptureCompletedEvent;
+}
+
+export const onUserLogin = (handler: (payload: UserLoginEvent) => void): (() => void) =>
+  onEvent('user.login', handler);
+
+export const onUserLogout = (handler: (payload: UserLogoutEvent) => void): (() => void) =>
+  onEvent('user.logout', handler);
+
+export const onDataChanged = (handler: (payload: DataChangedEvent) => void): (() => void) =>
+  onEvent('data.changed', handler);
+
+export const onCounterIncremented = (handler: (payload: CounterIncrementedEvent) => void): (() => void) =>
+  onEvent('counter.incremented', handler);
+
+export const onCounterChanged = (handler: (payload: CounterChangedEvent) => void): (() => void) =>
+  onEvent('counter.changed', handler);
+
+export const onDatabaseOperation = (handler: (payload: DatabaseOperationEvent) => void): (() => void) =>
+  onEvent('database.operation', handler);
+
+export const onDatabaseActivity = (handler: (payload: DatabaseActivityEvent) => void): (() => void) =>
+  onEvent('database.activity', handler);
+
+export const onSystemHealthCheck = (handler: (payload: SystemHealthCheckEvent) => void): (() => void) =>
+  onEvent('system.health.check', handler);
+
+export const onFrontendConnected = (handler: (payload: FrontendConnectedEvent) => void): (() => void) =>
+  onEvent('frontend.connected', handler);
+
+export const onFrontendDisconnected = (handler: (payload: FrontendDisconnectedEvent) => void): (() => void) =>
+  onEvent('frontend.disconnected', handler);
+
+export const onWindowStateChanged = (handler: (payload: WindowStateChangedEvent) => void): (() => void) =>
+  onEvent('window.state.changed', handler);
+
+export const onClipboardAccessed = (handler: (payload: ClipboardAccessedEvent) => void): (() => void) =>
+  onEvent('clipboard.accessed', handler);
+
+export const onPluginAccessDenied = (handler: (payload: PluginAccessDeniedEvent) => void): (() => void) =>
+  onEvent('plugin.access_denied', handler);
+
+export const onSystemMetrics = (handler: (payload: SystemMetricsEvent) => void): (() => void) =>
+  onEvent('system.metrics', handler);
+
+export const onSchedulerJobCompleted = (handler: (payload: SchedulerJobCompletedEvent) => void): (() => void) =>
+  onEvent('scheduler.job.completed', handler);
+
+export const onFsChanged = (handler: (payload: FsChangedEvent) => void): (() => void) =>
+  onEvent('fs.changed', handler);
+
+export const onImagesOrganizeProgress = (handler: (payload: ImagesOrganizeProgressEvent) => void): (() => void) =>
+  onEvent('images.organize.progress', handler);
+
+export const onFileTransferProgress = (handler: (payload: FileTransferProgressEvent) => void): (() => void) =>
+  onEvent('file.transfer.progress', handler);
+
+export const onFileUploaded = (handler: (payload: FileUploadedEvent) => void): (() => void) =>
+  onEvent('file.uploaded', handler);
+
+export const onSettingsChanged = (handler: (payload: SettingsChangedEvent) => void): (() => void) =>
+  onEvent('settings.changed', handler);
+
+export const onTaskProgress = (handler: (payload: TaskProgressEvent) => void): (() => void) =>
+  onEvent('task.progress', handler);
+
+export const onTaskCompleted = (handler: (payload: TaskCompletedEvent) => void): (() => void) =>
+  onEvent('task.completed', handler);
+
+export const onTaskFailed = (handler: (payload: TaskFailedEvent) => void): (() => void) =>
+  onEvent('task.failed', handler);
+
+export const onTaskCancelled = (handler: (payload: TaskCancelledEvent) => void): (() => void) =>
+  onEvent('task.cancelled', handler);
+
+export const onUpdateAvailable = (handler: (payload: UpdateAvailableEvent) => void): (() => void) =>
+  onEvent('update.available', handler);
+
+export const onUpdateInstalled = (handler: (payload: UpdateInstalledEvent) => void): (() => void) =>
+  onEvent('update.installed', handler);
+
+export const onFlagsChanged = (handler: (payload: FlagsChangedEvent) => void): (() => void) =>
+  onEvent('flags.changed', handler);
+
+export const onChaosInjected = (handler: (payload: ChaosInjectedEvent) => void): (() => void) =>
+  onEvent('chaos.injected', handler);
+
+export const onStatePatch = (handler: (payload: StatePatchEvent) => void): (() => void) =>
+  onEvent('state.patch', handler);
+
+export const onCommandAccepted = (handler: (payload: CommandAcceptedEvent) => void): (() => void) =>
+  onEvent('command.accepted', handler);
+
+export const onCommandApplied = (handler: (payload: CommandAppliedEvent) => void): (() => void) =>
+  onEvent('command.applied', handler);
+
+export const onCommandRejected = (handler: (payload: CommandRejectedEvent) => void): (() => void) =>
+  onEvent('command.rejected', handler);
+
+export const onPresenceJoined = (handler: (payload: PresenceJoinedEvent) => void): (() => void) =>
+  onEvent('presence.joined', handler);
+
+export const onPresenceLeft = (handler: (payload: PresenceLeftEvent) => void): (() => void) =>
+  onEvent('presence.left', handler);
+
+export const onCollabUpdate = (handler: (payload: CollabUpdateEvent) => void): (() => void) =>
+  onEvent('collab.update', handler);
+
+export const onHotkeyPressed = (handler: (payload: HotkeyPressedEvent) => void): (() => void) =>
+  onEvent('hotkey.pressed', handler);
+
+export const onDocumentExported = (handler: (payload: DocumentExportedEvent) => void): (() => void) =>
+  onEvent('document.exported', handler);
+
+export const onCaptureRequested = (handler: (payload: CaptureRequestedEvent) => void): (() => void) =>
+  onEvent('capture.requested', handler);
+
+export const onCaptureCompleted = (handler: (payload: CaptureCompletedEvent) => void): (() => void) =>
+  onEvent('capture.completed', handler);
@@ -82,7 +82,9 @@ export enum AppEventType {
   USER_LOGOUT = 'user.logout',
   DATA_CHANGED = 'data.changed',
   COUNTER_INCREMENTED = 'counter.incremented',
+  COUNTER_CHANGED = 'counter.changed',
   DATABASE_OPERATION = 'database.operation',
+  DATABASE_ACTIVITY = 'database.activity',
   SYSTEM_HEALTH_CHECK = 'system.health.check',
   BACKEND_CONNECTED = 'backend.connected',
   BACKEND_DISCONNECTED = 'backend.disconnected',
@@ -92,4 +94,32 @@ export enum AppEventType {
   APP_SHUTDOWN = 'app.shutdown',
   UI_READY = 'ui.ready',
   WINDOW_STATE_CHANGED = 'window.state.changed',
+  CLIPBOARD_ACCESSED = 'clipboard.accessed',
+  PLUGIN_ACCESS_DENIED = 'plugin.access_denied',
+  SYSTEM_METRICS = 'system.metrics',
+  JOB_COMPLETED = 'scheduler.job.completed',
+  FS_CHANGED = 'fs.changed',
+  IMAGES_ORGANIZE_PROGRESS = 'images.organize.progress',
+  FILE_TRANSFER_PROGRESS = 'file.transfer.progress',
+  FILE_UPLOADED = 'file.uploaded',
+  SETTINGS_CHANGED = 'settings.changed',
+  TASK_PROGRESS = 'task.progress',
+  TASK_COMPLETED = 'task.completed',
+  TASK_FAILED = 'task.failed',
+  TASK_CANCELLED = 'task.cancelled',
+  UPDATE_AVAILABLE = 'update.available',
+  UPDATE_INSTALLED = 'update.installed',
+  FLAGS_CHANGED = 'flags.changed',
+  CHAOS_INJECTED = 'chaos.injected',
+  STATE_PATCH = 'state.patch',
+  COMMAND_ACCEPTED = 'command.accepted',
+  COMMAND_APPLIED = 'command.applied',
+  COMMAND_REJECTED = 'command.rejected',
+  PRESENCE_JOINED = 'presence.joined',
+  PRESENCE_LEFT = 'presence.left',
+  COLLAB_UPDATE = 'collab.update',
+  HOTKEY_PRESSED = 'hotkey.pressed',
+  DOCUMENT_EXPORTED = 'document.exported',
+  CAPTURE_REQUESTED = 'capture.requested',
+  CAPTURE_COMPLETED = 'capture.completed',
 }
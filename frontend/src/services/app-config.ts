@@ -0,0 +1,49 @@
+/**
+ * Runtime configuration published by the backend
+ *
+ * The HTTP and WebSocket ports can differ from the defaults when a port was
+ * taken at startup. webui.js sets window.__APP_CONFIG__; /config.json serves
+ * the same object.
+ */
+
+export interface FrontendConfig {
+  app_name: string;
+  version: string;
+  http_port: number;
+  ws_port: number;
+}
+
+declare global {
+  interface Window {
+    __APP_CONFIG__?: FrontendConfig;
+    /** Injected into index.html when `[server] ws_require_token` is set */
+    __WS_TOKEN__?: string;
+  }
+}
+
+const DEFAULT_WS_PORT = 9000;
+
+export function getFrontendConfig(): FrontendConfig | undefined {
+  return window.__APP_CONFIG__;
+}
+
+export async function loadFrontendConfig(): Promise<FrontendConfig | undefined> {
+  if (!window.__APP_CONFIG__) {
+    try {
+      const response = await fetch('/config.json');
+      if (response.ok) {
+        window.__APP_CONFIG__ = await response.json();
+      }
+    } catch {
+      // Served without the backend (e.g. rsbuild dev server); keep the defaults
+    }
+  }
+  return window.__APP_CONFIG__;
+}
+
+export function getWebSocketUrl(): string {
+  const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
+  const host = window.location.hostname || 'localhost';
+  const token = window.__WS_TOKEN__ ? `/?token=${encodeURIComponent(window.__WS_TOKEN__)}` : '';
+  return `${protocol}//${host}:${window.__APP_CONFIG__?.ws_port ?? DEFAULT_WS_PORT}${token}`;
+}
@@ -0,0 +1,20 @@
+/**
+ * DevTools API client
+ *
+ * The backend requires a per-run token on every /api/devtools/* request.
+ * It is injected into index.html as window.__DEVTOOLS_TOKEN__.
+ */
+
+declare global {
+  interface Window {
+    __DEVTOOLS_TOKEN__?: string;
+  }
+}
+
+export function devtoolsFetch(path: string, init: RequestInit = {}): Promise<Response> {
+  const headers = new Headers(init.headers);
+  if (window.__DEVTOOLS_TOKEN__) {
+    headers.set('X-DevTools-Token', window.__DEVTOOLS_TOKEN__);
+  }
+  return fetch(path, { ...init, headers });
+}
@@ -0,0 +1,116 @@
+/**
+ * File upload and download over the WebSocket
+ *
+ * Uploads send `file.upload_begin`, then each chunk as a `file.upload_chunk`
+ * binary frame, then `file.upload_end`. Downloads send `file.download` and
+ * collect the `file.download_chunk` frames until `file.download_end`. Every
+ * chunk and the whole file are checked against their SHA-256.
+ */
+
+import {
+  CommandError,
+  type CommandErrorInfo,
+  fileDownload,
+  fileUploadBegin,
+  fileUploadEnd,
+  type UploadedFile,
+} from '../generated/client';
+import { type BinaryFrame, sha256Hex } from '../view-models/binary-frames';
+import { getCommunicationBridge } from '../view-models/communication-bridge';
+
+export interface TransferOptions {
+  /** Called with the bytes transferred so far and the file size */
+  onProgress?: (bytes: number, total: number) => void;
+}
+
+export interface UploadOptions extends TransferOptions {
+  /** Folder within `[file_transfer] allowed_dirs`; the first one when omitted */
+  directory?: string;
+  overwrite?: boolean;
+}
+
+function bridge() {
+  const bridge = getCommunicationBridge();
+  if (!bridge) {
+    throw new Error('Communication bridge not initialized');
+  }
+  return bridge;
+}
+
+export async function uploadFile(file: File, options: UploadOptions = {}): Promise<UploadedFile> {
+  const data = new Uint8Array(await file.arrayBuffer());
+  const { upload } = await fileUploadBegin({
+    name: file.name,
+    size: data.length,
+    directory: options.directory,
+    overwrite: options.overwrite,
+    sha256: await sha256Hex(data),
+  });
+
+  for (let offset = 0; offset < data.length; offset += upload.chunk_size) {
+    const chunk = data.subarray(offset, offset + upload.chunk_size);
+    const response = await bridge().requestFrame(
+      'file.upload_chunk',
+      { upload_id: upload.upload_id, offset, sha256: await sha256Hex(chunk) },
+      chunk,
+    );
+    if (response?.success === false) {
+      throw new CommandError('file.upload_chunk', response.error as CommandErrorInfo);
+    }
+    options.onProgress?.(response.received, data.length);
+  }
+
+  const { file: uploaded } = await fileUploadEnd({ upload_id: upload.upload_id, overwrite: options.overwrite });
+  return uploaded;
+}
+
+export async function downloadFile(path: string, options: TransferOptions = {}): Promise<Blob> {
+  const chunks: Uint8Array[] = [];
+  let received = 0;
+  let size = 0;
+  let downloadId: string | null = null;
+  // Frames may arrive before the response to file.download, so collect them until the id is known
+  const early: BinaryFrame[] = [];
+  let finish: (frame: BinaryFrame) => void = () => {};
+  const ended = new Promise<BinaryFrame>(resolve => {
+    finish = resolve;
+  });
+
+  const handle = (frame: BinaryFrame) => {
+    if (frame.header.name === 'file.download_end') {
+      finish(frame);
+    } else if (frame.header.name === 'file.download_chunk') {
+      // Copied: the frame's data is a view on the message buffer
+      chunks.push(frame.data.slice());
+      received += frame.data.length;
+      options.onProgress?.(received, size);
+    }
+  };
+  const unsubscribe = bridge().onFrame(frame => {
+    if (downloadId === null) {
+      early.push(frame);
+    } else if (frame.header.payload.download_id === downloadId) {
+      handle(frame);
+    }
+  });
+
+  try {
+    const response = await fileDownload({ path });
+    downloadId = response.download_id;
+    size = response.size;
+    early.filter(frame => frame.header.payload.download_id === downloadId).forEach(handle);
+
+    const end = (await ended).header.payload;
+    if (end.success === false) {
+      throw new Error(`Download of ${path} failed: ${end.error}`);
+    }
+    const blob = new Blob(chunks);
+    const actual = await sha256Hex(await blob.arrayBuffer());
+    if (actual !== end.sha256) {
+      throw new Error(`Download of ${path} is corrupt: expected SHA-256 ${end.sha256}, got ${actual}`);
+    }
+    return blob;
+  } finally {
+    unsubscribe();
+  }
+}
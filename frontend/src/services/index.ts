@@ -1,3 +1,6 @@
 // Services module exports
+export * from './app-config';
+export * from './devtools-client';
 export * from './error-logger';
 export * from './window-manager';
+export * from './file-transfer';
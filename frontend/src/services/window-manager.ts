@@ -1,4 +1,7 @@
 import { EventBus, AppEventType } from '../models/event-bus';
+import { windowStateChange, type WindowStateChangeRequest } from '../generated/client';
+
+type WindowAction = WindowStateChangeRequest['action'];
 
 declare global {
   interface Window {
@@ -200,7 +203,7 @@ class WindowManager {
     }
   }
 
-  private logWindowEvent(action: string, windowInfo: WindowInfo) {
+  private logWindowEvent(action: WindowAction, windowInfo: WindowInfo) {
     const logMessage = `Window "${windowInfo.title}" (${windowInfo.id}) ${action}`;
     const logMeta = {
       windowId: windowInfo.id,
@@ -225,7 +228,7 @@ class WindowManager {
     this.emitWindowEventToBackend(action, windowInfo);
   }
 
-  private emitWindowEventToBackend(action: string, windowInfo: WindowInfo) {
+  private emitWindowEventToBackend(action: WindowAction, windowInfo: WindowInfo) {
     // Create the window state change payload
     const payload = {
       id: windowInfo.id,
@@ -238,20 +241,20 @@ class WindowManager {
       totalWindows: this.windows.size,
     };
 
-    // Try to send via WebUI call first
-    if (window.webui && typeof window.webui.call === 'function') {
-      try {
-        window.webui.call('window_state_change', payload);
-        console.log(`[WINDOW-MANAGER] Sent ${action} event for window "${windowInfo.title}" via WebUI`);
-      } catch (error) {
-        console.error(`[WINDOW-MANAGER] Error sending ${action} event via WebUI:`, error);
-        // Fallback to custom event
+    windowStateChange({
+      id: windowInfo.id,
+      action,
+      windowTitle: windowInfo.title,
+      maximized: windowInfo.maximized,
+    })
+      .then(() => {
+        console.log(`[WINDOW-MANAGER] Sent ${action} event for window "${windowInfo.title}"`);
+      })
+      .catch((error) => {
+        console.error(`[WINDOW-MANAGER] Error sending ${action} event:`, error);
+        // Fallback: custom event that gets picked up by the communication bridge
         this.dispatchCustomEvent(payload);
-      }
-    } else {
-      // Fallback: emit event to backend via custom event that gets picked up by communication bridge
-      this.dispatchCustomEvent(payload);
-    }
+      });
   }
 
   private dispatchCustomEvent(payload: any) {
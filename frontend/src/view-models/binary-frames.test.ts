@@ -0,0 +1,34 @@
+/**
+ * Binary Frame Tests
+ *
+ * Frames must match the layout the backend reads and writes.
+ */
+
+import { describe, test, expect } from 'bun:test';
+import { decodeFrame, encodeFrame, isFrame, sha256Hex } from './binary-frames';
+
+describe('binary frames', () => {
+  test('should round-trip header and data', () => {
+    const header = { id: '1', name: 'file.upload_chunk', payload: { offset: 0 }, timestamp: 0, source: 'frontend' };
+    const frame = encodeFrame(header, new Uint8Array([1, 2, 3]));
+
+    expect(Array.from(frame.subarray(0, 4))).toEqual([0x57, 0x55, 0x42, 0x46]);
+    const decoded = decodeFrame(frame.buffer as ArrayBuffer);
+    expect(decoded?.header).toEqual(header);
+    expect(Array.from(decoded?.data ?? [])).toEqual([1, 2, 3]);
+  });
+
+  test('should reject JSON and truncated frames', () => {
+    const json = new TextEncoder().encode('{"name":"get_users"}');
+    expect(isFrame(json.buffer as ArrayBuffer)).toBe(false);
+
+    const frame = encodeFrame({ id: '1', name: 'x', payload: {}, timestamp: 0, source: 'frontend' }, new Uint8Array());
+    expect(decodeFrame(frame.slice(0, 10).buffer as ArrayBuffer)).toBeNull();
+  });
+
+  test('should hash like the backend', async () => {
+    expect(await sha256Hex(new TextEncoder().encode('hello!'))).toBe(
+      'ce06092fb948d9ffac7d1a376e404b26b7575bcc11ee05a4615fef4fec3a308b',
+    );
+  });
+});
@@ -0,0 +1,61 @@
+/**
+ * Binary file frames
+ *
+ * File data travels over the WebSocket as binary frames: the `WUBF` magic,
+ * the length of a JSON header as a big-endian u32, the header - a regular
+ * `{ id, name, payload, timestamp, source }` message - and the raw bytes.
+ */
+
+export const FRAME_MAGIC = new Uint8Array([0x57, 0x55, 0x42, 0x46]); // "WUBF"
+
+export interface FrameHeader {
+  id: string;
+  name: string;
+  payload: Record<string, unknown>;
+  timestamp: number;
+  source: string;
+}
+
+export interface BinaryFrame {
+  header: FrameHeader;
+  data: Uint8Array;
+}
+
+export function isFrame(buffer: ArrayBuffer): boolean {
+  const bytes = new Uint8Array(buffer, 0, Math.min(buffer.byteLength, FRAME_MAGIC.length));
+  return bytes.length === FRAME_MAGIC.length && bytes.every((byte, i) => byte === FRAME_MAGIC[i]);
+}
+
+export function encodeFrame(header: FrameHeader, data: Uint8Array): Uint8Array {
+  const json = new TextEncoder().encode(JSON.stringify(header));
+  const frame = new Uint8Array(FRAME_MAGIC.length + 4 + json.length + data.length);
+  frame.set(FRAME_MAGIC, 0);
+  new DataView(frame.buffer).setUint32(FRAME_MAGIC.length, json.length);
+  frame.set(json, FRAME_MAGIC.length + 4);
+  frame.set(data, FRAME_MAGIC.length + 4 + json.length);
+  return frame;
+}
+
+/** Split a frame into header and data; null if it is not a well-formed frame */
+export function decodeFrame(buffer: ArrayBuffer): BinaryFrame | null {
+  if (!isFrame(buffer) || buffer.byteLength < FRAME_MAGIC.length + 4) {
+    return null;
+  }
+  const length = new DataView(buffer).getUint32(FRAME_MAGIC.length);
+  const start = FRAME_MAGIC.length + 4;
+  if (buffer.byteLength < start + length) {
+    return null;
+  }
+  try {
+    const header = JSON.parse(new TextDecoder().decode(new Uint8Array(buffer, start, length)));
+    return { header, data: new Uint8Array(buffer, start + length) };
+  } catch {
+    return null;
+  }
+}
+
+/** Lowercase hex SHA-256, as the backend reports checksums */
+export async function sha256Hex(data: BufferSource): Promise<string> {
+  const digest = await crypto.subtle.digest('SHA-256', data);
+  return Array.from(new Uint8Array(digest), byte => byte.toString(16).padStart(2, '0')).join('');
+}
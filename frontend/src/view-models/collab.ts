@@ -0,0 +1,94 @@
+// Shared documents edited together across windows and clients (`collab` feature)
+//
+// `openSharedDocument` keeps a Yjs `Y.Doc` in step with the backend's copy:
+// it merges what `collab.sync` returns, sends back what the backend lacks,
+// sends every local change as `collab.update` and applies the `collab.update`
+// events of other clients. Each document is synced again after a reconnect,
+// so changes made while offline are merged then.
+
+import * as Y from 'yjs';
+import { collabSync, collabUpdate, onCollabUpdate } from '../generated/client';
+import { AppEventType, EventBus } from '../models/event-bus';
+
+/** Origin of the changes that came from the backend, which are not sent back */
+const REMOTE = Symbol('collab.remote');
+
+/** Told to the backend with every update, so this client can skip its own */
+const clientOrigin = crypto.randomUUID ? crypto.randomUUID() : Math.random().toString(36).substring(2, 15);
+
+const toBase64 = (bytes: Uint8Array): string => {
+  let binary = '';
+  for (const byte of bytes) binary += String.fromCharCode(byte);
+  return btoa(binary);
+};
+
+const fromBase64 = (encoded: string): Uint8Array => Uint8Array.from(atob(encoded), (char) => char.charCodeAt(0));
+
+/** An update without changes, which Yjs encodes in two bytes */
+const isEmpty = (update: Uint8Array) => update.length <= 2;
+
+export interface SharedDocument {
+  doc: Y.Doc;
+  close: () => void;
+}
+
+/** Open shared document `name`; `onError` hears about syncs that failed, e.g. on a build without the feature */
+export const openSharedDocument = (name: string, onError: (error: unknown) => void = () => undefined): SharedDocument => {
+  const doc = new Y.Doc();
+
+  const send = (update: Uint8Array) =>
+    collabUpdate({ document: name, update: toBase64(update), origin: clientOrigin }).catch(onError);
+
+  const sync = async () => {
+    try {
+      const response = await collabSync({ document: name, state_vector: toBase64(Y.encodeStateVector(doc)) });
+      Y.applyUpdate(doc, fromBase64(response.update), REMOTE);
+      const missing = Y.encodeStateAsUpdate(doc, fromBase64(response.state_vector));
+      if (!isEmpty(missing)) await send(missing);
+    } catch (error) {
+      onError(error);
+    }
+  };
+
+  const onLocalUpdate = (update: Uint8Array, origin: unknown) => {
+    if (origin !== REMOTE) send(update);
+  };
+  doc.on('update', onLocalUpdate);
+
+  const detachUpdates = onCollabUpdate((event) => {
+    if (event.document === name && event.origin !== clientOrigin) {
+      Y.applyUpdate(doc, fromBase64(event.update), REMOTE);
+    }
+  });
+  const detachReconnect = EventBus.subscribe(AppEventType.BACKEND_CONNECTED, () => sync());
+  sync();
+
+  return {
+    doc,
+    close: () => {
+      detachUpdates();
+      detachReconnect();
+      doc.off('update', onLocalUpdate);
+      doc.destroy();
+    },
+  };
+};
+
+/** Change `text` to read `next`, touching only the part that differs */
+export const replaceText = (text: Y.Text, next: string) => {
+  const current = text.toString();
+  let start = 0;
+  while (start < current.length && start < next.length && current[start] === next[start]) start++;
+  let end = 0;
+  while (
+    end < current.length - start &&
+    end < next.length - start &&
+    current[current.length - 1 - end] === next[next.length - 1 - end]
+  ) {
+    end++;
+  }
+  text.doc?.transact(() => {
+    text.delete(start, current.length - start - end);
+    text.insert(start, next.slice(start, next.length - end));
+  });
+};
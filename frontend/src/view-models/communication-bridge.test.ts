@@ -11,6 +11,7 @@ import {
   ErrorType,
   getCommunicationBridge,
   isBackendConnected,
+  requestFromBackend,
 } from '../view-models/communication-bridge';
 
 describe('ConnectionState enum', () => {
@@ -53,3 +54,9 @@ describe('isBackendConnected', () => {
     expect(connected).toBe(false);
   });
 });
+
+describe('requestFromBackend', () => {
+  test('should reject when not initialized', async () => {
+    await expect(requestFromBackend('counter.list')).rejects.toThrow('not initialized');
+  });
+});
@@ -1,4 +1,6 @@
 import { EventBus, AppEventType } from '../models/event-bus';
+import { getWebSocketUrl } from '../services/app-config';
+import { type BinaryFrame, decodeFrame, encodeFrame } from './binary-frames';
 
 // WebSocket connection states (matching WebSocket API)
 enum WSReadyState {
@@ -53,6 +55,12 @@ interface ConnectionStats {
   connectionStartTime: number;
 }
 
+interface PendingRequest {
+  resolve: (response: any) => void;
+  reject: (error: ConnectionError) => void;
+  timer: ReturnType<typeof setTimeout>;
+}
+
 // Communication bridge between frontend and backend
 class CommunicationBridge {
   private ws: WebSocket | null = null;
@@ -67,6 +75,9 @@ class CommunicationBridge {
   private connectionTimer: ReturnType<typeof setTimeout> | null = null;
   private pingInterval: ReturnType<typeof setInterval> | null = null;
   private readonly pingIntervalMs: number = 30000;
+  private readonly requestTimeoutMs: number = 30000;
+  private pendingRequests: Map<string, PendingRequest> = new Map();
+  private frameListeners: Set<(frame: BinaryFrame) => void> = new Set();
 
   constructor(private backendUrl: string = 'ws://localhost:8080/ws') {
     this.connect();
@@ -143,6 +154,7 @@ class CommunicationBridge {
       }, this.connectionTimeout);
 
       this.ws = new WebSocket(this.backendUrl);
+      this.ws.binaryType = 'arraybuffer';
 
       this.ws.onopen = () => {
         if (this.connectionTimer) {
@@ -164,12 +176,31 @@ class CommunicationBridge {
 
       this.ws.onmessage = (event) => {
         this.stats.messagesReceived++;
-        this.stats.bytesReceived += event.data.length;
+        this.stats.bytesReceived += typeof event.data === 'string' ? event.data.length : event.data.byteLength;
         this.stats.lastMessageAt = Date.now();
+
+        // Binary frames carry file data (file.download_chunk, file.download_end)
+        if (event.data instanceof ArrayBuffer) {
+          const frame = decodeFrame(event.data);
+          if (frame) {
+            this.frameListeners.forEach(listener => listener(frame));
+          } else {
+            console.warn('[CommunicationBridge] Ignoring unknown binary message');
+          }
+          return;
+        }
         
         try {
           const eventData = JSON.parse(event.data);
           console.log('[CommunicationBridge] Received message:', eventData);
+
+          // Responses carry the id of the request they answer
+          const pending = eventData.id ? this.pendingRequests.get(eventData.id) : undefined;
+          if (pending) {
+            clearTimeout(pending.timer);
+            this.pendingRequests.delete(eventData.id);
+            pending.resolve(eventData.payload);
+          }
           
           // Emit the received event through the event bus
           EventBus.emitSimple(eventData.name, {
@@ -198,6 +229,7 @@ class CommunicationBridge {
         
         const oldState = this.connectionState;
         this.setConnectionState(ConnectionState.CLOSED, `code_${event.code}`);
+        this.rejectPendingRequests(this.createError(ErrorType.TRANSPORT_ERROR, 'Connection closed before a response arrived'));
         
         EventBus.emitSimple(AppEventType.BACKEND_DISCONNECTED, {
           timestamp: Date.now(),
@@ -277,8 +309,13 @@ class CommunicationBridge {
     }
   }
 
-  // Send an event to the backend
-  public sendToBackend(eventType: string, payload: any): boolean {
+  // Send an event to the backend; a command with a `mutationId` is acknowledged and settled as a mutation
+  public sendToBackend(
+    eventType: string,
+    payload: any,
+    id: string = Math.random().toString(36).substring(2, 15),
+    mutationId?: string,
+  ): boolean {
     if (!this.ws || this.ws.readyState !== WSReadyState.OPEN) {
       console.warn('[CommunicationBridge] WebSocket not connected, cannot send:', eventType);
       EventBus.emitSimple(AppEventType.BACKEND_ERROR, {
@@ -288,11 +325,12 @@ class CommunicationBridge {
     }
 
     const event = {
-      id: Math.random().toString(36).substring(2, 15),
+      id,
       name: eventType,
       payload: payload,
       timestamp: Date.now(),
-      source: 'frontend'
+      source: 'frontend',
+      ...(mutationId ? { mutation_id: mutationId } : {})
     };
 
     try {
@@ -311,6 +349,60 @@ class CommunicationBridge {
     }
   }
 
+  // Send a binary file frame; its response arrives like any other
+  public sendFrame(name: string, payload: Record<string, unknown>, data: Uint8Array, id: string): boolean {
+    if (!this.ws || this.ws.readyState !== WSReadyState.OPEN) {
+      console.warn('[CommunicationBridge] WebSocket not connected, cannot send frame:', name);
+      return false;
+    }
+    const frame = encodeFrame({ id, name, payload, timestamp: Date.now(), source: 'frontend' }, data);
+    this.ws.send(frame);
+    this.stats.messagesSent++;
+    this.stats.bytesSent += frame.byteLength;
+    return true;
+  }
+
+  // Listen to binary frames from the backend; returns the unsubscribe function
+  public onFrame(listener: (frame: BinaryFrame) => void): () => void {
+    this.frameListeners.add(listener);
+    return () => this.frameListeners.delete(listener);
+  }
+
+  // Send a command and resolve with the backend's response to it
+  public request(name: string, payload: object = {}, timeoutMs: number = this.requestTimeoutMs, mutationId?: string): Promise<any> {
+    return this.awaitResponse(name, timeoutMs, id => this.sendToBackend(name, payload, id, mutationId));
+  }
+
+  // Send a binary file frame and resolve with the backend's response to it
+  public requestFrame(name: string, payload: Record<string, unknown>, data: Uint8Array, timeoutMs: number = this.requestTimeoutMs): Promise<any> {
+    return this.awaitResponse(name, timeoutMs, id => this.sendFrame(name, payload, data, id));
+  }
+
+  private awaitResponse(name: string, timeoutMs: number, send: (id: string) => boolean): Promise<any> {
+    const id = crypto.randomUUID ? crypto.randomUUID() : Math.random().toString(36).substring(2, 15);
+    return new Promise((resolve, reject) => {
+      const timer = setTimeout(() => {
+        this.pendingRequests.delete(id);
+        reject(this.createError(ErrorType.TIMEOUT, `No response to ${name} after ${timeoutMs}ms`));
+      }, timeoutMs);
+      this.pendingRequests.set(id, { resolve, reject, timer });
+
+      if (!send(id)) {
+        clearTimeout(timer);
+        this.pendingRequests.delete(id);
+        reject(this.createError(ErrorType.TRANSPORT_ERROR, 'WebSocket not connected'));
+      }
+    });
+  }
+
+  private rejectPendingRequests(error: ConnectionError): void {
+    this.pendingRequests.forEach(pending => {
+      clearTimeout(pending.timer);
+      pending.reject(error);
+    });
+    this.pendingRequests.clear();
+  }
+
   // Setup event listeners to forward events to backend
   private setupEventListeners(): void {
     // Listen for specific events that should be sent to backend
@@ -411,8 +503,8 @@ let communicationBridge: CommunicationBridge | null = null;
 
 export const initCommunicationBridge = (backendUrl?: string): CommunicationBridge => {
   if (!communicationBridge) {
-    // Default to WebSocket port 9000 (matching backend)
-    communicationBridge = new CommunicationBridge(backendUrl || 'ws://localhost:9000');
+    // Default to the WebSocket port published by the backend
+    communicationBridge = new CommunicationBridge(backendUrl || getWebSocketUrl());
   }
   return communicationBridge;
 };
@@ -431,6 +523,15 @@ export const sendEventToBackend = (eventType: string, payload: any): void => {
   }
 };
 
+// Helper function to send a command and wait for its response
+export const requestFromBackend = (name: string, payload: object = {}, mutationId?: string): Promise<any> => {
+  const bridge = getCommunicationBridge();
+  if (!bridge) {
+    return Promise.reject(new Error(`Communication bridge not initialized, cannot send ${name}`));
+  }
+  return bridge.request(name, payload, undefined, mutationId);
+};
+
 // Helper function to check connection status
 export const isBackendConnected = (): boolean => {
   const bridge = getCommunicationBridge();
@@ -1,2 +1,4 @@
 // ViewModels module exports
-export * from './communication-bridge';
\ No newline at end of file
+export * from './communication-bridge';
+export * from './binary-frames';
+export * from './state-sync';
@@ -0,0 +1,47 @@
+/**
+ * State Sync Tests
+ *
+ * Patches from `state.patch` must take a mirror to the backend's document.
+ */
+
+import { describe, test, expect } from 'bun:test';
+import { applyPatch } from './state-sync';
+
+describe('applyPatch', () => {
+  test('should apply the operations the backend sends', () => {
+    const users = [
+      { id: 1, name: 'Ann' },
+      { id: 2, name: 'Bob' },
+    ];
+    const patched = applyPatch(users, [
+      { op: 'replace', path: '/0/name', value: 'Anna' },
+      { op: 'remove', path: '/1' },
+      { op: 'add', path: '/-', value: { id: 3, name: 'Cy' } },
+    ]);
+
+    expect(patched).toEqual([
+      { id: 1, name: 'Anna' },
+      { id: 3, name: 'Cy' },
+    ]);
+    // The mirror's current state is left alone
+    expect(users[0].name).toBe('Ann');
+  });
+
+  test('should handle escaped keys, moves and the whole document', () => {
+    const system = { info: { 'a/b': 1, 'c~d': 2 }, metrics: null };
+    expect(
+      applyPatch(system, [
+        { op: 'move', from: '/info/a~1b', path: '/info/ab' },
+        { op: 'copy', from: '/info/c~0d', path: '/metrics' },
+        { op: 'test', path: '/metrics', value: 2 },
+      ]),
+    ).toEqual({ info: { ab: 1, 'c~d': 2 }, metrics: 2 });
+    expect(applyPatch(system, [{ op: 'replace', path: '', value: [] }])).toEqual([]);
+  });
+
+  test('should reject a patch that does not fit the document', () => {
+    expect(() => applyPatch([1], [{ op: 'remove', path: '/3' }])).toThrow();
+    expect(() => applyPatch({ a: 1 }, [{ op: 'replace', path: '/b', value: 2 }])).toThrow();
+    expect(() => applyPatch({ a: 1 }, [{ op: 'test', path: '/a', value: 2 }])).toThrow();
+  });
+});
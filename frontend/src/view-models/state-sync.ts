@@ -0,0 +1,267 @@
+// Mirrors of the backend's state documents (`users`, `counters`, `system`)
+//
+// `subscribeState` asks for a document with `state.subscribe` and keeps it
+// current by applying the JSON Patch of every `state.patch` event. Patches
+// must arrive in version order; after a gap, a patch that does not apply or
+// a reconnect, the document is read again.
+//
+// `mutateState` shows a change before the backend made it: listeners see the
+// document with the change applied until the command's `command.applied` or
+// `command.rejected` arrives with the backend's own state, which replaces the
+// guess or, when the command failed, rolls it back.
+
+import {
+  call,
+  type CommandAppliedEvent,
+  type CommandRejectedEvent,
+  type JsonPatchOperation,
+  onCommandApplied,
+  onCommandRejected,
+  onStatePatch,
+  type StatePatchEvent,
+  stateSubscribe,
+  stateUnsubscribe,
+} from '../generated/client';
+import { AppEventType, EventBus } from '../models/event-bus';
+
+export type StateDocument = StatePatchEvent['document'];
+
+type StateListener = (state: unknown) => void;
+
+type Update = (state: any) => unknown;
+
+interface Mirror {
+  /** 0 until the document is loaded */
+  version: number;
+  /** As the backend sent it */
+  state: unknown;
+  /** Changes shown ahead of the backend, by mutation id, oldest first */
+  pending: Map<string, Update>;
+  listeners: Set<StateListener>;
+}
+
+const unescapeToken = (token: string): string => token.replace(/~1/g, '/').replace(/~0/g, '~');
+
+const parsePointer = (pointer: string): string[] => {
+  if (pointer === '') return [];
+  if (!pointer.startsWith('/')) throw new Error(`Invalid JSON pointer: ${pointer}`);
+  return pointer.slice(1).split('/').map(unescapeToken);
+};
+
+const deepEqual = (a: unknown, b: unknown): boolean => JSON.stringify(a) === JSON.stringify(b);
+
+/** The container holding the last token of `path`, and that token */
+const locate = (document: unknown, path: string[]): [any, string] => {
+  let parent: any = document;
+  for (const token of path.slice(0, -1)) {
+    parent = parent?.[token];
+    if (parent === null || typeof parent !== 'object') throw new Error(`No such path: /${path.join('/')}`);
+  }
+  return [parent, path[path.length - 1]];
+};
+
+const arrayIndex = (array: unknown[], token: string, inserting: boolean): number => {
+  const index = token === '-' && inserting ? array.length : Number(token);
+  const limit = inserting ? array.length : array.length - 1;
+  if (!/^(0|[1-9]\d*|-)$/.test(token) || !Number.isInteger(index) || index > limit) {
+    throw new Error(`Index ${token} out of range`);
+  }
+  return index;
+};
+
+const read = (document: unknown, path: string[]): unknown => {
+  if (path.length === 0) return document;
+  const [parent, token] = locate(document, path);
+  const value = Array.isArray(parent) ? parent[arrayIndex(parent, token, false)] : parent[token];
+  if (value === undefined) throw new Error(`No such path: /${path.join('/')}`);
+  return value;
+};
+
+const add = (document: unknown, path: string[], value: unknown): unknown => {
+  if (path.length === 0) return value;
+  const [parent, token] = locate(document, path);
+  if (Array.isArray(parent)) {
+    parent.splice(arrayIndex(parent, token, true), 0, value);
+  } else {
+    parent[token] = value;
+  }
+  return document;
+};
+
+const remove = (document: unknown, path: string[]): unknown => {
+  if (path.length === 0) return null;
+  const [parent, token] = locate(document, path);
+  if (Array.isArray(parent)) {
+    parent.splice(arrayIndex(parent, token, false), 1);
+  } else {
+    if (!(token in parent)) throw new Error(`No such path: /${path.join('/')}`);
+    delete parent[token];
+  }
+  return document;
+};
+
+/** `document` with RFC 6902 `operations` applied; the document itself is left as it was */
+export const applyPatch = (document: unknown, operations: JsonPatchOperation[]): unknown => {
+  let result: unknown = structuredClone(document);
+  for (const operation of operations) {
+    const path = parsePointer(operation.path);
+    switch (operation.op) {
+      case 'add':
+        result = add(result, path, structuredClone(operation.value));
+        break;
+      case 'remove':
+        result = remove(result, path);
+        break;
+      case 'replace':
+        read(result, path);
+        result = add(remove(result, path), path, structuredClone(operation.value));
+        break;
+      case 'move': {
+        const from = parsePointer(operation.from ?? '');
+        const value = read(result, from);
+        result = add(remove(result, from), path, value);
+        break;
+      }
+      case 'copy':
+        result = add(result, path, structuredClone(read(result, parsePointer(operation.from ?? ''))));
+        break;
+      case 'test':
+        if (!deepEqual(read(result, path), operation.value)) throw new Error(`Test failed at ${operation.path}`);
+        break;
+    }
+  }
+  return result;
+};
+
+const mirrors = new Map<StateDocument, Mirror>();
+let detachListeners: (() => void) | null = null;
+
+/** The state listeners see: the backend's with the pending changes applied */
+const view = (mirror: Mirror): unknown => {
+  let state = mirror.state;
+  for (const [mutationId, update] of mirror.pending) {
+    try {
+      state = update(state);
+    } catch (error) {
+      console.warn(`[StateSync] Mutation ${mutationId} does not fit the current state`, error);
+    }
+  }
+  return state;
+};
+
+const publish = (mirror: Mirror) => {
+  if (mirror.version === 0) return;
+  const state = view(mirror);
+  for (const listener of mirror.listeners) listener(state);
+};
+
+const load = (document: StateDocument) =>
+  stateSubscribe({ document })
+    .then((response) => {
+      const mirror = mirrors.get(document);
+      // An earlier, slower load must not undo a later one
+      if (mirror && response.version >= mirror.version) {
+        mirror.version = response.version;
+        mirror.state = response.state;
+        publish(mirror);
+      }
+    })
+    .catch((error) => console.warn(`[StateSync] Failed to load the ${document} state`, error));
+
+const resync = (document: StateDocument) => {
+  const mirror = mirrors.get(document);
+  if (mirror) mirror.version = 0;
+  load(document);
+};
+
+const applyEvent = (event: StatePatchEvent) => {
+  const mirror = mirrors.get(event.document);
+  if (!mirror || mirror.version === 0 || event.version <= mirror.version) return;
+  if (event.version !== mirror.version + 1) {
+    resync(event.document);
+    return;
+  }
+  try {
+    mirror.state = applyPatch(mirror.state, event.patch);
+    mirror.version = event.version;
+    publish(mirror);
+  } catch (error) {
+    console.warn(`[StateSync] Patch ${event.version} of ${event.document} did not apply`, error);
+    resync(event.document);
+  }
+};
+
+/** Drop the change of a settled mutation and take the state it came with */
+const settle = (event: CommandAppliedEvent | CommandRejectedEvent) => {
+  const mirror = event.document ? mirrors.get(event.document) : undefined;
+  if (!mirror) return;
+  mirror.pending.delete(event.mutation_id);
+  // Patches up to the same version may still be on their way; they are ignored
+  if (event.version !== undefined && event.version >= mirror.version) {
+    mirror.version = event.version;
+    mirror.state = event.state;
+  }
+  publish(mirror);
+};
+
+const attachListeners = () => {
+  const detachPatches = onStatePatch(applyEvent);
+  const detachApplied = onCommandApplied(settle);
+  const detachRejected = onCommandRejected(settle);
+  // A new connection starts without subscriptions
+  const detachReconnect = EventBus.subscribe(AppEventType.BACKEND_CONNECTED, () => {
+    for (const document of mirrors.keys()) resync(document);
+  });
+  return () => {
+    detachPatches();
+    detachApplied();
+    detachRejected();
+    detachReconnect();
+  };
+};
+
+/** Call `listener` with `document` now (once loaded) and after every change; returns the unsubscribe function */
+export const subscribeState = (document: StateDocument, listener: StateListener): (() => void) => {
+  detachListeners ??= attachListeners();
+  let mirror = mirrors.get(document);
+  if (!mirror) {
+    mirror = { version: 0, state: undefined, pending: new Map(), listeners: new Set() };
+    mirrors.set(document, mirror);
+    load(document);
+  } else if (mirror.version > 0) {
+    listener(view(mirror));
+  }
+  mirror.listeners.add(listener);
+
+  return () => {
+    const current = mirrors.get(document);
+    if (!current?.listeners.delete(listener) || current.listeners.size > 0) return;
+    mirrors.delete(document);
+    stateUnsubscribe({ document }).catch(() => undefined);
+    if (mirrors.size === 0) {
+      detachListeners?.();
+      detachListeners = null;
+    }
+  };
+};
+
+/**
+ * Send command `name` as a mutation of `document`, showing `update` of the
+ * document to its listeners until the backend settles it; resolves with the
+ * response, failures reject with a `CommandError` after the rollback
+ */
+export const mutateState = async <T>(document: StateDocument, name: string, payload: object, update: Update): Promise<T> => {
+  const mutationId = crypto.randomUUID ? crypto.randomUUID() : Math.random().toString(36).substring(2, 15);
+  const mirror = mirrors.get(document);
+  if (mirror) {
+    mirror.pending.set(mutationId, update);
+    publish(mirror);
+  }
+  try {
+    return await call<T>(name, payload, mutationId);
+  } finally {
+    // Settled already unless the outcome never came, e.g. on a timeout or a lost connection
+    const current = mirrors.get(document);
+    if (current?.pending.delete(mutationId)) publish(current);
+  }
+};
@@ -5,10 +5,14 @@ import { Header } from './components/Header';
 import { Sidebar } from './components/Sidebar';
 import { MainContent } from './components/MainContent';
 import { BottomPanel } from './components/BottomPanel';
+import { CapturePrompt } from './components/CapturePrompt';
 import { useWebSocketStatus, useAppInitialization, useWindowManager } from './hooks/useAppLogic';
 import { useWindowOperations } from './hooks/useWindowOperations';
+import { useSyncedState } from './hooks/useSyncedState';
+import { usePresenceRegistration } from './hooks/usePresence';
+import { useHotkeyAction } from './hooks/useHotkeys';
 import { Logger } from './utils/logger';
-import { EventBus, AppEventType } from '../models/event-bus';
+import type { User } from '../generated/client';
 
 const App: React.FC = () => {
   // Initialize app
@@ -25,6 +29,7 @@ const App: React.FC = () => {
     closeWindow,
     closeAllWindows,
     hideAllWindows,
+    toggleAllWindows,
     dbUsers,
     setDbUsers,
     updateSQLiteTable,
@@ -32,26 +37,27 @@ const App: React.FC = () => {
     openSQLiteWindow,
   } = useWindowOperations(setActiveWindows);
 
-  // Handle database response
+  // Present on the backend, for the app's other windows
+  usePresenceRegistration({ name: document.title || undefined, kind: 'window' });
+
+  // Global shortcut from [hotkeys.bindings], see app.config.toml
+  useHotkeyAction('windows.toggle', toggleAllWindows);
+
+  // Users, mirrored from the backend's `users` state document
+  const users = useSyncedState<User[]>('users');
   useEffect(() => {
-    const handleDbResponse = ((event: CustomEvent) => {
-      const response = event.detail;
-      if (response.success) {
-        setDbUsers(response.data || []);
-        Logger.info('Users loaded from database', { count: response.data?.length || 0 });
-        updateSQLiteTable();
-
-        // Emit data changed event
-        EventBus.emitSimple('data.changed', {
-          table: 'users',
-          count: response.data?.length || 0,
-          action: 'loaded'
-        });
-      } else {
-        Logger.error('Failed to load users', { error: response.error });
-      }
-    }) as EventListener;
+    if (users) {
+      setDbUsers(users);
+      Logger.info('Users updated from the backend', { count: users.length });
+    }
+  }, [users, setDbUsers]);
 
+  // Redraw an open SQLite window once the users change
+  useEffect(() => {
+    updateSQLiteTable();
+  }, [updateSQLiteTable]);
+
+  useEffect(() => {
     const handleStatsResponse = ((event: CustomEvent) => {
       const response = event.detail;
       if (response.success) {
@@ -59,14 +65,9 @@ const App: React.FC = () => {
       }
     }) as EventListener;
 
-    window.addEventListener('db_response', handleDbResponse);
     window.addEventListener('stats_response', handleStatsResponse);
-
-    return () => {
-      window.removeEventListener('db_response', handleDbResponse);
-      window.removeEventListener('stats_response', handleStatsResponse);
-    };
-  }, [setDbUsers, updateSQLiteTable]);
+    return () => window.removeEventListener('stats_response', handleStatsResponse);
+  }, []);
 
   // Handle window resize
   useEffect(() => {
@@ -406,6 +407,66 @@ const App: React.FC = () => {
           font-weight: 500;
         }
 
+        .collab-notes {
+          background: white;
+          border-radius: 12px;
+          box-shadow: 0 4px 6px rgba(0,0,0,0.05);
+          padding: 1.25rem;
+          max-width: 800px;
+          margin: 0 auto;
+        }
+
+        .collab-notes-text {
+          width: 100%;
+          min-height: 160px;
+          padding: 0.75rem;
+          border: 1px solid #e2e8f0;
+          border-radius: 8px;
+          font-family: inherit;
+          font-size: 0.9rem;
+          resize: vertical;
+        }
+
+        .capture-prompt {
+          position: fixed;
+          top: 1rem;
+          right: 1rem;
+          z-index: 10000;
+          width: 320px;
+          background: white;
+          border-radius: 12px;
+          box-shadow: 0 10px 25px rgba(0,0,0,0.15);
+          padding: 1.25rem;
+        }
+
+        .capture-prompt-thumbnail {
+          display: block;
+          max-width: 100%;
+          margin-bottom: 0.5rem;
+          border: 1px solid #e2e8f0;
+          border-radius: 6px;
+        }
+
+        .capture-prompt-actions {
+          display: flex;
+          justify-content: flex-end;
+          gap: 0.5rem;
+        }
+
+        .capture-prompt-actions button {
+          padding: 0.4rem 0.9rem;
+          border: 1px solid #e2e8f0;
+          border-radius: 6px;
+          background: white;
+          cursor: pointer;
+        }
+
+        .capture-prompt-actions button.primary {
+          background: #4f46e5;
+          border-color: #4f46e5;
+          color: white;
+        }
+
         /* WinBox windows should respect sidebar width */
         .winbox {
           left: 200px !important;
@@ -475,6 +536,8 @@ const App: React.FC = () => {
 
         <BottomPanel wsStatus={wsStatus} />
       </div>
+
+      <CapturePrompt />
     </>
   );
 };
@@ -14,6 +14,8 @@
 import React, { useState, useEffect, useCallback, useRef } from 'react';
 import { EventBus, AppEventType } from '../../models/event-bus';
 import { ErrorLogger, useErrorLogger } from '../../services/error-logger';
+import { devtoolsFetch } from '../../services/devtools-client';
+import { getFrontendConfig } from '../../services/app-config';
 import type { WsStatus } from '../../types';
 
 // Types
@@ -35,7 +37,29 @@ interface SystemMetrics {
   events: {
     total_emitted: number;
     recent_events: { id: string; name: string; timestamp: string; source: string }[];
+    topics: {
+      name: string;
+      emitted: number;
+      last_emitted: string;
+      handler_calls: number;
+      handler_errors: number;
+      handler_total_us: number;
+      handler_max_us: number;
+    }[];
+    listeners: number;
+    open_taps: number;
   };
+  commands: {
+    command: string;
+    count: number;
+    errors: number;
+    error_rate: number;
+    mean_ms: number;
+    p50_ms: number;
+    p95_ms: number;
+    p99_ms: number;
+    max_ms: number;
+  }[];
 }
 
 interface LogEntry {
@@ -85,7 +109,7 @@ export const BottomPanel: React.FC<{ wsStatus: WsStatus }> = ({ wsStatus }) => {
 
     const fetchMetrics = async () => {
       try {
-        const response = await fetch('/api/devtools/metrics');
+        const response = await devtoolsFetch('/api/devtools/metrics');
         if (response.ok) {
           const data = await response.json();
           setSystemMetrics(data);
@@ -98,7 +122,8 @@ export const BottomPanel: React.FC<{ wsStatus: WsStatus }> = ({ wsStatus }) => {
           memory: { process_memory_mb: 0, available_system_mb: 0 },
           connections: { websocket_active: 0, http_requests_total: 0 },
           database: { tables: [], total_records: 0 },
-          events: { total_emitted: 0, recent_events: [] },
+          events: { total_emitted: 0, recent_events: [], topics: [], listeners: 0, open_taps: 0 },
+          commands: [],
         });
       }
     };
@@ -688,8 +713,8 @@ export const BottomPanel: React.FC<{ wsStatus: WsStatus }> = ({ wsStatus }) => {
                   <ConfigRow label="Frontend" value="React 18 + TypeScript" />
                   <ConfigRow label="Backend" value="Rust + WebUI" />
                   <ConfigRow label="Database" value="SQLite" />
-                  <ConfigRow label="WebSocket Port" value="9000" />
-                  <ConfigRow label="HTTP Port" value="8080" />
+                  <ConfigRow label="WebSocket Port" value={String(getFrontendConfig()?.ws_port ?? 9000)} />
+                  <ConfigRow label="HTTP Port" value={String(getFrontendConfig()?.http_port ?? window.location.port)} />
                 </div>
               </div>
             )}
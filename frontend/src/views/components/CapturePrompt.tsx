@@ -0,0 +1,48 @@
+// Asks the user before a window or screen is captured, then shows the screenshot taken
+
+import React, { useState } from 'react';
+import type { CaptureConfirmResponse } from '../../generated/client';
+import { useCaptureRequests } from '../hooks/useCapture';
+
+export const CapturePrompt: React.FC = () => {
+  const { request, pending, answer } = useCaptureRequests();
+  const [captured, setCaptured] = useState<CaptureConfirmResponse | null>(null);
+
+  const respond = async (allow: boolean) => {
+    const result = await answer(allow);
+    if (result?.thumbnail) setCaptured(result);
+  };
+
+  if (request) {
+    return (
+      <div className="capture-prompt" role="alertdialog" aria-labelledby="capture-prompt-title">
+        <h3 id="capture-prompt-title" className="card-title">Allow a screenshot?</h3>
+        <p className="card-description">
+          Something asked to capture the {request.kind} <strong>{request.target}</strong>
+          {pending > 1 ? ` (${pending - 1} more waiting)` : ''}.
+        </p>
+        <div className="capture-prompt-actions">
+          <button type="button" onClick={() => respond(false)}>Deny</button>
+          <button type="button" className="primary" onClick={() => respond(true)}>Allow</button>
+        </div>
+      </div>
+    );
+  }
+
+  if (captured) {
+    return (
+      <div className="capture-prompt" role="status">
+        <h3 className="card-title">Screenshot saved</h3>
+        <img className="capture-prompt-thumbnail" src={captured.thumbnail} alt={`Screenshot of ${captured.target}`} />
+        <p className="card-description">
+          {captured.width} x {captured.height}, {captured.path}
+        </p>
+        <div className="capture-prompt-actions">
+          <button type="button" onClick={() => setCaptured(null)}>Close</button>
+        </div>
+      </div>
+    );
+  }
+
+  return null;
+};
@@ -0,0 +1,30 @@
+// Notes shared by every window and client, a demo of the collab feature
+
+import React from 'react';
+import { useCollabText } from '../hooks/useCollab';
+
+interface CollabNotesProps {
+  document?: string;
+}
+
+export const CollabNotes: React.FC<CollabNotesProps> = ({ document = 'notes' }) => {
+  const { text, setText, available } = useCollabText(document);
+
+  return (
+    <section className="collab-notes">
+      <h3 className="card-title">Shared Notes</h3>
+      <p className="card-description">
+        {available
+          ? 'Edited live by every open window and client; changes merge and are kept in SQLite.'
+          : 'Unavailable: the backend was built without the collab feature or turned it off.'}
+      </p>
+      <textarea
+        className="collab-notes-text"
+        value={text}
+        disabled={!available}
+        onChange={(event) => setText(event.target.value)}
+        placeholder="Type here, then open another window to see it appear"
+      />
+    </section>
+  );
+};
@@ -1,6 +1,7 @@
 // Main content area with feature cards
 
 import React from 'react';
+import { CollabNotes } from './CollabNotes';
 
 interface MainContentProps {
   onOpenSystemInfo: () => void;
@@ -44,6 +45,8 @@ export const MainContent: React.FC<MainContentProps> = ({
           </div>
         </div>
       </section>
+
+      <CollabNotes />
     </main>
   );
 };
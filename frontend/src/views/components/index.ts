@@ -5,6 +5,8 @@ export { MainContent } from './MainContent';
 export { Header } from './Header';
 export { ErrorBoundary } from './ErrorBoundary';
 export { BottomPanel } from './BottomPanel';
+export { CollabNotes } from './CollabNotes';
+export { CapturePrompt } from './CapturePrompt';
 // Deprecated: Use BottomPanel instead
 // export { WebSocketStatusPanel } from './WebSocketStatusPanel';
 // export { DevToolsPanel } from './DevToolsPanel';
@@ -2,4 +2,13 @@
 
 export { useWebSocketStatus, useAppInitialization, useWindowManager } from './useAppLogic';
 export { useWindowOperations } from './useWindowOperations';
+export { useFeatureFlags, useFeatureFlag } from './useFeatureFlag';
+export { useSyncedState } from './useSyncedState';
+export { usePresence, usePresenceRegistration } from './usePresence';
+export { useCollabText } from './useCollab';
+export { useHotkeyAction } from './useHotkeys';
+export { useLaunchAtLogin } from './useOsIntegration';
+export { usePdfExport, snapshotPage } from './usePdfExport';
+export { useCaptureRequests } from './useCapture';
+export { useSound } from './useSound';
 // Note: useDatabaseOperations removed - functionality merged into useWindowOperations
@@ -0,0 +1,60 @@
+// Screen capture hooks
+
+import { useState, useEffect, useCallback } from 'react';
+import {
+  type CaptureConfirmResponse,
+  type CaptureRequestedEvent,
+  captureConfirm,
+  onCaptureCompleted,
+  onCaptureRequested,
+} from '../../generated/client';
+import { Logger } from '../utils/logger';
+
+/** Resolves after the browser painted twice, so a closed prompt is gone from a window screenshot */
+const afterPaint = () => new Promise<void>((resolve) => requestAnimationFrame(() => requestAnimationFrame(() => resolve())));
+
+/**
+ * Capture requests waiting for the user, oldest first; `answer` allows or
+ * refuses the first one and resolves with the capture, if one was taken.
+ * Requests leave the queue when answered here or elsewhere, or when they expire
+ */
+export const useCaptureRequests = () => {
+  const [requests, setRequests] = useState<CaptureRequestedEvent[]>([]);
+
+  useEffect(() => {
+    const drop = (requestId: string) => setRequests((queue) => queue.filter((request) => request.request_id !== requestId));
+    const timers = new Set<ReturnType<typeof setTimeout>>();
+    const detachRequested = onCaptureRequested((request) => {
+      setRequests((queue) => [...queue, request]);
+      const timer = setTimeout(() => {
+        timers.delete(timer);
+        drop(request.request_id);
+      }, request.expires_in_secs * 1000);
+      timers.add(timer);
+    });
+    const detachCompleted = onCaptureCompleted((event) => drop(event.request_id));
+    return () => {
+      detachRequested();
+      detachCompleted();
+      timers.forEach(clearTimeout);
+    };
+  }, []);
+
+  const answer = useCallback(
+    async (allow: boolean): Promise<CaptureConfirmResponse | null> => {
+      const request = requests[0];
+      if (!request) return null;
+      setRequests((queue) => queue.slice(1));
+      try {
+        if (allow) await afterPaint();
+        return await captureConfirm({ request_id: request.request_id, allow });
+      } catch (error) {
+        Logger.error(`Failed to answer the capture of ${request.target}`, error);
+        return null;
+      }
+    },
+    [requests],
+  );
+
+  return { request: requests[0] ?? null, pending: requests.length, answer };
+};
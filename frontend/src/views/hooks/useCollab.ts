@@ -0,0 +1,41 @@
+// Collaborative editing hooks
+
+import { useState, useEffect, useRef, useCallback } from 'react';
+import type * as Y from 'yjs';
+import { openSharedDocument, replaceText } from '../../view-models/collab';
+import { Logger } from '../utils/logger';
+
+/**
+ * Text `field` of shared document `document`, edited together with every other
+ * window and client; `available` turns false when the backend cannot share it,
+ * e.g. when it was built without the `collab` feature
+ */
+export const useCollabText = (document: string, field = 'text') => {
+  const [text, setText] = useState('');
+  const [available, setAvailable] = useState(true);
+  const shared = useRef<Y.Text | null>(null);
+
+  useEffect(() => {
+    setAvailable(true);
+    const { doc, close } = openSharedDocument(document, (error) => {
+      Logger.warn(`Failed to sync shared document ${document}`, error);
+      setAvailable(false);
+    });
+    const ytext = doc.getText(field);
+    const render = () => setText(ytext.toString());
+    shared.current = ytext;
+    setText(ytext.toString());
+    ytext.observe(render);
+    return () => {
+      ytext.unobserve(render);
+      shared.current = null;
+      close();
+    };
+  }, [document, field]);
+
+  const edit = useCallback((next: string) => {
+    if (shared.current) replaceText(shared.current, next);
+  }, []);
+
+  return { text, setText: edit, available };
+};
@@ -0,0 +1,31 @@
+// Feature flag hooks
+
+import { useState, useEffect } from 'react';
+import { flagsGetAll, onFlagsChanged } from '../../generated/client';
+import { Logger } from '../utils/logger';
+
+/** Every feature flag, kept current through `flags.changed`; empty until loaded */
+export const useFeatureFlags = (userId?: number) => {
+  const [flags, setFlags] = useState<Record<string, boolean>>({});
+
+  useEffect(() => {
+    let active = true;
+    const load = () =>
+      flagsGetAll(userId === undefined ? {} : { user_id: userId })
+        .then((response) => active && setFlags(response.flags))
+        .catch((error) => Logger.warn('Failed to load feature flags', error));
+
+    load();
+    // A global change may be hidden by a user override, so read the flags again
+    const unsubscribe = onFlagsChanged(() => load());
+    return () => {
+      active = false;
+      unsubscribe();
+    };
+  }, [userId]);
+
+  return flags;
+};
+
+/** Whether flag `name` is on; off while the flags load */
+export const useFeatureFlag = (name: string, userId?: number) => useFeatureFlags(userId)[name] === true;
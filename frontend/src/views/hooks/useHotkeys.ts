@@ -0,0 +1,18 @@
+// Global hotkey hooks
+
+import { useEffect, useRef } from 'react';
+import { type HotkeyPressedEvent, onHotkeyPressed } from '../../generated/client';
+
+/** Call `handler` whenever a global shortcut bound to `action` is pressed, even while the app is in the background */
+export const useHotkeyAction = (action: string, handler: (event: HotkeyPressedEvent) => void) => {
+  const latest = useRef(handler);
+  latest.current = handler;
+
+  useEffect(
+    () =>
+      onHotkeyPressed((event) => {
+        if (event.action === action) latest.current(event);
+      }),
+    [action],
+  );
+};
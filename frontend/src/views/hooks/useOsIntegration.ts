@@ -0,0 +1,35 @@
+// OS integration hooks
+
+import { useState, useEffect, useCallback } from 'react';
+import { type LaunchAtLogin, osIntegration, osSetLaunchAtLogin } from '../../generated/client';
+import { Logger } from '../utils/logger';
+
+/**
+ * Whether the app starts when the user logs in, for a settings panel;
+ * `status` is null until loaded and `setEnabled` adds or removes the login entry
+ */
+export const useLaunchAtLogin = () => {
+  const [status, setStatus] = useState<LaunchAtLogin | null>(null);
+
+  useEffect(() => {
+    let active = true;
+    osIntegration()
+      .then((response) => active && setStatus(response.launch_at_login))
+      .catch((error) => Logger.warn('Failed to load the launch-at-login setting', error));
+    return () => {
+      active = false;
+    };
+  }, []);
+
+  const setEnabled = useCallback(async (enabled: boolean) => {
+    try {
+      const response = await osSetLaunchAtLogin({ enabled });
+      setStatus(response.launch_at_login);
+    } catch (error) {
+      Logger.error('Failed to change the launch-at-login setting', error);
+      throw error;
+    }
+  }, []);
+
+  return { status, setEnabled };
+};
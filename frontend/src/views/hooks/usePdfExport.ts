@@ -0,0 +1,75 @@
+// PDF export hooks
+
+import { useState, useEffect, useCallback, useRef } from 'react';
+import { type DocumentExportedEvent, documentExportPdf, onDocumentExported } from '../../generated/client';
+import { Logger } from '../utils/logger';
+
+/**
+ * `root` as it is rendered now, as a standalone page: the document's head
+ * with its styles, no scripts, and the values typed into form fields
+ */
+export const snapshotPage = (root: Element = document.body): string => {
+  const clone = root.cloneNode(true) as Element;
+  const fields = root.querySelectorAll('input, textarea, select');
+  clone.querySelectorAll('input, textarea, select').forEach((field, index) => {
+    const original = fields[index];
+    if (original instanceof HTMLTextAreaElement) {
+      field.textContent = original.value;
+    } else if (original instanceof HTMLSelectElement) {
+      field.querySelectorAll('option')[original.selectedIndex]?.setAttribute('selected', '');
+    } else if (original instanceof HTMLInputElement) {
+      if (original.type !== 'checkbox' && original.type !== 'radio') field.setAttribute('value', original.value);
+      else if (original.checked) field.setAttribute('checked', '');
+    }
+  });
+  const head = document.head.cloneNode(true) as HTMLHeadElement;
+  for (const node of [head, clone]) node.querySelectorAll('script').forEach((script) => script.remove());
+  const body = root === document.body ? clone.outerHTML : `<body>${clone.outerHTML}</body>`;
+  return `<!DOCTYPE html>\n<html lang="${document.documentElement.lang}">${head.outerHTML}${body}</html>`;
+};
+
+export interface PdfExportOptions {
+  /** A complete page; a snapshot of the current page by default */
+  html?: string;
+  fileName?: string;
+  title?: string;
+}
+
+/**
+ * `exportPdf` asks where to save and prints in the background; `exporting`
+ * stays true until `document.exported` reports every export of this
+ * component, the last of them as `last`
+ */
+export const usePdfExport = () => {
+  const pending = useRef(new Set<string>());
+  const [exporting, setExporting] = useState(false);
+  const [last, setLast] = useState<DocumentExportedEvent | null>(null);
+
+  useEffect(
+    () =>
+      onDocumentExported((event) => {
+        if (!pending.current.delete(event.export_id)) return;
+        if (!event.success) Logger.warn(`PDF export to ${event.path} failed`, event.error);
+        setExporting(pending.current.size > 0);
+        setLast(event);
+      }),
+    [],
+  );
+
+  const exportPdf = useCallback(async ({ html, fileName, title }: PdfExportOptions = {}) => {
+    const response = await documentExportPdf({
+      html: html ?? snapshotPage(),
+      // Relative stylesheets and images load from the app's server
+      base_url: document.baseURI,
+      file_name: fileName,
+      title,
+    });
+    if (response.export_id) {
+      pending.current.add(response.export_id);
+      setExporting(true);
+    }
+    return response;
+  }, []);
+
+  return { exportPdf, exporting, last };
+};
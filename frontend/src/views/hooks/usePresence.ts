@@ -0,0 +1,60 @@
+// Presence hooks
+
+import { useState, useEffect } from 'react';
+import {
+  type ClientProfile,
+  onPresenceJoined,
+  onPresenceLeft,
+  presenceList,
+  presenceRegister,
+  type PresenceRegisterRequest,
+} from '../../generated/client';
+import { AppEventType, EventBus } from '../../models/event-bus';
+import { Logger } from '../utils/logger';
+
+/** Register this window as present with `profile`, again on every new connection */
+export const usePresenceRegistration = (profile: PresenceRegisterRequest) => {
+  const key = JSON.stringify(profile);
+
+  useEffect(() => {
+    const register = () =>
+      presenceRegister(JSON.parse(key)).catch((error) => Logger.warn('Failed to register presence', error));
+
+    register();
+    // A new connection starts without a profile
+    return EventBus.subscribe(AppEventType.BACKEND_CONNECTED, () => register());
+  }, [key]);
+};
+
+/** Clients present on the backend, kept current through `presence.joined` and `presence.left`; empty until loaded */
+export const usePresence = () => {
+  const [clients, setClients] = useState<ClientProfile[]>([]);
+
+  useEffect(() => {
+    let active = true;
+    const load = () =>
+      presenceList()
+        .then((response) => active && setClients(response.clients))
+        .catch((error) => Logger.warn('Failed to load presence', error));
+
+    load();
+    const detachJoined = onPresenceJoined((client) =>
+      setClients((current) =>
+        current.some((known) => known.id === client.id)
+          ? current.map((known) => (known.id === client.id ? client : known))
+          : [...current, client],
+      ),
+    );
+    const detachLeft = onPresenceLeft((client) => setClients((current) => current.filter((known) => known.id !== client.id)));
+    // Joins and leaves while disconnected were missed
+    const detachReconnect = EventBus.subscribe(AppEventType.BACKEND_CONNECTED, () => load());
+    return () => {
+      active = false;
+      detachJoined();
+      detachLeft();
+      detachReconnect();
+    };
+  }, []);
+
+  return clients;
+};
@@ -0,0 +1,52 @@
+// UI sound hooks
+
+import { useState, useEffect, useCallback } from 'react';
+import { soundList, soundPlay, soundSetMuted, soundSetVolume } from '../../generated/client';
+import { Logger } from '../utils/logger';
+
+export type SoundName = 'click' | 'success' | 'error' | 'notification';
+
+export interface SoundLevels {
+  volume: number;
+  muted: boolean;
+}
+
+/**
+ * Feedback sounds: `play` never throws, so it can be called from any
+ * handler; `levels` is null until loaded and `setVolume`/`setMuted` store
+ * the choice for every window
+ */
+export const useSound = () => {
+  const [levels, setLevels] = useState<SoundLevels | null>(null);
+
+  useEffect(() => {
+    let active = true;
+    soundList()
+      .then(({ volume, muted }) => active && setLevels({ volume, muted }))
+      .catch((error) => Logger.warn('Failed to load the sound settings', error));
+    return () => {
+      active = false;
+    };
+  }, []);
+
+  const play = useCallback(async (name: SoundName, volume?: number) => {
+    try {
+      return (await soundPlay({ name, volume })).played;
+    } catch (error) {
+      Logger.debug(`Could not play the ${name} sound`, error);
+      return false;
+    }
+  }, []);
+
+  const setVolume = useCallback(async (volume: number) => {
+    const response = await soundSetVolume({ volume });
+    setLevels({ volume: response.volume, muted: response.muted });
+  }, []);
+
+  const setMuted = useCallback(async (muted: boolean) => {
+    const response = await soundSetMuted({ muted });
+    setLevels({ volume: response.volume, muted: response.muted });
+  }, []);
+
+  return { play, levels, setVolume, setMuted };
+};
@@ -0,0 +1,13 @@
+// Backend state document hooks
+
+import { useState, useEffect } from 'react';
+import { type StateDocument, subscribeState } from '../../view-models/state-sync';
+
+/** Backend state document `document`, kept current through `state.patch`; undefined until loaded */
+export const useSyncedState = <T>(document: StateDocument): T | undefined => {
+  const [state, setState] = useState<T | undefined>(undefined);
+
+  useEffect(() => subscribeState(document, (next) => setState(next as T)), [document]);
+
+  return state;
+};
@@ -74,6 +74,16 @@ export const useWindowOperations = (setActiveWindows: React.Dispatch<React.SetSt
     Logger.info('All windows minimized - showing main view');
   }, []);
 
+  /** Minimize every window, or restore them all when none is showing */
+  const toggleAllWindows = useCallback(() => {
+    const windows = windowManager.getAllWindows();
+    if (windows.some((windowInfo) => !windowInfo.minimized)) {
+      hideAllWindows();
+      return;
+    }
+    windows.forEach((windowInfo) => windowInfo.winboxInstance.restore());
+  }, [hideAllWindows]);
+
   // Database operations
   const updateSQLiteTable = useCallback(() => {
     const tableBody = document.getElementById('users-table-body');
@@ -121,6 +131,7 @@ export const useWindowOperations = (setActiveWindows: React.Dispatch<React.SetSt
     closeWindow,
     closeAllWindows,
     hideAllWindows,
+    toggleAllWindows,
     dbUsers,
     setDbUsers,
     isLoadingUsers,
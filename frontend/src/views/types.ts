@@ -7,6 +7,7 @@ export interface User {
   role: string;
   status: string;
   created_at: string;
+  version: number;
 }
 
 export interface DbStats {
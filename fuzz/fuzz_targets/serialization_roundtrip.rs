@@ -0,0 +1,33 @@
+//! Messages decoded from arbitrary bytes in each format encode and decode
+//! back to the same message
+//!
+//!     cargo fuzz run serialization_roundtrip
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/infrastructure/serialization/serialization.rs"]
+mod serialization;
+
+// `crate::core::domain::clock` for the module above
+#[allow(dead_code)]
+#[path = "../../src/core"]
+mod core {
+    pub mod domain {
+        pub mod clock;
+    }
+}
+
+use serialization::{SerializationEngine, SerializationFormat};
+
+fuzz_target!(|data: &[u8]| {
+    for format in [SerializationFormat::Json, SerializationFormat::MessagePack, SerializationFormat::Cbor] {
+        let engine = SerializationEngine::new(format);
+        let Ok(message) = engine.deserialize(data) else { continue };
+        let bytes = engine.serialize(&message).unwrap();
+        let again = engine.deserialize(&bytes).unwrap();
+        assert_eq!((&again.id, &again.name, &again.payload, again.timestamp), (&message.id, &message.name, &message.payload, message.timestamp));
+    }
+});
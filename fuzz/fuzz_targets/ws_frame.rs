@@ -0,0 +1,26 @@
+//! Binary file frames: magic, header length, JSON header, data
+//!
+//!     cargo fuzz run ws_frame
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde_json::Value;
+
+#[allow(dead_code)]
+#[path = "../../src/viewmodel/ws_parser.rs"]
+mod ws_parser;
+
+use ws_parser::{parse, split_frame, MessageLimits, FRAME_MAGIC};
+
+fuzz_target!(|data: &[u8]| {
+    // Most inputs lack the magic; fuzz the rest of the frame behind it too
+    let mut frame = FRAME_MAGIC.to_vec();
+    frame.extend_from_slice(data);
+    for frame in [data, &frame[..]] {
+        if let Ok((header, rest)) = split_frame(frame) {
+            assert_eq!(FRAME_MAGIC.len() + 4 + header.len() + rest.len(), frame.len());
+            let _ = parse::<Value>(header, &MessageLimits::default());
+        }
+    }
+});
@@ -0,0 +1,53 @@
+//! Text and deflated binary WebSocket messages, as `parse` sees them
+//!
+//!     cargo fuzz run ws_message
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[allow(dead_code)]
+#[path = "../../src/viewmodel/ws_parser.rs"]
+mod ws_parser;
+
+use ws_parser::{parse, MessageLimits, ParseError};
+
+/// Same shape as `websocket_handler::WebSocketEvent`
+#[derive(Debug, Serialize, Deserialize)]
+struct WebSocketEvent {
+    id: String,
+    name: String,
+    payload: Value,
+    timestamp: u64,
+    source: String,
+}
+
+fuzz_target!(|data: &[u8]| {
+    let limits = MessageLimits { max_json_bytes: 4096, max_depth: 8, ..MessageLimits::default() };
+    match parse::<WebSocketEvent>(data, &limits) {
+        Ok(event) => {
+            // What was accepted is accepted again once re-encoded
+            let json = serde_json::to_vec(&event).unwrap();
+            let again: WebSocketEvent = parse(&json, &MessageLimits::default()).unwrap();
+            assert_eq!(again.payload, event.payload);
+        }
+        Err(ParseError::TooLarge { size, limit }) => assert!(size > limit),
+        Err(_) => {}
+    }
+    // The depth check never refuses what serde would accept within the limits
+    if let Ok(value) = serde_json::from_slice::<Value>(data) {
+        if data.len() <= limits.max_json_bytes && depth(&value) <= limits.max_depth {
+            assert!(parse::<Value>(data, &limits).is_ok());
+        }
+    }
+});
+
+fn depth(value: &Value) -> usize {
+    match value {
+        Value::Array(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+        Value::Object(fields) => 1 + fields.values().map(depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
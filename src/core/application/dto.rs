@@ -4,9 +4,11 @@
 
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use utoipa::ToSchema;
 
 /// User DTO for API responses
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, JsonSchema)]
 pub struct UserDto {
     pub id: i64,
     pub name: String,
@@ -17,7 +19,7 @@ pub struct UserDto {
 }
 
 /// Database statistics DTO
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, JsonSchema)]
 pub struct DatabaseStatsDto {
     pub users_count: i64,
     pub tables: Vec<String>,
@@ -25,7 +27,7 @@ pub struct DatabaseStatsDto {
 }
 
 /// Counter DTO
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, JsonSchema)]
 pub struct CounterDto {
     pub id: String,
     pub value: i64,
@@ -33,15 +35,41 @@ pub struct CounterDto {
 }
 
 /// System info DTO
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, JsonSchema)]
 pub struct SystemInfoDto {
     pub platform: String,
-    pub user_agent: String,
-    pub language: String,
-    pub screen_resolution: String,
-    pub memory_gb: Option<u32>,
-    pub cpu_cores: Option<u32>,
-    pub online: bool,
+    pub arch: String,
+    pub os_name: Option<String>,
+    pub os_version: Option<String>,
+    pub kernel_version: Option<String>,
+    pub hostname: Option<String>,
+    pub cpu_model: Option<String>,
+    pub cpu_cores: usize,
+    pub physical_cores: Option<usize>,
+    pub total_memory_bytes: u64,
+    pub used_memory_bytes: u64,
+    pub memory_usage_percent: f64,
+    pub uptime_secs: u64,
+}
+
+impl From<crate::core::domain::SystemInfo> for SystemInfoDto {
+    fn from(info: crate::core::domain::SystemInfo) -> Self {
+        Self {
+            memory_usage_percent: info.memory_usage_percent(),
+            platform: info.platform,
+            arch: info.arch,
+            os_name: info.os_name,
+            os_version: info.os_version,
+            kernel_version: info.kernel_version,
+            hostname: info.hostname,
+            cpu_model: info.cpu_model,
+            cpu_cores: info.cpu_cores,
+            physical_cores: info.physical_cores,
+            total_memory_bytes: info.total_memory_bytes,
+            used_memory_bytes: info.used_memory_bytes,
+            uptime_secs: info.uptime_secs,
+        }
+    }
 }
 
 /// API response wrapper
@@ -54,6 +82,7 @@ pub struct ApiResponse<T> {
 }
 
 impl<T> ApiResponse<T> {
+    #[allow(dead_code)]
     pub fn success(data: T) -> Self {
         Self {
             success: true,
@@ -72,6 +101,7 @@ impl<T> ApiResponse<T> {
         }
     }
     
+    #[allow(dead_code)]
     pub fn with_message(mut self, message: String) -> Self {
         self.message = Some(message);
         self
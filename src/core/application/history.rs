@@ -0,0 +1,164 @@
+//! Undo/redo journal - the command pattern for mutating commands
+//!
+//! Each change is recorded as an operation that knows its own inverse.
+//! Undoing applies the inverse of the newest entry and moves it to the redo
+//! stack; redoing applies the entry again. Recording a new change clears the
+//! redo stack. Applying is left to the caller, which only moves an entry once
+//! its operation went through.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Entries kept for undo before the oldest are dropped
+pub const HISTORY_LIMIT: usize = 100;
+
+/// An operation that can be reverted
+pub trait Reversible {
+    /// The operation that undoes this one
+    fn inverse(&self) -> Self;
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry<Op> {
+    pub id: u64,
+    /// What was done, such as `update_user 3`
+    pub label: String,
+    pub at: DateTime<Utc>,
+    pub operation: Op,
+}
+
+#[derive(Debug)]
+pub struct History<Op> {
+    undo: VecDeque<JournalEntry<Op>>,
+    redo: Vec<JournalEntry<Op>>,
+    next_id: u64,
+    limit: usize,
+}
+
+impl<Op> Default for History<Op> {
+    fn default() -> Self {
+        Self::new(HISTORY_LIMIT)
+    }
+}
+
+impl<Op> History<Op> {
+    pub const fn new(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            next_id: 1,
+            limit,
+        }
+    }
+}
+
+impl<Op: Reversible + Clone> History<Op> {
+    /// Journal a change that has been applied; returns its entry id
+    pub fn record(&mut self, label: impl Into<String>, operation: Op) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.redo.clear();
+        self.undo.push_back(JournalEntry {
+            id,
+            label: label.into(),
+            at: Utc::now(),
+            operation,
+        });
+        while self.undo.len() > self.limit.max(1) {
+            self.undo.pop_front();
+        }
+        id
+    }
+
+    /// The newest undoable entry and the operation that reverts it
+    pub fn next_undo(&self) -> Option<(&JournalEntry<Op>, Op)> {
+        self.undo.back().map(|entry| (entry, entry.operation.inverse()))
+    }
+
+    /// The newest undone entry and the operation that applies it again
+    pub fn next_redo(&self) -> Option<(&JournalEntry<Op>, Op)> {
+        self.redo.last().map(|entry| (entry, entry.operation.clone()))
+    }
+
+    /// Move the entry of `next_undo` to the redo stack once its inverse was applied
+    pub fn undone(&mut self) {
+        if let Some(entry) = self.undo.pop_back() {
+            self.redo.push(entry);
+        }
+    }
+
+    /// Move the entry of `next_redo` back to the undo stack once it was applied again
+    pub fn redone(&mut self) {
+        if let Some(entry) = self.redo.pop() {
+            self.undo.push_back(entry);
+        }
+    }
+
+    /// Drop the entry of `next_undo`, whose change can no longer be reverted
+    pub fn discard_undo(&mut self) {
+        self.undo.pop_back();
+    }
+
+    /// Drop the entry of `next_redo`, which can no longer be applied
+    pub fn discard_redo(&mut self) {
+        self.redo.pop();
+    }
+
+    /// Undoable entries, newest first
+    pub fn undo_entries(&self) -> impl Iterator<Item = &JournalEntry<Op>> {
+        self.undo.iter().rev()
+    }
+
+    /// Redoable entries, next to redo first
+    pub fn redo_entries(&self) -> impl Iterator<Item = &JournalEntry<Op>> {
+        self.redo.iter().rev()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Adds to a number; the inverse subtracts
+    #[derive(Debug, Clone, PartialEq)]
+    struct Add(i64);
+
+    impl Reversible for Add {
+        fn inverse(&self) -> Self {
+            Add(-self.0)
+        }
+    }
+
+    #[test]
+    fn test_undo_redo_and_limit() {
+        let mut value = 0;
+        let mut history = History::new(2);
+        for n in [1, 2, 4] {
+            value += n;
+            history.record(format!("add {}", n), Add(n));
+        }
+
+        // Only the newest two entries are kept
+        let (entry, inverse) = history.next_undo().unwrap();
+        assert_eq!((entry.id, inverse.clone()), (3, Add(-4)));
+        value += inverse.0;
+        history.undone();
+        value += history.next_undo().unwrap().1 .0;
+        history.undone();
+        assert_eq!(value, 1);
+        assert!(history.next_undo().is_none());
+
+        let (entry, operation) = history.next_redo().unwrap();
+        assert_eq!((entry.id, operation), (2, Add(2)));
+        history.redone();
+        assert_eq!(history.undo_entries().map(|e| e.id).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(history.redo_entries().map(|e| e.id).collect::<Vec<_>>(), vec![3]);
+
+        // A new change clears what could be redone
+        history.record("add 8", Add(8));
+        assert!(history.next_redo().is_none());
+        history.discard_undo();
+        assert_eq!(history.undo_entries().map(|e| e.label.as_str()).collect::<Vec<_>>(), vec!["add 2"]);
+    }
+}
@@ -3,12 +3,12 @@
 //! This layer contains use cases that orchestrate the domain layer.
 //! It implements the MVVM ViewModel logic for the backend.
 
-pub mod commands;
-pub mod queries;
-pub mod handlers;
 pub mod dto;
+pub mod history;
+pub mod tasks;
+pub mod validation;
 
-pub use commands::*;
-pub use queries::*;
-pub use handlers::*;
 pub use dto::*;
+pub use history::*;
+pub use tasks::*;
+pub use validation::*;
@@ -0,0 +1,353 @@
+//! Task Manager - Long-running background operations
+//!
+//! Commands spawn named tasks that report progress, can be cancelled and are
+//! listable while running. Lifecycle events are handed to a `TaskEventPublisher`
+//! so the application layer stays independent of the transport.
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Finished tasks kept for `list()` before the oldest are dropped
+const FINISHED_TASK_LIMIT: usize = 100;
+
+pub const TASK_PROGRESS: &str = "task.progress";
+pub const TASK_COMPLETED: &str = "task.completed";
+pub const TASK_FAILED: &str = "task.failed";
+pub const TASK_CANCELLED: &str = "task.cancelled";
+
+/// Receives task lifecycle events (implemented by the infrastructure layer)
+pub trait TaskEventPublisher: Send + Sync {
+    fn publish(&self, event: &str, payload: Value);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Public snapshot of a task
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct TaskInfo {
+    pub id: String,
+    pub name: String,
+    pub status: TaskStatus,
+    /// 0.0 - 1.0
+    pub progress: f32,
+    pub message: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub result: Option<Value>,
+    pub error: Option<String>,
+}
+
+struct TaskEntry {
+    info: TaskInfo,
+    cancelled: Arc<AtomicBool>,
+    cancel_notify: Arc<Notify>,
+}
+
+struct Inner {
+    tasks: Mutex<HashMap<String, TaskEntry>>,
+    publisher: Arc<dyn TaskEventPublisher>,
+}
+
+/// Handle passed to a running task for progress reporting and cancellation checks
+#[derive(Clone)]
+pub struct TaskContext {
+    id: String,
+    inner: Arc<Inner>,
+    cancelled: Arc<AtomicBool>,
+    cancel_notify: Arc<Notify>,
+}
+
+impl TaskContext {
+    #[allow(dead_code)]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Report progress (clamped to 0.0 - 1.0) with an optional status message
+    pub fn report(&self, progress: f32, message: Option<String>) {
+        let progress = progress.clamp(0.0, 1.0);
+        let payload = {
+            let mut tasks = self.inner.tasks.lock().unwrap();
+            let Some(entry) = tasks.get_mut(&self.id) else {
+                return;
+            };
+            entry.info.progress = progress;
+            entry.info.message = message.clone();
+            serde_json::json!({
+                "id": self.id,
+                "name": entry.info.name,
+                "progress": progress,
+                "message": message
+            })
+        };
+        self.inner.publisher.publish(TASK_PROGRESS, payload);
+    }
+
+    /// Cooperative cancellation check for blocking work
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the task has been cancelled
+    pub async fn cancelled(&self) {
+        loop {
+            // Register interest before checking the flag so a concurrent cancel is not missed
+            let notified = self.cancel_notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Registry of background tasks; cheap to clone
+#[derive(Clone)]
+pub struct TaskManager {
+    inner: Arc<Inner>,
+}
+
+impl TaskManager {
+    pub fn new(publisher: Arc<dyn TaskEventPublisher>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                tasks: Mutex::new(HashMap::new()),
+                publisher,
+            }),
+        }
+    }
+
+    fn register(&self, name: &str) -> TaskContext {
+        let id = uuid::Uuid::new_v4().to_string();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let cancel_notify = Arc::new(Notify::new());
+
+        let mut tasks = self.inner.tasks.lock().unwrap();
+        Self::prune_finished(&mut tasks);
+        tasks.insert(
+            id.clone(),
+            TaskEntry {
+                info: TaskInfo {
+                    id: id.clone(),
+                    name: name.to_string(),
+                    status: TaskStatus::Running,
+                    progress: 0.0,
+                    message: None,
+                    started_at: Utc::now(),
+                    finished_at: None,
+                    result: None,
+                    error: None,
+                },
+                cancelled: cancelled.clone(),
+                cancel_notify: cancel_notify.clone(),
+            },
+        );
+
+        TaskContext {
+            id,
+            inner: self.inner.clone(),
+            cancelled,
+            cancel_notify,
+        }
+    }
+
+    fn prune_finished(tasks: &mut HashMap<String, TaskEntry>) {
+        let mut finished: Vec<(DateTime<Utc>, String)> = tasks
+            .values()
+            .filter_map(|entry| entry.info.finished_at.map(|at| (at, entry.info.id.clone())))
+            .collect();
+        if finished.len() < FINISHED_TASK_LIMIT {
+            return;
+        }
+        finished.sort();
+        for (_, id) in finished.iter().take(finished.len() + 1 - FINISHED_TASK_LIMIT) {
+            tasks.remove(id);
+        }
+    }
+
+    fn finish(inner: &Inner, id: &str, cancelled: bool, result: Result<Value, String>) {
+        let (event, payload) = {
+            let mut tasks = inner.tasks.lock().unwrap();
+            let Some(entry) = tasks.get_mut(id) else {
+                return;
+            };
+            let info = &mut entry.info;
+            info.finished_at = Some(Utc::now());
+            let event = match (cancelled, result) {
+                (true, _) => {
+                    info.status = TaskStatus::Cancelled;
+                    TASK_CANCELLED
+                }
+                (false, Ok(value)) => {
+                    info.status = TaskStatus::Completed;
+                    info.progress = 1.0;
+                    info.result = Some(value);
+                    TASK_COMPLETED
+                }
+                (false, Err(e)) => {
+                    info.status = TaskStatus::Failed;
+                    info.error = Some(e);
+                    TASK_FAILED
+                }
+            };
+            (event, serde_json::to_value(&*info).unwrap_or_default())
+        };
+        inner.publisher.publish(event, payload);
+    }
+
+    /// Run an async task; cancelling drops the future at its next await point
+    pub fn spawn<F, Fut>(&self, name: &str, task: F) -> String
+    where
+        F: FnOnce(TaskContext) -> Fut,
+        Fut: Future<Output = Result<Value, String>> + Send + 'static,
+    {
+        let ctx = self.register(name);
+        let id = ctx.id.clone();
+        let future = task(ctx.clone());
+
+        tokio::spawn(async move {
+            let result = tokio::select! {
+                result = future => Some(result),
+                _ = ctx.cancelled() => None,
+            };
+            match result {
+                Some(result) => Self::finish(&ctx.inner, &ctx.id, ctx.is_cancelled(), result),
+                None => Self::finish(&ctx.inner, &ctx.id, true, Err("Cancelled".to_string())),
+            }
+        });
+        id
+    }
+
+    /// Run blocking work on the blocking pool; it must poll `ctx.is_cancelled()` to stop early
+    #[allow(dead_code)]
+    pub fn spawn_blocking<F>(&self, name: &str, task: F) -> String
+    where
+        F: FnOnce(&TaskContext) -> Result<Value, String> + Send + 'static,
+    {
+        let ctx = self.register(name);
+        let id = ctx.id.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let result = task(&ctx);
+            Self::finish(&ctx.inner, &ctx.id, ctx.is_cancelled(), result);
+        });
+        id
+    }
+
+    /// Request cancellation; returns false if the task is unknown or already finished
+    pub fn cancel(&self, id: &str) -> bool {
+        let tasks = self.inner.tasks.lock().unwrap();
+        match tasks.get(id) {
+            Some(entry) if entry.info.status == TaskStatus::Running => {
+                entry.cancelled.store(true, Ordering::SeqCst);
+                entry.cancel_notify.notify_waiters();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<TaskInfo> {
+        self.inner.tasks.lock().unwrap().get(id).map(|entry| entry.info.clone())
+    }
+
+    /// All known tasks, newest first
+    pub fn list(&self) -> Vec<TaskInfo> {
+        let mut list: Vec<TaskInfo> = self
+            .inner
+            .tasks
+            .lock()
+            .unwrap()
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect();
+        list.sort_by_key(|task| std::cmp::Reverse(task.started_at));
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingPublisher {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl TaskEventPublisher for RecordingPublisher {
+        fn publish(&self, event: &str, _payload: Value) {
+            self.events.lock().unwrap().push(event.to_string());
+        }
+    }
+
+    async fn wait_until_finished(manager: &TaskManager, id: &str) -> TaskInfo {
+        for _ in 0..100 {
+            let info = manager.get(id).unwrap();
+            if info.status != TaskStatus::Running {
+                return info;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("task {} did not finish", id);
+    }
+
+    #[tokio::test]
+    async fn test_task_reports_progress_and_completes() {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let manager = TaskManager::new(publisher.clone());
+
+        let id = manager.spawn("count", |ctx| async move {
+            ctx.report(0.5, Some("halfway".to_string()));
+            Ok(serde_json::json!(42))
+        });
+
+        let info = wait_until_finished(&manager, &id).await;
+        assert_eq!(info.status, TaskStatus::Completed);
+        assert_eq!(info.result, Some(serde_json::json!(42)));
+        assert_eq!(*publisher.events.lock().unwrap(), vec![TASK_PROGRESS, TASK_COMPLETED]);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_running_task() {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let manager = TaskManager::new(publisher.clone());
+
+        let id = manager.spawn("forever", |_ctx| async move {
+            std::future::pending::<()>().await;
+            Ok(Value::Null)
+        });
+
+        assert!(manager.cancel(&id));
+        let info = wait_until_finished(&manager, &id).await;
+        assert_eq!(info.status, TaskStatus::Cancelled);
+        assert!(!manager.cancel(&id));
+        assert_eq!(*publisher.events.lock().unwrap(), vec![TASK_CANCELLED]);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_task_failure() {
+        let manager = TaskManager::new(Arc::new(RecordingPublisher::default()));
+        let id = manager.spawn_blocking("broken", |_ctx| Err("boom".to_string()));
+
+        let info = wait_until_finished(&manager, &id).await;
+        assert_eq!(info.status, TaskStatus::Failed);
+        assert_eq!(info.error.as_deref(), Some("boom"));
+    }
+}
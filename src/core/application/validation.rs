@@ -0,0 +1,253 @@
+//! Payload Validation - Declarative field rules for incoming commands
+//!
+//! Each command declares a `PayloadSchema`; every rule is checked and all
+//! field errors are returned together in one `ValidationFailed` error so the
+//! frontend can mark every invalid form field at once.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use crate::error_handling::validation::validate_all;
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+
+/// A single field rule; all rules except `Required` pass when the field is absent
+#[derive(Debug, Clone)]
+pub enum Rule {
+    /// Present, not null and not a blank string
+    Required,
+    /// String length in characters (inclusive)
+    Length { min: usize, max: usize },
+    Email,
+    /// String must be one of the listed values
+    OneOf(&'static [&'static str]),
+    Integer,
+    Boolean,
+    /// A number from `min` to `max` (inclusive)
+    Range { min: f64, max: f64 },
+}
+
+impl Rule {
+    fn check(&self, value: &Value) -> bool {
+        match self {
+            Rule::Required => match value {
+                Value::Null => false,
+                Value::String(s) => !s.trim().is_empty(),
+                _ => true,
+            },
+            Rule::Length { min, max } => value
+                .as_str()
+                .map(|s| (*min..=*max).contains(&s.chars().count()))
+                .unwrap_or(false),
+            Rule::Email => value.as_str().map(is_email).unwrap_or(false),
+            Rule::OneOf(allowed) => value.as_str().map(|s| allowed.contains(&s)).unwrap_or(false),
+            Rule::Integer => value.is_i64() || value.is_u64(),
+            Rule::Boolean => value.is_boolean(),
+            Rule::Range { min, max } => value.as_f64().map(|n| (*min..=*max).contains(&n)).unwrap_or(false),
+        }
+    }
+
+    fn message(&self, field: &str) -> String {
+        match self {
+            Rule::Required => format!("{} is required", field),
+            Rule::Length { min, max } => {
+                format!("{} must be between {} and {} characters", field, min, max)
+            }
+            Rule::Email => format!("{} must be a valid email address", field),
+            Rule::OneOf(allowed) => format!("{} must be one of: {}", field, allowed.join(", ")),
+            Rule::Integer => format!("{} must be an integer", field),
+            Rule::Boolean => format!("{} must be true or false", field),
+            Rule::Range { min, max } => format!("{} must be a number from {} to {}", field, min, max),
+        }
+    }
+}
+
+fn is_email(s: &str) -> bool {
+    match s.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.split('.').count() >= 2
+                && domain.split('.').all(|part| !part.is_empty())
+                && !s.chars().any(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+/// One failed rule, as listed in the error context
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Field rules for one command payload
+#[derive(Debug, Clone, Default)]
+pub struct PayloadSchema {
+    fields: Vec<(&'static str, Vec<Rule>)>,
+}
+
+impl PayloadSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, name: &'static str, rules: impl IntoIterator<Item = Rule>) -> Self {
+        self.fields.push((name, rules.into_iter().collect()));
+        self
+    }
+
+    /// The rules as a JSON Schema object; `Required` cannot express "not blank"
+    pub fn json_schema(&self) -> Value {
+        let mut properties = serde_json::Map::new();
+        let mut required = Vec::new();
+        for (field, rules) in &self.fields {
+            let mut property = serde_json::Map::new();
+            for rule in rules {
+                match rule {
+                    Rule::Required => required.push(*field),
+                    Rule::Length { min, max } => {
+                        property.insert("type".into(), "string".into());
+                        property.insert("minLength".into(), (*min).into());
+                        property.insert("maxLength".into(), (*max).into());
+                    }
+                    Rule::Email => {
+                        property.insert("type".into(), "string".into());
+                        property.insert("format".into(), "email".into());
+                    }
+                    Rule::OneOf(allowed) => {
+                        property.insert("enum".into(), serde_json::json!(allowed));
+                    }
+                    Rule::Integer => {
+                        property.insert("type".into(), "integer".into());
+                    }
+                    Rule::Boolean => {
+                        property.insert("type".into(), "boolean".into());
+                    }
+                    Rule::Range { min, max } => {
+                        property.insert("type".into(), "number".into());
+                        property.insert("minimum".into(), (*min).into());
+                        property.insert("maximum".into(), (*max).into());
+                    }
+                }
+            }
+            properties.insert(field.to_string(), Value::Object(property));
+        }
+        serde_json::json!({ "type": "object", "properties": properties, "required": required })
+    }
+
+    /// All failed rules, in declaration order
+    pub fn errors(&self, payload: &Value) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        for (field, rules) in &self.fields {
+            let value = payload.get(*field).unwrap_or(&Value::Null);
+            let present = !value.is_null();
+
+            let checks = rules
+                .iter()
+                .filter(|rule| present || matches!(rule, Rule::Required))
+                .map(|rule| {
+                    let rule = rule.clone();
+                    let message = rule.message(field);
+                    let check: Box<dyn FnOnce(&Value) -> bool> = Box::new(move |v| rule.check(v));
+                    (check, ErrorCode::ValidationFailed, message)
+                })
+                .collect();
+
+            if let Err(failed) = validate_all(value, checks) {
+                errors.extend(failed.into_iter().map(|e| FieldError {
+                    field: field.to_string(),
+                    message: e.message,
+                }));
+            }
+        }
+        errors
+    }
+
+    /// Validate `payload`, aggregating every field error into one `ValidationFailed` error
+    ///
+    /// The error context carries `errors` (a list of `{ field, message }`) and
+    /// `fields` (messages grouped by field name).
+    pub fn validate(&self, command: &str, payload: &Value) -> AppResult<()> {
+        let errors = self.errors(payload);
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        let mut by_field: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for error in &errors {
+            by_field.entry(&error.field).or_default().push(&error.message);
+        }
+
+        Err(AppError::new(
+            ErrorCode::ValidationFailed,
+            format!("Invalid {} payload: {}", command, errors[0].message),
+        )
+        .with_context("command", command)
+        .with_context("fields", serde_json::json!(by_field))
+        .with_context("errors", serde_json::json!(errors)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_schema() -> PayloadSchema {
+        PayloadSchema::new()
+            .field("id", [Rule::Required, Rule::Integer])
+            .field("name", [Rule::Length { min: 1, max: 5 }])
+            .field("email", [Rule::Email])
+            .field("role", [Rule::OneOf(&["admin", "user"])])
+    }
+
+    #[test]
+    fn test_valid_payload_passes() {
+        let payload = serde_json::json!({ "id": 1, "name": "Ann", "email": "ann@example.com" });
+        assert!(user_schema().validate("update_user", &payload).is_ok());
+    }
+
+    #[test]
+    fn test_all_field_errors_are_aggregated() {
+        let payload = serde_json::json!({ "name": "Annabelle", "email": "ann@", "role": "root" });
+        let error = user_schema().validate("update_user", &payload).unwrap_err();
+
+        assert_eq!(error.code, ErrorCode::ValidationFailed);
+        let fields: Vec<&str> = error.context["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(fields, vec!["id", "name", "email", "role"]);
+        assert_eq!(error.context["fields"]["id"][0], "id is required");
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = user_schema().json_schema();
+        assert_eq!(schema["required"], serde_json::json!(["id"]));
+        assert_eq!(schema["properties"]["id"]["type"], "integer");
+        assert_eq!(schema["properties"]["name"]["maxLength"], 5);
+        assert_eq!(schema["properties"]["email"]["format"], "email");
+        assert_eq!(schema["properties"]["role"]["enum"], serde_json::json!(["admin", "user"]));
+    }
+
+    #[test]
+    fn test_email_format() {
+        assert!(is_email("a.b@example.co"));
+        assert!(!is_email("a@b"));
+        assert!(!is_email("a b@example.com"));
+        assert!(!is_email("@example.com"));
+    }
+
+    #[test]
+    fn test_number_range() {
+        let schema = PayloadSchema::new().field("volume", [Rule::Range { min: 0.0, max: 1.0 }]);
+        assert!(schema.validate("sound.set_volume", &serde_json::json!({ "volume": 0.5 })).is_ok());
+        assert!(schema.validate("sound.set_volume", &serde_json::json!({ "volume": 1 })).is_ok());
+        assert!(schema.validate("sound.set_volume", &serde_json::json!({ "volume": 1.5 })).is_err());
+        assert!(schema.validate("sound.set_volume", &serde_json::json!({ "volume": "loud" })).is_err());
+        assert_eq!(schema.json_schema()["properties"]["volume"]["maximum"], 1.0);
+    }
+}
@@ -0,0 +1,168 @@
+//! Clock and ID generator - where timestamps and ids come from
+//!
+//! Entities, events, messages and errors take the current time from `now()`
+//! and their ids from `new_id()` instead of calling `Utc::now` and
+//! `Uuid::new_v4` themselves. By default these are the system clock and
+//! random v4 UUIDs; a test swaps in `FixedClock` and `SequentialIds` for its
+//! own thread with `use_on_this_thread`, so what it builds is the same on
+//! every run.
+
+use chrono::{DateTime, Utc};
+use std::cell::RefCell;
+use std::sync::Arc;
+#[cfg(test)]
+use chrono::TimeZone;
+#[cfg(test)]
+use std::sync::{atomic::{AtomicU64, Ordering}, Mutex};
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Source of unique ids
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> String;
+}
+
+/// The operating system's clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Random v4 UUIDs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn next_id(&self) -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+}
+
+/// A clock that stands still until it is advanced
+#[cfg(test)]
+#[derive(Debug)]
+pub struct FixedClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+impl FixedClock {
+    pub fn at(now: DateTime<Utc>) -> Self {
+        Self { now: Mutex::new(now) }
+    }
+
+    /// 2024-01-01T00:00:00Z
+    pub fn epoch() -> Self {
+        Self::at(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap())
+    }
+
+    pub fn advance(&self, by: chrono::Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *now += by;
+    }
+}
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// `<prefix>-1`, `<prefix>-2`, ...
+#[cfg(test)]
+#[derive(Debug)]
+pub struct SequentialIds {
+    prefix: String,
+    issued: AtomicU64,
+}
+
+#[cfg(test)]
+impl SequentialIds {
+    pub fn new(prefix: &str) -> Self {
+        Self { prefix: prefix.to_string(), issued: AtomicU64::new(0) }
+    }
+}
+
+#[cfg(test)]
+impl IdGenerator for SequentialIds {
+    fn next_id(&self) -> String {
+        format!("{}-{}", self.prefix, self.issued.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+}
+
+type Sources = (Arc<dyn Clock>, Arc<dyn IdGenerator>);
+
+thread_local! {
+    static OVERRIDE: RefCell<Option<Sources>> = const { RefCell::new(None) };
+}
+
+/// The current time
+pub fn now() -> DateTime<Utc> {
+    OVERRIDE
+        .with(|sources| sources.borrow().as_ref().map(|(clock, _)| clock.now()))
+        .unwrap_or_else(|| SystemClock.now())
+}
+
+/// The current time in milliseconds since the Unix epoch
+pub fn now_millis() -> u64 {
+    now().timestamp_millis().max(0) as u64
+}
+
+/// A new unique id
+pub fn new_id() -> String {
+    OVERRIDE
+        .with(|sources| sources.borrow().as_ref().map(|(_, ids)| ids.next_id()))
+        .unwrap_or_else(|| RandomIds.next_id())
+}
+
+/// Puts the previous sources of the thread back when dropped
+#[cfg(test)]
+pub struct Restore {
+    previous: Option<Sources>,
+}
+
+#[cfg(test)]
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        OVERRIDE.with(|sources| *sources.borrow_mut() = previous);
+    }
+}
+
+/// Take time and ids from `clock` and `ids` on this thread until the
+/// returned guard is dropped. Tasks on other threads keep the defaults.
+#[cfg(test)]
+pub fn use_on_this_thread(clock: Arc<dyn Clock>, ids: Arc<dyn IdGenerator>) -> Restore {
+    let previous = OVERRIDE.with(|sources| sources.borrow_mut().replace((clock, ids)));
+    Restore { previous }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_sources_on_this_thread_only() {
+        let clock = Arc::new(FixedClock::epoch());
+        let restore = use_on_this_thread(clock.clone(), Arc::new(SequentialIds::new("user")));
+        assert_eq!((new_id(), new_id()), ("user-1".to_string(), "user-2".to_string()));
+        assert_eq!(now_millis(), 1_704_067_200_000);
+        clock.advance(chrono::Duration::seconds(90));
+        assert_eq!(now().to_rfc3339(), "2024-01-01T00:01:30+00:00");
+
+        let elsewhere = std::thread::spawn(|| (now().timestamp(), new_id())).join().unwrap();
+        assert!(elsewhere.0 > 1_704_067_290 && uuid::Uuid::parse_str(&elsewhere.1).is_ok());
+
+        drop(restore);
+        assert!(uuid::Uuid::parse_str(&new_id()).is_ok());
+        assert!(now() > Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap());
+    }
+}
@@ -7,9 +7,12 @@
 
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use crate::core::domain::{clock, DomainError};
 
 /// User entity - represents a user in the system
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[allow(dead_code)]
 pub struct User {
     pub id: i64,
     pub name: String,
@@ -18,6 +21,8 @@ pub struct User {
     pub status: UserStatus,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
+    /// Bumped on every stored update; 0 until first stored
+    pub version: i64,
 }
 
 /// User role enumeration
@@ -30,9 +35,22 @@ pub enum UserRole {
     Viewer,
 }
 
+impl UserRole {
+    /// Whether the role allows `action`: `view_content`, `edit_content` or `admin_panel`
+    pub fn can_perform(&self, action: &str) -> bool {
+        match action {
+            "admin_panel" => *self == UserRole::Admin,
+            "edit_content" => matches!(self, UserRole::Admin | UserRole::Editor),
+            "view_content" => true,
+            _ => false,
+        }
+    }
+}
+
 /// User status enumeration
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
+#[allow(dead_code)]
 pub enum UserStatus {
     Active,
     Inactive,
@@ -40,6 +58,7 @@ pub enum UserStatus {
     Suspended,
 }
 
+#[allow(dead_code)]
 impl User {
     /// Create a new user with validation
     pub fn new(
@@ -65,26 +84,28 @@ impl User {
             email,
             role,
             status,
-            created_at: Utc::now(),
+            created_at: clock::now(),
             updated_at: None,
+            version: 0,
         })
     }
 
     /// Update user status
     pub fn update_status(&mut self, status: UserStatus) {
         self.status = status;
-        self.updated_at = Some(Utc::now());
+        self.updated_at = Some(clock::now());
     }
 
     /// Update user role
     pub fn update_role(&mut self, role: UserRole) {
         self.role = role;
-        self.updated_at = Some(Utc::now());
+        self.updated_at = Some(clock::now());
     }
 }
 
 /// Database statistics entity
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[allow(dead_code)]
 pub struct DatabaseStats {
     pub users_count: i64,
     pub tables: Vec<String>,
@@ -93,40 +114,129 @@ pub struct DatabaseStats {
 }
 
 /// Counter entity for demonstration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Counter {
     pub id: String,
     pub value: i64,
     pub label: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Bumped on every stored update; 0 until first stored
+    pub version: i64,
 }
 
 impl Counter {
     pub fn new(id: String, label: String) -> Self {
-        let now = Utc::now();
+        let now = clock::now();
         Self {
             id,
             value: 0,
             label,
             created_at: now,
             updated_at: now,
+            version: 0,
         }
     }
 
     pub fn increment(&mut self) {
         self.value += 1;
-        self.updated_at = Utc::now();
+        self.updated_at = clock::now();
     }
 
     pub fn decrement(&mut self) {
         self.value -= 1;
-        self.updated_at = Utc::now();
+        self.updated_at = clock::now();
     }
 
     pub fn reset(&mut self) {
         self.value = 0;
-        self.updated_at = Utc::now();
+        self.updated_at = clock::now();
+    }
+
+    /// Change the state as `event` says
+    pub fn apply(&mut self, event: &CounterEvent) {
+        match event {
+            CounterEvent::Created { label } => {
+                self.label = label.clone();
+                self.reset();
+            }
+            CounterEvent::Incremented => self.increment(),
+            CounterEvent::Decremented => self.decrement(),
+            CounterEvent::Reset => self.reset(),
+            CounterEvent::Set { value } => {
+                self.value = *value;
+                self.updated_at = clock::now();
+            }
+        }
+    }
+
+    /// Fold the events of counter `id`, oldest first, onto `snapshot`; `None` without either
+    pub fn replay(id: &str, snapshot: Option<Counter>, events: &[RecordedCounterEvent]) -> Option<Counter> {
+        events.iter().fold(snapshot, |counter, recorded| {
+            let mut counter = counter.unwrap_or_else(|| Counter {
+                created_at: recorded.recorded_at,
+                ..Counter::new(id.to_string(), String::new())
+            });
+            counter.apply(&recorded.event);
+            counter.updated_at = recorded.recorded_at;
+            counter.version = recorded.sequence;
+            Some(counter)
+        })
+    }
+}
+
+/// What happened to a counter, as an event-sourced repository stores it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum CounterEvent {
+    #[serde(rename = "CounterCreated")]
+    Created { label: String },
+    #[serde(rename = "CounterIncremented")]
+    Incremented,
+    #[serde(rename = "CounterDecremented")]
+    Decremented,
+    #[serde(rename = "CounterReset")]
+    Reset,
+    /// The value was put back, as by undo and redo
+    #[serde(rename = "CounterSet")]
+    Set { value: i64 },
+}
+
+/// A `CounterEvent` at its place in the counter's stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RecordedCounterEvent {
+    /// 1 for the first event of a counter; the counter's version after it
+    pub sequence: i64,
+    pub recorded_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: CounterEvent,
+}
+
+/// A value of the settings store: JSON under a namespaced key, global or for one user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Setting {
+    /// Groups related keys, such as `ui` or `plugin.notes`
+    pub namespace: String,
+    pub key: String,
+    pub value: serde_json::Value,
+    /// The user this value belongs to; `None` for the global value
+    pub user_id: Option<i64>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Setting {
+    /// Namespaces and keys: 1-128 letters, digits, `.`, `_` or `-`
+    pub fn validate_name(kind: &str, name: &str) -> Result<(), DomainError> {
+        let valid = !name.is_empty()
+            && name.len() <= 128
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+        if !valid {
+            return Err(DomainError::ValidationError(format!(
+                "Invalid setting {} {:?}: use 1-128 letters, digits, '.', '_' or '-'",
+                kind, name
+            )));
+        }
+        Ok(())
     }
 }
 
@@ -134,11 +244,26 @@ impl Counter {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemInfo {
     pub platform: String,
-    pub user_agent: String,
-    pub language: String,
-    pub screen_resolution: String,
-    pub memory_gb: Option<u32>,
-    pub cpu_cores: Option<u32>,
-    pub online: bool,
+    pub arch: String,
+    pub os_name: Option<String>,
+    pub os_version: Option<String>,
+    pub kernel_version: Option<String>,
+    pub hostname: Option<String>,
+    pub cpu_model: Option<String>,
+    pub cpu_cores: usize,
+    pub physical_cores: Option<usize>,
+    pub total_memory_bytes: u64,
+    pub used_memory_bytes: u64,
+    pub uptime_secs: u64,
     pub timestamp: DateTime<Utc>,
 }
+
+impl SystemInfo {
+    /// Fraction of physical memory in use, 0.0 - 100.0
+    pub fn memory_usage_percent(&self) -> f64 {
+        if self.total_memory_bytes == 0 {
+            return 0.0;
+        }
+        self.used_memory_bytes as f64 / self.total_memory_bytes as f64 * 100.0
+    }
+}
@@ -6,6 +6,7 @@ use thiserror::Error;
 
 /// Domain error types
 #[derive(Error, Debug, Clone)]
+#[allow(dead_code)]
 pub enum DomainError {
     #[error("Entity not found: {0}")]
     NotFound(String),
@@ -24,6 +25,14 @@ pub enum DomainError {
     
     #[error("Invalid state transition: {0}")]
     InvalidStateTransition(String),
+
+    #[error("Version conflict on {entity} {id}: expected version {expected}, found {actual}")]
+    VersionConflict {
+        entity: String,
+        id: String,
+        expected: i64,
+        actual: i64,
+    },
 }
 
 /// Result type alias for domain operations
@@ -7,6 +7,7 @@
 //! 
 //! It should have NO dependencies on other layers.
 
+pub mod clock;
 pub mod entities;
 pub mod repositories;
 pub mod services;
@@ -15,6 +16,4 @@ pub mod errors;
 
 pub use entities::*;
 pub use repositories::*;
-pub use services::*;
-pub use value_objects::*;
 pub use errors::*;
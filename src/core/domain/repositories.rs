@@ -3,10 +3,11 @@
 //! Repositories provide an abstraction over data persistence.
 //! The domain layer defines the interface, infrastructure implements it.
 
-use crate::core::domain::{User, DatabaseStats, Counter, SystemInfo, DomainResult};
+use crate::core::domain::{User, DatabaseStats, Counter, CounterEvent, Setting, SystemInfo, DomainResult};
 
 /// User repository trait - defines contract for user data access
 #[async_trait::async_trait]
+#[allow(dead_code)]
 pub trait UserRepository: Send + Sync {
     /// Get all users
     async fn get_all(&self) -> DomainResult<Vec<User>>;
@@ -35,6 +36,7 @@ pub trait UserRepository: Send + Sync {
 
 /// Database statistics repository trait
 #[async_trait::async_trait]
+#[allow(dead_code)]
 pub trait DatabaseStatsRepository: Send + Sync {
     /// Get database statistics
     async fn get_stats(&self) -> DomainResult<DatabaseStats>;
@@ -53,6 +55,22 @@ pub trait CounterRepository: Send + Sync {
     async fn get_by_id(&self, id: &str) -> DomainResult<Option<Counter>>;
     async fn save(&self, counter: Counter) -> DomainResult<Counter>;
     async fn delete(&self, id: &str) -> DomainResult<()>;
+
+    /// Store `counter` as `events` left it; event-sourced repositories keep the events instead of the state
+    async fn save_changes(&self, counter: Counter, _events: Vec<CounterEvent>) -> DomainResult<Counter> {
+        self.save(counter).await
+    }
+}
+
+/// Settings repository trait; `user_id` `None` is the global scope
+#[async_trait::async_trait]
+pub trait SettingsRepository: Send + Sync {
+    async fn get(&self, namespace: &str, key: &str, user_id: Option<i64>) -> DomainResult<Option<Setting>>;
+    /// Every value of `namespace` in one scope, ordered by key
+    async fn list(&self, namespace: &str, user_id: Option<i64>) -> DomainResult<Vec<Setting>>;
+    async fn save(&self, setting: Setting) -> DomainResult<Setting>;
+    /// Whether there was a value to delete
+    async fn delete(&self, namespace: &str, key: &str, user_id: Option<i64>) -> DomainResult<bool>;
 }
 
 /// System info repository trait (typically in-memory or from system)
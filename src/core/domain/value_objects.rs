@@ -7,8 +7,10 @@ use serde::{Deserialize, Serialize};
 
 /// Email value object
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct Email(String);
 
+#[allow(dead_code)]
 impl Email {
     pub fn new(email: String) -> Result<Self, &'static str> {
         if email.contains('@') && email.contains('.') && email.len() <= 254 {
@@ -25,8 +27,10 @@ impl Email {
 
 /// Name value object
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct Name(String);
 
+#[allow(dead_code)]
 impl Name {
     pub fn new(name: String) -> Result<Self, &'static str> {
         let trimmed = name.trim();
@@ -44,14 +48,13 @@ impl Name {
 
 /// Timestamp value object
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct Timestamp(u64);
 
+#[allow(dead_code)]
 impl Timestamp {
     pub fn now() -> Self {
-        Self(std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64)
+        Self(crate::core::domain::clock::now_millis())
     }
     
     pub fn from_millis(millis: u64) -> Self {
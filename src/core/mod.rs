@@ -9,6 +9,3 @@
 
 pub mod domain;
 pub mod application;
-
-pub use domain::*;
-pub use application::*;
@@ -4,12 +4,18 @@
 //! Errors are values that flow through the system, not exceptions.
 
 use thiserror::Error;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
+use crate::core::domain::{clock, DomainError};
 
 /// Application error with rich metadata
+///
+/// The optional metadata is boxed so that `AppResult` stays small on the
+/// happy path; every fallible call in the app returns one.
 #[derive(Error, Debug, Clone, Serialize, Deserialize)]
+#[error("[{code}] {message}")]
 pub struct AppError {
     /// Unique error ID for tracking
     pub id: String,
@@ -24,20 +30,21 @@ pub struct AppError {
     pub cause: Option<String>,
     
     /// Additional context data
-    pub context: HashMap<String, serde_json::Value>,
+    #[allow(clippy::box_collection)]
+    pub context: Box<HashMap<String, serde_json::Value>>,
     
     /// When the error occurred
     pub timestamp: DateTime<Utc>,
     
     /// Where the error occurred (module/function)
-    pub location: Option<ErrorLocation>,
+    pub location: Option<Box<ErrorLocation>>,
     
     /// Suggested recovery action
-    pub recovery: Option<RecoveryAction>,
+    pub recovery: Option<Box<RecoveryAction>>,
 }
 
 /// Error codes for programmatic handling
-#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum ErrorCode {
     // Domain Errors (1000-1999)
     #[error("Entity not found")]
@@ -48,6 +55,8 @@ pub enum ErrorCode {
     BusinessRuleViolation = 1002,
     #[error("Invalid state transition")]
     InvalidStateTransition = 1003,
+    #[error("Version conflict")]
+    ConflictError = 1004,
     
     // Infrastructure Errors (2000-2999)
     #[error("Database error")]
@@ -58,6 +67,8 @@ pub enum ErrorCode {
     Timeout = 2002,
     #[error("Serialization error")]
     SerializationError = 2003,
+    #[error("Service unavailable")]
+    ServiceUnavailable = 2004,
     
     // Application Errors (3000-3999)
     #[error("Command failed")]
@@ -72,6 +83,8 @@ pub enum ErrorCode {
     UiError = 4000,
     #[error("Communication error")]
     CommunicationError = 4001,
+    #[error("Session expired")]
+    SessionExpired = 4002,
     
     // Plugin Errors (5000-5999)
     #[error("Plugin error")]
@@ -80,6 +93,8 @@ pub enum ErrorCode {
     PluginNotFound = 5001,
     #[error("Plugin capability not found")]
     PluginCapabilityNotFound = 5002,
+    #[error("Access denied")]
+    AccessDenied = 5003,
     
     // Unknown
     #[error("Unknown error")]
@@ -96,7 +111,11 @@ pub struct ErrorLocation {
 }
 
 /// Recovery actions for error handling
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Serialized as `{ "type": "retryWithBackoff", "maxRetries": 3, ... }` to match
+/// the frontend `RecoveryAction` union.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum RecoveryAction {
     Retry,
     RetryWithBackoff { max_retries: u32, delay_ms: u64 },
@@ -110,17 +129,35 @@ impl AppError {
     /// Create a new error with minimal information
     pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
         Self {
-            id: uuid::Uuid::new_v4().to_string(),
+            id: clock::new_id(),
             code,
             message: message.into(),
             cause: None,
-            context: HashMap::new(),
-            timestamp: Utc::now(),
+            context: Box::default(),
+            timestamp: clock::now(),
             location: None,
             recovery: None,
         }
     }
     
+    /// Optimistic concurrency failure: the caller read `expected_version`
+    /// but the stored record is already at `current_version`
+    pub fn conflict(
+        entity: &str,
+        id: impl Into<serde_json::Value>,
+        expected_version: i64,
+        current_version: i64,
+    ) -> Self {
+        Self::new(
+            ErrorCode::ConflictError,
+            format!("{} was modified by someone else", entity),
+        )
+        .with_context("entity", entity)
+        .with_context("id", id)
+        .with_context("expected_version", expected_version)
+        .with_context("current_version", current_version)
+    }
+
     /// Add context to the error
     pub fn with_context(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
         self.context.insert(key.into(), value.into());
@@ -128,19 +165,21 @@ impl AppError {
     }
     
     /// Add multiple context values
+    #[allow(dead_code)]
     pub fn with_context_map(mut self, context: HashMap<String, serde_json::Value>) -> Self {
         self.context.extend(context);
         self
     }
     
     /// Set the error location
+    #[allow(dead_code)]
     pub fn with_location(mut self, module: impl Into<String>, function: Option<&str>, line: Option<u32>) -> Self {
-        self.location = Some(ErrorLocation {
+        self.location = Some(Box::new(ErrorLocation {
             module: module.into(),
             function: function.map(String::from),
             line,
             file: None,
-        });
+        }));
         self
     }
     
@@ -152,11 +191,12 @@ impl AppError {
     
     /// Set recovery action
     pub fn with_recovery(mut self, recovery: RecoveryAction) -> Self {
-        self.recovery = Some(recovery);
+        self.recovery = Some(Box::new(recovery));
         self
     }
     
     /// Convert to Result
+    #[allow(dead_code)]
     pub fn into_result<T>(self) -> Result<T, Self> {
         Err(self)
     }
@@ -167,6 +207,7 @@ impl AppError {
     }
     
     /// Get full error details for debugging
+    #[allow(dead_code)]
     pub fn details(&self) -> String {
         let mut details = format!("Error ID: {}\n", self.id);
         details.push_str(&format!("Code: {:?}\n", self.code));
@@ -185,7 +226,7 @@ impl AppError {
         
         if !self.context.is_empty() {
             details.push_str("Context:\n");
-            for (key, value) in &self.context {
+            for (key, value) in self.context.iter() {
                 details.push_str(&format!("  {}: {}\n", key, value));
             }
         }
@@ -194,6 +235,51 @@ impl AppError {
     }
 }
 
+impl From<DomainError> for AppError {
+    fn from(error: DomainError) -> Self {
+        let code = match &error {
+            DomainError::NotFound(_) => ErrorCode::EntityNotFound,
+            DomainError::ValidationError(_) => ErrorCode::ValidationFailed,
+            DomainError::BusinessRuleViolation(_) | DomainError::AccessDenied(_) => {
+                ErrorCode::BusinessRuleViolation
+            }
+            DomainError::RepositoryError(_) => ErrorCode::DatabaseError,
+            DomainError::InvalidStateTransition(_) => ErrorCode::InvalidStateTransition,
+            DomainError::VersionConflict { entity, id, expected, actual } => {
+                return AppError::conflict(entity, id.as_str(), *expected, *actual);
+            }
+        };
+        AppError::new(code, error.to_string())
+    }
+}
+
+/// The inverse of `From<DomainError>`; infrastructure codes become `RepositoryError`
+impl From<AppError> for DomainError {
+    fn from(error: AppError) -> Self {
+        match error.code {
+            ErrorCode::EntityNotFound => DomainError::NotFound(error.message),
+            ErrorCode::ValidationFailed => DomainError::ValidationError(error.message),
+            ErrorCode::BusinessRuleViolation => DomainError::BusinessRuleViolation(error.message),
+            ErrorCode::InvalidStateTransition => DomainError::InvalidStateTransition(error.message),
+            ErrorCode::AccessDenied => DomainError::AccessDenied(error.message),
+            ErrorCode::ConflictError => {
+                let context = |key: &str| error.context.get(key);
+                let version = |key: &str| context(key).and_then(serde_json::Value::as_i64);
+                match (context("entity").and_then(serde_json::Value::as_str), context("id"), version("expected_version"), version("current_version")) {
+                    (Some(entity), Some(id), Some(expected), Some(actual)) => DomainError::VersionConflict {
+                        entity: entity.to_string(),
+                        id: id.as_str().map_or_else(|| id.to_string(), str::to_string),
+                        expected,
+                        actual,
+                    },
+                    _ => DomainError::BusinessRuleViolation(error.message),
+                }
+            }
+            _ => DomainError::RepositoryError(error.message),
+        }
+    }
+}
+
 /// Type alias for AppResult
 pub type AppResult<T> = Result<T, AppError>;
 
@@ -223,3 +309,25 @@ macro_rules! function_name {
         &name[..name.len() - 2]
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_errors_keep_their_kind() {
+        let not_found = DomainError::from(AppError::new(ErrorCode::EntityNotFound, "user 7"));
+        assert!(matches!(not_found, DomainError::NotFound(message) if message == "user 7"));
+        let denied = DomainError::from(AppError::new(ErrorCode::AccessDenied, "admins only"));
+        assert!(matches!(denied, DomainError::AccessDenied(_)));
+        let database = DomainError::from(AppError::new(ErrorCode::DatabaseError, "Database not initialized"));
+        assert!(matches!(database, DomainError::RepositoryError(_)));
+
+        let conflict = DomainError::from(AppError::conflict("user", 7, 2, 3));
+        assert!(matches!(
+            &conflict,
+            DomainError::VersionConflict { entity, id, expected: 2, actual: 3 } if entity == "user" && id == "7"
+        ));
+        assert_eq!(AppError::from(conflict).code, ErrorCode::ConflictError);
+    }
+}
@@ -0,0 +1,331 @@
+//! Circuit Breaker - Stop hammering a failing dependency
+//!
+//! After `failure_threshold` consecutive infrastructure failures the circuit
+//! opens and calls fail fast with `ServiceUnavailable`. Once `open_duration`
+//! has passed a single probe call is let through (half-open): success closes
+//! the circuit, failure opens it again. A probe that never reports back
+//! (its future dropped, or the operation panicked) frees the slot for the
+//! next call.
+
+use crate::error_handling::app_error::{AppError, AppResult, ErrorCode, RecoveryAction};
+use crate::model::core::AppConfig;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+/// Counters exposed through devtools
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CircuitBreakerStats {
+    pub name: String,
+    pub state: CircuitState,
+    pub failure_threshold: u32,
+    pub open_secs: u64,
+    pub consecutive_failures: u32,
+    pub total_calls: u64,
+    pub total_failures: u64,
+    pub rejected_calls: u64,
+    pub last_failure: Option<String>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+    pub opened_at: Option<DateTime<Utc>>,
+}
+
+struct BreakerState {
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+    stats: CircuitBreakerStats,
+}
+
+/// One admitted call; reports its outcome with `finish`
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probing: bool,
+}
+
+impl Permit<'_> {
+    fn finish(mut self, error: Option<&AppError>) {
+        self.probing = false;
+        self.breaker.record(error);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probing {
+            self.breaker.inner.lock().unwrap().probe_in_flight = false;
+            warn!("Circuit '{}' probe abandoned", self.breaker.name);
+        }
+    }
+}
+
+pub struct CircuitBreaker {
+    name: String,
+    failure_threshold: u32,
+    open_duration: Duration,
+    inner: Mutex<BreakerState>,
+}
+
+/// Only failures of the dependency itself trip the breaker; a validation
+/// error or version conflict says nothing about its health
+fn is_infrastructure_failure(code: ErrorCode) -> bool {
+    matches!(
+        code,
+        ErrorCode::DatabaseError
+            | ErrorCode::ConnectionFailed
+            | ErrorCode::Timeout
+            | ErrorCode::CommunicationError
+    )
+}
+
+impl CircuitBreaker {
+    pub fn new(name: impl Into<String>, failure_threshold: u32, open_duration: Duration) -> Self {
+        let name = name.into();
+        let failure_threshold = failure_threshold.max(1);
+        Self {
+            inner: Mutex::new(BreakerState {
+                opened_at: None,
+                probe_in_flight: false,
+                stats: CircuitBreakerStats {
+                    name: name.clone(),
+                    state: CircuitState::Closed,
+                    failure_threshold,
+                    open_secs: open_duration.as_secs(),
+                    consecutive_failures: 0,
+                    total_calls: 0,
+                    total_failures: 0,
+                    rejected_calls: 0,
+                    last_failure: None,
+                    last_failure_at: None,
+                    opened_at: None,
+                },
+            }),
+            name,
+            failure_threshold,
+            open_duration,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap().stats.state
+    }
+
+    pub fn stats(&self) -> CircuitBreakerStats {
+        self.inner.lock().unwrap().stats.clone()
+    }
+
+    /// Close the circuit and clear the failure streak
+    pub fn reset(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.stats.state = CircuitState::Closed;
+        inner.opened_at = None;
+        inner.probe_in_flight = false;
+        inner.stats.consecutive_failures = 0;
+        inner.stats.opened_at = None;
+        info!("Circuit '{}' reset", self.name);
+    }
+
+    /// Run `operation` unless the circuit is open
+    pub fn call<T>(&self, operation: impl FnOnce() -> AppResult<T>) -> AppResult<T> {
+        let permit = self.acquire()?;
+        let result = operation();
+        permit.finish(result.as_ref().err());
+        result
+    }
+
+    /// Async variant of `call`
+    #[allow(dead_code)]
+    pub async fn call_async<T, Fut>(&self, operation: impl FnOnce() -> Fut) -> AppResult<T>
+    where
+        Fut: std::future::Future<Output = AppResult<T>>,
+    {
+        let permit = self.acquire()?;
+        let result = operation().await;
+        permit.finish(result.as_ref().err());
+        result
+    }
+
+    fn acquire(&self) -> AppResult<Permit<'_>> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.stats.total_calls += 1;
+
+        if inner.stats.state == CircuitState::Open {
+            let elapsed = inner.opened_at.map(|at| at.elapsed()).unwrap_or_default();
+            if elapsed < self.open_duration {
+                inner.stats.rejected_calls += 1;
+                return Err(self.unavailable(self.open_duration - elapsed));
+            }
+            inner.stats.state = CircuitState::HalfOpen;
+            info!("Circuit '{}' half-open, probing", self.name);
+        }
+
+        if inner.stats.state == CircuitState::HalfOpen {
+            if inner.probe_in_flight {
+                inner.stats.rejected_calls += 1;
+                return Err(self.unavailable(Duration::from_secs(1)));
+            }
+            inner.probe_in_flight = true;
+        }
+        Ok(Permit {
+            breaker: self,
+            probing: inner.probe_in_flight,
+        })
+    }
+
+    fn record(&self, error: Option<&AppError>) {
+        let mut inner = self.inner.lock().unwrap();
+        let probing = std::mem::take(&mut inner.probe_in_flight);
+
+        match error.filter(|e| is_infrastructure_failure(e.code)) {
+            None => {
+                if inner.stats.state != CircuitState::Closed {
+                    info!("Circuit '{}' closed", self.name);
+                }
+                inner.stats.state = CircuitState::Closed;
+                inner.opened_at = None;
+                inner.stats.consecutive_failures = 0;
+                inner.stats.opened_at = None;
+            }
+            Some(e) => {
+                inner.stats.consecutive_failures += 1;
+                inner.stats.total_failures += 1;
+                inner.stats.last_failure = Some(e.message.clone());
+                inner.stats.last_failure_at = Some(Utc::now());
+
+                if probing || inner.stats.consecutive_failures >= self.failure_threshold {
+                    if inner.stats.state != CircuitState::Open {
+                        warn!(
+                            "Circuit '{}' opened after {} consecutive failures: {}",
+                            self.name, inner.stats.consecutive_failures, e.message
+                        );
+                    }
+                    inner.stats.state = CircuitState::Open;
+                    inner.opened_at = Some(Instant::now());
+                    inner.stats.opened_at = Some(Utc::now());
+                }
+            }
+        }
+    }
+
+    fn unavailable(&self, retry_in: Duration) -> AppError {
+        AppError::new(
+            ErrorCode::ServiceUnavailable,
+            format!("{} is temporarily unavailable", self.name),
+        )
+        .with_context("circuit", self.name.as_str())
+        .with_context("retry_in_ms", retry_in.as_millis() as u64)
+        .with_recovery(RecoveryAction::RetryWithBackoff {
+            max_retries: 1,
+            delay_ms: retry_in.as_millis() as u64,
+        })
+    }
+}
+
+fn registry() -> &'static Mutex<BTreeMap<String, Arc<CircuitBreaker>>> {
+    static BREAKERS: OnceLock<Mutex<BTreeMap<String, Arc<CircuitBreaker>>>> = OnceLock::new();
+    BREAKERS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// Shared breaker for `name`, created from `[circuit_breaker]` settings on first use
+pub fn circuit_breaker(name: &str) -> Arc<CircuitBreaker> {
+    registry()
+        .lock()
+        .unwrap()
+        .entry(name.to_string())
+        .or_insert_with(|| {
+            let config = AppConfig::global();
+            Arc::new(CircuitBreaker::new(
+                name,
+                config.get_circuit_failure_threshold(),
+                Duration::from_secs(config.get_circuit_open_secs()),
+            ))
+        })
+        .clone()
+}
+
+/// Stats of every breaker created so far
+pub fn circuit_breaker_stats() -> Vec<CircuitBreakerStats> {
+    registry().lock().unwrap().values().map(|b| b.stats()).collect()
+}
+
+/// Reset one breaker; returns false if it does not exist
+pub fn reset_circuit_breaker(name: &str) -> bool {
+    match registry().lock().unwrap().get(name) {
+        Some(breaker) => {
+            breaker.reset();
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fail() -> AppResult<()> {
+        Err(AppError::new(ErrorCode::DatabaseError, "disk I/O error"))
+    }
+
+    #[test]
+    fn test_opens_after_threshold_and_rejects() {
+        let breaker = CircuitBreaker::new("db", 2, Duration::from_secs(60));
+        assert!(breaker.call(fail).is_err());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.call(fail).is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        let rejected = breaker.call(|| Ok(())).unwrap_err();
+        assert_eq!(rejected.code, ErrorCode::ServiceUnavailable);
+        assert_eq!(breaker.stats().rejected_calls, 1);
+    }
+
+    #[test]
+    fn test_half_open_probe_closes_or_reopens() {
+        let breaker = CircuitBreaker::new("db", 1, Duration::from_millis(20));
+        assert!(breaker.call(fail).is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.call(fail).is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.call(|| Ok(())).is_ok());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.stats().consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_probe_frees_the_slot() {
+        let breaker = CircuitBreaker::new("db", 1, Duration::from_millis(20));
+        assert!(breaker.call(fail).is_err());
+        std::thread::sleep(Duration::from_millis(30));
+
+        let probe = breaker.call_async(std::future::pending::<AppResult<()>>);
+        assert!(tokio::time::timeout(Duration::from_millis(10), probe).await.is_err());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        assert!(breaker.call(|| Ok(())).is_ok());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_domain_errors_do_not_trip() {
+        let breaker = CircuitBreaker::new("db", 1, Duration::from_secs(60));
+        let result: AppResult<()> = breaker.call(|| Err(AppError::new(ErrorCode::ConflictError, "stale")));
+        assert!(result.is_err());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
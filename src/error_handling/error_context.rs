@@ -7,10 +7,12 @@ use crate::error_handling::app_error::{AppError, AppResult, ErrorCode};
 use std::collections::HashMap;
 
 /// Context builder for errors
+#[allow(dead_code)]
 pub struct ErrorContext {
     data: HashMap<String, serde_json::Value>,
 }
 
+#[allow(dead_code)]
 impl ErrorContext {
     pub fn new() -> Self {
         Self {
@@ -63,6 +65,7 @@ impl Default for ErrorContext {
 }
 
 /// Trait for adding context to results
+#[allow(dead_code)]
 pub trait WithErrorContext<T> {
     fn with_error_context<F>(self, f: F) -> AppResult<T>
     where
@@ -77,7 +80,7 @@ impl<T> WithErrorContext<T> for AppResult<T> {
         self.map_err(|e| {
             let context = f(ErrorContext::new());
             AppError {
-                context: context.into_map(),
+                context: Box::new(context.into_map()),
                 ..e
             }
         })
@@ -125,11 +128,12 @@ pub mod guards {
     }
     
     /// Require non-empty string
-    pub fn require_non_empty(
-        value: &str,
+    #[allow(dead_code)]
+    pub fn require_non_empty<'a>(
+        value: &'a str,
         code: ErrorCode,
         field: &str,
-    ) -> AppResult<&str> {
+    ) -> AppResult<&'a str> {
         if value.is_empty() {
             Err(AppError::new(code, format!("{} cannot be empty", field)))
         } else {
@@ -138,7 +142,7 @@ pub mod guards {
     }
     
     /// Require value in range
-    pub fn require_in_range<T: PartialOrd + Clone>(
+    pub fn require_in_range<T: PartialOrd + Clone + std::fmt::Debug>(
         value: T,
         min: T,
         max: T,
@@ -160,6 +164,7 @@ pub mod validation {
     use super::*;
     
     /// Validate a value with a predicate
+    #[allow(dead_code)]
     pub fn validate<T, F>(
         value: T,
         predicate: F,
@@ -176,14 +181,13 @@ pub mod validation {
         }
     }
     
+    /// A predicate with the error it raises when it fails
+    pub type Validation<T> = (Box<dyn FnOnce(&T) -> bool>, ErrorCode, String);
+
     /// Validate multiple conditions, collecting all errors
     pub fn validate_all<T>(
         value: &T,
-        validations: Vec<(
-            Box<dyn FnOnce(&T) -> bool>,
-            ErrorCode,
-            String,
-        )>,
+        validations: Vec<Validation<T>>,
     ) -> Result<(), Vec<AppError>> {
         let errors: Vec<AppError> = validations
             .into_iter()
@@ -4,15 +4,18 @@
 //! at application boundaries.
 
 use crate::error_handling::app_error::{AppError, AppResult, ErrorCode, RecoveryAction};
+use crate::infrastructure::i18n;
 use tracing::{error, warn, info};
 
 /// Error handler for processing errors at boundaries
+#[allow(dead_code)]
 pub struct ErrorHandler {
     log_level: LogLevel,
     include_stack: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
 pub enum LogLevel {
     Error,
     Warn,
@@ -28,17 +31,20 @@ impl ErrorHandler {
         }
     }
     
+    #[allow(dead_code)]
     pub fn with_log_level(mut self, level: LogLevel) -> Self {
         self.log_level = level;
         self
     }
     
+    #[allow(dead_code)]
     pub fn with_stack_trace(mut self, include: bool) -> Self {
         self.include_stack = include;
         self
     }
     
     /// Handle an error - log and potentially recover
+    #[allow(dead_code)]
     pub fn handle<T>(&self, result: AppResult<T>) -> AppResult<T> {
         match &result {
             Ok(_) => result,
@@ -50,6 +56,7 @@ impl ErrorHandler {
     }
     
     /// Handle error with recovery strategy
+    #[allow(dead_code)]
     pub fn handle_with_recovery<T, F>(&self, result: AppResult<T>, recover: F) -> AppResult<T>
     where
         F: FnOnce(&AppError) -> AppResult<T>,
@@ -60,14 +67,14 @@ impl ErrorHandler {
                 self.log_error(&e);
                 
                 // Try recovery if specified
-                match &e.recovery {
+                match e.recovery.as_deref() {
                     Some(RecoveryAction::Retry) => recover(&e),
                     Some(RecoveryAction::RetryWithBackoff { max_retries, delay_ms }) => {
                         // Simplified retry logic
                         self.log_retry(&e, *max_retries, *delay_ms);
                         recover(&e)
                     }
-                    Some(RecoveryAction::Fallback { fallback_value }) => {
+                    Some(RecoveryAction::Fallback { .. }) => {
                         self.log_fallback(&e);
                         // In real implementation, parse fallback_value
                         recover(&e)
@@ -88,29 +95,38 @@ impl ErrorHandler {
         }
     }
     
-    /// Convert error to user-friendly message
+    /// Convert error to user-friendly message, in the locale of the client being answered
     pub fn to_user_message(&self, error: &AppError) -> String {
+        i18n::t(&format!("error.{:?}", error.code), &[("message", &error.message)])
+    }
+    
+    /// Recovery hint for the client: the error's own action, or a default by code
+    pub fn recovery_hint(&self, error: &AppError) -> RecoveryAction {
+        if let Some(recovery) = error.recovery.as_deref() {
+            return recovery.clone();
+        }
         match error.code {
-            ErrorCode::EntityNotFound => "The requested item was not found".to_string(),
-            ErrorCode::ValidationFailed => format!("Validation failed: {}", error.message),
-            ErrorCode::BusinessRuleViolation => error.message.clone(),
-            ErrorCode::DatabaseError => "A database error occurred. Please try again.".to_string(),
-            ErrorCode::ConnectionFailed => "Connection failed. Please check your network.".to_string(),
-            ErrorCode::Timeout => "The operation timed out. Please try again.".to_string(),
-            ErrorCode::SerializationError => "Data format error. Please refresh.".to_string(),
-            ErrorCode::CommandFailed | ErrorCode::QueryFailed | ErrorCode::HandlerError => {
-                "An operation failed. Please try again.".to_string()
-            }
-            ErrorCode::UiError => "Display error. Please refresh the page.".to_string(),
-            ErrorCode::CommunicationError => "Communication error. Please check connection.".to_string(),
-            ErrorCode::PluginError | ErrorCode::PluginNotFound | ErrorCode::PluginCapabilityNotFound => {
-                "Feature unavailable. Please contact support.".to_string()
-            }
-            ErrorCode::Unknown => error.message.clone(),
+            ErrorCode::ConnectionFailed
+            | ErrorCode::Timeout
+            | ErrorCode::CommunicationError
+            | ErrorCode::DatabaseError
+            | ErrorCode::ServiceUnavailable => RecoveryAction::RetryWithBackoff {
+                max_retries: 3,
+                delay_ms: 1000,
+            },
+            ErrorCode::EntityNotFound
+            | ErrorCode::ValidationFailed
+            | ErrorCode::BusinessRuleViolation
+            | ErrorCode::InvalidStateTransition
+            | ErrorCode::ConflictError => RecoveryAction::UserNotification {
+                message: self.to_user_message(error),
+            },
+            _ => RecoveryAction::Abort,
         }
     }
     
     /// Log error based on severity
+    #[allow(dead_code)]
     fn log_error(&self, error: &AppError) {
         let message = if self.include_stack {
             error.details()
@@ -126,6 +142,7 @@ impl ErrorHandler {
         }
     }
     
+    #[allow(dead_code)]
     fn log_retry(&self, error: &AppError, max_retries: u32, delay_ms: u64) {
         warn!(
             error_id = %error.id,
@@ -135,6 +152,7 @@ impl ErrorHandler {
         );
     }
     
+    #[allow(dead_code)]
     fn log_fallback(&self, error: &AppError) {
         info!(
             error_id = %error.id,
@@ -142,6 +160,7 @@ impl ErrorHandler {
         );
     }
     
+    #[allow(dead_code)]
     fn log_user_notification(&self, message: &str) {
         info!("User notification: {}", message);
     }
@@ -158,6 +177,7 @@ pub struct GlobalErrorHandler;
 
 impl GlobalErrorHandler {
     /// Register global error handler
+    #[allow(dead_code)]
     pub fn register() {
         // Set up panic hook for unhandled errors
         std::panic::set_hook(Box::new(|panic_info| {
@@ -182,16 +202,60 @@ impl GlobalErrorHandler {
         }));
     }
     
-    /// Handle error and return JSON response
+    /// Standard failure envelope for command responses
+    ///
+    /// `code` is the variant name and `code_value` its number (e.g. 1004),
+    /// `user_message` is safe to show as-is and `recovery` tells the client
+    /// whether to retry, ask the user or give up.
     pub fn to_json_response(error: &AppError) -> serde_json::Value {
+        let handler = ErrorHandler::default();
         serde_json::json!({
             "success": false,
             "error": {
                 "code": format!("{:?}", error.code),
+                "code_value": error.code as u16,
                 "message": error.message,
+                "user_message": handler.to_user_message(error),
+                "recovery": handler.recovery_hint(error),
                 "id": error.id,
                 "timestamp": error.timestamp,
+                "context": error.context,
             }
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_response_envelope() {
+        let error = AppError::new(ErrorCode::Timeout, "query timed out").with_context("table", "users");
+        let response = GlobalErrorHandler::to_json_response(&error);
+
+        assert_eq!(response["success"], false);
+        assert_eq!(response["error"]["code"], "Timeout");
+        assert_eq!(response["error"]["code_value"], 2002);
+        assert_eq!(response["error"]["user_message"], "The operation timed out. Please try again.");
+        assert_eq!(response["error"]["recovery"]["type"], "retryWithBackoff");
+        assert_eq!(response["error"]["recovery"]["maxRetries"], 3);
+        assert_eq!(response["error"]["context"]["table"], "users");
+    }
+
+    #[test]
+    fn test_explicit_recovery_wins() {
+        let error = AppError::new(ErrorCode::Timeout, "busy").with_recovery(RecoveryAction::Retry);
+        let response = GlobalErrorHandler::to_json_response(&error);
+        assert_eq!(response["error"]["recovery"], serde_json::json!({ "type": "retry" }));
+    }
+
+    #[tokio::test]
+    async fn test_user_message_follows_the_client_locale() {
+        let error = AppError::new(ErrorCode::ValidationFailed, "name is required");
+        let response = i18n::scope(Some("de-DE".to_string()), async { GlobalErrorHandler::to_json_response(&error) }).await;
+        assert_eq!(response["error"]["user_message"], "Ungültige Eingabe: name is required");
+        assert_eq!(response["error"]["recovery"]["message"], "Ungültige Eingabe: name is required");
+        assert_eq!(ErrorHandler::new().to_user_message(&error), "Validation failed: name is required");
+    }
+}
@@ -7,11 +7,12 @@
 //! - Never thrown as exceptions (in business logic)
 
 pub mod app_error;
+pub mod circuit_breaker;
 pub mod result_ext;
 pub mod error_context;
 pub mod error_handler;
 
 pub use app_error::*;
-pub use result_ext::*;
+pub use circuit_breaker::*;
 pub use error_context::*;
 pub use error_handler::*;
@@ -6,6 +6,7 @@
 use crate::error_handling::app_error::{AppError, AppResult, ErrorCode};
 
 /// Extension trait for Result with AppError
+#[allow(dead_code)]
 pub trait ResultExt<T, E> {
     /// Map the success value
     fn map_ok<F, U>(self, f: F) -> Result<U, E>
@@ -39,7 +40,9 @@ pub trait ResultExt<T, E> {
         F: FnOnce(&E);
     
     /// Convert to Option, logging error
-    fn ok_or_log(self, context: &str) -> Option<T>;
+    fn ok_or_log(self, context: &str) -> Option<T>
+    where
+        E: std::fmt::Debug;
     
     /// Convert to default value on error
     fn unwrap_or_default_on_error(self) -> T where T: Default;
@@ -103,7 +106,10 @@ impl<T, E> ResultExt<T, E> for Result<T, E> {
         self
     }
     
-    fn ok_or_log(self, context: &str) -> Option<T> {
+    fn ok_or_log(self, context: &str) -> Option<T>
+    where
+        E: std::fmt::Debug,
+    {
         match self {
             Ok(v) => Some(v),
             Err(e) => {
@@ -129,6 +135,7 @@ impl<T, E> ResultExt<T, E> for Result<T, E> {
 }
 
 /// Specific extensions for AppResult
+#[allow(dead_code)]
 pub trait AppResultExt<T> {
     /// Add context to error
     fn with_context(self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> AppResult<T>;
@@ -136,9 +143,6 @@ pub trait AppResultExt<T> {
     /// Add error location
     fn with_location(self, module: impl Into<String>, function: Option<&str>, line: Option<u32>) -> AppResult<T>;
     
-    /// Convert domain error to app error
-    fn map_domain_error(self) -> AppResult<T>;
-    
     /// Log and convert to option
     fn log_error(self, context: &str) -> Option<T>;
     
@@ -158,18 +162,6 @@ impl<T> AppResultExt<T> for AppResult<T> {
         self.map_err(|e| e.with_location(module, function, line))
     }
     
-    fn map_domain_error(self) -> AppResult<T> {
-        use crate::core::domain::DomainError;
-        self.map_err(|e| {
-            // If it's a domain error, convert it
-            if let Some(domain_err) = e.downcast_ref::<DomainError>() {
-                AppError::new(ErrorCode::BusinessRuleViolation, domain_err.to_string())
-            } else {
-                e
-            }
-        })
-    }
-    
     fn log_error(self, context: &str) -> Option<T> {
         match self {
             Ok(v) => Some(v),
@@ -180,7 +172,7 @@ impl<T> AppResultExt<T> for AppResult<T> {
         }
     }
     
-    fn retry_on<F, Fut>(self, codes: &[ErrorCode], f: F) -> AppResult<T>
+    fn retry_on<F, Fut>(self, _codes: &[ErrorCode], _f: F) -> AppResult<T>
     where
         F: Fn() -> Fut,
         Fut: std::future::Future<Output = AppResult<T>>,
@@ -192,16 +184,19 @@ impl<T> AppResultExt<T> for AppResult<T> {
 }
 
 /// Create success result
+#[allow(dead_code)]
 pub fn ok<T>(value: T) -> AppResult<T> {
     Ok(value)
 }
 
 /// Create error result
+#[allow(dead_code)]
 pub fn err<T>(code: ErrorCode, message: impl Into<String>) -> AppResult<T> {
     Err(AppError::new(code, message))
 }
 
 /// Create error result with context builder
+#[allow(dead_code)]
 pub fn err_with<T>(code: ErrorCode, message: impl Into<String>) -> ErrorBuilder<T> {
     ErrorBuilder {
         error: AppError::new(code, message),
@@ -210,11 +205,13 @@ pub fn err_with<T>(code: ErrorCode, message: impl Into<String>) -> ErrorBuilder<
 }
 
 /// Builder for creating errors with context
+#[allow(dead_code)]
 pub struct ErrorBuilder<T> {
     error: AppError,
     _phantom: std::marker::PhantomData<T>,
 }
 
+#[allow(dead_code)]
 impl<T> ErrorBuilder<T> {
     pub fn context(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
         self.error = self.error.with_context(key, value);
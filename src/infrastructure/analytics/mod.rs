@@ -0,0 +1,321 @@
+//! Anonymous usage analytics, counted locally
+//!
+//! Every built-in command invoked and every window the frontend opens is
+//! counted per UTC day in the `analytics_daily` table. Counts are buffered in
+//! memory and written once a minute; nothing identifies the user, a payload
+//! or a session. `analytics.summary` shows the user their own data.
+//!
+//! Nothing leaves the machine unless the user opts in by setting `upload` to
+//! `true` in the `analytics` settings namespace and the app was built with
+//! `--features telemetry` and `[analytics] upload_url`. Each day finished
+//! after the opt-in is then posted once as a JSON summary; opting out and in
+//! again starts over.
+
+use chrono::{Days, NaiveDate, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tracing::{debug, error, info};
+use crate::infrastructure::commands::command_message;
+use crate::infrastructure::database::database;
+use crate::infrastructure::settings;
+use crate::model::core::{AppConfig, Database, UsageCount};
+
+/// Settings namespace of the opt-in
+pub const NAMESPACE: &str = "analytics";
+/// `true` under this key of `NAMESPACE` lets finished days be uploaded
+pub const OPT_IN: &str = "upload";
+/// Last day uploaded, kept in `NAMESPACE`
+const UPLOADED_THROUGH: &str = "uploaded_through";
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+/// Longest window name counted
+const MAX_NAME_LEN: usize = 64;
+/// Days `analytics.summary` covers unless asked otherwise
+const DEFAULT_DAYS: u64 = 7;
+
+pub const COMMAND: &str = "command";
+pub const WINDOW: &str = "window";
+
+type DbResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+/// Usage of one day
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageDay {
+    pub day: NaiveDate,
+    pub total: u64,
+    pub commands: BTreeMap<String, u64>,
+    pub windows: BTreeMap<String, u64>,
+}
+
+/// Group usage counts into days, oldest first
+pub fn summarize(rows: impl IntoIterator<Item = UsageCount>) -> Vec<UsageDay> {
+    let mut days: BTreeMap<NaiveDate, UsageDay> = BTreeMap::new();
+    for (day, kind, name, count) in rows {
+        let Ok(date) = day.parse::<NaiveDate>() else {
+            continue;
+        };
+        let usage = days.entry(date).or_insert_with(|| UsageDay {
+            day: date,
+            total: 0,
+            commands: BTreeMap::new(),
+            windows: BTreeMap::new(),
+        });
+        let counts = match kind.as_str() {
+            COMMAND => &mut usage.commands,
+            WINDOW => &mut usage.windows,
+            _ => continue,
+        };
+        *counts.entry(name).or_default() += count;
+        usage.total += count;
+    }
+    days.into_values().collect()
+}
+
+pub struct Analytics {
+    enabled: bool,
+    /// Counts not written yet, by day, kind and name
+    pending: Mutex<HashMap<(NaiveDate, &'static str, String), u64>>,
+}
+
+impl Analytics {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled, pending: Mutex::default() }
+    }
+
+    pub fn global() -> &'static Analytics {
+        static ANALYTICS: OnceLock<Analytics> = OnceLock::new();
+        ANALYTICS.get_or_init(|| Analytics::new(AppConfig::global().is_analytics_enabled()))
+    }
+
+    fn record(&self, kind: &'static str, name: &str, day: NaiveDate) {
+        if !self.enabled {
+            return;
+        }
+        if let Ok(mut pending) = self.pending.lock() {
+            *pending.entry((day, kind, name.to_string())).or_default() += 1;
+        }
+    }
+
+    /// Count a command; only built-in ones, so payload-like names never end up in the counts
+    pub fn command(&self, name: &str) {
+        static KNOWN: OnceLock<HashSet<String>> = OnceLock::new();
+        let known = KNOWN.get_or_init(|| {
+            crate::viewmodel::command_catalog::commands().into_iter().map(|command| command.name.to_string()).collect()
+        });
+        if known.contains(name) {
+            self.record(COMMAND, name, Utc::now().date_naive());
+        }
+    }
+
+    /// Count a window the frontend opened, by its title
+    pub fn window(&self, title: &str) {
+        let title: String = title.trim().chars().take(MAX_NAME_LEN).collect();
+        if !title.is_empty() {
+            self.record(WINDOW, &title, Utc::now().date_naive());
+        }
+    }
+
+    /// Write the buffered counts; they are kept for the next try if that fails
+    pub fn flush(&self, db: &Database) -> DbResult<usize> {
+        let Some(pending) = self.pending.lock().ok().map(|mut pending| std::mem::take(&mut *pending)) else {
+            return Ok(0);
+        };
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        let rows: Vec<_> = pending
+            .iter()
+            .map(|((day, kind, name), count)| (day.to_string(), kind.to_string(), name.clone(), *count))
+            .collect();
+        if let Err(e) = db.add_usage_counts(&rows) {
+            if let Ok(mut current) = self.pending.lock() {
+                for (key, count) in pending {
+                    *current.entry(key).or_default() += count;
+                }
+            }
+            return Err(e);
+        }
+        Ok(rows.len())
+    }
+}
+
+/// Write the buffered counts, e.g. before the app exits
+pub fn flush() {
+    let result = database().map_err(Into::into).and_then(|db| Analytics::global().flush(&db));
+    if let Err(e) = result {
+        error!("Failed to write usage counts: {}", e);
+    }
+}
+
+/// Whether the user opted in to uploads
+async fn opted_in() -> bool {
+    let Ok(repo) = settings::repository() else {
+        return false;
+    };
+    matches!(settings::get(&repo, NAMESPACE, OPT_IN, None).await, Ok(Some(setting)) if setting.value == Value::Bool(true))
+}
+
+async fn uploaded_through() -> Option<NaiveDate> {
+    let repo = settings::repository().ok()?;
+    let setting = settings::get(&repo, NAMESPACE, UPLOADED_THROUGH, None).await.ok()??;
+    setting.value.as_str()?.parse().ok()
+}
+
+async fn set_uploaded_through(day: Option<NaiveDate>) -> DbResult<()> {
+    let value = day.map_or(Value::Null, |day| Value::String(day.to_string()));
+    settings::set(&settings::repository()?, NAMESPACE, UPLOADED_THROUGH, value, None).await?;
+    Ok(())
+}
+
+/// Post every finished day after the last uploaded one; right after opting in,
+/// only mark where uploads start, so days from before the opt-in stay local
+#[cfg(feature = "telemetry")]
+async fn upload(db: &Database, url: &str) -> DbResult<()> {
+    let today = Utc::now().date_naive();
+    let Some(first) = uploaded_through().await.map(|day| day + Days::new(1)) else {
+        return set_uploaded_through(today.pred_opt()).await;
+    };
+    let days: Vec<UsageDay> = summarize(db.usage_counts(&first.to_string())?).into_iter().filter(|usage| usage.day < today).collect();
+    let url = url.to_string();
+    for usage in days {
+        let day = usage.day;
+        let url = url.clone();
+        let body = serde_json::json!({
+            "app_version": env!("CARGO_PKG_VERSION"),
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "day": usage.day,
+            "total": usage.total,
+            "commands": usage.commands,
+            "windows": usage.windows,
+        });
+        tokio::task::spawn_blocking(move || {
+            ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .post(&url)
+                .set("Content-Type", "application/json")
+                .send_string(&body.to_string())
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+        .await??;
+        // Recorded per day, so a failure later on does not send this day again
+        set_uploaded_through(Some(day)).await?;
+        info!("Uploaded the usage summary of {}", day);
+    }
+    Ok(())
+}
+
+/// Write counts once a minute; hourly, drop old days and upload finished ones if the user opted in
+pub fn start(db: Arc<Database>, config: &AppConfig) {
+    if !config.is_analytics_enabled() {
+        return;
+    }
+    let retention = config.get_analytics_retention_days();
+    #[cfg(feature = "telemetry")]
+    let upload_url = config.get_analytics_upload_url().and_then(|url| {
+        crate::infrastructure::secrets::SecretsManager::global()
+            .resolve(url)
+            .map_err(|e| error!("Ignoring analytics.upload_url: {}", e))
+            .ok()
+    });
+
+    tokio::spawn(async move {
+        let mut ticks: u64 = 0;
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            if let Err(e) = Analytics::global().flush(&db) {
+                error!("Failed to write usage counts: {}", e);
+            }
+            // The rest runs after the first minute and then hourly
+            ticks += 1;
+            if ticks % 60 != 1 {
+                continue;
+            }
+            let oldest = Utc::now().date_naive() - Days::new(retention);
+            match db.delete_usage_counts_before(&oldest.to_string()) {
+                Ok(0) => {}
+                Ok(deleted) => debug!("Deleted {} old usage counts", deleted),
+                Err(e) => error!("Failed to clean up usage counts: {}", e),
+            }
+            if !opted_in().await {
+                // Opting in again later starts over instead of sending the days in between
+                if uploaded_through().await.is_some() {
+                    if let Err(e) = set_uploaded_through(None).await {
+                        error!("Failed to reset the usage upload state: {}", e);
+                    }
+                }
+                continue;
+            }
+            #[cfg(feature = "telemetry")]
+            if let Some(url) = upload_url.as_deref() {
+                if let Err(e) = upload(&db, url).await {
+                    error!("Failed to upload usage summaries: {}", e);
+                }
+            }
+        }
+    });
+    info!("Usage analytics are counted locally");
+}
+
+async fn summary(payload: &Value) -> DbResult<Value> {
+    let db = database()?;
+    Analytics::global().flush(&db)?;
+    let days = payload.get("days").and_then(Value::as_u64).unwrap_or(DEFAULT_DAYS).clamp(1, 366);
+    let first = Utc::now().date_naive() - Days::new(days - 1);
+    Ok(serde_json::json!({
+        "success": true,
+        "enabled": AppConfig::global().is_analytics_enabled(),
+        "days": summarize(db.usage_counts(&first.to_string())?),
+        "upload": {
+            "opted_in": opted_in().await,
+            "available": cfg!(feature = "telemetry") && AppConfig::global().get_analytics_upload_url().is_some(),
+            "uploaded_through": uploaded_through().await,
+        },
+    }))
+}
+
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    let result = match name {
+        "analytics.summary" => summary(payload).await,
+        other => Err(format!("Unknown command: {}", other).into()),
+    };
+    command_message(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_are_flushed_and_summarized_per_day() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        let analytics = Analytics::new(true);
+        let day = |d: &str| d.parse::<NaiveDate>().unwrap();
+        analytics.record(COMMAND, "get_users", day("2026-03-01"));
+        analytics.record(COMMAND, "get_users", day("2026-03-01"));
+        analytics.record(WINDOW, "SQLite", day("2026-03-01"));
+        analytics.record(COMMAND, "get_users", day("2026-03-02"));
+        assert_eq!(analytics.flush(&db).unwrap(), 3);
+        analytics.record(COMMAND, "get_users", day("2026-03-02"));
+        analytics.flush(&db).unwrap();
+        assert_eq!(analytics.flush(&db).unwrap(), 0);
+
+        let days = summarize(db.usage_counts("2026-03-01").unwrap());
+        assert_eq!(days.len(), 2);
+        assert_eq!((days[0].total, days[0].commands["get_users"], days[0].windows["SQLite"]), (3, 2, 1));
+        assert_eq!((days[1].day, days[1].commands["get_users"]), (day("2026-03-02"), 2));
+
+        assert_eq!(db.delete_usage_counts_before("2026-03-02").unwrap(), 2);
+        assert_eq!(summarize(db.usage_counts("2026-01-01").unwrap()).len(), 1);
+
+        let disabled = Analytics::new(false);
+        disabled.record(COMMAND, "get_users", day("2026-03-01"));
+        assert_eq!(disabled.flush(&db).unwrap(), 0);
+    }
+}
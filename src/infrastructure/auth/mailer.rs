@@ -0,0 +1,140 @@
+//! Outgoing mail, `[mail]`
+//!
+//! `transport = "log"` writes each mail to the application log, which is all
+//! a development setup needs. `transport = "smtp"` sends through an SMTP relay
+//! and needs the `smtp` feature; without it every send fails rather than
+//! leaking tokens into the log.
+
+use std::sync::OnceLock;
+use thiserror::Error;
+use tracing::{error, info};
+use crate::model::core::AppConfig;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, Error)]
+pub enum MailError {
+    #[cfg_attr(not(feature = "smtp"), allow(dead_code))]
+    #[error("Invalid address {0}: {1}")]
+    Address(String, String),
+    #[error("Mail transport error: {0}")]
+    Transport(String),
+    #[error("Mail is unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// Delivers mail; sends block, so async callers go through `spawn_blocking`
+pub trait Mailer: Send + Sync {
+    fn send(&self, email: &Email) -> Result<(), MailError>;
+}
+
+/// Writes mail to the log instead of delivering it
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send(&self, email: &Email) -> Result<(), MailError> {
+        info!("Mail to {} ({}):\n{}", email.to, email.subject, email.body);
+        Ok(())
+    }
+}
+
+/// Fails every send, for a transport this build cannot use
+struct UnavailableMailer(String);
+
+impl Mailer for UnavailableMailer {
+    fn send(&self, _email: &Email) -> Result<(), MailError> {
+        Err(MailError::Unavailable(self.0.clone()))
+    }
+}
+
+#[cfg(feature = "smtp")]
+pub struct SmtpMailer {
+    from: lettre::message::Mailbox,
+    transport: lettre::SmtpTransport,
+}
+
+#[cfg(feature = "smtp")]
+impl SmtpMailer {
+    pub fn from_config(config: &AppConfig) -> Result<Self, MailError> {
+        use lettre::transport::smtp::authentication::Credentials;
+        use lettre::SmtpTransport;
+
+        let host = config.get_mail_smtp_host();
+        let transport_error = |e: lettre::transport::smtp::Error| MailError::Transport(e.to_string());
+        let builder = match config.get_mail_smtp_tls() {
+            "tls" => SmtpTransport::relay(host).map_err(transport_error)?,
+            "none" => SmtpTransport::builder_dangerous(host),
+            _ => SmtpTransport::starttls_relay(host).map_err(transport_error)?,
+        };
+        let mut builder = builder.port(config.get_mail_smtp_port());
+        if let Some(username) = config.get_mail_smtp_username() {
+            let password = match config.get_mail_smtp_password() {
+                Some(value) => crate::infrastructure::secrets::SecretsManager::global()
+                    .resolve(value)
+                    .map_err(|e| MailError::Unavailable(e.to_string()))?,
+                None => String::new(),
+            };
+            builder = builder.credentials(Credentials::new(username.to_string(), password));
+        }
+
+        let from = config.get_mail_from();
+        Ok(Self {
+            from: from.parse().map_err(|e: lettre::address::AddressError| MailError::Address(from.to_string(), e.to_string()))?,
+            transport: builder.build(),
+        })
+    }
+}
+
+#[cfg(feature = "smtp")]
+impl Mailer for SmtpMailer {
+    fn send(&self, email: &Email) -> Result<(), MailError> {
+        use lettre::Transport;
+
+        let message = lettre::Message::builder()
+            .from(self.from.clone())
+            .to(email
+                .to
+                .parse()
+                .map_err(|e: lettre::address::AddressError| MailError::Address(email.to.clone(), e.to_string()))?)
+            .subject(email.subject.clone())
+            .body(email.body.clone())
+            .map_err(|e| MailError::Transport(e.to_string()))?;
+        self.transport.send(&message).map_err(|e| MailError::Transport(e.to_string()))?;
+        info!("Sent mail to {} ({})", email.to, email.subject);
+        Ok(())
+    }
+}
+
+fn from_config(config: &AppConfig) -> Box<dyn Mailer> {
+    match config.get_mail_transport() {
+        "log" => Box::new(LogMailer),
+        #[cfg(feature = "smtp")]
+        "smtp" => match SmtpMailer::from_config(config) {
+            Ok(mailer) => Box::new(mailer),
+            Err(e) => {
+                error!("SMTP mail is unavailable: {}", e);
+                Box::new(UnavailableMailer(e.to_string()))
+            }
+        },
+        #[cfg(not(feature = "smtp"))]
+        "smtp" => {
+            error!("[mail] transport = \"smtp\" needs a build with the smtp feature");
+            Box::new(UnavailableMailer("built without the smtp feature".to_string()))
+        }
+        other => {
+            error!("Unknown [mail] transport: {}", other);
+            Box::new(UnavailableMailer(format!("unknown transport {}", other)))
+        }
+    }
+}
+
+/// The mailer of this run, from `[mail]`
+pub fn global() -> &'static dyn Mailer {
+    static MAILER: OnceLock<Box<dyn Mailer>> = OnceLock::new();
+    MAILER.get_or_init(|| from_config(AppConfig::global())).as_ref()
+}
@@ -0,0 +1,271 @@
+//! Password reset and email verification, `[auth]`
+//!
+//! Both flows mail a single-use token to the address of a row of the `users`
+//! table. Only the SHA-256 of a token is stored, in `auth_tokens`, together
+//! with its expiry; asking again replaces the user's earlier token.
+//! `auth.reset_password` stores an Argon2 hash of the new password and
+//! `auth.verify_email` sets `email_verified_at`, which an email change clears.
+//! `auth.request_password_reset` answers the same for known and unknown
+//! addresses, so it cannot be used to find out who has an account.
+
+pub mod mailer;
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+use thiserror::Error;
+use tracing::{info, warn};
+use crate::infrastructure::commands::command_message;
+use crate::infrastructure::database::database;
+use crate::infrastructure::file_transfer::sha256_hex;
+use crate::model::core::{AppConfig, AuthTokenPurpose, Database};
+use mailer::{Email, MailError};
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Unknown user: {0}")]
+    UnknownUser(i64),
+    #[error("The token is invalid, expired or already used")]
+    InvalidToken,
+    #[error("Passwords must be at least {0} characters long")]
+    WeakPassword(usize),
+    #[error("Cannot hash the password: {0}")]
+    Hash(String),
+    #[error(transparent)]
+    Mail(#[from] MailError),
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl From<crate::error_handling::AppError> for AuthError {
+    fn from(e: crate::error_handling::AppError) -> Self {
+        AuthError::Database(e.message)
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for AuthError {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        AuthError::Database(e.to_string())
+    }
+}
+
+/// A new token: 256 random bits as hex
+pub(crate) fn generate_token() -> String {
+    format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+/// What `auth_tokens` and `sessions` store in place of the token
+pub(crate) fn token_hash(token: &str) -> String {
+    sha256_hex(token.trim().as_bytes())
+}
+
+/// An Argon2id hash of `password` in PHC string format
+pub fn hash_password(password: &str) -> Result<String, AuthError> {
+    let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes()).map_err(|e| AuthError::Hash(e.to_string()))?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AuthError::Hash(e.to_string()))
+}
+
+/// Whether `password` matches a hash from `hash_password`
+#[allow(dead_code)]
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthPolicy {
+    pub reset_ttl: Duration,
+    pub verification_ttl: Duration,
+    pub min_password_length: usize,
+    pub reset_link: Option<String>,
+    pub verification_link: Option<String>,
+}
+
+impl AuthPolicy {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            reset_ttl: Duration::minutes(config.get_auth_reset_token_ttl_mins() as i64),
+            verification_ttl: Duration::hours(config.get_auth_verification_token_ttl_hours() as i64),
+            min_password_length: config.get_auth_min_password_length(),
+            reset_link: config.get_auth_reset_link().map(String::from),
+            verification_link: config.get_auth_verification_link().map(String::from),
+        }
+    }
+
+    /// The link template with the token filled in, or the bare token
+    fn token_text(link: Option<&str>, token: &str) -> String {
+        match link {
+            Some(link) => link.replace("{token}", token),
+            None => format!("Token: {}", token),
+        }
+    }
+
+    /// Store a reset token for the user with `email` and return the mail carrying it;
+    /// `None` when no user has that address
+    pub fn request_password_reset(&self, db: &Database, email: &str, now: DateTime<Utc>) -> Result<Option<Email>, AuthError> {
+        let Some(user) = db.get_user_by_email(email)? else {
+            return Ok(None);
+        };
+        let user_id = user["id"].as_i64().unwrap_or_default();
+        let token = generate_token();
+        db.insert_auth_token(&token_hash(&token), user_id, AuthTokenPurpose::PasswordReset, &now, &(now + self.reset_ttl))?;
+        info!("Issued a password reset token for user {}", user_id);
+        Ok(Some(Email {
+            to: user["email"].as_str().unwrap_or_default().to_string(),
+            subject: "Reset your password".to_string(),
+            body: format!(
+                "Hello {},\n\nUse this to choose a new password within {} minutes:\n\n{}\n\nIf you did not ask for a reset, ignore this mail.\n",
+                user["name"].as_str().unwrap_or_default(),
+                self.reset_ttl.num_minutes(),
+                Self::token_text(self.reset_link.as_deref(), &token),
+            ),
+        }))
+    }
+
+    /// Use a reset token to replace its user's password; returns the user id
+    pub fn reset_password(&self, db: &Database, token: &str, password: &str, now: DateTime<Utc>) -> Result<i64, AuthError> {
+        if password.chars().count() < self.min_password_length {
+            return Err(AuthError::WeakPassword(self.min_password_length));
+        }
+        let password_hash = hash_password(password)?;
+        let user_id = db
+            .consume_auth_token(&token_hash(token), AuthTokenPurpose::PasswordReset, &now)?
+            .ok_or(AuthError::InvalidToken)?;
+        if !db.set_password_hash(user_id, &password_hash)? {
+            return Err(AuthError::UnknownUser(user_id));
+        }
+        info!("Reset the password of user {}", user_id);
+        Ok(user_id)
+    }
+
+    /// Store a verification token for user `user_id` and return the mail carrying it
+    pub fn request_email_verification(&self, db: &Database, user_id: i64, now: DateTime<Utc>) -> Result<Email, AuthError> {
+        let user = db.get_user(user_id)?.ok_or(AuthError::UnknownUser(user_id))?;
+        let token = generate_token();
+        db.insert_auth_token(&token_hash(&token), user_id, AuthTokenPurpose::EmailVerification, &now, &(now + self.verification_ttl))?;
+        info!("Issued an email verification token for user {}", user_id);
+        Ok(Email {
+            to: user["email"].as_str().unwrap_or_default().to_string(),
+            subject: "Verify your email address".to_string(),
+            body: format!(
+                "Hello {},\n\nUse this within {} hours to confirm this is your address:\n\n{}\n",
+                user["name"].as_str().unwrap_or_default(),
+                self.verification_ttl.num_hours(),
+                Self::token_text(self.verification_link.as_deref(), &token),
+            ),
+        })
+    }
+
+    /// Use a verification token to mark its user's email verified; returns the user id
+    pub fn verify_email(&self, db: &Database, token: &str, now: DateTime<Utc>) -> Result<i64, AuthError> {
+        let user_id = db
+            .consume_auth_token(&token_hash(token), AuthTokenPurpose::EmailVerification, &now)?
+            .ok_or(AuthError::InvalidToken)?;
+        if !db.set_email_verified(user_id, &now)? {
+            return Err(AuthError::UnknownUser(user_id));
+        }
+        info!("Verified the email address of user {}", user_id);
+        Ok(user_id)
+    }
+}
+
+async fn send(email: Email) -> Result<(), AuthError> {
+    tokio::task::spawn_blocking(move || mailer::global().send(&email))
+        .await
+        .map_err(|e| MailError::Transport(e.to_string()))??;
+    Ok(())
+}
+
+async fn run(name: &str, payload: &Value) -> Result<Value, AuthError> {
+    let policy = AuthPolicy::from_config(AppConfig::global());
+    let text = |key: &str| payload.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+    let now = Utc::now();
+    match name {
+        "auth.request_password_reset" => {
+            if let Some(email) = policy.request_password_reset(&*database()?, &text("email"), now)? {
+                // Sent in the background so unknown addresses answer just as fast
+                tokio::spawn(async move {
+                    if let Err(e) = send(email).await {
+                        warn!("Failed to send a password reset mail: {}", e);
+                    }
+                });
+            }
+            Ok(serde_json::json!({ "success": true }))
+        }
+        "auth.reset_password" => {
+            let user_id = policy.reset_password(&*database()?, &text("token"), &text("password"), now)?;
+            Ok(serde_json::json!({ "success": true, "user_id": user_id }))
+        }
+        "auth.request_email_verification" => {
+            let user_id = payload.get("user_id").and_then(Value::as_i64).unwrap_or_default();
+            let email = policy.request_email_verification(&*database()?, user_id, now)?;
+            send(email).await?;
+            Ok(serde_json::json!({ "success": true }))
+        }
+        "auth.verify_email" => {
+            let user_id = policy.verify_email(&*database()?, &text("token"), now)?;
+            Ok(serde_json::json!({ "success": true, "user_id": user_id }))
+        }
+        other => Ok(serde_json::json!({ "success": false, "error": format!("Unknown command: {}", other) })),
+    }
+}
+
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    command_message(run(name, payload).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The token a mail carries on its `Token:` line
+    fn mailed_token(email: &Email) -> String {
+        email.body.lines().find_map(|line| line.strip_prefix("Token: ")).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_reset_and_verification_tokens() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        db.insert_sample_data().unwrap();
+        let user_id = 2;
+        let policy = AuthPolicy {
+            reset_ttl: Duration::minutes(30),
+            verification_ttl: Duration::hours(1),
+            min_password_length: 10,
+            reset_link: None,
+            verification_link: Some("https://example.com/verify?token={token}".to_string()),
+        };
+        let now = Utc::now();
+
+        assert!(policy.request_password_reset(&db, "nobody@example.com", now).unwrap().is_none());
+        let first = mailed_token(&policy.request_password_reset(&db, "JANE@example.com", now).unwrap().unwrap());
+        let mail = policy.request_password_reset(&db, "jane@example.com", now).unwrap().unwrap();
+        assert_eq!(mail.to, "jane@example.com");
+        let token = mailed_token(&mail);
+
+        // Asking again replaces the earlier token; short passwords are refused
+        assert!(matches!(policy.reset_password(&db, &first, "long enough pw", now), Err(AuthError::InvalidToken)));
+        assert!(matches!(policy.reset_password(&db, &token, "short", now), Err(AuthError::WeakPassword(10))));
+        assert!(matches!(
+            policy.reset_password(&db, &token, "long enough pw", now + Duration::hours(1)),
+            Err(AuthError::InvalidToken)
+        ));
+        assert_eq!(policy.reset_password(&db, &token, "long enough pw", now).unwrap(), user_id);
+        assert!(matches!(policy.reset_password(&db, &token, "another password", now), Err(AuthError::InvalidToken)));
+
+        // Verification tokens only verify, through the configured link
+        let mail = policy.request_email_verification(&db, user_id, now).unwrap();
+        let token = mail.body.split("?token=").nth(1).unwrap().lines().next().unwrap().to_string();
+        assert!(matches!(policy.reset_password(&db, &token, "long enough pw", now), Err(AuthError::InvalidToken)));
+        assert_eq!(policy.verify_email(&db, &token, now).unwrap(), user_id);
+        assert!(matches!(policy.request_email_verification(&db, 999, now), Err(AuthError::UnknownUser(999))));
+
+        let hash = hash_password("long enough pw").unwrap();
+        assert!(verify_password("long enough pw", &hash));
+        assert!(!verify_password("wrong password", &hash));
+    }
+}
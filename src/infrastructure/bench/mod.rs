@@ -0,0 +1,91 @@
+//! Quick in-process micro-benchmarks for `devtools.bench`
+//!
+//! Measures what the criterion benches in `benches/` cannot reach from
+//! outside the binary: emit throughput of a private `EventBus` with one
+//! subscriber and one listener, like the app's own bus. Next to it the encode
+//! time and size of a sample `get_users` response in each serialization
+//! format built in. Runs on the calling thread and takes a few hundred
+//! milliseconds with the defaults, so the numbers are for spotting
+//! regressions on the same machine, not for comparing machines.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use utoipa::ToSchema;
+use crate::infrastructure::event_bus::{Event, EventBus};
+use crate::infrastructure::serialization::serialization::{sample_users_message, FormatComparison, SerializationEngine};
+
+const DEFAULT_EVENTS: u64 = 10_000;
+const MAX_EVENTS: u64 = 1_000_000;
+const DEFAULT_USERS: usize = 100;
+const MAX_USERS: usize = 10_000;
+const DEFAULT_ENCODES: u32 = 200;
+const MAX_ENCODES: u32 = 100_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EventThroughput {
+    pub events: u64,
+    /// Calls of the subscriber, equal to `events` unless one failed to emit
+    pub delivered: u64,
+    pub elapsed_ms: f64,
+    pub events_per_sec: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BenchReport {
+    pub event_emit: EventThroughput,
+    /// Users in the sample `get_users` response that was encoded
+    pub users: usize,
+    pub formats: FormatComparison,
+}
+
+/// Emit `events` events on a bus of its own
+pub fn event_throughput(events: u64) -> EventThroughput {
+    let bus = EventBus::new();
+    let delivered = Arc::new(AtomicU64::new(0));
+    let counter = Arc::clone(&delivered);
+    let subscribed = bus.subscribe("bench.tick", move |_| {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    });
+    if subscribed.is_err() {
+        return EventThroughput { events: 0, delivered: 0, elapsed_ms: 0.0, events_per_sec: 0.0 };
+    }
+    // Never read: the channel drops what it cannot hold, as for a slow client
+    let _listener = futures::executor::block_on(bus.listen());
+
+    let started = Instant::now();
+    futures::executor::block_on(async {
+        for sequence in 0..events {
+            let event = Event::new("bench.tick".to_string(), serde_json::json!({ "sequence": sequence }), "backend".to_string());
+            let _ = bus.emit(event).await;
+        }
+    });
+    let elapsed = started.elapsed().as_secs_f64();
+    EventThroughput {
+        events,
+        delivered: delivered.load(Ordering::Relaxed),
+        elapsed_ms: (elapsed * 10_000.0).round() / 10.0,
+        events_per_sec: if elapsed > 0.0 { (events as f64 / elapsed).round() } else { 0.0 },
+    }
+}
+
+/// `devtools.bench`: optional `events`, `users` and `encodes` counts
+pub fn run(args: &serde_json::Value) -> BenchReport {
+    let events = args.get("events").and_then(|v| v.as_u64()).unwrap_or(DEFAULT_EVENTS).min(MAX_EVENTS);
+    let users = args
+        .get("users")
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_USERS, |users| (users as usize).min(MAX_USERS));
+    let encodes = args
+        .get("encodes")
+        .and_then(|v| v.as_u64())
+        .map_or(DEFAULT_ENCODES, |encodes| encodes.min(MAX_ENCODES as u64) as u32);
+
+    BenchReport {
+        event_emit: event_throughput(events),
+        users,
+        formats: SerializationEngine::measure_formats(&sample_users_message(users), encodes),
+    }
+}
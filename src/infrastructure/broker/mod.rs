@@ -0,0 +1,416 @@
+//! Bridge to an external message broker, NATS or MQTT (`broker` feature)
+//!
+//! Events named in `[broker] publish` are sent to the broker as JSON
+//! (`id`, `name`, `payload`, `origin`) on `<prefix>.<name>` for NATS or
+//! `<prefix>/<name with / for .>` for MQTT. Messages arriving on the
+//! `subscribe` subjects are emitted on the EventBus with source `broker`:
+//! envelopes keep their name and id, other messages are named after their
+//! subject without the prefix and carry their JSON, or text, as the payload.
+//! Events from the broker are never sent back, and our own messages coming
+//! round again are dropped by their `origin`.
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info, warn};
+use crate::infrastructure::event_bus::{event_matches, Event, EventBus};
+use crate::infrastructure::secrets::SecretsManager;
+use crate::model::core::AppConfig;
+
+/// `source` of events that came from the broker
+const SOURCE: &str = "broker";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BrokerKind {
+    Nats,
+    Mqtt,
+}
+
+impl BrokerKind {
+    pub fn parse(kind: &str) -> Result<Self, String> {
+        match kind {
+            "nats" => Ok(Self::Nats),
+            "mqtt" => Ok(Self::Mqtt),
+            other => Err(format!("Unknown broker kind '{}', expected nats or mqtt", other)),
+        }
+    }
+
+    fn separator(self) -> char {
+        match self {
+            Self::Nats => '.',
+            Self::Mqtt => '/',
+        }
+    }
+}
+
+/// Which events go where
+#[derive(Debug, Clone)]
+pub struct Routes {
+    pub kind: BrokerKind,
+    pub prefix: String,
+    pub publish: Vec<String>,
+}
+
+impl Routes {
+    /// `name` is listed in `publish`, exactly or under a `prefix.*` pattern
+    pub fn publishes(&self, name: &str) -> bool {
+        self.publish.iter().any(|pattern| event_matches(pattern, name))
+    }
+
+    /// The subject event `name` is published on
+    pub fn subject(&self, name: &str) -> String {
+        let separator = self.kind.separator();
+        let name = name.replace('.', &separator.to_string());
+        match self.prefix.is_empty() {
+            true => name,
+            false => format!("{}{}{}", self.prefix, separator, name),
+        }
+    }
+
+    /// The event name of a message on `subject`
+    pub fn event_name(&self, subject: &str) -> String {
+        let name = subject
+            .strip_prefix(self.prefix.as_str())
+            .filter(|_| !self.prefix.is_empty())
+            .and_then(|rest| rest.strip_prefix(self.kind.separator()))
+            .unwrap_or(subject);
+        name.replace('/', ".")
+    }
+}
+
+/// What travels over the broker
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    id: String,
+    name: String,
+    payload: Arc<Value>,
+    /// Bridge that sent it
+    origin: String,
+}
+
+/// The event to emit for a message, `None` for our own
+fn incoming_event(routes: &Routes, origin: &str, subject: &str, body: &[u8]) -> Option<Event> {
+    if let Ok(envelope) = serde_json::from_slice::<Envelope>(body) {
+        if envelope.origin == origin {
+            return None;
+        }
+        return Some(Event {
+            id: envelope.id,
+            name: envelope.name,
+            payload: envelope.payload,
+            source: SOURCE.to_string(),
+        });
+    }
+    let payload = serde_json::from_slice(body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()));
+    Some(Event::new(routes.event_name(subject), payload, SOURCE.to_string()))
+}
+
+enum Connection {
+    Nats(async_nats::Client),
+    Mqtt(rumqttc::AsyncClient),
+}
+
+impl Connection {
+    async fn publish(&self, subject: String, body: Vec<u8>) -> Result<(), String> {
+        match self {
+            Self::Nats(client) => client.publish(subject, body.into()).await.map_err(|e| e.to_string()),
+            // Fails rather than waits while the request queue is full during a reconnect
+            Self::Mqtt(client) => client
+                .try_publish(subject, rumqttc::QoS::AtLeastOnce, false, body)
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// `host` and `port` of an `mqtt://` or `tcp://` URL
+fn mqtt_address(url: &str) -> Result<(String, u16), String> {
+    let address = url
+        .strip_prefix("mqtt://")
+        .or_else(|| url.strip_prefix("tcp://"))
+        .unwrap_or(url)
+        .trim_end_matches('/');
+    match address.rsplit_once(':') {
+        Some((host, port)) => Ok((
+            host.to_string(),
+            port.parse().map_err(|_| format!("Invalid port in broker url {}", url))?,
+        )),
+        None => Ok((address.to_string(), 1883)),
+    }
+}
+
+pub struct Bridge {
+    origin: String,
+    routes: OnceLock<Routes>,
+    url: OnceLock<String>,
+    subscribe: OnceLock<Vec<String>>,
+    connected: AtomicBool,
+    published: AtomicU64,
+    received: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl Bridge {
+    fn new() -> Self {
+        Self {
+            origin: uuid::Uuid::new_v4().simple().to_string(),
+            routes: OnceLock::new(),
+            url: OnceLock::new(),
+            subscribe: OnceLock::new(),
+            connected: AtomicBool::new(false),
+            published: AtomicU64::new(0),
+            received: AtomicU64::new(0),
+            last_error: Mutex::new(None),
+        }
+    }
+
+    fn fail(&self, message: String) {
+        warn!("[broker] {}", message);
+        if let Ok(mut last_error) = self.last_error.lock() {
+            *last_error = Some(message);
+        }
+    }
+
+    async fn inject(&self, subject: &str, body: &[u8]) {
+        let Some(routes) = self.routes.get() else {
+            return;
+        };
+        let Some(event) = incoming_event(routes, &self.origin, subject, body) else {
+            return;
+        };
+        self.received.fetch_add(1, Ordering::Relaxed);
+        debug!("[broker] {} from {}", event.name, subject);
+        if let Err(e) = EventBus::global().emit(event).await {
+            self.fail(format!("Cannot emit a message from {}: {}", subject, e));
+        }
+    }
+
+    async fn connect(&'static self, config: &AppConfig, routes: &Routes) -> Result<Connection, String> {
+        let url = config.get_broker_url();
+        let password = config
+            .get_broker_password()
+            .map(|password| SecretsManager::global().resolve(password))
+            .transpose()
+            .map_err(|e| format!("Cannot read the broker password: {}", e))?;
+        let subscribe = config.get_broker_subscribe();
+
+        match routes.kind {
+            BrokerKind::Nats => {
+                let mut options = async_nats::ConnectOptions::new()
+                    .name(config.get_app_name())
+                    .retry_on_initial_connect()
+                    .event_callback(move |event| async move {
+                        match event {
+                            async_nats::Event::Connected => self.connected.store(true, Ordering::Relaxed),
+                            async_nats::Event::Disconnected | async_nats::Event::Closed => {
+                                self.connected.store(false, Ordering::Relaxed)
+                            }
+                            other => self.fail(format!("NATS: {}", other)),
+                        }
+                    });
+                if let (Some(username), Some(password)) = (config.get_broker_username(), password) {
+                    options = options.user_and_password(username.to_string(), password);
+                }
+                let client = options.connect(url).await.map_err(|e| format!("Cannot connect to {}: {}", url, e))?;
+
+                let mut subscribers = Vec::new();
+                for subject in subscribe {
+                    let subscriber = client
+                        .subscribe(subject.clone())
+                        .await
+                        .map_err(|e| format!("Cannot subscribe to {}: {}", subject, e))?;
+                    subscribers.push(subscriber);
+                }
+                tokio::spawn(async move {
+                    let mut messages = futures::stream::select_all(subscribers);
+                    while let Some(message) = messages.next().await {
+                        self.inject(message.subject.as_str(), &message.payload).await;
+                    }
+                });
+                Ok(Connection::Nats(client))
+            }
+            BrokerKind::Mqtt => {
+                let (host, port) = mqtt_address(url)?;
+                let client_id = config
+                    .get_broker_client_id()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("rustwebui-{}", &self.origin[..8]));
+                let mut options = rumqttc::MqttOptions::new(client_id, host, port);
+                options.set_keep_alive(std::time::Duration::from_secs(30));
+                if let (Some(username), Some(password)) = (config.get_broker_username(), password) {
+                    options.set_credentials(username, password);
+                }
+                let (client, mut event_loop) = rumqttc::AsyncClient::new(options, 64);
+
+                let subscriber = client.clone();
+                tokio::spawn(async move {
+                    loop {
+                        match event_loop.poll().await {
+                            // Subscriptions do not outlive a clean session, so renew them on every connect
+                            Ok(rumqttc::Event::Incoming(rumqttc::Packet::ConnAck(_))) => {
+                                self.connected.store(true, Ordering::Relaxed);
+                                for topic in &subscribe {
+                                    if let Err(e) = subscriber.subscribe(topic.clone(), rumqttc::QoS::AtLeastOnce).await {
+                                        self.fail(format!("Cannot subscribe to {}: {}", topic, e));
+                                    }
+                                }
+                            }
+                            Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(publish))) => {
+                                self.inject(&publish.topic, &publish.payload).await;
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                // The next poll reconnects
+                                if self.connected.swap(false, Ordering::Relaxed) {
+                                    self.fail(format!("MQTT connection lost: {}", e));
+                                }
+                                tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                            }
+                        }
+                    }
+                });
+                Ok(Connection::Mqtt(client))
+            }
+        }
+    }
+
+    /// Connect and forward events both ways until the process ends
+    async fn run(&'static self, config: &AppConfig) -> Result<(), String> {
+        let routes = Routes {
+            kind: BrokerKind::parse(config.get_broker_kind())?,
+            prefix: config.get_broker_prefix().to_string(),
+            publish: config.get_broker_publish(),
+        };
+        let _ = self.routes.set(routes.clone());
+        let _ = self.url.set(config.get_broker_url().to_string());
+        let _ = self.subscribe.set(config.get_broker_subscribe());
+
+        let mut events = EventBus::global().listen().await;
+        let connection = self.connect(config, &routes).await?;
+        info!("Bridging events with {} ({:?})", config.get_broker_url(), routes.kind);
+
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    self.fail(format!("{} events were not sent to the broker", missed));
+                    continue;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+            if event.source == SOURCE || !routes.publishes(&event.name) {
+                continue;
+            }
+            let envelope = Envelope {
+                id: event.id,
+                name: event.name,
+                payload: event.payload,
+                origin: self.origin.clone(),
+            };
+            let body = serde_json::to_vec(&envelope).map_err(|e| e.to_string())?;
+            match connection.publish(routes.subject(&envelope.name), body).await {
+                Ok(()) => {
+                    self.published.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) => self.fail(format!("Cannot publish {}: {}", envelope.name, e)),
+            }
+        }
+    }
+
+    pub fn status(&self) -> Value {
+        serde_json::json!({
+            "success": true,
+            "running": self.routes.get().is_some(),
+            "kind": self.routes.get().map(|routes| routes.kind),
+            "url": self.url.get(),
+            "connected": self.connected.load(Ordering::Relaxed),
+            "publish": self.routes.get().map(|routes| &routes.publish),
+            "subscribe": self.subscribe.get(),
+            "published": self.published.load(Ordering::Relaxed),
+            "received": self.received.load(Ordering::Relaxed),
+            "last_error": self.last_error.lock().ok().and_then(|error| error.clone()),
+        })
+    }
+}
+
+pub fn bridge() -> &'static Bridge {
+    static BRIDGE: OnceLock<Bridge> = OnceLock::new();
+    BRIDGE.get_or_init(Bridge::new)
+}
+
+/// Start bridging in the background
+pub fn start(config: &'static AppConfig) {
+    tokio::spawn(async move {
+        if let Err(e) = bridge().run(config).await {
+            bridge().fail(e);
+        }
+    });
+}
+
+/// Handle `broker.*` commands from the frontend
+pub fn handle_command(name: &str, _payload: &Value) -> Value {
+    match name {
+        "broker.status" => bridge().status(),
+        _ => serde_json::json!({
+            "success": false,
+            "error": format!("Unknown broker command: {}", name)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes(kind: BrokerKind) -> Routes {
+        Routes {
+            kind,
+            prefix: "app".to_string(),
+            publish: vec!["data.changed".to_string(), "counter.*".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_routes_map_events_to_subjects() {
+        let nats = routes(BrokerKind::Nats);
+        assert!(nats.publishes("data.changed"));
+        assert!(nats.publishes("counter.changed"));
+        assert!(!nats.publishes("data.changed.more"));
+        assert!(!nats.publishes("system.metrics"));
+        assert_eq!(nats.subject("data.changed"), "app.data.changed");
+        assert_eq!(nats.event_name("app.data.changed"), "data.changed");
+        assert_eq!(nats.event_name("sensors.temperature"), "sensors.temperature");
+        assert_eq!(nats.event_name("application.started"), "application.started");
+
+        let mqtt = routes(BrokerKind::Mqtt);
+        assert_eq!(mqtt.subject("data.changed"), "app/data/changed");
+        assert_eq!(mqtt.event_name("app/sensors/temperature"), "sensors.temperature");
+        assert!(BrokerKind::parse("kafka").is_err());
+    }
+
+    #[test]
+    fn test_incoming_messages_become_events() {
+        let routes = routes(BrokerKind::Mqtt);
+        let envelope = br#"{"id":"1","name":"data.changed","payload":{"table":"users"},"origin":"other"}"#;
+        let event = incoming_event(&routes, "mine", "app/data/changed", envelope).unwrap();
+        assert_eq!((event.id.as_str(), event.name.as_str(), event.source.as_str()), ("1", "data.changed", "broker"));
+        assert!(incoming_event(&routes, "other", "app/data/changed", envelope).is_none());
+
+        let reading = incoming_event(&routes, "mine", "app/sensors/temperature", br#"{"celsius":21.5}"#).unwrap();
+        assert_eq!(reading.name, "sensors.temperature");
+        assert_eq!(reading.payload["celsius"], 21.5);
+        let text = incoming_event(&routes, "mine", "alerts", b"door open").unwrap();
+        assert_eq!((text.name.as_str(), text.payload.as_str()), ("alerts", Some("door open")));
+    }
+
+    #[test]
+    fn test_mqtt_address() {
+        assert_eq!(mqtt_address("mqtt://broker.local:8883").unwrap(), ("broker.local".to_string(), 8883));
+        assert_eq!(mqtt_address("tcp://10.0.0.5").unwrap(), ("10.0.0.5".to_string(), 1883));
+        assert!(mqtt_address("mqtt://broker:port").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+//! Read-through cache for hot queries, `[cache]`
+//!
+//! `get_users` and `get_db_stats` are answered from memory while their entry
+//! is younger than `ttl_secs`. Every `data.changed` on the EventBus drops all
+//! entries before the event reaches any client, so a client refreshing on that
+//! event always reads the new rows; the TTL only bounds how long a change made
+//! outside the app (another process on the same file) stays unseen. Hits,
+//! misses and invalidations are in `/api/devtools/cache` and the devtools
+//! metrics.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, error};
+use utoipa::ToSchema;
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::model::core::AppConfig;
+
+/// Entry of the `get_users` command
+pub const USERS: &str = "get_users";
+
+/// Entry of the `get_db_stats` command
+pub const DB_STATS: &str = "get_db_stats";
+
+/// Counters exposed through devtools
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CacheStats {
+    pub enabled: bool,
+    pub ttl_secs: u64,
+    /// Keys currently cached
+    pub entries: Vec<String>,
+    pub hits: u64,
+    pub misses: u64,
+    /// Times the entries were dropped by `data.changed`
+    pub invalidations: u64,
+}
+
+pub struct QueryCache {
+    enabled: bool,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, Value)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+impl QueryCache {
+    pub fn new(enabled: bool, ttl: Duration) -> Self {
+        Self {
+            enabled,
+            ttl,
+            entries: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            invalidations: AtomicU64::new(0),
+        }
+    }
+
+    pub fn global() -> &'static QueryCache {
+        static CACHE: OnceLock<QueryCache> = OnceLock::new();
+        CACHE.get_or_init(|| {
+            let config = AppConfig::global();
+            QueryCache::new(config.is_cache_enabled(), Duration::from_secs(config.get_cache_ttl_secs()))
+        })
+    }
+
+    fn cached(&self, key: &str) -> Option<Value> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .get(key)
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// The value of `key`, from memory if it is fresh, else from `load`.
+    /// Failures are returned as they are and not cached.
+    pub fn get_or_load<E>(&self, key: &str, load: impl FnOnce() -> Result<Value, E>) -> Result<Value, E> {
+        if !self.enabled {
+            return load();
+        }
+        if let Some(value) = self.cached(key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(value);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = load()?;
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(key.to_string(), (Instant::now(), value.clone()));
+        }
+        Ok(value)
+    }
+
+    /// Drop every entry
+    pub fn invalidate(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            if !entries.is_empty() {
+                debug!("Dropping {} cached queries", entries.len());
+            }
+            entries.clear();
+        }
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let mut entries: Vec<String> = self
+            .entries
+            .lock()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|(_, (stored_at, _))| stored_at.elapsed() < self.ttl)
+                    .map(|(key, _)| key.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        entries.sort();
+        CacheStats {
+            enabled: self.enabled,
+            ttl_secs: self.ttl.as_secs(),
+            entries,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            invalidations: self.invalidations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Drop the cached queries on every `data.changed`
+pub fn start() {
+    let cache = QueryCache::global();
+    if !cache.enabled {
+        return;
+    }
+    let subscribed = EventBus::global().subscribe(&AppEventType::DataChanged.to_string(), |_| {
+        QueryCache::global().invalidate();
+        Ok(())
+    });
+    if let Err(e) = subscribed {
+        error!("Failed to subscribe the query cache to data changes: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(calls: &mut u32) -> Result<Value, String> {
+        *calls += 1;
+        Ok(serde_json::json!(*calls))
+    }
+
+    #[test]
+    fn test_hits_until_invalidated() {
+        let cache = QueryCache::new(true, Duration::from_secs(60));
+        let mut calls = 0;
+        assert_eq!(cache.get_or_load(USERS, || load(&mut calls)), Ok(serde_json::json!(1)));
+        assert_eq!(cache.get_or_load(USERS, || load(&mut calls)), Ok(serde_json::json!(1)));
+        assert_eq!(cache.get_or_load::<String>(DB_STATS, || Err("locked".to_string())), Err("locked".to_string()));
+        assert_eq!(cache.stats().entries, vec![USERS.to_string()]);
+
+        cache.invalidate();
+        assert_eq!(cache.get_or_load(USERS, || load(&mut calls)), Ok(serde_json::json!(2)));
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.invalidations), (1, 3, 1));
+    }
+
+    #[test]
+    fn test_expired_and_disabled_caches_load_every_time() {
+        let mut calls = 0;
+        let expired = QueryCache::new(true, Duration::ZERO);
+        expired.get_or_load(USERS, || load(&mut calls)).unwrap();
+        expired.get_or_load(USERS, || load(&mut calls)).unwrap();
+        assert!(expired.stats().entries.is_empty());
+
+        let disabled = QueryCache::new(false, Duration::from_secs(60));
+        disabled.get_or_load(USERS, || load(&mut calls)).unwrap();
+        assert_eq!(disabled.get_or_load(USERS, || load(&mut calls)), Ok(serde_json::json!(4)));
+        assert_eq!(disabled.stats().misses, 0);
+    }
+}
@@ -0,0 +1,289 @@
+//! Screenshots of a window or a screen (`capture` feature)
+//!
+//! Nothing is captured without the user's consent. `capture.window { title? }`
+//! and `capture.screen { monitor? }` only ask: they pick the target, emit
+//! `capture.requested` with a `request_id`, the `kind` and the `target`, and
+//! answer with the same. The frontend asks the user and answers with
+//! `capture.confirm { request_id, allow }`; allowing captures the target,
+//! saves a PNG in `[capture] dir` and returns its `path`, `width`, `height`
+//! and a PNG `thumbnail` data URL. Either way `capture.completed` tells every
+//! client the outcome. A request not confirmed within `confirm_timeout_secs`
+//! expires; each is confirmed at most once.
+//!
+//! `capture.window` takes the first window whose title contains `title`
+//! (the app's own window title by default), `capture.screen` the monitor
+//! named `monitor` (the primary one by default).
+
+use base64::Engine;
+use image::RgbaImage;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+use xcap::{Monitor, Window};
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+use crate::infrastructure::commands::command_result;
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::model::core::AppConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Window,
+    Screen,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Window => "window",
+            Kind::Screen => "screen",
+        }
+    }
+}
+
+/// What a request captures: a window by its title or a monitor by its name
+#[derive(Debug, Clone, PartialEq)]
+struct Target {
+    id: u32,
+    name: String,
+}
+
+struct Request {
+    kind: Kind,
+    target: Target,
+    expires: Instant,
+}
+
+/// Capture requests waiting for the user
+#[derive(Default)]
+struct Requests(HashMap<String, Request>);
+
+impl Requests {
+    fn add(&mut self, kind: Kind, target: Target, now: Instant, ttl: Duration) -> String {
+        self.0.retain(|_, request| request.expires > now);
+        let id = uuid::Uuid::new_v4().to_string();
+        self.0.insert(id.clone(), Request { kind, target, expires: now + ttl });
+        id
+    }
+
+    /// The request `id`, unless it is unknown, expired or was already taken
+    fn take(&mut self, id: &str, now: Instant) -> Option<(Kind, Target)> {
+        self.0
+            .remove(id)
+            .filter(|request| request.expires > now)
+            .map(|request| (request.kind, request.target))
+    }
+}
+
+fn requests() -> MutexGuard<'static, Requests> {
+    static REQUESTS: OnceLock<Mutex<Requests>> = OnceLock::new();
+    REQUESTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn failed(e: impl std::fmt::Display) -> AppError {
+    AppError::new(ErrorCode::CommandFailed, format!("Capture failed: {}", e))
+}
+
+fn find_window(title: &str) -> AppResult<Target> {
+    let wanted = title.to_lowercase();
+    for window in Window::all().map_err(failed)? {
+        let Ok(name) = window.title() else { continue };
+        if name.to_lowercase().contains(&wanted) && !window.is_minimized().unwrap_or(false) {
+            return Ok(Target { id: window.id().map_err(failed)?, name });
+        }
+    }
+    Err(AppError::new(ErrorCode::EntityNotFound, format!("No visible window titled like '{}'", title)).with_context("title", title))
+}
+
+fn find_monitor(name: Option<&str>) -> AppResult<Target> {
+    let monitors = Monitor::all().map_err(failed)?;
+    let found = match name {
+        Some(name) => monitors.into_iter().find(|monitor| monitor.name().is_ok_and(|n| n == name)),
+        None => monitors.into_iter().find(|monitor| monitor.is_primary().unwrap_or(false)),
+    };
+    let monitor = found.ok_or_else(|| {
+        AppError::new(ErrorCode::EntityNotFound, format!("No monitor named '{}'", name.unwrap_or("primary")))
+    })?;
+    Ok(Target { id: monitor.id().map_err(failed)?, name: monitor.name().map_err(failed)? })
+}
+
+fn grab(kind: Kind, target: &Target) -> AppResult<RgbaImage> {
+    let gone = || AppError::new(ErrorCode::EntityNotFound, format!("'{}' is gone", target.name));
+    match kind {
+        Kind::Window => Window::all()
+            .map_err(failed)?
+            .into_iter()
+            .find(|window| window.id().is_ok_and(|id| id == target.id))
+            .ok_or_else(gone)?
+            .capture_image()
+            .map_err(failed),
+        Kind::Screen => Monitor::all()
+            .map_err(failed)?
+            .into_iter()
+            .find(|monitor| monitor.id().is_ok_and(|id| id == target.id))
+            .ok_or_else(gone)?
+            .capture_image()
+            .map_err(failed),
+    }
+}
+
+/// `width` x `height` scaled down to fit in a `size` square, never up
+fn fit(width: u32, height: u32, size: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= size {
+        return (width.max(1), height.max(1));
+    }
+    let scale = |side: u32| ((side as u64 * size as u64 + longest as u64 / 2) / longest as u64).max(1) as u32;
+    (scale(width), scale(height))
+}
+
+/// PNG data URL of `image` fitted in a `size` square
+fn thumbnail(image: &RgbaImage, size: u32) -> AppResult<String> {
+    let (width, height) = fit(image.width(), image.height(), size);
+    let mut png = Vec::new();
+    image::imageops::thumbnail(image, width, height)
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(failed)?;
+    Ok(format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png)))
+}
+
+/// Capture the target and save it in `dir`
+fn save(kind: Kind, target: &Target, dir: &Path, thumbnail_size: u32) -> AppResult<Value> {
+    let image = grab(kind, target)?;
+    std::fs::create_dir_all(dir).map_err(failed)?;
+    let path = dir.join(format!("{}-{}.png", kind.as_str(), chrono::Local::now().format("%Y%m%d-%H%M%S-%3f")));
+    image.save(&path).map_err(failed)?;
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
+    info!("Captured {} to {}", target.name, path.display());
+    Ok(serde_json::json!({
+        "path": path.to_string_lossy(),
+        "width": image.width(),
+        "height": image.height(),
+        "thumbnail": thumbnail(&image, thumbnail_size)?,
+    }))
+}
+
+async fn emit(event: AppEventType, payload: Value) {
+    let name = event.to_string();
+    if let Err(e) = EventBus::global().emit_simple(&name, payload).await {
+        error!("Failed to emit {}: {}", name, e);
+    }
+}
+
+async fn request(kind: Kind, payload: &Value) -> AppResult<Value> {
+    let config = AppConfig::global();
+    let text = |field: &str| payload.get(field).and_then(Value::as_str).filter(|value| !value.is_empty()).map(str::to_string);
+    let target = match kind {
+        Kind::Window => {
+            let title = text("title").unwrap_or_else(|| config.get_window_title().to_string());
+            tokio::task::spawn_blocking(move || find_window(&title)).await.map_err(failed)??
+        }
+        Kind::Screen => {
+            let monitor = text("monitor");
+            tokio::task::spawn_blocking(move || find_monitor(monitor.as_deref())).await.map_err(failed)??
+        }
+    };
+    let ttl = Duration::from_secs(config.get_capture_confirm_timeout_secs());
+    let request_id = requests().add(kind, target.clone(), Instant::now(), ttl);
+    let announced = serde_json::json!({
+        "request_id": request_id,
+        "kind": kind,
+        "target": target.name,
+        "expires_in_secs": ttl.as_secs(),
+    });
+    emit(AppEventType::CaptureRequested, announced.clone()).await;
+    let mut response = announced;
+    response["success"] = Value::Bool(true);
+    Ok(response)
+}
+
+async fn confirm(payload: &Value) -> AppResult<Value> {
+    let request_id = payload.get("request_id").and_then(Value::as_str).unwrap_or_default().to_string();
+    let allow = payload.get("allow").and_then(Value::as_bool).unwrap_or(false);
+    let (kind, target) = requests().take(&request_id, Instant::now()).ok_or_else(|| {
+        AppError::new(ErrorCode::EntityNotFound, "No pending capture request; it may have expired").with_context("request_id", request_id.as_str())
+    })?;
+
+    let mut completed = serde_json::json!({
+        "request_id": request_id,
+        "kind": kind,
+        "target": target.name,
+        "allowed": allow,
+    });
+    if !allow {
+        emit(AppEventType::CaptureCompleted, completed.clone()).await;
+        completed["success"] = Value::Bool(true);
+        return Ok(completed);
+    }
+
+    let config = AppConfig::global();
+    let dir = PathBuf::from(config.get_capture_dir());
+    let size = config.get_capture_thumbnail_size();
+    let saved = tokio::task::spawn_blocking(move || save(kind, &target, &dir, size)).await.map_err(failed)?;
+    match saved {
+        Ok(capture) => {
+            for field in ["path", "width", "height"] {
+                completed[field] = capture[field].clone();
+            }
+            emit(AppEventType::CaptureCompleted, completed.clone()).await;
+            completed["thumbnail"] = capture["thumbnail"].clone();
+            completed["success"] = Value::Bool(true);
+            Ok(completed)
+        }
+        Err(e) => {
+            completed["error"] = Value::String(e.message.clone());
+            emit(AppEventType::CaptureCompleted, completed).await;
+            Err(e)
+        }
+    }
+}
+
+async fn run(name: &str, payload: &Value) -> AppResult<Value> {
+    match name {
+        "capture.window" => request(Kind::Window, payload).await,
+        "capture.screen" => request(Kind::Screen, payload).await,
+        "capture.confirm" => confirm(payload).await,
+        other => Err(AppError::new(ErrorCode::CommandFailed, format!("Unknown command: {}", other))),
+    }
+}
+
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    command_result(run(name, payload).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_are_confirmed_once_before_they_expire() {
+        let mut requests = Requests::default();
+        let now = Instant::now();
+        let target = Target { id: 7, name: "Editor".to_string() };
+        let ttl = Duration::from_secs(60);
+
+        let id = requests.add(Kind::Window, target.clone(), now, ttl);
+        assert_eq!(requests.take(&id, now), Some((Kind::Window, target.clone())));
+        assert_eq!(requests.take(&id, now), None);
+
+        let late = requests.add(Kind::Screen, target.clone(), now, ttl);
+        assert_eq!(requests.take(&late, now + ttl), None);
+
+        // Adding drops the expired ones
+        requests.add(Kind::Screen, target.clone(), now, ttl);
+        requests.add(Kind::Screen, target, now + ttl * 2, ttl);
+        assert_eq!(requests.0.len(), 1);
+
+        assert_eq!(fit(1920, 1080, 320), (320, 180));
+        assert_eq!(fit(600, 2400, 320), (80, 320));
+        assert_eq!(fit(200, 100, 320), (200, 100));
+        assert_eq!(fit(5000, 1, 320), (320, 1));
+    }
+}
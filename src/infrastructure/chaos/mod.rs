@@ -0,0 +1,166 @@
+//! Fault injection for resilience testing, `[chaos]`
+//!
+//! With `[chaos] enabled = true` in a debug build, WebSocket commands are
+//! disturbed at the configured probabilities: their database calls are held
+//! up, incoming messages are dropped as if lost on the way, and commands are
+//! answered with an injected `HandlerError` instead of being run. Each fault
+//! is logged and emitted as `chaos.injected`, so the frontend's timeouts,
+//! reconnection and error messages can be exercised and every failure traced
+//! back to its cause. Release builds never inject anything.
+
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tracing::warn;
+use crate::error_handling::{AppError, ErrorCode};
+use crate::infrastructure::event_bus::EventBus;
+use crate::model::core::AppConfig;
+use crate::viewmodel::protocol;
+
+/// Emitted for every injected fault
+pub const INJECTED: &str = "chaos.injected";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    DbDelay,
+    WsDrop,
+    HandlerError,
+}
+
+/// What to inject and how often; nothing at all by default
+#[derive(Debug, Clone, Default)]
+pub struct ChaosConfig {
+    pub db_delay_probability: f64,
+    pub db_delay: Duration,
+    pub ws_drop_probability: f64,
+    pub handler_error_probability: f64,
+    /// Commands that may fail; empty for every command but `hello`
+    pub commands: Vec<String>,
+}
+
+pub struct Chaos {
+    config: ChaosConfig,
+    /// xorshift64* state, never 0
+    rng: Mutex<u64>,
+}
+
+impl Chaos {
+    pub fn new(config: ChaosConfig, seed: u64) -> Self {
+        Self { config, rng: Mutex::new(seed.max(1)) }
+    }
+
+    pub fn global() -> &'static Chaos {
+        static CHAOS: OnceLock<Chaos> = OnceLock::new();
+        CHAOS.get_or_init(|| {
+            let config = AppConfig::global();
+            if !config.is_chaos_enabled() {
+                return Chaos::new(ChaosConfig::default(), 1);
+            }
+            let chaos = ChaosConfig {
+                db_delay_probability: config.get_chaos_db_delay_probability(),
+                db_delay: Duration::from_millis(config.get_chaos_db_delay_ms()),
+                ws_drop_probability: config.get_chaos_ws_drop_probability(),
+                handler_error_probability: config.get_chaos_handler_error_probability(),
+                commands: config.get_chaos_commands(),
+            };
+            let seed = config
+                .get_chaos_seed()
+                .unwrap_or_else(|| (uuid::Uuid::new_v4().as_u128() >> 64) as u64);
+            warn!("Chaos mode is on (seed {}): {:?}", seed, chaos);
+            Chaos::new(chaos, seed)
+        })
+    }
+
+    /// True with `probability`
+    fn draw(&self, probability: f64) -> bool {
+        if probability <= 0.0 {
+            return false;
+        }
+        let mut state = self.rng.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *state ^= *state >> 12;
+        *state ^= *state << 25;
+        *state ^= *state >> 27;
+        let value = state.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        ((value >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    /// Hold up the calling thread before a database call, as a slow query would
+    pub fn delay_db(&self) {
+        if self.draw(self.config.db_delay_probability) {
+            let delay_ms = self.config.db_delay.as_millis() as u64;
+            report(Fault::DbDelay, None, serde_json::json!({ "delay_ms": delay_ms }));
+            std::thread::sleep(self.config.db_delay);
+        }
+    }
+
+    /// Whether to drop an incoming WebSocket message of `bytes` bytes unread
+    pub fn drop_ws_message(&self, bytes: usize) -> bool {
+        let drop = self.draw(self.config.ws_drop_probability);
+        if drop {
+            report(Fault::WsDrop, None, serde_json::json!({ "bytes": bytes }));
+        }
+        drop
+    }
+
+    /// An error to answer `command` with instead of running it
+    pub fn handler_error(&self, command: &str) -> Option<AppError> {
+        let eligible = if self.config.commands.is_empty() {
+            command != protocol::HELLO
+        } else {
+            self.config.commands.iter().any(|name| name == command)
+        };
+        if !eligible || !self.draw(self.config.handler_error_probability) {
+            return None;
+        }
+        report(Fault::HandlerError, Some(command), serde_json::json!({}));
+        Some(
+            AppError::new(ErrorCode::HandlerError, format!("{} failed: error injected by chaos mode", command))
+                .with_context("command", command)
+                .with_context("chaos", true),
+        )
+    }
+}
+
+fn report(fault: Fault, command: Option<&str>, mut details: serde_json::Value) {
+    warn!("Chaos: injected {:?} {}", fault, command.unwrap_or_default());
+    details["fault"] = serde_json::json!(fault);
+    if let Some(command) = command {
+        details["command"] = serde_json::json!(command);
+    }
+    if let Err(e) = futures::executor::block_on(EventBus::global().emit_simple(INJECTED, details)) {
+        warn!("Failed to emit {}: {}", INJECTED, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draws_follow_the_probabilities() {
+        let chaos = Chaos::new(ChaosConfig::default(), 42);
+        let hits = (0..10_000).filter(|_| chaos.draw(0.25)).count();
+        assert!((2_200..2_800).contains(&hits), "{} hits", hits);
+        assert!((0..1_000).all(|_| !chaos.draw(0.0)));
+        assert!((0..1_000).all(|_| chaos.draw(1.0)));
+
+        // The same seed gives the same run
+        let (a, b) = (Chaos::new(ChaosConfig::default(), 7), Chaos::new(ChaosConfig::default(), 7));
+        assert!((0..100).all(|_| a.draw(0.5) == b.draw(0.5)));
+    }
+
+    #[test]
+    fn test_handler_errors_only_for_eligible_commands() {
+        let always = ChaosConfig { handler_error_probability: 1.0, ..ChaosConfig::default() };
+        let chaos = Chaos::new(always.clone(), 1);
+        let error = chaos.handler_error("get_users").expect("an injected error");
+        assert_eq!(error.code, ErrorCode::HandlerError);
+        assert!(chaos.handler_error(protocol::HELLO).is_none());
+
+        let chaos = Chaos::new(ChaosConfig { commands: vec!["create_user".to_string()], ..always }, 1);
+        assert!(chaos.handler_error("get_users").is_none());
+        assert!(chaos.handler_error("create_user").is_some());
+        assert!(Chaos::new(ChaosConfig::default(), 1).handler_error("get_users").is_none());
+    }
+}
@@ -0,0 +1,116 @@
+//! Clipboard bridge - OS clipboard access for the frontend
+//!
+//! Every read and write is reported on the event bus as a
+//! `clipboard.accessed` audit event (never including the contents).
+
+use arboard::Clipboard;
+use serde_json::Value;
+use std::sync::{Mutex, OnceLock};
+use tracing::{debug, error};
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+
+/// Largest text accepted by `clipboard.write_text`
+const MAX_TEXT_LENGTH: usize = 1024 * 1024;
+
+// The clipboard owner must stay alive on X11/Wayland, otherwise written
+// contents disappear as soon as the handle is dropped.
+static CLIPBOARD: OnceLock<Mutex<Option<Clipboard>>> = OnceLock::new();
+
+fn with_clipboard<T>(f: impl FnOnce(&mut Clipboard) -> Result<T, arboard::Error>) -> Result<T, String> {
+    let mut guard = CLIPBOARD
+        .get_or_init(|| Mutex::new(None))
+        .lock()
+        .map_err(|_| "Clipboard lock poisoned".to_string())?;
+
+    if guard.is_none() {
+        *guard = Some(Clipboard::new().map_err(|e| e.to_string())?);
+    }
+
+    match guard.as_mut() {
+        Some(clipboard) => f(clipboard).map_err(|e| e.to_string()),
+        None => Err("Clipboard unavailable".to_string()),
+    }
+}
+
+/// Read the current clipboard text
+pub fn read_text() -> Result<String, String> {
+    with_clipboard(|clipboard| clipboard.get_text())
+}
+
+/// Replace the clipboard contents with `text`
+pub fn write_text(text: &str) -> Result<(), String> {
+    if text.len() > MAX_TEXT_LENGTH {
+        return Err(format!("Text exceeds maximum clipboard size of {} bytes", MAX_TEXT_LENGTH));
+    }
+    with_clipboard(|clipboard| clipboard.set_text(text))
+}
+
+async fn emit_audit_event(operation: &str, length: usize, error: Option<&str>) {
+    let bus = EventBus::global();
+    if let Err(e) = bus
+        .emit_simple(
+            &AppEventType::ClipboardAccessed.to_string(),
+            serde_json::json!({
+                "operation": operation,
+                "length": length,
+                "success": error.is_none(),
+                "error": error
+            }),
+        )
+        .await
+    {
+        error!("Failed to emit clipboard audit event: {}", e);
+    }
+}
+
+/// Handle `clipboard.*` commands from the frontend
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    match name {
+        "clipboard.read_text" => {
+            let result = tokio::task::spawn_blocking(read_text)
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()));
+
+            let length = result.as_ref().map(|t| t.len()).unwrap_or(0);
+            emit_audit_event("read", length, result.as_ref().err().map(String::as_str)).await;
+
+            match result {
+                Ok(text) => {
+                    debug!("Clipboard read: {} bytes", text.len());
+                    serde_json::json!({ "success": true, "text": text })
+                }
+                Err(e) => serde_json::json!({ "success": false, "error": e }),
+            }
+        }
+        "clipboard.write_text" => {
+            let text = match payload.get("text").and_then(|v| v.as_str()) {
+                Some(text) => text.to_string(),
+                None => {
+                    return serde_json::json!({
+                        "success": false,
+                        "error": "Missing 'text' field"
+                    });
+                }
+            };
+
+            let length = text.len();
+            let result = tokio::task::spawn_blocking(move || write_text(&text))
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()));
+
+            emit_audit_event("write", length, result.as_ref().err().map(String::as_str)).await;
+
+            match result {
+                Ok(()) => {
+                    debug!("Clipboard written: {} bytes", length);
+                    serde_json::json!({ "success": true, "length": length })
+                }
+                Err(e) => serde_json::json!({ "success": false, "error": e }),
+            }
+        }
+        _ => serde_json::json!({
+            "success": false,
+            "error": format!("Unknown clipboard command: {}", name)
+        }),
+    }
+}
@@ -0,0 +1,188 @@
+//! Collaborative editing of shared documents (`collab` feature)
+//!
+//! A shared document is a Yjs CRDT, kept by `yrs` and named by the client.
+//! `collab.sync { document, state_vector? }` returns what the backend holds
+//! that the client's state vector lacks (everything when it sends none) as a
+//! base64 Yjs update, along with the backend's own state vector so the client
+//! can send back what the backend lacks. `collab.update { document, update,
+//! origin? }` merges a client's update, stores it and announces it as
+//! `collab.update` with the same `origin`, so every window and client applies
+//! it and the sender can skip its own. Concurrent edits merge the same way
+//! everywhere, whatever order the updates arrive in.
+//!
+//! Updates are kept in `collab_updates` and applied again in order when a
+//! document is first used after a start. Once a document has more than
+//! `COMPACT_AFTER` of them, they are replaced by one holding its whole state.
+
+use base64::Engine;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use tracing::{debug, error};
+use yrs::updates::decoder::Decode;
+use yrs::updates::encoder::Encode;
+use yrs::{Doc, ReadTxn, StateVector, Transact, Update};
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+use crate::infrastructure::commands::command_result;
+use crate::infrastructure::database::database;
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::model::core::Database;
+
+/// Stored updates of a document before they are compacted into one
+const COMPACT_AFTER: u64 = 500;
+
+/// The documents used since the start
+fn documents() -> MutexGuard<'static, HashMap<String, Doc>> {
+    static DOCUMENTS: OnceLock<Mutex<HashMap<String, Doc>>> = OnceLock::new();
+    DOCUMENTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn invalid(message: impl Into<String>) -> AppError {
+    AppError::new(ErrorCode::ValidationFailed, message)
+}
+
+fn database_error(e: Box<dyn std::error::Error>) -> AppError {
+    AppError::new(ErrorCode::DatabaseError, e.to_string())
+}
+
+fn decode_base64(field: &str, value: &str) -> AppResult<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD
+        .decode(value)
+        .map_err(|e| invalid(format!("{} is not base64: {}", field, e)))
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// Merge the v1-encoded `update` into `doc`
+fn merge(doc: &Doc, update: &[u8]) -> AppResult<()> {
+    let update = Update::decode_v1(update).map_err(|e| invalid(format!("update is not a Yjs update: {}", e)))?;
+    doc.transact_mut()
+        .apply_update(update)
+        .map_err(|e| invalid(format!("update does not apply: {}", e)))
+}
+
+/// What `doc` holds beyond `state_vector` (everything when `None`), and its own state vector
+fn diff(doc: &Doc, state_vector: Option<&[u8]>) -> AppResult<(Vec<u8>, Vec<u8>)> {
+    let remote = match state_vector {
+        Some(bytes) => StateVector::decode_v1(bytes).map_err(|e| invalid(format!("state_vector is not a Yjs state vector: {}", e)))?,
+        None => StateVector::default(),
+    };
+    let txn = doc.transact();
+    Ok((txn.encode_state_as_update_v1(&remote), txn.state_vector().encode_v1()))
+}
+
+/// Run `f` on `name`, loaded from its stored updates on first use
+fn with_document<T>(db: &Database, name: &str, f: impl FnOnce(&Doc) -> AppResult<T>) -> AppResult<T> {
+    let mut documents = documents();
+    if !documents.contains_key(name) {
+        let doc = Doc::new();
+        let updates = db.collab_updates(name).map_err(database_error)?;
+        for update in &updates {
+            merge(&doc, update)?;
+        }
+        debug!("Loaded shared document {} from {} updates", name, updates.len());
+        documents.insert(name.to_string(), doc);
+    }
+    f(&documents[name])
+}
+
+async fn run(name: &str, payload: &Value) -> AppResult<Value> {
+    let db = database()?;
+    let document = payload.get("document").and_then(Value::as_str).unwrap_or_default();
+    match name {
+        "collab.sync" => {
+            let state_vector = payload
+                .get("state_vector")
+                .and_then(Value::as_str)
+                .map(|encoded| decode_base64("state_vector", encoded))
+                .transpose()?;
+            let (update, state_vector) = with_document(&db, document, |doc| diff(doc, state_vector.as_deref()))?;
+            Ok(serde_json::json!({
+                "success": true,
+                "document": document,
+                "update": encode_base64(&update),
+                "state_vector": encode_base64(&state_vector),
+            }))
+        }
+        "collab.update" => {
+            let update = decode_base64("update", payload.get("update").and_then(Value::as_str).unwrap_or_default())?;
+            let state_vector = with_document(&db, document, |doc| {
+                merge(doc, &update)?;
+                // Stored while the document is held, so updates are kept in the order they were merged
+                if db.append_collab_update(document, &update).map_err(database_error)? > COMPACT_AFTER {
+                    let (state, _) = diff(doc, None)?;
+                    db.compact_collab_updates(document, &state).map_err(database_error)?;
+                    debug!("Compacted the updates of shared document {}", document);
+                }
+                Ok(doc.transact().state_vector().encode_v1())
+            })?;
+            let event = serde_json::json!({
+                "document": document,
+                "update": encode_base64(&update),
+                "origin": payload.get("origin").and_then(Value::as_str),
+            });
+            if let Err(e) = EventBus::global().emit_simple(&AppEventType::CollabUpdate.to_string(), event).await {
+                error!("Failed to emit the update of shared document {}: {}", document, e);
+            }
+            Ok(serde_json::json!({ "success": true, "document": document, "state_vector": encode_base64(&state_vector) }))
+        }
+        other => Err(AppError::new(ErrorCode::CommandFailed, format!("Unknown command: {}", other))),
+    }
+}
+
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    command_result(run(name, payload).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yrs::{GetString, Text};
+
+    fn edit(doc: &Doc, index: u32, text: &str) -> Vec<u8> {
+        let notes = doc.get_or_insert_text("notes");
+        let before = doc.transact().state_vector();
+        notes.insert(&mut doc.transact_mut(), index, text);
+        doc.transact().encode_state_as_update_v1(&before)
+    }
+
+    fn text(doc: &Doc) -> String {
+        doc.get_or_insert_text("notes").get_string(&doc.transact())
+    }
+
+    #[test]
+    fn test_concurrent_edits_converge() {
+        let shared = Doc::new();
+        let (first, second) = (Doc::new(), Doc::new());
+        let hello = edit(&first, 0, "hello");
+        merge(&shared, &hello).unwrap();
+        merge(&second, &hello).unwrap();
+
+        // Both edit before seeing the other's change, and reach the backend in either order
+        let world = edit(&first, 5, " world");
+        let bang = edit(&second, 5, "!");
+        merge(&shared, &bang).unwrap();
+        merge(&shared, &world).unwrap();
+        merge(&first, &bang).unwrap();
+        merge(&second, &world).unwrap();
+        assert_eq!(text(&first), text(&shared));
+        assert_eq!(text(&second), text(&shared));
+
+        // A client that has nothing gets everything; one that is current gets nothing new
+        let (everything, state_vector) = diff(&shared, None).unwrap();
+        let late = Doc::new();
+        merge(&late, &everything).unwrap();
+        assert_eq!(text(&late), text(&shared));
+        let (nothing, _) = diff(&shared, Some(&state_vector)).unwrap();
+        merge(&late, &nothing).unwrap();
+        assert_eq!(text(&late), text(&shared));
+
+        assert!(merge(&shared, b"not an update").is_err());
+        assert!(diff(&shared, Some(b"\xff")).is_err());
+    }
+}
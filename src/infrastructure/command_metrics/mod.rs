@@ -0,0 +1,207 @@
+//! Latency and error counts of the command bus, per command
+//!
+//! Every command answered by `handle_function_call` is timed into a
+//! histogram of its name, and counted as an error when the answer has
+//! `success: false`. `CommandStats` with estimated percentiles go out with the
+//! devtools metrics, and `/metrics` renders the histograms in the Prometheus
+//! text format, so slow or failing handlers show up without a profiler.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Upper bounds of the histogram buckets, in microseconds; slower calls land
+/// in one more bucket without a bound
+const BUCKETS_US: [u64; 16] = [
+    100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000, 1_000_000,
+    2_500_000, 5_000_000, 10_000_000,
+];
+
+/// Distinct command names kept; calls of any further name go under `OTHER`
+const MAX_COMMANDS: usize = 256;
+const OTHER: &str = "other";
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS_US.len() + 1],
+    count: u64,
+    errors: u64,
+    sum_us: u64,
+    max_us: u64,
+}
+
+impl Histogram {
+    fn record(&mut self, elapsed_us: u64, failed: bool) {
+        let bucket = BUCKETS_US.iter().position(|&bound| elapsed_us <= bound).unwrap_or(BUCKETS_US.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.errors += u64::from(failed);
+        self.sum_us += elapsed_us;
+        self.max_us = self.max_us.max(elapsed_us);
+    }
+
+    /// Estimated `q` quantile in microseconds, interpolated within its bucket
+    fn quantile_us(&self, q: f64) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let rank = (q * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (index, &in_bucket) in self.buckets.iter().enumerate() {
+            if seen + in_bucket < rank {
+                seen += in_bucket;
+                continue;
+            }
+            let lower = if index == 0 { 0 } else { BUCKETS_US[index - 1] };
+            let upper = BUCKETS_US.get(index).copied().unwrap_or(self.max_us).min(self.max_us);
+            let fraction = (rank - seen) as f64 / in_bucket as f64;
+            return lower as f64 + (upper.saturating_sub(lower)) as f64 * fraction;
+        }
+        self.max_us as f64
+    }
+}
+
+/// One command's calls, as shown by devtools
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CommandStats {
+    pub command: String,
+    pub count: u64,
+    pub errors: u64,
+    /// `errors / count`
+    pub error_rate: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Default)]
+pub struct CommandMetrics {
+    commands: Mutex<BTreeMap<String, Histogram>>,
+}
+
+impl CommandMetrics {
+    pub fn global() -> &'static CommandMetrics {
+        static METRICS: OnceLock<CommandMetrics> = OnceLock::new();
+        METRICS.get_or_init(CommandMetrics::default)
+    }
+
+    pub fn record(&self, command: &str, elapsed: Duration, failed: bool) {
+        let Ok(mut commands) = self.commands.lock() else {
+            return;
+        };
+        let name = if commands.contains_key(command) || commands.len() < MAX_COMMANDS { command } else { OTHER };
+        commands.entry(name.to_string()).or_default().record(elapsed.as_micros() as u64, failed);
+    }
+
+    /// Every command called so far, slowest p95 first
+    pub fn stats(&self) -> Vec<CommandStats> {
+        let Ok(commands) = self.commands.lock() else {
+            return Vec::new();
+        };
+        let mut stats: Vec<CommandStats> = commands
+            .iter()
+            .map(|(command, histogram)| CommandStats {
+                command: command.clone(),
+                count: histogram.count,
+                errors: histogram.errors,
+                error_rate: histogram.errors as f64 / histogram.count.max(1) as f64,
+                mean_ms: histogram.sum_us as f64 / histogram.count.max(1) as f64 / 1000.0,
+                p50_ms: histogram.quantile_us(0.50) / 1000.0,
+                p95_ms: histogram.quantile_us(0.95) / 1000.0,
+                p99_ms: histogram.quantile_us(0.99) / 1000.0,
+                max_ms: histogram.max_us as f64 / 1000.0,
+            })
+            .collect();
+        stats.sort_by(|a, b| b.p95_ms.total_cmp(&a.p95_ms).then_with(|| a.command.cmp(&b.command)));
+        stats
+    }
+
+    /// The histograms in the Prometheus text exposition format
+    pub fn prometheus(&self) -> String {
+        let commands = match self.commands.lock() {
+            Ok(commands) => commands.clone(),
+            Err(_) => BTreeMap::new(),
+        };
+        let mut out = String::new();
+        out.push_str("# HELP command_duration_seconds Time taken to answer a command\n");
+        out.push_str("# TYPE command_duration_seconds histogram\n");
+        for (command, histogram) in &commands {
+            let label = escape_label(command);
+            let mut cumulative = 0;
+            for (index, &in_bucket) in histogram.buckets.iter().enumerate() {
+                cumulative += in_bucket;
+                let le = BUCKETS_US.get(index).map_or_else(|| "+Inf".to_string(), |&bound| seconds(bound));
+                let _ = writeln!(out, "command_duration_seconds_bucket{{command=\"{}\",le=\"{}\"}} {}", label, le, cumulative);
+            }
+            let _ = writeln!(out, "command_duration_seconds_sum{{command=\"{}\"}} {}", label, seconds(histogram.sum_us));
+            let _ = writeln!(out, "command_duration_seconds_count{{command=\"{}\"}} {}", label, histogram.count);
+        }
+        out.push_str("# HELP command_errors_total Commands answered with success: false\n");
+        out.push_str("# TYPE command_errors_total counter\n");
+        for (command, histogram) in &commands {
+            let _ = writeln!(out, "command_errors_total{{command=\"{}\"}} {}", escape_label(command), histogram.errors);
+        }
+        out
+    }
+}
+
+fn seconds(us: u64) -> String {
+    (us as f64 / 1_000_000.0).to_string()
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_and_error_rate() {
+        let metrics = CommandMetrics::default();
+        // 90 fast calls and 10 slow ones, two of which fail
+        for _ in 0..90 {
+            metrics.record("get_users", Duration::from_micros(800), false);
+        }
+        for index in 0..10 {
+            metrics.record("get_users", Duration::from_millis(40), index < 2);
+        }
+        metrics.record("get_db_stats", Duration::from_micros(50), false);
+
+        let stats = metrics.stats();
+        assert_eq!(stats.iter().map(|stats| stats.command.as_str()).collect::<Vec<_>>(), ["get_users", "get_db_stats"]);
+        let users = &stats[0];
+        assert_eq!((users.count, users.errors), (100, 2));
+        assert!((users.error_rate - 0.02).abs() < 1e-9);
+        // Within the bucket of the true value, never above the slowest call
+        assert!((0.5..=1.0).contains(&users.p50_ms), "{}", users.p50_ms);
+        assert!((25.0..=40.0).contains(&users.p95_ms), "{}", users.p95_ms);
+        assert!(users.p99_ms <= users.max_ms && users.max_ms == 40.0);
+        assert!(stats[1].p99_ms <= 0.05);
+    }
+
+    #[test]
+    fn test_prometheus_buckets_are_cumulative() {
+        let metrics = CommandMetrics::default();
+        metrics.record("get_users", Duration::from_micros(300), false);
+        metrics.record("get_users", Duration::from_secs(20), true);
+        metrics.record("say \"hi\"", Duration::from_micros(10), false);
+
+        let text = metrics.prometheus();
+        assert!(text.contains("command_duration_seconds_bucket{command=\"get_users\",le=\"0.0001\"} 0\n"));
+        assert!(text.contains("command_duration_seconds_bucket{command=\"get_users\",le=\"0.0005\"} 1\n"));
+        assert!(text.contains("command_duration_seconds_bucket{command=\"get_users\",le=\"10\"} 1\n"));
+        assert!(text.contains("command_duration_seconds_bucket{command=\"get_users\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("command_duration_seconds_count{command=\"get_users\"} 2\n"));
+        assert!(text.contains("command_errors_total{command=\"get_users\"} 1\n"));
+        assert!(text.contains("command=\"say \\\"hi\\\"\""));
+    }
+}
@@ -0,0 +1,18 @@
+//! Answers of the feature modules' `handle_command` glue
+//!
+//! A module answers with its own success object, or with one of these for a
+//! failure: the standard error envelope for an `AppError`, or just the message
+//! for the modules whose errors are plain strings.
+
+use serde_json::Value;
+use crate::error_handling::{AppResult, GlobalErrorHandler};
+
+/// A feature module's answer to a command, failing with the error envelope
+pub fn command_result(result: AppResult<Value>) -> Value {
+    result.unwrap_or_else(|e| GlobalErrorHandler::to_json_response(&e))
+}
+
+/// A feature module's answer to a command, failing with just the error message
+pub fn command_message<E: std::fmt::Display>(result: Result<Value, E>) -> Value {
+    result.unwrap_or_else(|e| serde_json::json!({ "success": false, "error": e.to_string() }))
+}
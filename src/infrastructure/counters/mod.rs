@@ -0,0 +1,246 @@
+//! Named counters persisted in SQLite
+//!
+//! Every change is broadcast as a `counter.changed` event so all open
+//! windows show the same values, and journaled for `undo`. With
+//! `[counters] event_sourced = true` counters are kept as streams of
+//! `CounterEvent`s instead of rows; the commands work the same either way.
+
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tracing::error;
+use crate::core::domain::{Counter, CounterEvent, CounterRepository, DomainError, DomainResult, RecordedCounterEvent};
+use crate::infrastructure::database::{database, SqliteCounterEventStore, SqliteCounterRepository};
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::infrastructure::history::{self, Operation};
+use crate::model::core::AppConfig;
+
+/// Counter used by the webui `increment_counter` / `reset_counter` bindings
+pub const DEFAULT_COUNTER_ID: &str = "default";
+
+// Serializes read-modify-write updates so concurrent increments are not lost
+static MUTATION_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy)]
+pub enum CounterOperation {
+    Increment,
+    Decrement,
+    Reset,
+}
+
+impl CounterOperation {
+    fn name(self) -> &'static str {
+        match self {
+            CounterOperation::Increment => "increment",
+            CounterOperation::Decrement => "decrement",
+            CounterOperation::Reset => "reset",
+        }
+    }
+
+    fn event(self) -> CounterEvent {
+        match self {
+            CounterOperation::Increment => CounterEvent::Incremented,
+            CounterOperation::Decrement => CounterEvent::Decremented,
+            CounterOperation::Reset => CounterEvent::Reset,
+        }
+    }
+}
+
+/// The event store, when `[counters] event_sourced` is on
+fn event_store() -> DomainResult<Option<SqliteCounterEventStore>> {
+    let config = AppConfig::global();
+    if !config.is_counters_event_sourced() {
+        return Ok(None);
+    }
+    Ok(Some(SqliteCounterEventStore::new(database()?, config.get_counters_snapshot_every())))
+}
+
+fn repository() -> DomainResult<Box<dyn CounterRepository>> {
+    Ok(match event_store()? {
+        Some(store) => Box::new(store),
+        None => Box::new(SqliteCounterRepository::new(database()?)),
+    })
+}
+
+async fn broadcast(counter: &Counter, operation: &str) {
+    if let Err(e) = EventBus::global()
+        .emit_simple(
+            &AppEventType::CounterChanged.to_string(),
+            serde_json::json!({ "operation": operation, "counter": counter }),
+        )
+        .await
+    {
+        error!("Failed to emit counter changed event: {}", e);
+    }
+}
+
+pub async fn list() -> DomainResult<Vec<Counter>> {
+    repository()?.get_all().await
+}
+
+pub async fn create(id: Option<&str>, label: &str) -> DomainResult<Counter> {
+    let label = label.trim();
+    if label.is_empty() || label.len() > 100 {
+        return Err(DomainError::ValidationError(
+            "Label must be between 1 and 100 characters".to_string(),
+        ));
+    }
+
+    let id = id
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let counter = insert(&id, label, "create").await?;
+    history::record(
+        format!("counter.create {}", id),
+        Operation::CreateCounter { id, label: counter.label.clone() },
+    )
+    .await;
+    Ok(counter)
+}
+
+async fn insert(id: &str, label: &str, operation: &str) -> DomainResult<Counter> {
+    let _guard = MUTATION_LOCK.lock().await;
+    let repo = repository()?;
+    if repo.get_by_id(id).await?.is_some() {
+        return Err(DomainError::BusinessRuleViolation(format!("Counter {} already exists", id)));
+    }
+
+    let created = CounterEvent::Created { label: label.to_string() };
+    let counter = repo.save_changes(Counter::new(id.to_string(), label.to_string()), vec![created]).await?;
+    broadcast(&counter, operation).await;
+    Ok(counter)
+}
+
+/// Apply `operation` to counter `id`; the default counter is created on first use
+pub async fn apply(id: &str, operation: CounterOperation) -> DomainResult<Counter> {
+    let guard = MUTATION_LOCK.lock().await;
+    let repo = repository()?;
+
+    let mut events = Vec::new();
+    let mut counter = match repo.get_by_id(id).await? {
+        Some(counter) => counter,
+        None if id == DEFAULT_COUNTER_ID => {
+            events.push(CounterEvent::Created { label: "Default".to_string() });
+            Counter::new(id.to_string(), "Default".to_string())
+        }
+        None => return Err(DomainError::NotFound(format!("Counter {}", id))),
+    };
+
+    let from = counter.value;
+    let event = operation.event();
+    counter.apply(&event);
+    events.push(event);
+
+    let counter = repo.save_changes(counter, events).await?;
+    broadcast(&counter, operation.name()).await;
+    // Journaled after releasing the lock, which `undo` takes while holding the journal
+    drop(guard);
+    if counter.value != from {
+        history::record(
+            format!("counter.{} {}", operation.name(), id),
+            Operation::SetCounter { id: id.to_string(), from, to: counter.value },
+        )
+        .await;
+    }
+    Ok(counter)
+}
+
+/// Move counter `id` from `from` to `to`, for undo; fails once it no longer holds `from`
+pub async fn set_value(id: &str, from: i64, to: i64) -> DomainResult<Counter> {
+    let _guard = MUTATION_LOCK.lock().await;
+    let repo = repository()?;
+    let mut counter = repo
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| DomainError::NotFound(format!("Counter {}", id)))?;
+    if counter.value != from {
+        return Err(DomainError::BusinessRuleViolation(format!(
+            "Counter {} is at {}, not {}",
+            id, counter.value, from
+        )));
+    }
+
+    let event = CounterEvent::Set { value: to };
+    counter.apply(&event);
+    let counter = repo.save_changes(counter, vec![event]).await?;
+    broadcast(&counter, "set").await;
+    Ok(counter)
+}
+
+/// Create counter `id` again, for undo of its deletion
+pub async fn restore(id: &str, label: &str) -> DomainResult<Counter> {
+    insert(id, label, "restore").await
+}
+
+/// Delete counter `id`, for undo of its creation; fails once it no longer holds `value`
+pub async fn remove(id: &str, value: i64) -> DomainResult<Counter> {
+    let _guard = MUTATION_LOCK.lock().await;
+    let repo = repository()?;
+    let counter = repo
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| DomainError::NotFound(format!("Counter {}", id)))?;
+    if counter.value != value {
+        return Err(DomainError::BusinessRuleViolation(format!(
+            "Counter {} is at {}, not {}",
+            id, counter.value, value
+        )));
+    }
+
+    repo.delete(id).await?;
+    broadcast(&counter, "delete").await;
+    Ok(counter)
+}
+
+/// The event stream of counter `id`, oldest first; only event-sourced counters have one
+pub fn events(id: &str) -> DomainResult<Vec<RecordedCounterEvent>> {
+    let store = event_store()?.ok_or_else(|| {
+        DomainError::BusinessRuleViolation("Counters are not event-sourced ([counters] event_sourced = false)".to_string())
+    })?;
+    let events = store.events(id)?;
+    if events.is_empty() {
+        return Err(DomainError::NotFound(format!("Counter {}", id)));
+    }
+    Ok(events)
+}
+
+/// Handle `counter.*` commands from the frontend
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    let id = payload
+        .get("id")
+        .and_then(|v| v.as_str())
+        .unwrap_or(DEFAULT_COUNTER_ID);
+
+    let result = match name {
+        "counter.list" => {
+            return match list().await {
+                Ok(counters) => serde_json::json!({ "success": true, "counters": counters }),
+                Err(e) => serde_json::json!({ "success": false, "error": e.to_string() }),
+            };
+        }
+        "counter.events" => {
+            return match events(id) {
+                Ok(events) => serde_json::json!({ "success": true, "events": events }),
+                Err(e) => serde_json::json!({ "success": false, "error": e.to_string() }),
+            };
+        }
+        "counter.create" => {
+            let label = payload.get("label").and_then(|v| v.as_str()).unwrap_or_default();
+            create(payload.get("id").and_then(|v| v.as_str()), label).await
+        }
+        "counter.increment" => apply(id, CounterOperation::Increment).await,
+        "counter.decrement" => apply(id, CounterOperation::Decrement).await,
+        "counter.reset" => apply(id, CounterOperation::Reset).await,
+        _ => {
+            return serde_json::json!({
+                "success": false,
+                "error": format!("Unknown counter command: {}", name)
+            });
+        }
+    };
+
+    match result {
+        Ok(counter) => serde_json::json!({ "success": true, "counter": counter }),
+        Err(e) => serde_json::json!({ "success": false, "error": e.to_string() }),
+    }
+}
@@ -0,0 +1,223 @@
+//! Crash reports written from the panic hook
+//!
+//! Each panic produces one JSON file in `crashes/` with the panic message,
+//! a resolved backtrace, the last emitted events and log records, host
+//! details and the effective configuration with secrets redacted.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::core::application::SystemInfoDto;
+use crate::core::domain::SystemInfoRepository;
+use crate::infrastructure::event_bus::{EventBus, EventRecord};
+use crate::infrastructure::logging::request_context;
+use crate::infrastructure::logging::ring_buffer::{self, LogRecord, RingBuffer};
+use crate::infrastructure::system_info::SysinfoRepository;
+use crate::model::core::AppConfig;
+
+pub const CRASH_DIR: &str = "crashes";
+
+/// Older reports beyond this count are deleted when a new one is written
+const MAX_REPORTS: usize = 20;
+
+/// Config keys containing any of these fragments are replaced with `[redacted]`
+const SECRET_KEY_FRAGMENTS: &[&str] = &["password", "secret", "token", "api_key", "credential", "private"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub app_version: String,
+    pub thread: String,
+    pub location: String,
+    pub message: String,
+    /// The HTTP request the panicking thread was handling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    pub backtrace: String,
+    pub recent_events: Vec<EventRecord>,
+    /// From the log ring buffer, when `[logging] ring_buffer_file` is set
+    #[serde(default)]
+    pub recent_logs: Vec<LogRecord>,
+    pub system: Option<SystemInfoDto>,
+    pub config: Value,
+}
+
+/// Listing entry for `crash.list`
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashSummary {
+    pub file: String,
+    pub timestamp: DateTime<Utc>,
+    pub message: String,
+    pub location: String,
+}
+
+impl CrashReport {
+    /// Gather everything about the current panic; runs inside the panic hook
+    pub fn capture(thread: &str, location: &str, message: &str) -> Self {
+        // A fresh provider: the shared one may be locked by the panicking thread
+        let system = SysinfoRepository::new()
+            .get_current()
+            .ok()
+            .map(SystemInfoDto::from);
+
+        let config = serde_json::to_value(AppConfig::global())
+            .map(redact_secrets)
+            .unwrap_or(Value::Null);
+
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            thread: thread.to_string(),
+            location: location.to_string(),
+            message: message.to_string(),
+            request_id: request_context::current(),
+            backtrace: format!("{:?}", backtrace::Backtrace::new()),
+            recent_events: EventBus::global().recent_events(),
+            recent_logs: ring_buffer::global().map(RingBuffer::records).unwrap_or_default(),
+            system,
+            config,
+        }
+    }
+
+    fn file_name(&self) -> String {
+        format!(
+            "crash-{}-{}.json",
+            self.timestamp.format("%Y%m%d-%H%M%S"),
+            &self.id[..8]
+        )
+    }
+
+    /// Write the report into `dir` and prune old reports
+    pub fn write_to(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(self.file_name());
+        fs::write(&path, serde_json::to_vec_pretty(self)?)?;
+        prune(dir, MAX_REPORTS);
+        Ok(path)
+    }
+}
+
+/// Replace values of secret-looking keys, recursively
+pub fn redact_secrets(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let lower = key.to_lowercase();
+                    if SECRET_KEY_FRAGMENTS.iter().any(|fragment| lower.contains(fragment)) {
+                        (key, Value::String("[redacted]".to_string()))
+                    } else {
+                        (key, redact_secrets(value))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact_secrets).collect()),
+        other => other,
+    }
+}
+
+fn report_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with("crash-") && name.ends_with(".json"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // Names start with the timestamp, so this is newest first
+    files.sort_by(|a, b| b.cmp(a));
+    files
+}
+
+fn prune(dir: &Path, keep: usize) {
+    for old in report_files(dir).into_iter().skip(keep) {
+        let _ = fs::remove_file(old);
+    }
+}
+
+/// Saved reports, newest first
+pub fn list_reports(dir: &Path) -> Vec<CrashSummary> {
+    report_files(dir)
+        .into_iter()
+        .filter_map(|path| {
+            let report: CrashReport = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+            Some(CrashSummary {
+                file: path.file_name()?.to_string_lossy().into_owned(),
+                timestamp: report.timestamp,
+                message: report.message,
+                location: report.location,
+            })
+        })
+        .collect()
+}
+
+/// Read one report by file name; anything that is not a plain report file name is rejected
+pub fn read_report(dir: &Path, file: &str) -> Result<CrashReport, String> {
+    let is_plain_name = Path::new(file).file_name().and_then(|name| name.to_str()) == Some(file);
+    if !is_plain_name || !file.starts_with("crash-") || !file.ends_with(".json") {
+        return Err(format!("Invalid crash report name: {}", file));
+    }
+    let bytes = fs::read(dir.join(file)).map_err(|e| format!("Crash report {}: {}", file, e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Crash report {} is unreadable: {}", file, e))
+}
+
+/// Handle `crash.list` / `crash.read` devtools commands
+pub fn handle_command(name: &str, payload: &Value) -> Value {
+    let dir = Path::new(CRASH_DIR);
+    match name {
+        "crash.list" => serde_json::json!({ "crashes": list_reports(dir) }),
+        "crash.read" => {
+            let file = payload.get("file").and_then(|v| v.as_str()).unwrap_or_default();
+            match read_report(dir, file) {
+                Ok(report) => serde_json::json!({ "crash": report }),
+                Err(e) => serde_json::json!({ "error": e }),
+            }
+        }
+        _ => serde_json::json!({ "error": format!("Unknown crash command: {}", name) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    #[test]
+    fn test_redacts_nested_secrets() {
+        let config = serde_json::json!({
+            "database": { "path": "app.db", "password": "hunter2" },
+            "sync": [{ "api_key": "abc", "url": "https://example.com" }]
+        });
+        let redacted = redact_secrets(config);
+        assert_eq!(redacted["database"]["password"], "[redacted]");
+        assert_eq!(redacted["database"]["path"], "app.db");
+        assert_eq!(redacted["sync"][0]["api_key"], "[redacted]");
+        assert_eq!(redacted["sync"][0]["url"], "https://example.com");
+    }
+
+    #[test]
+    fn test_write_list_and_read() {
+        let dir = TempDir::new("crash-reporter-roundtrip");
+        let report = CrashReport::capture("main", "src/main.rs:1", "boom");
+        let path = report.write_to(&dir).unwrap();
+        let file = path.file_name().unwrap().to_str().unwrap();
+
+        let listed = list_reports(&dir);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].message, "boom");
+
+        assert_eq!(read_report(&dir, file).unwrap().id, report.id);
+        assert!(read_report(&dir, "../app.config.toml").is_err());
+    }
+}
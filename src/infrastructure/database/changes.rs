@@ -0,0 +1,194 @@
+//! Row changes of the writer connection, published as `data.changed`
+//!
+//! SQLite's update hook reports every row inserted, updated or deleted
+//! through the writer, whichever `Database` method, `db.execute` call or
+//! plugin made it. The changes of a transaction are held until it commits
+//! (and dropped when it rolls back); the commit wakes the outbox relay, which
+//! writes them to the outbox as one `data.changed` event per row: `table`, `rowid` and
+//! `operation` (`insert`, `update` or `delete`). A commit touching more than
+//! `MAX_ROW_EVENTS` rows of a table gets one event per table and operation
+//! with their `count` instead.
+//!
+//! The hook only sees this process: changes by another program on the same
+//! file go unnoticed. SQLite also skips it for rows deleted by an unfiltered
+//! `DELETE FROM t` and rows replaced by `INSERT OR REPLACE`. Tables of
+//! `[database] change_events_ignore` are not reported, nor are the outbox
+//! itself and SQLite's own tables.
+
+use rusqlite::hooks::Action;
+use rusqlite::Connection;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Rows of one table in one batch reported one by one
+pub const MAX_ROW_EVENTS: usize = 100;
+
+/// `[database] change_events_ignore` when unset: bookkeeping written on
+/// nearly every request, counter events, which have `counter.changed`, and
+/// updates of shared documents, which have `collab.update`
+pub const DEFAULT_IGNORED: &[&str] = &[
+    "sessions",
+    "auth_tokens",
+    "analytics_daily",
+    "window_state",
+    "counter_events",
+    "counter_snapshots",
+    "webhook_deliveries",
+    "collab_updates",
+];
+
+/// Tables whose changes are never reported; events of the outbox would feed themselves
+const ALWAYS_IGNORED: &[&str] = &["outbox"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowChange {
+    pub table: String,
+    pub rowid: i64,
+    pub operation: ChangeOperation,
+}
+
+#[derive(Default)]
+struct Changes {
+    /// Of the transaction in progress
+    pending: Vec<RowChange>,
+    /// Committed, not yet taken
+    committed: Vec<RowChange>,
+}
+
+/// What the hooks of one connection collected
+pub struct ChangeLog {
+    changes: Mutex<Changes>,
+    ignored: Vec<String>,
+}
+
+impl ChangeLog {
+    /// Set the hooks of `conn`, replacing earlier ones; changes of `ignored` tables are skipped
+    pub fn install(conn: &Connection, ignored: &[String]) -> Arc<Self> {
+        let log = Arc::new(Self { changes: Mutex::new(Changes::default()), ignored: ignored.to_vec() });
+        let hook = Arc::clone(&log);
+        conn.update_hook(Some(move |action: Action, _: &str, table: &str, rowid: i64| hook.changed(action, table, rowid)));
+        let hook = Arc::clone(&log);
+        conn.commit_hook(Some(move || {
+            hook.committed();
+            // false lets the commit go ahead
+            false
+        }));
+        let hook = Arc::clone(&log);
+        conn.rollback_hook(Some(move || hook.changes().pending.clear()));
+        log
+    }
+
+    fn changes(&self) -> MutexGuard<'_, Changes> {
+        self.changes.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn changed(&self, action: Action, table: &str, rowid: i64) {
+        let operation = match action {
+            Action::SQLITE_INSERT => ChangeOperation::Insert,
+            Action::SQLITE_UPDATE => ChangeOperation::Update,
+            Action::SQLITE_DELETE => ChangeOperation::Delete,
+            _ => return,
+        };
+        let ignored = table.starts_with("sqlite_")
+            || ALWAYS_IGNORED.contains(&table)
+            || self.ignored.iter().any(|ignored| ignored == table);
+        if !ignored {
+            self.changes().pending.push(RowChange { table: table.to_string(), rowid, operation });
+        }
+    }
+
+    fn committed(&self) {
+        let mut changes = self.changes();
+        if changes.pending.is_empty() {
+            return;
+        }
+        let pending = std::mem::take(&mut changes.pending);
+        changes.committed.extend(pending);
+        drop(changes);
+        crate::infrastructure::outbox::wake();
+    }
+
+    /// The committed changes not taken yet, in order
+    pub fn take(&self) -> Vec<RowChange> {
+        std::mem::take(&mut self.changes().committed)
+    }
+}
+
+/// `data.changed` payloads for `changes`, one per row, with repeats of the
+/// same change dropped, or per table and operation beyond `MAX_ROW_EVENTS`
+pub fn events(changes: &[RowChange]) -> Vec<Value> {
+    let mut rows: Vec<&RowChange> = Vec::new();
+    for change in changes {
+        if !rows.contains(&change) {
+            rows.push(change);
+        }
+    }
+    let mut per_table: BTreeMap<&str, usize> = BTreeMap::new();
+    for row in &rows {
+        *per_table.entry(row.table.as_str()).or_default() += 1;
+    }
+
+    let mut events = Vec::new();
+    let mut summaries: BTreeMap<(&str, ChangeOperation), u64> = BTreeMap::new();
+    for row in rows {
+        if per_table[row.table.as_str()] > MAX_ROW_EVENTS {
+            *summaries.entry((row.table.as_str(), row.operation)).or_default() += 1;
+        } else {
+            events.push(serde_json::json!({ "operation": row.operation, "table": row.table, "rowid": row.rowid }));
+        }
+    }
+    events.extend(
+        summaries
+            .into_iter()
+            .map(|((table, operation), count)| serde_json::json!({ "operation": operation, "table": table, "count": count })),
+    );
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_committed_row_changes_become_events() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE users (name TEXT); CREATE TABLE sessions (id TEXT); CREATE TABLE outbox (id TEXT);").unwrap();
+        let log = ChangeLog::install(&conn, &["sessions".to_string()]);
+
+        conn.execute_batch(
+            "INSERT INTO users VALUES ('Ann'); INSERT INTO sessions VALUES ('s'); INSERT INTO outbox VALUES ('o');
+             UPDATE users SET name = 'Anna'; UPDATE users SET name = 'Annie';",
+        )
+        .unwrap();
+        conn.execute_batch("BEGIN; INSERT INTO users VALUES ('Bob'); ROLLBACK;").unwrap();
+        conn.execute_batch("BEGIN; DELETE FROM users WHERE rowid = 1; COMMIT;").unwrap();
+
+        let changes = log.take();
+        assert_eq!(changes.len(), 4);
+        assert!(log.take().is_empty());
+        assert_eq!(
+            events(&changes),
+            [
+                serde_json::json!({ "operation": "insert", "table": "users", "rowid": 1 }),
+                serde_json::json!({ "operation": "update", "table": "users", "rowid": 1 }),
+                serde_json::json!({ "operation": "delete", "table": "users", "rowid": 1 }),
+            ]
+        );
+
+        let bulk: Vec<RowChange> = (0..=MAX_ROW_EVENTS as i64)
+            .map(|rowid| RowChange { table: "counters".to_string(), rowid, operation: ChangeOperation::Insert })
+            .collect();
+        assert_eq!(events(&bulk), [serde_json::json!({ "operation": "insert", "table": "counters", "count": 101 })]);
+    }
+}
@@ -0,0 +1,143 @@
+//! Event-sourced `CounterRepository`, `[counters] event_sourced = true`
+//!
+//! A counter is the stream of its `CounterEvent`s in `counter_events`; its
+//! version is the sequence of its last event. Loading folds the events onto
+//! the latest snapshot in `counter_snapshots`, which is rewritten every
+//! `snapshot_every` events. Deleting a counter removes its stream.
+
+use chrono::Utc;
+use std::sync::Arc;
+use crate::core::domain::{Counter, CounterEvent, CounterRepository, DomainError, DomainResult, RecordedCounterEvent};
+use crate::infrastructure::database::Database;
+
+pub struct SqliteCounterEventStore {
+    db: Arc<Database>,
+    snapshot_every: i64,
+}
+
+impl SqliteCounterEventStore {
+    pub fn new(db: Arc<Database>, snapshot_every: u64) -> Self {
+        Self {
+            db,
+            snapshot_every: snapshot_every.max(1) as i64,
+        }
+    }
+
+    /// The stream of counter `id`, oldest first
+    pub fn events(&self, id: &str) -> DomainResult<Vec<RecordedCounterEvent>> {
+        self.db.get_counter_events(id, 0).map_err(repository_error)
+    }
+}
+
+fn repository_error(e: Box<dyn std::error::Error>) -> DomainError {
+    DomainError::RepositoryError(e.to_string())
+}
+
+#[async_trait::async_trait]
+impl CounterRepository for SqliteCounterEventStore {
+    async fn get_all(&self) -> DomainResult<Vec<Counter>> {
+        let mut counters = Vec::new();
+        for id in self.db.get_counter_stream_ids().map_err(repository_error)? {
+            counters.extend(self.get_by_id(&id).await?);
+        }
+        counters.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        Ok(counters)
+    }
+
+    async fn get_by_id(&self, id: &str) -> DomainResult<Option<Counter>> {
+        let snapshot = self.db.get_counter_snapshot(id).map_err(repository_error)?;
+        let after = snapshot.as_ref().map_or(0, |counter| counter.version);
+        let events = self.db.get_counter_events(id, after).map_err(repository_error)?;
+        Ok(Counter::replay(id, snapshot, &events))
+    }
+
+    /// Without events to go by, the state is recorded as created or set
+    async fn save(&self, counter: Counter) -> DomainResult<Counter> {
+        let mut events = Vec::new();
+        if counter.version == 0 {
+            events.push(CounterEvent::Created { label: counter.label.clone() });
+        }
+        if counter.version > 0 || counter.value != 0 {
+            events.push(CounterEvent::Set { value: counter.value });
+        }
+        self.save_changes(counter, events).await
+    }
+
+    async fn delete(&self, id: &str) -> DomainResult<()> {
+        match self.db.delete_counter_stream(id).map_err(repository_error)? {
+            true => Ok(()),
+            false => Err(DomainError::NotFound(format!("Counter {}", id))),
+        }
+    }
+
+    async fn save_changes(&self, mut counter: Counter, events: Vec<CounterEvent>) -> DomainResult<Counter> {
+        let expected = counter.version;
+        let recorded_at = Utc::now();
+        let recorded: Vec<RecordedCounterEvent> = events
+            .into_iter()
+            .zip(expected + 1..)
+            .map(|(event, sequence)| RecordedCounterEvent { sequence, recorded_at, event })
+            .collect();
+
+        if !self.db.append_counter_events(&counter.id, expected, &recorded).map_err(repository_error)? {
+            let actual = self.get_by_id(&counter.id).await?.map(|stored| stored.version).unwrap_or_default();
+            return Err(DomainError::VersionConflict {
+                entity: "Counter".to_string(),
+                id: counter.id,
+                expected,
+                actual,
+            });
+        }
+
+        counter.version = expected + recorded.len() as i64;
+        counter.updated_at = recorded_at;
+        if counter.version / self.snapshot_every > expected / self.snapshot_every {
+            self.db.save_counter_snapshot(&counter).map_err(repository_error)?;
+        }
+        Ok(counter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_events_fold_into_the_counter() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        db.init().unwrap();
+        let store = SqliteCounterEventStore::new(db.clone(), 3);
+
+        let created = Counter::new("clicks".to_string(), "Clicks".to_string());
+        let mut counter = store
+            .save_changes(created, vec![CounterEvent::Created { label: "Clicks".to_string() }])
+            .await
+            .unwrap();
+        for event in [CounterEvent::Incremented, CounterEvent::Incremented, CounterEvent::Reset, CounterEvent::Decremented] {
+            counter.apply(&event);
+            counter = store.save_changes(counter, vec![event]).await.unwrap();
+        }
+        assert_eq!((counter.value, counter.version), (-1, 5));
+
+        // Loading replays the events after the snapshot taken at sequence 3
+        assert_eq!(db.get_counter_snapshot("clicks").unwrap().unwrap().version, 3);
+        let stored = store.get_by_id("clicks").await.unwrap().unwrap();
+        assert_eq!((stored.value, stored.version, stored.label.as_str()), (-1, 5, "Clicks"));
+        let types: Vec<_> = store.events("clicks").unwrap().into_iter().map(|recorded| recorded.event).collect();
+        assert_eq!(types[1], CounterEvent::Incremented);
+        assert_eq!(types.len(), 5);
+
+        // A stale version cannot append
+        let mut stale = stored.clone();
+        stale.version = 4;
+        assert!(matches!(
+            store.save_changes(stale, vec![CounterEvent::Incremented]).await,
+            Err(DomainError::VersionConflict { expected: 4, actual: 5, .. })
+        ));
+
+        assert_eq!(store.get_all().await.unwrap().len(), 1);
+        store.delete("clicks").await.unwrap();
+        assert!(store.get_by_id("clicks").await.unwrap().is_none());
+        assert!(matches!(store.delete("clicks").await, Err(DomainError::NotFound(_))));
+    }
+}
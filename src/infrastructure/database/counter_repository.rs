@@ -0,0 +1,106 @@
+//! SQLite-backed `CounterRepository`
+
+use std::sync::Arc;
+use crate::core::domain::{Counter, CounterRepository, DomainError, DomainResult};
+use crate::infrastructure::database::Database;
+
+pub struct SqliteCounterRepository {
+    db: Arc<Database>,
+}
+
+impl SqliteCounterRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+}
+
+fn repository_error(e: Box<dyn std::error::Error>) -> DomainError {
+    DomainError::RepositoryError(e.to_string())
+}
+
+#[async_trait::async_trait]
+impl CounterRepository for SqliteCounterRepository {
+    async fn get_all(&self) -> DomainResult<Vec<Counter>> {
+        self.db.get_counters().map_err(repository_error)
+    }
+
+    async fn get_by_id(&self, id: &str) -> DomainResult<Option<Counter>> {
+        self.db.get_counter(id).map_err(repository_error)
+    }
+
+    async fn save(&self, mut counter: Counter) -> DomainResult<Counter> {
+        if !self.db.save_counter(&counter).map_err(repository_error)? {
+            let actual = self
+                .db
+                .get_counter(&counter.id)
+                .map_err(repository_error)?
+                .map(|stored| stored.version)
+                .unwrap_or_default();
+            return Err(DomainError::VersionConflict {
+                entity: "Counter".to_string(),
+                id: counter.id,
+                expected: counter.version,
+                actual,
+            });
+        }
+        counter.version += 1;
+        Ok(counter)
+    }
+
+    async fn delete(&self, id: &str) -> DomainResult<()> {
+        match self.db.delete_counter(id).map_err(repository_error)? {
+            true => Ok(()),
+            false => Err(DomainError::NotFound(format!("Counter {}", id))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_counter_round_trip() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        let repo = SqliteCounterRepository::new(Arc::new(db));
+
+        let mut counter = repo.save(Counter::new("clicks".to_string(), "Clicks".to_string())).await.unwrap();
+        counter.increment();
+        counter.increment();
+        repo.save(counter).await.unwrap();
+
+        let stored = repo.get_by_id("clicks").await.unwrap().unwrap();
+        assert_eq!(stored.value, 2);
+        assert_eq!(stored.label, "Clicks");
+        assert_eq!(repo.get_all().await.unwrap().len(), 1);
+
+        repo.delete("clicks").await.unwrap();
+        assert!(repo.get_by_id("clicks").await.unwrap().is_none());
+        assert!(matches!(repo.delete("clicks").await, Err(DomainError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_stale_save_is_rejected() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        let repo = SqliteCounterRepository::new(Arc::new(db));
+
+        let first = repo.save(Counter::new("clicks".to_string(), "Clicks".to_string())).await.unwrap();
+        assert_eq!(first.version, 1);
+        let mut stale = first.clone();
+
+        let mut fresh = first;
+        fresh.increment();
+        assert_eq!(repo.save(fresh).await.unwrap().version, 2);
+
+        stale.reset();
+        match repo.save(stale).await {
+            Err(DomainError::VersionConflict { expected, actual, .. }) => {
+                assert_eq!((expected, actual), (1, 2));
+            }
+            other => panic!("expected version conflict, got {:?}", other),
+        }
+        assert_eq!(repo.get_by_id("clicks").await.unwrap().unwrap().value, 1);
+    }
+}
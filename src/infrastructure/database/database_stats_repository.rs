@@ -0,0 +1,67 @@
+//! SQLite-backed `DatabaseStatsRepository`
+
+use std::sync::Arc;
+use tracing::warn;
+use crate::core::domain::{clock, DatabaseStats, DatabaseStatsRepository, DomainError, DomainResult};
+use crate::infrastructure::database::Database;
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+
+pub struct SqliteDatabaseStatsRepository {
+    db: Arc<Database>,
+}
+
+impl SqliteDatabaseStatsRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+}
+
+fn repository_error(e: Box<dyn std::error::Error>) -> DomainError {
+    DomainError::RepositoryError(e.to_string())
+}
+
+#[async_trait::async_trait]
+impl DatabaseStatsRepository for SqliteDatabaseStatsRepository {
+    /// Emits `database.operation` with `operation: "get_stats"`
+    async fn get_stats(&self) -> DomainResult<DatabaseStats> {
+        let stats = self.db.get_db_stats().map_err(repository_error)?;
+        let event = serde_json::json!({ "operation": "get_stats", "stats": &stats });
+        if let Err(e) = EventBus::global().emit_simple(&AppEventType::DatabaseOperation.to_string(), event).await {
+            warn!("Failed to emit get stats event: {}", e);
+        }
+        Ok(DatabaseStats {
+            users_count: stats["users"].as_i64().unwrap_or_default(),
+            tables: serde_json::from_value(stats["tables"].clone()).map_err(|e| DomainError::RepositoryError(e.to_string()))?,
+            database_size: Some(self.db.size_bytes().map_err(repository_error)?),
+            last_updated: clock::now(),
+        })
+    }
+
+    async fn get_table_count(&self) -> DomainResult<usize> {
+        Ok(self.get_stats().await?.tables.len())
+    }
+
+    async fn get_total_records(&self) -> DomainResult<i64> {
+        self.db.total_records().map_err(repository_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stats_count_the_sample_data() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        db.insert_sample_data().unwrap();
+        let repo = SqliteDatabaseStatsRepository::new(Arc::new(db));
+
+        let stats = repo.get_stats().await.unwrap();
+        assert_eq!(stats.users_count, 4);
+        assert!(stats.tables.iter().any(|table| table == "users"));
+        assert!(stats.database_size.unwrap() > 0);
+        assert_eq!(repo.get_table_count().await.unwrap(), stats.tables.len());
+        assert!(repo.get_total_records().await.unwrap() >= 4);
+    }
+}
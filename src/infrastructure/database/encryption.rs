@@ -0,0 +1,231 @@
+//! SQLCipher encryption of the app database (`encrypted-db` feature)
+//!
+//! With `[database] encrypted = true` the file is opened with a passphrase:
+//! `encryption_key` when set (plain or a `secret:<name>` reference),
+//! otherwise one generated on first start and kept in the OS keychain, so it
+//! never sits next to the database. A plain SQLite file found at the path is
+//! migrated once with `sqlcipher_export` and then replaced by the encrypted
+//! copy. `db.rekey` re-encrypts the file under a new keychain passphrase.
+
+use rusqlite::Connection;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{info, warn};
+use crate::infrastructure::commands::command_message;
+use crate::infrastructure::database::database;
+use crate::infrastructure::secrets::{SecretsError, SecretsManager, SECRET_REF_PREFIX};
+use crate::model::core::AppConfig;
+
+/// First bytes of every unencrypted SQLite file; SQLCipher files start with a random salt
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error(transparent)]
+    Secrets(#[from] SecretsError),
+    #[error("Database error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Wrong key, or not a database: {0}")]
+    WrongKey(String),
+    #[error("The database is not encrypted")]
+    NotEncrypted,
+    #[error("The key comes from [database] encryption_key, which db.rekey cannot update")]
+    KeyFromConfig,
+    #[error("Cannot leave WAL mode while another connection uses the database (journal mode is still {0})")]
+    JournalMode(String),
+}
+
+/// Name of the secret holding the passphrase: the `encryption_key` reference, or one per database file
+fn key_secret_name(config: &AppConfig) -> Result<String, EncryptionError> {
+    match config.get_db_encryption_key() {
+        None => Ok(format!("database:{}", config.get_db_path())),
+        Some(value) => value
+            .strip_prefix(SECRET_REF_PREFIX)
+            .map(String::from)
+            .ok_or(EncryptionError::KeyFromConfig),
+    }
+}
+
+fn generate_key() -> String {
+    format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+/// The passphrase from the config, else from the keychain, storing a new one on first use
+pub fn database_key(config: &AppConfig) -> Result<String, EncryptionError> {
+    let secrets = SecretsManager::global();
+    match config.get_db_encryption_key() {
+        Some(value) => Ok(secrets.resolve(value)?),
+        None => Ok(secrets.get_or_create(&key_secret_name(config)?, || {
+            info!("Storing a new database key in the OS keychain");
+            generate_key()
+        })?),
+    }
+}
+
+/// Whether `path` is an unencrypted SQLite database
+pub fn is_plaintext(path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|_| &header == SQLITE_HEADER)
+}
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Key `conn` and check the key by reading the schema
+pub fn apply_key(conn: &Connection, key: &str) -> Result<(), EncryptionError> {
+    conn.pragma_update(None, "key", key)?;
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+        .map_err(|e| EncryptionError::WrongKey(e.to_string()))
+}
+
+/// Replace the plain database at `path` with an encrypted copy
+pub fn migrate_plaintext(path: &Path, key: &str) -> Result<(), EncryptionError> {
+    let encrypted = sibling(path, ".encrypting");
+    if encrypted.exists() {
+        fs::remove_file(&encrypted)?;
+    }
+    {
+        let conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+        conn.execute(
+            "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+            rusqlite::params![encrypted.to_string_lossy(), key],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+        conn.execute_batch("DETACH DATABASE encrypted;")?;
+    }
+    apply_key(&Connection::open(&encrypted)?, key)?;
+
+    fs::rename(&encrypted, path)?;
+    for suffix in ["-wal", "-shm"] {
+        let _ = fs::remove_file(sibling(path, suffix));
+    }
+    info!("Migrated {} to an encrypted database", path.display());
+    Ok(())
+}
+
+/// Open the database at `[database] path` with its key, migrating a plain one first
+pub fn open(config: &AppConfig) -> Result<Connection, EncryptionError> {
+    let path = Path::new(config.get_db_path());
+    let key = database_key(config)?;
+    if is_plaintext(path) {
+        migrate_plaintext(path, &key)?;
+    }
+    let conn = Connection::open(path)?;
+    apply_key(&conn, &key)?;
+    Ok(conn)
+}
+
+/// Key another connection to the database `open` opened, e.g. a reader
+pub fn unlock(conn: &Connection, config: &AppConfig) -> Result<(), EncryptionError> {
+    apply_key(conn, &database_key(config)?)
+}
+
+/// Re-encrypt `conn` under `key`. SQLCipher cannot rekey in WAL mode, so the journal is
+/// switched for it, which only works while no other connection has the file open
+pub fn rekey(conn: &Connection, key: &str) -> Result<(), EncryptionError> {
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+    // SQLite answers with the mode in effect, which stays `wal` when it could not switch
+    let mode: String = conn.query_row("PRAGMA journal_mode=DELETE", [], |row| row.get(0))?;
+    if !mode.eq_ignore_ascii_case("delete") {
+        return Err(EncryptionError::JournalMode(mode));
+    }
+    let result = conn.pragma_update(None, "rekey", key);
+    conn.execute_batch("PRAGMA journal_mode=WAL;")?;
+    Ok(result?)
+}
+
+/// Re-encrypt the open database under a new keychain passphrase
+fn rekey_database() -> Result<(), EncryptionError> {
+    let config = AppConfig::global();
+    if !config.is_db_encrypted() {
+        return Err(EncryptionError::NotEncrypted);
+    }
+    let name = key_secret_name(config)?;
+    let db = database().map_err(|e| EncryptionError::Io(std::io::Error::other(e.message)))?;
+
+    let secrets = SecretsManager::global();
+    let old_key = secrets.get(&name)?.ok_or_else(|| SecretsError::NotFound(name.clone()))?;
+    let new_key = generate_key();
+    db.rekey(config, &new_key)?;
+    if let Err(e) = secrets.set(&name, &new_key) {
+        // Without the new key stored the file could not be opened again
+        warn!("Could not store the new database key, restoring the old one: {}", e);
+        db.rekey(config, &old_key)?;
+        return Err(e.into());
+    }
+    info!("Database re-encrypted with a new key");
+
+    let bus = crate::infrastructure::event_bus::EventBus::global();
+    if let Err(e) = futures::executor::block_on(bus.emit_simple(
+        &crate::infrastructure::event_bus::AppEventType::DatabaseOperation.to_string(),
+        serde_json::json!({ "operation": "rekey", "database": config.get_db_path() }),
+    )) {
+        warn!("Failed to emit database rekey event: {}", e);
+    }
+    Ok(())
+}
+
+pub fn handle_command(name: &str, _payload: &Value) -> Value {
+    match name {
+        "db.rekey" => command_message(rekey_database().map(|()| serde_json::json!({ "success": true }))),
+        other => serde_json::json!({ "success": false, "error": format!("Unknown command: {}", other) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    #[test]
+    fn test_migrates_and_rekeys() {
+        let dir = TempDir::new("encryption-test");
+        let path = dir.join("app.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch("PRAGMA journal_mode=WAL; CREATE TABLE t (v TEXT); INSERT INTO t VALUES ('kept');")
+                .unwrap();
+        }
+        assert!(is_plaintext(&path));
+
+        migrate_plaintext(&path, "first").unwrap();
+        assert!(!is_plaintext(&path));
+        assert!(apply_key(&Connection::open(&path).unwrap(), "wrong").is_err());
+
+        let conn = Connection::open(&path).unwrap();
+        apply_key(&conn, "first").unwrap();
+        conn.execute_batch("PRAGMA journal_mode=WAL;").unwrap();
+
+        // Another connection keeps the file in WAL mode, so nothing is rekeyed;
+        // SQLite reports that as busy or by staying in `wal`
+        let reader = Connection::open(&path).unwrap();
+        apply_key(&reader, "first").unwrap();
+        assert!(matches!(rekey(&conn, "second"), Err(EncryptionError::Sqlite(_) | EncryptionError::JournalMode(_))));
+        drop(reader);
+        apply_key(&Connection::open(&path).unwrap(), "first").unwrap();
+
+        rekey(&conn, "second").unwrap();
+        drop(conn);
+
+        let conn = Connection::open(&path).unwrap();
+        assert!(apply_key(&conn, "first").is_err());
+        let conn = Connection::open(&path).unwrap();
+        apply_key(&conn, "second").unwrap();
+        let value: String = conn.query_row("SELECT v FROM t", [], |row| row.get(0)).unwrap();
+        assert_eq!(value, "kept");
+        let backup = dir.join("backup.db");
+        conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()]).unwrap();
+        assert!(!is_plaintext(&backup));
+    }
+}
@@ -0,0 +1,164 @@
+//! SQLite tuning from `[database]`, WAL checkpoints, and the `db.analyze` report
+//!
+//! The PRAGMAs of `DatabaseTuning` are set on the connection right after it
+//! is opened. SQLite's own defaults are kept unless configured; with WAL,
+//! `synchronous = "NORMAL"` is the usual faster choice, giving up only the
+//! last transactions on power loss, never consistency. The WAL file grows
+//! between checkpoints and after `VACUUM`, so the `database.checkpoint` job
+//! truncates it on `[scheduler] checkpoint_schedule` and `database.vacuum`
+//! does so after vacuuming.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use utoipa::ToSchema;
+use crate::model::core::AppConfig;
+
+pub const SYNCHRONOUS_MODES: &[&str] = &["OFF", "NORMAL", "FULL", "EXTRA"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DatabaseTuning {
+    pub synchronous: String,
+    /// Pages when positive, KiB when negative, as `PRAGMA cache_size`
+    pub cache_size: i64,
+    /// Bytes of the file read through memory mapping; 0 = none
+    pub mmap_size: u64,
+    pub busy_timeout_ms: u64,
+}
+
+impl DatabaseTuning {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            synchronous: config.get_db_synchronous().to_string(),
+            cache_size: config.get_db_cache_size(),
+            mmap_size: config.get_db_mmap_size(),
+            busy_timeout_ms: config.get_db_busy_timeout_ms(),
+        }
+    }
+
+    pub fn apply(&self, conn: &Connection) -> rusqlite::Result<()> {
+        conn.busy_timeout(Duration::from_millis(self.busy_timeout_ms))?;
+        conn.pragma_update(None, "synchronous", &self.synchronous)?;
+        conn.pragma_update(None, "cache_size", self.cache_size)?;
+        conn.pragma_update(None, "mmap_size", self.mmap_size as i64)?;
+        Ok(())
+    }
+}
+
+/// Outcome of `PRAGMA wal_checkpoint(TRUNCATE)`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Checkpoint {
+    /// Another connection held the database, so not every frame was copied back
+    pub busy: bool,
+    /// Frames in the WAL before the checkpoint
+    pub wal_frames: i64,
+    pub checkpointed_frames: i64,
+}
+
+/// Copy the WAL back into the database file and truncate it
+pub fn checkpoint(conn: &Connection) -> rusqlite::Result<Checkpoint> {
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+        Ok(Checkpoint {
+            busy: row.get::<_, i64>(0)? != 0,
+            wal_frames: row.get(1)?,
+            checkpointed_frames: row.get(2)?,
+        })
+    })
+}
+
+/// Space of one table or index, from the `dbstat` table
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ObjectPages {
+    pub name: String,
+    pub pages: i64,
+    /// Bytes of its pages holding no data
+    pub unused_bytes: i64,
+}
+
+/// What `db.analyze` reports
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DatabaseAnalysis {
+    pub page_size: i64,
+    pub page_count: i64,
+    /// Pages no longer used, which `VACUUM` gives back
+    pub freelist_count: i64,
+    pub size_bytes: i64,
+    /// Share of the file on the freelist, 0 to 1
+    pub fragmentation: f64,
+    pub journal_mode: String,
+    /// The PRAGMAs in effect
+    pub tuning: DatabaseTuning,
+    /// Largest first
+    pub objects: Vec<ObjectPages>,
+}
+
+fn pragma<T: rusqlite::types::FromSql>(conn: &Connection, name: &str) -> rusqlite::Result<T> {
+    conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))
+}
+
+pub fn analyze(conn: &Connection) -> rusqlite::Result<DatabaseAnalysis> {
+    let page_size: i64 = pragma(conn, "page_size")?;
+    let page_count: i64 = pragma(conn, "page_count")?;
+    let freelist_count: i64 = pragma(conn, "freelist_count")?;
+    let synchronous = pragma::<i64>(conn, "synchronous")?;
+    let tuning = DatabaseTuning {
+        synchronous: SYNCHRONOUS_MODES.get(synchronous as usize).copied().unwrap_or("FULL").to_string(),
+        cache_size: pragma(conn, "cache_size")?,
+        mmap_size: pragma::<i64>(conn, "mmap_size")?.max(0) as u64,
+        busy_timeout_ms: pragma::<i64>(conn, "busy_timeout")?.max(0) as u64,
+    };
+    Ok(DatabaseAnalysis {
+        page_size,
+        page_count,
+        freelist_count,
+        size_bytes: page_size * page_count,
+        fragmentation: if page_count > 0 { freelist_count as f64 / page_count as f64 } else { 0.0 },
+        journal_mode: pragma(conn, "journal_mode")?,
+        tuning,
+        // `dbstat` is compiled into the bundled SQLite, but not necessarily into a system one
+        objects: object_pages(conn).unwrap_or_default(),
+    })
+}
+
+fn object_pages(conn: &Connection) -> rusqlite::Result<Vec<ObjectPages>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, COUNT(*), SUM(unused) FROM dbstat GROUP BY name ORDER BY COUNT(*) DESC, name",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(ObjectPages { name: row.get(0)?, pages: row.get(1)?, unused_bytes: row.get(2)? })
+    })?;
+    rows.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    #[test]
+    fn test_tuning_is_applied_and_reported() {
+        let dir = TempDir::new("maintenance");
+        let path = dir.join("app.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "PRAGMA journal_mode=WAL;
+             CREATE TABLE t (v TEXT);
+             WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 500)
+             INSERT INTO t SELECT hex(randomblob(100)) FROM n;
+             DELETE FROM t WHERE rowid % 2 = 0;",
+        )
+        .unwrap();
+        let tuning = DatabaseTuning { synchronous: "NORMAL".to_string(), cache_size: -4096, mmap_size: 1 << 20, busy_timeout_ms: 250 };
+        tuning.apply(&conn).unwrap();
+
+        let analysis = analyze(&conn).unwrap();
+        assert_eq!(analysis.tuning, tuning);
+        assert_eq!(analysis.journal_mode, "wal");
+        assert!(analysis.objects.iter().any(|object| object.name == "t" && object.pages > 1));
+
+        let done = checkpoint(&conn).unwrap();
+        assert!(!done.busy);
+        assert_eq!(done.wal_frames, done.checkpointed_frames);
+        assert_eq!(std::fs::metadata(format!("{}-wal", path.display())).unwrap().len(), 0);
+    }
+}
@@ -1,8 +1,60 @@
 //! Database infrastructure module
 //!
-//! Re-exports the Database implementation from model::core for backward compatibility.
-//! New code should use model::core::Database directly.
+//! `sqlite::Database` is the storage of the application, with the domain
+//! repositories implemented on top of it. `model::core` re-exports it and its
+//! records, so older imports keep working; new code imports them from here.
+//! `database()` is the instance opened at startup, for every layer, and
+//! `user_repository()`/`database_stats_repository()` the repositories over it.
 
-// Re-export for backward compatibility
-#[allow(unused_imports)]
-pub use crate::model::core::Database;
+pub mod changes;
+pub mod counter_event_store;
+pub mod counter_repository;
+pub mod database_stats_repository;
+#[cfg(feature = "encrypted-db")]
+pub mod encryption;
+pub mod maintenance;
+pub mod query;
+pub mod read_pool;
+pub mod settings_repository;
+pub mod sqlite;
+pub mod user_repository;
+
+pub use counter_event_store::SqliteCounterEventStore;
+pub use counter_repository::SqliteCounterRepository;
+pub use database_stats_repository::SqliteDatabaseStatsRepository;
+pub use settings_repository::SqliteSettingsRepository;
+pub use sqlite::Database;
+pub use user_repository::SqliteUserRepository;
+
+use lazy_static::lazy_static;
+use std::sync::{Arc, Mutex};
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+
+// Shared database reference using lazy static
+lazy_static! {
+    pub static ref DATABASE: Arc<Mutex<Option<Arc<Database>>>> = Arc::new(Mutex::new(None));
+}
+
+pub fn init_database(db: Arc<Database>) {
+    let mut db_guard = DATABASE.lock().unwrap();
+    *db_guard = Some(db);
+}
+
+/// The database opened at startup
+pub fn database() -> AppResult<Arc<Database>> {
+    DATABASE
+        .lock()
+        .map_err(|_| AppError::new(ErrorCode::DatabaseError, "Database lock poisoned"))?
+        .clone()
+        .ok_or_else(|| AppError::new(ErrorCode::DatabaseError, "Database not initialized"))
+}
+
+/// `UserRepository` over the shared database
+pub fn user_repository() -> AppResult<SqliteUserRepository> {
+    database().map(SqliteUserRepository::new)
+}
+
+/// `DatabaseStatsRepository` over the shared database
+pub fn database_stats_repository() -> AppResult<SqliteDatabaseStatsRepository> {
+    database().map(SqliteDatabaseStatsRepository::new)
+}
@@ -0,0 +1,296 @@
+//! Read-only connections next to the single writer, `[database] read_connections`
+//!
+//! SQLite in WAL mode lets readers work while a write is in progress, but
+//! not through one connection behind one mutex. `Database` therefore keeps
+//! its writer and a pool of read-only connections: read-heavy queries
+//! (`get_users`, stats, `db.query`, settings and counter reads) check one out
+//! and never wait for a write, each seeing the last committed state. With no
+//! readers (an in-memory database, `read_connections = 0`) reads go through
+//! the writer as before. Both sides count how often callers had to wait and
+//! for how long, reported by `/api/devtools/db/pool`.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Condvar, LockResult, Mutex, MutexGuard, TryLockError};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Waits for one side of the database
+#[derive(Debug, Default)]
+pub struct Contention {
+    acquired: AtomicU64,
+    contended: AtomicU64,
+    wait_us: AtomicU64,
+    max_wait_us: AtomicU64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ContentionStats {
+    pub acquired: u64,
+    /// Acquisitions that had to wait
+    pub contended: u64,
+    pub wait_ms_total: f64,
+    pub max_wait_ms: f64,
+}
+
+impl Contention {
+    fn record(&self, waited: Option<Duration>) {
+        self.acquired.fetch_add(1, Ordering::Relaxed);
+        if let Some(waited) = waited {
+            let us = waited.as_micros() as u64;
+            self.contended.fetch_add(1, Ordering::Relaxed);
+            self.wait_us.fetch_add(us, Ordering::Relaxed);
+            self.max_wait_us.fetch_max(us, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stats(&self) -> ContentionStats {
+        ContentionStats {
+            acquired: self.acquired.load(Ordering::Relaxed),
+            contended: self.contended.load(Ordering::Relaxed),
+            wait_ms_total: self.wait_us.load(Ordering::Relaxed) as f64 / 1000.0,
+            max_wait_ms: self.max_wait_us.load(Ordering::Relaxed) as f64 / 1000.0,
+        }
+    }
+}
+
+/// A mutex that counts how often `lock` had to wait
+#[derive(Debug, Default)]
+pub struct TimedMutex<T> {
+    inner: Mutex<T>,
+    contention: Contention,
+}
+
+impl<T> TimedMutex<T> {
+    pub fn new(value: T) -> Self {
+        Self { inner: Mutex::new(value), contention: Contention::default() }
+    }
+
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+        match self.inner.try_lock() {
+            Ok(guard) => {
+                self.contention.record(None);
+                Ok(guard)
+            }
+            Err(TryLockError::WouldBlock) => {
+                let started = Instant::now();
+                let guard = self.inner.lock();
+                self.contention.record(Some(started.elapsed()));
+                guard
+            }
+            Err(TryLockError::Poisoned(poisoned)) => Err(poisoned),
+        }
+    }
+
+    pub fn contention(&self) -> ContentionStats {
+        self.contention.stats()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PoolStats {
+    /// Read-only connections; 0 when reads share the writer
+    pub readers: usize,
+    pub idle_readers: usize,
+    pub writer: ContentionStats,
+    pub reads: ContentionStats,
+}
+
+#[derive(Default)]
+pub struct ReadPool {
+    idle: Mutex<Vec<Connection>>,
+    returned: Condvar,
+    size: usize,
+    /// Readers checked out; changed with `idle` locked
+    checked_out: AtomicUsize,
+    closed: AtomicBool,
+    contention: Contention,
+}
+
+/// A checked out reader, back in the pool when dropped
+pub struct PooledReader<'a> {
+    pool: &'a ReadPool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledReader<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("reader is held until dropped")
+    }
+}
+
+impl Drop for PooledReader<'_> {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else {
+            return;
+        };
+        let mut idle = self.pool.idle();
+        self.pool.checked_out.fetch_sub(1, Ordering::AcqRel);
+        if self.pool.closed.load(Ordering::Acquire) {
+            // `close` waits for the last reader out
+            drop(conn);
+            self.pool.returned.notify_all();
+            return;
+        }
+        idle.push(conn);
+        self.pool.returned.notify_one();
+    }
+}
+
+impl ReadPool {
+    pub fn new(readers: Vec<Connection>) -> Self {
+        Self {
+            size: readers.len(),
+            idle: Mutex::new(readers),
+            ..Self::default()
+        }
+    }
+
+    fn idle(&self) -> MutexGuard<'_, Vec<Connection>> {
+        self.idle.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn size(&self) -> usize {
+        if self.closed.load(Ordering::Acquire) {
+            0
+        } else {
+            self.size
+        }
+    }
+
+    /// A free reader, waiting for one if all are busy; `None` without readers
+    pub fn get(&self) -> Option<PooledReader<'_>> {
+        if self.size() == 0 {
+            return None;
+        }
+        let mut idle = self.idle();
+        let mut waited = None;
+        if idle.is_empty() {
+            let started = Instant::now();
+            while idle.is_empty() {
+                if self.closed.load(Ordering::Acquire) {
+                    return None;
+                }
+                idle = self.returned.wait(idle).unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+            waited = Some(started.elapsed());
+        }
+        let conn = idle.pop();
+        if conn.is_some() {
+            self.checked_out.fetch_add(1, Ordering::AcqRel);
+        }
+        self.contention.record(waited);
+        conn.map(|conn| PooledReader { pool: self, conn: Some(conn) })
+    }
+
+    /// Close every reader, waiting for those checked out to come back, and return
+    /// how many there were; reads go to the writer until `reopen`
+    #[cfg_attr(not(feature = "encrypted-db"), allow(dead_code))]
+    pub fn close(&self) -> usize {
+        let mut idle = self.idle();
+        self.closed.store(true, Ordering::Release);
+        idle.clear();
+        self.returned.notify_all();
+        while self.checked_out.load(Ordering::Acquire) > 0 {
+            idle = self.returned.wait(idle).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+        self.size
+    }
+
+    /// Put the pool back in use with `readers` after `close`
+    #[cfg_attr(not(feature = "encrypted-db"), allow(dead_code))]
+    pub fn reopen(&self, readers: Vec<Connection>) {
+        let mut idle = self.idle();
+        debug_assert_eq!(readers.len(), self.size);
+        *idle = readers;
+        self.closed.store(false, Ordering::Release);
+        self.returned.notify_all();
+    }
+
+    pub fn stats(&self, writer: ContentionStats) -> PoolStats {
+        PoolStats {
+            readers: self.size(),
+            idle_readers: self.idle().len(),
+            writer,
+            reads: self.contention.stats(),
+        }
+    }
+}
+
+/// A connection to read with: a pooled reader, or the writer when there are none
+pub enum Reader<'a> {
+    Pooled(PooledReader<'a>),
+    Writer(MutexGuard<'a, Connection>),
+}
+
+impl Deref for Reader<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        match self {
+            Reader::Pooled(reader) => reader,
+            Reader::Writer(writer) => writer,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_readers_are_shared_and_waits_counted() {
+        let pool = Arc::new(ReadPool::new(vec![Connection::open_in_memory().unwrap()]));
+        let first = pool.get().unwrap();
+        assert_eq!(pool.stats(ContentionStats::default()).idle_readers, 0);
+
+        let waiting = {
+            let pool = Arc::clone(&pool);
+            std::thread::spawn(move || pool.get().map(|reader| reader.query_row("SELECT 1", [], |row| row.get::<_, i64>(0)).unwrap()))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        drop(first);
+        assert_eq!(waiting.join().unwrap(), Some(1));
+
+        let stats = pool.stats(ContentionStats::default());
+        assert_eq!((stats.readers, stats.idle_readers), (1, 1));
+        assert_eq!((stats.reads.acquired, stats.reads.contended), (2, 1));
+        assert!(stats.reads.max_wait_ms > 0.0);
+
+        pool.close();
+        assert!(pool.get().is_none());
+        assert!(ReadPool::default().get().is_none());
+
+        let writer = TimedMutex::new(0);
+        drop(writer.lock().unwrap());
+        assert_eq!(writer.contention().acquired, 1);
+        assert_eq!(writer.contention().contended, 0);
+    }
+
+    #[test]
+    fn test_close_waits_for_checked_out_readers() {
+        let open = || Connection::open_in_memory().unwrap();
+        let pool = Arc::new(ReadPool::new(vec![open(), open()]));
+        let held = pool.get().unwrap();
+
+        let closing = {
+            let pool = Arc::clone(&pool);
+            std::thread::spawn(move || pool.close())
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!closing.is_finished());
+        drop(held);
+        assert_eq!(closing.join().unwrap(), 2);
+        assert!(pool.get().is_none());
+        assert_eq!(pool.stats(ContentionStats::default()).idle_readers, 0);
+
+        pool.reopen(vec![open(), open()]);
+        assert_eq!(pool.stats(ContentionStats::default()).readers, 2);
+        assert!(pool.get().is_some());
+    }
+}
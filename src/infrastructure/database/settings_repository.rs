@@ -0,0 +1,79 @@
+//! SQLite-backed `SettingsRepository`
+
+use std::sync::Arc;
+use crate::core::domain::{DomainError, DomainResult, Setting, SettingsRepository};
+use crate::infrastructure::database::Database;
+
+pub struct SqliteSettingsRepository {
+    db: Arc<Database>,
+}
+
+impl SqliteSettingsRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+}
+
+fn repository_error(e: Box<dyn std::error::Error>) -> DomainError {
+    DomainError::RepositoryError(e.to_string())
+}
+
+#[async_trait::async_trait]
+impl SettingsRepository for SqliteSettingsRepository {
+    async fn get(&self, namespace: &str, key: &str, user_id: Option<i64>) -> DomainResult<Option<Setting>> {
+        self.db.get_setting(namespace, key, user_id).map_err(repository_error)
+    }
+
+    async fn list(&self, namespace: &str, user_id: Option<i64>) -> DomainResult<Vec<Setting>> {
+        self.db.list_settings(namespace, user_id).map_err(repository_error)
+    }
+
+    async fn save(&self, setting: Setting) -> DomainResult<Setting> {
+        self.db.save_setting(&setting).map_err(repository_error)?;
+        Ok(setting)
+    }
+
+    async fn delete(&self, namespace: &str, key: &str, user_id: Option<i64>) -> DomainResult<bool> {
+        self.db.delete_setting(namespace, key, user_id).map_err(repository_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{SubsecRound, Utc};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_scopes_are_kept_apart() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        let repo = SqliteSettingsRepository::new(Arc::new(db));
+        let setting = |key: &str, value, user_id| Setting {
+            namespace: "ui".to_string(),
+            key: key.to_string(),
+            value,
+            user_id,
+            // Stored timestamps keep milliseconds
+            updated_at: Utc::now().trunc_subsecs(3),
+        };
+
+        let global = repo.save(setting("theme", json!("light"), None)).await.unwrap();
+        let mine = repo.save(setting("theme", json!({ "name": "dark", "contrast": 2 }), Some(2))).await.unwrap();
+        repo.save(setting("density", json!("compact"), None)).await.unwrap();
+
+        assert_eq!(repo.get("ui", "theme", None).await.unwrap(), Some(global));
+        assert_eq!(repo.get("ui", "theme", Some(2)).await.unwrap(), Some(mine));
+        assert!(repo.get("ui", "theme", Some(3)).await.unwrap().is_none());
+        let keys: Vec<String> = repo.list("ui", None).await.unwrap().into_iter().map(|s| s.key).collect();
+        assert_eq!(keys, ["density", "theme"]);
+
+        // Saving again replaces the value
+        repo.save(setting("theme", json!("sepia"), None)).await.unwrap();
+        assert_eq!(repo.get("ui", "theme", None).await.unwrap().unwrap().value, json!("sepia"));
+
+        assert!(repo.delete("ui", "theme", Some(2)).await.unwrap());
+        assert!(!repo.delete("ui", "theme", Some(2)).await.unwrap());
+        assert_eq!(repo.list("ui", Some(2)).await.unwrap(), vec![]);
+    }
+}
@@ -0,0 +1,1811 @@
+//! The SQLite database of the application
+//!
+//! `Database` is the one implementation of storage: a writer connection,
+//! the read-only pool of `read_pool`, the change hooks of `changes` and the
+//! tuning of `maintenance`. The repositories of this module (`counter_repository`,
+//! `settings_repository`, `counter_event_store`) put the domain traits on top of
+//! it; `model::core` re-exports it and its records for older imports.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::info;
+use crate::core::domain::{Counter, RecordedCounterEvent, Setting, User, UserStatus};
+use crate::error_handling::{guards, AppError, AppResult, ErrorCode};
+use crate::infrastructure::database::changes::{self, ChangeLog};
+use crate::infrastructure::database::maintenance::{self, Checkpoint, DatabaseAnalysis, DatabaseTuning};
+use crate::infrastructure::database::query::{Filter, Order, Select};
+use crate::infrastructure::database::read_pool::{PoolStats, ReadPool, Reader, TimedMutex};
+use crate::model::core::AppConfig;
+
+/// Persisted geometry of a window, restored on the next start
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    pub window_id: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+}
+
+/// A client's session, kept across reconnects until it expires or is revoked
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UserSession {
+    pub id: String,
+    /// Address of the connection that opened it
+    pub client: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_seen_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    /// Locale of the messages sent to the client; the default one when unset
+    pub locale: Option<String>,
+}
+
+/// An event in the `outbox` table, stored with the change that caused it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutboxMessage {
+    /// Stays the same across redeliveries, so consumers can drop duplicates
+    pub id: String,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Failed attempts to publish it so far
+    pub attempts: i64,
+    pub last_error: Option<String>,
+}
+
+/// An outbound integration registered with `webhooks.register`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// Event names, or `name.*` patterns, it receives
+    pub events: Vec<String>,
+    /// Key of the HMAC-SHA256 request signatures, only shown on registration
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookDeliveryStatus {
+    /// Not sent yet, or waiting for a retry
+    Pending,
+    Delivered,
+    /// Given up after the last attempt
+    Failed,
+}
+
+impl WebhookDeliveryStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookDeliveryStatus::Pending => "pending",
+            WebhookDeliveryStatus::Delivered => "delivered",
+            WebhookDeliveryStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "pending" => Some(WebhookDeliveryStatus::Pending),
+            "delivered" => Some(WebhookDeliveryStatus::Delivered),
+            "failed" => Some(WebhookDeliveryStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// An event sent, or to be sent, to a webhook, as kept in `webhook_deliveries`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebhookDelivery {
+    /// Stays the same across retries, sent as `X-Webhook-Id`
+    pub id: String,
+    pub webhook_id: String,
+    pub event_id: String,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i64,
+    /// HTTP status of the last response
+    pub response_status: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// When a pending delivery is tried next
+    pub next_attempt_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// What an `auth_tokens` row lets its holder do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthTokenPurpose {
+    PasswordReset,
+    EmailVerification,
+}
+
+impl AuthTokenPurpose {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuthTokenPurpose::PasswordReset => "password_reset",
+            AuthTokenPurpose::EmailVerification => "email_verification",
+        }
+    }
+}
+
+/// Fixed-width UTC text, so stored timestamps also compare correctly in SQL
+fn sql_timestamp(time: &chrono::DateTime<chrono::Utc>) -> String {
+    time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Queue `event` in the outbox as part of the caller's transaction; the relay
+/// publishes it once the transaction is committed and `outbox::wake` is called
+fn enqueue_outbox(conn: &Connection, event: &str, payload: &serde_json::Value, now: &chrono::DateTime<chrono::Utc>) -> rusqlite::Result<String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = sql_timestamp(now);
+    conn.execute(
+        "INSERT INTO outbox (id, event, payload, created_at, next_attempt_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+        rusqlite::params![id, event, payload.to_string(), now],
+    )?;
+    Ok(id)
+}
+
+fn outbox_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<OutboxMessage> {
+    let payload: String = row.get(2)?;
+    Ok(OutboxMessage {
+        id: row.get(0)?,
+        event: row.get(1)?,
+        payload: serde_json::from_str(&payload)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?,
+        created_at: row_timestamp(row, 3)?,
+        attempts: row.get(4)?,
+        last_error: row.get(5)?,
+    })
+}
+
+fn webhook_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Webhook> {
+    let events: String = row.get(2)?;
+    Ok(Webhook {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        events: serde_json::from_str(&events)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?,
+        secret: row.get(3)?,
+        created_at: row_timestamp(row, 4)?,
+    })
+}
+
+const WEBHOOK_DELIVERY_COLUMNS: &[&str] = &[
+    "id",
+    "webhook_id",
+    "event_id",
+    "event",
+    "payload",
+    "status",
+    "attempts",
+    "response_status",
+    "last_error",
+    "created_at",
+    "next_attempt_at",
+    "delivered_at",
+];
+
+fn webhook_delivery_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<WebhookDelivery> {
+    let payload: String = row.get(4)?;
+    let status: String = row.get(5)?;
+    let optional_timestamp = |index: usize| match row.get::<_, Option<String>>(index)? {
+        Some(_) => row_timestamp(row, index).map(Some),
+        None => Ok(None),
+    };
+    Ok(WebhookDelivery {
+        id: row.get(0)?,
+        webhook_id: row.get(1)?,
+        event_id: row.get(2)?,
+        event: row.get(3)?,
+        payload: serde_json::from_str(&payload)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Text, Box::new(e)))?,
+        status: WebhookDeliveryStatus::parse(&status).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, format!("Unknown delivery status {}", status).into())
+        })?,
+        attempts: row.get(6)?,
+        response_status: row.get(7)?,
+        last_error: row.get(8)?,
+        created_at: row_timestamp(row, 9)?,
+        next_attempt_at: optional_timestamp(10)?,
+        delivered_at: optional_timestamp(11)?,
+    })
+}
+
+fn row_timestamp(row: &rusqlite::Row<'_>, index: usize) -> rusqlite::Result<chrono::DateTime<chrono::Utc>> {
+    let text: String = row.get(index)?;
+    chrono::DateTime::parse_from_rfc3339(&text)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+fn session_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<UserSession> {
+    Ok(UserSession {
+        id: row.get(0)?,
+        client: row.get(1)?,
+        created_at: row_timestamp(row, 2)?,
+        last_seen_at: row_timestamp(row, 3)?,
+        expires_at: row_timestamp(row, 4)?,
+        locale: row.get(5)?,
+    })
+}
+
+fn counter_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Counter> {
+    let timestamp = |index: usize| row_timestamp(row, index);
+
+    Ok(Counter {
+        id: row.get(0)?,
+        label: row.get(1)?,
+        value: row.get(2)?,
+        created_at: timestamp(3)?,
+        updated_at: timestamp(4)?,
+        version: row.get(5)?,
+    })
+}
+
+/// `settings.user_id` of global values; rowids of `users` start at 1
+const GLOBAL_SETTING_USER: i64 = 0;
+
+fn setting_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Setting> {
+    let value: String = row.get(2)?;
+    let user_id: i64 = row.get(3)?;
+    Ok(Setting {
+        namespace: row.get(0)?,
+        key: row.get(1)?,
+        value: serde_json::from_str(&value)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?,
+        user_id: (user_id != GLOBAL_SETTING_USER).then_some(user_id),
+        updated_at: row_timestamp(row, 4)?,
+    })
+}
+
+const USER_COLUMNS: &[&str] = &["id", "name", "email", "role", "version"];
+
+/// Fields `get_users` can sort by
+pub const USER_SORT_FIELDS: &[&str] = &["id", "name", "email", "role"];
+
+/// Which users `find_users` lists, in which order; the default is all of them by id
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserQuery {
+    pub role: Option<String>,
+    /// Part of the name or email, case-insensitive
+    pub search: Option<String>,
+    /// One of `USER_SORT_FIELDS`
+    pub sort: Option<String>,
+    pub order: Order,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+fn user_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<serde_json::Value> {
+    let (id, name, email, role, version): (i64, String, String, String, i64) =
+        (row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?);
+    Ok(serde_json::json!({
+        "id": id,
+        "name": name,
+        "email": email,
+        "role": role,
+        "version": version
+    }))
+}
+
+const USER_ENTITY_COLUMNS: &[&str] = &["id", "name", "email", "role", "version", "created_at", "updated_at"];
+
+/// The `User` entity of a `USER_ENTITY_COLUMNS` row; the table keeps no
+/// status, so stored users are active
+fn user_entity_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<User> {
+    let role: String = row.get(3)?;
+    let updated_at: Option<String> = row.get(6)?;
+    Ok(User {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        email: row.get(2)?,
+        role: serde_json::from_value(serde_json::Value::String(role.to_ascii_lowercase()))
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?,
+        status: UserStatus::Active,
+        version: row.get(4)?,
+        created_at: row_timestamp(row, 5)?,
+        updated_at: updated_at.map(|_| row_timestamp(row, 6)).transpose()?,
+    })
+}
+
+/// Add `column` to an existing table; SQLite has no `ADD COLUMN IF NOT EXISTS`
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if !columns.iter().any(|name| name == column) {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+        info!("Added column {}.{}", table, column);
+    }
+    Ok(())
+}
+
+/// `(day, kind, name, count)` row of the `analytics_daily` table
+pub type UsageCount = (String, String, String, u64);
+
+/// Fields changed by `Database::update_user`; `None` keeps the stored value
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UserChanges {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub role: Option<String>,
+}
+
+impl UserChanges {
+    pub fn validate(&self) -> AppResult<()> {
+        if let Some(name) = &self.name {
+            guards::require_in_range(name.trim().len(), 1, 100, ErrorCode::ValidationFailed, "Name length")?;
+        }
+        if let Some(email) = &self.email {
+            guards::require_that(email.contains('@'), ErrorCode::ValidationFailed, "Invalid email format")?;
+        }
+        if let Some(role) = &self.role {
+            guards::require_that(
+                matches!(role.as_str(), "admin" | "user" | "editor" | "viewer"),
+                ErrorCode::ValidationFailed,
+                format!("Unknown role: {}", role),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Database {
+    connection: Arc<TimedMutex<Connection>>,
+    /// Read-only connections for `reader()`, from `[database] read_connections`
+    readers: ReadPool,
+    /// Row changes made through `connection`, published as `data.changed`
+    changes: Arc<ChangeLog>,
+}
+
+/// SQLite value for a JSON parameter; arrays and objects are stored as JSON text
+fn sql_value(value: &serde_json::Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value;
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(text) => Value::Text(text.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+impl Database {
+    pub fn new(db_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_connection(Connection::open(db_path)?, db_path)
+    }
+
+    /// Open the configured database, through SQLCipher when `[database] encrypted = true`,
+    /// and apply the PRAGMAs of `[database]`
+    pub fn open(config: &AppConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut db = Self::open_file(config)?;
+        let tuning = DatabaseTuning::from_config(config);
+        {
+            let conn = db.connection.lock().unwrap();
+            tuning.apply(&conn)?;
+            db.changes = ChangeLog::install(&conn, &config.get_db_change_events_ignore());
+        }
+        // Every connection to ":memory:" is a database of its own
+        let path = config.get_db_path();
+        if !path.is_empty() && path != ":memory:" && !path.starts_with("file::memory:") {
+            let readers = (0..config.get_db_read_connections())
+                .map(|_| Self::open_reader(config, &tuning))
+                .collect::<Result<Vec<_>, _>>()?;
+            db.readers = ReadPool::new(readers);
+        }
+        Ok(db)
+    }
+
+    fn open_read_only(path: &str) -> rusqlite::Result<Connection> {
+        Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX)
+    }
+
+    fn open_reader(config: &AppConfig, tuning: &DatabaseTuning) -> Result<Connection, Box<dyn std::error::Error>> {
+        let conn = Self::open_read_only(config.get_db_path())?;
+        #[cfg(feature = "encrypted-db")]
+        if config.is_db_encrypted() {
+            crate::infrastructure::database::encryption::unlock(&conn, config)?;
+        }
+        tuning.apply(&conn)?;
+        Ok(conn)
+    }
+
+    /// A connection for queries that only read: a pooled reader, which never
+    /// waits for a write, or the writer when there are no readers
+    fn reader(&self) -> Result<Reader<'_>, &'static str> {
+        match self.readers.get() {
+            Some(reader) => Ok(Reader::Pooled(reader)),
+            None => self
+                .connection
+                .lock()
+                .map(Reader::Writer)
+                .map_err(|_| "Database connection lock is poisoned"),
+        }
+    }
+
+    /// Readers, and how long reads and writes waited for a connection
+    pub fn pool_stats(&self) -> PoolStats {
+        self.readers.stats(self.connection.contention())
+    }
+
+    fn open_file(config: &AppConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        if !config.is_db_encrypted() {
+            return Self::new(config.get_db_path());
+        }
+        #[cfg(feature = "encrypted-db")]
+        {
+            let conn = crate::infrastructure::database::encryption::open(config)?;
+            Self::with_connection(conn, config.get_db_path())
+        }
+        #[cfg(not(feature = "encrypted-db"))]
+        Err("[database] encrypted = true needs a build with the encrypted-db feature".into())
+    }
+
+    fn with_connection(conn: Connection, db_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        // Enable WAL mode for better concurrency
+        conn.execute_batch("PRAGMA journal_mode=WAL;")?;
+
+        // Emit database connection event
+        if let Ok(bus) =
+            std::panic::catch_unwind(crate::infrastructure::event_bus::EventBus::global)
+        {
+            if let Err(e) = futures::executor::block_on(bus.emit_simple(
+                &crate::infrastructure::event_bus::AppEventType::DatabaseOperation.to_string(),
+                serde_json::json!({
+                    "operation": "connect",
+                    "database": db_path
+                }),
+            )) {
+                eprintln!("Failed to emit database connection event: {}", e);
+            }
+        }
+
+        let ignored: Vec<String> = changes::DEFAULT_IGNORED.iter().map(|table| table.to_string()).collect();
+        Ok(Database {
+            changes: ChangeLog::install(&conn, &ignored),
+            connection: Arc::new(TimedMutex::new(conn)),
+            readers: ReadPool::default(),
+        })
+    }
+
+    /// Re-encrypt the database file under `key`. The readers are closed first, as
+    /// the file cannot leave WAL mode while they have it open, and reopened with `key`
+    #[cfg(feature = "encrypted-db")]
+    pub fn rekey(&self, config: &AppConfig, key: &str) -> Result<(), crate::infrastructure::database::encryption::EncryptionError> {
+        use crate::infrastructure::database::encryption;
+
+        let conn = self.connection.lock().unwrap();
+        let readers = self.readers.close();
+        // Reads share the writer until the readers are back, or for good if the rekey failed
+        encryption::rekey(&conn, key)?;
+        if readers > 0 {
+            let tuning = DatabaseTuning::from_config(config);
+            let reopened = (0..readers)
+                .map(|_| {
+                    let reader = Self::open_read_only(config.get_db_path())?;
+                    encryption::apply_key(&reader, key)?;
+                    tuning.apply(&reader)?;
+                    Ok(reader)
+                })
+                .collect::<Result<Vec<_>, encryption::EncryptionError>>();
+            match reopened {
+                Ok(reopened) => self.readers.reopen(reopened),
+                // The file is rekeyed either way; failing here would lose the new key
+                Err(e) => tracing::warn!("Could not reopen the database readers, reads share the writer: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn init(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS users (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                email TEXT NOT NULL,
+                role TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS window_state (
+                window_id TEXT PRIMARY KEY,
+                x INTEGER NOT NULL,
+                y INTEGER NOT NULL,
+                width INTEGER NOT NULL,
+                height INTEGER NOT NULL,
+                maximized INTEGER NOT NULL DEFAULT 0,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS counters (
+                id TEXT PRIMARY KEY,
+                label TEXT NOT NULL,
+                value INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1
+            )",
+            [],
+        )?;
+
+        // Events waiting to be published, written in the transaction of their change
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS outbox (
+                id TEXT PRIMARY KEY,
+                event TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at TEXT NOT NULL,
+                last_error TEXT,
+                published_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_outbox_pending ON outbox (published_at, next_attempt_at);",
+        )?;
+
+        // Registered webhooks and the log of what was sent to them
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS webhooks (
+                id TEXT PRIMARY KEY,
+                url TEXT NOT NULL,
+                events TEXT NOT NULL,
+                secret TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS webhook_deliveries (
+                id TEXT PRIMARY KEY,
+                webhook_id TEXT NOT NULL,
+                event_id TEXT NOT NULL,
+                event TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                status TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                response_status INTEGER,
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                next_attempt_at TEXT,
+                delivered_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries (status, next_attempt_at);
+            CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries (webhook_id, created_at);",
+        )?;
+
+        // Event store of event-sourced counters, with a snapshot of each stream
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS counter_events (
+                stream_id TEXT NOT NULL,
+                sequence INTEGER NOT NULL,
+                event_type TEXT NOT NULL,
+                data TEXT NOT NULL,
+                recorded_at TEXT NOT NULL,
+                PRIMARY KEY (stream_id, sequence)
+            );
+            CREATE TABLE IF NOT EXISTS counter_snapshots (
+                stream_id TEXT PRIMARY KEY,
+                sequence INTEGER NOT NULL,
+                state TEXT NOT NULL
+            );",
+        )?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                id TEXT PRIMARY KEY,
+                client TEXT,
+                created_at TEXT NOT NULL,
+                last_seen_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_sessions_expires_at ON sessions (expires_at);",
+        )?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS settings (
+                namespace TEXT NOT NULL,
+                key TEXT NOT NULL,
+                user_id INTEGER NOT NULL DEFAULT 0,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (namespace, key, user_id)
+            );",
+        )?;
+
+        // Only a hash of each token is stored; the token itself is mailed
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS auth_tokens (
+                token_hash TEXT PRIMARY KEY,
+                user_id INTEGER NOT NULL,
+                purpose TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL,
+                used_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_auth_tokens_user ON auth_tokens (user_id, purpose);",
+        )?;
+
+        // Usage counts per UTC day, see infrastructure::analytics
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS analytics_daily (
+                day TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                count INTEGER NOT NULL,
+                PRIMARY KEY (day, kind, name)
+            );",
+        )?;
+
+        // Updates of the shared documents of the `collab` feature, in the order they were applied
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS collab_updates (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                document TEXT NOT NULL,
+                data BLOB NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_collab_updates_document ON collab_updates (document, id);",
+        )?;
+
+        // Databases created before optimistic locking lack the version columns
+        add_column_if_missing(&conn, "users", "version", "INTEGER NOT NULL DEFAULT 1")?;
+        add_column_if_missing(&conn, "counters", "version", "INTEGER NOT NULL DEFAULT 1")?;
+        add_column_if_missing(&conn, "users", "password_hash", "TEXT")?;
+        add_column_if_missing(&conn, "users", "email_verified_at", "TEXT")?;
+        // Users from before the timestamps count as created now
+        add_column_if_missing(&conn, "users", "created_at", "TEXT")?;
+        add_column_if_missing(&conn, "users", "updated_at", "TEXT")?;
+        conn.execute("UPDATE users SET created_at = ?1 WHERE created_at IS NULL", [sql_timestamp(&chrono::Utc::now())])?;
+        add_column_if_missing(&conn, "sessions", "locale", "TEXT")?;
+        // Only a hash of the resume token is stored; sessions from before it cannot be resumed
+        add_column_if_missing(&conn, "sessions", "resume_token_hash", "TEXT")?;
+        conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_sessions_resume_token ON sessions (resume_token_hash);")?;
+
+        // Emit database initialization event
+        if let Ok(bus) =
+            std::panic::catch_unwind(crate::infrastructure::event_bus::EventBus::global)
+        {
+            if let Err(e) = futures::executor::block_on(bus.emit_simple(
+                &crate::infrastructure::event_bus::AppEventType::DatabaseOperation.to_string(),
+                serde_json::json!({
+                    "operation": "init_schema",
+                    "table": "users"
+                }),
+            )) {
+                eprintln!("Failed to emit database initialization event: {}", e);
+            }
+        }
+
+        info!("Database schema initialized");
+        Ok(())
+    }
+
+    pub fn insert_sample_data(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        // Insert sample users if table is empty
+        let count: i64 = tx.query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0))?;
+
+        if count == 0 {
+            let sample_users = [
+                ("John Doe", "john@example.com", "admin"),
+                ("Jane Smith", "jane@example.com", "editor"),
+                ("Bob Johnson", "bob@example.com", "user"),
+                ("Alice Brown", "alice@example.com", "user"),
+            ];
+
+            let now = sql_timestamp(&chrono::Utc::now());
+            for (name, email, role) in &sample_users {
+                tx.execute(
+                    "INSERT INTO users (name, email, role, created_at) VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![name, email, role, now],
+                )?;
+            }
+            tx.commit()?;
+
+            info!("Sample data inserted into database");
+        } else {
+            info!("Sample data already exists, skipping insertion");
+        }
+
+        Ok(())
+    }
+
+    /// The users matching `query`, ordered and paged by it
+    pub fn find_users(&self, query: &UserQuery) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error>> {
+        let sort = query.sort.as_deref().unwrap_or("id");
+        let mut select = Select::from("users")
+            .columns(USER_COLUMNS)
+            .filter_opt(query.role.clone().map(|role| Filter::eq("role", role)))
+            .filter_opt(query.search.as_deref().map(|text| Filter::any([Filter::contains("name", text), Filter::contains("email", text)])))
+            .order_by_field(sort, USER_SORT_FIELDS, query.order)?;
+        if sort != "id" {
+            select = select.order_by("id", Order::Asc);
+        }
+        if let Some(limit) = query.limit {
+            select = select.limit(limit);
+        }
+        let conn = self.reader()?;
+        let users = select.offset(query.offset).query_map(&conn, user_from_row)?;
+
+        // Emit get users event
+        if let Ok(bus) =
+            std::panic::catch_unwind(crate::infrastructure::event_bus::EventBus::global)
+        {
+            if let Err(e) = futures::executor::block_on(bus.emit_simple(
+                &crate::infrastructure::event_bus::AppEventType::DatabaseOperation.to_string(),
+                serde_json::json!({
+                    "operation": "get_users",
+                    "count": users.len()
+                }),
+            )) {
+                eprintln!("Failed to emit get users event: {}", e);
+            }
+        }
+
+        Ok(users)
+    }
+
+    pub fn get_user(&self, id: i64) -> Result<Option<serde_json::Value>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let user = conn
+            .query_row("SELECT id, name, email, role, version FROM users WHERE id = ?1", [id], user_from_row)
+            .optional()?;
+        Ok(user)
+    }
+
+    pub fn get_user_by_email(&self, email: &str) -> Result<Option<serde_json::Value>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let user = conn
+            .query_row(
+                "SELECT id, name, email, role, version FROM users WHERE email = ?1 COLLATE NOCASE",
+                [email.trim()],
+                user_from_row,
+            )
+            .optional()?;
+        Ok(user)
+    }
+
+    /// The `User` entities matching `filter`, by id
+    pub fn user_entities(&self, filter: Option<Filter>) -> Result<Vec<User>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let users = Select::from("users")
+            .columns(USER_ENTITY_COLUMNS)
+            .filter_opt(filter)
+            .order_by("id", Order::Asc)
+            .query_map(&conn, user_entity_from_row)?;
+        Ok(users)
+    }
+
+    /// Store the hash of a new token for `user_id`; earlier tokens of the user for
+    /// the same purpose stop working, and expired ones are removed
+    pub fn insert_auth_token(
+        &self,
+        token_hash: &str,
+        user_id: i64,
+        purpose: AuthTokenPurpose,
+        now: &chrono::DateTime<chrono::Utc>,
+        expires_at: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM auth_tokens WHERE expires_at <= ?1 OR (user_id = ?2 AND purpose = ?3)",
+            rusqlite::params![sql_timestamp(now), user_id, purpose.as_str()],
+        )?;
+        tx.execute(
+            "INSERT INTO auth_tokens (token_hash, user_id, purpose, created_at, expires_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![token_hash, user_id, purpose.as_str(), sql_timestamp(now), sql_timestamp(expires_at)],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Mark an unused, unexpired token as used; returns its user
+    pub fn consume_auth_token(
+        &self,
+        token_hash: &str,
+        purpose: AuthTokenPurpose,
+        now: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let user_id = conn
+            .query_row(
+                "UPDATE auth_tokens SET used_at = ?3
+                 WHERE token_hash = ?1 AND purpose = ?2 AND used_at IS NULL AND expires_at > ?3
+                 RETURNING user_id",
+                rusqlite::params![token_hash, purpose.as_str(), sql_timestamp(now)],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(user_id)
+    }
+
+    pub fn set_password_hash(&self, user_id: i64, password_hash: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE users SET password_hash = ?2, version = version + 1 WHERE id = ?1",
+            rusqlite::params![user_id, password_hash],
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn set_email_verified(&self, user_id: i64, now: &chrono::DateTime<chrono::Utc>) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE users SET email_verified_at = ?2 WHERE id = ?1",
+            rusqlite::params![user_id, sql_timestamp(now)],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Apply `changes` to user `id` only if it is still at `expected_version`
+    ///
+    /// A stale version fails with `ErrorCode::ConflictError`; the error context
+    /// carries the current record so the client can merge and retry.
+    pub fn update_user(
+        &self,
+        id: i64,
+        changes: &UserChanges,
+        expected_version: i64,
+    ) -> AppResult<serde_json::Value> {
+        changes.validate()?;
+
+        let db_error = |e: rusqlite::Error| {
+            AppError::new(ErrorCode::DatabaseError, "Failed to update user").with_cause(e.to_string())
+        };
+
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(db_error)?;
+        let updated = tx
+            .execute(
+                "UPDATE users SET
+                    name = COALESCE(?1, name),
+                    email = COALESCE(?2, email),
+                    email_verified_at = CASE WHEN ?2 IS NULL OR ?2 = email THEN email_verified_at END,
+                    role = COALESCE(?3, role),
+                    version = version + 1,
+                    updated_at = ?6
+                 WHERE id = ?4 AND version = ?5",
+                rusqlite::params![
+                    changes.name.as_deref().map(str::trim),
+                    changes.email,
+                    changes.role,
+                    id,
+                    expected_version,
+                    sql_timestamp(&chrono::Utc::now())
+                ],
+            )
+            .map_err(db_error)?;
+
+        let user = tx
+            .query_row(
+                "SELECT id, name, email, role, version FROM users WHERE id = ?1",
+                [id],
+                user_from_row,
+            )
+            .optional()
+            .map_err(db_error)?
+            .ok_or_else(|| {
+                AppError::new(ErrorCode::EntityNotFound, format!("User {} not found", id))
+                    .with_context("id", id)
+            })?;
+
+        if updated == 0 {
+            let current_version = user["version"].as_i64().unwrap_or_default();
+            return Err(AppError::conflict("User", id, expected_version, current_version)
+                .with_context("current", user));
+        }
+
+        tx.commit().map_err(db_error)?;
+        Ok(user)
+    }
+
+    /// Insert a user, under `id` when given (as when a deletion is undone)
+    pub fn create_user(&self, id: Option<i64>, name: &str, email: &str, role: &str) -> AppResult<serde_json::Value> {
+        UserChanges {
+            name: Some(name.to_string()),
+            email: Some(email.to_string()),
+            role: Some(role.to_string()),
+        }
+        .validate()?;
+
+        let db_error = |e: rusqlite::Error| {
+            AppError::new(ErrorCode::DatabaseError, "Failed to create user").with_cause(e.to_string())
+        };
+
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(db_error)?;
+        let user = tx
+            .query_row(
+                "INSERT INTO users (id, name, email, role, created_at) VALUES (?1, ?2, ?3, ?4, ?5)
+                 RETURNING id, name, email, role, version",
+                rusqlite::params![id, name.trim(), email, role, sql_timestamp(&chrono::Utc::now())],
+                user_from_row,
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::SqliteFailure(ref failure, _) if failure.code == rusqlite::ErrorCode::ConstraintViolation => {
+                    AppError::new(ErrorCode::BusinessRuleViolation, format!("User {} already exists", id.unwrap_or_default()))
+                        .with_context("id", id)
+                }
+                e => db_error(e),
+            })?;
+
+        tx.commit().map_err(db_error)?;
+        Ok(user)
+    }
+
+    /// Delete user `id` only if it is still at `expected_version`; returns the deleted record
+    pub fn delete_user(&self, id: i64, expected_version: i64) -> AppResult<serde_json::Value> {
+        let db_error = |e: rusqlite::Error| {
+            AppError::new(ErrorCode::DatabaseError, "Failed to delete user").with_cause(e.to_string())
+        };
+
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction().map_err(db_error)?;
+        let user = tx
+            .query_row("SELECT id, name, email, role, version FROM users WHERE id = ?1", [id], user_from_row)
+            .optional()
+            .map_err(db_error)?
+            .ok_or_else(|| {
+                AppError::new(ErrorCode::EntityNotFound, format!("User {} not found", id)).with_context("id", id)
+            })?;
+        let current_version = user["version"].as_i64().unwrap_or_default();
+        if current_version != expected_version {
+            return Err(AppError::conflict("User", id, expected_version, current_version)
+                .with_context("current", user));
+        }
+        tx.execute("DELETE FROM users WHERE id = ?1", [id]).map_err(db_error)?;
+
+        tx.commit().map_err(db_error)?;
+        Ok(user)
+    }
+
+    /// Cheap round trip used by the readiness check
+    pub fn ping(&self) -> AppResult<()> {
+        let conn = self
+            .connection
+            .lock()
+            .map_err(|_| AppError::new(ErrorCode::DatabaseError, "Database connection lock is poisoned"))?;
+        conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0))
+            .map_err(|e| AppError::new(ErrorCode::DatabaseError, format!("Database query failed: {}", e)))?;
+        Ok(())
+    }
+
+    /// Run `sql` with JSON `params` and return the rows as objects keyed by
+    /// column; with `read_only`, statements that would write are refused
+    pub fn query_json(
+        &self,
+        sql: &str,
+        params: &[serde_json::Value],
+        read_only: bool,
+    ) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error>> {
+        let conn = if read_only {
+            self.reader()?
+        } else {
+            Reader::Writer(self.connection.lock().map_err(|_| "Database connection lock is poisoned")?)
+        };
+        let mut stmt = conn.prepare(sql)?;
+        if read_only && !stmt.readonly() {
+            return Err("Statement is not read-only".into());
+        }
+        let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+        let mut rows = stmt.query(rusqlite::params_from_iter(params.iter().map(sql_value)))?;
+
+        let mut result = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut object = serde_json::Map::new();
+            for (index, column) in columns.iter().enumerate() {
+                let value = match row.get_ref(index)? {
+                    rusqlite::types::ValueRef::Null => serde_json::Value::Null,
+                    rusqlite::types::ValueRef::Integer(i) => serde_json::json!(i),
+                    rusqlite::types::ValueRef::Real(f) => serde_json::json!(f),
+                    rusqlite::types::ValueRef::Text(text) => serde_json::json!(String::from_utf8_lossy(text)),
+                    rusqlite::types::ValueRef::Blob(blob) => serde_json::json!(blob),
+                };
+                object.insert(column.clone(), value);
+            }
+            result.push(serde_json::Value::Object(object));
+        }
+        Ok(result)
+    }
+
+    /// Run a statement with JSON `params`; returns the number of changed rows
+    pub fn execute_json(&self, sql: &str, params: &[serde_json::Value]) -> Result<usize, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().map_err(|_| "Database connection lock is poisoned")?;
+        Ok(conn.execute(sql, rusqlite::params_from_iter(params.iter().map(sql_value)))?)
+    }
+
+    /// User count and table names; `DatabaseStatsRepository::get_stats` announces them
+    pub fn get_db_stats(&self) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+
+        // Get user count
+        let user_count: i64 = conn.query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0))?;
+
+        // Get table names
+        let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type='table'")?;
+        let table_names: Vec<String> = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<String>, _>>()?;
+
+        Ok(serde_json::json!({
+            "users": user_count,
+            "tables": table_names
+        }))
+    }
+
+    /// Size of the database file, from its page count
+    pub fn size_bytes(&self) -> Result<i64, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        Ok(conn.query_row("SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()", [], |row| row.get(0))?)
+    }
+
+    /// Rows of all tables together
+    pub fn total_records(&self) -> Result<i64, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'")?;
+        let tables = stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut total = 0i64;
+        for table in tables {
+            let sql = format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\""));
+            total += conn.query_row(&sql, [], |row| row.get::<_, i64>(0))?;
+        }
+        Ok(total)
+    }
+
+    pub fn save_window_state(&self, state: &WindowState) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+
+        conn.execute(
+            "INSERT INTO window_state (window_id, x, y, width, height, maximized, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(window_id) DO UPDATE SET
+                x = excluded.x,
+                y = excluded.y,
+                width = excluded.width,
+                height = excluded.height,
+                maximized = excluded.maximized,
+                updated_at = excluded.updated_at",
+            rusqlite::params![
+                state.window_id,
+                state.x,
+                state.y,
+                state.width,
+                state.height,
+                state.maximized,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn load_window_state(&self, window_id: &str) -> Result<Option<WindowState>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+
+        let state = conn
+            .query_row(
+                "SELECT window_id, x, y, width, height, maximized FROM window_state WHERE window_id = ?1",
+                [window_id],
+                |row| {
+                    Ok(WindowState {
+                        window_id: row.get(0)?,
+                        x: row.get(1)?,
+                        y: row.get(2)?,
+                        width: row.get(3)?,
+                        height: row.get(4)?,
+                        maximized: row.get(5)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        Ok(state)
+    }
+
+    pub fn get_counters(&self) -> Result<Vec<Counter>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, label, value, created_at, updated_at, version FROM counters ORDER BY created_at, id",
+        )?;
+        let counters = stmt
+            .query_map([], counter_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(counters)
+    }
+
+    pub fn get_counter(&self, id: &str) -> Result<Option<Counter>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let counter = conn
+            .query_row(
+                "SELECT id, label, value, created_at, updated_at, version FROM counters WHERE id = ?1",
+                [id],
+                counter_from_row,
+            )
+            .optional()?;
+        Ok(counter)
+    }
+
+    /// Insert or update `counter`; returns false when the stored row is no
+    /// longer at `counter.version` (it was changed since it was read)
+    pub fn save_counter(&self, counter: &Counter) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let written = conn.execute(
+            "INSERT INTO counters (id, label, value, created_at, updated_at, version)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6 + 1)
+             ON CONFLICT(id) DO UPDATE SET
+                label = excluded.label,
+                value = excluded.value,
+                updated_at = excluded.updated_at,
+                version = excluded.version
+             WHERE counters.version = ?6",
+            rusqlite::params![
+                counter.id,
+                counter.label,
+                counter.value,
+                counter.created_at.to_rfc3339(),
+                counter.updated_at.to_rfc3339(),
+                counter.version,
+            ],
+        )?;
+        Ok(written > 0)
+    }
+
+    pub fn delete_counter(&self, id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM counters WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
+
+    /// Append `events` to the stream of counter `id` if its last event is still
+    /// `expected_version` (0 for a new stream); returns false otherwise
+    pub fn append_counter_events(
+        &self,
+        id: &str,
+        expected_version: i64,
+        events: &[RecordedCounterEvent],
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+        let last: i64 = tx.query_row(
+            "SELECT COALESCE(MAX(sequence), 0) FROM counter_events WHERE stream_id = ?1",
+            [id],
+            |row| row.get(0),
+        )?;
+        if last != expected_version {
+            return Ok(false);
+        }
+        for recorded in events {
+            let data = serde_json::to_value(&recorded.event)?;
+            tx.execute(
+                "INSERT INTO counter_events (stream_id, sequence, event_type, data, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    id,
+                    recorded.sequence,
+                    data["type"].as_str().unwrap_or_default(),
+                    data.to_string(),
+                    sql_timestamp(&recorded.recorded_at)
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Events of counter `id` after `after_sequence`, oldest first
+    pub fn get_counter_events(&self, id: &str, after_sequence: i64) -> Result<Vec<RecordedCounterEvent>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let rows = Select::from("counter_events")
+            .columns(&["sequence", "recorded_at", "data"])
+            .filter(Filter::eq("stream_id", id.to_string()))
+            .filter(Filter::gt("sequence", after_sequence))
+            .order_by("sequence", Order::Asc)
+            .query_map(&conn, |row| Ok((row.get::<_, i64>(0)?, row_timestamp(row, 1)?, row.get::<_, String>(2)?)))?;
+        let events = rows
+            .into_iter()
+            .map(|(sequence, recorded_at, data)| {
+                Ok(RecordedCounterEvent { sequence, recorded_at, event: serde_json::from_str(&data)? })
+            })
+            .collect::<Result<Vec<_>, serde_json::Error>>()?;
+        Ok(events)
+    }
+
+    /// Ids of the counters with an event stream
+    pub fn get_counter_stream_ids(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare("SELECT DISTINCT stream_id FROM counter_events ORDER BY stream_id")?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(ids)
+    }
+
+    /// The state of counter `id` at its latest snapshot
+    pub fn get_counter_snapshot(&self, id: &str) -> Result<Option<Counter>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let state: Option<String> = conn
+            .query_row("SELECT state FROM counter_snapshots WHERE stream_id = ?1", [id], |row| row.get(0))
+            .optional()?;
+        Ok(state.map(|state| serde_json::from_str(&state)).transpose()?)
+    }
+
+    pub fn save_counter_snapshot(&self, counter: &Counter) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "INSERT INTO counter_snapshots (stream_id, sequence, state) VALUES (?1, ?2, ?3)
+             ON CONFLICT(stream_id) DO UPDATE SET sequence = excluded.sequence, state = excluded.state",
+            rusqlite::params![counter.id, counter.version, serde_json::to_string(counter)?],
+        )?;
+        Ok(())
+    }
+
+    /// Remove the events and snapshot of counter `id`; returns whether it had any
+    pub fn delete_counter_stream(&self, id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+        let deleted = tx.execute("DELETE FROM counter_events WHERE stream_id = ?1", [id])?;
+        tx.execute("DELETE FROM counter_snapshots WHERE stream_id = ?1", [id])?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    /// Queue the `data.changed` events of the row changes committed since the
+    /// last call, due at `now`; returns how many were queued
+    pub fn record_changes(&self, now: &chrono::DateTime<chrono::Utc>) -> Result<usize, Box<dyn std::error::Error>> {
+        let changes = self.changes.take();
+        if changes.is_empty() {
+            return Ok(0);
+        }
+        let events = changes::events(&changes);
+        let event = crate::infrastructure::event_bus::AppEventType::DataChanged.to_string();
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+        for payload in &events {
+            enqueue_outbox(&tx, &event, payload, now)?;
+        }
+        tx.commit()?;
+        Ok(events.len())
+    }
+
+    /// Unpublished outbox events due by `now`, oldest first
+    pub fn pending_outbox(&self, now: &chrono::DateTime<chrono::Utc>, limit: usize) -> Result<Vec<OutboxMessage>, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, event, payload, created_at, attempts, last_error FROM outbox
+             WHERE published_at IS NULL AND next_attempt_at <= ?1
+             ORDER BY created_at, rowid LIMIT ?2",
+        )?;
+        let messages = stmt
+            .query_map(rusqlite::params![sql_timestamp(now), limit as i64], outbox_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(messages)
+    }
+
+    pub fn mark_outbox_published(&self, id: &str, now: &chrono::DateTime<chrono::Utc>) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE outbox SET published_at = ?2, last_error = NULL WHERE id = ?1",
+            rusqlite::params![id, sql_timestamp(now)],
+        )?;
+        Ok(())
+    }
+
+    /// Count a failed attempt and hold the event back until `retry_at`
+    pub fn mark_outbox_failed(&self, id: &str, error: &str, retry_at: &chrono::DateTime<chrono::Utc>) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE outbox SET attempts = attempts + 1, last_error = ?2, next_attempt_at = ?3 WHERE id = ?1",
+            rusqlite::params![id, error, sql_timestamp(retry_at)],
+        )?;
+        Ok(())
+    }
+
+    /// Remove events published before `before`; returns how many
+    pub fn delete_published_outbox(&self, before: &chrono::DateTime<chrono::Utc>) -> Result<usize, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        Ok(conn.execute(
+            "DELETE FROM outbox WHERE published_at IS NOT NULL AND published_at < ?1",
+            [sql_timestamp(before)],
+        )?)
+    }
+
+    /// Add usage counts to the daily totals
+    pub fn add_usage_counts(&self, counts: &[UsageCount]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+        for (day, kind, name, count) in counts {
+            tx.execute(
+                "INSERT INTO analytics_daily (day, kind, name, count) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (day, kind, name) DO UPDATE SET count = count + excluded.count",
+                rusqlite::params![day, kind, name, *count as i64],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Usage counts of the days from `first_day` on, oldest first
+    pub fn usage_counts(&self, first_day: &str) -> Result<Vec<UsageCount>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT day, kind, name, count FROM analytics_daily WHERE day >= ?1 ORDER BY day, kind, name",
+        )?;
+        let counts = stmt
+            .query_map([first_day], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get::<_, i64>(3)? as u64)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(counts)
+    }
+
+    /// Remove the usage counts of days before `day`; returns how many
+    pub fn delete_usage_counts_before(&self, day: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        Ok(conn.execute("DELETE FROM analytics_daily WHERE day < ?1", [day])?)
+    }
+
+    /// Unpublished events: how many, how many of them have failed, and the oldest one
+    pub fn outbox_backlog(&self) -> Result<(i64, i64, Option<OutboxMessage>), Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let (pending, failing) = conn.query_row(
+            "SELECT COUNT(*), COUNT(*) FILTER (WHERE attempts > 0) FROM outbox WHERE published_at IS NULL",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let oldest = conn
+            .query_row(
+                "SELECT id, event, payload, created_at, attempts, last_error FROM outbox
+                 WHERE published_at IS NULL ORDER BY created_at, rowid LIMIT 1",
+                [],
+                outbox_from_row,
+            )
+            .optional()?;
+        Ok((pending, failing, oldest))
+    }
+
+    pub fn get_setting(&self, namespace: &str, key: &str, user_id: Option<i64>) -> Result<Option<Setting>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let setting = conn
+            .query_row(
+                "SELECT namespace, key, value, user_id, updated_at FROM settings
+                 WHERE namespace = ?1 AND key = ?2 AND user_id = ?3",
+                rusqlite::params![namespace, key, user_id.unwrap_or(GLOBAL_SETTING_USER)],
+                setting_from_row,
+            )
+            .optional()?;
+        Ok(setting)
+    }
+
+    pub fn list_settings(&self, namespace: &str, user_id: Option<i64>) -> Result<Vec<Setting>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT namespace, key, value, user_id, updated_at FROM settings
+             WHERE namespace = ?1 AND user_id = ?2 ORDER BY key",
+        )?;
+        let settings = stmt
+            .query_map(rusqlite::params![namespace, user_id.unwrap_or(GLOBAL_SETTING_USER)], setting_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(settings)
+    }
+
+    pub fn save_setting(&self, setting: &Setting) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "INSERT INTO settings (namespace, key, user_id, value, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (namespace, key, user_id) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+            rusqlite::params![
+                setting.namespace,
+                setting.key,
+                setting.user_id.unwrap_or(GLOBAL_SETTING_USER),
+                serde_json::to_string(&setting.value)?,
+                sql_timestamp(&setting.updated_at),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_setting(&self, namespace: &str, key: &str, user_id: Option<i64>) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let deleted = conn.execute(
+            "DELETE FROM settings WHERE namespace = ?1 AND key = ?2 AND user_id = ?3",
+            rusqlite::params![namespace, key, user_id.unwrap_or(GLOBAL_SETTING_USER)],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    pub fn insert_session(&self, session: &UserSession, resume_token_hash: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "INSERT INTO sessions (id, client, created_at, last_seen_at, expires_at, locale, resume_token_hash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                session.id,
+                session.client,
+                sql_timestamp(&session.created_at),
+                sql_timestamp(&session.last_seen_at),
+                sql_timestamp(&session.expires_at),
+                session.locale,
+                resume_token_hash,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The session whose resume token hashes to `resume_token_hash`, if it has not expired by `now`
+    pub fn get_session_by_resume_token(
+        &self,
+        resume_token_hash: &str,
+        now: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<UserSession>, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let session = conn
+            .query_row(
+                "SELECT id, client, created_at, last_seen_at, expires_at, locale FROM sessions
+                 WHERE resume_token_hash = ?1 AND expires_at > ?2",
+                rusqlite::params![resume_token_hash, sql_timestamp(now)],
+                session_from_row,
+            )
+            .optional()?;
+        Ok(session)
+    }
+
+    /// Record activity at `last_seen_at` and move the expiry; false when the
+    /// session is gone or already expired
+    pub fn refresh_session(
+        &self,
+        id: &str,
+        last_seen_at: &chrono::DateTime<chrono::Utc>,
+        expires_at: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let last_seen_at = sql_timestamp(last_seen_at);
+        let updated = conn.execute(
+            "UPDATE sessions SET last_seen_at = ?2, expires_at = ?3 WHERE id = ?1 AND expires_at > ?2",
+            rusqlite::params![id, last_seen_at, sql_timestamp(expires_at)],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Set or clear the locale of session `id`; false when it is gone
+    pub fn set_session_locale(&self, id: &str, locale: Option<&str>) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let updated = conn.execute("UPDATE sessions SET locale = ?2 WHERE id = ?1", rusqlite::params![id, locale])?;
+        Ok(updated > 0)
+    }
+
+    /// Unexpired sessions, most recently active first
+    pub fn list_sessions(&self, now: &chrono::DateTime<chrono::Utc>) -> Result<Vec<UserSession>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, client, created_at, last_seen_at, expires_at, locale FROM sessions
+             WHERE expires_at > ?1 ORDER BY last_seen_at DESC",
+        )?;
+        let sessions = stmt
+            .query_map([sql_timestamp(now)], session_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(sessions)
+    }
+
+    pub fn delete_session(&self, id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM sessions WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
+
+    /// Remove sessions expired by `now`; returns how many
+    pub fn delete_expired_sessions(&self, now: &chrono::DateTime<chrono::Utc>) -> Result<usize, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        Ok(conn.execute("DELETE FROM sessions WHERE expires_at <= ?1", [sql_timestamp(now)])?)
+    }
+
+    /// Rebuild the database file to reclaim free pages
+    pub fn vacuum(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute_batch("VACUUM;")?;
+        Ok(())
+    }
+
+    /// Copy the WAL back into the database file and truncate it
+    pub fn checkpoint(&self) -> Result<Checkpoint, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        Ok(maintenance::checkpoint(&conn)?)
+    }
+
+    /// Page counts, fragmentation and the PRAGMAs in effect
+    pub fn analyze(&self) -> Result<DatabaseAnalysis, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        Ok(maintenance::analyze(&conn)?)
+    }
+
+    /// Write a consistent copy of the database to `path`
+    pub fn backup_to(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute("VACUUM INTO ?1", [path.to_string_lossy()])?;
+        Ok(())
+    }
+}
+
+/// `webhooks` and `webhook_deliveries`, used by the `webhooks` feature
+#[cfg_attr(not(feature = "webhooks"), allow(dead_code))]
+impl Database {
+    pub fn insert_webhook(&self, webhook: &Webhook) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "INSERT INTO webhooks (id, url, events, secret, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                webhook.id,
+                webhook.url,
+                serde_json::to_string(&webhook.events)?,
+                webhook.secret,
+                sql_timestamp(&webhook.created_at)
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_webhooks(&self) -> Result<Vec<Webhook>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare("SELECT id, url, events, secret, created_at FROM webhooks ORDER BY created_at, id")?;
+        let webhooks = stmt.query_map([], webhook_from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(webhooks)
+    }
+
+    /// Remove a webhook with its delivery log; false if there was none
+    pub fn delete_webhook(&self, id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM webhook_deliveries WHERE webhook_id = ?1", [id])?;
+        let deleted = tx.execute("DELETE FROM webhooks WHERE id = ?1", [id])?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    /// Queue `event` for webhook `webhook_id`, due right away; returns the delivery id
+    pub fn enqueue_webhook_delivery(
+        &self,
+        webhook_id: &str,
+        event_id: &str,
+        event: &str,
+        payload: &serde_json::Value,
+        now: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "INSERT INTO webhook_deliveries (id, webhook_id, event_id, event, payload, status, created_at, next_attempt_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+            rusqlite::params![
+                id,
+                webhook_id,
+                event_id,
+                event,
+                payload.to_string(),
+                WebhookDeliveryStatus::Pending.as_str(),
+                sql_timestamp(now)
+            ],
+        )?;
+        Ok(id)
+    }
+
+    /// Pending deliveries due by `now`, oldest first
+    pub fn due_webhook_deliveries(&self, now: &chrono::DateTime<chrono::Utc>, limit: usize) -> Result<Vec<WebhookDelivery>, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        let deliveries = Select::from("webhook_deliveries")
+            .columns(WEBHOOK_DELIVERY_COLUMNS)
+            .filter(Filter::eq("status", WebhookDeliveryStatus::Pending.as_str().to_string()))
+            .filter(Filter::le("next_attempt_at", sql_timestamp(now)))
+            .order_by("created_at", Order::Asc)
+            .order_by("rowid", Order::Asc)
+            .limit(limit)
+            .query_map(&conn, webhook_delivery_from_row)?;
+        Ok(deliveries)
+    }
+
+    /// Store the outcome of an attempt. A failed one is retried at `retry_at`,
+    /// or marks the delivery failed without it
+    pub fn record_webhook_attempt(
+        &self,
+        id: &str,
+        response_status: Option<u16>,
+        error: Option<&str>,
+        retry_at: Option<&chrono::DateTime<chrono::Utc>>,
+        now: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let status = match (error, retry_at) {
+            (None, _) => WebhookDeliveryStatus::Delivered,
+            (Some(_), Some(_)) => WebhookDeliveryStatus::Pending,
+            (Some(_), None) => WebhookDeliveryStatus::Failed,
+        };
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "UPDATE webhook_deliveries SET status = ?2, attempts = attempts + 1, response_status = ?3, last_error = ?4,
+                 next_attempt_at = ?5, delivered_at = ?6
+             WHERE id = ?1",
+            rusqlite::params![
+                id,
+                status.as_str(),
+                response_status,
+                error,
+                retry_at.map(sql_timestamp),
+                error.is_none().then(|| sql_timestamp(now))
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The delivery log, newest first, optionally of one webhook or status
+    pub fn list_webhook_deliveries(
+        &self,
+        webhook_id: Option<&str>,
+        status: Option<WebhookDeliveryStatus>,
+        limit: usize,
+    ) -> Result<Vec<WebhookDelivery>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let deliveries = Select::from("webhook_deliveries")
+            .columns(WEBHOOK_DELIVERY_COLUMNS)
+            .filter_opt(webhook_id.map(|id| Filter::eq("webhook_id", id.to_string())))
+            .filter_opt(status.map(|status| Filter::eq("status", status.as_str().to_string())))
+            .order_by("created_at", Order::Desc)
+            .order_by("rowid", Order::Desc)
+            .limit(limit)
+            .query_map(&conn, webhook_delivery_from_row)?;
+        Ok(deliveries)
+    }
+
+    /// Remove finished deliveries created before `before`; returns how many
+    pub fn delete_webhook_deliveries(&self, before: &chrono::DateTime<chrono::Utc>) -> Result<usize, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        Ok(conn.execute(
+            "DELETE FROM webhook_deliveries WHERE status != ?1 AND created_at < ?2",
+            rusqlite::params![WebhookDeliveryStatus::Pending.as_str(), sql_timestamp(before)],
+        )?)
+    }
+}
+
+/// `collab_updates`, used by the `collab` feature
+#[cfg_attr(not(feature = "collab"), allow(dead_code))]
+impl Database {
+    /// The stored updates of `document`, oldest first
+    pub fn collab_updates(&self, document: &str) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+        let conn = self.reader()?;
+        let mut stmt = conn.prepare_cached("SELECT data FROM collab_updates WHERE document = ?1 ORDER BY id")?;
+        let updates = stmt.query_map([document], |row| row.get(0))?.collect::<Result<Vec<Vec<u8>>, _>>()?;
+        Ok(updates)
+    }
+
+    /// Store an update of `document`; returns how many it has now
+    pub fn append_collab_update(&self, document: &str, data: &[u8]) -> Result<u64, Box<dyn std::error::Error>> {
+        let conn = self.connection.lock().unwrap();
+        conn.execute(
+            "INSERT INTO collab_updates (document, data, created_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![document, data, sql_timestamp(&chrono::Utc::now())],
+        )?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM collab_updates WHERE document = ?1", [document], |row| row.get(0))?;
+        Ok(count as u64)
+    }
+
+    /// Replace the updates of `document` with `state`, one update holding all of them
+    pub fn compact_collab_updates(&self, document: &str, state: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conn = self.connection.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM collab_updates WHERE document = ?1", [document])?;
+        tx.execute(
+            "INSERT INTO collab_updates (document, data, created_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![document, state, sql_timestamp(&chrono::Utc::now())],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "encrypted-db")]
+    use crate::tests::testkit::TempDir;
+
+    #[test]
+    fn test_update_user_detects_stale_version() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        db.insert_sample_data().unwrap();
+
+        let rename = UserChanges {
+            name: Some("John Q. Doe".to_string()),
+            ..Default::default()
+        };
+        let user = db.update_user(1, &rename, 1).unwrap();
+        assert_eq!(user["version"], 2);
+        assert_eq!(user["name"], "John Q. Doe");
+
+        let conflict = db.update_user(1, &rename, 1).unwrap_err();
+        assert_eq!(conflict.code, ErrorCode::ConflictError);
+        assert_eq!(conflict.context["current_version"], 2);
+        assert_eq!(conflict.context["current"]["name"], "John Q. Doe");
+
+        let missing = db.update_user(99, &rename, 1).unwrap_err();
+        assert_eq!(missing.code, ErrorCode::EntityNotFound);
+    }
+
+    #[test]
+    fn test_find_users_filters_sorts_and_pages() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        db.insert_sample_data().unwrap();
+        let names = |query: UserQuery| -> Vec<String> {
+            db.find_users(&query).unwrap().iter().map(|user| user["name"].as_str().unwrap().to_string()).collect()
+        };
+
+        let all = names(UserQuery::default());
+        assert_eq!(all.len(), db.user_entities(None).unwrap().len());
+        let by_name = names(UserQuery { sort: Some("name".to_string()), order: Order::Desc, ..Default::default() });
+        let mut expected = all.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(by_name, expected);
+        assert_eq!(names(UserQuery { limit: Some(1), offset: 1, ..Default::default() }), all[1..2]);
+        assert_eq!(names(UserQuery { search: Some("JOHN".to_string()), ..Default::default() }), ["John Doe", "Bob Johnson"]);
+        let users = UserQuery { role: Some("user".to_string()), sort: Some("name".to_string()), ..Default::default() };
+        assert_eq!(names(users), ["Alice Brown", "Bob Johnson"]);
+        assert!(names(UserQuery { search: Some("%".to_string()), ..Default::default() }).is_empty());
+        assert!(db.find_users(&UserQuery { sort: Some("version".to_string()), ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn test_query_json() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        db.insert_sample_data().unwrap();
+
+        let rows = db.query_json("SELECT id, name FROM users WHERE id = ?1", &[serde_json::json!(1)], true).unwrap();
+        assert_eq!(rows, vec![serde_json::json!({ "id": 1, "name": "John Doe" })]);
+        assert!(db.query_json("DELETE FROM users", &[], true).is_err());
+        assert_eq!(db.execute_json("DELETE FROM users WHERE id = ?1", &[serde_json::json!(1)]).unwrap(), 1);
+    }
+
+    #[cfg(feature = "encrypted-db")]
+    #[test]
+    fn test_rekey_reopens_the_readers() {
+        let dir = TempDir::new("rekey-test");
+        let mut config = AppConfig::default();
+        config.database.path = dir.join("app.db").to_string_lossy().into_owned();
+        config.database.encrypted = Some(true);
+        config.database.encryption_key = Some("first".to_string());
+        config.database.read_connections = Some(2);
+
+        let db = Database::open(&config).unwrap();
+        db.init().unwrap();
+        db.insert_sample_data().unwrap();
+        let users = db.get_db_stats().unwrap()["users"].clone();
+        db.rekey(&config, "second").unwrap();
+
+        // The readers are open again, under the new key
+        assert_eq!(db.pool_stats().readers, 2);
+        assert_eq!(db.get_db_stats().unwrap()["users"], users);
+        config.database.encryption_key = Some("second".to_string());
+        drop(db);
+        assert_eq!(Database::open(&config).unwrap().get_db_stats().unwrap()["users"], users);
+    }
+}
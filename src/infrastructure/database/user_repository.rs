@@ -0,0 +1,136 @@
+//! SQLite-backed `UserRepository`
+
+use std::sync::Arc;
+use crate::core::domain::{DomainError, DomainResult, User, UserRepository, UserRole, UserStatus};
+use crate::infrastructure::database::query::Filter;
+use crate::infrastructure::database::sqlite::UserChanges;
+use crate::infrastructure::database::Database;
+
+pub struct SqliteUserRepository {
+    db: Arc<Database>,
+}
+
+impl SqliteUserRepository {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+
+    fn find(&self, filter: Filter) -> DomainResult<Option<User>> {
+        Ok(self.db.user_entities(Some(filter)).map_err(repository_error)?.into_iter().next())
+    }
+
+    /// User `id` as just written
+    fn stored(&self, id: i64) -> DomainResult<User> {
+        self.find(Filter::eq("id", id))?.ok_or_else(|| DomainError::NotFound(format!("User {}", id)))
+    }
+}
+
+fn repository_error(e: Box<dyn std::error::Error>) -> DomainError {
+    DomainError::RepositoryError(e.to_string())
+}
+
+/// The `role` column value of `role`
+fn role_name(role: UserRole) -> String {
+    serde_json::to_value(role)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// The `users` table has no status column, so only active users can be stored
+fn require_active(user: &User) -> DomainResult<()> {
+    if user.status != UserStatus::Active {
+        return Err(DomainError::ValidationError(format!("Users are stored as active, not {:?}", user.status)));
+    }
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl UserRepository for SqliteUserRepository {
+    async fn get_all(&self) -> DomainResult<Vec<User>> {
+        self.db.user_entities(None).map_err(repository_error)
+    }
+
+    async fn get_by_id(&self, id: i64) -> DomainResult<Option<User>> {
+        self.find(Filter::eq("id", id))
+    }
+
+    /// Case-insensitive, like the password reset lookup
+    async fn get_by_email(&self, email: &str) -> DomainResult<Option<User>> {
+        match self.db.get_user_by_email(email).map_err(repository_error)? {
+            Some(user) => self.get_by_id(user["id"].as_i64().unwrap_or_default()).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Stored under `user.id`, or a new id when it is 0
+    async fn create(&self, user: User) -> DomainResult<User> {
+        require_active(&user)?;
+        let id = (user.id != 0).then_some(user.id);
+        let created = self.db.create_user(id, &user.name, &user.email, &role_name(user.role))?;
+        self.stored(created["id"].as_i64().unwrap_or_default())
+    }
+
+    /// Fails with `VersionConflict` unless the stored user is still at `user.version`
+    async fn update(&self, user: User) -> DomainResult<User> {
+        require_active(&user)?;
+        let changes = UserChanges {
+            name: Some(user.name),
+            email: Some(user.email),
+            role: Some(role_name(user.role)),
+        };
+        self.db.update_user(user.id, &changes, user.version)?;
+        self.stored(user.id)
+    }
+
+    async fn delete(&self, id: i64) -> DomainResult<()> {
+        let user = self.stored(id)?;
+        self.db.delete_user(id, user.version)?;
+        Ok(())
+    }
+
+    async fn get_by_role(&self, role: UserRole) -> DomainResult<Vec<User>> {
+        self.db.user_entities(Some(Filter::eq("role", role_name(role)))).map_err(repository_error)
+    }
+
+    async fn get_by_status(&self, status: UserStatus) -> DomainResult<Vec<User>> {
+        match status {
+            UserStatus::Active => self.get_all().await,
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_user_round_trip() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        let repo = SqliteUserRepository::new(Arc::new(db));
+
+        let new = User::new(0, "Ann".to_string(), "ann@example.com".to_string(), UserRole::Editor, UserStatus::Active).unwrap();
+        let ann = repo.create(new).await.unwrap();
+        assert_eq!((ann.version, ann.updated_at), (1, None));
+        assert_eq!(repo.get_by_email("ANN@example.com").await.unwrap(), Some(ann.clone()));
+        assert_eq!(repo.get_by_role(UserRole::Editor).await.unwrap(), vec![ann.clone()]);
+        assert!(repo.get_by_status(UserStatus::Suspended).await.unwrap().is_empty());
+
+        let mut promoted = ann.clone();
+        promoted.update_role(UserRole::Admin);
+        let promoted = repo.update(promoted).await.unwrap();
+        assert_eq!((promoted.role, promoted.version), (UserRole::Admin, 2));
+        assert!(promoted.updated_at.is_some());
+        assert!(matches!(repo.update(ann.clone()).await, Err(DomainError::VersionConflict { expected: 1, actual: 2, .. })));
+
+        let mut suspended = promoted.clone();
+        suspended.update_status(UserStatus::Suspended);
+        assert!(matches!(repo.update(suspended).await, Err(DomainError::ValidationError(_))));
+
+        repo.delete(ann.id).await.unwrap();
+        assert!(repo.get_by_id(ann.id).await.unwrap().is_none());
+        assert!(matches!(repo.delete(ann.id).await, Err(DomainError::NotFound(_))));
+    }
+}
@@ -0,0 +1,128 @@
+//! Native file dialogs - open/save pickers exposed to the frontend
+
+use rfd::AsyncFileDialog;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+/// File type filter shown in the dialog (e.g. "Images" -> ["png", "jpg"])
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogFilter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+/// Options accepted by all dialog commands
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DialogOptions {
+    pub title: Option<String>,
+    pub directory: Option<String>,
+    pub file_name: Option<String>,
+    pub filters: Vec<DialogFilter>,
+    pub multiple: bool,
+}
+
+impl DialogOptions {
+    fn builder(&self) -> AsyncFileDialog {
+        let mut dialog = AsyncFileDialog::new();
+
+        if let Some(title) = &self.title {
+            dialog = dialog.set_title(title);
+        }
+        if let Some(directory) = &self.directory {
+            dialog = dialog.set_directory(directory);
+        }
+        if let Some(file_name) = &self.file_name {
+            dialog = dialog.set_file_name(file_name);
+        }
+        for filter in &self.filters {
+            let extensions: Vec<&str> = filter.extensions.iter().map(|e| e.trim_start_matches('.')).collect();
+            dialog = dialog.add_filter(&filter.name, &extensions);
+        }
+
+        dialog
+    }
+}
+
+/// Let the user pick one or more files; an empty list means the dialog was cancelled
+pub async fn open_file(options: &DialogOptions) -> Vec<PathBuf> {
+    let dialog = options.builder();
+
+    if options.multiple {
+        dialog
+            .pick_files()
+            .await
+            .map(|files| files.iter().map(|f| f.path().to_path_buf()).collect())
+            .unwrap_or_default()
+    } else {
+        dialog
+            .pick_file()
+            .await
+            .map(|f| vec![f.path().to_path_buf()])
+            .unwrap_or_default()
+    }
+}
+
+/// Let the user pick one or more folders; an empty list means the dialog was cancelled
+pub async fn open_folder(options: &DialogOptions) -> Vec<PathBuf> {
+    let dialog = options.builder();
+
+    if options.multiple {
+        dialog
+            .pick_folders()
+            .await
+            .map(|folders| folders.iter().map(|f| f.path().to_path_buf()).collect())
+            .unwrap_or_default()
+    } else {
+        dialog
+            .pick_folder()
+            .await
+            .map(|f| vec![f.path().to_path_buf()])
+            .unwrap_or_default()
+    }
+}
+
+/// Let the user choose a destination file; `None` means the dialog was cancelled
+pub async fn save_file(options: &DialogOptions) -> Option<PathBuf> {
+    options.builder().save_file().await.map(|f| f.path().to_path_buf())
+}
+
+/// Handle `dialog.*` commands from the frontend
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    let options: DialogOptions = match serde_json::from_value(payload.clone()) {
+        Ok(options) => options,
+        Err(e) if payload.is_null() => {
+            debug!("No dialog options supplied ({}), using defaults", e);
+            DialogOptions::default()
+        }
+        Err(e) => {
+            warn!("Invalid dialog options for {}: {}", name, e);
+            return serde_json::json!({
+                "success": false,
+                "error": format!("Invalid dialog options: {}", e)
+            });
+        }
+    };
+
+    let paths = match name {
+        "dialog.open_file" => open_file(&options).await,
+        "dialog.open_folder" => open_folder(&options).await,
+        "dialog.save_file" => save_file(&options).await.into_iter().collect(),
+        _ => {
+            return serde_json::json!({
+                "success": false,
+                "error": format!("Unknown dialog command: {}", name)
+            });
+        }
+    };
+
+    debug!("{} returned {} path(s)", name, paths.len());
+
+    serde_json::json!({
+        "success": true,
+        "cancelled": paths.is_empty(),
+        "paths": paths.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>()
+    })
+}
@@ -0,0 +1,252 @@
+//! LAN discovery of other running instances over mDNS (`discovery` feature)
+//!
+//! Each instance advertises its WebSocket and HTTP ports as a
+//! `_rustwebui._tcp` service and browses for the same type. Peers found this
+//! way are listed by `peers.list` with a ready-to-use `ws_url`, and
+//! `peer.discovered` / `peer.lost` events are emitted as they come and go.
+
+use chrono::{DateTime, Utc};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Mutex, OnceLock, RwLock};
+use tracing::{debug, info, warn};
+use crate::infrastructure::event_bus::EventBus;
+use crate::model::core::AppConfig;
+
+/// Another instance seen on the network
+#[derive(Debug, Clone, Serialize)]
+pub struct Peer {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub host: String,
+    pub addresses: Vec<String>,
+    pub ws_port: u16,
+    pub http_port: Option<u16>,
+    /// `ws://<address>:<ws_port>`, preferring an IPv4 address
+    pub ws_url: Option<String>,
+    pub last_seen: DateTime<Utc>,
+}
+
+pub struct Discovery {
+    instance_id: String,
+    daemon: Mutex<Option<ServiceDaemon>>,
+    /// Keyed by the mDNS full name, which `ServiceRemoved` refers to
+    peers: RwLock<HashMap<String, Peer>>,
+}
+
+/// Build a peer from a resolved service; `None` for ourselves or incomplete records
+fn peer_from_info(info: &ServiceInfo, own_id: &str) -> Option<Peer> {
+    let id = info.get_property_val_str("id")?;
+    if id == own_id {
+        return None;
+    }
+    let ws_port = info.get_property_val_str("ws_port")?.parse().ok()?;
+
+    let mut addresses: Vec<&IpAddr> = info.get_addresses().iter().collect();
+    addresses.sort_by_key(|addr| (addr.is_ipv6(), addr.to_string()));
+    let ws_url = addresses.first().map(|addr| match addr {
+        IpAddr::V4(v4) => format!("ws://{}:{}", v4, ws_port),
+        IpAddr::V6(v6) => format!("ws://[{}]:{}", v6, ws_port),
+    });
+
+    Some(Peer {
+        id: id.to_string(),
+        name: info.get_property_val_str("name").unwrap_or_default().to_string(),
+        version: info.get_property_val_str("version").unwrap_or_default().to_string(),
+        host: info.get_hostname().trim_end_matches('.').to_string(),
+        addresses: addresses.iter().map(|addr| addr.to_string()).collect(),
+        ws_port,
+        http_port: info.get_property_val_str("http_port").and_then(|port| port.parse().ok()),
+        ws_url,
+        last_seen: Utc::now(),
+    })
+}
+
+/// mDNS instance labels are limited to 63 bytes and best kept to `[A-Za-z0-9-]`
+fn instance_label(app_name: &str, instance_id: &str) -> String {
+    let name: String = app_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .take(50)
+        .collect();
+    format!("{}-{}", name.trim_matches('-'), &instance_id[..8])
+}
+
+fn emit(event: &str, payload: Value) {
+    if let Err(e) = futures::executor::block_on(EventBus::global().emit_simple(event, payload)) {
+        debug!("Failed to emit {}: {}", event, e);
+    }
+}
+
+impl Discovery {
+    fn new() -> Self {
+        Self {
+            instance_id: uuid::Uuid::new_v4().simple().to_string(),
+            daemon: Mutex::new(None),
+            peers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.daemon.lock().map(|daemon| daemon.is_some()).unwrap_or(false)
+    }
+
+    /// Advertise this instance and start browsing for others
+    pub fn start(&'static self, config: &AppConfig, ws_port: u16, http_port: u16) -> Result<(), String> {
+        let mut daemon_slot = self.daemon.lock().map_err(|_| "Discovery lock poisoned".to_string())?;
+        if daemon_slot.is_some() {
+            return Ok(());
+        }
+
+        let service_type = config.get_discovery_service_type();
+        let daemon = ServiceDaemon::new().map_err(|e| format!("Cannot start mDNS daemon: {}", e))?;
+        let host = sysinfo::System::host_name().unwrap_or_else(|| "localhost".to_string());
+        let (ws_port_text, http_port_text) = (ws_port.to_string(), http_port.to_string());
+        let properties = [
+            ("id", self.instance_id.as_str()),
+            ("name", config.get_app_name()),
+            ("version", config.get_version()),
+            ("ws_port", ws_port_text.as_str()),
+            ("http_port", http_port_text.as_str()),
+        ];
+        let service = ServiceInfo::new(
+            service_type,
+            &instance_label(config.get_app_name(), &self.instance_id),
+            &format!("{}.local.", host),
+            "",
+            ws_port,
+            &properties[..],
+        )
+        .map_err(|e| format!("Invalid mDNS service: {}", e))?
+        .enable_addr_auto();
+
+        daemon
+            .register(service)
+            .map_err(|e| format!("Cannot advertise on mDNS: {}", e))?;
+        let events = daemon
+            .browse(service_type)
+            .map_err(|e| format!("Cannot browse mDNS: {}", e))?;
+        info!("Advertising {} on mDNS as {}", service_type, self.instance_id);
+
+        std::thread::Builder::new()
+            .name("mdns-discovery".to_string())
+            .spawn(move || {
+                // Ends when the daemon shuts down and drops the sender
+                while let Ok(event) = events.recv() {
+                    self.handle_event(event);
+                }
+            })
+            .map_err(|e| format!("Cannot start discovery thread: {}", e))?;
+
+        *daemon_slot = Some(daemon);
+        Ok(())
+    }
+
+    fn handle_event(&self, event: ServiceEvent) {
+        match event {
+            ServiceEvent::ServiceResolved(info) => {
+                let Some(peer) = peer_from_info(&info, &self.instance_id) else {
+                    return;
+                };
+                let Ok(mut peers) = self.peers.write() else {
+                    return;
+                };
+                let is_new = peers.insert(info.get_fullname().to_string(), peer.clone()).is_none();
+                drop(peers);
+                if is_new {
+                    info!("Discovered peer {} at {:?}", peer.name, peer.ws_url);
+                    emit("peer.discovered", serde_json::to_value(&peer).unwrap_or_default());
+                }
+            }
+            ServiceEvent::ServiceRemoved(_, fullname) => {
+                let removed = self.peers.write().ok().and_then(|mut peers| peers.remove(&fullname));
+                if let Some(peer) = removed {
+                    info!("Peer {} left", peer.name);
+                    emit("peer.lost", serde_json::json!({ "id": peer.id, "name": peer.name }));
+                }
+            }
+            other => debug!("mDNS: {:?}", other),
+        }
+    }
+
+    /// Known peers, most recently seen first
+    pub fn peers(&self) -> Vec<Peer> {
+        let mut peers: Vec<Peer> = self
+            .peers
+            .read()
+            .map(|peers| peers.values().cloned().collect())
+            .unwrap_or_default();
+        peers.sort_by_key(|peer| std::cmp::Reverse(peer.last_seen));
+        peers
+    }
+
+    /// Withdraw the advertisement and stop browsing
+    pub fn stop(&self) {
+        let daemon = self.daemon.lock().ok().and_then(|mut daemon| daemon.take());
+        if let Some(daemon) = daemon {
+            if let Err(e) = daemon.shutdown() {
+                warn!("Failed to shut down mDNS daemon: {}", e);
+            }
+            if let Ok(mut peers) = self.peers.write() {
+                peers.clear();
+            }
+        }
+    }
+}
+
+pub fn discovery() -> &'static Discovery {
+    static DISCOVERY: OnceLock<Discovery> = OnceLock::new();
+    DISCOVERY.get_or_init(Discovery::new)
+}
+
+/// Handle `peers.*` commands from the frontend
+pub fn handle_command(name: &str, _payload: &Value) -> Value {
+    let discovery = discovery();
+
+    match name {
+        "peers.list" => serde_json::json!({
+            "success": true,
+            "advertising": discovery.is_running(),
+            "instance_id": discovery.instance_id,
+            "peers": discovery.peers(),
+        }),
+        _ => serde_json::json!({
+            "success": false,
+            "error": format!("Unknown peers command: {}", name)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(id: &str, addresses: &str) -> ServiceInfo {
+        let properties = [("id", id), ("name", "Demo"), ("version", "1.0.0"), ("ws_port", "9001"), ("http_port", "8081")];
+        ServiceInfo::new("_rustwebui._tcp.local.", "demo", "demo-host.local.", addresses, 9001, &properties[..]).unwrap()
+    }
+
+    #[test]
+    fn test_peer_from_info() {
+        let peer = peer_from_info(&service("other", "fe80::1,192.168.1.20"), "mine").unwrap();
+        assert_eq!(peer.name, "Demo");
+        assert_eq!(peer.host, "demo-host.local");
+        assert_eq!(peer.http_port, Some(8081));
+        assert_eq!(peer.ws_url.as_deref(), Some("ws://192.168.1.20:9001"));
+        assert_eq!(peer.addresses.len(), 2);
+    }
+
+    #[test]
+    fn test_own_service_is_not_a_peer() {
+        assert!(peer_from_info(&service("mine", "192.168.1.20"), "mine").is_none());
+    }
+
+    #[test]
+    fn test_instance_label() {
+        assert_eq!(instance_label("Rust WebUI Application", "0123456789abcdef"), "Rust-WebUI-Application-01234567");
+    }
+}
@@ -0,0 +1,175 @@
+//! Summaries in place of high-frequency events, `[batching]`
+//!
+//! An event matching a batched topic is counted instead of emitted: it reaches
+//! no subscriber, client, webhook or broker. Every `interval_ms` the topic's
+//! counts go out as one `summary` event (`database.activity` for the default
+//! `database.operation` topic), and nothing goes out for an idle interval.
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use super::{event_matches, Event, EventBus};
+use crate::model::core::{AppConfig, BatchTopicSettings};
+
+/// Interval of a topic that does not set one
+const DEFAULT_INTERVAL_MS: u64 = 2000;
+
+struct Pending {
+    from: DateTime<Utc>,
+    count: u64,
+    counts: BTreeMap<String, u64>,
+}
+
+pub struct Topic {
+    pattern: String,
+    summary: String,
+    interval: Duration,
+    group_by: Option<String>,
+    pending: Mutex<Option<Pending>>,
+}
+
+impl Topic {
+    pub fn new(pattern: &str, settings: &BatchTopicSettings) -> Self {
+        let summary = settings
+            .summary
+            .clone()
+            .unwrap_or_else(|| format!("{}.summary", pattern.trim_end_matches(".*").trim_end_matches('*')));
+        Self {
+            pattern: pattern.to_string(),
+            summary,
+            interval: Duration::from_millis(settings.interval_ms.unwrap_or(DEFAULT_INTERVAL_MS).max(100)),
+            group_by: settings.group_by.clone(),
+            pending: Mutex::default(),
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        name != self.summary && event_matches(&self.pattern, name)
+    }
+
+    fn count(&self, event: &Event) {
+        let key = self
+            .group_by
+            .as_ref()
+            .and_then(|field| event.payload.get(field))
+            .map(|value| value.as_str().map_or_else(|| value.to_string(), str::to_string))
+            .unwrap_or_else(|| event.name.clone());
+        if let Ok(mut pending) = self.pending.lock() {
+            let pending = pending.get_or_insert_with(|| Pending { from: Utc::now(), count: 0, counts: BTreeMap::new() });
+            pending.count += 1;
+            *pending.counts.entry(key).or_default() += 1;
+        }
+    }
+
+    /// The summary of the events counted since the last one, if there were any
+    pub fn take(&self) -> Option<Value> {
+        let pending = self.pending.lock().ok()?.take()?;
+        Some(serde_json::json!({
+            "topic": self.pattern,
+            "count": pending.count,
+            "counts": pending.counts,
+            "from": pending.from,
+            "to": Utc::now(),
+        }))
+    }
+}
+
+pub struct Batcher {
+    topics: Vec<Topic>,
+}
+
+impl Batcher {
+    pub fn new(topics: Vec<Topic>) -> Self {
+        Self { topics }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(
+            config
+                .get_batching_topics()
+                .iter()
+                .map(|(pattern, settings)| Topic::new(pattern, settings))
+                .collect(),
+        )
+    }
+
+    /// Count `event` if it belongs to a topic; false if it is emitted as usual
+    pub fn absorb(&self, event: &Event) -> bool {
+        match self.topics.iter().find(|topic| topic.matches(&event.name)) {
+            Some(topic) => {
+                topic.count(event);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Batch the configured topics of the global bus and emit their summaries
+pub fn start(config: &AppConfig) {
+    if !config.is_batching_enabled() {
+        return;
+    }
+    let bus = EventBus::global();
+    let batcher = bus.batcher.get_or_init(|| Batcher::from_config(config));
+    for index in 0..batcher.topics.len() {
+        let bus = bus.clone();
+        let topic = &batcher.topics[index];
+        info!("Batching {} into {} every {:?}", topic.pattern, topic.summary, topic.interval);
+        tokio::spawn(async move {
+            let Some(topic) = bus.batcher.get().map(|batcher| &batcher.topics[index]) else {
+                return;
+            };
+            let mut ticker = tokio::time::interval(topic.interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Some(payload) = topic.take() {
+                    let event = Event::new(topic.summary.clone(), payload, "backend".to_string());
+                    if let Err(e) = bus.publish(event).await {
+                        error!("Failed to emit {}: {}", topic.summary, e);
+                    }
+                }
+            }
+        });
+    }
+    if batcher.topics.is_empty() {
+        warn!("Batching is enabled without topics");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(name: &str, operation: &str) -> Event {
+        Event::new(name.to_string(), serde_json::json!({ "operation": operation }), "backend".to_string())
+    }
+
+    #[test]
+    fn test_counts_matching_events_until_taken() {
+        let settings = BatchTopicSettings { summary: Some("database.activity".to_string()), interval_ms: None, group_by: Some("operation".to_string()) };
+        let batcher = Batcher::new(vec![
+            Topic::new("database.operation", &settings),
+            Topic::new("fs.*", &BatchTopicSettings::default()),
+        ]);
+        assert!(batcher.absorb(&event("database.operation", "get_users")));
+        assert!(batcher.absorb(&event("database.operation", "get_users")));
+        assert!(batcher.absorb(&event("database.operation", "get_stats")));
+        assert!(batcher.absorb(&event("fs.changed", "create")));
+        assert!(!batcher.absorb(&event("data.changed", "update")));
+        assert!(!batcher.absorb(&event("database.activity", "get_users")));
+
+        let summary = batcher.topics[0].take().unwrap();
+        assert_eq!(summary["count"], 3);
+        assert_eq!(summary["counts"], serde_json::json!({ "get_users": 2, "get_stats": 1 }));
+        assert!(batcher.topics[0].take().is_none());
+
+        let files = &batcher.topics[1];
+        assert_eq!((files.summary.as_str(), files.interval), ("fs.summary", Duration::from_millis(DEFAULT_INTERVAL_MS)));
+        assert_eq!(files.take().unwrap()["counts"], serde_json::json!({ "fs.changed": 1 }));
+    }
+}
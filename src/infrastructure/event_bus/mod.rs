@@ -1,36 +1,89 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+pub mod batching;
+pub mod tap;
+
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::sync::{broadcast, RwLock};
 use tracing::{debug, error};
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use utoipa::ToSchema;
+use crate::core::domain::clock;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Event {
     pub id: String,
     pub name: String,
-    pub payload: serde_json::Value,
+    /// Shared by every clone, so fanning an event out to many receivers never copies it
+    pub payload: Arc<serde_json::Value>,
     pub source: String,
 }
 
 impl Event {
     pub fn new(name: String, payload: serde_json::Value, source: String) -> Self {
         Self {
-            id: Uuid::new_v4().to_string(),
+            id: clock::new_id(),
             name,
-            payload,
+            payload: Arc::new(payload),
             source,
         }
     }
 }
 
+/// Whether event `name` is selected by `pattern`: the name itself,
+/// `prefix.*` for every name starting with `prefix.`, or `*` for all
+pub fn event_matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(start) => name.starts_with(start),
+        None => pattern == name,
+    }
+}
+
+/// Emitted events kept in memory for devtools and crash reports
+const EVENT_LOG_SIZE: usize = 50;
+
+/// An emitted event with the time it went out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// Per-name counters for the devtools inspector
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TopicStats {
+    pub name: String,
+    pub emitted: u64,
+    pub last_emitted: DateTime<Utc>,
+    /// Calls of local subscribers, and how many of them failed
+    pub handler_calls: u64,
+    pub handler_errors: u64,
+    pub handler_total_us: u64,
+    pub handler_max_us: u64,
+}
+
 pub type EventHandler = Arc<dyn Fn(&Event) -> Result<(), Box<dyn std::error::Error + Send + Sync>> + Send + Sync>;
 
+/// A local subscriber; those with an owner can be removed together
+struct Subscriber {
+    owner: Option<String>,
+    handler: EventHandler,
+}
+
 pub struct EventBus {
-    subscribers: Arc<RwLock<HashMap<String, Vec<EventHandler>>>>,
+    subscribers: Arc<RwLock<HashMap<String, Vec<Subscriber>>>>,
     broadcast_sender: broadcast::Sender<Event>,
     #[allow(dead_code)]
     broadcast_receiver: broadcast::Receiver<Event>,
+    event_log: Mutex<VecDeque<EventRecord>>,
+    total_emitted: AtomicU64,
+    topics: Mutex<HashMap<String, TopicStats>>,
+    taps: tap::Taps,
+    /// Set by `batching::start`
+    batcher: OnceLock<batching::Batcher>,
 }
 
 impl EventBus {
@@ -40,6 +93,11 @@ impl EventBus {
             subscribers: Arc::new(RwLock::new(HashMap::new())),
             broadcast_sender: sender,
             broadcast_receiver: receiver,
+            event_log: Mutex::new(VecDeque::with_capacity(EVENT_LOG_SIZE)),
+            total_emitted: AtomicU64::new(0),
+            topics: Mutex::default(),
+            taps: tap::Taps::default(),
+            batcher: OnceLock::new(),
         }
     }
 
@@ -48,24 +106,94 @@ impl EventBus {
     where
         F: Fn(&Event) -> Result<(), Box<dyn std::error::Error + Send + Sync>> + Send + Sync + 'static,
     {
+        self.add_subscriber(event_name, None, Arc::new(handler));
+        Ok(())
+    }
+
+    /// `subscribe` on behalf of `owner`, so that `unsubscribe_owner` can
+    /// remove the handler again, e.g. before a plugin's library is unloaded
+    pub fn subscribe_as<F>(&self, owner: &str, event_name: &str, handler: F) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: Fn(&Event) -> Result<(), Box<dyn std::error::Error + Send + Sync>> + Send + Sync + 'static,
+    {
+        self.add_subscriber(event_name, Some(owner.to_string()), Arc::new(handler));
+        Ok(())
+    }
+
+    /// Remove every handler subscribed with `subscribe_as(owner, ..)`; returns how many
+    pub fn unsubscribe_owner(&self, owner: &str) -> usize {
+        let mut subscribers = futures::executor::block_on(self.subscribers.write());
+        let mut removed = 0;
+        subscribers.retain(|_, handlers| {
+            let before = handlers.len();
+            handlers.retain(|subscriber| subscriber.owner.as_deref() != Some(owner));
+            removed += before - handlers.len();
+            !handlers.is_empty()
+        });
+        removed
+    }
+
+    fn add_subscriber(&self, event_name: &str, owner: Option<String>, handler: EventHandler) {
         let mut subscribers = futures::executor::block_on(self.subscribers.write());
         let handlers = subscribers.entry(event_name.to_string()).or_insert_with(Vec::new);
-        handlers.push(Arc::new(handler));
-        Ok(())
+        handlers.push(Subscriber { owner, handler });
     }
 
     pub async fn emit(&self, event: Event) -> Result<(), Box<dyn std::error::Error>> {
-        // Notify local subscribers
+        if self.batcher.get().is_some_and(|batcher| batcher.absorb(&event)) {
+            return Ok(());
+        }
+        self.publish(event).await
+    }
+
+    /// Emit `event` without batching it
+    async fn publish(&self, event: Event) -> Result<(), Box<dyn std::error::Error>> {
+        // Notify local subscribers, timing each of them
+        let mut latencies_us = Vec::new();
+        let mut errors = 0;
         let subscribers = self.subscribers.read().await;
         if let Some(handlers) = subscribers.get(&event.name) {
-            for handler in handlers {
-                if let Err(e) = handler(&event) {
+            for subscriber in handlers {
+                let started = Instant::now();
+                if let Err(e) = (subscriber.handler)(&event) {
+                    errors += 1;
                     error!("Error in event handler for '{}': {}", event.name, e);
                 }
+                latencies_us.push(started.elapsed().as_micros() as u64);
             }
         }
         drop(subscribers);
 
+        let timestamp = clock::now();
+        self.total_emitted.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut topics) = self.topics.lock() {
+            let stats = topics.entry(event.name.clone()).or_insert_with(|| TopicStats {
+                name: event.name.clone(),
+                emitted: 0,
+                last_emitted: timestamp,
+                handler_calls: 0,
+                handler_errors: 0,
+                handler_total_us: 0,
+                handler_max_us: 0,
+            });
+            stats.emitted += 1;
+            stats.last_emitted = timestamp;
+            stats.handler_calls += latencies_us.len() as u64;
+            stats.handler_errors += errors as u64;
+            stats.handler_total_us += latencies_us.iter().sum::<u64>();
+            stats.handler_max_us = latencies_us.iter().copied().fold(stats.handler_max_us, u64::max);
+        }
+        if let Ok(mut log) = self.event_log.lock() {
+            if log.len() == EVENT_LOG_SIZE {
+                log.pop_front();
+            }
+            log.push_back(EventRecord {
+                timestamp,
+                event: event.clone(),
+            });
+        }
+        self.taps.offer(&event, timestamp, &latencies_us, errors);
+
         // Broadcast to all receivers
         if self.broadcast_sender.send(event).is_err() {
             debug!("No receivers for event broadcast");
@@ -83,6 +211,60 @@ impl EventBus {
         self.broadcast_sender.subscribe()
     }
 
+    /// Last emitted events, oldest first
+    ///
+    /// Never blocks: returns nothing if the log is locked, so it is safe to
+    /// call from a panic hook.
+    pub fn recent_events(&self) -> Vec<EventRecord> {
+        match self.event_log.try_lock() {
+            Ok(log) => log.iter().cloned().collect(),
+            Err(std::sync::TryLockError::Poisoned(log)) => log.into_inner().iter().cloned().collect(),
+            Err(std::sync::TryLockError::WouldBlock) => Vec::new(),
+        }
+    }
+
+    pub fn total_emitted(&self) -> u64 {
+        self.total_emitted.load(Ordering::Relaxed)
+    }
+
+    /// Counters of every event name published so far, most emitted first
+    pub fn topic_stats(&self) -> Vec<TopicStats> {
+        let mut stats: Vec<TopicStats> = match self.topics.lock() {
+            Ok(topics) => topics.values().cloned().collect(),
+            Err(poisoned) => poisoned.into_inner().values().cloned().collect(),
+        };
+        stats.sort_by(|a, b| b.emitted.cmp(&a.emitted).then_with(|| a.name.cmp(&b.name)));
+        stats
+    }
+
+    /// Live views of this bus for the devtools inspector
+    pub fn taps(&self) -> &tap::Taps {
+        &self.taps
+    }
+
+    /// Readiness check: the subscriber registry is not stuck behind a writer,
+    /// the event log is usable and the broadcast channel is still open
+    pub fn check(&self) -> Result<(), String> {
+        drop(
+            self.subscribers
+                .try_read()
+                .map_err(|_| "Subscriber registry is locked".to_string())?,
+        );
+        if self.event_log.is_poisoned() {
+            return Err("Event log lock is poisoned".to_string());
+        }
+        // The bus holds its own receiver, so zero means the channel is gone
+        if self.broadcast_sender.receiver_count() == 0 {
+            return Err("Broadcast channel is closed".to_string());
+        }
+        Ok(())
+    }
+
+    /// Number of active `listen()` receivers, excluding the bus's own
+    pub fn listener_count(&self) -> usize {
+        self.broadcast_sender.receiver_count().saturating_sub(1)
+    }
+
     #[allow(dead_code)]
     pub async fn register_event_handler<F>(&self, event_name: &str, handler: F) -> Result<(), Box<dyn std::error::Error>>
     where
@@ -110,17 +292,92 @@ impl EventBus {
 }
 
 // Predefined event types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum AppEventType {
     UserLogin,
     UserLogout,
     DataChanged,
     CounterIncremented,
+    CounterChanged,
     DatabaseOperation,
+    DatabaseActivity,
     SystemHealthCheck,
     FrontendConnected,
     FrontendDisconnected,
     WindowStateChanged,
+    ClipboardAccessed,
+    PluginAccessDenied,
+    SystemMetrics,
+    JobCompleted,
+    FsChanged,
+    ImagesOrganizeProgress,
+    FileTransferProgress,
+    FileUploaded,
+    SettingsChanged,
+    TaskProgress,
+    TaskCompleted,
+    TaskFailed,
+    TaskCancelled,
+    UpdateAvailable,
+    UpdateInstalled,
+    FlagsChanged,
+    ChaosInjected,
+    StatePatch,
+    CommandAccepted,
+    CommandApplied,
+    CommandRejected,
+    PresenceJoined,
+    PresenceLeft,
+    CollabUpdate,
+    HotkeyPressed,
+    DocumentExported,
+    CaptureRequested,
+    CaptureCompleted,
+}
+
+impl AppEventType {
+    /// Every event type, for catalogs such as the generated frontend client
+    pub const ALL: &'static [AppEventType] = &[
+        AppEventType::UserLogin,
+        AppEventType::UserLogout,
+        AppEventType::DataChanged,
+        AppEventType::CounterIncremented,
+        AppEventType::CounterChanged,
+        AppEventType::DatabaseOperation,
+        AppEventType::DatabaseActivity,
+        AppEventType::SystemHealthCheck,
+        AppEventType::FrontendConnected,
+        AppEventType::FrontendDisconnected,
+        AppEventType::WindowStateChanged,
+        AppEventType::ClipboardAccessed,
+        AppEventType::PluginAccessDenied,
+        AppEventType::SystemMetrics,
+        AppEventType::JobCompleted,
+        AppEventType::FsChanged,
+        AppEventType::ImagesOrganizeProgress,
+        AppEventType::FileTransferProgress,
+        AppEventType::FileUploaded,
+        AppEventType::SettingsChanged,
+        AppEventType::TaskProgress,
+        AppEventType::TaskCompleted,
+        AppEventType::TaskFailed,
+        AppEventType::TaskCancelled,
+        AppEventType::UpdateAvailable,
+        AppEventType::UpdateInstalled,
+        AppEventType::FlagsChanged,
+        AppEventType::ChaosInjected,
+        AppEventType::StatePatch,
+        AppEventType::CommandAccepted,
+        AppEventType::CommandApplied,
+        AppEventType::CommandRejected,
+        AppEventType::PresenceJoined,
+        AppEventType::PresenceLeft,
+        AppEventType::CollabUpdate,
+        AppEventType::HotkeyPressed,
+        AppEventType::DocumentExported,
+        AppEventType::CaptureRequested,
+        AppEventType::CaptureCompleted,
+    ];
 }
 
 impl ToString for AppEventType {
@@ -130,11 +387,41 @@ impl ToString for AppEventType {
             AppEventType::UserLogout => "user.logout".to_string(),
             AppEventType::DataChanged => "data.changed".to_string(),
             AppEventType::CounterIncremented => "counter.incremented".to_string(),
+            AppEventType::CounterChanged => "counter.changed".to_string(),
             AppEventType::DatabaseOperation => "database.operation".to_string(),
+            AppEventType::DatabaseActivity => "database.activity".to_string(),
             AppEventType::SystemHealthCheck => "system.health.check".to_string(),
             AppEventType::FrontendConnected => "frontend.connected".to_string(),
             AppEventType::FrontendDisconnected => "frontend.disconnected".to_string(),
             AppEventType::WindowStateChanged => "window.state.changed".to_string(),
+            AppEventType::ClipboardAccessed => "clipboard.accessed".to_string(),
+            AppEventType::PluginAccessDenied => "plugin.access_denied".to_string(),
+            AppEventType::SystemMetrics => "system.metrics".to_string(),
+            AppEventType::JobCompleted => "scheduler.job.completed".to_string(),
+            AppEventType::FsChanged => "fs.changed".to_string(),
+            AppEventType::ImagesOrganizeProgress => "images.organize.progress".to_string(),
+            AppEventType::FileTransferProgress => "file.transfer.progress".to_string(),
+            AppEventType::FileUploaded => "file.uploaded".to_string(),
+            AppEventType::SettingsChanged => "settings.changed".to_string(),
+            AppEventType::TaskProgress => crate::core::application::TASK_PROGRESS.to_string(),
+            AppEventType::TaskCompleted => crate::core::application::TASK_COMPLETED.to_string(),
+            AppEventType::TaskFailed => crate::core::application::TASK_FAILED.to_string(),
+            AppEventType::TaskCancelled => crate::core::application::TASK_CANCELLED.to_string(),
+            AppEventType::UpdateAvailable => "update.available".to_string(),
+            AppEventType::UpdateInstalled => "update.installed".to_string(),
+            AppEventType::FlagsChanged => "flags.changed".to_string(),
+            AppEventType::ChaosInjected => crate::infrastructure::chaos::INJECTED.to_string(),
+            AppEventType::StatePatch => "state.patch".to_string(),
+            AppEventType::CommandAccepted => "command.accepted".to_string(),
+            AppEventType::CommandApplied => "command.applied".to_string(),
+            AppEventType::CommandRejected => "command.rejected".to_string(),
+            AppEventType::PresenceJoined => "presence.joined".to_string(),
+            AppEventType::PresenceLeft => "presence.left".to_string(),
+            AppEventType::CollabUpdate => "collab.update".to_string(),
+            AppEventType::HotkeyPressed => "hotkey.pressed".to_string(),
+            AppEventType::DocumentExported => "document.exported".to_string(),
+            AppEventType::CaptureRequested => "capture.requested".to_string(),
+            AppEventType::CaptureCompleted => "capture.completed".to_string(),
         }
     }
 }
@@ -162,21 +449,63 @@ impl EventMiddleware {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio;
+    use std::sync::atomic::AtomicBool;
+    use crate::tests::testkit::TestApp;
 
     #[tokio::test]
     async fn test_event_bus_basic() {
+        let mut client = TestApp::shared().client().await;
+        let handled = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&handled);
+        let bus = EventBus::global();
+        bus.subscribe("test.event", move |event| {
+            flag.store(event.payload["test"] == "data", Ordering::Relaxed);
+            Ok(())
+        })
+        .unwrap();
+
+        bus.emit_simple("test.event", serde_json::json!({"test": "data"})).await.unwrap();
+
+        // Subscribers run before the event goes out, and clients receive it
+        assert!(handled.load(Ordering::Relaxed));
+        let received = client.next_event("test.event").await;
+        assert_eq!(received.payload, serde_json::json!({"test": "data"}));
+        assert_eq!(received.source, "backend");
+    }
+
+    #[tokio::test]
+    async fn test_ids_and_times_come_from_the_clock() {
+        let fixed = clock::FixedClock::epoch();
+        let at = clock::Clock::now(&fixed);
+        let _restore = clock::use_on_this_thread(Arc::new(fixed), Arc::new(clock::SequentialIds::new("event")));
         let bus = EventBus::new();
 
-        // Test that we can subscribe without errors
-        let result = bus.subscribe("test.event", |_| {
-            Ok(())
-        });
-        
-        assert!(result.is_ok());
+        bus.emit_simple("test.first", serde_json::json!({})).await.unwrap();
+        bus.emit(Event::new("test.second".to_string(), serde_json::json!({}), "test".to_string())).await.unwrap();
 
-        // Test that we can emit without errors
-        let emit_result = bus.emit_simple("test.event", serde_json::json!({"test": "data"})).await;
-        assert!(emit_result.is_ok());
+        let recorded: Vec<(String, DateTime<Utc>)> =
+            bus.recent_events().into_iter().map(|record| (record.event.id, record.timestamp)).collect();
+        assert_eq!(recorded, [("event-1".to_string(), at), ("event-2".to_string(), at)]);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_topic_stats_and_taps_see_handler_results() {
+        let bus = EventBus::new();
+        bus.subscribe("test.tapped", |_| Ok(())).unwrap();
+        bus.subscribe("test.tapped", |_| Err("handler failed".into())).unwrap();
+        let (_, receiver) = bus.taps().open(tap::TapFilter::from_query("names=test.*"));
+
+        bus.emit_simple("test.tapped", serde_json::json!({ "n": 1 })).await.unwrap();
+        bus.emit_simple("test.tapped", serde_json::json!({ "n": 2 })).await.unwrap();
+        bus.emit_simple("other.event", serde_json::json!({})).await.unwrap();
+
+        let stats = bus.topic_stats();
+        assert_eq!(stats.iter().map(|topic| (topic.name.as_str(), topic.emitted)).collect::<Vec<_>>(), [("test.tapped", 2), ("other.event", 1)]);
+        assert_eq!((stats[0].handler_calls, stats[0].handler_errors), (4, 2));
+
+        let tapped: Vec<tap::TappedEvent> = receiver.try_iter().collect();
+        assert_eq!(tapped.len(), 2);
+        assert_eq!((tapped[1].seq, tapped[1].handler_latencies_us.len(), tapped[1].handler_errors), (2, 2, 1));
+        assert_eq!(tapped[1].payload["n"], 2);
+    }
+}
@@ -0,0 +1,256 @@
+//! Live view of the bus for the devtools inspector
+//!
+//! A tap receives every event the bus publishes that passes its filter, with
+//! the payload's size and how long each local subscriber took to handle it.
+//! Taps are opened by `GET /api/devtools/events/tap`, which streams them as
+//! server-sent events, and can be paused, resumed and refiltered by id. A
+//! paused tap, or one whose client cannot keep up, counts what it misses
+//! instead of queueing it. With no tap open, publishing does no extra work.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use super::{event_matches, Event};
+use crate::core::domain::clock;
+
+/// Events held for a tap whose client is slow to read
+const TAP_QUEUE_SIZE: usize = 1024;
+
+/// A published event as seen by a tap
+#[derive(Debug, Clone, Serialize)]
+pub struct TappedEvent {
+    /// Position among the events handed to taps, so gaps show what was missed
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub id: String,
+    pub name: String,
+    pub source: String,
+    /// Size of the payload as JSON
+    pub payload_bytes: usize,
+    /// Time each local subscriber took, in the order they subscribed
+    pub handler_latencies_us: Vec<u64>,
+    pub handler_errors: usize,
+    pub payload: Arc<serde_json::Value>,
+}
+
+/// Which events a tap receives; an empty list lets everything through
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TapFilter {
+    /// Event name patterns, as for `event_matches`
+    #[serde(default)]
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub sources: Vec<String>,
+}
+
+impl TapFilter {
+    /// From `?names=user.*,database.*&sources=backend`
+    pub fn from_query(query: &str) -> Self {
+        let mut filter = Self::default();
+        for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+            let list = match key.as_ref() {
+                "names" => &mut filter.names,
+                "sources" => &mut filter.sources,
+                _ => continue,
+            };
+            list.extend(value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from));
+        }
+        filter
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        (self.names.is_empty() || self.names.iter().any(|pattern| event_matches(pattern, &event.name)))
+            && (self.sources.is_empty() || self.sources.contains(&event.source))
+    }
+}
+
+/// An open tap, as listed by `events.tap.list`
+#[derive(Debug, Clone, Serialize)]
+pub struct TapInfo {
+    pub id: String,
+    pub opened_at: DateTime<Utc>,
+    pub filter: TapFilter,
+    pub paused: bool,
+    pub delivered: u64,
+    /// Events that matched while paused or while the queue was full
+    pub missed: u64,
+}
+
+struct Tap {
+    id: String,
+    opened_at: DateTime<Utc>,
+    filter: Mutex<TapFilter>,
+    paused: AtomicBool,
+    delivered: AtomicU64,
+    missed: AtomicU64,
+    sender: SyncSender<TappedEvent>,
+}
+
+impl Tap {
+    fn info(&self) -> TapInfo {
+        TapInfo {
+            id: self.id.clone(),
+            opened_at: self.opened_at,
+            filter: self.filter.lock().map(|filter| filter.clone()).unwrap_or_default(),
+            paused: self.paused.load(Ordering::Relaxed),
+            delivered: self.delivered.load(Ordering::Relaxed),
+            missed: self.missed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The taps open on one bus
+#[derive(Default)]
+pub struct Taps {
+    taps: Mutex<Vec<Arc<Tap>>>,
+    /// Mirrors `taps.len()`, so publishing can skip the lock when there are none
+    open: AtomicUsize,
+    seq: AtomicU64,
+}
+
+impl Taps {
+    /// Open a tap; it stays open until its receiver is dropped or it is closed
+    pub fn open(&self, filter: TapFilter) -> (String, Receiver<TappedEvent>) {
+        let (sender, receiver) = mpsc::sync_channel(TAP_QUEUE_SIZE);
+        let tap = Tap {
+            id: clock::new_id(),
+            opened_at: clock::now(),
+            filter: Mutex::new(filter),
+            paused: AtomicBool::new(false),
+            delivered: AtomicU64::new(0),
+            missed: AtomicU64::new(0),
+            sender,
+        };
+        let id = tap.id.clone();
+        let mut taps = self.taps.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        taps.push(Arc::new(tap));
+        self.open.store(taps.len(), Ordering::Relaxed);
+        (id, receiver)
+    }
+
+    pub fn close(&self, id: &str) -> bool {
+        let mut taps = self.taps.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let before = taps.len();
+        taps.retain(|tap| tap.id != id);
+        self.open.store(taps.len(), Ordering::Relaxed);
+        taps.len() < before
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.open.load(Ordering::Relaxed) == 0
+    }
+
+    pub fn list(&self) -> Vec<TapInfo> {
+        let taps = self.taps.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        taps.iter().map(|tap| tap.info()).collect()
+    }
+
+    /// Pause or resume a tap; its state afterwards, or `None` for an unknown id
+    pub fn set_paused(&self, id: &str, paused: bool) -> Option<TapInfo> {
+        let tap = self.find(id)?;
+        tap.paused.store(paused, Ordering::Relaxed);
+        Some(tap.info())
+    }
+
+    pub fn set_filter(&self, id: &str, filter: TapFilter) -> Option<TapInfo> {
+        let tap = self.find(id)?;
+        if let Ok(mut current) = tap.filter.lock() {
+            *current = filter;
+        }
+        Some(tap.info())
+    }
+
+    fn find(&self, id: &str) -> Option<Arc<Tap>> {
+        let taps = self.taps.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        taps.iter().find(|tap| tap.id == id).cloned()
+    }
+
+    /// Hand a just-published event to every tap it passes the filter of
+    pub(super) fn offer(&self, event: &Event, timestamp: DateTime<Utc>, latencies_us: &[u64], errors: usize) {
+        if self.is_empty() {
+            return;
+        }
+        let mut taps = self.taps.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut tapped: Option<TappedEvent> = None;
+        let mut closed = Vec::new();
+        for tap in taps.iter() {
+            if !tap.filter.lock().is_ok_and(|filter| filter.matches(event)) {
+                continue;
+            }
+            if tap.paused.load(Ordering::Relaxed) {
+                tap.missed.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            let tapped = tapped.get_or_insert_with(|| TappedEvent {
+                seq: self.seq.fetch_add(1, Ordering::Relaxed) + 1,
+                timestamp,
+                id: event.id.clone(),
+                name: event.name.clone(),
+                source: event.source.clone(),
+                payload_bytes: serde_json::to_vec(&event.payload).map_or(0, |bytes| bytes.len()),
+                handler_latencies_us: latencies_us.to_vec(),
+                handler_errors: errors,
+                payload: Arc::clone(&event.payload),
+            });
+            match tap.sender.try_send(tapped.clone()) {
+                Ok(()) => {
+                    tap.delivered.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(TrySendError::Full(_)) => {
+                    tap.missed.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(TrySendError::Disconnected(_)) => closed.push(tap.id.clone()),
+            }
+        }
+        if !closed.is_empty() {
+            // Their clients went away
+            taps.retain(|tap| !closed.contains(&tap.id));
+            self.open.store(taps.len(), Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(name: &str, source: &str) -> Event {
+        Event::new(name.to_string(), serde_json::json!({ "id": 1 }), source.to_string())
+    }
+
+    #[test]
+    fn test_filter_from_query() {
+        let filter = TapFilter::from_query("names=user.*,%20database.operation&sources=backend&other=1");
+        assert_eq!(filter.names, ["user.*", "database.operation"]);
+        assert!(filter.matches(&event("user.created", "backend")));
+        assert!(!filter.matches(&event("user.created", "frontend")));
+        assert!(!filter.matches(&event("counter.changed", "backend")));
+        assert!(TapFilter::from_query("").matches(&event("counter.changed", "frontend")));
+    }
+
+    #[test]
+    fn test_paused_taps_count_what_they_miss() {
+        let taps = Taps::default();
+        let (id, receiver) = taps.open(TapFilter::from_query("names=user.*"));
+        taps.offer(&event("user.created", "backend"), Utc::now(), &[12, 30], 1);
+        taps.offer(&event("counter.changed", "backend"), Utc::now(), &[], 0);
+        taps.set_paused(&id, true);
+        taps.offer(&event("user.deleted", "backend"), Utc::now(), &[], 0);
+        let info = taps.set_paused(&id, false).unwrap();
+        taps.offer(&event("user.updated", "backend"), Utc::now(), &[], 0);
+
+        let received: Vec<TappedEvent> = receiver.try_iter().collect();
+        assert_eq!(received.iter().map(|tapped| tapped.name.as_str()).collect::<Vec<_>>(), ["user.created", "user.updated"]);
+        assert_eq!(received[0].handler_latencies_us, [12, 30]);
+        assert_eq!(received[0].payload_bytes, r#"{"id":1}"#.len());
+        assert_eq!((info.delivered, info.missed), (1, 1));
+
+        // A tap whose receiver is gone is closed on the next event
+        drop(receiver);
+        taps.offer(&event("user.created", "backend"), Utc::now(), &[], 0);
+        assert!(taps.is_empty());
+        assert!(taps.set_paused(&id, true).is_none());
+    }
+}
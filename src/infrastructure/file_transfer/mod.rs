@@ -0,0 +1,841 @@
+//! File transfer over the WebSocket - chunked uploads and streamed downloads
+//!
+//! File data travels in binary frames (`encode_frame`): the `WUBF` magic, the
+//! length of a JSON header as a big-endian u32, the header itself - a regular
+//! `{ id, name, payload, timestamp, source }` message - and the raw bytes.
+//!
+//! An upload is `file.upload_begin`, one `file.upload_chunk` frame per chunk,
+//! each answered with a response, then `file.upload_end`. `file.download`
+//! answers with the file size and then streams `file.download_chunk` frames,
+//! closed by a `file.download_end` frame. Every chunk carries the SHA-256 of
+//! its data and the whole file is checked at the end. Files are only written
+//! to and read from `[file_transfer] allowed_dirs`, up to `max_file_size_mb`.
+//!
+//! `POST /api/upload` stores the files of a `multipart/form-data` body the
+//! same way (`receive_multipart`), streaming each one to disk. Both kinds of
+//! upload are held to `allowed_extensions` before any data is written and
+//! passed through the upload scanners before they are moved into place.
+
+pub mod multipart;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::infrastructure::shell::expand_home;
+use crate::infrastructure::workers;
+use crate::model::core::AppConfig;
+use crate::viewmodel::ws_parser::{self, MessageLimits};
+use multipart::Multipart;
+
+pub const UPLOAD_BEGIN: &str = "file.upload_begin";
+pub const UPLOAD_CHUNK: &str = "file.upload_chunk";
+pub const UPLOAD_END: &str = "file.upload_end";
+pub const DOWNLOAD: &str = "file.download";
+pub const DOWNLOAD_CHUNK: &str = "file.download_chunk";
+pub const DOWNLOAD_END: &str = "file.download_end";
+
+pub use crate::viewmodel::ws_parser::FRAME_MAGIC;
+
+#[derive(Debug, Error)]
+pub enum TransferError {
+    #[error("Invalid file name: {0}")]
+    InvalidName(String),
+    #[error("Path does not exist: {0}")]
+    NotFound(String),
+    #[error("Path is outside the allowed directories: {0}")]
+    NotAllowed(String),
+    #[error("File already exists: {0}")]
+    AlreadyExists(String),
+    #[error("File is {size} bytes, the limit is {limit}")]
+    TooLarge { size: u64, limit: u64 },
+    #[error("Unknown upload: {0}")]
+    UnknownUpload(String),
+    #[error("Chunk starts at offset {got}, expected {expected}")]
+    OutOfOrder { expected: u64, got: u64 },
+    #[error("Chunk of {0} bytes is larger than the chunk size or the rest of the file")]
+    ChunkTooLarge(usize),
+    #[error("Checksum mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+    #[error("Upload incomplete: {received} of {size} bytes received")]
+    Incomplete { received: u64, size: u64 },
+    #[error("Malformed binary frame: {0}")]
+    MalformedFrame(String),
+    #[error("Malformed multipart body: {0}")]
+    MalformedBody(String),
+    #[error("File type not allowed: {0}")]
+    ExtensionNotAllowed(String),
+    #[error("Upload rejected by {scanner}: {reason}")]
+    Rejected { scanner: String, reason: String },
+    #[error("File I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl TransferError {
+    /// HTTP status for answering `POST /api/upload` with this error
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::NotFound(_) => 404,
+            Self::NotAllowed(_) => 403,
+            Self::AlreadyExists(_) => 409,
+            Self::TooLarge { .. } => 413,
+            Self::ExtensionNotAllowed(_) => 415,
+            Self::Rejected { .. } => 422,
+            Self::Io(_) => 500,
+            _ => 400,
+        }
+    }
+}
+
+/// Checks a complete upload before it is moved into place, e.g. a virus scanner
+pub trait UploadScanner: Send + Sync {
+    fn name(&self) -> &str;
+    /// `Err` with a reason rejects the file, which is then deleted
+    fn scan(&self, file_name: &str, path: &Path) -> Result<(), String>;
+}
+
+static SCANNERS: RwLock<Vec<Arc<dyn UploadScanner>>> = RwLock::new(Vec::new());
+
+/// Run `scanner` on every upload from now on, after `[file_transfer] scan_command`
+#[allow(dead_code)]
+pub fn register_scanner(scanner: Arc<dyn UploadScanner>) {
+    info!("Registered upload scanner {}", scanner.name());
+    SCANNERS.write().unwrap().push(scanner);
+}
+
+/// `[file_transfer] scan_command`: the file path is appended, any exit status but 0 rejects
+struct ScanCommand(Vec<String>);
+
+impl UploadScanner for ScanCommand {
+    fn name(&self) -> &str {
+        &self.0[0]
+    }
+
+    fn scan(&self, _file_name: &str, path: &Path) -> Result<(), String> {
+        let output = Command::new(&self.0[0])
+            .args(&self.0[1..])
+            .arg(path)
+            .output()
+            .map_err(|e| format!("could not run scanner: {}", e))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let report = String::from_utf8_lossy(&output.stdout);
+        let report = report.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+        Err(format!("{} {}", output.status, report).trim_end().to_string())
+    }
+}
+
+/// Where files may go and how big they may be
+#[derive(Debug, Clone)]
+pub struct TransferPolicy {
+    allowed_dirs: Vec<PathBuf>,
+    pub max_file_size: u64,
+    pub chunk_size: usize,
+    /// Lowercase, without the dot; empty allows any file
+    pub allowed_extensions: Vec<String>,
+    pub scan_command: Vec<String>,
+}
+
+impl TransferPolicy {
+    pub fn new(allowed_dirs: Vec<PathBuf>, max_file_size: u64, chunk_size: usize) -> Self {
+        Self {
+            // Directories that do not exist can never contain a valid path
+            allowed_dirs: allowed_dirs
+                .into_iter()
+                .filter_map(|dir| dir.canonicalize().ok())
+                .collect(),
+            max_file_size,
+            chunk_size,
+            allowed_extensions: Vec::new(),
+            scan_command: Vec::new(),
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        // The upload directory comes first so it is the default destination
+        let dirs = config
+            .get_file_transfer_upload_dir()
+            .into_iter()
+            .chain(config.get_file_transfer_allowed_dirs())
+            .map(|dir| expand_home(&dir))
+            .collect();
+        Self {
+            allowed_extensions: config.get_file_transfer_allowed_extensions(),
+            scan_command: config.get_file_transfer_scan_command(),
+            ..Self::new(
+                dirs,
+                config.get_file_transfer_max_file_size_mb() * 1024 * 1024,
+                config.get_file_transfer_chunk_size_kb() * 1024,
+            )
+        }
+    }
+
+    fn check_allowed(&self, canonical: PathBuf) -> Result<PathBuf, TransferError> {
+        if !self.allowed_dirs.iter().any(|dir| canonical.starts_with(dir)) {
+            return Err(TransferError::NotAllowed(canonical.display().to_string()));
+        }
+        Ok(canonical)
+    }
+
+    /// Upload destination: `directory`, or the first allowed directory when omitted
+    pub fn directory(&self, directory: Option<&str>) -> Result<PathBuf, TransferError> {
+        let Some(directory) = directory else {
+            return self
+                .allowed_dirs
+                .first()
+                .cloned()
+                .ok_or_else(|| TransferError::NotAllowed("no allowed directories configured".to_string()));
+        };
+        let canonical = expand_home(directory)
+            .canonicalize()
+            .ok()
+            .filter(|dir| dir.is_dir())
+            .ok_or_else(|| TransferError::NotFound(directory.to_string()))?;
+        self.check_allowed(canonical)
+    }
+
+    /// Download source: an existing file within an allowed directory
+    pub fn file(&self, path: &str) -> Result<PathBuf, TransferError> {
+        let canonical = expand_home(path)
+            .canonicalize()
+            .ok()
+            .filter(|file| file.is_file())
+            .ok_or_else(|| TransferError::NotFound(path.to_string()))?;
+        self.check_allowed(canonical)
+    }
+
+    fn check_size(&self, size: u64) -> Result<(), TransferError> {
+        if size > self.max_file_size {
+            return Err(TransferError::TooLarge { size, limit: self.max_file_size });
+        }
+        Ok(())
+    }
+
+    /// A bare file name - no directories, no `.` or `..` - with an allowed extension
+    fn check_name(&self, name: &str) -> Result<(), TransferError> {
+        match Path::new(name).file_name() {
+            Some(file_name) if file_name == name && !name.contains(['/', '\\']) => {}
+            _ => return Err(TransferError::InvalidName(name.to_string())),
+        }
+        let extension = Path::new(name)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        if !self.allowed_extensions.is_empty()
+            && !extension.is_some_and(|extension| self.allowed_extensions.contains(&extension))
+        {
+            return Err(TransferError::ExtensionNotAllowed(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Run `scan_command` and the registered scanners on a complete upload
+    fn scan(&self, name: &str, path: &Path) -> Result<(), TransferError> {
+        let mut scanners: Vec<Arc<dyn UploadScanner>> = Vec::new();
+        if !self.scan_command.is_empty() {
+            scanners.push(Arc::new(ScanCommand(self.scan_command.clone())));
+        }
+        scanners.extend(SCANNERS.read().unwrap().iter().cloned());
+        for scanner in scanners {
+            if let Err(reason) = scanner.scan(name, path) {
+                return Err(TransferError::Rejected { scanner: scanner.name().to_string(), reason });
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Whether `frame` is a file frame rather than a JSON message
+pub fn is_frame(frame: &[u8]) -> bool {
+    frame.starts_with(FRAME_MAGIC)
+}
+
+pub fn encode_frame(header: &Value, data: &[u8]) -> Vec<u8> {
+    let header = serde_json::to_vec(header).unwrap_or_default();
+    let mut frame = Vec::with_capacity(FRAME_MAGIC.len() + 4 + header.len() + data.len());
+    frame.extend_from_slice(FRAME_MAGIC);
+    frame.extend_from_slice(&(header.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&header);
+    frame.extend_from_slice(data);
+    frame
+}
+
+/// Header and data of a frame, the header parsed within `limits`
+pub fn decode_frame<'a, H: DeserializeOwned>(frame: &'a [u8], limits: &MessageLimits) -> Result<(H, &'a [u8]), TransferError> {
+    let malformed = |e: ws_parser::ParseError| TransferError::MalformedFrame(e.to_string());
+    let (header, data) = ws_parser::split_frame(frame).map_err(malformed)?;
+    let header = ws_parser::parse(header, limits).map_err(malformed)?;
+    Ok((header, data))
+}
+
+fn frame_header(id: &str, name: &str, payload: Value) -> Value {
+    serde_json::json!({
+        "id": id,
+        "name": name,
+        "payload": payload,
+        "timestamp": chrono::Utc::now().timestamp_millis(),
+        "source": "backend",
+    })
+}
+
+async fn emit_progress(id: &str, direction: &str, name: &str, bytes: u64, total: u64) {
+    let payload = serde_json::json!({
+        "transfer_id": id,
+        "direction": direction,
+        "name": name,
+        "bytes": bytes,
+        "total": total,
+    });
+    if let Err(e) = EventBus::global()
+        .emit_simple(&AppEventType::FileTransferProgress.to_string(), payload)
+        .await
+    {
+        error!("Failed to emit file transfer progress: {}", e);
+    }
+}
+
+/// Announce a stored upload; `transport` is `websocket` or `http`
+pub async fn emit_uploaded(file: &CompletedUpload, transport: &str) {
+    let payload = serde_json::json!({
+        "path": file.path,
+        "name": file.path.file_name().map(|name| name.to_string_lossy()),
+        "size": file.size,
+        "sha256": file.sha256,
+        "transport": transport,
+    });
+    if let Err(e) = EventBus::global()
+        .emit_simple(&AppEventType::FileUploaded.to_string(), payload)
+        .await
+    {
+        error!("Failed to emit file uploaded event: {}", e);
+    }
+}
+
+struct Upload {
+    name: String,
+    target: PathBuf,
+    /// Written here and renamed to `target` once complete and verified
+    part: PathBuf,
+    file: File,
+    size: u64,
+    received: u64,
+    hasher: Sha256,
+    sha256: Option<String>,
+    last_activity: Instant,
+}
+
+impl Upload {
+    fn discard(self) {
+        drop(self.file);
+        if let Err(e) = fs::remove_file(&self.part) {
+            warn!("Failed to remove partial upload {}: {}", self.part.display(), e);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadInfo {
+    pub upload_id: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub chunk_size: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadProgress {
+    pub upload_id: String,
+    pub name: String,
+    pub received: u64,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CompletedUpload {
+    #[schema(value_type = String)]
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+}
+
+pub struct FileTransferService {
+    uploads: Mutex<HashMap<String, Upload>>,
+}
+
+impl FileTransferService {
+    fn new() -> Self {
+        Self {
+            uploads: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn global() -> &'static FileTransferService {
+        static SERVICE: OnceLock<FileTransferService> = OnceLock::new();
+        SERVICE.get_or_init(FileTransferService::new)
+    }
+
+    /// Drop uploads that have not received a chunk for `max_idle`, e.g. after a disconnect
+    fn expire(uploads: &mut HashMap<String, Upload>, max_idle: Duration) {
+        let stale: Vec<String> = uploads
+            .iter()
+            .filter(|(_, upload)| upload.last_activity.elapsed() > max_idle)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in stale {
+            if let Some(upload) = uploads.remove(&id) {
+                info!("Abandoning idle upload {} of {}", id, upload.name);
+                upload.discard();
+            }
+        }
+    }
+
+    pub fn begin_upload(
+        &self,
+        policy: &TransferPolicy,
+        name: &str,
+        size: u64,
+        directory: Option<&str>,
+        overwrite: bool,
+        sha256: Option<String>,
+    ) -> Result<UploadInfo, TransferError> {
+        policy.check_name(name)?;
+        policy.check_size(size)?;
+        let directory = policy.directory(directory)?;
+        let target = directory.join(name);
+        if target.exists() && !overwrite {
+            return Err(TransferError::AlreadyExists(target.display().to_string()));
+        }
+
+        let upload_id = uuid::Uuid::new_v4().to_string();
+        let part = directory.join(format!(".{}.{}.part", name, upload_id));
+        let file = File::create(&part)?;
+
+        let mut uploads = self.uploads.lock().unwrap();
+        Self::expire(&mut uploads, Duration::from_secs(AppConfig::global().get_file_transfer_upload_timeout_secs()));
+        info!("Receiving {} ({} bytes) into {}", name, size, directory.display());
+        uploads.insert(
+            upload_id.clone(),
+            Upload {
+                name: name.to_string(),
+                target: target.clone(),
+                part,
+                file,
+                size,
+                received: 0,
+                hasher: Sha256::new(),
+                sha256: sha256.map(|sum| sum.to_lowercase()),
+                last_activity: Instant::now(),
+            },
+        );
+        Ok(UploadInfo { upload_id, path: target, size, chunk_size: policy.chunk_size })
+    }
+
+    /// Append one chunk; it must start where the previous one ended and match `sha256`.
+    /// A rejected chunk leaves the upload as it was, so it can be sent again.
+    pub fn write_chunk(
+        &self,
+        policy: &TransferPolicy,
+        upload_id: &str,
+        offset: u64,
+        sha256: &str,
+        data: &[u8],
+    ) -> Result<UploadProgress, TransferError> {
+        let mut uploads = self.uploads.lock().unwrap();
+        let upload = uploads
+            .get_mut(upload_id)
+            .ok_or_else(|| TransferError::UnknownUpload(upload_id.to_string()))?;
+        upload.last_activity = Instant::now();
+
+        if offset != upload.received {
+            return Err(TransferError::OutOfOrder { expected: upload.received, got: offset });
+        }
+        if data.len() > policy.chunk_size || upload.received + data.len() as u64 > upload.size {
+            return Err(TransferError::ChunkTooLarge(data.len()));
+        }
+        let actual = sha256_hex(data);
+        if !actual.eq_ignore_ascii_case(sha256) {
+            return Err(TransferError::ChecksumMismatch { expected: sha256.to_string(), actual });
+        }
+
+        upload.file.write_all(data)?;
+        upload.hasher.update(data);
+        upload.received += data.len() as u64;
+        Ok(UploadProgress {
+            upload_id: upload_id.to_string(),
+            name: upload.name.clone(),
+            received: upload.received,
+            size: upload.size,
+        })
+    }
+
+    /// Verify and scan a fully received upload and move it into place. An incomplete
+    /// upload stays open; a checksum mismatch or a rejection discards it.
+    pub fn finish_upload(&self, policy: &TransferPolicy, upload_id: &str, overwrite: bool) -> Result<CompletedUpload, TransferError> {
+        let mut uploads = self.uploads.lock().unwrap();
+        let upload = uploads
+            .get(upload_id)
+            .ok_or_else(|| TransferError::UnknownUpload(upload_id.to_string()))?;
+        if upload.received != upload.size {
+            return Err(TransferError::Incomplete { received: upload.received, size: upload.size });
+        }
+
+        let mut upload = uploads.remove(upload_id).expect("upload looked up above");
+        // Scanning may take a while, other transfers go on meanwhile
+        drop(uploads);
+        let sha256 = hex(&std::mem::take(&mut upload.hasher).finalize());
+        if let Some(expected) = upload.sha256.clone().filter(|expected| *expected != sha256) {
+            upload.discard();
+            return Err(TransferError::ChecksumMismatch { expected, actual: sha256 });
+        }
+        if upload.target.exists() && !overwrite {
+            let target = upload.target.display().to_string();
+            upload.discard();
+            return Err(TransferError::AlreadyExists(target));
+        }
+
+        upload.file.flush()?;
+        upload.file.sync_all()?;
+        if let Err(e) = policy.scan(&upload.name, &upload.part) {
+            upload.discard();
+            return Err(e);
+        }
+        if let Err(e) = fs::rename(&upload.part, &upload.target) {
+            upload.discard();
+            return Err(e.into());
+        }
+        info!("Received {} ({} bytes)", upload.target.display(), upload.size);
+        Ok(CompletedUpload { path: upload.target, size: upload.size, sha256 })
+    }
+}
+
+/// Hashes what it writes to `file`
+struct HashingWriter {
+    file: File,
+    hasher: Sha256,
+}
+
+impl Write for HashingWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        let written = self.file.write(data)?;
+        self.hasher.update(&data[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Store the files of a `multipart/form-data` body in `directory`, each streamed to a
+/// part file, checked like a WebSocket upload and then moved into place. Plain form
+/// fields are skipped. Stops at the first rejected file; the ones before it are kept.
+pub fn receive_multipart(
+    policy: &TransferPolicy,
+    body: impl Read,
+    boundary: &str,
+    directory: Option<&str>,
+    overwrite: bool,
+) -> Result<Vec<CompletedUpload>, TransferError> {
+    let directory = policy.directory(directory)?;
+    let mut multipart = Multipart::new(body, boundary);
+    let mut stored = Vec::new();
+
+    while let Some(part) = multipart.next_part()? {
+        // An empty file input is sent with an empty file name
+        let Some(name) = part.filename.filter(|name| !name.is_empty()) else {
+            continue;
+        };
+        policy.check_name(&name)?;
+        let target = directory.join(&name);
+        if target.exists() && !overwrite {
+            return Err(TransferError::AlreadyExists(target.display().to_string()));
+        }
+
+        let part_path = directory.join(format!(".{}.{}.part", name, uuid::Uuid::new_v4()));
+        let mut writer = HashingWriter { file: File::create(&part_path)?, hasher: Sha256::new() };
+        let result = multipart.copy_body(&mut writer, policy.max_file_size).and_then(|size| {
+            let HashingWriter { file, hasher } = writer;
+            file.sync_all()?;
+            drop(file);
+            policy.scan(&name, &part_path)?;
+            fs::rename(&part_path, &target)?;
+            Ok(CompletedUpload { path: target, size, sha256: hex(&hasher.finalize()) })
+        });
+        match result {
+            Ok(completed) => {
+                info!("Received {} ({} bytes) over HTTP", completed.path.display(), completed.size);
+                stored.push(completed);
+            }
+            Err(e) => {
+                if let Err(remove) = fs::remove_file(&part_path) {
+                    warn!("Failed to remove partial upload {}: {}", part_path.display(), remove);
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(stored)
+}
+
+fn failure(name: &str, error: TransferError) -> Value {
+    warn!("{} rejected: {}", name, error);
+    serde_json::json!({ "success": false, "error": error.to_string() })
+}
+
+/// Handle `file.upload_begin` and `file.upload_end`
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    let service = FileTransferService::global();
+    let policy = TransferPolicy::from_config(AppConfig::global());
+    let overwrite = payload.get("overwrite").and_then(Value::as_bool).unwrap_or(false);
+
+    match name {
+        UPLOAD_BEGIN => {
+            let file_name = payload["name"].as_str().unwrap_or_default();
+            let size = payload["size"].as_u64().unwrap_or_default();
+            let directory = payload.get("directory").and_then(Value::as_str);
+            let sha256 = payload.get("sha256").and_then(Value::as_str).map(str::to_string);
+            match service.begin_upload(&policy, file_name, size, directory, overwrite, sha256) {
+                Ok(info) => serde_json::json!({ "success": true, "upload": info }),
+                Err(e) => failure(name, e),
+            }
+        }
+        UPLOAD_END => {
+            let upload_id = payload["upload_id"].as_str().unwrap_or_default().to_string();
+            // Hashing the file and upload scanners may take a while
+            let finished = workers::run(workers::HASHING, move || service.finish_upload(&policy, &upload_id, overwrite))
+                .await
+                .unwrap_or_else(|e| Err(std::io::Error::other(e).into()));
+            match finished {
+                Ok(completed) => {
+                    emit_uploaded(&completed, "websocket").await;
+                    serde_json::json!({ "success": true, "file": completed })
+                }
+                Err(e) => failure(name, e),
+            }
+        }
+        _ => serde_json::json!({
+            "success": false,
+            "error": format!("Unknown file transfer command: {}", name)
+        }),
+    }
+}
+
+/// Handle a `file.upload_chunk` frame carrying `data`
+pub async fn handle_chunk(payload: &Value, data: &[u8]) -> Value {
+    let policy = TransferPolicy::from_config(AppConfig::global());
+    let upload_id = payload["upload_id"].as_str().unwrap_or_default();
+    let offset = payload["offset"].as_u64().unwrap_or_default();
+    let (id, sha256, data) = (upload_id.to_string(), payload["sha256"].as_str().unwrap_or_default().to_string(), data.to_vec());
+
+    let written = workers::run(workers::HASHING, move || FileTransferService::global().write_chunk(&policy, &id, offset, &sha256, &data))
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e).into()));
+    match written {
+        Ok(progress) => {
+            emit_progress(upload_id, "upload", &progress.name, progress.received, progress.size).await;
+            serde_json::json!({ "success": true, "upload_id": upload_id, "received": progress.received, "size": progress.size })
+        }
+        Err(e) => failure(UPLOAD_CHUNK, e),
+    }
+}
+
+/// Handle `file.download`: check the file, then stream it into `frames` in the background
+pub fn start_download(payload: &Value, frames: mpsc::Sender<Vec<u8>>) -> Value {
+    let policy = TransferPolicy::from_config(AppConfig::global());
+    let checked = policy.file(payload["path"].as_str().unwrap_or_default()).and_then(|path| {
+        let size = fs::metadata(&path)?.len();
+        policy.check_size(size)?;
+        Ok((path, size))
+    });
+    let (path, size) = match checked {
+        Ok(checked) => checked,
+        Err(e) => return failure(DOWNLOAD, e),
+    };
+
+    let download_id = uuid::Uuid::new_v4().to_string();
+    info!("Sending {} ({} bytes)", path.display(), size);
+    tokio::spawn(stream_download(download_id.clone(), path.clone(), size, policy.chunk_size, frames));
+    serde_json::json!({
+        "success": true,
+        "download_id": download_id,
+        "path": path,
+        "size": size,
+        "chunk_size": policy.chunk_size,
+    })
+}
+
+async fn stream_download(id: String, path: PathBuf, size: u64, chunk_size: usize, frames: mpsc::Sender<Vec<u8>>) {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let result = async {
+        let mut file = tokio::fs::File::open(&path).await?;
+        let mut hasher = Sha256::new();
+        let mut sent = 0u64;
+        let mut index = 0u64;
+        loop {
+            let mut chunk = Vec::with_capacity(chunk_size);
+            (&mut file).take(chunk_size as u64).read_to_end(&mut chunk).await?;
+            if chunk.is_empty() {
+                break;
+            }
+            hasher.update(&chunk);
+            let header = frame_header(
+                &id,
+                DOWNLOAD_CHUNK,
+                serde_json::json!({ "download_id": id, "index": index, "offset": sent, "sha256": sha256_hex(&chunk) }),
+            );
+            sent += chunk.len() as u64;
+            index += 1;
+            if frames.send(encode_frame(&header, &chunk)).await.is_err() {
+                return Ok(None);
+            }
+            emit_progress(&id, "download", &name, sent, size).await;
+        }
+        Ok::<_, std::io::Error>(Some((sent, hex(&hasher.finalize()))))
+    }
+    .await;
+
+    let payload = match result {
+        Ok(None) => {
+            debug!("Connection closed during download {}", id);
+            return;
+        }
+        Ok(Some((sent, sha256))) => serde_json::json!({ "success": true, "download_id": id, "size": sent, "sha256": sha256 }),
+        Err(e) => {
+            error!("Download of {} failed: {}", path.display(), e);
+            serde_json::json!({ "success": false, "download_id": id, "error": e.to_string() })
+        }
+    };
+    if frames.send(encode_frame(&frame_header(&id, DOWNLOAD_END, payload), &[])).await.is_err() {
+        debug!("Connection closed before the end of download {}", id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    #[test]
+    fn test_frames_round_trip() {
+        let header = serde_json::json!({ "name": UPLOAD_CHUNK, "payload": { "offset": 0 } });
+        let frame = encode_frame(&header, b"data");
+        assert!(is_frame(&frame));
+        let (decoded, data): (Value, &[u8]) = decode_frame(&frame, &MessageLimits::default()).unwrap();
+        assert_eq!(decoded, header);
+        assert_eq!(data, b"data");
+
+        assert!(!is_frame(b"{\"name\":\"get_users\"}"));
+        assert!(decode_frame::<Value>(&frame[..10], &MessageLimits::default()).is_err());
+    }
+
+    #[test]
+    fn test_upload_checks_order_checksums_and_limits() {
+        let dir = TempDir::new("file-transfer-upload");
+        let policy = TransferPolicy::new(vec![dir.to_path_buf()], 10, 4);
+        let service = FileTransferService::new();
+
+        assert!(matches!(service.begin_upload(&policy, "../x", 1, None, false, None), Err(TransferError::InvalidName(_))));
+        assert!(matches!(service.begin_upload(&policy, "big", 11, None, false, None), Err(TransferError::TooLarge { .. })));
+        assert!(matches!(
+            service.begin_upload(&policy, "x", 1, Some("/"), false, None),
+            Err(TransferError::NotAllowed(_))
+        ));
+
+        let info = service
+            .begin_upload(&policy, "hello.txt", 6, None, false, Some(sha256_hex(b"hello!")))
+            .unwrap();
+        let id = info.upload_id.as_str();
+        assert!(matches!(service.write_chunk(&policy, id, 0, &sha256_hex(b"hel"), b"help"), Err(TransferError::ChecksumMismatch { .. })));
+        assert!(matches!(service.write_chunk(&policy, id, 0, &sha256_hex(b"hello!"), b"hello!"), Err(TransferError::ChunkTooLarge(6))));
+        service.write_chunk(&policy, id, 0, &sha256_hex(b"hell"), b"hell").unwrap();
+        assert!(matches!(service.write_chunk(&policy, id, 0, &sha256_hex(b"o!"), b"o!"), Err(TransferError::OutOfOrder { expected: 4, got: 0 })));
+        assert!(matches!(service.finish_upload(&policy, id, false), Err(TransferError::Incomplete { received: 4, size: 6 })));
+        service.write_chunk(&policy, id, 4, &sha256_hex(b"o!"), b"o!").unwrap();
+
+        let completed = service.finish_upload(&policy, id, false).unwrap();
+        assert_eq!(completed.sha256, sha256_hex(b"hello!"));
+        assert_eq!(fs::read(dir.join("hello.txt")).unwrap(), b"hello!");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        assert!(matches!(
+            service.begin_upload(&policy, "hello.txt", 1, None, false, None),
+            Err(TransferError::AlreadyExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_multipart_upload_checks_extensions_and_scanners() {
+        let dir = TempDir::new("file-transfer-multipart");
+        let mut policy = TransferPolicy::new(vec![dir.to_path_buf()], 10, 4);
+        policy.allowed_extensions = vec!["txt".to_string()];
+        let body = |name: &str, data: &str| {
+            format!(
+                "--b\r\nContent-Disposition: form-data; name=\"note\"\r\n\r\nskipped\r\n\
+                 --b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\r\n{}\r\n--b--\r\n",
+                name, data
+            )
+        };
+
+        let stored = receive_multipart(&policy, body("a.txt", "hello!").as_bytes(), "b", None, false).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].sha256, sha256_hex(b"hello!"));
+        assert_eq!(fs::read(dir.join("a.txt")).unwrap(), b"hello!");
+
+        let upload = |policy: &TransferPolicy, name: &str, data: &str| {
+            receive_multipart(policy, body(name, data).as_bytes(), "b", None, false)
+        };
+        assert!(matches!(upload(&policy, "a.txt", "again"), Err(TransferError::AlreadyExists(_))));
+        assert!(matches!(upload(&policy, "b.exe", "x"), Err(TransferError::ExtensionNotAllowed(_))));
+        assert!(matches!(upload(&policy, "b.txt", "far too long"), Err(TransferError::TooLarge { .. })));
+        #[cfg(unix)]
+        {
+            policy.scan_command = vec!["false".to_string()];
+            assert!(matches!(upload(&policy, "b.txt", "x"), Err(TransferError::Rejected { .. })));
+        }
+        // Rejected files leave nothing behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_streams_chunks() {
+        let dir = TempDir::new("file-transfer-download");
+        let path = dir.join("data.bin");
+        fs::write(&path, b"0123456789").unwrap();
+
+        let (tx, mut rx) = mpsc::channel(8);
+        stream_download("d1".to_string(), path, 10, 4, tx).await;
+        let mut received = Vec::new();
+        let mut end = Value::Null;
+        while let Some(frame) = rx.recv().await {
+            let (header, data): (Value, &[u8]) = decode_frame(&frame, &MessageLimits::default()).unwrap();
+            match header["name"].as_str() {
+                Some(DOWNLOAD_CHUNK) => {
+                    assert_eq!(header["payload"]["sha256"], sha256_hex(data));
+                    received.extend_from_slice(data);
+                }
+                _ => end = header["payload"].clone(),
+            }
+        }
+        assert_eq!(received, b"0123456789");
+        assert_eq!(end["sha256"], sha256_hex(b"0123456789"));
+    }
+}
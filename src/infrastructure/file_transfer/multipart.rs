@@ -0,0 +1,254 @@
+//! Streaming `multipart/form-data` reader
+//!
+//! Parts are handed out one at a time and their bodies copied straight to a
+//! writer, so an upload is never held in memory: only the read buffer and
+//! enough bytes to recognise a boundary split across two reads are kept.
+
+use std::io::{self, Read, Write};
+use super::TransferError;
+
+const READ_SIZE: usize = 64 * 1024;
+/// Part headers are a few short lines; anything longer is not a form upload
+const MAX_HEADER_SIZE: usize = 16 * 1024;
+
+/// The `boundary` parameter of a `multipart/form-data` content type
+pub fn boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';');
+    if !params.next()?.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|boundary| !boundary.is_empty() && boundary.len() <= 70)
+}
+
+/// The `Content-Disposition` of one part
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    pub name: String,
+    /// Set for file fields, `None` for plain form fields
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+}
+
+pub struct Multipart<R> {
+    reader: R,
+    /// `\r\n--boundary`; the first boundary has no leading CRLF, see `new`
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+    /// Inside a part body that has not been read to its end yet
+    in_body: bool,
+    finished: bool,
+}
+
+impl<R: Read> Multipart<R> {
+    pub fn new(reader: R, boundary: &str) -> Self {
+        Self {
+            reader,
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            // Lets the first boundary match the delimiter like all later ones
+            buffer: b"\r\n".to_vec(),
+            in_body: true,
+            finished: false,
+        }
+    }
+
+    /// Read more of the body into the buffer; false at the end of the input
+    fn fill(&mut self) -> Result<bool, TransferError> {
+        let start = self.buffer.len();
+        self.buffer.resize(start + READ_SIZE, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buffer[start..]) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result,
+            }
+        };
+        self.buffer.truncate(start + read.as_ref().copied().unwrap_or(0));
+        Ok(read? > 0)
+    }
+
+    fn find(&self, needle: &[u8]) -> Option<usize> {
+        self.buffer.windows(needle.len()).position(|window| window == needle)
+    }
+
+    /// Move on to the next part, skipping what is left of the current one.
+    /// `None` after the closing boundary.
+    pub fn next_part(&mut self) -> Result<Option<Part>, TransferError> {
+        if self.finished {
+            return Ok(None);
+        }
+        if self.in_body {
+            self.copy_body(&mut io::sink(), u64::MAX)?;
+        }
+
+        // After a delimiter: `--` closes the body, CRLF starts a part's headers
+        while self.buffer.len() < 2 {
+            if !self.fill()? {
+                return Err(malformed("body ends after a boundary"));
+            }
+        }
+        if self.buffer.starts_with(b"--") {
+            self.finished = true;
+            return Ok(None);
+        }
+        if !self.buffer.starts_with(b"\r\n") {
+            return Err(malformed("boundary is not followed by a line break"));
+        }
+
+        let end = loop {
+            if let Some(end) = self.find(b"\r\n\r\n") {
+                break end;
+            }
+            if self.buffer.len() > MAX_HEADER_SIZE {
+                return Err(malformed("part headers are too long"));
+            }
+            if !self.fill()? {
+                return Err(malformed("body ends inside part headers"));
+            }
+        };
+        let headers = String::from_utf8_lossy(&self.buffer[2..end]).into_owned();
+        self.buffer.drain(..end + 4);
+        self.in_body = true;
+        parse_part(&headers).map(Some)
+    }
+
+    /// Copy the body of the current part to `out`, failing once it passes `limit` bytes.
+    /// Returns the number of bytes copied.
+    pub fn copy_body(&mut self, out: &mut impl Write, limit: u64) -> Result<u64, TransferError> {
+        if !self.in_body {
+            return Ok(0);
+        }
+        let mut copied = 0u64;
+        loop {
+            let (take, found) = match self.find(&self.delimiter) {
+                Some(index) => (index, true),
+                // The tail may be the start of a delimiter split across reads
+                None => (self.buffer.len().saturating_sub(self.delimiter.len() - 1), false),
+            };
+            copied += take as u64;
+            if copied > limit {
+                return Err(TransferError::TooLarge { size: copied, limit });
+            }
+            out.write_all(&self.buffer[..take])?;
+            if found {
+                self.buffer.drain(..take + self.delimiter.len());
+                self.in_body = false;
+                return Ok(copied);
+            }
+            self.buffer.drain(..take);
+            if !self.fill()? {
+                return Err(malformed("body ends inside a part"));
+            }
+        }
+    }
+}
+
+fn malformed(reason: &str) -> TransferError {
+    TransferError::MalformedBody(reason.to_string())
+}
+
+fn parse_part(headers: &str) -> Result<Part, TransferError> {
+    let mut part = None;
+    let mut content_type = None;
+    for line in headers.split("\r\n") {
+        let Some((field, value)) = line.split_once(':') else {
+            continue;
+        };
+        if field.trim().eq_ignore_ascii_case("content-disposition") {
+            part = Some(parse_disposition(value)?);
+        } else if field.trim().eq_ignore_ascii_case("content-type") {
+            content_type = Some(value.trim().to_string());
+        }
+    }
+    let mut part = part.ok_or_else(|| malformed("part has no Content-Disposition"))?;
+    part.content_type = content_type;
+    Ok(part)
+}
+
+/// `form-data; name="file"; filename="a.txt"`
+fn parse_disposition(value: &str) -> Result<Part, TransferError> {
+    let mut params = value.split(';');
+    if params.next().map(str::trim) != Some("form-data") {
+        return Err(malformed("part is not form-data"));
+    }
+    let mut name = None;
+    let mut filename = None;
+    for (key, value) in params.filter_map(|param| param.split_once('=')) {
+        let value = value.trim().trim_matches('"').to_string();
+        match key.trim() {
+            "name" => name = Some(value),
+            "filename" => filename = Some(value),
+            _ => {}
+        }
+    }
+    Ok(Part {
+        name: name.ok_or_else(|| malformed("part has no name"))?,
+        filename,
+        content_type: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out at most `step` bytes per read, to split boundaries across reads
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.step.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    const BODY: &[u8] = b"preamble\r\n--XyZ\r\n\
+        Content-Disposition: form-data; name=\"note\"\r\n\r\n\
+        hi\r\n--XyZ\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a.bin\"\r\n\
+        Content-Type: application/octet-stream\r\n\r\n\
+        line one\r\n-XyZ\r\n--XyZ--\r\n";
+
+    #[test]
+    fn test_reads_parts_across_split_reads() {
+        assert_eq!(boundary("multipart/form-data; boundary=\"XyZ\"").as_deref(), Some("XyZ"));
+        assert_eq!(boundary("application/json"), None);
+
+        for step in [1, 3, 7, BODY.len()] {
+            let mut multipart = Multipart::new(Trickle { data: BODY, step }, "XyZ");
+            let note = multipart.next_part().unwrap().unwrap();
+            assert_eq!((note.name.as_str(), note.filename.as_deref()), ("note", None));
+
+            let file = multipart.next_part().unwrap().unwrap();
+            assert_eq!(file.filename.as_deref(), Some("a.bin"));
+            assert_eq!(file.content_type.as_deref(), Some("application/octet-stream"));
+            let mut data = Vec::new();
+            assert_eq!(multipart.copy_body(&mut data, 100).unwrap(), 14);
+            assert_eq!(data, b"line one\r\n-XyZ");
+            assert_eq!(multipart.next_part().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_rejects_oversized_and_truncated_bodies() {
+        let mut multipart = Multipart::new(BODY, "XyZ");
+        multipart.next_part().unwrap();
+        multipart.next_part().unwrap();
+        assert!(matches!(
+            multipart.copy_body(&mut Vec::new(), 10),
+            Err(TransferError::TooLarge { limit: 10, .. })
+        ));
+
+        let mut multipart = Multipart::new(&BODY[..BODY.len() - 20], "XyZ");
+        multipart.next_part().unwrap();
+        multipart.next_part().unwrap();
+        assert!(matches!(multipart.copy_body(&mut Vec::new(), 100), Err(TransferError::MalformedBody(_))));
+    }
+}
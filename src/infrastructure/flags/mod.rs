@@ -0,0 +1,230 @@
+//! Feature flags: turn starter features on and off while the app runs
+//!
+//! `[features]` in app.config.toml gives each flag its default. `flags.set`
+//! overrides a flag globally or for one user; overrides live in the `flags`
+//! settings namespace, so they survive restarts, and every change is emitted
+//! as `flags.changed`. The commands of a gated feature (see `GATES`) fail
+//! while its flag is off, and frontends read `flags.get_all` to show or hide
+//! the matching parts of the UI. Flags that are neither gates, configured nor
+//! overridden are off.
+
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, RwLock};
+use tracing::{error, info, warn};
+use crate::core::domain::{DomainResult, SettingsRepository};
+use crate::infrastructure::commands::command_message;
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::infrastructure::settings;
+use crate::model::core::AppConfig;
+
+/// Settings namespace of the overrides
+pub const NAMESPACE: &str = "flags";
+
+/// Flag of `POST /api/hooks/{name}`
+pub const HOOKS: &str = "hooks";
+
+/// Command prefixes and the flag their commands need; these flags are on
+/// unless `[features]` or an override turns them off
+const GATES: &[(&str, &str)] = &[
+    ("broker.", "broker"),
+    ("capture.", "capture"),
+    ("collab.", "collab"),
+    ("hotkeys.", "hotkeys"),
+    ("peers.", "discovery"),
+    ("sound.", "media"),
+    ("update.", "updater"),
+    ("webhooks.", "webhooks"),
+];
+
+/// The boolean values of a settings namespace; anything else is ignored
+fn booleans(values: Map<String, Value>) -> BTreeMap<String, bool> {
+    values
+        .into_iter()
+        .filter_map(|(name, value)| match value.as_bool() {
+            Some(enabled) => Some((name, enabled)),
+            None => {
+                warn!("Ignoring feature flag override {} = {}, flags are true or false", name, value);
+                None
+            }
+        })
+        .collect()
+}
+
+pub struct FeatureFlags {
+    defaults: BTreeMap<String, bool>,
+    /// Global overrides, mirrored from the settings store
+    overrides: RwLock<BTreeMap<String, bool>>,
+}
+
+impl FeatureFlags {
+    pub fn new(configured: &BTreeMap<String, bool>) -> Self {
+        let mut defaults: BTreeMap<String, bool> = GATES
+            .iter()
+            .map(|(_, flag)| flag.to_string())
+            .chain([HOOKS.to_string()])
+            .map(|flag| (flag, true))
+            .collect();
+        defaults.extend(configured.iter().map(|(name, enabled)| (name.clone(), *enabled)));
+        Self { defaults, overrides: RwLock::default() }
+    }
+
+    pub fn global() -> &'static FeatureFlags {
+        static FLAGS: OnceLock<FeatureFlags> = OnceLock::new();
+        FLAGS.get_or_init(|| FeatureFlags::new(AppConfig::global().get_feature_flags()))
+    }
+
+    fn overrides(&self) -> BTreeMap<String, bool> {
+        self.overrides.read().map(|overrides| overrides.clone()).unwrap_or_default()
+    }
+
+    /// Whether flag `name` is on for everyone
+    pub fn is_enabled(&self, name: &str) -> bool {
+        let overridden = self.overrides.read().ok().and_then(|overrides| overrides.get(name).copied());
+        overridden.or_else(|| self.defaults.get(name).copied()).unwrap_or(false)
+    }
+
+    /// The flag that is off and so refuses `command`, if any
+    pub fn refusing(&self, command: &str) -> Option<&'static str> {
+        GATES
+            .iter()
+            .find(|(prefix, _)| command.starts_with(prefix))
+            .map(|(_, flag)| *flag)
+            .filter(|flag| !self.is_enabled(flag))
+    }
+
+    /// Read the global overrides from the settings store
+    pub async fn load(&self, repo: &impl SettingsRepository) -> DomainResult<()> {
+        let overrides = booleans(settings::values(repo, NAMESPACE, None).await?);
+        if !overrides.is_empty() {
+            info!("Feature flag overrides: {:?}", overrides);
+        }
+        if let Ok(mut current) = self.overrides.write() {
+            *current = overrides;
+        }
+        Ok(())
+    }
+
+    /// Every flag as it is for `user_id`, or for everyone, and the overrides behind it
+    pub async fn all(&self, repo: &impl SettingsRepository, user_id: Option<i64>) -> DomainResult<(BTreeMap<String, bool>, BTreeMap<String, bool>)> {
+        let mut overrides = self.overrides();
+        if user_id.is_some() {
+            overrides.extend(booleans(
+                repo.list(NAMESPACE, user_id)
+                    .await?
+                    .into_iter()
+                    .map(|setting| (setting.key, setting.value))
+                    .collect(),
+            ));
+        }
+        let mut flags = self.defaults.clone();
+        flags.extend(overrides.iter().map(|(name, enabled)| (name.clone(), *enabled)));
+        Ok((flags, overrides))
+    }
+
+    /// Override flag `name` globally or for `user_id`; `None` removes the override.
+    /// Returns whether the flag is now on for that scope and emits `flags.changed`.
+    pub async fn set(&self, repo: &impl SettingsRepository, name: &str, enabled: Option<bool>, user_id: Option<i64>) -> DomainResult<bool> {
+        settings::set(repo, NAMESPACE, name, enabled.map_or(Value::Null, Value::Bool), user_id).await?;
+        if user_id.is_none() {
+            if let Ok(mut overrides) = self.overrides.write() {
+                match enabled {
+                    Some(enabled) => overrides.insert(name.to_string(), enabled),
+                    None => overrides.remove(name),
+                };
+            }
+        }
+        let now = match (user_id, enabled) {
+            (Some(_), Some(enabled)) => enabled,
+            _ => self.is_enabled(name),
+        };
+        info!("Feature flag {} is now {} ({})", name, if now { "on" } else { "off" }, user_id.map_or("everyone".to_string(), |id| format!("user {}", id)));
+
+        let payload = serde_json::json!({ "name": name, "enabled": now, "override": enabled, "user_id": user_id });
+        if let Err(e) = EventBus::global().emit_simple(&AppEventType::FlagsChanged.to_string(), payload).await {
+            error!("Failed to emit flags changed event: {}", e);
+        }
+        Ok(now)
+    }
+}
+
+/// Read the global overrides once the database is open
+pub async fn start() {
+    let result = match settings::repository() {
+        Ok(repo) => FeatureFlags::global().load(&repo).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        error!("Failed to load feature flag overrides, using [features] only: {}", e);
+    }
+}
+
+async fn run(name: &str, payload: &Value) -> DomainResult<Value> {
+    let repo = settings::repository()?;
+    let flags = FeatureFlags::global();
+    let user_id = payload.get("user_id").and_then(Value::as_i64);
+    match name {
+        "flags.get_all" => {
+            let (flags, overrides) = flags.all(&repo, user_id).await?;
+            Ok(serde_json::json!({ "success": true, "flags": flags, "overrides": overrides }))
+        }
+        "flags.set" => {
+            if let Some(id) = user_id {
+                settings::check_user(id).await?;
+            }
+            let flag = payload.get("name").and_then(Value::as_str).unwrap_or_default();
+            let enabled = flags.set(&repo, flag, payload.get("enabled").and_then(Value::as_bool), user_id).await?;
+            Ok(serde_json::json!({ "success": true, "name": flag, "enabled": enabled }))
+        }
+        other => Ok(serde_json::json!({ "success": false, "error": format!("Unknown command: {}", other) })),
+    }
+}
+
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    command_message(run(name, payload).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::database::SqliteSettingsRepository;
+    use crate::model::core::Database;
+    use std::sync::Arc;
+
+    fn repo() -> SqliteSettingsRepository {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        SqliteSettingsRepository::new(Arc::new(db))
+    }
+
+    #[tokio::test]
+    async fn test_overrides_win_over_config() {
+        let repo = repo();
+        let flags = FeatureFlags::new(&BTreeMap::from([("dark_mode".to_string(), true), ("broker".to_string(), false)]));
+        assert!(flags.is_enabled("dark_mode"));
+        assert!(flags.is_enabled("webhooks"));
+        assert!(!flags.is_enabled("broker"));
+        assert!(!flags.is_enabled("unknown"));
+
+        assert!(!flags.set(&repo, "dark_mode", Some(false), None).await.unwrap());
+        assert!(flags.set(&repo, "dark_mode", Some(true), Some(7)).await.unwrap());
+        let (user, overrides) = flags.all(&repo, Some(7)).await.unwrap();
+        assert_eq!((user["dark_mode"], overrides.len()), (true, 1));
+        let (everyone, _) = flags.all(&repo, None).await.unwrap();
+        assert!(!everyone["dark_mode"]);
+
+        // A fresh instance reads the global overrides back; removing one restores the default
+        let reloaded = FeatureFlags::new(&BTreeMap::from([("dark_mode".to_string(), true)]));
+        reloaded.load(&repo).await.unwrap();
+        assert!(!reloaded.is_enabled("dark_mode"));
+        assert!(reloaded.set(&repo, "dark_mode", None, None).await.unwrap());
+    }
+
+    #[test]
+    fn test_gates_refuse_commands_of_disabled_features() {
+        let flags = FeatureFlags::new(&BTreeMap::from([("webhooks".to_string(), false)]));
+        assert_eq!(flags.refusing("webhooks.list"), Some("webhooks"));
+        assert_eq!(flags.refusing("update.check"), None);
+        assert_eq!(flags.refusing("settings.get"), None);
+    }
+}
@@ -0,0 +1,388 @@
+//! File system watcher - recursive directory watches streamed as `fs.changed` events
+//!
+//! Raw notify events are collected for a short debounce window and published
+//! as one batch per watch, grouped into created / modified / removed paths.
+//! Watches belong to the WebSocket connections that made them: only those
+//! receive their `fs.changed` events, see and stop them, and a watch stops
+//! once no connection holds it.
+
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::model::core::AppConfig;
+
+#[derive(Debug, Error)]
+pub enum WatchError {
+    #[error("Not a directory: {0}")]
+    NotADirectory(String),
+    #[error("Watcher limit reached ({0} active watches)")]
+    LimitReached(usize),
+    #[error("Watch not found: {0}")]
+    NotFound(String),
+    #[error("Failed to watch path: {0}")]
+    Notify(#[from] notify::Error),
+}
+
+/// Paths changed during one debounce window
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+pub struct ChangeBatch {
+    pub created: BTreeSet<PathBuf>,
+    pub modified: BTreeSet<PathBuf>,
+    pub removed: BTreeSet<PathBuf>,
+}
+
+impl ChangeBatch {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+
+    /// Fold one raw event into the batch, collapsing repeated changes to the same path
+    pub fn record(&mut self, event: &Event) {
+        for path in &event.paths {
+            match event.kind {
+                EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                    self.removed.remove(path);
+                    self.modified.remove(path);
+                    self.created.insert(path.clone());
+                }
+                EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                    self.modified.remove(path);
+                    // Created and removed within the same window: never visible to the client
+                    if !self.created.remove(path) {
+                        self.removed.insert(path.clone());
+                    }
+                }
+                EventKind::Modify(_) => {
+                    if !self.created.contains(path) {
+                        self.modified.insert(path.clone());
+                    }
+                }
+                EventKind::Access(_) | EventKind::Any | EventKind::Other => {}
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchInfo {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+struct ActiveWatch {
+    path: PathBuf,
+    /// `Owned::owner` of the connections holding the watch
+    owners: HashSet<u64>,
+    // Dropping the watcher stops notify; the task then drains and exits
+    _watcher: RecommendedWatcher,
+    task: JoinHandle<()>,
+}
+
+pub struct FsWatcherService {
+    watches: Mutex<HashMap<String, ActiveWatch>>,
+}
+
+impl FsWatcherService {
+    fn new() -> Self {
+        Self {
+            watches: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn global() -> &'static FsWatcherService {
+        static SERVICE: OnceLock<FsWatcherService> = OnceLock::new();
+        SERVICE.get_or_init(FsWatcherService::new)
+    }
+
+    /// Start watching `path` recursively for `owner`; watching the same directory twice returns the existing watch
+    pub fn watch(&self, path: &str, owner: u64) -> Result<WatchInfo, WatchError> {
+        let path = PathBuf::from(path)
+            .canonicalize()
+            .ok()
+            .filter(|p| p.is_dir())
+            .ok_or_else(|| WatchError::NotADirectory(path.to_string()))?;
+
+        let mut watches = self.watches.lock().unwrap();
+        if let Some((id, watch)) = watches.iter_mut().find(|(_, w)| w.path == path) {
+            watch.owners.insert(owner);
+            return Ok(WatchInfo { id: id.clone(), path });
+        }
+
+        let config = AppConfig::global();
+        let max_watchers = config.get_fs_max_watchers();
+        if watches.len() >= max_watchers {
+            return Err(WatchError::LimitReached(max_watchers));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| match result {
+            Ok(event) => {
+                let _ = tx.send(event);
+            }
+            Err(e) => warn!("File watcher error: {}", e),
+        })?;
+        watcher.watch(&path, RecursiveMode::Recursive)?;
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let debounce = Duration::from_millis(config.get_fs_debounce_ms());
+        let task = tokio::spawn(forward_changes(id.clone(), path.clone(), rx, debounce));
+
+        info!("Watching {} ({})", path.display(), id);
+        watches.insert(
+            id.clone(),
+            ActiveWatch {
+                path: path.clone(),
+                owners: HashSet::from([owner]),
+                _watcher: watcher,
+                task,
+            },
+        );
+        Ok(WatchInfo { id, path })
+    }
+
+    /// Let go of `owner`'s watch by id or by the watched path, stopping it once nobody holds it
+    pub fn unwatch(&self, id_or_path: &str, owner: u64) -> Result<WatchInfo, WatchError> {
+        let mut watches = self.watches.lock().unwrap();
+        let canonical = PathBuf::from(id_or_path).canonicalize().ok();
+        let (id, watch) = watches
+            .iter_mut()
+            .find(|(id, w)| w.owners.contains(&owner) && (id.as_str() == id_or_path || Some(&w.path) == canonical.as_ref()))
+            .ok_or_else(|| WatchError::NotFound(id_or_path.to_string()))?;
+
+        watch.owners.remove(&owner);
+        let info = WatchInfo { id: id.clone(), path: watch.path.clone() };
+        if watch.owners.is_empty() {
+            Self::stop(&mut watches, &info.id);
+        }
+        Ok(info)
+    }
+
+    /// Let go of every watch of `owner`
+    pub fn release(&self, owner: u64) {
+        let mut watches = self.watches.lock().unwrap();
+        let mut unheld = Vec::new();
+        for (id, watch) in watches.iter_mut() {
+            if watch.owners.remove(&owner) && watch.owners.is_empty() {
+                unheld.push(id.clone());
+            }
+        }
+        for id in unheld {
+            Self::stop(&mut watches, &id);
+        }
+    }
+
+    fn stop(watches: &mut HashMap<String, ActiveWatch>, id: &str) {
+        if let Some(watch) = watches.remove(id) {
+            watch.task.abort();
+            info!("Stopped watching {} ({})", watch.path.display(), id);
+        }
+    }
+
+    /// The watches `owner` holds
+    pub fn list(&self, owner: u64) -> Vec<WatchInfo> {
+        self.watches
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, w)| w.owners.contains(&owner))
+            .map(|(id, w)| WatchInfo {
+                id: id.clone(),
+                path: w.path.clone(),
+            })
+            .collect()
+    }
+}
+
+/// The watches one WebSocket connection holds; dropping it lets go of them
+#[derive(Debug)]
+pub struct Owned {
+    owner: u64,
+    ids: HashSet<String>,
+}
+
+impl Default for Owned {
+    fn default() -> Self {
+        static NEXT_OWNER: AtomicU64 = AtomicU64::new(1);
+        Self { owner: NEXT_OWNER.fetch_add(1, Ordering::Relaxed), ids: HashSet::new() }
+    }
+}
+
+impl Owned {
+    /// Whether an event should reach the connection: any event but `fs.changed`
+    /// of a watch it does not hold
+    pub fn forwards(&self, name: &str, payload: &Value) -> bool {
+        name != AppEventType::FsChanged.to_string()
+            || payload["watch_id"].as_str().is_some_and(|id| self.ids.contains(id))
+    }
+}
+
+impl Drop for Owned {
+    fn drop(&mut self) {
+        if !self.ids.is_empty() {
+            FsWatcherService::global().release(self.owner);
+        }
+    }
+}
+
+/// Batch raw events per debounce window and publish them on the event bus
+async fn forward_changes(
+    watch_id: String,
+    root: PathBuf,
+    mut rx: mpsc::UnboundedReceiver<Event>,
+    debounce: Duration,
+) {
+    let bus = EventBus::global();
+
+    while let Some(first) = rx.recv().await {
+        let mut batch = ChangeBatch::default();
+        batch.record(&first);
+
+        let deadline = tokio::time::sleep(debounce);
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                _ = &mut deadline => break,
+                event = rx.recv() => match event {
+                    Some(event) => batch.record(&event),
+                    None => break,
+                },
+            }
+        }
+
+        if batch.is_empty() {
+            continue;
+        }
+
+        debug!(
+            "{}: {} created, {} modified, {} removed",
+            root.display(),
+            batch.created.len(),
+            batch.modified.len(),
+            batch.removed.len()
+        );
+        if let Err(e) = bus
+            .emit_simple(
+                &AppEventType::FsChanged.to_string(),
+                serde_json::json!({
+                    "watch_id": watch_id,
+                    "path": root,
+                    "created": batch.created,
+                    "modified": batch.modified,
+                    "removed": batch.removed
+                }),
+            )
+            .await
+        {
+            error!("Failed to emit fs.changed event: {}", e);
+        }
+    }
+}
+
+/// Handle `watch_path` / `unwatch` / `list_watches` for the connection holding `owned`
+pub fn handle_command(name: &str, payload: &Value, owned: &mut Owned) -> Value {
+    let service = FsWatcherService::global();
+
+    let result = match name {
+        "watch_path" => {
+            let path = payload.get("path").and_then(|v| v.as_str()).unwrap_or_default();
+            service.watch(path, owned.owner).inspect(|watch| {
+                owned.ids.insert(watch.id.clone());
+            })
+        }
+        "unwatch" => {
+            let target = payload
+                .get("id")
+                .or_else(|| payload.get("path"))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            service.unwatch(target, owned.owner).inspect(|watch| {
+                owned.ids.remove(&watch.id);
+            })
+        }
+        "list_watches" => {
+            return serde_json::json!({ "success": true, "watches": service.list(owned.owner) });
+        }
+        _ => {
+            return serde_json::json!({
+                "success": false,
+                "error": format!("Unknown watcher command: {}", name)
+            });
+        }
+    };
+
+    match result {
+        Ok(watch) => serde_json::json!({ "success": true, "watch": watch }),
+        Err(e) => serde_json::json!({ "success": false, "error": e.to_string() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+    use notify::event::{CreateKind, DataChange, RemoveKind};
+
+    fn event(kind: EventKind, path: &str) -> Event {
+        Event::new(kind).add_path(PathBuf::from(path))
+    }
+
+    #[test]
+    fn test_batch_groups_changes() {
+        let mut batch = ChangeBatch::default();
+        batch.record(&event(EventKind::Create(CreateKind::File), "/w/new.txt"));
+        batch.record(&event(EventKind::Modify(ModifyKind::Data(DataChange::Any)), "/w/new.txt"));
+        batch.record(&event(EventKind::Modify(ModifyKind::Data(DataChange::Any)), "/w/old.txt"));
+        batch.record(&event(EventKind::Remove(RemoveKind::File), "/w/gone.txt"));
+
+        assert_eq!(batch.created.iter().collect::<Vec<_>>(), vec![&PathBuf::from("/w/new.txt")]);
+        assert_eq!(batch.modified.iter().collect::<Vec<_>>(), vec![&PathBuf::from("/w/old.txt")]);
+        assert_eq!(batch.removed.iter().collect::<Vec<_>>(), vec![&PathBuf::from("/w/gone.txt")]);
+    }
+
+    #[test]
+    fn test_transient_file_is_dropped() {
+        let mut batch = ChangeBatch::default();
+        batch.record(&event(EventKind::Create(CreateKind::File), "/w/tmp"));
+        batch.record(&event(EventKind::Remove(RemoveKind::File), "/w/tmp"));
+        batch.record(&event(EventKind::Access(notify::event::AccessKind::Any), "/w/read"));
+        assert!(batch.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_watches_belong_to_their_connections() {
+        let dir = TempDir::new("fs-watcher");
+        let path = dir.to_str().unwrap();
+        let service = FsWatcherService::global();
+        let (mut first, mut second) = (Owned::default(), Owned::default());
+
+        let watch = handle_command("watch_path", &serde_json::json!({ "path": path }), &mut first)["watch"]["id"].clone();
+        let changed = |id: &Value| serde_json::json!({ "watch_id": id });
+        assert!(first.forwards("fs.changed", &changed(&watch)));
+        assert!(!second.forwards("fs.changed", &changed(&watch)));
+        assert!(second.forwards("settings.changed", &Value::Null));
+        assert!(service.list(second.owner).is_empty());
+        let refused = handle_command("unwatch", &serde_json::json!({ "id": watch }), &mut second);
+        assert_eq!(refused["success"], false);
+
+        // Shared, and kept while either holds it
+        let shared = handle_command("watch_path", &serde_json::json!({ "path": path }), &mut second)["watch"]["id"].clone();
+        assert_eq!(shared, watch);
+        handle_command("unwatch", &serde_json::json!({ "path": path }), &mut first);
+        assert!(!first.forwards("fs.changed", &changed(&watch)));
+        assert_eq!(service.list(second.owner).len(), 1);
+
+        drop(second);
+        assert!(!service.watches.lock().unwrap().contains_key(watch.as_str().unwrap()));
+    }
+}
@@ -0,0 +1,205 @@
+//! Undo/redo for user and counter changes
+//!
+//! `update_user`, `create_user`, `delete_user`, `counter.create` and the
+//! counter updates journal what they changed. `undo` reverts the newest change
+//! and `redo` applies it again; `history.list` shows both stacks. Operations
+//! carry the values before and after rather than row versions, so a change can
+//! still be undone after unrelated edits, but not once its row has changed
+//! since: such an entry is dropped from the journal. Undoing a deletion
+//! restores the user's id, name, email and role; the password and email
+//! verification are gone.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+use crate::core::application::{History, Reversible, HISTORY_LIMIT};
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+use crate::infrastructure::commands::command_result;
+use crate::infrastructure::counters;
+use crate::infrastructure::database::database;
+use crate::model::core::{Database, UserChanges};
+
+static JOURNAL: Mutex<History<Operation>> = Mutex::const_new(History::new(HISTORY_LIMIT));
+
+/// The fields of a `users` row that undo restores
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserRecord {
+    pub id: i64,
+    pub name: String,
+    pub email: String,
+    pub role: String,
+}
+
+impl UserRecord {
+    /// From a user as `Database` returns it
+    pub fn from_value(user: &Value) -> Option<Self> {
+        serde_json::from_value(user.clone()).ok()
+    }
+}
+
+/// A journaled change
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Operation {
+    UpdateUser { from: UserRecord, to: UserRecord },
+    CreateUser { user: UserRecord },
+    DeleteUser { user: UserRecord },
+    SetCounter { id: String, from: i64, to: i64 },
+    CreateCounter { id: String, label: String },
+    DeleteCounter { id: String, label: String },
+}
+
+impl Reversible for Operation {
+    fn inverse(&self) -> Self {
+        match self.clone() {
+            Operation::UpdateUser { from, to } => Operation::UpdateUser { from: to, to: from },
+            Operation::CreateUser { user } => Operation::DeleteUser { user },
+            Operation::DeleteUser { user } => Operation::CreateUser { user },
+            Operation::SetCounter { id, from, to } => Operation::SetCounter { id, from: to, to: from },
+            Operation::CreateCounter { id, label } => Operation::DeleteCounter { id, label },
+            Operation::DeleteCounter { id, label } => Operation::CreateCounter { id, label },
+        }
+    }
+}
+
+/// Journal a change that has been applied
+pub async fn record(label: impl Into<String>, operation: Operation) {
+    JOURNAL.lock().await.record(label, operation);
+}
+
+/// The version of user `expected.id` while it still matches `expected`
+fn unchanged_version(db: &Database, expected: &UserRecord) -> AppResult<i64> {
+    let current = db
+        .get_user(expected.id)
+        .map_err(|e| AppError::new(ErrorCode::DatabaseError, e.to_string()))?
+        .ok_or_else(|| AppError::new(ErrorCode::EntityNotFound, format!("User {} not found", expected.id)))?;
+    if UserRecord::from_value(&current).as_ref() != Some(expected) {
+        return Err(AppError::new(ErrorCode::ConflictError, format!("User {} was changed since", expected.id))
+            .with_context("current", current));
+    }
+    Ok(current["version"].as_i64().unwrap_or_default())
+}
+
+async fn apply(operation: &Operation) -> AppResult<Value> {
+    match operation {
+        Operation::UpdateUser { from, to } => {
+            let db = database()?;
+            let version = unchanged_version(&db, from)?;
+            let changes = UserChanges {
+                name: Some(to.name.clone()),
+                email: Some(to.email.clone()),
+                role: Some(to.role.clone()),
+            };
+            Ok(serde_json::json!({ "user": db.update_user(to.id, &changes, version)? }))
+        }
+        Operation::CreateUser { user } => {
+            let created = database()?.create_user(Some(user.id), &user.name, &user.email, &user.role)?;
+            Ok(serde_json::json!({ "user": created }))
+        }
+        Operation::DeleteUser { user } => {
+            let db = database()?;
+            let version = unchanged_version(&db, user)?;
+            Ok(serde_json::json!({ "user": db.delete_user(user.id, version)? }))
+        }
+        Operation::SetCounter { id, from, to } => {
+            Ok(serde_json::json!({ "counter": counters::set_value(id, *from, *to).await? }))
+        }
+        Operation::CreateCounter { id, label } => Ok(serde_json::json!({ "counter": counters::restore(id, label).await? })),
+        Operation::DeleteCounter { id, .. } => Ok(serde_json::json!({ "counter": counters::remove(id, 0).await? })),
+    }
+}
+
+/// Undo the newest change, or redo the newest undone one
+async fn step(redo: bool) -> AppResult<Value> {
+    let mut journal = JOURNAL.lock().await;
+    let next = if redo { journal.next_redo() } else { journal.next_undo() };
+    let Some((entry, operation)) = next.map(|(entry, operation)| (entry.clone(), operation)) else {
+        let message = if redo { "Nothing to redo" } else { "Nothing to undo" };
+        return Err(AppError::new(ErrorCode::InvalidStateTransition, message));
+    };
+
+    match apply(&operation).await {
+        Ok(result) => {
+            if redo {
+                journal.redone();
+            } else {
+                journal.undone();
+            }
+            info!("{} {}", if redo { "Redid" } else { "Undid" }, entry.label);
+            Ok(serde_json::json!({ "success": true, "entry": entry, "result": result }))
+        }
+        Err(e) => {
+            // A change whose row moved on cannot be reverted later either
+            if e.code != ErrorCode::DatabaseError {
+                warn!("Dropping {} from the history: {}", entry.label, e.message);
+                if redo {
+                    journal.discard_redo();
+                } else {
+                    journal.discard_undo();
+                }
+            }
+            Err(e.with_context("entry", entry.label))
+        }
+    }
+}
+
+async fn list() -> Value {
+    let journal = JOURNAL.lock().await;
+    serde_json::json!({
+        "success": true,
+        "undo": journal.undo_entries().collect::<Vec<_>>(),
+        "redo": journal.redo_entries().collect::<Vec<_>>(),
+    })
+}
+
+pub async fn handle_command(name: &str, _payload: &Value) -> Value {
+    let result = match name {
+        "undo" => step(false).await,
+        "redo" => step(true).await,
+        "history.list" => return list().await,
+        other => return serde_json::json!({ "success": false, "error": format!("Unknown command: {}", other) }),
+    };
+    command_result(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inverse_swaps_before_and_after() {
+        let jane = UserRecord { id: 2, name: "Jane".to_string(), email: "jane@example.com".to_string(), role: "editor".to_string() };
+        let renamed = UserRecord { name: "Janet".to_string(), ..jane.clone() };
+        let update = Operation::UpdateUser { from: jane.clone(), to: renamed.clone() };
+        assert_eq!(update.inverse(), Operation::UpdateUser { from: renamed, to: jane.clone() });
+        assert_eq!(update.inverse().inverse(), update);
+        assert_eq!(Operation::CreateUser { user: jane.clone() }.inverse(), Operation::DeleteUser { user: jane });
+
+        let set = Operation::SetCounter { id: "clicks".to_string(), from: 1, to: 2 };
+        assert_eq!(set.inverse(), Operation::SetCounter { id: "clicks".to_string(), from: 2, to: 1 });
+
+        let user = serde_json::json!({ "id": 2, "name": "Jane", "email": "j@example.com", "role": "editor", "version": 4 });
+        assert_eq!(UserRecord::from_value(&user).unwrap().email, "j@example.com");
+    }
+
+    #[test]
+    fn test_create_and_delete_user_restore_the_record() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        db.insert_sample_data().unwrap();
+
+        let bob = UserRecord::from_value(&db.get_user(3).unwrap().unwrap()).unwrap();
+        let version = unchanged_version(&db, &bob).unwrap();
+        db.delete_user(3, version).unwrap();
+        assert!(matches!(unchanged_version(&db, &bob), Err(e) if e.code == ErrorCode::EntityNotFound));
+
+        let restored = db.create_user(Some(bob.id), &bob.name, &bob.email, &bob.role).unwrap();
+        assert_eq!(UserRecord::from_value(&restored).unwrap(), bob);
+        assert!(db.create_user(Some(bob.id), &bob.name, &bob.email, &bob.role).is_err());
+
+        let changes = UserChanges { role: Some("admin".to_string()), ..Default::default() };
+        db.update_user(3, &changes, 1).unwrap();
+        assert!(matches!(unchanged_version(&db, &bob), Err(e) if e.code == ErrorCode::ConflictError));
+    }
+}
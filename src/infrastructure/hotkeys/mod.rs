@@ -0,0 +1,329 @@
+//! OS-level keyboard shortcuts, `[hotkeys]` (`hotkeys` feature)
+//!
+//! Shortcuts are registered with the OS through `global-hotkey`, so they work
+//! while the app is in the background: `[hotkeys.bindings]` at startup and
+//! `hotkeys.register { accelerator, action?, command?, payload? }` at runtime.
+//! An accelerator is modifiers and one key, e.g. `ctrl+shift+KeyH` or
+//! `alt+F1`. Each press runs the binding's `command` through the command bus,
+//! if it has one, and emits `hotkey.pressed` with the `action`, the command
+//! and its `result`, so the frontend can react, e.g. hide its windows.
+//!
+//! A shortcut is bound once: registering another spelling of a bound one
+//! (`shift+ctrl+h` for `ctrl+shift+KeyH`) or one the OS refuses, typically
+//! because another program holds it, fails with a `ConflictError`.
+//!
+//! The manager lives on a thread of its own; on Windows that thread also runs
+//! the message loop hotkeys are delivered through. macOS wants the manager on
+//! the main thread, which the window owns, so hotkeys are not started there.
+
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::{mpsc, Mutex, MutexGuard, OnceLock};
+use tokio::sync::oneshot;
+use tracing::{error, info, warn};
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+use crate::infrastructure::commands::command_result;
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::model::core::{AppConfig, HotkeyBinding};
+use crate::viewmodel::websocket_handler::WebSocketHandler;
+
+/// `source` of the bindings from `[hotkeys.bindings]`
+pub const CONFIG: &str = "config";
+/// `source` of the bindings from `hotkeys.register`
+pub const RUNTIME: &str = "runtime";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Binding {
+    /// As the OS knows it, e.g. `shift+control+KeyH`
+    pub accelerator: String,
+    pub action: Option<String>,
+    pub command: Option<String>,
+    /// Sent with the command, `{}` when none
+    pub payload: Value,
+    pub source: &'static str,
+    #[serde(skip)]
+    hotkey: HotKey,
+}
+
+impl Binding {
+    fn new(accelerator: &str, binding: HotkeyBinding, source: &'static str) -> AppResult<Self> {
+        let hotkey: HotKey = accelerator.parse().map_err(|e| {
+            AppError::new(ErrorCode::ValidationFailed, format!("Invalid accelerator {}: {}", accelerator, e))
+                .with_context("accelerator", accelerator)
+        })?;
+        Ok(Self {
+            accelerator: hotkey.into_string(),
+            action: binding.action.filter(|action| !action.is_empty()),
+            command: binding.command.filter(|command| !command.is_empty()),
+            payload: binding.payload.filter(|payload| !payload.is_null()).unwrap_or_else(|| Value::Object(Default::default())),
+            source,
+            hotkey,
+        })
+    }
+}
+
+fn conflict(binding: &Binding, reason: impl std::fmt::Display) -> AppError {
+    AppError::new(ErrorCode::ConflictError, format!("{} is taken: {}", binding.accelerator, reason))
+        .with_context("accelerator", binding.accelerator.as_str())
+}
+
+/// The bound shortcuts, in the order they were bound
+#[derive(Debug, Default)]
+struct Bindings(Vec<Binding>);
+
+impl Bindings {
+    /// Claim the binding's shortcut unless it is bound already
+    fn claim(&mut self, binding: &Binding) -> AppResult<()> {
+        if let Some(existing) = self.0.iter().find(|existing| existing.hotkey.id() == binding.hotkey.id()) {
+            let owner = existing.command.as_deref().or(existing.action.as_deref()).unwrap_or(existing.source);
+            return Err(conflict(binding, format!("already bound to {}", owner)));
+        }
+        self.0.push(binding.clone());
+        Ok(())
+    }
+
+    fn release(&mut self, hotkey: &HotKey) -> Option<Binding> {
+        let index = self.0.iter().position(|binding| binding.hotkey.id() == hotkey.id())?;
+        Some(self.0.remove(index))
+    }
+
+    fn get(&self, id: u32) -> Option<&Binding> {
+        self.0.iter().find(|binding| binding.hotkey.id() == id)
+    }
+}
+
+/// Work for the manager thread; each answers whether the OS agreed
+enum Request {
+    Register(HotKey, oneshot::Sender<Result<(), String>>),
+    Unregister(HotKey, oneshot::Sender<Result<(), String>>),
+}
+
+struct Hotkeys {
+    bindings: Mutex<Bindings>,
+    requests: mpsc::Sender<Request>,
+}
+
+static HOTKEYS: OnceLock<Hotkeys> = OnceLock::new();
+
+impl Hotkeys {
+    fn bindings(&self) -> MutexGuard<'_, Bindings> {
+        self.bindings.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn ask(&self, request: impl FnOnce(oneshot::Sender<Result<(), String>>) -> Request) -> Result<(), String> {
+        let (reply, answer) = oneshot::channel();
+        self.requests.send(request(reply)).map_err(|_| "The hotkeys thread stopped".to_string())?;
+        answer.await.map_err(|_| "The hotkeys thread stopped".to_string())?
+    }
+
+    async fn register(&self, binding: Binding) -> AppResult<Binding> {
+        // Claimed first, so two registrations of one shortcut cannot both reach the OS
+        self.bindings().claim(&binding)?;
+        if let Err(reason) = self.ask(|reply| Request::Register(binding.hotkey, reply)).await {
+            self.bindings().release(&binding.hotkey);
+            return Err(conflict(&binding, reason));
+        }
+        info!("Registered hotkey {}", binding.accelerator);
+        Ok(binding)
+    }
+
+    async fn unregister(&self, accelerator: &str) -> AppResult<Binding> {
+        let hotkey = Binding::new(accelerator, HotkeyBinding::default(), RUNTIME)?.hotkey;
+        let binding = self.bindings().release(&hotkey).ok_or_else(|| {
+            AppError::new(ErrorCode::EntityNotFound, format!("No hotkey {} is registered", accelerator))
+        })?;
+        if let Err(reason) = self.ask(|reply| Request::Unregister(hotkey, reply)).await {
+            warn!("Failed to unregister hotkey {}: {}", binding.accelerator, reason);
+        }
+        Ok(binding)
+    }
+}
+
+/// Run the manager's requests on this thread until the app stops
+fn serve(manager: GlobalHotKeyManager, requests: mpsc::Receiver<Request>) {
+    loop {
+        match next_request(&requests) {
+            Ok(Some(Request::Register(hotkey, reply))) => {
+                let _ = reply.send(manager.register(hotkey).map_err(|e| e.to_string()));
+            }
+            Ok(Some(Request::Unregister(hotkey, reply))) => {
+                let _ = reply.send(manager.unregister(hotkey).map_err(|e| e.to_string()));
+            }
+            Ok(None) => {}
+            // Nothing can send requests any more
+            Err(_) => break,
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn next_request(requests: &mpsc::Receiver<Request>) -> Result<Option<Request>, mpsc::RecvTimeoutError> {
+    requests.recv().map(Some).map_err(|_| mpsc::RecvTimeoutError::Disconnected)
+}
+
+/// Hotkeys reach the manager's hidden window as messages of this thread
+#[cfg(windows)]
+fn next_request(requests: &mpsc::Receiver<Request>) -> Result<Option<Request>, mpsc::RecvTimeoutError> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE};
+    // SAFETY: MSG is plain data, and the messages are those of this thread's own windows
+    unsafe {
+        let mut message: MSG = std::mem::zeroed();
+        while PeekMessageW(&mut message, std::ptr::null_mut(), 0, 0, PM_REMOVE) != 0 {
+            TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+    match requests.recv_timeout(std::time::Duration::from_millis(10)) {
+        Ok(request) => Ok(Some(request)),
+        Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Start the manager thread; the requests it takes once the OS gave it one
+fn spawn_manager() -> Result<mpsc::Sender<Request>, String> {
+    let (requests, incoming) = mpsc::channel();
+    let (ready, started) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("hotkeys".to_string())
+        .spawn(move || match GlobalHotKeyManager::new() {
+            Ok(manager) => {
+                let _ = ready.send(Ok(()));
+                serve(manager, incoming);
+            }
+            Err(e) => {
+                let _ = ready.send(Err(e.to_string()));
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    started.recv().map_err(|_| "The hotkeys thread stopped".to_string())??;
+    Ok(requests)
+}
+
+/// Run the command of the binding pressed and announce the press
+async fn pressed(id: u32) {
+    let Some(binding) = HOTKEYS.get().and_then(|hotkeys| hotkeys.bindings().get(id).cloned()) else {
+        return;
+    };
+    let result = match &binding.command {
+        Some(command) => WebSocketHandler::handle_function_call(command, &binding.payload).await,
+        None => None,
+    };
+    let payload = serde_json::json!({
+        "accelerator": binding.accelerator,
+        "action": binding.action,
+        "command": binding.command,
+        "result": result,
+    });
+    if let Err(e) = EventBus::global().emit_simple(&AppEventType::HotkeyPressed.to_string(), payload).await {
+        error!("Failed to emit hotkey.pressed for {}: {}", binding.accelerator, e);
+    }
+}
+
+/// Register `[hotkeys.bindings]` with the OS; must run inside the tokio runtime
+pub fn start(config: &AppConfig) {
+    if !config.is_hotkeys_enabled() {
+        return;
+    }
+    if cfg!(target_os = "macos") {
+        warn!("Global hotkeys are not supported on macOS, where they need the main thread");
+        return;
+    }
+    let requests = match spawn_manager() {
+        Ok(requests) => requests,
+        Err(e) => {
+            error!("Global hotkeys are off: {}", e);
+            return;
+        }
+    };
+    let runtime = tokio::runtime::Handle::current();
+    GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+        if event.state() == HotKeyState::Pressed {
+            runtime.spawn(pressed(event.id()));
+        }
+    }));
+    let hotkeys = HOTKEYS.get_or_init(|| Hotkeys { bindings: Mutex::default(), requests });
+
+    let bindings = config.get_hotkey_bindings();
+    tokio::spawn(async move {
+        for (accelerator, binding) in bindings {
+            let registered = match Binding::new(&accelerator, binding, CONFIG) {
+                Ok(binding) => hotkeys.register(binding).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = registered {
+                warn!("Skipping hotkey {}: {}", accelerator, e.message);
+            }
+        }
+    });
+}
+
+fn hotkeys() -> AppResult<&'static Hotkeys> {
+    HOTKEYS.get().ok_or_else(|| {
+        AppError::new(ErrorCode::ServiceUnavailable, "Global hotkeys are not running, see [hotkeys] enabled")
+    })
+}
+
+async fn run(name: &str, payload: &Value) -> AppResult<Value> {
+    let hotkeys = hotkeys()?;
+    let accelerator = payload.get("accelerator").and_then(Value::as_str).unwrap_or_default();
+    match name {
+        "hotkeys.list" => {
+            let bindings = hotkeys.bindings().0.clone();
+            Ok(serde_json::json!({ "success": true, "bindings": bindings }))
+        }
+        "hotkeys.register" => {
+            let binding = HotkeyBinding {
+                action: payload.get("action").and_then(Value::as_str).map(String::from),
+                command: payload.get("command").and_then(Value::as_str).map(String::from),
+                payload: payload.get("payload").cloned(),
+            };
+            let binding = hotkeys.register(Binding::new(accelerator, binding, RUNTIME)?).await?;
+            Ok(serde_json::json!({ "success": true, "binding": binding }))
+        }
+        "hotkeys.unregister" => {
+            let binding = hotkeys.unregister(accelerator).await?;
+            Ok(serde_json::json!({ "success": true, "binding": binding }))
+        }
+        other => Err(AppError::new(ErrorCode::CommandFailed, format!("Unknown command: {}", other))),
+    }
+}
+
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    command_result(run(name, payload).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(accelerator: &str, command: Option<&str>) -> AppResult<Binding> {
+        let binding = HotkeyBinding { command: command.map(String::from), ..Default::default() };
+        Binding::new(accelerator, binding, RUNTIME)
+    }
+
+    #[test]
+    fn test_a_shortcut_is_bound_once() {
+        let mut bindings = Bindings::default();
+        let toggle = binding("ctrl+shift+KeyH", Some("ui.toggle")).unwrap();
+        assert_eq!(toggle.accelerator, "shift+control+KeyH");
+        assert_eq!(toggle.payload, serde_json::json!({}));
+        bindings.claim(&toggle).unwrap();
+
+        // Another spelling of the same shortcut
+        let conflict = bindings.claim(&binding("Shift+Control+H", None).unwrap()).unwrap_err();
+        assert_eq!(conflict.code, ErrorCode::ConflictError);
+        assert!(conflict.message.contains("ui.toggle"));
+        bindings.claim(&binding("ctrl+alt+KeyH", None).unwrap()).unwrap();
+        assert_eq!(bindings.get(toggle.hotkey.id()), Some(&toggle));
+
+        assert_eq!(bindings.release(&toggle.hotkey), Some(toggle.clone()));
+        assert_eq!(bindings.release(&toggle.hotkey), None);
+        bindings.claim(&toggle).unwrap();
+
+        assert_eq!(binding("ctrl+shift", None).unwrap_err().code, ErrorCode::ValidationFailed);
+        assert_eq!(binding("ctrl+KeyH+KeyJ", None).unwrap_err().code, ErrorCode::ValidationFailed);
+    }
+}
@@ -0,0 +1,204 @@
+//! Localized messages for the frontend
+//!
+//! Messages are looked up by key in JSON bundles, one flat object per
+//! locale. `en` and `de` are built in; `<[i18n] dir>/<locale>.json` adds a
+//! locale or replaces single messages of a built-in one. `{name}`
+//! placeholders are filled from the arguments. A missing message falls back
+//! to the default locale, then to `en`, then to the key itself.
+//!
+//! A WebSocket client picks its locale with `hello { locale }` or
+//! `session.set_locale`; it is kept with the session and applies to the
+//! `user_message` of every error sent to that client, including the
+//! `userNotification` recovery hint. Other callers get `[i18n] default_locale`.
+
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+use crate::model::core::AppConfig;
+
+const BUILT_IN: &[(&str, &str)] = &[("en", include_str!("locales/en.json")), ("de", include_str!("locales/de.json"))];
+
+/// Locale every message exists in
+const FALLBACK: &str = "en";
+
+tokio::task_local! {
+    static LOCALE: String;
+}
+
+type Bundle = HashMap<String, String>;
+
+fn parse(text: &str) -> Result<Bundle, String> {
+    let values: BTreeMap<String, Value> = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    values
+        .into_iter()
+        .map(|(key, value)| match value {
+            Value::String(message) => Ok((key, message)),
+            other => Err(format!("{} is {}, messages are strings", key, other)),
+        })
+        .collect()
+}
+
+/// `de_DE.UTF-8` and `de-de` become `de-DE`
+fn normalize(tag: &str) -> String {
+    let tag = tag.split('.').next().unwrap_or_default().replace('_', "-");
+    let mut parts = tag.split('-').filter(|part| !part.is_empty());
+    let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+    parts.fold(language, |tag, part| {
+        let part = if part.len() == 2 { part.to_ascii_uppercase() } else { part.to_string() };
+        format!("{}-{}", tag, part)
+    })
+}
+
+pub struct Catalog {
+    default_locale: String,
+    bundles: BTreeMap<String, Bundle>,
+}
+
+impl Catalog {
+    /// The built-in bundles
+    pub fn new(default_locale: &str) -> Self {
+        let bundles = BUILT_IN
+            .iter()
+            .map(|(locale, text)| (locale.to_string(), parse(text).expect("built-in message bundle")))
+            .collect();
+        Self { default_locale: normalize(default_locale), bundles }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        let mut catalog = Self::new(config.get_default_locale());
+        if let Some(dir) = config.get_i18n_dir() {
+            catalog.load_dir(Path::new(dir));
+        }
+        if !catalog.bundles.contains_key(&catalog.default_locale) {
+            warn!("No messages for the default locale {}, using {}", catalog.default_locale, FALLBACK);
+            catalog.default_locale = FALLBACK.to_string();
+        }
+        catalog
+    }
+
+    pub fn global() -> &'static Catalog {
+        static CATALOG: OnceLock<Catalog> = OnceLock::new();
+        CATALOG.get_or_init(|| Catalog::from_config(AppConfig::global()))
+    }
+
+    /// Merge `<locale>.json` bundles from `dir` over the built-in ones
+    pub fn load_dir(&mut self, dir: &Path) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Cannot read message bundles from {}: {}", dir.display(), e);
+                return;
+            }
+        };
+        for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+            let Some(locale) = path.extension().filter(|ext| *ext == "json").and(path.file_stem()).and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            match std::fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|text| parse(&text)) {
+                Ok(bundle) => {
+                    info!("Loaded {} messages for locale {} from {}", bundle.len(), normalize(locale), path.display());
+                    self.add(locale, bundle);
+                }
+                Err(e) => warn!("Skipping message bundle {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    pub fn add(&mut self, locale: &str, bundle: Bundle) {
+        self.bundles.entry(normalize(locale)).or_default().extend(bundle);
+    }
+
+    pub fn default_locale(&self) -> &str {
+        &self.default_locale
+    }
+
+    /// Locales with a bundle
+    pub fn locales(&self) -> Vec<&str> {
+        self.bundles.keys().map(String::as_str).collect()
+    }
+
+    /// The available locale serving `requested`: itself, else its language
+    pub fn negotiate(&self, requested: &str) -> Option<String> {
+        let tag = normalize(requested);
+        if self.bundles.contains_key(&tag) {
+            return Some(tag);
+        }
+        let language = tag.split('-').next().unwrap_or_default();
+        self.bundles.contains_key(language).then(|| language.to_string())
+    }
+
+    /// Message `key` in `locale` with `{name}` placeholders filled from `args`
+    pub fn message(&self, locale: Option<&str>, key: &str, args: &[(&str, &str)]) -> String {
+        let requested = locale.and_then(|locale| self.negotiate(locale));
+        let template = [requested.as_deref(), Some(self.default_locale.as_str()), Some(FALLBACK)]
+            .into_iter()
+            .flatten()
+            .find_map(|locale| self.bundles.get(locale).and_then(|bundle| bundle.get(key)));
+        let Some(template) = template else {
+            return key.to_string();
+        };
+        args.iter()
+            .fold(template.clone(), |message, (name, value)| message.replace(&format!("{{{}}}", name), value))
+    }
+}
+
+/// Run `future` with messages in `locale`, or the default one
+pub async fn scope<F: Future>(locale: Option<String>, future: F) -> F::Output {
+    match locale {
+        Some(locale) => LOCALE.scope(locale, future).await,
+        None => future.await,
+    }
+}
+
+/// Locale of the client being answered, if it chose one
+pub fn current_locale() -> Option<String> {
+    LOCALE.try_with(Clone::clone).ok()
+}
+
+/// Message `key` in the locale of the client being answered
+pub fn t(key: &str, args: &[(&str, &str)]) -> String {
+    Catalog::global().message(current_locale().as_deref(), key, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiates_and_falls_back() {
+        let mut catalog = Catalog::new("de");
+        catalog.add("fr", HashMap::from([("error.Timeout".to_string(), "Délai dépassé".to_string())]));
+
+        assert_eq!(catalog.negotiate("de_AT.UTF-8"), Some("de".to_string()));
+        assert_eq!(catalog.negotiate("FR-fr"), Some("fr".to_string()));
+        assert_eq!(catalog.negotiate("ja"), None);
+
+        assert_eq!(catalog.message(Some("fr-CA"), "error.Timeout", &[]), "Délai dépassé");
+        // Missing in fr, so the default locale's message
+        assert_eq!(catalog.message(Some("fr"), "error.EntityNotFound", &[]), "Der angeforderte Eintrag wurde nicht gefunden");
+        assert_eq!(catalog.message(Some("en"), "error.ValidationFailed", &[("message", "name is required")]), "Validation failed: name is required");
+        assert_eq!(catalog.message(None, "no.such.key", &[]), "no.such.key");
+    }
+
+    #[tokio::test]
+    async fn test_scope_sets_the_current_locale() {
+        assert_eq!(current_locale(), None);
+        let inside = scope(Some("de".to_string()), async { current_locale() }).await;
+        assert_eq!(inside.as_deref(), Some("de"));
+    }
+
+    #[test]
+    fn test_built_in_bundles_have_the_same_keys() {
+        let catalog = Catalog::new(FALLBACK);
+        let mut english: Vec<_> = catalog.bundles[FALLBACK].keys().collect();
+        english.sort();
+        for (locale, bundle) in &catalog.bundles {
+            let mut keys: Vec<_> = bundle.keys().collect();
+            keys.sort();
+            assert_eq!(keys, english, "{}", locale);
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Image organizer - sort photos into `YYYY/MM` folders by capture date
+//!
+//! The capture date comes from EXIF `DateTimeOriginal` when present and the
+//! file modification time otherwise. Runs are dry-run by default so the
+//! frontend can show the planned moves before anything is touched.
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::infrastructure::tasks::task_manager;
+use crate::infrastructure::workers;
+
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "webp", "bmp", "tif", "tiff", "heic", "heif", "avif", "raw", "cr2", "nef", "arw", "dng",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrganizeMode {
+    #[default]
+    Copy,
+    Move,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrganizeOptions {
+    pub source: String,
+    /// Defaults to the source folder
+    #[serde(default)]
+    pub destination: Option<String>,
+    #[serde(default)]
+    pub mode: OrganizeMode,
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+    #[serde(default = "default_recursive")]
+    pub recursive: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+fn default_recursive() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateSource {
+    Exif,
+    Modified,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub date_source: DateSource,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedFile {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct OrganizeReport {
+    pub dry_run: bool,
+    pub scanned: usize,
+    pub organized: usize,
+    pub already_in_place: usize,
+    pub failed: Vec<FailedFile>,
+    pub moves: Vec<PlannedMove>,
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Collect image files under `dir`, skipping hidden entries
+fn scan_images(dir: &Path, recursive: bool, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.'));
+        if hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            if recursive {
+                scan_images(&path, recursive, out)?;
+            }
+        } else if is_image(&path) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn exif_date(path: &Path) -> Option<(i32, u32)> {
+    let file = fs::File::open(path).ok()?;
+    let exif = exif::Reader::new()
+        .read_from_container(&mut std::io::BufReader::new(file))
+        .ok()?;
+
+    [exif::Tag::DateTimeOriginal, exif::Tag::DateTimeDigitized, exif::Tag::DateTime]
+        .iter()
+        .find_map(|tag| match &exif.get_field(*tag, exif::In::PRIMARY)?.value {
+            exif::Value::Ascii(values) => {
+                let date = exif::DateTime::from_ascii(values.first()?).ok()?;
+                (date.year > 0 && (1..=12).contains(&date.month)).then_some((date.year as i32, date.month as u32))
+            }
+            _ => None,
+        })
+}
+
+/// Year and month the image was taken, and where that came from
+fn capture_date(path: &Path) -> std::io::Result<(i32, u32, DateSource)> {
+    if let Some((year, month)) = exif_date(path) {
+        return Ok((year, month, DateSource::Exif));
+    }
+    let modified: DateTime<Utc> = fs::metadata(path)?.modified()?.into();
+    Ok((modified.year(), modified.month(), DateSource::Modified))
+}
+
+/// `destination/YYYY/MM/<file name>`, with a numeric suffix if that name exists or is already `planned`
+fn target_path(destination: &Path, year: i32, month: u32, file: &Path, planned: &HashSet<PathBuf>) -> PathBuf {
+    let folder = destination.join(format!("{:04}", year)).join(format!("{:02}", month));
+    let is_free = |path: &PathBuf| !path.exists() && !planned.contains(path);
+    let candidate = folder.join(file.file_name().unwrap_or_default());
+    if is_free(&candidate) {
+        return candidate;
+    }
+
+    let stem = file.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+    let extension = file.extension().and_then(|e| e.to_str());
+    (1..)
+        .map(|n| match extension {
+            Some(ext) => folder.join(format!("{}-{}.{}", stem, n, ext)),
+            None => folder.join(format!("{}-{}", stem, n)),
+        })
+        .find(is_free)
+        .expect("unbounded suffix search")
+}
+
+fn transfer(from: &Path, to: &Path, mode: OrganizeMode) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match mode {
+        OrganizeMode::Copy => fs::copy(from, to).map(|_| ()),
+        // Fall back to copy + delete when moving across file systems
+        OrganizeMode::Move => fs::rename(from, to).or_else(|_| {
+            fs::copy(from, to)?;
+            fs::remove_file(from)
+        }),
+    }
+}
+
+/// Organize the images in `options.source`, calling `progress(done, total, current)` per file;
+/// returning `false` from `progress` stops the run
+pub fn organize(
+    options: &OrganizeOptions,
+    mut progress: impl FnMut(usize, usize, &Path) -> bool,
+) -> Result<OrganizeReport, String> {
+    let source = PathBuf::from(&options.source)
+        .canonicalize()
+        .map_err(|e| format!("Invalid source folder '{}': {}", options.source, e))?;
+    let destination = match &options.destination {
+        Some(dest) => {
+            fs::create_dir_all(dest).map_err(|e| format!("Invalid destination '{}': {}", dest, e))?;
+            PathBuf::from(dest).canonicalize().map_err(|e| e.to_string())?
+        }
+        None => source.clone(),
+    };
+
+    let mut images = Vec::new();
+    scan_images(&source, options.recursive, &mut images).map_err(|e| e.to_string())?;
+    images.sort();
+
+    let mut report = OrganizeReport {
+        dry_run: options.dry_run,
+        scanned: images.len(),
+        ..Default::default()
+    };
+    let mut planned = HashSet::new();
+
+    for (index, image) in images.iter().enumerate() {
+        if !progress(index, images.len(), image) {
+            return Err("Cancelled".to_string());
+        }
+
+        let (year, month, date_source) = match capture_date(image) {
+            Ok(date) => date,
+            Err(e) => {
+                report.failed.push(FailedFile { path: image.clone(), error: e.to_string() });
+                continue;
+            }
+        };
+
+        let expected_folder = destination.join(format!("{:04}", year)).join(format!("{:02}", month));
+        if image.parent() == Some(expected_folder.as_path()) {
+            report.already_in_place += 1;
+            continue;
+        }
+
+        let target = target_path(&destination, year, month, image, &planned);
+        if !options.dry_run {
+            if let Err(e) = transfer(image, &target, options.mode) {
+                warn!("Failed to organize {}: {}", image.display(), e);
+                report.failed.push(FailedFile { path: image.clone(), error: e.to_string() });
+                continue;
+            }
+        }
+
+        report.organized += 1;
+        planned.insert(target.clone());
+        report.moves.push(PlannedMove { from: image.clone(), to: target, date_source });
+    }
+
+    progress(images.len(), images.len(), &source);
+    Ok(report)
+}
+
+/// Run `organize`, reporting progress and the final report on the event bus
+pub fn organize_with_events(options: &OrganizeOptions) -> Result<OrganizeReport, String> {
+    organize_with_events_until(options, |_, _| true)
+}
+
+/// Like `organize_with_events`, stopping when `keep_going(done, total)` returns false
+fn organize_with_events_until(
+    options: &OrganizeOptions,
+    keep_going: impl Fn(usize, usize) -> bool,
+) -> Result<OrganizeReport, String> {
+    let bus = EventBus::global();
+    let progress_event = AppEventType::ImagesOrganizeProgress.to_string();
+
+    info!(
+        "Organizing images in {} ({:?}, dry run: {})",
+        options.source, options.mode, options.dry_run
+    );
+    let result = organize(options, |done, total, current| {
+        if let Err(e) = futures::executor::block_on(bus.emit_simple(
+            &progress_event,
+            serde_json::json!({
+                "processed": done,
+                "total": total,
+                "current": current,
+                "dry_run": options.dry_run
+            }),
+        )) {
+            error!("Failed to emit organize progress event: {}", e);
+        }
+        keep_going(done, total)
+    });
+
+    let payload = match &result {
+        Ok(report) => serde_json::json!({ "success": true, "report": report }),
+        Err(e) => serde_json::json!({ "success": false, "error": e }),
+    };
+    if let Err(e) = futures::executor::block_on(bus.emit_simple("utility.images.organize", payload)) {
+        error!("Failed to emit organize images event: {}", e);
+    }
+
+    result
+}
+
+/// Handle `images.organize` from the frontend
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    if name != "images.organize" {
+        return serde_json::json!({
+            "success": false,
+            "error": format!("Unknown images command: {}", name)
+        });
+    }
+
+    let options: OrganizeOptions = match serde_json::from_value(payload.clone()) {
+        Ok(options) => options,
+        Err(e) => {
+            return serde_json::json!({
+                "success": false,
+                "error": format!("Invalid organize options: {}", e)
+            });
+        }
+    };
+
+    // Long runs go through the task manager so the frontend can follow and cancel them
+    if payload.get("background").and_then(|v| v.as_bool()).unwrap_or(false) {
+        // Cancelling stops waiting for the pool; a started run stops at its next image
+        let task_id = task_manager().spawn("images.organize", move |ctx| async move {
+            workers::run(workers::IMAGES, move || {
+                let report = organize_with_events_until(&options, |done, total| {
+                    ctx.report(done as f32 / total.max(1) as f32, Some(format!("{}/{} images", done, total)));
+                    !ctx.is_cancelled()
+                })?;
+                serde_json::to_value(report).map_err(|e| e.to_string())
+            })
+            .await
+            .and_then(|report| report)
+        });
+        return serde_json::json!({ "success": true, "task_id": task_id });
+    }
+
+    let result = workers::run(workers::IMAGES, move || organize_with_events(&options))
+        .await
+        .and_then(|result| result);
+
+    match result {
+        Ok(report) => serde_json::json!({ "success": true, "report": report }),
+        Err(e) => serde_json::json!({ "success": false, "error": e }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    fn options(source: &Path, dry_run: bool) -> OrganizeOptions {
+        OrganizeOptions {
+            source: source.to_string_lossy().into_owned(),
+            destination: None,
+            mode: OrganizeMode::Move,
+            dry_run,
+            recursive: true,
+        }
+    }
+
+    #[test]
+    fn test_target_path_avoids_collisions() {
+        let dir = TempDir::new("organize-test");
+        fs::create_dir_all(dir.join("2024/03")).unwrap();
+        fs::write(dir.join("2024/03/photo.jpg"), b"").unwrap();
+
+        let mut planned = HashSet::new();
+        let target = target_path(&dir, 2024, 3, Path::new("/elsewhere/photo.jpg"), &planned);
+        assert_eq!(target, dir.join("2024/03/photo-1.jpg"));
+
+        planned.insert(target);
+        let target = target_path(&dir, 2024, 3, Path::new("/other/photo.jpg"), &planned);
+        assert_eq!(target, dir.join("2024/03/photo-2.jpg"));
+    }
+
+    #[test]
+    fn test_dry_run_leaves_files_untouched() {
+        let temp = TempDir::new("organize-test");
+        let dir = temp.canonicalize().unwrap();
+        fs::write(dir.join("a.png"), b"not really a png").unwrap();
+        fs::write(dir.join("notes.txt"), b"").unwrap();
+
+        let mut calls = 0;
+        let report = organize(&options(&dir, true), |_, _, _| {
+            calls += 1;
+            true
+        }).unwrap();
+        assert_eq!(report.scanned, 1);
+        assert_eq!(report.organized, 1);
+        assert_eq!(report.moves[0].date_source, DateSource::Modified);
+        assert!(dir.join("a.png").exists());
+        assert!(!report.moves[0].to.exists());
+        assert_eq!(calls, 2);
+
+        // A real run moves the file; a second run finds it already in place
+        let report = organize(&options(&dir, false), |_, _, _| true).unwrap();
+        assert!(report.moves[0].to.exists());
+        assert!(!dir.join("a.png").exists());
+        let report = organize(&options(&dir, false), |_, _, _| true).unwrap();
+        assert_eq!(report.already_in_place, 1);
+    }
+}
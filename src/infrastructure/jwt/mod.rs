@@ -0,0 +1,278 @@
+//! Signed API tokens (JWT) for external tools, `[jwt]`
+//!
+//! With `[jwt] enabled = true` every gRPC call needs an `authorization:
+//! Bearer <token>` entry, and `/api/devtools/*` accepts an admin's token in
+//! place of the per-run DevTools token. `token.issue` signs a token for a row
+//! of the `users` table; a WebSocket client must prove admin rights for it.
+//! The `role` claim is the user's `UserRole` and decides what the bearer may
+//! do. HS256 tokens are signed with `secret`, or with a key generated on first
+//! use and kept in the OS keychain; RS256 tokens with the PEM key pair named
+//! by `private_key` and `public_key`.
+
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::OnceLock;
+use thiserror::Error;
+use tracing::{error, info};
+use crate::core::domain::{UserRepository, UserRole};
+use crate::infrastructure::commands::command_message;
+use crate::infrastructure::database::user_repository;
+use crate::infrastructure::secrets::{SecretsError, SecretsManager, SECRET_REF_PREFIX};
+use crate::model::core::AppConfig;
+
+/// Actions of `UserRole::can_perform` the API surfaces check
+#[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+pub const VIEW: &str = "view_content";
+#[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+pub const EDIT: &str = "edit_content";
+pub const ADMIN: &str = "admin_panel";
+
+/// Keychain secret of the generated HS256 key
+const GENERATED_SECRET: &str = "jwt:hs256";
+
+#[derive(Debug, Error)]
+pub enum JwtError {
+    #[error(transparent)]
+    Secrets(#[from] SecretsError),
+    #[error("Cannot read key file {0}: {1}")]
+    KeyFile(String, std::io::Error),
+    #[error("Unsupported [jwt] algorithm: {0} (use HS256 or RS256)")]
+    UnsupportedAlgorithm(String),
+    #[error("RS256 needs both [jwt] private_key and public_key")]
+    MissingKey,
+    #[error("Invalid key: {0}")]
+    Key(jsonwebtoken::errors::Error),
+    #[error("API tokens are disabled ([jwt] enabled = false)")]
+    Disabled,
+    #[error("API tokens are unavailable: {0}")]
+    Unavailable(String),
+    #[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+    #[error("Missing bearer token")]
+    Missing,
+    #[error("Invalid token: {0}")]
+    Invalid(jsonwebtoken::errors::Error),
+    #[error("The token's role does not allow {0}")]
+    Forbidden(String),
+    #[error("Unknown user: {0}")]
+    UnknownUser(i64),
+    #[error("Database error: {0}")]
+    Database(String),
+    #[error("The token lifetime is out of range")]
+    Lifetime,
+}
+
+/// Claims of the tokens `token.issue` signs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claims {
+    /// Id of the user in the `users` table
+    pub sub: String,
+    pub name: String,
+    pub role: UserRole,
+    pub iss: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+pub struct JwtAuth {
+    algorithm: Algorithm,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    issuer: String,
+    ttl: Duration,
+}
+
+/// A PEM key from a file, or from the keychain for a `secret:<name>` reference
+fn read_pem(value: &str) -> Result<Vec<u8>, JwtError> {
+    if value.starts_with(SECRET_REF_PREFIX) {
+        return Ok(SecretsManager::global().resolve(value)?.into_bytes());
+    }
+    std::fs::read(value).map_err(|e| JwtError::KeyFile(value.to_string(), e))
+}
+
+impl JwtAuth {
+    pub fn hs256(secret: &[u8], issuer: &str, ttl: Duration) -> Self {
+        Self {
+            algorithm: Algorithm::HS256,
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+            issuer: issuer.to_string(),
+            ttl,
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Result<Self, JwtError> {
+        let issuer = config.get_jwt_issuer();
+        let ttl = i64::try_from(config.get_jwt_ttl_mins())
+            .ok()
+            .and_then(Duration::try_minutes)
+            .ok_or(JwtError::Lifetime)?;
+        match config.get_jwt_algorithm().to_ascii_uppercase().as_str() {
+            "HS256" => {
+                let secrets = SecretsManager::global();
+                let secret = match config.get_jwt_secret() {
+                    Some(value) => secrets.resolve(value)?,
+                    None => secrets.get_or_create(GENERATED_SECRET, || {
+                        info!("Storing a new API token signing key in the OS keychain");
+                        format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+                    })?,
+                };
+                Ok(Self::hs256(secret.as_bytes(), issuer, ttl))
+            }
+            "RS256" => {
+                let (Some(private_key), Some(public_key)) = (config.get_jwt_private_key(), config.get_jwt_public_key()) else {
+                    return Err(JwtError::MissingKey);
+                };
+                Ok(Self {
+                    algorithm: Algorithm::RS256,
+                    encoding: EncodingKey::from_rsa_pem(&read_pem(private_key)?).map_err(JwtError::Key)?,
+                    decoding: DecodingKey::from_rsa_pem(&read_pem(public_key)?).map_err(JwtError::Key)?,
+                    issuer: issuer.to_string(),
+                    ttl,
+                })
+            }
+            other => Err(JwtError::UnsupportedAlgorithm(other.to_string())),
+        }
+    }
+
+    /// The keys of this run; a configuration error is logged once and fails every check
+    pub fn global() -> Result<&'static JwtAuth, JwtError> {
+        static AUTH: OnceLock<Result<JwtAuth, String>> = OnceLock::new();
+        let config = AppConfig::global();
+        if !config.is_jwt_enabled() {
+            return Err(JwtError::Disabled);
+        }
+        AUTH.get_or_init(|| {
+            Self::from_config(config).map_err(|e| {
+                error!("API tokens are unavailable: {}", e);
+                e.to_string()
+            })
+        })
+        .as_ref()
+        .map_err(|e| JwtError::Unavailable(e.clone()))
+    }
+
+    /// A lifetime of `mins` minutes, at least one and at most the configured lifetime
+    pub fn lifetime(&self, mins: u64) -> Duration {
+        i64::try_from(mins)
+            .ok()
+            .and_then(Duration::try_minutes)
+            .map_or(self.ttl, |ttl| ttl.clamp(Duration::minutes(1), self.ttl))
+    }
+
+    /// Claims for user `sub`, valid for `ttl` or the configured lifetime from `now`
+    pub fn claims(&self, sub: i64, name: &str, role: UserRole, ttl: Option<Duration>, now: DateTime<Utc>) -> Result<Claims, JwtError> {
+        let expires = now.checked_add_signed(ttl.unwrap_or(self.ttl)).ok_or(JwtError::Lifetime)?;
+        Ok(Claims {
+            sub: sub.to_string(),
+            name: name.to_string(),
+            role,
+            iss: self.issuer.clone(),
+            iat: now.timestamp(),
+            exp: expires.timestamp(),
+        })
+    }
+
+    pub fn sign(&self, claims: &Claims) -> Result<String, JwtError> {
+        jsonwebtoken::encode(&Header::new(self.algorithm), claims, &self.encoding).map_err(JwtError::Key)
+    }
+
+    /// Claims of `token` when its signature, issuer and expiry check out
+    pub fn verify(&self, token: &str) -> Result<Claims, JwtError> {
+        let mut validation = Validation::new(self.algorithm);
+        validation.set_issuer(&[&self.issuer]);
+        jsonwebtoken::decode::<Claims>(token, &self.decoding, &validation)
+            .map(|data| data.claims)
+            .map_err(JwtError::Invalid)
+    }
+
+    /// Verify `token` and check its role allows `action`
+    pub fn authorize(&self, token: &str, action: &str) -> Result<Claims, JwtError> {
+        let claims = self.verify(token)?;
+        if !claims.role.can_perform(action) {
+            return Err(JwtError::Forbidden(action.to_string()));
+        }
+        Ok(claims)
+    }
+}
+
+/// Check an `Authorization` value (`Bearer <token>`) for `action` with the configured keys
+#[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+pub fn authorize(header: Option<&str>, action: &str) -> Result<Claims, JwtError> {
+    let token = header
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(JwtError::Missing)?;
+    JwtAuth::global()?.authorize(token.trim(), action)
+}
+
+/// Sign a token for user `user_id` with the user's current name and role
+async fn issue(payload: &Value) -> Result<Value, JwtError> {
+    let auth = JwtAuth::global()?;
+    let user_id = payload.get("user_id").and_then(Value::as_i64).unwrap_or_default();
+    let ttl = payload.get("ttl_mins").and_then(Value::as_u64).map(|mins| auth.lifetime(mins));
+
+    let users = user_repository().map_err(|e| JwtError::Database(e.message))?;
+    let user = users
+        .get_by_id(user_id)
+        .await
+        .map_err(|e| JwtError::Database(e.to_string()))?
+        .ok_or(JwtError::UnknownUser(user_id))?;
+
+    let claims = auth.claims(user_id, &user.name, user.role, ttl, Utc::now())?;
+    let token = auth.sign(&claims)?;
+    info!("Issued an API token for user {} ({:?})", user_id, user.role);
+    let expires_at = DateTime::<Utc>::from_timestamp(claims.exp, 0).unwrap_or_default();
+    Ok(serde_json::json!({
+        "success": true,
+        "token": token,
+        "token_type": "Bearer",
+        "expires_at": expires_at,
+        "claims": claims,
+    }))
+}
+
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    match name {
+        "token.issue" => command_message(issue(payload).await),
+        other => serde_json::json!({ "success": false, "error": format!("Unknown command: {}", other) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signs_verifies_and_checks_roles() {
+        let auth = JwtAuth::hs256(b"test-secret", "tests", Duration::minutes(5));
+        let now = Utc::now();
+
+        let editor = auth.claims(2, "Jane", UserRole::Editor, None, now).unwrap();
+        let token = auth.sign(&editor).unwrap();
+        assert_eq!(auth.verify(&token).unwrap(), editor);
+        assert!(auth.authorize(&token, EDIT).is_ok());
+        assert!(matches!(auth.authorize(&token, ADMIN), Err(JwtError::Forbidden(_))));
+
+        // Other keys, other issuers and expired tokens are refused
+        let other_key = JwtAuth::hs256(b"other-secret", "tests", Duration::minutes(5));
+        assert!(matches!(other_key.verify(&token), Err(JwtError::Invalid(_))));
+        let other_issuer = JwtAuth::hs256(b"test-secret", "elsewhere", Duration::minutes(5));
+        assert!(matches!(other_issuer.verify(&token), Err(JwtError::Invalid(_))));
+        let expired = auth.claims(2, "Jane", UserRole::Editor, Some(Duration::minutes(5)), now - Duration::hours(1)).unwrap();
+        assert!(matches!(auth.verify(&auth.sign(&expired).unwrap()), Err(JwtError::Invalid(_))));
+        assert!(matches!(auth.verify("not.a.token"), Err(JwtError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_lifetimes_are_capped() {
+        let auth = JwtAuth::hs256(b"test-secret", "tests", Duration::minutes(60));
+        assert_eq!(auth.lifetime(0), Duration::minutes(1));
+        assert_eq!(auth.lifetime(30), Duration::minutes(30));
+        assert_eq!(auth.lifetime(u64::MAX), Duration::minutes(60));
+        assert_eq!(auth.lifetime(i64::MAX as u64 / 60), Duration::minutes(60));
+
+        let forever = JwtAuth::hs256(b"test-secret", "tests", Duration::MAX);
+        assert!(matches!(forever.claims(2, "Jane", UserRole::Editor, None, Utc::now()), Err(JwtError::Lifetime)));
+    }
+}
@@ -10,8 +10,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tracing::{error, warn, info, debug};
 
-/// Error severity levels
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Error severity levels, ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ErrorSeverity {
     /// Debug level - for development
@@ -222,6 +222,9 @@ pub fn log_error_with_severity(
     suggestion: Option<&str>,
 ) {
     let mut context_data = context.data;
+    if let Some(request_id) = super::request_context::current() {
+        context_data.entry("request_id".to_string()).or_insert(serde_json::json!(request_id));
+    }
     
     // Add error details
     context_data.insert("error_type".to_string(), serde_json::json!(std::any::type_name_of_val(&error)));
@@ -262,6 +265,9 @@ pub fn log_error_with_severity(
     if let Ok(json) = serde_json::to_string(&entry) {
         debug!("ERROR_JSON: {}", json);
     }
+
+    #[cfg(feature = "telemetry")]
+    crate::infrastructure::telemetry::Telemetry::global().report_entry(&entry);
 }
 
 /// Log an error with full context and severity (static lifetime version)
@@ -318,7 +324,7 @@ macro_rules! log_critical {
     };
 }
 
-/// Setup panic hook for better panic messages
+/// Setup panic hook for better panic messages and a crash report in `crashes/`
 pub fn setup_panic_hook() {
     std::panic::set_hook(Box::new(|panic_info| {
         let location = panic_info.location()
@@ -353,6 +359,16 @@ pub fn setup_panic_hook() {
             location,
             message
         );
+
+        let report = crate::infrastructure::crash_reporter::CrashReport::capture(&thread, &location, &message);
+        match report.write_to(std::path::Path::new(crate::infrastructure::crash_reporter::CRASH_DIR)) {
+            Ok(path) => eprintln!("\x1b[1mCrash report:\x1b[0m {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+
+        #[cfg(feature = "telemetry")]
+        crate::infrastructure::telemetry::Telemetry::global()
+            .report_panic(&thread, &location, &message, &report.backtrace);
     }));
 }
 
@@ -0,0 +1,80 @@
+//! The `[logging] file` log, next to the console
+//!
+//! Records are written in plain text through one shared handle. The
+//! `logs.rotate` job renames the file once it grows too large and calls
+//! `reopen`, so the app goes on writing to a fresh file at the same path.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing_subscriber::fmt::MakeWriter;
+
+struct LogFile {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+static LOG_FILE: OnceLock<LogFile> = OnceLock::new();
+
+fn open_file(path: &Path, append: bool) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+}
+
+/// Open `path` (emptied first unless `append`) as the log file of the process
+pub fn writer(path: &Path, append: bool) -> io::Result<LogFileWriter> {
+    let file = open_file(path, append)?;
+    let _ = LOG_FILE.set(LogFile { path: path.to_path_buf(), file: Mutex::new(file) });
+    Ok(LogFileWriter)
+}
+
+/// Path of the log file, once `writer` opened it
+pub fn path() -> Option<&'static Path> {
+    LOG_FILE.get().map(|log| log.path.as_path())
+}
+
+/// Start a new file at the log file's path, after it was renamed away;
+/// false when there is no log file
+pub fn reopen() -> io::Result<bool> {
+    let Some(log) = LOG_FILE.get() else {
+        return Ok(false);
+    };
+    let file = open_file(&log.path, true)?;
+    if let Ok(mut current) = log.file.lock() {
+        *current = file;
+    }
+    Ok(true)
+}
+
+/// Writes into the global log file
+#[derive(Debug, Clone, Copy)]
+pub struct LogFileWriter;
+
+impl Write for LogFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match LOG_FILE.get().map(|log| log.file.lock()) {
+            Some(Ok(mut file)) => file.write(buf),
+            _ => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match LOG_FILE.get().map(|log| log.file.lock()) {
+            Some(Ok(mut file)) => file.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogFileWriter {
+    type Writer = LogFileWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        *self
+    }
+}
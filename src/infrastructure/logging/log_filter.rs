@@ -0,0 +1,128 @@
+//! Runtime log filter adjustment
+//!
+//! The `EnvFilter` installed by `init_logging_with_config` is wrapped in a
+//! reload layer so devtools can raise or lower levels per target without a
+//! restart (`logging.set_level`, `logging.get_filter`).
+
+use serde_json::Value;
+use std::sync::OnceLock;
+use tracing::info;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+const LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error", "off"];
+
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Wrap the startup filter so it can be replaced later
+pub fn reloadable(filter: EnvFilter) -> reload::Layer<EnvFilter, Registry> {
+    let (layer, handle) = reload::Layer::new(filter);
+    let _ = FILTER_HANDLE.set(handle);
+    layer
+}
+
+/// Directives of the active filter, e.g. `rustwebui_app=info,tungstenite=warn`
+pub fn current_filter() -> Result<String, String> {
+    FILTER_HANDLE
+        .get()
+        .ok_or_else(|| "Logging is not initialized".to_string())?
+        .with_current(|filter| filter.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Replace the whole filter with `directives` (`RUST_LOG` syntax)
+pub fn set_filter(directives: &str) -> Result<String, String> {
+    let filter = EnvFilter::try_new(directives).map_err(|e| format!("Invalid filter '{}': {}", directives, e))?;
+    FILTER_HANDLE
+        .get()
+        .ok_or_else(|| "Logging is not initialized".to_string())?
+        .reload(filter)
+        .map_err(|e| e.to_string())?;
+    info!("Log filter set to {}", directives);
+    current_filter()
+}
+
+/// Set `level` for `target` (or the default level when `target` is `None`),
+/// keeping every other directive of the active filter
+pub fn set_level(target: Option<&str>, level: &str) -> Result<String, String> {
+    let level = level.to_lowercase();
+    if !LEVELS.contains(&level.as_str()) {
+        return Err(format!("Unknown log level '{}', expected one of {}", level, LEVELS.join(", ")));
+    }
+    if let Some(target) = target {
+        if target.is_empty() || target.contains([',', '=', ' ']) {
+            return Err(format!("Invalid log target '{}'", target));
+        }
+    }
+    set_filter(&with_level(&current_filter()?, target, &level))
+}
+
+/// `filter` with the directive for `target` replaced or appended
+fn with_level(filter: &str, target: Option<&str>, level: &str) -> String {
+    let mut directives: Vec<String> = filter
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .filter(|directive| directive_target(directive) != target)
+        .map(String::from)
+        .collect();
+    directives.push(match target {
+        Some(target) => format!("{}={}", target, level),
+        None => level.to_string(),
+    });
+    directives.join(",")
+}
+
+/// Target of a directive; `None` for a bare default level like `warn`
+fn directive_target(directive: &str) -> Option<&str> {
+    match directive.rsplit_once('=') {
+        Some((target, _)) => Some(target),
+        None if LEVELS.contains(&directive.to_lowercase().as_str()) => None,
+        None => Some(directive),
+    }
+}
+
+/// Handle `logging.get_filter` / `logging.set_level` devtools commands
+pub fn handle_command(name: &str, payload: &Value) -> Value {
+    let result = match name {
+        "logging.get_filter" => current_filter(),
+        "logging.set_level" => {
+            let level = payload.get("level").and_then(|v| v.as_str()).unwrap_or_default();
+            let target = payload.get("target").and_then(|v| v.as_str()).filter(|t| !t.is_empty());
+            set_level(target, level)
+        }
+        _ => Err(format!("Unknown logging command: {}", name)),
+    };
+    match result {
+        Ok(filter) => serde_json::json!({ "filter": filter }),
+        Err(e) => serde_json::json!({ "error": e }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_level_replaces_target_directive() {
+        let filter = "rustwebui_app=info,tungstenite=warn";
+        assert_eq!(
+            with_level(filter, Some("rustwebui_app"), "debug"),
+            "tungstenite=warn,rustwebui_app=debug"
+        );
+        assert_eq!(
+            with_level(filter, Some("rusqlite"), "trace"),
+            "rustwebui_app=info,tungstenite=warn,rusqlite=trace"
+        );
+    }
+
+    #[test]
+    fn test_with_level_sets_default_level() {
+        assert_eq!(with_level("warn,rustwebui_app=info", None, "error"), "rustwebui_app=info,error");
+    }
+
+    #[test]
+    fn test_rejects_unknown_level() {
+        assert!(set_level(None, "loud").is_err());
+        assert!(set_level(Some("a,b"), "info").is_err());
+    }
+}
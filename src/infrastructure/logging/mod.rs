@@ -3,6 +3,10 @@
 #![allow(dead_code)]
 
 pub mod error_logger;
+pub mod log_file;
+pub mod log_filter;
+pub mod request_context;
+pub mod ring_buffer;
 
 use tracing::info;
 use tracing_subscriber::{fmt, EnvFilter, prelude::__tracing_subscriber_SubscriberExt};
@@ -0,0 +1,33 @@
+//! The id of the HTTP request the current thread is handling
+//!
+//! Set by the HTTP layer for the duration of a request, so records of the
+//! log ring buffer, error log entries and crash reports written meanwhile can
+//! name the request, as its `X-Request-Id` and access log line do.
+
+use std::cell::RefCell;
+
+thread_local! {
+    static CURRENT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// While alive, `current()` on this thread is the request's id
+pub struct RequestGuard {
+    previous: Option<String>,
+}
+
+pub fn enter(request_id: &str) -> RequestGuard {
+    let previous = CURRENT.with(|current| current.borrow_mut().replace(request_id.to_string()));
+    RequestGuard { previous }
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+pub fn current() -> Option<String> {
+    // `try_with`: also called from the panic hook, possibly while the thread is torn down
+    CURRENT.try_with(|current| current.borrow().clone()).ok().flatten()
+}
@@ -0,0 +1,326 @@
+//! Last log records in a memory-mapped file, for post-mortem debugging
+//!
+//! With `[logging] ring_buffer_file` set, every log record at or above
+//! `ring_buffer_level` is written into a fixed number of slots of that file,
+//! the oldest overwritten first, whatever the console filter currently lets
+//! through. The file is mapped into memory, so the records survive the process
+//! being killed or aborting outright: the OS writes the pages back. A panic
+//! copies them into the crash report, `ringbuffer.dump` reads them from the
+//! running app, and `read_file` from a file left behind.
+//!
+//! Layout: a 32 byte header (`MAGIC`, slot size and slot count as little
+//! endian u32), then the slots. A slot is its sequence number (u64, 0 while
+//! empty or being written), the length of its JSON (u16) and the JSON.
+
+use chrono::{DateTime, Utc};
+use memmap2::MmapMut;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use crate::core::domain::clock;
+
+const MAGIC: &[u8; 8] = b"LOGRING1";
+const HEADER_SIZE: usize = 32;
+pub const SLOT_SIZE: usize = 512;
+/// Sequence number and length
+const SLOT_HEADER_SIZE: usize = 10;
+const MAX_RECORD_BYTES: usize = SLOT_SIZE - SLOT_HEADER_SIZE;
+
+/// One log record as kept in the ring
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub seq: u64,
+    pub timestamp: DateTime<Utc>,
+    pub level: String,
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread: Option<String>,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+    /// The message or fields were cut to fit a slot
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+impl LogRecord {
+    /// The record as slot content, cut down until it fits
+    fn encode(&mut self) -> Vec<u8> {
+        let mut bytes = serde_json::to_vec(self).unwrap_or_default();
+        if bytes.len() > MAX_RECORD_BYTES {
+            self.truncated = true;
+            self.fields.clear();
+            bytes = serde_json::to_vec(self).unwrap_or_default();
+        }
+        while bytes.len() > MAX_RECORD_BYTES && !self.message.is_empty() {
+            let excess = bytes.len() - MAX_RECORD_BYTES;
+            let mut keep = self.message.len().saturating_sub(excess.max(8));
+            while !self.message.is_char_boundary(keep) {
+                keep -= 1;
+            }
+            self.message.truncate(keep);
+            bytes = serde_json::to_vec(self).unwrap_or_default();
+        }
+        bytes
+    }
+}
+
+struct Ring {
+    map: MmapMut,
+    slots: usize,
+    next_seq: u64,
+}
+
+pub struct RingBuffer {
+    ring: Mutex<Ring>,
+}
+
+impl RingBuffer {
+    /// Map `path` with room for `slots` records; records of an earlier run
+    /// with the same geometry are kept and numbered on from
+    pub fn open(path: &Path, slots: usize) -> io::Result<Self> {
+        let slots = slots.max(1);
+        let len = (HEADER_SIZE + slots * SLOT_SIZE) as u64;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        if file.metadata()?.len() != len {
+            file.set_len(0)?;
+            file.set_len(len)?;
+        }
+        // SAFETY: the file is this app's own; if something else writes to it,
+        // reading only sees garbled records, which `parse` skips
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        if geometry(&map) != Some((SLOT_SIZE, slots)) {
+            map.fill(0);
+            map[..8].copy_from_slice(MAGIC);
+            map[8..12].copy_from_slice(&(SLOT_SIZE as u32).to_le_bytes());
+            map[12..16].copy_from_slice(&(slots as u32).to_le_bytes());
+        }
+        let next_seq = parse(&map).last().map_or(1, |record| record.seq + 1);
+        Ok(Self { ring: Mutex::new(Ring { map, slots, next_seq }) })
+    }
+
+    pub fn push(&self, mut record: LogRecord) {
+        let mut ring = self.ring.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let seq = ring.next_seq;
+        ring.next_seq += 1;
+        record.seq = seq;
+        let bytes = record.encode();
+        let start = HEADER_SIZE + (seq as usize % ring.slots) * SLOT_SIZE;
+        let slot = &mut ring.map[start..start + SLOT_SIZE];
+        // Zero the sequence first, so a slot caught half written reads as empty
+        slot[..8].copy_from_slice(&0u64.to_le_bytes());
+        slot[8..10].copy_from_slice(&(bytes.len() as u16).to_le_bytes());
+        slot[SLOT_HEADER_SIZE..SLOT_HEADER_SIZE + bytes.len()].copy_from_slice(&bytes);
+        slot[..8].copy_from_slice(&seq.to_le_bytes());
+    }
+
+    /// Records in the ring, oldest first
+    ///
+    /// Never blocks: returns nothing if a writer holds the ring, so it is
+    /// safe to call from a panic hook.
+    pub fn records(&self) -> Vec<LogRecord> {
+        match self.ring.try_lock() {
+            Ok(ring) => parse(&ring.map),
+            Err(std::sync::TryLockError::Poisoned(ring)) => parse(&ring.into_inner().map),
+            Err(std::sync::TryLockError::WouldBlock) => Vec::new(),
+        }
+    }
+}
+
+/// Slot size and count from a ring's header, if it has one
+fn geometry(bytes: &[u8]) -> Option<(usize, usize)> {
+    if bytes.len() < HEADER_SIZE || &bytes[..8] != MAGIC {
+        return None;
+    }
+    let slot_size = u32::from_le_bytes(bytes[8..12].try_into().ok()?) as usize;
+    let slots = u32::from_le_bytes(bytes[12..16].try_into().ok()?) as usize;
+    Some((slot_size, slots))
+}
+
+/// Every readable record of a ring, oldest first
+pub fn parse(bytes: &[u8]) -> Vec<LogRecord> {
+    let Some((slot_size, slots)) = geometry(bytes) else {
+        return Vec::new();
+    };
+    if slot_size <= SLOT_HEADER_SIZE {
+        return Vec::new();
+    }
+    let mut records: Vec<LogRecord> = bytes[HEADER_SIZE..]
+        .chunks_exact(slot_size)
+        .take(slots)
+        .filter_map(|slot| {
+            let seq = u64::from_le_bytes(slot[..8].try_into().ok()?);
+            let len = u16::from_le_bytes(slot[8..10].try_into().ok()?) as usize;
+            let json = slot.get(SLOT_HEADER_SIZE..SLOT_HEADER_SIZE + len)?;
+            let record: LogRecord = serde_json::from_slice(json).ok()?;
+            (seq != 0 && record.seq == seq).then_some(record)
+        })
+        .collect();
+    records.sort_by_key(|record| record.seq);
+    records
+}
+
+/// Records of a ring file, e.g. one left behind by a process that died
+pub fn read_file(path: &Path) -> io::Result<Vec<LogRecord>> {
+    Ok(parse(&fs::read(path)?))
+}
+
+static RING: OnceLock<RingBuffer> = OnceLock::new();
+
+/// The ring of this process, once logging has opened it
+pub fn global() -> Option<&'static RingBuffer> {
+    RING.get()
+}
+
+/// Open the ring at `path` and return the layer that fills it
+pub fn layer(path: &Path, slots: usize) -> io::Result<RingLayer> {
+    let ring = RingBuffer::open(path, slots)?;
+    let _ = RING.set(ring);
+    Ok(RingLayer)
+}
+
+/// Writes every event it sees into the global ring
+pub struct RingLayer;
+
+impl<S: Subscriber> Layer<S> for RingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let Some(ring) = RING.get() else {
+            return;
+        };
+        let metadata = event.metadata();
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        if let Some(request_id) = super::request_context::current() {
+            visitor.fields.entry("request_id").or_insert(Value::String(request_id));
+        }
+        ring.push(LogRecord {
+            seq: 0,
+            timestamp: clock::now(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            thread: std::thread::current().name().map(String::from),
+            message: visitor.message,
+            fields: visitor.fields,
+            truncated: false,
+        });
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: Map<String, Value>,
+}
+
+impl FieldVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        if field.name() == "message" {
+            self.message = value.as_str().map_or_else(|| value.to_string(), String::from);
+        } else {
+            self.fields.insert(field.name().to_string(), value);
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.insert(field, Value::String(format!("{:?}", value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::String(value.to_string()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+}
+
+/// `ringbuffer.dump`: the records of the running app, the last `limit` if given
+pub fn handle_command(payload: &Value) -> Value {
+    let Some(ring) = global() else {
+        return serde_json::json!({ "error": "The log ring buffer is off; set [logging] ring_buffer_file" });
+    };
+    let mut records = ring.records();
+    if let Some(limit) = payload.get("limit").and_then(Value::as_u64) {
+        records.drain(..records.len().saturating_sub(limit as usize));
+    }
+    serde_json::json!({ "records": records })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    fn record(message: &str) -> LogRecord {
+        LogRecord {
+            seq: 0,
+            timestamp: Utc::now(),
+            level: "INFO".to_string(),
+            target: "test".to_string(),
+            thread: None,
+            message: message.to_string(),
+            fields: Map::new(),
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn test_ring_keeps_the_newest_records_across_reopening() {
+        let dir = TempDir::new("ring");
+        let path = dir.join("ring.bin");
+        let ring = RingBuffer::open(&path, 4).unwrap();
+        for index in 1..=6 {
+            ring.push(record(&format!("record {}", index)));
+        }
+        let messages = |records: Vec<LogRecord>| records.into_iter().map(|record| record.message).collect::<Vec<_>>();
+        assert_eq!(messages(ring.records()), ["record 3", "record 4", "record 5", "record 6"]);
+        drop(ring);
+
+        // As a later run, or a post-mortem reader, finds the file
+        assert_eq!(messages(read_file(&path).unwrap()), ["record 3", "record 4", "record 5", "record 6"]);
+        let ring = RingBuffer::open(&path, 4).unwrap();
+        ring.push(record("record 7"));
+        assert_eq!(ring.records().last().map(|record| record.seq), Some(7));
+        drop(ring);
+
+        // Another geometry starts over
+        assert!(RingBuffer::open(&path, 8).unwrap().records().is_empty());
+    }
+
+    #[test]
+    fn test_long_records_are_cut_to_fit() {
+        let mut long = record(&"é".repeat(400));
+        long.fields.insert("payload".to_string(), Value::String("x".repeat(300)));
+        let bytes = long.encode();
+        assert!(bytes.len() <= MAX_RECORD_BYTES);
+        let decoded: LogRecord = serde_json::from_slice(&bytes).unwrap();
+        assert!(decoded.truncated && decoded.fields.is_empty());
+        assert!(decoded.message.starts_with("éé"));
+
+        let mut short = record("short");
+        assert!(!serde_json::from_slice::<LogRecord>(&short.encode()).unwrap().truncated);
+    }
+}
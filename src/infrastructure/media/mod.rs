@@ -0,0 +1,165 @@
+//! UI feedback sounds (`media` feature)
+//!
+//! `sound.play { name, volume? }` plays one of the sounds built into the
+//! app (`click`, `success`, `error` and `notification`) and answers without
+//! waiting for it to end; sounds played together overlap. It plays at the
+//! stored `volume` times the `volume` given, and not at all while `muted`.
+//! Both are kept in the `sound` settings namespace, changed with
+//! `sound.set_volume { volume }` and `sound.set_muted { muted }`, and
+//! returned by `sound.list` with the names of the sounds.
+//!
+//! rodio's output stream has to stay on the thread that opened it, so the
+//! first sound starts a thread that opens the default output device and
+//! plays every sound. Without a device, sounds are `ServiceUnavailable`
+//! until the next start.
+
+use rodio::{Decoder, OutputStream, Source};
+use serde_json::{Map, Value};
+use std::io::Cursor;
+use std::sync::mpsc::{self, Sender};
+use std::sync::OnceLock;
+use tracing::{info, warn};
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+use crate::infrastructure::commands::command_result;
+use crate::infrastructure::settings;
+
+/// Settings namespace of the sound preferences
+pub const NAMESPACE: &str = "sound";
+/// From 0 to 1, `DEFAULT_VOLUME` until set
+const VOLUME: &str = "volume";
+const MUTED: &str = "muted";
+const DEFAULT_VOLUME: f64 = 0.8;
+
+/// The built-in sounds, short WAV files
+const SOUNDS: &[(&str, &[u8])] = &[
+    ("click", include_bytes!("sounds/click.wav")),
+    ("success", include_bytes!("sounds/success.wav")),
+    ("error", include_bytes!("sounds/error.wav")),
+    ("notification", include_bytes!("sounds/notification.wav")),
+];
+
+fn sound(name: &str) -> Option<&'static [u8]> {
+    SOUNDS.iter().find(|(sound, _)| *sound == name).map(|(_, wav)| *wav)
+}
+
+struct Play {
+    wav: &'static [u8],
+    volume: f32,
+}
+
+/// Open the default output device on a thread of its own and play what it is sent
+fn spawn_player() -> Result<Sender<Play>, String> {
+    let (sender, plays) = mpsc::channel::<Play>();
+    let (started, opened) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("media".to_string())
+        .spawn(move || {
+            let (_stream, output) = match OutputStream::try_default() {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = started.send(Err(format!("No audio output: {}", e)));
+                    return;
+                }
+            };
+            let _ = started.send(Ok(()));
+            for play in plays {
+                let played = Decoder::new(Cursor::new(play.wav))
+                    .map_err(|e| e.to_string())
+                    .and_then(|source| output.play_raw(source.convert_samples::<f32>().amplify(play.volume)).map_err(|e| e.to_string()));
+                if let Err(e) = played {
+                    warn!("Could not play a sound: {}", e);
+                }
+            }
+        })
+        .map_err(|e| format!("Could not start the audio thread: {}", e))?;
+    opened.recv().map_err(|_| "The audio thread stopped".to_string())??;
+    info!("Opened the default audio output");
+    Ok(sender)
+}
+
+/// The player, started on first use
+fn player() -> AppResult<&'static Sender<Play>> {
+    static PLAYER: OnceLock<Result<Sender<Play>, String>> = OnceLock::new();
+    PLAYER
+        .get_or_init(spawn_player)
+        .as_ref()
+        .map_err(|e| AppError::new(ErrorCode::ServiceUnavailable, e.clone()))
+}
+
+/// Stored volume and mute flag
+fn levels(values: &Map<String, Value>) -> (f64, bool) {
+    let volume = values.get(VOLUME).and_then(Value::as_f64).map_or(DEFAULT_VOLUME, |volume| volume.clamp(0.0, 1.0));
+    (volume, values.get(MUTED).and_then(Value::as_bool).unwrap_or(false))
+}
+
+async fn stored() -> AppResult<(f64, bool)> {
+    Ok(levels(&settings::values(&settings::repository()?, NAMESPACE, None).await?))
+}
+
+async fn status() -> AppResult<Value> {
+    let (volume, muted) = stored().await?;
+    Ok(serde_json::json!({
+        "success": true,
+        "sounds": SOUNDS.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+        "volume": volume,
+        "muted": muted,
+    }))
+}
+
+async fn play(payload: &Value) -> AppResult<Value> {
+    let name = payload.get("name").and_then(Value::as_str).unwrap_or_default();
+    let wav = sound(name).ok_or_else(|| {
+        AppError::new(ErrorCode::EntityNotFound, format!("No sound named '{}'", name)).with_context("name", name)
+    })?;
+    let (stored_volume, muted) = stored().await?;
+    let volume = stored_volume * payload.get("volume").and_then(Value::as_f64).unwrap_or(1.0).clamp(0.0, 1.0);
+    if muted || volume == 0.0 {
+        return Ok(serde_json::json!({ "success": true, "played": false }));
+    }
+    let player = tokio::task::spawn_blocking(player)
+        .await
+        .map_err(|e| AppError::new(ErrorCode::CommandFailed, e.to_string()))??;
+    player
+        .send(Play { wav, volume: volume as f32 })
+        .map_err(|_| AppError::new(ErrorCode::ServiceUnavailable, "The audio thread stopped"))?;
+    Ok(serde_json::json!({ "success": true, "played": true }))
+}
+
+async fn run(name: &str, payload: &Value) -> AppResult<Value> {
+    match name {
+        "sound.play" => return play(payload).await,
+        "sound.list" => {}
+        "sound.set_volume" => {
+            let volume = payload.get("volume").and_then(Value::as_f64).unwrap_or(DEFAULT_VOLUME).clamp(0.0, 1.0);
+            settings::set(&settings::repository()?, NAMESPACE, VOLUME, serde_json::json!(volume), None).await?;
+        }
+        "sound.set_muted" => {
+            let muted = payload.get("muted").and_then(Value::as_bool).unwrap_or(false);
+            settings::set(&settings::repository()?, NAMESPACE, MUTED, Value::Bool(muted), None).await?;
+        }
+        other => return Err(AppError::new(ErrorCode::CommandFailed, format!("Unknown command: {}", other))),
+    }
+    status().await
+}
+
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    command_result(run(name, payload).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sounds_are_wav_and_levels_have_defaults() {
+        for (name, wav) in SOUNDS {
+            assert_eq!((&wav[..4], &wav[8..12]), (&b"RIFF"[..], &b"WAVE"[..]), "{} is not a WAV file", name);
+        }
+        assert!(sound("click").is_some());
+        assert!(sound("beep").is_none());
+
+        assert_eq!(levels(&Map::new()), (DEFAULT_VOLUME, false));
+        let stored = serde_json::json!({ "volume": 3, "muted": true });
+        assert_eq!(levels(stored.as_object().unwrap()), (1.0, true));
+    }
+}
@@ -0,0 +1,269 @@
+//! Live system metrics - background sampler publishing `system.metrics` events
+//!
+//! The sampler only collects while at least one WebSocket client is
+//! connected; otherwise it stays paused until one connects. The WebSocket
+//! handler reports the count through `set_clients` as connections open and
+//! close. Listeners on the event bus do not count, since the fan-out and state
+//! sync always listen.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, Pid, ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::model::core::AppConfig;
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ProcessMetrics {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub virtual_memory_bytes: u64,
+    pub run_time_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct MetricsSample {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub cpu_percent: f32,
+    pub per_core_percent: Vec<f32>,
+    pub total_memory_bytes: u64,
+    pub used_memory_bytes: u64,
+    pub total_swap_bytes: u64,
+    pub used_swap_bytes: u64,
+    pub app_process: Option<ProcessMetrics>,
+    pub top_processes: Vec<ProcessMetrics>,
+}
+
+fn process_metrics(pid: Pid, process: &sysinfo::Process) -> ProcessMetrics {
+    ProcessMetrics {
+        pid: pid.as_u32(),
+        name: process.name().to_string_lossy().into_owned(),
+        cpu_percent: process.cpu_usage(),
+        memory_bytes: process.memory(),
+        virtual_memory_bytes: process.virtual_memory(),
+        run_time_secs: process.run_time(),
+    }
+}
+
+/// Keep the `limit` processes using the most memory, largest first
+fn top_by_memory(mut processes: Vec<ProcessMetrics>, limit: usize) -> Vec<ProcessMetrics> {
+    processes.sort_by_key(|p| std::cmp::Reverse(p.memory_bytes));
+    processes.truncate(limit);
+    processes
+}
+
+/// Refresh `system` and build a snapshot; CPU figures need two refreshes to be meaningful
+fn collect_sample(system: &mut System, top_processes: usize) -> MetricsSample {
+    system.refresh_cpu_usage();
+    system.refresh_memory();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::new().with_cpu().with_memory(),
+    );
+
+    let app_pid = sysinfo::get_current_pid().ok();
+    let processes: Vec<ProcessMetrics> = system
+        .processes()
+        .iter()
+        .map(|(pid, process)| process_metrics(*pid, process))
+        .collect();
+
+    MetricsSample {
+        timestamp: chrono::Utc::now(),
+        cpu_percent: system.global_cpu_usage(),
+        per_core_percent: system.cpus().iter().map(|cpu| cpu.cpu_usage()).collect(),
+        total_memory_bytes: system.total_memory(),
+        used_memory_bytes: system.used_memory(),
+        total_swap_bytes: system.total_swap(),
+        used_swap_bytes: system.used_swap(),
+        app_process: app_pid.and_then(|pid| system.process(pid).map(|p| process_metrics(pid, p))),
+        top_processes: top_by_memory(processes, top_processes),
+    }
+}
+
+/// Background task that periodically publishes metrics on the event bus
+pub struct MetricsSampler {
+    interval_secs: AtomicU64,
+    paused: AtomicBool,
+    samples_emitted: AtomicU64,
+    /// Connected WebSocket clients, as last reported by `set_clients`
+    clients: AtomicUsize,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl MetricsSampler {
+    fn new() -> Self {
+        Self {
+            interval_secs: AtomicU64::new(AppConfig::global().get_metrics_sample_interval_secs()),
+            paused: AtomicBool::new(false),
+            samples_emitted: AtomicU64::new(0),
+            clients: AtomicUsize::new(0),
+            task: Mutex::new(None),
+        }
+    }
+
+    /// Record how many WebSocket clients are connected
+    pub fn set_clients(&self, count: usize) {
+        self.clients.store(count, Ordering::Relaxed);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.task
+            .lock()
+            .map(|task| task.as_ref().is_some_and(|handle| !handle.is_finished()))
+            .unwrap_or(false)
+    }
+
+    /// Start sampling (or change the interval if already running); returns false if it was already running
+    pub fn start(&'static self, interval_secs: Option<u64>) -> bool {
+        if let Some(secs) = interval_secs {
+            self.interval_secs.store(secs.max(1), Ordering::Relaxed);
+        }
+
+        let mut task = match self.task.lock() {
+            Ok(task) => task,
+            Err(_) => return false,
+        };
+        if task.as_ref().is_some_and(|handle| !handle.is_finished()) {
+            return false;
+        }
+
+        info!("Starting metrics sampler ({}s interval)", self.interval_secs.load(Ordering::Relaxed));
+        *task = Some(tokio::spawn(self.run()));
+        true
+    }
+
+    /// Stop sampling; returns false if it was not running
+    pub fn stop(&self) -> bool {
+        let handle = self.task.lock().ok().and_then(|mut task| task.take());
+        match handle {
+            Some(handle) => {
+                handle.abort();
+                self.paused.store(false, Ordering::Relaxed);
+                info!("Metrics sampler stopped");
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn status(&self) -> Value {
+        serde_json::json!({
+            "running": self.is_running(),
+            "paused": self.paused.load(Ordering::Relaxed),
+            "interval_secs": self.interval_secs.load(Ordering::Relaxed),
+            "samples_emitted": self.samples_emitted.load(Ordering::Relaxed),
+            "clients": self.clients.load(Ordering::Relaxed)
+        })
+    }
+
+    async fn run(&'static self) {
+        let bus = EventBus::global();
+        let mut system = System::new_with_specifics(
+            RefreshKind::new()
+                .with_cpu(CpuRefreshKind::new().with_cpu_usage())
+                .with_memory(MemoryRefreshKind::everything()),
+        );
+
+        loop {
+            let interval = Duration::from_secs(self.interval_secs.load(Ordering::Relaxed));
+            tokio::time::sleep(interval).await;
+
+            if self.clients.load(Ordering::Relaxed) == 0 {
+                if !self.paused.swap(true, Ordering::Relaxed) {
+                    debug!("No WebSocket clients, pausing metrics sampler");
+                }
+                continue;
+            }
+            if self.paused.swap(false, Ordering::Relaxed) {
+                debug!("WebSocket client connected, resuming metrics sampler");
+            }
+
+            let top_processes = AppConfig::global().get_metrics_top_processes();
+            let sample = collect_sample(&mut system, top_processes);
+            let payload = match serde_json::to_value(&sample) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    error!("Failed to serialize metrics sample: {}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = bus.emit_simple(&AppEventType::SystemMetrics.to_string(), payload).await {
+                error!("Failed to emit system metrics: {}", e);
+            } else {
+                self.samples_emitted.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+pub fn metrics_sampler() -> &'static MetricsSampler {
+    static SAMPLER: OnceLock<MetricsSampler> = OnceLock::new();
+    SAMPLER.get_or_init(MetricsSampler::new)
+}
+
+/// Handle `metrics.*` commands from the frontend
+pub fn handle_command(name: &str, payload: &Value) -> Value {
+    let sampler = metrics_sampler();
+
+    match name {
+        "metrics.start" => {
+            let interval = payload.get("interval_secs").and_then(|v| v.as_u64());
+            let started = sampler.start(interval);
+            serde_json::json!({ "success": true, "started": started, "status": sampler.status() })
+        }
+        "metrics.stop" => {
+            let stopped = sampler.stop();
+            serde_json::json!({ "success": true, "stopped": stopped, "status": sampler.status() })
+        }
+        "metrics.status" => serde_json::json!({ "success": true, "status": sampler.status() }),
+        _ => serde_json::json!({
+            "success": false,
+            "error": format!("Unknown metrics command: {}", name)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, memory_bytes: u64) -> ProcessMetrics {
+        ProcessMetrics {
+            pid,
+            name: format!("proc-{}", pid),
+            cpu_percent: 0.0,
+            memory_bytes,
+            virtual_memory_bytes: 0,
+            run_time_secs: 0,
+        }
+    }
+
+    #[test]
+    fn test_top_by_memory() {
+        let top = top_by_memory(vec![process(1, 10), process(2, 30), process(3, 20)], 2);
+        let pids: Vec<u32> = top.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_collect_sample_includes_app_process() {
+        let mut system = System::new();
+        let sample = collect_sample(&mut system, 3);
+        assert!(sample.total_memory_bytes > 0);
+        assert!(sample.top_processes.len() <= 3);
+        assert_eq!(
+            sample.app_process.map(|p| p.pid),
+            sysinfo::get_current_pid().ok().map(|pid| pid.as_u32())
+        );
+    }
+}
@@ -1,8 +1,62 @@
+pub mod analytics;
+pub mod auth;
+pub mod bench;
+#[cfg(feature = "broker")]
+pub mod broker;
+pub mod cache;
+#[cfg(feature = "capture")]
+pub mod capture;
+pub mod chaos;
+pub mod clipboard;
+#[cfg(feature = "collab")]
+pub mod collab;
+pub mod command_metrics;
+pub mod commands;
+pub mod counters;
+pub mod crash_reporter;
 pub mod database;
+pub mod dialog;
+#[cfg(feature = "discovery")]
+pub mod discovery;
 pub mod event_bus;
+pub mod file_transfer;
+pub mod flags;
+pub mod fs_watcher;
+pub mod history;
+#[cfg(feature = "hotkeys")]
+pub mod hotkeys;
+pub mod i18n;
+pub mod image_organizer;
+pub mod jwt;
 pub mod logging;
+#[cfg(feature = "media")]
+pub mod media;
+pub mod metrics;
+pub mod os_integration;
+pub mod outbox;
+pub mod pdf;
+pub mod presence;
+pub mod recorder;
+pub mod runtime;
+pub mod scheduler;
+pub mod schema;
+pub mod secrets;
 pub mod serialization;
-pub mod websocket;
+pub mod server;
+pub mod sessions;
+pub mod settings;
+pub mod shell;
+pub mod startup;
+pub mod state_sync;
+pub mod system_info;
+pub mod tasks;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+#[cfg(feature = "updater")]
+pub mod updater;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
+pub mod workers;
 
 // Re-export EventBus for convenience
 #[allow(unused_imports)]
@@ -0,0 +1,319 @@
+//! OS integration settings: launching the app when the user logs in
+//!
+//! `os.integration` returns what a settings panel shows: the `platform` and
+//! `launch_at_login` with whether the login entry exists (`enabled`), what
+//! the user chose (`preference`, `null` until they did), whether this
+//! platform is `supported` and where the entry lives (`location`).
+//! `os.set_launch_at_login { enabled }` adds or removes the entry and stores
+//! the choice as `launch_at_login` in the `os` settings namespace.
+//!
+//! The entry is `$XDG_CONFIG_HOME/autostart/<name>.desktop` on Linux and
+//! other freedesktop systems, a LaunchAgent in `~/Library/LaunchAgents` on
+//! macOS and a value under `HKCU\...\CurrentVersion\Run` on Windows. The
+//! stored choice wins over the entry: at startup the entry is written again
+//! when it is on, so it follows the executable when that moves or is
+//! updated, and removed when it is off. Nothing is touched before the user
+//! chose.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::io;
+use std::path::PathBuf;
+use tracing::{info, warn};
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+use crate::infrastructure::commands::command_result;
+use crate::infrastructure::settings;
+
+/// Settings namespace of the OS integration preferences
+pub const NAMESPACE: &str = "os";
+/// `true` or `false` under this key of `NAMESPACE` once the user chose
+pub const LAUNCH_AT_LOGIN: &str = "launch_at_login";
+
+/// File name of the entry, and its name in the registry
+const ENTRY_NAME: &str = env!("CARGO_PKG_NAME");
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LaunchAtLogin {
+    /// Whether the login entry exists now
+    pub enabled: bool,
+    pub preference: Option<bool>,
+    pub supported: bool,
+    /// File or registry key of the entry
+    pub location: Option<String>,
+}
+
+/// The program started at login: the AppImage when running from one, else this executable
+fn executable() -> io::Result<PathBuf> {
+    match std::env::var_os("APPIMAGE") {
+        Some(appimage) if cfg!(target_os = "linux") => Ok(PathBuf::from(appimage)),
+        _ => std::env::current_exe(),
+    }
+}
+
+/// `path` as one argument of a desktop entry's `Exec` key
+#[cfg_attr(any(target_os = "macos", windows), allow(dead_code))]
+fn exec_argument(path: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in path.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    // String values unescape `\\` before the quoting rules apply, and `%` starts a field code
+    quoted.replace('\\', "\\\\").replace('%', "%%")
+}
+
+#[cfg_attr(any(target_os = "macos", windows), allow(dead_code))]
+fn desktop_entry(name: &str, program: &str) -> String {
+    format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec={}\nTerminal=false\nX-GNOME-Autostart-enabled=true\n",
+        name.replace(['\n', '\r'], " "),
+        exec_argument(program)
+    )
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn launch_agent(label: &str, program: &str) -> String {
+    let escape = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;");
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        escape(label),
+        escape(program)
+    )
+}
+
+/// The login entry as a file, on freedesktop systems and macOS
+#[cfg(unix)]
+mod platform {
+    use super::*;
+
+    pub const SUPPORTED: bool = true;
+
+    #[cfg(not(target_os = "macos"))]
+    fn dir() -> Option<PathBuf> {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config.join("autostart"))
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn location() -> Option<PathBuf> {
+        Some(dir()?.join(format!("{}.desktop", ENTRY_NAME)))
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn contents(program: &str) -> String {
+        desktop_entry(crate::model::core::AppConfig::global().get_app_name(), program)
+    }
+
+    #[cfg(target_os = "macos")]
+    fn dir() -> Option<PathBuf> {
+        Some(PathBuf::from(std::env::var_os("HOME")?).join("Library").join("LaunchAgents"))
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn location() -> Option<PathBuf> {
+        Some(dir()?.join(format!("{}.plist", ENTRY_NAME)))
+    }
+
+    #[cfg(target_os = "macos")]
+    fn contents(program: &str) -> String {
+        launch_agent(ENTRY_NAME, program)
+    }
+
+    fn entry() -> io::Result<PathBuf> {
+        location().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No home directory to keep the login entry in"))
+    }
+
+    pub fn is_enabled() -> io::Result<bool> {
+        Ok(entry()?.is_file())
+    }
+
+    pub fn enable() -> io::Result<()> {
+        let entry = entry()?;
+        if let Some(dir) = entry.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(entry, contents(&executable()?.to_string_lossy()))
+    }
+
+    pub fn disable() -> io::Result<()> {
+        match std::fs::remove_file(entry()?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The login entry as a `Run` value of the current user, written with `reg.exe`
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::os::windows::process::CommandExt;
+    use std::process::Command;
+
+    pub const SUPPORTED: bool = true;
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    /// Keeps reg.exe from flashing a console window
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    pub fn location() -> Option<PathBuf> {
+        Some(PathBuf::from(format!(r"{}\{}", RUN_KEY, ENTRY_NAME)))
+    }
+
+    fn reg(args: &[&str]) -> io::Result<bool> {
+        let output = Command::new("reg").args(args).creation_flags(CREATE_NO_WINDOW).output()?;
+        Ok(output.status.success())
+    }
+
+    pub fn is_enabled() -> io::Result<bool> {
+        reg(&["query", RUN_KEY, "/v", ENTRY_NAME])
+    }
+
+    pub fn enable() -> io::Result<()> {
+        let program = format!("\"{}\"", executable()?.display());
+        if !reg(&["add", RUN_KEY, "/v", ENTRY_NAME, "/t", "REG_SZ", "/d", &program, "/f"])? {
+            return Err(io::Error::other("reg.exe could not write the Run value"));
+        }
+        Ok(())
+    }
+
+    pub fn disable() -> io::Result<()> {
+        if is_enabled()? && !reg(&["delete", RUN_KEY, "/v", ENTRY_NAME, "/f"])? {
+            return Err(io::Error::other("reg.exe could not remove the Run value"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use super::*;
+
+    pub const SUPPORTED: bool = false;
+
+    pub fn location() -> Option<PathBuf> {
+        None
+    }
+
+    pub fn is_enabled() -> io::Result<bool> {
+        Ok(false)
+    }
+
+    pub fn enable() -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Launching at login is not supported on this platform"))
+    }
+
+    pub fn disable() -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Add or remove the login entry, on a blocking thread
+async fn apply(enabled: bool) -> AppResult<()> {
+    let applied = tokio::task::spawn_blocking(move || if enabled { platform::enable() } else { platform::disable() })
+        .await
+        .map_err(|e| AppError::new(ErrorCode::CommandFailed, e.to_string()))?;
+    applied.map_err(|e| {
+        let code = if e.kind() == io::ErrorKind::Unsupported { ErrorCode::ServiceUnavailable } else { ErrorCode::CommandFailed };
+        AppError::new(code, format!("Could not change the login entry: {}", e))
+    })
+}
+
+async fn preference() -> AppResult<Option<bool>> {
+    let setting = settings::get(&settings::repository()?, NAMESPACE, LAUNCH_AT_LOGIN, None).await?;
+    Ok(setting.and_then(|setting| setting.value.as_bool()))
+}
+
+async fn status() -> AppResult<LaunchAtLogin> {
+    let enabled = tokio::task::spawn_blocking(platform::is_enabled)
+        .await
+        .map_err(|e| AppError::new(ErrorCode::CommandFailed, e.to_string()))?
+        .unwrap_or_else(|e| {
+            warn!("Could not read the login entry: {}", e);
+            false
+        });
+    Ok(LaunchAtLogin {
+        enabled,
+        preference: preference().await?,
+        supported: platform::SUPPORTED,
+        location: platform::location().map(|location| location.display().to_string()),
+    })
+}
+
+/// Bring the login entry in line with the stored preference, if the user chose
+pub fn start() {
+    tokio::spawn(async {
+        let preference = match preference().await {
+            Ok(Some(preference)) => preference,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Could not read the launch-at-login preference: {}", e.message);
+                return;
+            }
+        };
+        match apply(preference).await {
+            Ok(()) => info!("Launch at login is {}", if preference { "on" } else { "off" }),
+            Err(e) => warn!("{}", e.message),
+        }
+    });
+}
+
+async fn run(name: &str, payload: &Value) -> AppResult<Value> {
+    match name {
+        "os.integration" => {}
+        "os.set_launch_at_login" => {
+            let enabled = payload.get("enabled").and_then(Value::as_bool).unwrap_or_default();
+            apply(enabled).await?;
+            settings::set(&settings::repository()?, NAMESPACE, LAUNCH_AT_LOGIN, Value::Bool(enabled), None).await?;
+        }
+        other => return Err(AppError::new(ErrorCode::CommandFailed, format!("Unknown command: {}", other))),
+    }
+    Ok(serde_json::json!({
+        "success": true,
+        "platform": std::env::consts::OS,
+        "launch_at_login": status().await?,
+    }))
+}
+
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    command_result(run(name, payload).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_quote_the_program() {
+        assert_eq!(exec_argument("/opt/My App/app"), r#""/opt/My App/app""#);
+        assert_eq!(exec_argument(r#"/tmp/a"$b\c%d"#), r#""/tmp/a\\"\\$b\\\\c%%d""#);
+
+        let entry = desktop_entry("Demo\nApp", "/usr/bin/demo");
+        assert!(entry.starts_with("[Desktop Entry]\n"));
+        assert!(entry.contains("\nName=Demo App\n"));
+        assert!(entry.contains("\nExec=\"/usr/bin/demo\"\n"));
+
+        let agent = launch_agent("demo", "/Applications/R&D <1>.app/Contents/MacOS/demo");
+        assert!(agent.contains("<string>/Applications/R&amp;D &lt;1&gt;.app/Contents/MacOS/demo</string>"));
+        assert!(agent.contains("<key>RunAtLoad</key>\n    <true/>"));
+    }
+}
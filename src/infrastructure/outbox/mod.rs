@@ -0,0 +1,321 @@
+//! Reliable publication of database events, `[outbox]`
+//!
+//! Row changes caught by the update hook of the database (see
+//! `database::changes`) are written to the `outbox` table as `data.changed`
+//! events by the relay, woken by their commit; other events can be queued in
+//! the transaction of their change. The relay publishes pending rows to the
+//! EventBus and to every other `OutboxSink` (the `webhook_url` with `--features outbox-webhook`, or a
+//! broker registered with `register_sink`) and then marks them published.
+//! Delivery is at least once: a row that failed to publish, or whose process
+//! stopped before marking it, is published again under the same id. That id is
+//! the event id on the bus and the webhook's `Idempotency-Key`, so consumers
+//! can drop duplicates; the bus sink itself skips ids it delivered recently.
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use tokio::sync::Notify;
+use tracing::{debug, error, info, warn};
+use crate::infrastructure::commands::command_message;
+use crate::infrastructure::database::database;
+use crate::infrastructure::event_bus::{Event, EventBus};
+use crate::model::core::{AppConfig, Database, OutboxMessage};
+
+/// Event ids the bus sink remembers to skip redeliveries
+const RECENT_IDS: usize = 1024;
+
+static WAKE: Notify = Notify::const_new();
+
+/// Tell the relay new events were committed
+pub fn wake() {
+    WAKE.notify_one();
+}
+
+/// Somewhere outbox events are delivered to
+#[async_trait::async_trait]
+pub trait OutboxSink: Send + Sync {
+    fn name(&self) -> &str;
+    async fn publish(&self, message: &OutboxMessage) -> Result<(), String>;
+}
+
+/// Sinks added by the application besides the built-in ones
+fn extra_sinks() -> &'static RwLock<Vec<Arc<dyn OutboxSink>>> {
+    static SINKS: OnceLock<RwLock<Vec<Arc<dyn OutboxSink>>>> = OnceLock::new();
+    SINKS.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+/// Deliver outbox events to `sink` as well, such as a message broker client
+#[allow(dead_code)]
+pub fn register_sink(sink: Arc<dyn OutboxSink>) {
+    info!("Outbox events are also published to {}", sink.name());
+    extra_sinks().write().unwrap().push(sink);
+}
+
+/// Publishes to the in-process EventBus, skipping ids it published recently
+#[derive(Default)]
+pub struct EventBusSink {
+    recent: Mutex<(VecDeque<String>, HashSet<String>)>,
+}
+
+impl EventBusSink {
+    /// Remember `id`; false if it was already published
+    fn first_delivery(&self, id: &str) -> bool {
+        let mut recent = self.recent.lock().unwrap();
+        let (order, ids) = &mut *recent;
+        if !ids.insert(id.to_string()) {
+            return false;
+        }
+        order.push_back(id.to_string());
+        if order.len() > RECENT_IDS {
+            if let Some(oldest) = order.pop_front() {
+                ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+#[async_trait::async_trait]
+impl OutboxSink for EventBusSink {
+    fn name(&self) -> &str {
+        "event bus"
+    }
+
+    async fn publish(&self, message: &OutboxMessage) -> Result<(), String> {
+        if !self.first_delivery(&message.id) {
+            debug!("Outbox event {} was already on the bus", message.id);
+            return Ok(());
+        }
+        let event = Event {
+            id: message.id.clone(),
+            name: message.event.clone(),
+            payload: Arc::new(message.payload.clone()),
+            source: "backend".to_string(),
+        };
+        EventBus::global().emit(event).await.map_err(|e| e.to_string())
+    }
+}
+
+/// POSTs each event as JSON with its id as `Idempotency-Key`
+#[cfg(feature = "outbox-webhook")]
+pub struct WebhookSink {
+    url: String,
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "outbox-webhook")]
+impl WebhookSink {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            agent: ureq::AgentBuilder::new().timeout(std::time::Duration::from_secs(10)).build(),
+        }
+    }
+}
+
+#[cfg(feature = "outbox-webhook")]
+#[async_trait::async_trait]
+impl OutboxSink for WebhookSink {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    async fn publish(&self, message: &OutboxMessage) -> Result<(), String> {
+        let request = self
+            .agent
+            .post(&self.url)
+            .set("Content-Type", "application/json")
+            .set("Idempotency-Key", &message.id);
+        let body = serde_json::to_string(message).map_err(|e| e.to_string())?;
+        tokio::task::spawn_blocking(move || request.send_string(&body).map(|_| ()).map_err(|e| e.to_string()))
+            .await
+            .map_err(|e| e.to_string())?
+    }
+}
+
+/// Delay before attempt `attempts + 1`: doubling from a second, at most about four minutes
+fn retry_delay(attempts: i64) -> Duration {
+    Duration::seconds(1 << attempts.clamp(0, 8))
+}
+
+pub struct Relay {
+    db: Arc<Database>,
+    sinks: Vec<Arc<dyn OutboxSink>>,
+    batch_size: usize,
+}
+
+impl Relay {
+    pub fn new(db: Arc<Database>, sinks: Vec<Arc<dyn OutboxSink>>, batch_size: usize) -> Self {
+        Self { db, sinks, batch_size }
+    }
+
+    /// The event bus, the configured webhook and registered sinks
+    fn from_config(db: Arc<Database>, config: &AppConfig) -> Self {
+        #[cfg(feature = "outbox-webhook")]
+        let webhook = config
+            .get_outbox_webhook_url()
+            .map(|url| Arc::new(WebhookSink::new(url)) as Arc<dyn OutboxSink>);
+        #[cfg(not(feature = "outbox-webhook"))]
+        let webhook = {
+            if config.get_outbox_webhook_url().is_some() {
+                error!("[outbox] webhook_url needs a build with the outbox-webhook feature");
+            }
+            None
+        };
+        let bus: Arc<dyn OutboxSink> = Arc::new(EventBusSink::default());
+        Self::new(db, std::iter::once(bus).chain(webhook).collect(), config.get_outbox_batch_size())
+    }
+
+    /// Publish `message` to every sink; the first failure stops it
+    async fn deliver(&self, message: &OutboxMessage) -> Result<(), String> {
+        let extra = extra_sinks().read().unwrap().clone();
+        for sink in self.sinks.iter().chain(extra.iter()) {
+            sink.publish(message)
+                .await
+                .map_err(|e| format!("{}: {}", sink.name(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Publish the events due by `now`; returns how many were published
+    pub async fn drain(&self, now: DateTime<Utc>) -> Result<usize, Box<dyn std::error::Error>> {
+        self.db.record_changes(&now)?;
+        let mut published = 0;
+        loop {
+            let batch = self.db.pending_outbox(&now, self.batch_size)?;
+            let full = batch.len() == self.batch_size;
+            for message in batch {
+                match self.deliver(&message).await {
+                    Ok(()) => {
+                        self.db.mark_outbox_published(&message.id, &Utc::now())?;
+                        published += 1;
+                    }
+                    Err(e) => {
+                        warn!("Failed to publish outbox event {} ({}): {}", message.id, message.event, e);
+                        self.db.mark_outbox_failed(&message.id, &e, &(now + retry_delay(message.attempts)))?;
+                    }
+                }
+            }
+            if !full {
+                return Ok(published);
+            }
+        }
+    }
+}
+
+/// Run the relay for this process
+pub fn start(db: Arc<Database>, config: &AppConfig) {
+    let relay = Relay::from_config(db, config);
+    let poll_interval = std::time::Duration::from_millis(config.get_outbox_poll_interval_ms());
+    let retention = Duration::hours(config.get_outbox_retention_hours() as i64);
+
+    tokio::spawn(async move {
+        let mut last_cleanup = None;
+        loop {
+            if let Err(e) = relay.drain(Utc::now()).await {
+                error!("Outbox relay failed: {}", e);
+            }
+            if last_cleanup.is_none_or(|at: DateTime<Utc>| Utc::now() - at > Duration::minutes(10)) {
+                match relay.db.delete_published_outbox(&(Utc::now() - retention)) {
+                    Ok(0) => {}
+                    Ok(deleted) => debug!("Deleted {} published outbox events", deleted),
+                    Err(e) => error!("Failed to clean up the outbox: {}", e),
+                }
+                last_cleanup = Some(Utc::now());
+            }
+            tokio::select! {
+                _ = WAKE.notified() => {}
+                _ = tokio::time::sleep(poll_interval) => {}
+            }
+        }
+    });
+    info!("Outbox relay started");
+}
+
+fn status() -> Result<Value, Box<dyn std::error::Error>> {
+    let db = database()?;
+    let (pending, failing, oldest) = db.outbox_backlog()?;
+    Ok(serde_json::json!({
+        "success": true,
+        "pending": pending,
+        "failing": failing,
+        "oldest": oldest,
+    }))
+}
+
+pub fn handle_command(name: &str, _payload: &Value) -> Value {
+    match name {
+        "outbox.status" => command_message(status()),
+        other => serde_json::json!({ "success": false, "error": format!("Unknown command: {}", other) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::core::UserChanges;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Collects what it is sent; fails while `failing` is set
+    struct TestSink {
+        received: Mutex<Vec<String>>,
+        failing: AtomicBool,
+    }
+
+    #[async_trait::async_trait]
+    impl OutboxSink for TestSink {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        async fn publish(&self, message: &OutboxMessage) -> Result<(), String> {
+            if self.failing.load(Ordering::SeqCst) {
+                return Err("unavailable".to_string());
+            }
+            self.received.lock().unwrap().push(message.payload["operation"].as_str().unwrap_or_default().to_string());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mutations_are_published_at_least_once() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        db.init().unwrap();
+        db.insert_sample_data().unwrap();
+        let sink = Arc::new(TestSink { received: Mutex::new(Vec::new()), failing: AtomicBool::new(true) });
+        let relay = Relay::new(db.clone(), vec![sink.clone()], 1);
+
+        // A refused update leaves no event behind
+        let changes = UserChanges { name: Some("Janet".to_string()), ..Default::default() };
+        assert!(db.update_user(2, &changes, 7).is_err());
+        db.update_user(2, &changes, 1).unwrap();
+
+        let now = Utc::now();
+        assert_eq!(relay.drain(now).await.unwrap(), 0);
+        let (pending, failing, oldest) = db.outbox_backlog().unwrap();
+        assert_eq!((pending, failing), (5, 5));
+        assert_eq!(oldest.unwrap().last_error.as_deref(), Some("test: unavailable"));
+
+        // Failed events wait for their retry, then go out in order
+        sink.failing.store(false, Ordering::SeqCst);
+        assert_eq!(relay.drain(now).await.unwrap(), 0);
+        assert_eq!(relay.drain(now + Duration::seconds(2)).await.unwrap(), 5);
+        assert_eq!(*sink.received.lock().unwrap(), vec!["insert", "insert", "insert", "insert", "update"]);
+        assert_eq!(db.outbox_backlog().unwrap().0, 0);
+        assert_eq!(db.delete_published_outbox(&(Utc::now() + Duration::seconds(1))).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_bus_sink_skips_redeliveries() {
+        let sink = EventBusSink::default();
+        assert!(sink.first_delivery("a"));
+        assert!(!sink.first_delivery("a"));
+        for i in 0..RECENT_IDS {
+            sink.first_delivery(&i.to_string());
+        }
+        assert!(sink.first_delivery("a"));
+        assert_eq!(retry_delay(0), Duration::seconds(1));
+        assert_eq!(retry_delay(20), Duration::seconds(256));
+    }
+}
@@ -0,0 +1,243 @@
+//! Printing HTML to PDF with a headless browser
+//!
+//! `document.export_pdf { html, base_url?, file_name?, title? }` asks for a
+//! destination in the native save dialog, answers at once with the
+//! `export_id` and `path` (or `cancelled: true`) and prints in the
+//! background. The HTML is a complete page, such as the frontend's snapshot
+//! of a route; `base_url` resolves its relative links and stylesheets. A
+//! Chromium-based browser prints it with `--headless --print-to-pdf` in a
+//! throwaway profile, so page size and margins come from the page's
+//! `@page` CSS. The PDF is written next to the page first and copied to
+//! the destination only once complete, so a failed print leaves an existing
+//! file alone. `document.exported` reports the outcome with the same
+//! `export_id`.
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tracing::{error, info, warn};
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+use crate::infrastructure::commands::command_result;
+use crate::infrastructure::dialog::{self, DialogFilter, DialogOptions};
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::model::core::AppConfig;
+
+/// Looked up on the PATH, in this order
+const BROWSERS: &[&str] = &[
+    "google-chrome",
+    "google-chrome-stable",
+    "chromium",
+    "chromium-browser",
+    "microsoft-edge",
+    "microsoft-edge-stable",
+    "brave-browser",
+    "chrome",
+    "msedge",
+];
+
+/// Where browsers are installed outside the PATH
+fn installed_browsers() -> Vec<PathBuf> {
+    if cfg!(target_os = "macos") {
+        ["Google Chrome", "Chromium", "Microsoft Edge", "Brave Browser"]
+            .iter()
+            .map(|app| PathBuf::from(format!("/Applications/{0}.app/Contents/MacOS/{0}", app)))
+            .collect()
+    } else if cfg!(windows) {
+        let roots = ["ProgramFiles", "ProgramFiles(x86)", "LocalAppData"].into_iter().filter_map(std::env::var_os);
+        roots
+            .flat_map(|root| {
+                [
+                    r"Google\Chrome\Application\chrome.exe",
+                    r"Microsoft\Edge\Application\msedge.exe",
+                    r"BraveSoftware\Brave-Browser\Application\brave.exe",
+                    r"Chromium\Application\chrome.exe",
+                ]
+                .map(|exe| Path::new(&root).join(exe))
+            })
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+fn on_path(name: &str) -> Option<PathBuf> {
+    let file = if cfg!(windows) { format!("{}.exe", name) } else { name.to_string() };
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(&file))
+        .find(|candidate| candidate.is_file())
+}
+
+/// `[pdf] browser`, a path or a name on the PATH, else the first browser found
+fn find_browser(config: &AppConfig) -> Option<PathBuf> {
+    match config.get_pdf_browser() {
+        Some(browser) if Path::new(browser).components().count() > 1 => Some(PathBuf::from(browser)),
+        Some(browser) => on_path(browser),
+        None => BROWSERS
+            .iter()
+            .find_map(|name| on_path(name))
+            .or_else(|| installed_browsers().into_iter().find(|path| path.is_file())),
+    }
+}
+
+/// `html` with a `<base href>` for `base_url`, so its relative URLs resolve there rather than in a temp directory
+fn with_base(html: &str, base_url: &str) -> String {
+    let base = format!("<base href=\"{}\">", base_url.replace('&', "&amp;").replace('"', "&quot;"));
+    let lower = html.to_ascii_lowercase();
+    let head = lower
+        .match_indices("<head")
+        .map(|(at, _)| at)
+        .find(|at| lower[at + 5..].starts_with(|c: char| c == '>' || c.is_ascii_whitespace()));
+    match head.and_then(|head| lower[head..].find('>').map(|end| head + end + 1)) {
+        Some(at) => format!("{}{}{}", &html[..at], base, &html[at..]),
+        None => format!("{}{}", base, html),
+    }
+}
+
+/// `file://` URL of an absolute path
+fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut url = String::from(if path.starts_with('/') { "file://" } else { "file:///" });
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => url.push(byte as char),
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+/// Print `html` into `output` with `browser`; the size of the PDF written
+async fn print(browser: &Path, html: &str, work: &Path, output: &Path, timeout: Duration) -> Result<u64, String> {
+    tokio::fs::create_dir_all(work).await.map_err(|e| format!("Could not create {}: {}", work.display(), e))?;
+    let page = work.join("page.html");
+    let printed = work.join("page.pdf");
+    tokio::fs::write(&page, html).await.map_err(|e| format!("Could not write the page: {}", e))?;
+
+    let child = Command::new(browser)
+        .args(["--headless", "--disable-gpu", "--no-first-run", "--no-default-browser-check"])
+        // The first is the flag of current versions, the second of older ones
+        .args(["--no-pdf-header-footer", "--print-to-pdf-no-header"])
+        .arg(format!("--user-data-dir={}", work.join("profile").display()))
+        .arg(format!("--print-to-pdf={}", printed.display()))
+        .arg(file_url(&page))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Could not start {}: {}", browser.display(), e))?;
+    let finished = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| format!("The browser did not finish printing within {} seconds", timeout.as_secs()))?
+        .map_err(|e| format!("The browser failed: {}", e))?;
+
+    match tokio::fs::metadata(&printed).await {
+        Ok(metadata) if metadata.len() > 0 => {}
+        _ => {
+            let stderr = String::from_utf8_lossy(&finished.stderr);
+            let last = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("no output");
+            return Err(format!("The browser wrote no PDF ({}): {}", finished.status, last));
+        }
+    }
+    tokio::fs::copy(&printed, output)
+        .await
+        .map_err(|e| format!("Could not save {}: {}", output.display(), e))
+}
+
+/// Print in the background and announce the outcome as `document.exported`
+fn export(browser: PathBuf, html: String, export_id: String, output: PathBuf) {
+    let timeout = Duration::from_secs(AppConfig::global().get_pdf_timeout_secs());
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let work = std::env::temp_dir().join(format!("rustwebui-pdf-{}", export_id));
+        let result = print(&browser, &html, &work, &output, timeout).await;
+        if let Err(e) = tokio::fs::remove_dir_all(&work).await {
+            warn!("Could not remove {}: {}", work.display(), e);
+        }
+        let (bytes, error) = match result {
+            Ok(bytes) => {
+                info!("Exported {} ({} bytes)", output.display(), bytes);
+                (Some(bytes), None)
+            }
+            Err(e) => {
+                warn!("PDF export {} failed: {}", export_id, e);
+                (None, Some(e))
+            }
+        };
+        let payload = serde_json::json!({
+            "export_id": export_id,
+            "path": output.to_string_lossy(),
+            "success": error.is_none(),
+            "bytes": bytes,
+            "error": error,
+            "duration_ms": started.elapsed().as_millis() as u64,
+        });
+        if let Err(e) = EventBus::global().emit_simple(&AppEventType::DocumentExported.to_string(), payload).await {
+            error!("Failed to emit document.exported for {}: {}", export_id, e);
+        }
+    });
+}
+
+async fn run(name: &str, payload: &Value) -> AppResult<Value> {
+    if name != "document.export_pdf" {
+        return Err(AppError::new(ErrorCode::CommandFailed, format!("Unknown command: {}", name)));
+    }
+    let text = |field: &str| payload.get(field).and_then(Value::as_str).filter(|value| !value.is_empty());
+    let html = match (text("html"), text("base_url")) {
+        (Some(html), Some(base_url)) => with_base(html, base_url),
+        (Some(html), None) => html.to_string(),
+        (None, _) => return Err(AppError::new(ErrorCode::ValidationFailed, "html is required")),
+    };
+    // Before the dialog, so the user does not pick a file for nothing
+    let browser = find_browser(AppConfig::global()).ok_or_else(|| {
+        AppError::new(
+            ErrorCode::ServiceUnavailable,
+            "No Chromium-based browser found to print with; install Chrome, Chromium or Edge, or set [pdf] browser",
+        )
+    })?;
+
+    let options = DialogOptions {
+        title: Some(text("title").unwrap_or("Export as PDF").to_string()),
+        file_name: Some(text("file_name").unwrap_or("document.pdf").to_string()),
+        filters: vec![DialogFilter { name: "PDF".to_string(), extensions: vec!["pdf".to_string()] }],
+        ..Default::default()
+    };
+    let Some(mut output) = dialog::save_file(&options).await else {
+        return Ok(serde_json::json!({ "success": true, "cancelled": true }));
+    };
+    if output.extension().is_none() {
+        output.set_extension("pdf");
+    }
+
+    let export_id = uuid::Uuid::new_v4().to_string();
+    export(browser, html, export_id.clone(), output.clone());
+    Ok(serde_json::json!({
+        "success": true,
+        "cancelled": false,
+        "export_id": export_id,
+        "path": output.to_string_lossy(),
+    }))
+}
+
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    command_result(run(name, payload).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages_resolve_against_the_base_url() {
+        assert_eq!(
+            with_base("<html><HEAD lang=\"en\"><title>x</title></HEAD></html>", "http://localhost:8080/?a=1&b=\"2\""),
+            "<html><HEAD lang=\"en\"><base href=\"http://localhost:8080/?a=1&amp;b=&quot;2&quot;\"><title>x</title></HEAD></html>"
+        );
+        assert_eq!(with_base("<p>Report</p>", "http://localhost/"), "<base href=\"http://localhost/\"><p>Report</p>");
+
+        assert_eq!(file_url(Path::new("/tmp/my export/page.html")), "file:///tmp/my%20export/page.html");
+        assert_eq!(file_url(Path::new(r"C:\Users\Zoë\page.html")), "file:///C:/Users/Zo%C3%AB/page.html");
+    }
+}
@@ -0,0 +1,140 @@
+//! Presence of the connected clients, e.g. the windows of a multi-window app
+//!
+//! A WebSocket connection becomes present by sending `presence.register
+//! { name?, kind?, metadata? }`. The backend keeps a profile of it: a
+//! generated `id`, the `name` and `kind` the client gave (`window` when left
+//! out), its free-form `metadata`, the `session` it said hello with, its
+//! `address` and when it `joined_at`. The profile is announced as
+//! `presence.joined`; registering again replaces it, keeps the id and join
+//! time, and announces it again. When the connection closes, the profile is
+//! dropped and sent one last time as `presence.left`. `presence.list` returns
+//! every present client, longest present first. Connections that never
+//! registered are not listed.
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use tracing::error;
+use crate::core::domain::clock;
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+
+/// `kind` of a client that did not give one
+pub const DEFAULT_KIND: &str = "window";
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct ClientProfile {
+    pub id: String,
+    pub name: Option<String>,
+    pub kind: String,
+    /// Whatever the client registered with, `{}` when nothing
+    pub metadata: Value,
+    /// Id of the session the connection said hello with
+    pub session: Option<String>,
+    pub address: Option<String>,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// The present clients
+#[derive(Default)]
+pub struct Presence {
+    /// In the order they joined
+    clients: Mutex<Vec<ClientProfile>>,
+}
+
+impl Presence {
+    pub fn global() -> &'static Self {
+        static PRESENCE: OnceLock<Presence> = OnceLock::new();
+        PRESENCE.get_or_init(Self::default)
+    }
+
+    fn clients(&self) -> MutexGuard<'_, Vec<ClientProfile>> {
+        self.clients.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Every present client, longest present first
+    pub fn list(&self) -> Vec<ClientProfile> {
+        self.clients().clone()
+    }
+
+    /// Keep the profile `payload` describes for the connection owning `registration`
+    fn register(&self, registration: &mut Registration, payload: &Value, session: Option<&str>, address: Option<&str>) -> ClientProfile {
+        let id = registration.0.get_or_insert_with(clock::new_id).clone();
+        let mut clients = self.clients();
+        let current = clients.iter().position(|client| client.id == id);
+        let profile = ClientProfile {
+            joined_at: current.map_or_else(clock::now, |index| clients[index].joined_at),
+            id,
+            name: payload.get("name").and_then(Value::as_str).map(String::from),
+            kind: payload.get("kind").and_then(Value::as_str).unwrap_or(DEFAULT_KIND).to_string(),
+            metadata: payload.get("metadata").filter(|metadata| !metadata.is_null()).cloned().unwrap_or_else(|| Value::Object(Default::default())),
+            session: session.map(String::from),
+            address: address.map(String::from),
+        };
+        match current {
+            Some(index) => clients[index] = profile.clone(),
+            None => clients.push(profile.clone()),
+        }
+        profile
+    }
+
+    fn remove(&self, registration: &mut Registration) -> Option<ClientProfile> {
+        let id = registration.0.take()?;
+        let mut clients = self.clients();
+        let index = clients.iter().position(|client| client.id == id)?;
+        Some(clients.remove(index))
+    }
+}
+
+/// The profile of one connection, once it registered
+#[derive(Debug, Default)]
+pub struct Registration(Option<String>);
+
+/// `presence.register` for the connection owning `registration`
+pub async fn register(payload: &Value, registration: &mut Registration, session: Option<&str>, address: Option<&str>) -> Value {
+    let profile = Presence::global().register(registration, payload, session, address);
+    announce(AppEventType::PresenceJoined, &profile).await;
+    serde_json::json!({ "success": true, "client": profile })
+}
+
+/// Drop the profile of a closed connection, if it registered one
+pub async fn leave(registration: &mut Registration) {
+    if let Some(profile) = Presence::global().remove(registration) {
+        announce(AppEventType::PresenceLeft, &profile).await;
+    }
+}
+
+async fn announce(event: AppEventType, profile: &ClientProfile) {
+    let payload = serde_json::to_value(profile).unwrap_or_default();
+    if let Err(e) = EventBus::global().emit_simple(&event.to_string(), payload).await {
+        error!("Failed to emit {} for client {}: {}", event.to_string(), profile.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_clients_are_listed_until_they_leave() {
+        let presence = Presence::default();
+        let (mut first, mut second) = (Registration::default(), Registration::default());
+
+        let window = presence.register(&mut first, &json!({ "name": "Main", "metadata": { "route": "/" } }), Some("s-1"), Some("127.0.0.1"));
+        assert_eq!((window.kind.as_str(), window.session.as_deref()), (DEFAULT_KIND, Some("s-1")));
+        let tools = presence.register(&mut second, &json!({ "kind": "devtools", "metadata": null }), None, None);
+        assert_eq!(tools.metadata, json!({}));
+        assert_eq!(presence.list(), [window.clone(), tools.clone()]);
+
+        // Registering again keeps the client's place
+        let renamed = presence.register(&mut first, &json!({ "name": "Settings" }), Some("s-1"), None);
+        assert_eq!((renamed.id.as_str(), renamed.joined_at), (window.id.as_str(), window.joined_at));
+        assert_eq!(presence.list()[0].name.as_deref(), Some("Settings"));
+
+        assert_eq!(presence.remove(&mut first), Some(renamed));
+        assert_eq!(presence.remove(&mut first), None);
+        assert_eq!(presence.list(), [tools]);
+    }
+}
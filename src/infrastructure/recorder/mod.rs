@@ -0,0 +1,210 @@
+//! Session recording and replay, `[recorder]` and `--replay`
+//!
+//! With `[recorder] enabled = true` every command received over the WebSocket
+//! is appended to `<dir>/session-<time>.jsonl`, one `RecordedCommand` per
+//! line, with values of secret-looking keys redacted. A user who hit a bug can
+//! send the file along, and starting the app with `--replay <file>` runs the
+//! same commands again through the command bus, at the recorded pace or
+//! `--replay-speed` times faster, against the local database.
+//!
+//! Commands answered by the connection itself (`hello`, the session commands,
+//! settings watches, downloads) are recorded for context but not replayed.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+use crate::core::domain::clock;
+use crate::infrastructure::crash_reporter::redact_secrets;
+use crate::model::core::AppConfig;
+use crate::viewmodel::websocket_handler::{WebSocketHandler, CONNECTION_COMMANDS};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedCommand {
+    /// Milliseconds since the recording started
+    pub at_ms: u64,
+    pub timestamp: DateTime<Utc>,
+    /// Address of the connection that sent the command
+    pub client: Option<String>,
+    pub name: String,
+    pub payload: Value,
+}
+
+struct Recording {
+    started: Instant,
+    file: Mutex<File>,
+}
+
+static RECORDING: OnceLock<Recording> = OnceLock::new();
+
+/// Open this run's recording file if `[recorder]` is enabled
+pub fn start(config: &AppConfig) {
+    if !config.is_recorder_enabled() {
+        return;
+    }
+    let dir = Path::new(config.get_recorder_dir());
+    let path = dir.join(format!("session-{}.jsonl", clock::now().format("%Y%m%d-%H%M%S")));
+    let opened = fs::create_dir_all(dir).and_then(|()| OpenOptions::new().create(true).append(true).open(&path));
+    match opened {
+        Ok(file) => {
+            info!("Recording WebSocket commands to {}", path.display());
+            let _ = RECORDING.set(Recording { started: Instant::now(), file: Mutex::new(file) });
+        }
+        Err(e) => error!("Failed to open the session recording {}: {}", path.display(), e),
+    }
+}
+
+/// Append a received command to the recording, if one is open
+pub fn record(client: Option<&str>, name: &str, payload: &Value) {
+    let Some(recording) = RECORDING.get() else {
+        return;
+    };
+    let command = RecordedCommand {
+        at_ms: recording.started.elapsed().as_millis() as u64,
+        timestamp: clock::now(),
+        client: client.map(String::from),
+        name: name.to_string(),
+        payload: redact_secrets(payload.clone()),
+    };
+    let Ok(mut line) = serde_json::to_string(&command) else {
+        return;
+    };
+    line.push('\n');
+    let mut file = recording.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    // Written line by line so a crash keeps everything up to it
+    if let Err(e) = file.write_all(line.as_bytes()) {
+        warn!("Failed to record {}: {}", name, e);
+    }
+}
+
+/// Read a recording; the line number of the first malformed line is in the error
+pub fn load(path: &Path) -> io::Result<Vec<RecordedCommand>> {
+    let mut commands = Vec::new();
+    for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let command = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", index + 1, e))
+        })?;
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ReplaySummary {
+    pub replayed: usize,
+    /// Replayed commands answered with `success: false`
+    pub failed: usize,
+    /// Connection commands, which are not replayed
+    pub skipped: usize,
+}
+
+/// Run `commands` through `run` in order, keeping their recorded spacing
+/// divided by `speed`; a `speed` of 0 runs them back to back
+pub async fn replay<F, Fut>(commands: &[RecordedCommand], speed: f64, mut run: F) -> ReplaySummary
+where
+    F: FnMut(String, Value) -> Fut,
+    Fut: std::future::Future<Output = Option<Value>>,
+{
+    let mut summary = ReplaySummary::default();
+    let mut previous_ms = commands.first().map_or(0, |command| command.at_ms);
+    for command in commands {
+        if speed > 0.0 {
+            let gap = command.at_ms.saturating_sub(previous_ms) as f64 / speed;
+            tokio::time::sleep(Duration::from_secs_f64(gap / 1000.0)).await;
+        }
+        previous_ms = command.at_ms;
+        if CONNECTION_COMMANDS.contains(&command.name.as_str()) {
+            summary.skipped += 1;
+            continue;
+        }
+        let response = run(command.name.clone(), command.payload.clone()).await;
+        summary.replayed += 1;
+        match response.as_ref().and_then(|response| response.get("success")).and_then(Value::as_bool) {
+            Some(false) => {
+                summary.failed += 1;
+                let error = response.as_ref().map(|response| &response["error"]);
+                let message = error.map(|error| error.get("message").unwrap_or(error).to_string()).unwrap_or_default();
+                warn!("Replayed {} at {} ms: failed: {}", command.name, command.at_ms, message);
+            }
+            _ => info!("Replayed {} at {} ms", command.name, command.at_ms),
+        }
+    }
+    summary
+}
+
+/// `--replay <file> [--replay-speed <factor>]`: replay the file in the background
+pub fn start_replay(path: PathBuf, speed: f64) {
+    let commands = match load(&path) {
+        Ok(commands) => commands,
+        Err(e) => {
+            error!("Cannot replay {}: {}", path.display(), e);
+            return;
+        }
+    };
+    info!("Replaying {} command(s) from {} at {}x", commands.len(), path.display(), speed);
+    tokio::spawn(async move {
+        let summary = replay(&commands, speed, |name, payload| async move {
+            WebSocketHandler::handle_function_call(&name, &payload).await
+        })
+        .await;
+        info!(
+            "Replay of {} finished: {} replayed, {} failed, {} skipped",
+            path.display(),
+            summary.replayed,
+            summary.failed,
+            summary.skipped
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+    use crate::viewmodel::protocol;
+
+    fn command(at_ms: u64, name: &str) -> RecordedCommand {
+        RecordedCommand { at_ms, timestamp: Utc::now(), client: None, name: name.to_string(), payload: serde_json::json!({}) }
+    }
+
+    #[tokio::test]
+    async fn test_replay_keeps_order_and_skips_connection_commands() {
+        let commands = vec![command(0, protocol::HELLO), command(200, "get_users"), command(400, "create_user")];
+        let mut seen = Vec::new();
+        let started = Instant::now();
+        let summary = replay(&commands, 10.0, |name, _| {
+            seen.push(name.clone());
+            async move { (name == "create_user").then(|| serde_json::json!({ "success": false, "error": {} })) }
+        })
+        .await;
+
+        assert_eq!(seen, ["get_users", "create_user"]);
+        assert_eq!(summary, ReplaySummary { replayed: 2, failed: 1, skipped: 1 });
+        // 400 ms recorded, replayed ten times faster
+        assert!(started.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_load_reports_the_bad_line() {
+        let dir = TempDir::new("recording");
+        let path = dir.join("recording.jsonl");
+        let good = serde_json::to_string(&command(5, "get_users")).unwrap();
+        fs::write(&path, format!("{}\n\n{}\nnot json\n", good, good)).unwrap();
+        let error = load(&path).unwrap_err();
+        fs::write(&path, format!("{}\n{}\n", good, good)).unwrap();
+        let loaded = load(&path).unwrap();
+
+        assert!(error.to_string().contains("line 4"), "{}", error);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!((loaded[0].at_ms, loaded[0].name.as_str()), (5, "get_users"));
+    }
+}
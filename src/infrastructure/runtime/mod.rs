@@ -0,0 +1,131 @@
+//! The tokio runtime, sized from `[runtime]`, and its metrics for devtools
+//!
+//! `main` builds the runtime with `build` before anything else runs, instead
+//! of `#[tokio::main]`, so low-end machines can get by with fewer worker and
+//! blocking threads. `metrics()` reports what the runtime is doing for
+//! `/api/devtools/runtime`: live tasks and queue depths overall and per worker.
+//! Blocking pool figures need tokio's unstable metrics, i.e. a build with
+//! `RUSTFLAGS="--cfg tokio_unstable"`; otherwise they are `null`.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::sync::OnceLock;
+use tokio::runtime::{Handle, Runtime};
+use utoipa::ToSchema;
+use crate::model::core::AppConfig;
+
+/// The thread counts the app was started with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RuntimeTuning {
+    pub worker_threads: usize,
+    pub max_blocking_threads: usize,
+    pub http_threads: usize,
+}
+
+impl RuntimeTuning {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            worker_threads: config.get_runtime_worker_threads(),
+            max_blocking_threads: config.get_runtime_max_blocking_threads(),
+            http_threads: config.get_http_threads(),
+        }
+    }
+}
+
+/// One worker thread
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkerMetrics {
+    pub worker: usize,
+    /// Time spent running tasks since startup
+    pub busy_ms: u64,
+    /// Times it ran out of work and parked
+    pub park_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RuntimeMetrics {
+    pub tuning: RuntimeTuning,
+    /// Spawned tasks not finished yet
+    pub alive_tasks: usize,
+    /// Tasks waiting in the queue shared by all workers
+    pub global_queue_depth: usize,
+    pub workers: Vec<WorkerMetrics>,
+    /// Threads of the blocking pool, idle or not (`tokio_unstable` builds only)
+    pub blocking_threads: Option<usize>,
+    pub idle_blocking_threads: Option<usize>,
+    /// Blocking work waiting for a thread (`tokio_unstable` builds only)
+    pub blocking_queue_depth: Option<usize>,
+    /// Tasks spawned since startup (`tokio_unstable` builds only)
+    pub spawned_tasks: Option<u64>,
+}
+
+static RUNTIME: OnceLock<(Handle, RuntimeTuning)> = OnceLock::new();
+
+/// The runtime for `config`; the first one built is the one `metrics()` reports on
+pub fn build(config: &AppConfig) -> io::Result<Runtime> {
+    let tuning = RuntimeTuning::from_config(config);
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(tuning.worker_threads)
+        .max_blocking_threads(tuning.max_blocking_threads)
+        .enable_all()
+        .build()?;
+    let _ = RUNTIME.set((runtime.handle().clone(), tuning));
+    Ok(runtime)
+}
+
+/// `None` until `build` ran
+pub fn metrics() -> Option<RuntimeMetrics> {
+    RUNTIME.get().map(|(handle, tuning)| snapshot(handle, *tuning))
+}
+
+fn snapshot(handle: &Handle, tuning: RuntimeTuning) -> RuntimeMetrics {
+    let metrics = handle.metrics();
+    let workers = (0..metrics.num_workers())
+        .map(|worker| WorkerMetrics {
+            worker,
+            busy_ms: metrics.worker_total_busy_duration(worker).as_millis() as u64,
+            park_count: metrics.worker_park_count(worker),
+        })
+        .collect();
+    #[cfg(tokio_unstable)]
+    let (blocking_threads, idle_blocking_threads, blocking_queue_depth, spawned_tasks) = (
+        Some(metrics.num_blocking_threads()),
+        Some(metrics.num_idle_blocking_threads()),
+        Some(metrics.blocking_queue_depth()),
+        Some(metrics.spawned_tasks_count()),
+    );
+    #[cfg(not(tokio_unstable))]
+    let (blocking_threads, idle_blocking_threads, blocking_queue_depth, spawned_tasks) = (None, None, None, None);
+    RuntimeMetrics {
+        tuning,
+        alive_tasks: metrics.num_alive_tasks(),
+        global_queue_depth: metrics.global_queue_depth(),
+        workers,
+        blocking_threads,
+        idle_blocking_threads,
+        blocking_queue_depth,
+        spawned_tasks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::core::RuntimeSettings;
+
+    #[test]
+    fn test_runtime_is_sized_from_config() {
+        let config = AppConfig {
+            runtime: RuntimeSettings { worker_threads: Some(2), max_blocking_threads: Some(0), http_threads: Some(500) },
+            ..AppConfig::default()
+        };
+        let tuning = RuntimeTuning::from_config(&config);
+        assert_eq!(tuning, RuntimeTuning { worker_threads: 2, max_blocking_threads: 1, http_threads: 64 });
+
+        let runtime = build(&config).unwrap();
+        let metrics = snapshot(runtime.handle(), tuning);
+        assert_eq!(metrics.workers.len(), 2);
+        assert_eq!(metrics.tuning.worker_threads, 2);
+        assert_eq!(metrics.blocking_queue_depth.is_some(), cfg!(tokio_unstable));
+    }
+}
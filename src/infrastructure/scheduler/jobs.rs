@@ -0,0 +1,206 @@
+//! Built-in maintenance jobs registered at startup
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::error;
+use crate::core::application::SystemInfoDto;
+use crate::core::domain::SystemInfoRepository;
+use crate::infrastructure::database::database;
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::infrastructure::logging::log_file;
+use crate::infrastructure::sessions::SessionManager;
+use crate::infrastructure::system_info::system_info_repository;
+use crate::infrastructure::workers;
+use crate::model::core::AppConfig;
+use super::{Job, JobSchedule, RetryPolicy, Scheduler, SchedulerError};
+
+/// Run blocking work off the async runtime, one job at a time by default
+async fn blocking<F>(f: F) -> Result<String, String>
+where
+    F: FnOnce() -> Result<String, String> + Send + 'static,
+{
+    workers::run(workers::JOBS, f).await.and_then(|result| result)
+}
+
+fn vacuum_database() -> Result<String, String> {
+    let db = database().map_err(|e| e.message)?;
+    let before = db.analyze().map_err(|e| e.to_string())?;
+    db.vacuum().map_err(|e| e.to_string())?;
+    // VACUUM rewrites the whole database through the WAL
+    db.checkpoint().map_err(|e| e.to_string())?;
+    Ok(format!("Database vacuumed, {} free page(s) reclaimed", before.freelist_count))
+}
+
+fn checkpoint_database() -> Result<String, String> {
+    let done = database().map_err(|e| e.message)?.checkpoint().map_err(|e| e.to_string())?;
+    if done.busy {
+        return Err(format!("Checkpoint blocked by a reader after {} of {} WAL frame(s)", done.checkpointed_frames, done.wal_frames));
+    }
+    Ok(format!("Checkpointed {} WAL frame(s) and truncated the WAL", done.checkpointed_frames))
+}
+
+fn backup_database(backup_dir: &Path, keep: usize) -> Result<String, String> {
+    std::fs::create_dir_all(backup_dir).map_err(|e| e.to_string())?;
+
+    let file_name = format!("backup-{}.db", chrono::Utc::now().format("%Y%m%d-%H%M%S"));
+    let path = backup_dir.join(file_name);
+    database().map_err(|e| e.message)?.backup_to(&path).map_err(|e| e.to_string())?;
+
+    let removed = prune_files(backup_dir, "backup-", keep).map_err(|e| e.to_string())?;
+    Ok(format!("Backup written to {} ({} old backup(s) removed)", path.display(), removed))
+}
+
+/// Delete the oldest files in `dir` starting with `prefix`, keeping `keep` of them
+fn prune_files(dir: &Path, prefix: &str, keep: usize) -> std::io::Result<usize> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(prefix))
+        })
+        .collect();
+
+    // Names embed a sortable timestamp, newest last
+    files.sort();
+    let excess = files.len().saturating_sub(keep);
+    for path in &files[..excess] {
+        std::fs::remove_file(path)?;
+    }
+    Ok(excess)
+}
+
+/// Rename `path` to `path.1` (shifting older copies) once it exceeds `max_bytes`,
+/// then have the app write to a new file
+fn rotate_log(path: &Path, max_bytes: u64, keep: usize) -> Result<String, String> {
+    let size = match std::fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return Ok(format!("{} does not exist, nothing to rotate", path.display())),
+    };
+    if size < max_bytes {
+        return Ok(format!("{} is {} bytes, below rotation threshold", path.display(), size));
+    }
+
+    let rotated = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+    let _ = std::fs::remove_file(rotated(keep.max(1)));
+    for n in (1..keep.max(1)).rev() {
+        let from = rotated(n);
+        if from.exists() {
+            std::fs::rename(&from, rotated(n + 1)).map_err(|e| e.to_string())?;
+        }
+    }
+    std::fs::rename(path, rotated(1)).map_err(|e| e.to_string())?;
+    log_file::reopen().map_err(|e| e.to_string())?;
+    Ok(format!("Rotated {} ({} bytes)", path.display(), size))
+}
+
+fn cleanup_sessions() -> Result<String, String> {
+    let removed = SessionManager::global().cleanup().map_err(|e| e.to_string())?;
+    Ok(format!("Removed {} expired session(s)", removed))
+}
+
+async fn publish_metrics_snapshot() -> Result<String, String> {
+    let info = system_info_repository().get_current().map_err(|e| e.to_string())?;
+    let payload = serde_json::json!({
+        "type": "scheduled_snapshot",
+        "system": SystemInfoDto::from(info)
+    });
+
+    if let Err(e) = EventBus::global()
+        .emit_simple(&AppEventType::SystemHealthCheck.to_string(), payload)
+        .await
+    {
+        error!("Failed to emit metrics snapshot: {}", e);
+        return Err(e.to_string());
+    }
+    Ok("Metrics snapshot published".to_string())
+}
+
+/// Register the maintenance jobs configured in `[scheduler]`
+pub fn register_builtin_jobs(scheduler: &Scheduler, config: &AppConfig) -> Result<(), SchedulerError> {
+    scheduler.register(
+        Job::new("database.vacuum", JobSchedule::cron(config.get_vacuum_schedule())?, || {
+            blocking(vacuum_database)
+        })
+        .with_description("Reclaim unused space in the SQLite database"),
+    )?;
+
+    scheduler.register(
+        Job::new("database.checkpoint", JobSchedule::cron(config.get_checkpoint_schedule())?, || {
+            blocking(checkpoint_database)
+        })
+        .with_description("Copy the WAL back into the database file and truncate it"),
+    )?;
+
+    let backup_dir = PathBuf::from(config.get_backup_dir());
+    let backup_keep = config.get_backup_keep();
+    scheduler.register(
+        Job::new("database.backup", JobSchedule::cron(config.get_backup_schedule())?, move || {
+            let backup_dir = backup_dir.clone();
+            blocking(move || backup_database(&backup_dir, backup_keep))
+        })
+        .with_description("Copy the database into the backup folder and prune old copies"),
+    )?;
+
+    let log_max_bytes = config.get_log_max_size_mb() * 1024 * 1024;
+    let log_keep = config.get_log_keep();
+    scheduler.register(
+        Job::new("logs.rotate", JobSchedule::every(Duration::from_secs(3600)), move || {
+            blocking(move || match log_file::path() {
+                Some(path) => rotate_log(path, log_max_bytes, log_keep),
+                None => Ok("File logging is disabled, nothing to rotate".to_string()),
+            })
+        })
+        .with_description("Rotate the log file once it exceeds the size limit")
+        .with_retry(RetryPolicy::none()),
+    )?;
+
+    scheduler.register(
+        Job::new("sessions.cleanup", JobSchedule::every(Duration::from_secs(900)), || blocking(cleanup_sessions))
+            .with_description("Delete expired WebSocket client sessions"),
+    )?;
+
+    scheduler.register(
+        Job::new("metrics.snapshot", JobSchedule::every(Duration::from_secs(300)), publish_metrics_snapshot)
+            .with_description("Publish a system health snapshot on the event bus")
+            .with_retry(RetryPolicy::none()),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let dir = TempDir::new("scheduler-jobs");
+        for stamp in ["20240101", "20240102", "20240103"] {
+            std::fs::write(dir.join(format!("backup-{}.db", stamp)), b"").unwrap();
+        }
+        std::fs::write(dir.join("other.db"), b"").unwrap();
+
+        assert_eq!(prune_files(&dir, "backup-", 2).unwrap(), 1);
+        assert!(!dir.join("backup-20240101.db").exists());
+        assert!(dir.join("backup-20240103.db").exists());
+        assert!(dir.join("other.db").exists());
+    }
+
+    #[test]
+    fn test_rotate_log_shifts_copies() {
+        let dir = TempDir::new("scheduler-jobs");
+        let log = dir.join("app.log");
+        std::fs::write(&log, b"0123456789").unwrap();
+        std::fs::write(dir.join("app.log.1"), b"old").unwrap();
+
+        rotate_log(&log, 100, 3).unwrap();
+        assert!(log.exists());
+
+        rotate_log(&log, 5, 3).unwrap();
+        assert!(!log.exists());
+        assert_eq!(std::fs::read(dir.join("app.log.1")).unwrap(), b"0123456789");
+        assert_eq!(std::fs::read(dir.join("app.log.2")).unwrap(), b"old");
+    }
+}
@@ -0,0 +1,467 @@
+//! Background job scheduler - interval and cron jobs with retry and run history
+//!
+//! Jobs are registered at startup (see `jobs::register_builtin_jobs`) or by
+//! plugins through `Scheduler::global().register(...)`.
+
+pub mod jobs;
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{error, info, warn};
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+
+/// Number of finished runs kept in memory
+const HISTORY_LIMIT: usize = 200;
+/// Runs `scheduler.history` returns without a `limit`; at most `HISTORY_LIMIT`
+const DEFAULT_HISTORY_PAGE: usize = 50;
+
+pub type JobFuture = Pin<Box<dyn Future<Output = Result<String, String>> + Send>>;
+pub type JobFn = Arc<dyn Fn() -> JobFuture + Send + Sync>;
+
+#[derive(Debug, Error)]
+pub enum SchedulerError {
+    #[error("Invalid cron expression '{0}': {1}")]
+    InvalidCron(String, String),
+    #[error("Job already registered: {0}")]
+    DuplicateJob(String),
+    #[error("Job not found: {0}")]
+    JobNotFound(String),
+    #[error("Job is already running: {0}")]
+    AlreadyRunning(String),
+    #[error("Scheduler has not been started")]
+    NotStarted,
+}
+
+/// When a job runs
+#[derive(Debug, Clone)]
+pub enum JobSchedule {
+    Interval(Duration),
+    Cron(Box<cron::Schedule>),
+}
+
+impl JobSchedule {
+    pub fn every(interval: Duration) -> Self {
+        JobSchedule::Interval(interval)
+    }
+
+    /// Parse a cron expression with a seconds field, e.g. `"0 30 2 * * *"`
+    pub fn cron(expression: &str) -> Result<Self, SchedulerError> {
+        cron::Schedule::from_str(expression)
+            .map(|schedule| JobSchedule::Cron(Box::new(schedule)))
+            .map_err(|e| SchedulerError::InvalidCron(expression.to_string(), e.to_string()))
+    }
+
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            JobSchedule::Interval(interval) => chrono::Duration::from_std(*interval)
+                .ok()
+                .map(|interval| after + interval),
+            JobSchedule::Cron(schedule) => schedule.after(&after).next(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            JobSchedule::Interval(interval) => format!("every {}s", interval.as_secs()),
+            JobSchedule::Cron(schedule) => format!("cron {}", schedule),
+        }
+    }
+}
+
+/// How often a failing job is retried, with exponential backoff between attempts
+#[derive(Debug, Clone, Serialize)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff_secs: u64,
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff_secs: 0,
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        Duration::from_secs(self.initial_backoff_secs.saturating_mul(1 << attempt.saturating_sub(1).min(16)))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_secs: 5,
+        }
+    }
+}
+
+/// A named unit of background work
+pub struct Job {
+    name: String,
+    description: String,
+    schedule: JobSchedule,
+    retry: RetryPolicy,
+    run: JobFn,
+}
+
+impl Job {
+    pub fn new<F, Fut>(name: &str, schedule: JobSchedule, run: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            description: String::new(),
+            schedule,
+            retry: RetryPolicy::default(),
+            run: Arc::new(move || Box::pin(run())),
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobTrigger {
+    Schedule,
+    Manual,
+}
+
+/// Outcome of one job execution (including retries)
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct JobRun {
+    pub job: String,
+    pub trigger: JobTrigger,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub attempts: u32,
+    pub success: bool,
+    pub message: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Snapshot of a registered job for `scheduler.list`
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub name: String,
+    pub description: String,
+    pub schedule: String,
+    pub retry: RetryPolicy,
+    pub next_run: Option<DateTime<Utc>>,
+    pub running: bool,
+    pub run_count: u64,
+    pub failure_count: u64,
+    pub last_run: Option<JobRun>,
+}
+
+struct JobState {
+    job: Arc<Job>,
+    next_run: Option<DateTime<Utc>>,
+    running: bool,
+    run_count: u64,
+    failure_count: u64,
+    last_run: Option<JobRun>,
+}
+
+pub struct Scheduler {
+    jobs: Mutex<HashMap<String, JobState>>,
+    history: Mutex<VecDeque<JobRun>>,
+    runtime: OnceLock<tokio::runtime::Handle>,
+}
+
+impl Scheduler {
+    fn new() -> Self {
+        Self {
+            jobs: Mutex::new(HashMap::new()),
+            history: Mutex::new(VecDeque::new()),
+            runtime: OnceLock::new(),
+        }
+    }
+
+    pub fn global() -> &'static Scheduler {
+        static SCHEDULER: OnceLock<Scheduler> = OnceLock::new();
+        SCHEDULER.get_or_init(Scheduler::new)
+    }
+
+    /// Add a job; its first run is computed from the current time
+    pub fn register(&self, job: Job) -> Result<(), SchedulerError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.contains_key(&job.name) {
+            return Err(SchedulerError::DuplicateJob(job.name));
+        }
+
+        let next_run = job.schedule.next_after(Utc::now());
+        info!("Registered job '{}' ({}), next run {:?}", job.name, job.schedule.describe(), next_run);
+        jobs.insert(
+            job.name.clone(),
+            JobState {
+                job: Arc::new(job),
+                next_run,
+                running: false,
+                run_count: 0,
+                failure_count: 0,
+                last_run: None,
+            },
+        );
+        Ok(())
+    }
+
+    /// Start the dispatch loop on the current Tokio runtime
+    pub fn start(&'static self) {
+        if self.runtime.set(tokio::runtime::Handle::current()).is_err() {
+            warn!("Scheduler already started");
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(Duration::from_secs(1));
+            loop {
+                tick.tick().await;
+                for job in self.take_due_jobs(Utc::now()) {
+                    tokio::spawn(self.execute(job, JobTrigger::Schedule));
+                }
+            }
+        });
+        info!("Scheduler started");
+    }
+
+    /// Mark every due, idle job as running and advance its next run time
+    fn take_due_jobs(&self, now: DateTime<Utc>) -> Vec<Arc<Job>> {
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.values_mut()
+            .filter(|state| !state.running && state.next_run.is_some_and(|next| next <= now))
+            .map(|state| {
+                state.running = true;
+                state.next_run = state.job.schedule.next_after(now);
+                state.job.clone()
+            })
+            .collect()
+    }
+
+    async fn execute(&self, job: Arc<Job>, trigger: JobTrigger) -> JobRun {
+        let started_at = Utc::now();
+        let mut attempts = 0;
+        let result = loop {
+            attempts += 1;
+            match (job.run)().await {
+                Ok(message) => break Ok(message),
+                Err(e) if attempts < job.retry.max_attempts => {
+                    let backoff = job.retry.backoff(attempts);
+                    warn!("Job '{}' failed (attempt {}): {}; retrying in {:?}", job.name, attempts, e, backoff);
+                    tokio::time::sleep(backoff).await;
+                }
+                Err(e) => break Err(e),
+            }
+        };
+
+        let run = JobRun {
+            job: job.name.clone(),
+            trigger,
+            started_at,
+            finished_at: Utc::now(),
+            attempts,
+            success: result.is_ok(),
+            message: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().cloned(),
+        };
+
+        match &result {
+            Ok(_) => info!("Job '{}' completed after {} attempt(s)", job.name, attempts),
+            Err(e) => error!("Job '{}' failed after {} attempt(s): {}", job.name, attempts, e),
+        }
+        self.record(&run);
+
+        if let Err(e) = EventBus::global()
+            .emit_simple(
+                &AppEventType::JobCompleted.to_string(),
+                serde_json::to_value(&run).unwrap_or_default(),
+            )
+            .await
+        {
+            error!("Failed to emit job completed event: {}", e);
+        }
+
+        run
+    }
+
+    fn record(&self, run: &JobRun) {
+        if let Some(state) = self.jobs.lock().unwrap().get_mut(&run.job) {
+            state.running = false;
+            state.run_count += 1;
+            if !run.success {
+                state.failure_count += 1;
+            }
+            state.last_run = Some(run.clone());
+        }
+
+        let mut history = self.history.lock().unwrap();
+        if history.len() >= HISTORY_LIMIT {
+            history.pop_front();
+        }
+        history.push_back(run.clone());
+    }
+
+    fn claim(&self, name: &str) -> Result<Arc<Job>, SchedulerError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let state = jobs
+            .get_mut(name)
+            .ok_or_else(|| SchedulerError::JobNotFound(name.to_string()))?;
+        if state.running {
+            return Err(SchedulerError::AlreadyRunning(name.to_string()));
+        }
+        state.running = true;
+        Ok(state.job.clone())
+    }
+
+    /// Run a job immediately and wait for it to finish
+    pub async fn run_now(&self, name: &str) -> Result<JobRun, SchedulerError> {
+        let job = self.claim(name)?;
+        Ok(self.execute(job, JobTrigger::Manual).await)
+    }
+
+    /// Run a job immediately in the background (for callers outside the runtime)
+    pub fn trigger(&'static self, name: &str) -> Result<(), SchedulerError> {
+        let runtime = self.runtime.get().ok_or(SchedulerError::NotStarted)?;
+        let job = self.claim(name)?;
+        runtime.spawn(self.execute(job, JobTrigger::Manual));
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<JobInfo> {
+        let jobs = self.jobs.lock().unwrap();
+        let mut list: Vec<JobInfo> = jobs
+            .values()
+            .map(|state| JobInfo {
+                name: state.job.name.clone(),
+                description: state.job.description.clone(),
+                schedule: state.job.schedule.describe(),
+                retry: state.job.retry.clone(),
+                next_run: state.next_run,
+                running: state.running,
+                run_count: state.run_count,
+                failure_count: state.failure_count,
+                last_run: state.last_run.clone(),
+            })
+            .collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        list
+    }
+
+    /// Most recent runs first, optionally filtered by job name
+    pub fn history(&self, job: Option<&str>, limit: usize) -> Vec<JobRun> {
+        self.history
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|run| job.is_none_or(|name| run.job == name))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Handle `scheduler.*` commands from the frontend / devtools
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    let scheduler = Scheduler::global();
+
+    match name {
+        "scheduler.list" => serde_json::json!({ "success": true, "jobs": scheduler.list() }),
+        "scheduler.history" => {
+            let job = payload.get("job").and_then(|v| v.as_str());
+            let limit = payload
+                .get("limit")
+                .and_then(|v| v.as_u64())
+                .map_or(DEFAULT_HISTORY_PAGE, |limit| limit.clamp(1, HISTORY_LIMIT as u64) as usize);
+            serde_json::json!({ "success": true, "runs": scheduler.history(job, limit) })
+        }
+        "scheduler.run_now" => {
+            let job = payload.get("job").and_then(|v| v.as_str()).unwrap_or_default();
+            match scheduler.run_now(job).await {
+                Ok(run) => serde_json::json!({ "success": run.success, "run": run }),
+                Err(e) => serde_json::json!({ "success": false, "error": e.to_string() }),
+            }
+        }
+        _ => serde_json::json!({
+            "success": false,
+            "error": format!("Unknown scheduler command: {}", name)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_cron_next_run() {
+        let schedule = JobSchedule::cron("0 30 2 * * *").unwrap();
+        let after = DateTime::parse_from_rfc3339("2024-01-01T03:00:00Z").unwrap().with_timezone(&Utc);
+        let next = schedule.next_after(after).unwrap();
+        assert_eq!(next.to_rfc3339(), "2024-01-02T02:30:00+00:00");
+        assert!(JobSchedule::cron("not a cron").is_err());
+    }
+
+    #[test]
+    fn test_due_jobs_are_claimed_once() {
+        let scheduler = Scheduler::new();
+        scheduler
+            .register(Job::new("tick", JobSchedule::every(Duration::from_secs(60)), || async {
+                Ok(String::new())
+            }))
+            .unwrap();
+        assert!(scheduler.register(Job::new("tick", JobSchedule::every(Duration::from_secs(1)), || async {
+            Ok(String::new())
+        })).is_err());
+
+        let later = Utc::now() + chrono::Duration::seconds(120);
+        assert_eq!(scheduler.take_due_jobs(later).len(), 1);
+        // Still running, so not dispatched again
+        assert!(scheduler.take_due_jobs(later + chrono::Duration::seconds(120)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_job_is_retried() {
+        static CALLS: AtomicU32 = AtomicU32::new(0);
+        let scheduler = Scheduler::new();
+        scheduler
+            .register(
+                Job::new("flaky", JobSchedule::every(Duration::from_secs(3600)), || async {
+                    match CALLS.fetch_add(1, Ordering::SeqCst) {
+                        0 => Err("first attempt fails".to_string()),
+                        _ => Ok("done".to_string()),
+                    }
+                })
+                .with_retry(RetryPolicy { max_attempts: 3, initial_backoff_secs: 0 }),
+            )
+            .unwrap();
+
+        let run = scheduler.run_now("flaky").await.unwrap();
+        assert!(run.success);
+        assert_eq!(run.attempts, 2);
+        assert_eq!(scheduler.history(Some("flaky"), 10).len(), 1);
+        assert_eq!(scheduler.list()[0].run_count, 1);
+    }
+}
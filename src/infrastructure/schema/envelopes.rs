@@ -0,0 +1,34 @@
+//! Shape of a failed command response
+//!
+//! Built with `json!` by `GlobalErrorHandler::to_json_response`; this type
+//! mirrors it so its schema can be derived.
+
+#![allow(dead_code)]
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use crate::error_handling::{ErrorCode, RecoveryAction};
+
+/// Payload of a response whose command failed
+#[derive(Serialize, JsonSchema)]
+pub struct ErrorResponse {
+    /// Always `false`
+    pub success: bool,
+    pub error: ErrorBody,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub code_value: u16,
+    pub message: String,
+    /// Safe to show to the user
+    pub user_message: String,
+    pub recovery: RecoveryAction,
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub context: HashMap<String, Value>,
+}
@@ -0,0 +1,361 @@
+//! Payload shapes of the application events
+//!
+//! Most events are emitted with `json!` payloads; these types mirror them so
+//! their schemas can be derived. Events that carry an existing type (tasks,
+//! metrics samples, job runs) use it directly in `super::event_schema`.
+
+#![allow(dead_code)]
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use crate::core::application::SystemInfoDto;
+use crate::core::domain::Counter;
+use crate::infrastructure::database::changes::ChangeOperation;
+use super::envelopes::ErrorBody;
+
+/// `user.login` / `user.logout`
+#[derive(Serialize, JsonSchema)]
+pub struct UserSessionPayload {
+    pub user_id: Option<i64>,
+}
+
+/// `data.changed`
+#[derive(Serialize, JsonSchema)]
+pub struct DataChangedPayload {
+    pub operation: ChangeOperation,
+    pub table: String,
+    /// The row changed, unless `count` rows were
+    pub rowid: Option<i64>,
+    pub count: Option<u64>,
+}
+
+/// `counter.incremented`
+#[derive(Serialize, JsonSchema)]
+pub struct CounterIncrementedPayload {
+    pub id: String,
+    pub value: i64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CounterChange {
+    Increment,
+    Decrement,
+    Reset,
+    Create,
+    /// Undo or redo moved the value
+    Set,
+    /// Undo or redo created the counter again
+    Restore,
+    /// Undo removed the counter
+    Delete,
+}
+
+/// `counter.changed`
+#[derive(Serialize, JsonSchema)]
+pub struct CounterChangedPayload {
+    pub operation: CounterChange,
+    pub counter: Counter,
+}
+
+/// `database.operation`
+#[derive(Serialize, JsonSchema)]
+pub struct DatabaseOperationPayload {
+    pub operation: String,
+    pub count: Option<u64>,
+}
+
+/// `database.activity`, the `[batching]` summary of `database.operation`
+#[derive(Serialize, JsonSchema)]
+pub struct BatchSummaryPayload {
+    /// The batched event name or pattern
+    pub topic: String,
+    pub count: u64,
+    /// Events per `group_by` value, or per event name
+    pub counts: BTreeMap<String, u64>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// `system.health.check`
+#[derive(Serialize, JsonSchema)]
+pub struct SystemHealthCheckPayload {
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub system: Option<SystemInfoDto>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowAction {
+    Created,
+    Focused,
+    Blurred,
+    Minimized,
+    Restored,
+    Maximized,
+    Resized,
+    Moved,
+    Closed,
+}
+
+/// `window.state.changed`
+#[derive(Serialize, JsonSchema)]
+pub struct WindowStateChangedPayload {
+    pub id: String,
+    pub action: WindowAction,
+    pub title: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardOperation {
+    Read,
+    Write,
+}
+
+/// `clipboard.accessed`
+#[derive(Serialize, JsonSchema)]
+pub struct ClipboardAccessedPayload {
+    pub operation: ClipboardOperation,
+    pub length: usize,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// `plugin.access_denied`
+#[derive(Serialize, JsonSchema)]
+pub struct PluginAccessDeniedPayload {
+    pub plugin: String,
+    pub operation: String,
+    pub permission: String,
+    /// A permission of this kind is declared, just not one covering the call
+    pub declared: bool,
+}
+
+/// `fs.changed`
+#[derive(Serialize, JsonSchema)]
+pub struct FsChangedPayload {
+    pub watch_id: String,
+    pub path: String,
+    pub created: Vec<String>,
+    pub modified: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// `images.organize.progress`
+#[derive(Serialize, JsonSchema)]
+pub struct ImagesOrganizeProgressPayload {
+    pub processed: usize,
+    pub total: usize,
+    pub current: String,
+    pub dry_run: bool,
+}
+
+/// `file.transfer.progress`
+#[derive(Serialize, JsonSchema)]
+pub struct FileTransferProgressPayload {
+    /// `upload_id` or `download_id`
+    pub transfer_id: String,
+    pub direction: TransferDirection,
+    /// File name
+    pub name: String,
+    /// Bytes transferred so far
+    pub bytes: u64,
+    pub total: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+/// `file.uploaded`
+#[derive(Serialize, JsonSchema)]
+pub struct FileUploadedPayload {
+    /// Where the file was stored
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+    pub transport: UploadTransport,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadTransport {
+    Websocket,
+    Http,
+}
+
+/// `settings.changed`
+#[derive(Serialize, JsonSchema)]
+pub struct SettingsChangedPayload {
+    pub namespace: String,
+    pub key: String,
+    /// `null` once the value was removed
+    pub value: Value,
+    /// `null` for a global value
+    pub user_id: Option<i64>,
+}
+
+/// `task.progress`
+#[derive(Serialize, JsonSchema)]
+pub struct TaskProgressPayload {
+    pub id: String,
+    pub name: String,
+    /// 0.0 - 1.0
+    pub progress: f32,
+    pub message: Option<String>,
+}
+
+/// `update.available`
+#[derive(Serialize, JsonSchema)]
+pub struct UpdateAvailablePayload {
+    pub current_version: String,
+    pub version: String,
+    pub notes: Option<String>,
+    /// Release page
+    pub url: Option<String>,
+    pub published_at: Option<String>,
+    /// A signed download for this platform exists and `[updater] public_key` is set
+    pub installable: bool,
+}
+
+/// `update.installed`
+#[derive(Serialize, JsonSchema)]
+pub struct UpdateInstalledPayload {
+    pub version: String,
+    pub restart_required: bool,
+}
+
+/// `flags.changed`
+#[derive(Serialize, JsonSchema)]
+pub struct FlagsChangedPayload {
+    pub name: String,
+    /// Whether the flag is now on, for `user_id` when set
+    pub enabled: bool,
+    /// The override set, `null` once it was removed
+    #[serde(rename = "override")]
+    pub override_value: Option<bool>,
+    pub user_id: Option<i64>,
+}
+
+/// `chaos.injected`, a fault injected by `[chaos]` in a debug build
+#[derive(Serialize, JsonSchema)]
+pub struct ChaosInjectedPayload {
+    /// `db_delay`, `ws_drop` or `handler_error`
+    pub fault: String,
+    /// The command answered with an error, for `handler_error`
+    pub command: Option<String>,
+    /// How long the database call was held up, for `db_delay`
+    pub delay_ms: Option<u64>,
+    /// Size of the dropped message, for `ws_drop`
+    pub bytes: Option<u64>,
+}
+
+/// `state.patch`, a change of a state document a connection subscribed to
+#[derive(Serialize, JsonSchema)]
+pub struct StatePatchPayload {
+    /// `users`, `counters` or `system`
+    pub document: String,
+    /// One more than the version the patch applies to
+    pub version: u64,
+    /// RFC 6902 operations
+    pub patch: Vec<Value>,
+}
+
+/// `command.accepted`, sent to the connection that sent a command with a `mutation_id`
+#[derive(Serialize, JsonSchema)]
+pub struct CommandAcceptedPayload {
+    pub mutation_id: String,
+    pub command: String,
+}
+
+/// `command.applied`, a mutation that succeeded
+#[derive(Serialize, JsonSchema)]
+pub struct CommandAppliedPayload {
+    pub mutation_id: String,
+    pub command: String,
+    /// The response of the command
+    pub result: Value,
+    /// The state document the command changes, with its version and state after it
+    pub document: Option<String>,
+    pub version: Option<u64>,
+    pub state: Option<Value>,
+}
+
+/// `command.rejected`, a mutation that failed
+#[derive(Serialize, JsonSchema)]
+pub struct CommandRejectedPayload {
+    pub mutation_id: String,
+    pub command: String,
+    pub error: ErrorBody,
+    /// The state document the command would have changed, with its version and state
+    pub document: Option<String>,
+    pub version: Option<u64>,
+    pub state: Option<Value>,
+}
+
+/// `collab.update`, a change to a shared document
+#[derive(Serialize, JsonSchema)]
+pub struct CollabUpdatePayload {
+    pub document: String,
+    /// Base64 of the Yjs update (v1 encoding)
+    pub update: String,
+    /// Whatever the client that made the change sent along, so it can skip its own
+    pub origin: Option<String>,
+}
+
+/// `hotkey.pressed`, a registered global shortcut was pressed
+#[derive(Serialize, JsonSchema)]
+pub struct HotkeyPressedPayload {
+    /// As the OS knows it, e.g. `shift+control+KeyH`
+    pub accelerator: String,
+    pub action: Option<String>,
+    pub command: Option<String>,
+    /// The response of the command, if the binding has one
+    pub result: Option<Value>,
+}
+
+/// `document.exported`, a `document.export_pdf` finished
+#[derive(Serialize, JsonSchema)]
+pub struct DocumentExportedPayload {
+    pub export_id: String,
+    pub path: String,
+    pub success: bool,
+    /// Size of the PDF written
+    pub bytes: Option<u64>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// `capture.requested`, a screenshot waiting for the user's confirmation
+#[derive(Serialize, JsonSchema)]
+pub struct CaptureRequestedPayload {
+    pub request_id: String,
+    /// `window` or `screen`
+    pub kind: String,
+    /// Title of the window or name of the monitor
+    pub target: String,
+    pub expires_in_secs: u64,
+}
+
+/// `capture.completed`, a capture request was confirmed or refused
+#[derive(Serialize, JsonSchema)]
+pub struct CaptureCompletedPayload {
+    pub request_id: String,
+    pub kind: String,
+    pub target: String,
+    pub allowed: bool,
+    /// The PNG saved, when allowed and captured
+    pub path: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub error: Option<String>,
+}
@@ -0,0 +1,143 @@
+//! JSON Schemas for the WebSocket envelopes, event payloads and DTOs
+//!
+//! Served by `GET /api/schemas` and the `schemas.get` command so the
+//! frontend and external consumers can validate payloads. Each schema is
+//! self-contained: the types it references are inlined under `$defs`.
+
+pub mod envelopes;
+pub mod events;
+
+use schemars::{JsonSchema, SchemaGenerator};
+use serde_json::{Map, Value};
+use std::sync::OnceLock;
+use crate::core::application::{CounterDto, DatabaseStatsDto, SystemInfoDto, TaskInfo, UserDto};
+use crate::infrastructure::event_bus::AppEventType;
+use crate::infrastructure::metrics::MetricsSample;
+use crate::infrastructure::presence::ClientProfile;
+use crate::infrastructure::scheduler::JobRun;
+use crate::infrastructure::serialization::serialization::WsMessage;
+use envelopes::ErrorResponse;
+use events::*;
+
+pub const WS_MESSAGE: &str = "ws_message";
+pub const ERROR_RESPONSE: &str = "error_response";
+
+fn schema_for<T: JsonSchema>() -> Value {
+    SchemaGenerator::default().into_root_schema_for::<T>().to_value()
+}
+
+/// Schema of the payload of `event`
+fn event_schema(event: AppEventType) -> Value {
+    match event {
+        AppEventType::UserLogin | AppEventType::UserLogout => schema_for::<UserSessionPayload>(),
+        AppEventType::DataChanged => schema_for::<DataChangedPayload>(),
+        AppEventType::CounterIncremented => schema_for::<CounterIncrementedPayload>(),
+        AppEventType::CounterChanged => schema_for::<CounterChangedPayload>(),
+        AppEventType::DatabaseOperation => schema_for::<DatabaseOperationPayload>(),
+        AppEventType::DatabaseActivity => schema_for::<BatchSummaryPayload>(),
+        AppEventType::SystemHealthCheck => schema_for::<SystemHealthCheckPayload>(),
+        AppEventType::FrontendConnected | AppEventType::FrontendDisconnected => schema_for::<Map<String, Value>>(),
+        AppEventType::WindowStateChanged => schema_for::<WindowStateChangedPayload>(),
+        AppEventType::ClipboardAccessed => schema_for::<ClipboardAccessedPayload>(),
+        AppEventType::PluginAccessDenied => schema_for::<PluginAccessDeniedPayload>(),
+        AppEventType::SystemMetrics => schema_for::<MetricsSample>(),
+        AppEventType::JobCompleted => schema_for::<JobRun>(),
+        AppEventType::FsChanged => schema_for::<FsChangedPayload>(),
+        AppEventType::ImagesOrganizeProgress => schema_for::<ImagesOrganizeProgressPayload>(),
+        AppEventType::FileTransferProgress => schema_for::<FileTransferProgressPayload>(),
+        AppEventType::FileUploaded => schema_for::<FileUploadedPayload>(),
+        AppEventType::SettingsChanged => schema_for::<SettingsChangedPayload>(),
+        AppEventType::TaskProgress => schema_for::<TaskProgressPayload>(),
+        AppEventType::TaskCompleted | AppEventType::TaskFailed | AppEventType::TaskCancelled => {
+            schema_for::<TaskInfo>()
+        }
+        AppEventType::UpdateAvailable => schema_for::<UpdateAvailablePayload>(),
+        AppEventType::UpdateInstalled => schema_for::<UpdateInstalledPayload>(),
+        AppEventType::FlagsChanged => schema_for::<FlagsChangedPayload>(),
+        AppEventType::ChaosInjected => schema_for::<ChaosInjectedPayload>(),
+        AppEventType::StatePatch => schema_for::<StatePatchPayload>(),
+        AppEventType::CommandAccepted => schema_for::<CommandAcceptedPayload>(),
+        AppEventType::CommandApplied => schema_for::<CommandAppliedPayload>(),
+        AppEventType::CommandRejected => schema_for::<CommandRejectedPayload>(),
+        AppEventType::PresenceJoined | AppEventType::PresenceLeft => schema_for::<ClientProfile>(),
+        AppEventType::CollabUpdate => schema_for::<CollabUpdatePayload>(),
+        AppEventType::HotkeyPressed => schema_for::<HotkeyPressedPayload>(),
+        AppEventType::DocumentExported => schema_for::<DocumentExportedPayload>(),
+        AppEventType::CaptureRequested => schema_for::<CaptureRequestedPayload>(),
+        AppEventType::CaptureCompleted => schema_for::<CaptureCompletedPayload>(),
+    }
+}
+
+/// `{ ws_message, error_response, events: { <event name>: .. }, dtos: { <type name>: .. } }`
+pub fn all() -> &'static Value {
+    static SCHEMAS: OnceLock<Value> = OnceLock::new();
+    SCHEMAS.get_or_init(|| {
+        let events: Map<String, Value> = AppEventType::ALL
+            .iter()
+            .map(|event| (event.to_string(), event_schema(*event)))
+            .collect();
+        let dtos: Map<String, Value> = [
+            ("UserDto", schema_for::<UserDto>()),
+            ("CounterDto", schema_for::<CounterDto>()),
+            ("DatabaseStatsDto", schema_for::<DatabaseStatsDto>()),
+            ("SystemInfoDto", schema_for::<SystemInfoDto>()),
+        ]
+        .into_iter()
+        .map(|(name, schema)| (name.to_string(), schema))
+        .collect();
+        serde_json::json!({
+            WS_MESSAGE: schema_for::<WsMessage>(),
+            ERROR_RESPONSE: schema_for::<ErrorResponse>(),
+            "events": events,
+            "dtos": dtos,
+        })
+    })
+}
+
+/// One schema by name: `ws_message`, `error_response`, an event name or a DTO name
+pub fn get(name: &str) -> Option<&'static Value> {
+    let schemas = all();
+    if name == WS_MESSAGE || name == ERROR_RESPONSE {
+        return schemas.get(name);
+    }
+    schemas["events"].get(name).or_else(|| schemas["dtos"].get(name))
+}
+
+/// `schemas.get`: every schema, or the one named by `name`
+pub fn handle_command(_name: &str, payload: &Value) -> Value {
+    match payload.get("name").and_then(Value::as_str) {
+        None => serde_json::json!({ "success": true, "schemas": all() }),
+        Some(name) => match get(name) {
+            Some(schema) => serde_json::json!({ "success": true, "name": name, "schema": schema }),
+            None => serde_json::json!({ "success": false, "error": format!("Unknown schema: {}", name) }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_event_and_dto_has_a_schema() {
+        let schemas = all();
+        assert_eq!(schemas["events"].as_object().unwrap().len(), AppEventType::ALL.len());
+        assert_eq!(schemas[WS_MESSAGE]["required"], serde_json::json!(["id", "name", "payload", "timestamp", "source"]));
+
+        let changed = get("counter.changed").unwrap();
+        assert_eq!(changed["properties"]["operation"]["$ref"], "#/$defs/CounterChange");
+        assert!(changed["$defs"]["Counter"].is_object());
+        assert!(get("SystemInfoDto").unwrap()["properties"]["cpu_cores"].is_object());
+        assert!(get(ERROR_RESPONSE).unwrap()["$defs"]["RecoveryAction"].is_object());
+        assert!(get("nope").is_none());
+    }
+
+    #[test]
+    fn test_command_response() {
+        let one = handle_command("schemas.get", &serde_json::json!({ "name": "task.completed" }));
+        assert_eq!(one["success"], true);
+        assert_eq!(one["schema"]["title"], "TaskInfo");
+        let unknown = handle_command("schemas.get", &serde_json::json!({ "name": "nope" }));
+        assert_eq!(unknown["success"], false);
+    }
+}
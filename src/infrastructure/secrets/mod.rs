@@ -0,0 +1,184 @@
+//! Secrets kept in the OS keychain instead of plaintext config values
+//!
+//! Values live in the platform store (macOS Keychain, Windows Credential
+//! Manager, the Secret Service on Linux) under the `[secrets] service` name.
+//! Application secrets are stored as `app/<name>`, a plugin's own secrets as
+//! `plugin/<id>/<name>`; plugins reach them only through the permission
+//! checked `SecretsAccess` facade. Config values written as `secret:<name>`
+//! are looked up here, so API tokens and keys need not sit in the file.
+
+use std::sync::OnceLock;
+use thiserror::Error;
+use crate::model::core::AppConfig;
+
+/// Prefix of config values that name an application secret
+pub const SECRET_REF_PREFIX: &str = "secret:";
+
+#[derive(Debug, Error)]
+pub enum SecretsError {
+    #[error("Invalid secret name: {0:?}")]
+    InvalidName(String),
+    #[error("Secret not found: {0}")]
+    NotFound(String),
+    #[error("Keychain error: {0}")]
+    Keychain(String),
+}
+
+/// Where secret values are kept
+pub trait SecretStore: Send + Sync {
+    fn get(&self, account: &str) -> Result<Option<String>, SecretsError>;
+    fn set(&self, account: &str, value: &str) -> Result<(), SecretsError>;
+    /// Whether there was a value to delete
+    fn delete(&self, account: &str) -> Result<bool, SecretsError>;
+}
+
+/// The OS keychain, entries grouped under one service name
+struct KeychainStore {
+    service: String,
+}
+
+impl KeychainStore {
+    fn entry(&self, account: &str) -> Result<keyring::Entry, SecretsError> {
+        keyring::Entry::new(&self.service, account).map_err(|e| SecretsError::Keychain(e.to_string()))
+    }
+}
+
+impl SecretStore for KeychainStore {
+    fn get(&self, account: &str) -> Result<Option<String>, SecretsError> {
+        match self.entry(account)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(SecretsError::Keychain(e.to_string())),
+        }
+    }
+
+    fn set(&self, account: &str, value: &str) -> Result<(), SecretsError> {
+        self.entry(account)?
+            .set_password(value)
+            .map_err(|e| SecretsError::Keychain(e.to_string()))
+    }
+
+    fn delete(&self, account: &str) -> Result<bool, SecretsError> {
+        match self.entry(account)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(SecretsError::Keychain(e.to_string())),
+        }
+    }
+}
+
+/// Names are free text without control characters, like `database:app.db` or `github-token`
+fn check_name(name: &str) -> Result<(), SecretsError> {
+    if name.is_empty() || name.len() > 256 || name.chars().any(char::is_control) {
+        return Err(SecretsError::InvalidName(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Keychain account of plugin `plugin_id`'s secret `name`
+pub fn plugin_account(plugin_id: &str, name: &str) -> Result<String, SecretsError> {
+    check_name(name)?;
+    Ok(format!("plugin/{}/{}", plugin_id, name))
+}
+
+pub struct SecretsManager {
+    store: Box<dyn SecretStore>,
+}
+
+impl SecretsManager {
+    pub fn new(store: Box<dyn SecretStore>) -> Self {
+        Self { store }
+    }
+
+    pub fn global() -> &'static SecretsManager {
+        static SECRETS: OnceLock<SecretsManager> = OnceLock::new();
+        SECRETS.get_or_init(|| {
+            Self::new(Box::new(KeychainStore {
+                service: AppConfig::global().get_secrets_service().to_string(),
+            }))
+        })
+    }
+
+    /// Application secret `name`
+    pub fn get(&self, name: &str) -> Result<Option<String>, SecretsError> {
+        check_name(name)?;
+        self.store.get(&format!("app/{}", name))
+    }
+
+    pub fn set(&self, name: &str, value: &str) -> Result<(), SecretsError> {
+        check_name(name)?;
+        self.store.set(&format!("app/{}", name), value)
+    }
+
+    #[allow(dead_code)]
+    pub fn delete(&self, name: &str) -> Result<bool, SecretsError> {
+        check_name(name)?;
+        self.store.delete(&format!("app/{}", name))
+    }
+
+    /// Secret `name`, storing `generate()` first if there is none yet
+    pub fn get_or_create(&self, name: &str, generate: impl FnOnce() -> String) -> Result<String, SecretsError> {
+        if let Some(value) = self.get(name)? {
+            return Ok(value);
+        }
+        let value = generate();
+        self.set(name, &value)?;
+        Ok(value)
+    }
+
+    /// A config value, looked up when it is a `secret:<name>` reference
+    pub fn resolve(&self, value: &str) -> Result<String, SecretsError> {
+        match value.strip_prefix(SECRET_REF_PREFIX) {
+            Some(name) => self.get(name)?.ok_or_else(|| SecretsError::NotFound(name.to_string())),
+            None => Ok(value.to_string()),
+        }
+    }
+
+    /// Raw access by keychain account, for the plugin facades
+    pub(crate) fn store(&self) -> &dyn SecretStore {
+        self.store.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStore(Mutex<HashMap<String, String>>);
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, account: &str) -> Result<Option<String>, SecretsError> {
+            Ok(self.0.lock().unwrap().get(account).cloned())
+        }
+
+        fn set(&self, account: &str, value: &str) -> Result<(), SecretsError> {
+            self.0.lock().unwrap().insert(account.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, account: &str) -> Result<bool, SecretsError> {
+            Ok(self.0.lock().unwrap().remove(account).is_some())
+        }
+    }
+
+    #[test]
+    fn test_resolves_references_and_namespaces() {
+        let secrets = SecretsManager::new(Box::<MemoryStore>::default());
+        secrets.set("api-token", "t0k3n").unwrap();
+        assert_eq!(secrets.store().get("app/api-token").unwrap().as_deref(), Some("t0k3n"));
+
+        assert_eq!(secrets.resolve("secret:api-token").unwrap(), "t0k3n");
+        assert_eq!(secrets.resolve("plain value").unwrap(), "plain value");
+        assert!(matches!(secrets.resolve("secret:missing"), Err(SecretsError::NotFound(_))));
+        assert!(matches!(secrets.set("", "x"), Err(SecretsError::InvalidName(_))));
+
+        assert_eq!(secrets.get_or_create("generated", || "first".to_string()).unwrap(), "first");
+        assert_eq!(secrets.get_or_create("generated", || "second".to_string()).unwrap(), "first");
+        assert!(secrets.delete("generated").unwrap());
+        assert!(!secrets.delete("generated").unwrap());
+        assert_eq!(plugin_account("notes", "sync-token").unwrap(), "plugin/notes/sync-token");
+    }
+}
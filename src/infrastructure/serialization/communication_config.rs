@@ -255,6 +255,11 @@ impl CommunicationConfig {
         #[cfg(not(feature = "protobuf"))]
         info!("║ ✗ Protobuf      │ Disabled │ Add 'protobuf' feature to enable                ║");
         
+        #[cfg(feature = "grpc")]
+        info!("║ ✓ gRPC          │ Enabled  │ tonic service from proto/app.proto              ║");
+        #[cfg(not(feature = "grpc"))]
+        info!("║ ✗ gRPC          │ Disabled │ Add 'grpc' feature to enable                    ║");
+        
         info!("╠═════════════════════════════════════════════════════════════════════════════╣");
         info!("║ Build with: cargo build --features 'all-formats'                            ║");
         info!("║ Or: cargo build --features 'msgpack cbor'                                   ║");
@@ -1,8 +1,12 @@
-/// Serialization module supporting multiple formats
-/// Provides unified interface for JSON, MessagePack, CBOR, and Protobuf
+//! Serialization module supporting multiple formats
+//! Provides unified interface for JSON, MessagePack, CBOR, and Protobuf
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::time::Instant;
+use utoipa::ToSchema;
+use crate::core::domain::clock;
 #[allow(unused_imports)]
 use tracing::debug;
 
@@ -55,7 +59,7 @@ impl SerializationFormat {
 }
 
 /// WebSocket message envelope for all serialization formats
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WsMessage {
     pub id: String,
     pub name: String,
@@ -64,20 +68,21 @@ pub struct WsMessage {
     pub source: String,
     #[serde(default)]
     pub format: Option<String>,
+    /// Set by the client to make a command a mutation, acknowledged with `command.accepted`
+    #[serde(default)]
+    pub mutation_id: Option<String>,
 }
 
 impl WsMessage {
     pub fn new(name: &str, payload: Value, source: &str) -> Self {
         Self {
-            id: uuid::Uuid::new_v4().to_string(),
+            id: clock::new_id(),
             name: name.to_string(),
             payload,
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64,
+            timestamp: clock::now_millis(),
             source: source.to_string(),
             format: None,
+            mutation_id: None,
         }
     }
 
@@ -91,12 +96,10 @@ impl WsMessage {
             id: id.to_string(),
             name: name.to_string(),
             payload,
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64,
+            timestamp: clock::now_millis(),
             source: "backend".to_string(),
             format: None,
+            mutation_id: None,
         }
     }
 }
@@ -230,17 +233,65 @@ impl SerializationEngine {
             msgpack_size,
             cbor_size,
             protobuf_size: 0, // Would need actual implementation
+            ..FormatComparison::default()
+        }
+    }
+
+    /// Sizes plus the mean time to encode `message` over `iterations` runs
+    pub fn measure_formats(message: &WsMessage, iterations: u32) -> FormatComparison {
+        let iterations = iterations.max(1);
+        let mean_micros = |format: SerializationFormat| {
+            let engine = SerializationEngine::new(format);
+            if engine.serialize(message).is_err() {
+                return 0.0;
+            }
+            let started = Instant::now();
+            for _ in 0..iterations {
+                std::hint::black_box(engine.serialize(std::hint::black_box(message)).ok());
+            }
+            let micros = started.elapsed().as_secs_f64() * 1_000_000.0 / iterations as f64;
+            (micros * 100.0).round() / 100.0
+        };
+        FormatComparison {
+            iterations,
+            json_encode_us: mean_micros(SerializationFormat::Json),
+            msgpack_encode_us: mean_micros(SerializationFormat::MessagePack),
+            cbor_encode_us: mean_micros(SerializationFormat::Cbor),
+            ..Self::get_format_comparison(message)
         }
     }
 }
 
-/// Format size comparison for analysis
-#[derive(Debug, Clone)]
+/// A `get_users` response with `count` users, the payload the formats are compared on
+pub fn sample_users_message(count: usize) -> WsMessage {
+    let users: Vec<Value> = (1..=count)
+        .map(|id| {
+            serde_json::json!({
+                "id": id,
+                "name": format!("User {}", id),
+                "email": format!("user{}@example.com", id),
+                "role": if id % 5 == 0 { "admin" } else { "user" },
+                "status": "active",
+                "created_at": "2024-01-01T12:00:00Z",
+            })
+        })
+        .collect();
+    WsMessage::new("get_users", serde_json::json!({ "success": true, "data": users, "count": count }), "backend")
+}
+
+/// Format size and encode time comparison for analysis.
+/// Sizes are 0 and times 0.0 for formats the build does not include.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct FormatComparison {
     pub json_size: usize,
     pub msgpack_size: usize,
     pub cbor_size: usize,
     pub protobuf_size: usize,
+    /// Encodes averaged into the times below; 0 when only sizes were taken
+    pub iterations: u32,
+    pub json_encode_us: f64,
+    pub msgpack_encode_us: f64,
+    pub cbor_encode_us: f64,
 }
 
 impl FormatComparison {
@@ -267,6 +318,18 @@ impl FormatComparison {
                    self.cbor_size, ratio, self.json_size as f64 / self.cbor_size as f64);
         }
         
+        if self.iterations > 0 {
+            debug!("╠═══════════════╧══════════════╧════════════════════════╣");
+            debug!("║ Encode time, mean of {:<8} runs                       ║", self.iterations);
+            debug!("║ JSON          │ {:>10.2} µs                           ║", self.json_encode_us);
+            if self.msgpack_size > 0 {
+                debug!("║ MessagePack   │ {:>10.2} µs                           ║", self.msgpack_encode_us);
+            }
+            if self.cbor_size > 0 {
+                debug!("║ CBOR          │ {:>10.2} µs                           ║", self.cbor_encode_us);
+            }
+        }
+        
         debug!("╚════════════════════════════════════════════════════════╝");
     }
 }
@@ -339,6 +402,7 @@ pub fn display_serialization_details() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::any;
     use serde_json::json;
 
     #[test]
@@ -362,6 +426,47 @@ mod tests {
         assert_eq!(SerializationFormat::from_str("invalid"), None);
     }
 
+    #[test]
+    #[cfg(feature = "msgpack")]
+    fn test_measured_comparison() {
+        let comparison = SerializationEngine::measure_formats(&sample_users_message(20), 5);
+        assert_eq!(comparison.iterations, 5);
+        assert!(comparison.msgpack_size > 0 && comparison.msgpack_size < comparison.json_size);
+        assert!(comparison.json_encode_us > 0.0 && comparison.msgpack_encode_us > 0.0);
+        assert_eq!(comparison.protobuf_size, 0);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_round_trip_in_each_format(
+            id in "[a-z0-9-]{0,36}",
+            name in "[a-z_.]{1,32}",
+            text in any::<String>(),
+            count in any::<i64>(),
+            flags in proptest::collection::vec(any::<bool>(), 0..8),
+            timestamp in any::<u64>(),
+        ) {
+            let message = WsMessage {
+                id,
+                name,
+                payload: json!({ "text": text, "count": count, "flags": flags }),
+                timestamp,
+                source: "frontend".to_string(),
+                format: None,
+                mutation_id: None,
+            };
+            for format in [SerializationFormat::Json, SerializationFormat::MessagePack, SerializationFormat::Cbor] {
+                let engine = SerializationEngine::new(format);
+                let Ok(bytes) = engine.serialize(&message) else { continue };
+                let decoded = engine.deserialize(&bytes).unwrap();
+                proptest::prop_assert_eq!(&decoded.id, &message.id);
+                proptest::prop_assert_eq!(&decoded.name, &message.name);
+                proptest::prop_assert_eq!(&decoded.payload, &message.payload);
+                proptest::prop_assert_eq!(decoded.timestamp, message.timestamp);
+            }
+        }
+    }
+
     #[test]
     fn test_mime_types() {
         assert_eq!(SerializationFormat::Json.mime_type(), "application/json");
@@ -0,0 +1,76 @@
+//! Listener setup for the HTTP and WebSocket servers
+//!
+//! Ports come from `[server]`; when one is taken the next ports are tried,
+//! and the ports actually bound are published to the frontend through
+//! `/config.json` and the generated `webui.js`.
+
+use serde::Serialize;
+use std::io;
+use std::net::TcpListener;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use crate::model::core::AppConfig;
+
+/// Ports in use for this run, as seen by the frontend
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FrontendConfig {
+    pub app_name: String,
+    pub version: String,
+    pub http_port: u16,
+    pub ws_port: u16,
+}
+
+static FRONTEND_CONFIG: OnceLock<FrontendConfig> = OnceLock::new();
+
+/// Bind `host:port`, moving on to the next port up to `attempts` times if it is in use
+pub fn bind_with_fallback(host: &str, port: u16, attempts: u16) -> io::Result<TcpListener> {
+    let mut last_error = None;
+    for candidate in (0..=attempts).filter_map(|offset| port.checked_add(offset)) {
+        match TcpListener::bind((host, candidate)) {
+            Ok(listener) => {
+                if candidate != port {
+                    warn!("Port {} is in use, using {} instead", port, candidate);
+                }
+                return Ok(listener);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => last_error = Some(e),
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| io::Error::new(io::ErrorKind::AddrInUse, format!("No free port from {}", port))))
+}
+
+/// Record the bound ports; only the first call takes effect
+pub fn publish_ports(config: &AppConfig, http_port: u16, ws_port: u16) -> &'static FrontendConfig {
+    let published = FRONTEND_CONFIG.get_or_init(|| FrontendConfig {
+        app_name: config.get_app_name().to_string(),
+        version: config.get_version().to_string(),
+        http_port,
+        ws_port,
+    });
+    info!("Serving HTTP on port {}, WebSocket on port {}", published.http_port, published.ws_port);
+    published
+}
+
+pub fn frontend_config() -> Option<&'static FrontendConfig> {
+    FRONTEND_CONFIG.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_falls_back_to_next_free_port() {
+        let taken = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        match bind_with_fallback("127.0.0.1", port, 20) {
+            Ok(listener) => assert_ne!(listener.local_addr().unwrap().port(), port),
+            // Every following port happened to be busy as well
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::AddrInUse),
+        }
+        assert_eq!(bind_with_fallback("127.0.0.1", port, 0).unwrap_err().kind(), io::ErrorKind::AddrInUse);
+    }
+}
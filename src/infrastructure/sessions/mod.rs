@@ -0,0 +1,230 @@
+//! Sessions of WebSocket clients, kept in the `sessions` table
+//!
+//! A connection gets a session with its first message. `hello` hands a new
+//! session's `resume_token` to the client, which passes it again as
+//! `resume_token` in `hello` after a reconnect and keeps the session while it
+//! has not expired. Only a hash of the token is stored, and the session id,
+//! which `session.list` shows, is not enough to take a session over. Activity slides the expiry forward
+//! by `[sessions] idle_timeout_mins`, never past `max_lifetime_hours` after
+//! creation, and is written back at most once a minute. The session also
+//! keeps the client's locale, see `infrastructure::i18n`. `session.revoke`
+//! deletes a session and closes its connection; like `session.list` it needs
+//! admin rights over WebSocket. The `sessions.cleanup` job removes expired rows.
+
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use serde_json::Value;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+use tracing::{debug, info};
+use crate::infrastructure::auth::{generate_token, token_hash};
+use crate::infrastructure::commands::command_message;
+use crate::infrastructure::database::database;
+use crate::model::core::{AppConfig, Database, UserSession};
+
+/// Activity more recent than this after the last write is not written again
+const REFRESH_INTERVAL_SECS: i64 = 60;
+
+type DbResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+/// A session from `open`
+#[derive(Debug)]
+pub struct OpenedSession {
+    pub session: UserSession,
+    /// Secret that continues the session after a reconnect; only a new session
+    /// has one, a resumed one keeps the token the client already holds
+    pub resume_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionPolicy {
+    pub idle_timeout: Duration,
+    pub max_lifetime: Duration,
+}
+
+impl SessionPolicy {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            idle_timeout: Duration::minutes(config.get_session_idle_timeout_mins() as i64),
+            max_lifetime: Duration::hours(config.get_session_max_lifetime_hours() as i64),
+        }
+    }
+
+    fn expiry(&self, created_at: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+        (now + self.idle_timeout).min(created_at + self.max_lifetime)
+    }
+
+    /// The unexpired session of `resume_token`, refreshed, or a new one
+    pub fn open(&self, db: &Database, resume_token: Option<&str>, client: Option<String>, now: DateTime<Utc>) -> DbResult<OpenedSession> {
+        let resumed = resume_token
+            .map(|token| db.get_session_by_resume_token(&token_hash(token), &now))
+            .transpose()?
+            .flatten();
+        if let Some(mut session) = resumed {
+            if self.refresh(db, &mut session, now)? {
+                debug!("Resumed session {}", session.id);
+                return Ok(OpenedSession { session, resume_token: None });
+            }
+        }
+        let session = UserSession {
+            id: uuid::Uuid::new_v4().to_string(),
+            client,
+            created_at: now,
+            last_seen_at: now,
+            expires_at: self.expiry(now, now),
+            locale: None,
+        };
+        let resume_token = generate_token();
+        db.insert_session(&session, &token_hash(&resume_token))?;
+        debug!("Opened session {}", session.id);
+        Ok(OpenedSession { session, resume_token: Some(resume_token) })
+    }
+
+    /// Record activity at `now`; false when the session has expired or was revoked
+    pub fn touch(&self, db: &Database, session: &mut UserSession, now: DateTime<Utc>) -> DbResult<bool> {
+        if now >= session.expires_at {
+            return Ok(false);
+        }
+        if now - session.last_seen_at < Duration::seconds(REFRESH_INTERVAL_SECS) {
+            return Ok(true);
+        }
+        self.refresh(db, session, now)
+    }
+
+    fn refresh(&self, db: &Database, session: &mut UserSession, now: DateTime<Utc>) -> DbResult<bool> {
+        let expires_at = self.expiry(session.created_at, now);
+        if !db.refresh_session(&session.id, &now, &expires_at)? {
+            return Ok(false);
+        }
+        session.last_seen_at = now;
+        session.expires_at = expires_at;
+        Ok(true)
+    }
+}
+
+pub struct SessionManager {
+    policy: SessionPolicy,
+    /// Ids of revoked sessions, so their connections can close
+    revoked: broadcast::Sender<String>,
+}
+
+impl SessionManager {
+    pub fn global() -> &'static SessionManager {
+        static SESSIONS: OnceLock<SessionManager> = OnceLock::new();
+        SESSIONS.get_or_init(|| SessionManager {
+            policy: SessionPolicy::from_config(AppConfig::global()),
+            revoked: broadcast::channel(16).0,
+        })
+    }
+
+    /// The current time at the precision sessions are stored with
+    fn now() -> DateTime<Utc> {
+        Utc::now().trunc_subsecs(3)
+    }
+
+    pub fn open(&self, resume_token: Option<&str>, client: Option<String>) -> DbResult<OpenedSession> {
+        self.policy.open(&*database()?, resume_token, client, Self::now())
+    }
+
+    pub fn touch(&self, session: &mut UserSession) -> DbResult<bool> {
+        self.policy.touch(&*database()?, session, Self::now())
+    }
+
+    /// Keep `locale` with `session`; `None` goes back to the default locale
+    pub fn set_locale(&self, session: &mut UserSession, locale: Option<String>) -> DbResult<()> {
+        database()?.set_session_locale(&session.id, locale.as_deref())?;
+        session.locale = locale;
+        Ok(())
+    }
+
+    pub fn list(&self) -> DbResult<Vec<UserSession>> {
+        database()?.list_sessions(&Self::now())
+    }
+
+    /// Delete session `id` and close the connections using it
+    pub fn revoke(&self, id: &str) -> DbResult<bool> {
+        let revoked = database()?.delete_session(id)?;
+        if revoked {
+            info!("Revoked session {}", id);
+            // No receivers just means no connection uses it right now
+            let _ = self.revoked.send(id.to_string());
+        }
+        Ok(revoked)
+    }
+
+    pub fn subscribe_revoked(&self) -> broadcast::Receiver<String> {
+        self.revoked.subscribe()
+    }
+
+    /// Delete expired sessions; returns how many
+    pub fn cleanup(&self) -> DbResult<usize> {
+        database()?.delete_expired_sessions(&Self::now())
+    }
+}
+
+pub fn handle_command(name: &str, payload: &Value) -> Value {
+    let sessions = SessionManager::global();
+    let result = match name {
+        "session.list" => sessions.list().map(|list| serde_json::json!({ "success": true, "sessions": list })),
+        "session.revoke" => {
+            let id = payload.get("id").and_then(Value::as_str).unwrap_or_default();
+            sessions.revoke(id).map(|revoked| match revoked {
+                true => serde_json::json!({ "success": true }),
+                false => serde_json::json!({ "success": false, "error": format!("Unknown session: {}", id) }),
+            })
+        }
+        other => Err(format!("Unknown command: {}", other).into()),
+    };
+    command_message(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliding_expiry_and_resume() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        let policy = SessionPolicy { idle_timeout: Duration::minutes(30), max_lifetime: Duration::hours(2) };
+        // Stored timestamps keep milliseconds
+        let start = Utc::now().trunc_subsecs(3);
+
+        let opened = policy.open(&db, None, Some("127.0.0.1".to_string()), start).unwrap();
+        let (mut session, resume_token) = (opened.session, opened.resume_token.unwrap());
+        assert_eq!(session.expires_at, start + Duration::minutes(30));
+
+        // Activity within the refresh interval is not written back
+        assert!(policy.touch(&db, &mut session, start + Duration::seconds(10)).unwrap());
+        assert_eq!(session.last_seen_at, start);
+
+        let later = start + Duration::minutes(20);
+        assert!(policy.touch(&db, &mut session, later).unwrap());
+        assert_eq!(session.expires_at, later + Duration::minutes(30));
+
+        // The id alone does not resume a session
+        let guessed = policy.open(&db, Some(&session.id), None, start + Duration::minutes(40)).unwrap();
+        assert_ne!(guessed.session.id, session.id);
+        assert!(db.delete_session(&guessed.session.id).unwrap());
+
+        // Resuming keeps the id and the token; the maximum lifetime caps the sliding expiry
+        let opened = policy.open(&db, Some(&resume_token), None, start + Duration::minutes(45)).unwrap();
+        assert!(opened.resume_token.is_none());
+        let mut resumed = opened.session;
+        assert_eq!(resumed.id, session.id);
+        assert!(policy.touch(&db, &mut resumed, start + Duration::minutes(70)).unwrap());
+        assert!(policy.touch(&db, &mut resumed, start + Duration::minutes(95)).unwrap());
+        assert_eq!(resumed.expires_at, start + Duration::hours(2));
+        assert!(!policy.touch(&db, &mut resumed, start + Duration::hours(3)).unwrap());
+
+        // An expired session is replaced by a new one and removed by cleanup
+        let replaced = policy.open(&db, Some(&resume_token), None, start + Duration::hours(3)).unwrap().session;
+        assert_ne!(replaced.id, session.id);
+        assert_eq!(db.delete_expired_sessions(&(start + Duration::hours(3))).unwrap(), 1);
+        assert_eq!(db.list_sessions(&(start + Duration::hours(3))).unwrap(), vec![replaced.clone()]);
+
+        // A revoked session cannot be refreshed
+        assert!(db.delete_session(&replaced.id).unwrap());
+        let mut revoked = replaced;
+        assert!(!policy.touch(&db, &mut revoked, start + Duration::hours(4)).unwrap());
+    }
+}
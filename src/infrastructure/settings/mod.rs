@@ -0,0 +1,231 @@
+//! Namespaced preferences and plugin configuration, kept in the `settings` table
+//!
+//! A value is any JSON under a `namespace` and `key`, either global or for
+//! one `user_id`. Reading for a user falls back to the global value of each
+//! key, so a default set once can be overridden per user; setting `null`
+//! removes a value. Every change is emitted as `settings.changed`, which a
+//! WebSocket connection receives only for the namespaces it has
+//! `settings.watch`ed. Plugins keep their configuration in the
+//! `plugin.<id>` namespace through `context.settings()`.
+
+use chrono::{SubsecRound, Utc};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use tracing::error;
+use crate::core::domain::{DomainError, DomainResult, Setting, SettingsRepository, UserRepository};
+use crate::infrastructure::commands::command_message;
+use crate::infrastructure::database::{database, user_repository, SqliteSettingsRepository};
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+
+/// Largest value accepted, as JSON text
+const MAX_VALUE_BYTES: usize = 64 * 1024;
+
+pub(crate) fn repository() -> DomainResult<SqliteSettingsRepository> {
+    Ok(SqliteSettingsRepository::new(database()?))
+}
+
+/// Fail with `NotFound` unless user `id` exists
+pub(crate) async fn check_user(id: i64) -> DomainResult<()> {
+    if user_repository()?.get_by_id(id).await?.is_none() {
+        return Err(DomainError::NotFound(format!("User {}", id)));
+    }
+    Ok(())
+}
+
+/// Namespace of plugin `plugin_id`'s own settings
+pub fn plugin_namespace(plugin_id: &str) -> String {
+    format!("plugin.{}", plugin_id)
+}
+
+/// The value of `key` for `user_id`, else the global one
+pub async fn get(repo: &impl SettingsRepository, namespace: &str, key: &str, user_id: Option<i64>) -> DomainResult<Option<Setting>> {
+    Setting::validate_name("namespace", namespace)?;
+    Setting::validate_name("key", key)?;
+    if user_id.is_some() {
+        if let Some(setting) = repo.get(namespace, key, user_id).await? {
+            return Ok(Some(setting));
+        }
+    }
+    repo.get(namespace, key, None).await
+}
+
+/// Every key of `namespace` with its value for `user_id`, else the global one
+pub async fn values(repo: &impl SettingsRepository, namespace: &str, user_id: Option<i64>) -> DomainResult<Map<String, Value>> {
+    Setting::validate_name("namespace", namespace)?;
+    let mut values: Map<String, Value> = repo
+        .list(namespace, None)
+        .await?
+        .into_iter()
+        .map(|setting| (setting.key, setting.value))
+        .collect();
+    if user_id.is_some() {
+        values.extend(repo.list(namespace, user_id).await?.into_iter().map(|setting| (setting.key, setting.value)));
+    }
+    Ok(values)
+}
+
+/// Store `value` under `key`, or remove it for `null`; emits `settings.changed` when something changed
+pub async fn set(
+    repo: &impl SettingsRepository,
+    namespace: &str,
+    key: &str,
+    value: Value,
+    user_id: Option<i64>,
+) -> DomainResult<Option<Setting>> {
+    Setting::validate_name("namespace", namespace)?;
+    Setting::validate_name("key", key)?;
+    if value.is_null() {
+        if repo.delete(namespace, key, user_id).await? {
+            broadcast(namespace, key, &Value::Null, user_id).await;
+        }
+        return Ok(None);
+    }
+    if value.to_string().len() > MAX_VALUE_BYTES {
+        return Err(DomainError::ValidationError(format!(
+            "Setting values are limited to {} KiB of JSON",
+            MAX_VALUE_BYTES / 1024
+        )));
+    }
+
+    let setting = repo
+        .save(Setting {
+            namespace: namespace.to_string(),
+            key: key.to_string(),
+            value,
+            user_id,
+            // Stored timestamps keep milliseconds
+            updated_at: Utc::now().trunc_subsecs(3),
+        })
+        .await?;
+    broadcast(namespace, key, &setting.value, user_id).await;
+    Ok(Some(setting))
+}
+
+async fn broadcast(namespace: &str, key: &str, value: &Value, user_id: Option<i64>) {
+    if let Err(e) = EventBus::global()
+        .emit_simple(
+            &AppEventType::SettingsChanged.to_string(),
+            serde_json::json!({ "namespace": namespace, "key": key, "value": value, "user_id": user_id }),
+        )
+        .await
+    {
+        error!("Failed to emit settings changed event: {}", e);
+    }
+}
+
+/// The namespaces one WebSocket connection watches
+#[derive(Debug, Default)]
+pub struct Watches(HashSet<String>);
+
+impl Watches {
+    pub fn watch(&mut self, namespace: &str) {
+        self.0.insert(namespace.to_string());
+    }
+
+    pub fn unwatch(&mut self, namespace: &str) -> bool {
+        self.0.remove(namespace)
+    }
+
+    /// Whether an event should reach the connection: any event but `settings.changed`
+    /// of a namespace it does not watch
+    pub fn forwards(&self, name: &str, payload: &Value) -> bool {
+        name != AppEventType::SettingsChanged.to_string()
+            || payload["namespace"].as_str().is_some_and(|namespace| self.0.contains(namespace))
+    }
+}
+
+fn text<'a>(payload: &'a Value, field: &str) -> &'a str {
+    payload.get(field).and_then(Value::as_str).unwrap_or_default()
+}
+
+/// `settings.watch`/`settings.unwatch` for the connection owning `watches`
+pub async fn handle_watch(name: &str, payload: &Value, watches: &mut Watches) -> Value {
+    let namespace = text(payload, "namespace");
+    if name == "settings.unwatch" {
+        return serde_json::json!({ "success": true, "watching": watches.unwatch(namespace) });
+    }
+    let user_id = payload.get("user_id").and_then(Value::as_i64);
+    let result = match repository() {
+        Ok(repo) => values(&repo, namespace, user_id).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(values) => {
+            watches.watch(namespace);
+            serde_json::json!({ "success": true, "values": values })
+        }
+        Err(e) => serde_json::json!({ "success": false, "error": e.to_string() }),
+    }
+}
+
+async fn run(name: &str, payload: &Value) -> DomainResult<Value> {
+    let repo = repository()?;
+    let namespace = text(payload, "namespace");
+    let user_id = payload.get("user_id").and_then(Value::as_i64);
+    match name {
+        "settings.get" => match payload.get("key").and_then(Value::as_str) {
+            Some(key) => {
+                let setting = get(&repo, namespace, key, user_id).await?;
+                Ok(serde_json::json!({ "success": true, "value": setting.as_ref().map(|s| &s.value), "setting": setting }))
+            }
+            None => Ok(serde_json::json!({ "success": true, "values": values(&repo, namespace, user_id).await? })),
+        },
+        "settings.set" => {
+            if let Some(id) = user_id {
+                check_user(id).await?;
+            }
+            let value = payload.get("value").cloned().unwrap_or(Value::Null);
+            let setting = set(&repo, namespace, text(payload, "key"), value, user_id).await?;
+            Ok(serde_json::json!({ "success": true, "setting": setting }))
+        }
+        other => Ok(serde_json::json!({ "success": false, "error": format!("Unknown command: {}", other) })),
+    }
+}
+
+pub async fn handle_command(name: &str, payload: &Value) -> Value {
+    command_message(run(name, payload).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::core::Database;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_user_values_fall_back_to_global() {
+        let db = Database::new(":memory:").unwrap();
+        db.init().unwrap();
+        let repo = SqliteSettingsRepository::new(Arc::new(db));
+
+        set(&repo, "ui", "theme", json!("light"), None).await.unwrap();
+        set(&repo, "ui", "density", json!("comfortable"), None).await.unwrap();
+        set(&repo, "ui", "theme", json!("dark"), Some(2)).await.unwrap();
+
+        assert_eq!(get(&repo, "ui", "theme", Some(2)).await.unwrap().unwrap().value, json!("dark"));
+        assert_eq!(get(&repo, "ui", "theme", Some(3)).await.unwrap().unwrap().value, json!("light"));
+        assert_eq!(values(&repo, "ui", Some(2)).await.unwrap(), json!({ "density": "comfortable", "theme": "dark" }).as_object().cloned().unwrap());
+
+        // Removing the user's value uncovers the global one again
+        assert!(set(&repo, "ui", "theme", Value::Null, Some(2)).await.unwrap().is_none());
+        assert_eq!(get(&repo, "ui", "theme", Some(2)).await.unwrap().unwrap().value, json!("light"));
+
+        assert!(matches!(set(&repo, "ui/x", "theme", json!(1), None).await, Err(DomainError::ValidationError(_))));
+        let huge = Value::String("x".repeat(MAX_VALUE_BYTES));
+        assert!(matches!(set(&repo, "ui", "theme", huge, None).await, Err(DomainError::ValidationError(_))));
+    }
+
+    #[test]
+    fn test_watches_filter_settings_events() {
+        let mut watches = Watches::default();
+        let changed = AppEventType::SettingsChanged.to_string();
+        assert!(!watches.forwards(&changed, &json!({ "namespace": "ui" })));
+        assert!(watches.forwards("counter.changed", &json!({})));
+        watches.watch("ui");
+        assert!(watches.forwards(&changed, &json!({ "namespace": "ui" })));
+        assert!(!watches.forwards(&changed, &json!({ "namespace": "plugin.notes" })));
+        assert!(watches.unwatch("ui"));
+        assert!(!watches.forwards(&changed, &json!({ "namespace": "ui" })));
+    }
+}
@@ -0,0 +1,252 @@
+//! Shell integration - open URLs/paths with the OS default handler
+//!
+//! Every request is validated against the `[shell]` allowlist in the
+//! configuration so the frontend cannot launch arbitrary programs.
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{info, warn};
+use crate::model::core::AppConfig;
+
+/// File extensions that would execute code when opened on some platform
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "exe", "bat", "cmd", "com", "msi", "ps1", "vbs", "js", "jse", "wsf", "scr", "lnk",
+    "sh", "bash", "command", "app", "desktop", "appimage", "run", "jar",
+];
+
+#[derive(Debug, Error)]
+pub enum ShellError {
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("URL scheme '{0}' is not allowed")]
+    SchemeNotAllowed(String),
+    #[error("Path does not exist: {0}")]
+    PathNotFound(String),
+    #[error("Path is outside the allowed locations: {0}")]
+    PathNotAllowed(String),
+    #[error("Refusing to open executable file: {0}")]
+    ExecutableRejected(String),
+    #[error("Failed to open: {0}")]
+    OpenFailed(String),
+}
+
+/// Allowlist applied to every shell request
+#[derive(Debug, Clone)]
+pub struct ShellPolicy {
+    url_schemes: Vec<String>,
+    allowed_roots: Vec<PathBuf>,
+}
+
+impl ShellPolicy {
+    pub fn new(url_schemes: Vec<String>, allowed_roots: Vec<PathBuf>) -> Self {
+        Self {
+            url_schemes: url_schemes.into_iter().map(|s| s.to_lowercase()).collect(),
+            // Roots that do not exist can never contain a valid path
+            allowed_roots: allowed_roots
+                .into_iter()
+                .filter_map(|root| root.canonicalize().ok())
+                .collect(),
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        let roots = config
+            .get_shell_allowed_paths()
+            .iter()
+            .map(|p| expand_home(p))
+            .collect();
+        Self::new(config.get_shell_allowed_url_schemes(), roots)
+    }
+
+    /// Check that `url` is well-formed and uses an allowed scheme
+    pub fn validate_url(&self, url: &str) -> Result<(), ShellError> {
+        if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(ShellError::InvalidUrl(url.to_string()));
+        }
+
+        let scheme = match url.split_once(':') {
+            Some((scheme, rest)) if !scheme.is_empty() && !rest.is_empty() => scheme,
+            _ => return Err(ShellError::InvalidUrl(url.to_string())),
+        };
+
+        if !scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        {
+            return Err(ShellError::InvalidUrl(url.to_string()));
+        }
+
+        if !self.url_schemes.contains(&scheme.to_lowercase()) {
+            return Err(ShellError::SchemeNotAllowed(scheme.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Resolve `path` and check it lies within an allowed root
+    pub fn validate_path(&self, path: &str) -> Result<PathBuf, ShellError> {
+        let canonical = expand_home(path)
+            .canonicalize()
+            .map_err(|_| ShellError::PathNotFound(path.to_string()))?;
+
+        if !self.allowed_roots.iter().any(|root| canonical.starts_with(root)) {
+            return Err(ShellError::PathNotAllowed(canonical.display().to_string()));
+        }
+
+        Ok(canonical)
+    }
+}
+
+/// Expand a leading `~` to the user's home directory
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    match (path.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest.trim_start_matches(['/', '\\'])),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    if path.is_dir() {
+        return false;
+    }
+
+    let by_extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| EXECUTABLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let by_mode = std::fs::metadata(path)
+            .map(|m| m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false);
+        by_extension || by_mode
+    }
+    #[cfg(not(unix))]
+    {
+        by_extension
+    }
+}
+
+/// Open a URL in the default browser / handler
+pub fn open_url(policy: &ShellPolicy, url: &str) -> Result<(), ShellError> {
+    policy.validate_url(url)?;
+    info!("Opening URL: {}", url);
+    opener::open_browser(url).map_err(|e| ShellError::OpenFailed(e.to_string()))
+}
+
+/// Open a file or folder with its default application
+pub fn open_path(policy: &ShellPolicy, path: &str) -> Result<PathBuf, ShellError> {
+    let path = policy.validate_path(path)?;
+    if is_executable(&path) {
+        return Err(ShellError::ExecutableRejected(path.display().to_string()));
+    }
+    info!("Opening path: {}", path.display());
+    opener::open(&path).map_err(|e| ShellError::OpenFailed(e.to_string()))?;
+    Ok(path)
+}
+
+/// Show a file or folder selected in the system file manager
+pub fn reveal(policy: &ShellPolicy, path: &str) -> Result<PathBuf, ShellError> {
+    let path = policy.validate_path(path)?;
+    info!("Revealing path: {}", path.display());
+    opener::reveal(&path).map_err(|e| ShellError::OpenFailed(e.to_string()))?;
+    Ok(path)
+}
+
+/// Open the application's working directory (where the database and logs live)
+pub fn open_app_folder() -> Result<PathBuf, ShellError> {
+    let dir = std::env::current_dir().map_err(|e| ShellError::OpenFailed(e.to_string()))?;
+    info!("Opening application folder: {}", dir.display());
+    opener::open(&dir).map_err(|e| ShellError::OpenFailed(e.to_string()))?;
+    Ok(dir)
+}
+
+/// Handle `shell.*` commands from the frontend
+pub fn handle_command(name: &str, payload: &Value) -> Value {
+    let policy = ShellPolicy::from_config(AppConfig::global());
+    let target = payload
+        .get("url")
+        .or_else(|| payload.get("path"))
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+
+    let result = match name {
+        "shell.open_url" => open_url(&policy, target).map(|_| target.to_string()),
+        "shell.open_path" => open_path(&policy, target).map(|p| p.display().to_string()),
+        "shell.reveal" => reveal(&policy, target).map(|p| p.display().to_string()),
+        _ => {
+            return serde_json::json!({
+                "success": false,
+                "error": format!("Unknown shell command: {}", name)
+            });
+        }
+    };
+
+    match result {
+        Ok(opened) => serde_json::json!({ "success": true, "target": opened }),
+        Err(e) => {
+            warn!("{} rejected: {}", name, e);
+            serde_json::json!({ "success": false, "error": e.to_string() })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    fn policy_for(root: &Path) -> ShellPolicy {
+        ShellPolicy::new(vec!["https".to_string()], vec![root.to_path_buf()])
+    }
+
+    #[test]
+    fn test_url_scheme_allowlist() {
+        let policy = policy_for(&std::env::temp_dir());
+        assert!(policy.validate_url("https://example.com").is_ok());
+        assert!(policy.validate_url("HTTPS://example.com").is_ok());
+        assert!(matches!(policy.validate_url("file:///etc/passwd"), Err(ShellError::SchemeNotAllowed(_))));
+        assert!(matches!(policy.validate_url("javascript:alert(1)"), Err(ShellError::SchemeNotAllowed(_))));
+    }
+
+    #[test]
+    fn test_malformed_urls_rejected() {
+        let policy = policy_for(&std::env::temp_dir());
+        assert!(matches!(policy.validate_url("example.com"), Err(ShellError::InvalidUrl(_))));
+        assert!(matches!(policy.validate_url("https://a b"), Err(ShellError::InvalidUrl(_))));
+        assert!(matches!(policy.validate_url("ht tp://x"), Err(ShellError::InvalidUrl(_))));
+    }
+
+    #[test]
+    fn test_path_containment() {
+        let root = TempDir::new("shell-test");
+        let inside = root.join("inside");
+        std::fs::create_dir_all(&inside).unwrap();
+        let policy = policy_for(&inside);
+
+        assert!(policy.validate_path(inside.to_str().unwrap()).is_ok());
+
+        let escaped = inside.join("..");
+        assert!(matches!(
+            policy.validate_path(escaped.to_str().unwrap()),
+            Err(ShellError::PathNotAllowed(_))
+        ));
+        assert!(matches!(
+            policy.validate_path(root.join("missing").to_str().unwrap()),
+            Err(ShellError::PathNotFound(_))
+        ));
+
+    }
+
+    #[test]
+    fn test_executables_detected() {
+        assert!(is_executable(Path::new("installer.exe")));
+        assert!(is_executable(Path::new("script.SH")));
+        assert!(!is_executable(Path::new("photo.jpg")));
+    }
+}
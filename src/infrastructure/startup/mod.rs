@@ -0,0 +1,116 @@
+//! Boot-time profile of the app
+//!
+//! `main` marks the end of each startup step on a `Profiler`; a phase lasts
+//! from the previous mark to its own. Once the window is shown the phases
+//! become the `StartupReport`, which is logged with the slowest phase and
+//! served as `/api/devtools/startup`, so a regression in the template's boot
+//! time shows up in the log of the next run.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::info;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StartupPhase {
+    pub name: String,
+    /// When the phase began, counted from the start of `main`
+    pub offset_ms: f64,
+    pub duration_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StartupReport {
+    pub started_at: DateTime<Utc>,
+    pub total_ms: f64,
+    pub phases: Vec<StartupPhase>,
+}
+
+impl StartupReport {
+    pub fn slowest(&self) -> Option<&StartupPhase> {
+        self.phases.iter().max_by(|a, b| a.duration_ms.total_cmp(&b.duration_ms))
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 10_000.0).round() / 10.0
+}
+
+pub struct Profiler {
+    started_at: DateTime<Utc>,
+    started: Instant,
+    last: Instant,
+    phases: Vec<StartupPhase>,
+}
+
+impl Profiler {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self { started_at: Utc::now(), started: now, last: now, phases: Vec::new() }
+    }
+
+    /// End phase `name`, which began at the previous mark
+    pub fn mark(&mut self, name: &str) {
+        let now = Instant::now();
+        self.phases.push(StartupPhase {
+            name: name.to_string(),
+            offset_ms: millis(self.last - self.started),
+            duration_ms: millis(now - self.last),
+        });
+        self.last = now;
+    }
+
+    pub fn report(&self) -> StartupReport {
+        StartupReport {
+            started_at: self.started_at,
+            total_ms: millis(self.last - self.started),
+            phases: self.phases.clone(),
+        }
+    }
+
+    /// Log the report and keep it for devtools
+    pub fn finish(self) {
+        let report = self.report();
+        let phases: Vec<String> = report
+            .phases
+            .iter()
+            .map(|phase| format!("{} {} ms", phase.name, phase.duration_ms))
+            .collect();
+        info!("Started in {} ms: {}", report.total_ms, phases.join(", "));
+        if let Some(slowest) = report.slowest() {
+            info!("Slowest startup phase: {} ({} ms)", slowest.name, slowest.duration_ms);
+        }
+        let _ = REPORT.set(report);
+    }
+}
+
+static REPORT: OnceLock<StartupReport> = OnceLock::new();
+
+/// The report of this run, once startup has finished
+pub fn report() -> Option<&'static StartupReport> {
+    REPORT.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases_follow_each_other() {
+        let mut profiler = Profiler::start();
+        profiler.mark("config.load");
+        std::thread::sleep(Duration::from_millis(2));
+        profiler.mark("database.open");
+        let report = profiler.report();
+
+        let names: Vec<&str> = report.phases.iter().map(|phase| phase.name.as_str()).collect();
+        assert_eq!(names, ["config.load", "database.open"]);
+        let database = &report.phases[1];
+        assert!(database.duration_ms >= 2.0);
+        assert_eq!(database.offset_ms, report.phases[0].duration_ms);
+        assert!((database.offset_ms + database.duration_ms - report.total_ms).abs() < 0.2);
+        assert_eq!(report.slowest().unwrap().name, "database.open");
+    }
+}
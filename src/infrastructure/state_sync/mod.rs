@@ -0,0 +1,228 @@
+//! Server-side state documents mirrored by the frontend store
+//!
+//! The backend keeps a few named JSON documents: `users` (the user list),
+//! `counters` (every counter) and `system` (the host and the last
+//! `system.metrics` sample). A connection sends `state.subscribe` and gets a
+//! document with its `version`; from then on, every change comes as a
+//! `state.patch` event carrying the next version and an RFC 6902 JSON Patch
+//! from the previous one. A client applies patches in order, ignores any
+//! not newer than what it holds and subscribes again when it sees a gap,
+//! e.g. after missed events.
+//!
+//! A document is only kept once a client asked for it. It is read again on
+//! the events that change it (`data.changed` of the `users` table,
+//! `counter.changed`, `system.metrics`), and a patch goes out only when the
+//! read differs from what was sent before.
+
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex;
+use tracing::{debug, error, warn};
+use crate::core::application::SystemInfoDto;
+use crate::core::domain::{DomainError, DomainResult, SystemInfoRepository};
+use crate::infrastructure::database::database;
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::infrastructure::{counters, system_info};
+use crate::model::core::UserQuery;
+
+pub const USERS: &str = "users";
+pub const COUNTERS: &str = "counters";
+pub const SYSTEM: &str = "system";
+
+/// Every document a client may subscribe to
+pub const DOCUMENTS: &[&str] = &[USERS, COUNTERS, SYSTEM];
+
+#[derive(Debug, Clone, PartialEq)]
+struct Document {
+    version: u64,
+    value: Value,
+}
+
+/// The documents clients asked for, as last sent
+#[derive(Default)]
+pub struct StateStore {
+    // Held across a read so two reads of one document cannot cross
+    documents: Mutex<HashMap<&'static str, Document>>,
+    /// The last `system.metrics` sample
+    metrics: std::sync::Mutex<Option<Value>>,
+}
+
+impl StateStore {
+    pub fn global() -> &'static Self {
+        static STORE: OnceLock<StateStore> = OnceLock::new();
+        STORE.get_or_init(Self::default)
+    }
+
+    /// Version and value of `name`, read first if no client asked for it before
+    pub async fn snapshot(&self, name: &'static str) -> DomainResult<(u64, Value)> {
+        let mut documents = self.documents.lock().await;
+        if let Some(document) = documents.get(name) {
+            return Ok((document.version, document.value.clone()));
+        }
+        let value = self.read(name).await?;
+        documents.insert(name, Document { version: 1, value: value.clone() });
+        Ok((1, value))
+    }
+
+    /// Read `name` again if a client asked for it, and publish what changed
+    pub async fn refresh(&self, name: &'static str) {
+        let mut documents = self.documents.lock().await;
+        if !documents.contains_key(name) {
+            return;
+        }
+        let value = match self.read(name).await {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Could not read the {} state: {}", name, e);
+                return;
+            }
+        };
+        let Some(document) = documents.get_mut(name) else {
+            return;
+        };
+        let Some(payload) = update(name, document, value) else {
+            return;
+        };
+        // Still holding the documents, so patches go out in version order
+        if let Err(e) = EventBus::global().emit_simple(&AppEventType::StatePatch.to_string(), payload).await {
+            error!("Failed to emit the {} state patch: {}", name, e);
+        }
+    }
+
+    async fn read(&self, name: &str) -> DomainResult<Value> {
+        match name {
+            USERS => {
+                let users = database()?.find_users(&UserQuery::default()).map_err(|e| DomainError::RepositoryError(e.to_string()))?;
+                Ok(Value::from(users))
+            }
+            COUNTERS => Ok(serde_json::to_value(counters::list().await?).unwrap_or_default()),
+            SYSTEM => {
+                let info = system_info::system_info_repository().get_current().map(SystemInfoDto::from).ok();
+                let metrics = self.metrics.lock().map(|metrics| metrics.clone()).unwrap_or_default();
+                Ok(serde_json::json!({ "app_version": env!("CARGO_PKG_VERSION"), "info": info, "metrics": metrics }))
+            }
+            other => Err(DomainError::ValidationError(format!("Unknown state document: {}", other))),
+        }
+    }
+}
+
+/// Move `document` to `value`; the `state.patch` payload unless nothing changed
+fn update(name: &str, document: &mut Document, value: Value) -> Option<Value> {
+    let patch = json_patch::diff(&document.value, &value);
+    if patch.0.is_empty() {
+        return None;
+    }
+    document.version += 1;
+    document.value = value;
+    Some(serde_json::json!({ "document": name, "version": document.version, "patch": patch }))
+}
+
+/// The documents of one connection
+#[derive(Debug, Default)]
+pub struct Subscriptions(HashSet<String>);
+
+impl Subscriptions {
+    /// Whether an event should reach the connection: any event but `state.patch`
+    /// of a document it did not subscribe to
+    pub fn forwards(&self, name: &str, payload: &Value) -> bool {
+        name != AppEventType::StatePatch.to_string()
+            || payload["document"].as_str().is_some_and(|document| self.0.contains(document))
+    }
+}
+
+/// `state.subscribe`/`state.unsubscribe` for the connection owning `subscriptions`
+pub async fn handle_subscription(name: &str, payload: &Value, subscriptions: &mut Subscriptions) -> Value {
+    let requested = payload.get("document").and_then(Value::as_str).unwrap_or_default();
+    let Some(document) = DOCUMENTS.iter().copied().find(|document| *document == requested) else {
+        return serde_json::json!({ "success": false, "error": format!("Unknown state document: {}", requested) });
+    };
+    if name == "state.unsubscribe" {
+        return serde_json::json!({ "success": true, "subscribed": subscriptions.0.remove(document) });
+    }
+    match StateStore::global().snapshot(document).await {
+        Ok((version, state)) => {
+            subscriptions.0.insert(document.to_string());
+            serde_json::json!({ "success": true, "document": document, "version": version, "state": state })
+        }
+        Err(e) => serde_json::json!({ "success": false, "error": e.to_string() }),
+    }
+}
+
+/// The document an event changes, if any
+fn changed_by(name: &str, payload: &Value) -> Option<&'static str> {
+    if name == AppEventType::DataChanged.to_string() {
+        (payload["table"] == USERS).then_some(USERS)
+    } else if name == AppEventType::CounterChanged.to_string() {
+        Some(COUNTERS)
+    } else if name == AppEventType::SystemMetrics.to_string() {
+        Some(SYSTEM)
+    } else {
+        None
+    }
+}
+
+/// Keep the documents clients asked for current
+pub fn start() {
+    tokio::spawn(async move {
+        let store = StateStore::global();
+        let mut events = EventBus::global().listen().await;
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    let Some(document) = changed_by(&event.name, &event.payload) else {
+                        continue;
+                    };
+                    if document == SYSTEM {
+                        if let Ok(mut metrics) = store.metrics.lock() {
+                            *metrics = Some((*event.payload).clone());
+                        }
+                    }
+                    debug!("{} changes the {} state", event.name, document);
+                    store.refresh(document).await;
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    // Whatever was missed, reading every document catches up
+                    warn!("State sync missed {} events", missed);
+                    for document in DOCUMENTS {
+                        store.refresh(document).await;
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_patches_take_the_mirror_to_the_new_state() {
+        let mut document = Document { version: 1, value: json!([{ "id": 1, "name": "Ann" }, { "id": 2, "name": "Bob" }]) };
+        let mut mirror = document.value.clone();
+        assert_eq!(update(USERS, &mut document, mirror.clone()), None);
+
+        let users = json!([{ "id": 1, "name": "Anna" }, { "id": 3, "name": "Cy" }]);
+        let payload = update(USERS, &mut document, users.clone()).unwrap();
+        assert_eq!((payload["document"].as_str(), payload["version"].as_u64()), (Some(USERS), Some(2)));
+        let patch: json_patch::Patch = serde_json::from_value(payload["patch"].clone()).unwrap();
+        json_patch::patch(&mut mirror, &patch).unwrap();
+        assert_eq!(mirror, users);
+        assert_eq!(document, Document { version: 2, value: users });
+
+        let mut subscriptions = Subscriptions::default();
+        let patched = AppEventType::StatePatch.to_string();
+        assert!(!subscriptions.forwards(&patched, &payload));
+        assert!(subscriptions.forwards("counter.changed", &json!({})));
+        subscriptions.0.insert(USERS.to_string());
+        assert!(subscriptions.forwards(&patched, &payload));
+        assert!(!subscriptions.forwards(&patched, &json!({ "document": COUNTERS })));
+
+        assert_eq!(changed_by("data.changed", &json!({ "table": "users", "rowid": 1 })), Some(USERS));
+        assert_eq!(changed_by("data.changed", &json!({ "table": "webhooks" })), None);
+    }
+}
@@ -0,0 +1,110 @@
+//! System information provider backed by the `sysinfo` crate
+
+use chrono::Utc;
+use serde_json::Value;
+use std::sync::{Mutex, OnceLock};
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System};
+use tracing::error;
+use crate::core::application::SystemInfoDto;
+use crate::core::domain::{DomainError, DomainResult, SystemInfo, SystemInfoRepository};
+
+/// Reads OS, CPU and memory details from the host
+pub struct SysinfoRepository {
+    system: Mutex<System>,
+}
+
+impl SysinfoRepository {
+    pub fn new() -> Self {
+        let system = System::new_with_specifics(
+            RefreshKind::new()
+                .with_cpu(CpuRefreshKind::new())
+                .with_memory(MemoryRefreshKind::new().with_ram()),
+        );
+        Self {
+            system: Mutex::new(system),
+        }
+    }
+
+    /// Resident memory of this process in bytes, if it can be determined
+    pub fn process_memory_bytes(&self) -> Option<u64> {
+        let pid = sysinfo::get_current_pid().ok()?;
+        let mut system = self.system.lock().ok()?;
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            true,
+            ProcessRefreshKind::new().with_memory(),
+        );
+        system.process(pid).map(|process| process.memory())
+    }
+}
+
+impl Default for SysinfoRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemInfoRepository for SysinfoRepository {
+    fn get_current(&self) -> DomainResult<SystemInfo> {
+        let mut system = self
+            .system
+            .lock()
+            .map_err(|_| DomainError::RepositoryError("System info lock poisoned".to_string()))?;
+        system.refresh_memory_specifics(MemoryRefreshKind::new().with_ram());
+
+        Ok(SystemInfo {
+            platform: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            os_name: System::name(),
+            os_version: System::long_os_version().or_else(System::os_version),
+            kernel_version: System::kernel_version(),
+            hostname: System::host_name(),
+            cpu_model: system
+                .cpus()
+                .first()
+                .map(|cpu| cpu.brand().trim().to_string())
+                .filter(|brand| !brand.is_empty()),
+            cpu_cores: system.cpus().len(),
+            physical_cores: system.physical_core_count(),
+            total_memory_bytes: system.total_memory(),
+            used_memory_bytes: system.used_memory(),
+            uptime_secs: System::uptime(),
+            timestamp: Utc::now(),
+        })
+    }
+}
+
+/// Shared provider; CPU/memory handles are reused between calls
+pub fn system_info_repository() -> &'static SysinfoRepository {
+    static REPOSITORY: OnceLock<SysinfoRepository> = OnceLock::new();
+    REPOSITORY.get_or_init(SysinfoRepository::new)
+}
+
+/// Current system information as a JSON response for the frontend
+pub fn get_system_info_response() -> Value {
+    match system_info_repository().get_current() {
+        Ok(info) => serde_json::json!({
+            "success": true,
+            "data": SystemInfoDto::from(info),
+            "app_version": env!("CARGO_PKG_VERSION")
+        }),
+        Err(e) => {
+            error!("Failed to read system info: {}", e);
+            serde_json::json!({ "success": false, "error": e.to_string() })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_host_details() {
+        let info = SysinfoRepository::new().get_current().unwrap();
+        assert_eq!(info.platform, std::env::consts::OS);
+        assert!(info.cpu_cores > 0);
+        assert!(info.total_memory_bytes >= info.used_memory_bytes);
+        assert!((0.0..=100.0).contains(&info.memory_usage_percent()));
+    }
+}
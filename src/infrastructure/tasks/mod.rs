@@ -0,0 +1,79 @@
+//! Shared task manager wired to the event bus
+
+use serde_json::Value;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::mpsc;
+use tracing::error;
+use crate::core::application::{TaskEventPublisher, TaskManager};
+use crate::infrastructure::event_bus::EventBus;
+
+/// Publishes task lifecycle events on the global event bus
+///
+/// Events go through one forwarding task so progress and completion
+/// arrive in the order they were reported.
+struct EventBusTaskPublisher {
+    sender: OnceLock<mpsc::UnboundedSender<(String, Value)>>,
+}
+
+async fn emit(event: &str, payload: Value) {
+    if let Err(e) = EventBus::global().emit_simple(event, payload).await {
+        error!("Failed to emit {} event: {}", event, e);
+    }
+}
+
+impl TaskEventPublisher for EventBusTaskPublisher {
+    fn publish(&self, event: &str, payload: Value) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            futures::executor::block_on(emit(event, payload));
+            return;
+        };
+
+        let sender = self.sender.get_or_init(|| {
+            let (tx, mut rx) = mpsc::unbounded_channel::<(String, Value)>();
+            runtime.spawn(async move {
+                while let Some((event, payload)) = rx.recv().await {
+                    emit(&event, payload).await;
+                }
+            });
+            tx
+        });
+        let _ = sender.send((event.to_string(), payload));
+    }
+}
+
+pub fn task_manager() -> &'static TaskManager {
+    static MANAGER: OnceLock<TaskManager> = OnceLock::new();
+    MANAGER.get_or_init(|| {
+        TaskManager::new(Arc::new(EventBusTaskPublisher {
+            sender: OnceLock::new(),
+        }))
+    })
+}
+
+/// Handle `task.*` commands from the frontend
+pub fn handle_command(name: &str, payload: &Value) -> Value {
+    let manager = task_manager();
+    let id = payload.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+
+    match name {
+        "task.list" => serde_json::json!({ "success": true, "tasks": manager.list() }),
+        "task.get" => match manager.get(id) {
+            Some(task) => serde_json::json!({ "success": true, "task": task }),
+            None => serde_json::json!({ "success": false, "error": format!("Task not found: {}", id) }),
+        },
+        "task.cancel" => {
+            if manager.cancel(id) {
+                serde_json::json!({ "success": true, "id": id })
+            } else {
+                serde_json::json!({
+                    "success": false,
+                    "error": format!("Task not found or already finished: {}", id)
+                })
+            }
+        }
+        _ => serde_json::json!({
+            "success": false,
+            "error": format!("Unknown task command: {}", name)
+        }),
+    }
+}
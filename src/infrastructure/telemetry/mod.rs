@@ -0,0 +1,435 @@
+//! Error reporting to Sentry or a generic webhook (`telemetry` feature)
+//!
+//! Error-level entries from `error_logger` and panics are turned into a
+//! `TelemetryReport`, scrubbed of personal data and sent from a background
+//! thread. Nothing is sent unless `[telemetry] enabled = true` and, when
+//! `require_consent` is set, the user granted consent with
+//! `telemetry.set_consent`. Error entries are sampled; panics always go out.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use crate::infrastructure::crash_reporter::redact_secrets;
+use crate::infrastructure::logging::error_logger::{ErrorLogEntry, ErrorSeverity};
+use crate::infrastructure::secrets::SecretsManager;
+use crate::model::core::AppConfig;
+
+pub const CONSENT_FILE: &str = "telemetry_consent.json";
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Payload posted to the webhook; the Sentry event is derived from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryReport {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub release: String,
+    pub severity: ErrorSeverity,
+    pub category: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub context: HashMap<String, Value>,
+    pub stack_trace: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ConsentRecord {
+    granted: bool,
+    updated_at: DateTime<Utc>,
+}
+
+/// Counters exposed through `telemetry.status`
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryStatus {
+    pub enabled: bool,
+    pub consent_required: bool,
+    pub consent_granted: bool,
+    pub sample_rate: f64,
+    pub sentry: bool,
+    pub webhook: bool,
+    pub sent: u64,
+    pub dropped: u64,
+    pub failed: u64,
+}
+
+/// Where reports go, parsed once from `[telemetry]`
+#[derive(Debug, Clone, Default)]
+struct Targets {
+    sentry: Option<SentryTarget>,
+    webhook_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SentryTarget {
+    store_url: String,
+    public_key: String,
+}
+
+impl SentryTarget {
+    /// `https://<key>@<host>/<project>` -> `https://<host>/api/<project>/store/`
+    fn from_dsn(dsn: &str) -> Option<Self> {
+        let (scheme, rest) = dsn.split_once("://")?;
+        let (public_key, host_and_path) = rest.split_once('@')?;
+        let public_key = public_key.split(':').next()?;
+        let (host, project) = host_and_path.rsplit_once('/')?;
+        if public_key.is_empty() || host.is_empty() || project.is_empty() {
+            return None;
+        }
+        Some(Self {
+            store_url: format!("{}://{}/api/{}/store/", scheme, host, project),
+            public_key: public_key.to_string(),
+        })
+    }
+
+    fn event(report: &TelemetryReport) -> Value {
+        let level = match report.severity {
+            ErrorSeverity::Debug => "debug",
+            ErrorSeverity::Info => "info",
+            ErrorSeverity::Warning => "warning",
+            ErrorSeverity::Error => "error",
+            ErrorSeverity::Critical | ErrorSeverity::Fatal => "fatal",
+        };
+        serde_json::json!({
+            "event_id": report.id.replace('-', ""),
+            "timestamp": report.timestamp.to_rfc3339(),
+            "level": level,
+            "logger": report.category,
+            "platform": "other",
+            "release": report.release,
+            "message": { "formatted": report.message },
+            "culprit": report.location,
+            "extra": report.context,
+            "tags": { "category": report.category },
+        })
+    }
+}
+
+/// A config value, read from the keychain when it is a `secret:<name>` reference
+fn secret_setting(value: &str, field: &str) -> Option<String> {
+    SecretsManager::global()
+        .resolve(value)
+        .map_err(|e| warn!("Ignoring telemetry.{}: {}", field, e))
+        .ok()
+}
+
+pub struct Telemetry {
+    targets: Targets,
+    sender: Mutex<Option<Sender<TelemetryReport>>>,
+    consent: AtomicBool,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl Telemetry {
+    fn new() -> Self {
+        let config = AppConfig::global();
+        Self {
+            targets: Targets {
+                sentry: config
+                    .get_telemetry_sentry_dsn()
+                    .and_then(|dsn| secret_setting(dsn, "sentry_dsn"))
+                    .and_then(|dsn| {
+                        let target = SentryTarget::from_dsn(&dsn);
+                        if target.is_none() {
+                            warn!("Ignoring malformed telemetry.sentry_dsn");
+                        }
+                        target
+                    }),
+                webhook_url: config
+                    .get_telemetry_webhook_url()
+                    .and_then(|url| secret_setting(url, "webhook_url")),
+            },
+            sender: Mutex::new(None),
+            consent: AtomicBool::new(load_consent(Path::new(CONSENT_FILE))),
+            sent: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        }
+    }
+
+    pub fn global() -> &'static Telemetry {
+        static TELEMETRY: OnceLock<Telemetry> = OnceLock::new();
+        TELEMETRY.get_or_init(Telemetry::new)
+    }
+
+    fn has_target(&self) -> bool {
+        self.targets.sentry.is_some() || self.targets.webhook_url.is_some()
+    }
+
+    /// Enabled, configured and, if required, consented to
+    pub fn is_active(&self) -> bool {
+        let config = AppConfig::global();
+        config.is_telemetry_enabled()
+            && self.has_target()
+            && (!config.is_telemetry_consent_required() || self.consent.load(Ordering::Relaxed))
+    }
+
+    pub fn status(&self) -> TelemetryStatus {
+        let config = AppConfig::global();
+        TelemetryStatus {
+            enabled: config.is_telemetry_enabled(),
+            consent_required: config.is_telemetry_consent_required(),
+            consent_granted: self.consent.load(Ordering::Relaxed),
+            sample_rate: config.get_telemetry_sample_rate(),
+            sentry: self.targets.sentry.is_some(),
+            webhook: self.targets.webhook_url.is_some(),
+            sent: self.sent.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Record the user's choice; it is remembered across restarts
+    pub fn set_consent(&self, granted: bool) -> std::io::Result<()> {
+        self.consent.store(granted, Ordering::Relaxed);
+        let record = ConsentRecord { granted, updated_at: Utc::now() };
+        fs::write(CONSENT_FILE, serde_json::to_vec_pretty(&record)?)?;
+        info!("Telemetry consent {}", if granted { "granted" } else { "revoked" });
+        Ok(())
+    }
+
+    /// Queue an `error_logger` entry; below `Error` severity it is ignored
+    pub fn report_entry(&'static self, entry: &ErrorLogEntry) {
+        if entry.severity < ErrorSeverity::Error || !self.is_active() {
+            return;
+        }
+        if !sampled(AppConfig::global().get_telemetry_sample_rate()) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let report = TelemetryReport {
+            id: entry.id.clone(),
+            timestamp: entry.timestamp,
+            release: env!("CARGO_PKG_VERSION").to_string(),
+            severity: entry.severity,
+            category: entry.category.clone(),
+            message: entry.message.clone(),
+            location: entry.location.clone(),
+            context: entry.context.clone(),
+            stack_trace: entry.stack_trace.clone(),
+        };
+        self.enqueue(scrub(report, &AppConfig::global().get_telemetry_scrub_fields()));
+    }
+
+    /// Send a panic right away; the process may not live long enough for the queue
+    pub fn report_panic(&self, thread: &str, location: &str, message: &str, backtrace: &str) {
+        if !self.is_active() {
+            return;
+        }
+        let report = TelemetryReport {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            release: env!("CARGO_PKG_VERSION").to_string(),
+            severity: ErrorSeverity::Fatal,
+            category: "panic".to_string(),
+            message: message.to_string(),
+            location: Some(location.to_string()),
+            context: HashMap::from([("thread".to_string(), Value::from(thread))]),
+            stack_trace: Some(backtrace.to_string()),
+        };
+        self.send(&scrub(report, &AppConfig::global().get_telemetry_scrub_fields()));
+    }
+
+    fn enqueue(&'static self, report: TelemetryReport) {
+        let mut sender = self.sender.lock().unwrap();
+        let tx = sender.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::channel::<TelemetryReport>();
+            std::thread::Builder::new()
+                .name("telemetry".to_string())
+                .spawn(move || {
+                    for report in rx {
+                        self.send(&report);
+                    }
+                })
+                .expect("failed to spawn telemetry thread");
+            tx
+        });
+        if tx.send(report).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn send(&self, report: &TelemetryReport) {
+        let agent = ureq::AgentBuilder::new().timeout(SEND_TIMEOUT).build();
+        let mut delivered = true;
+
+        if let Some(sentry) = &self.targets.sentry {
+            let auth = format!(
+                "Sentry sentry_version=7, sentry_client=rustwebui-app/{}, sentry_key={}",
+                env!("CARGO_PKG_VERSION"),
+                sentry.public_key
+            );
+            let result = agent
+                .post(&sentry.store_url)
+                .set("X-Sentry-Auth", &auth)
+                .set("Content-Type", "application/json")
+                .send_string(&SentryTarget::event(report).to_string());
+            if let Err(e) = result {
+                delivered = false;
+                debug!("Sentry report {} failed: {}", report.id, e);
+            }
+        }
+
+        if let Some(url) = &self.targets.webhook_url {
+            let body = serde_json::to_string(report).unwrap_or_default();
+            let result = agent
+                .post(url)
+                .set("Content-Type", "application/json")
+                .send_string(&body);
+            if let Err(e) = result {
+                delivered = false;
+                debug!("Telemetry webhook report {} failed: {}", report.id, e);
+            }
+        }
+
+        let counter = if delivered { &self.sent } else { &self.failed };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn load_consent(path: &Path) -> bool {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<ConsentRecord>(&bytes).ok())
+        .is_some_and(|record| record.granted)
+}
+
+/// Random draw against `rate`, using the v4 UUID generator as entropy source
+fn sampled(rate: f64) -> bool {
+    if rate >= 1.0 {
+        return true;
+    }
+    let draw = (uuid::Uuid::new_v4().as_u128() >> 64) as f64 / u64::MAX as f64;
+    draw < rate
+}
+
+/// Replace anything that looks like an email address
+fn scrub_emails(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let looks_like_email = word
+                .split_once('@')
+                .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+            if looks_like_email { "[email]" } else { word }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn scrub_value(value: Value, fields: &[String]) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    if fields.iter().any(|field| field.eq_ignore_ascii_case(&key)) {
+                        (key, Value::String("[scrubbed]".to_string()))
+                    } else {
+                        (key, scrub_value(value, fields))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(|v| scrub_value(v, fields)).collect()),
+        Value::String(text) => Value::String(scrub_emails(&text)),
+        other => other,
+    }
+}
+
+/// Strip PII from a report: configured context keys, secrets, email addresses
+pub fn scrub(mut report: TelemetryReport, fields: &[String]) -> TelemetryReport {
+    let context = Value::Object(report.context.into_iter().collect());
+    report.context = match redact_secrets(scrub_value(context, fields)) {
+        Value::Object(map) => map.into_iter().collect(),
+        _ => HashMap::new(),
+    };
+    report.message = scrub_emails(&report.message);
+    report
+}
+
+/// Handle `telemetry.status` / `telemetry.set_consent` (WebSocket and devtools)
+pub fn handle_command(name: &str, payload: &Value) -> Value {
+    let telemetry = Telemetry::global();
+    match name {
+        "telemetry.status" => serde_json::json!({ "success": true, "telemetry": telemetry.status() }),
+        "telemetry.set_consent" => {
+            let granted = payload.get("granted").and_then(|v| v.as_bool()).unwrap_or(false);
+            match telemetry.set_consent(granted) {
+                Ok(()) => serde_json::json!({ "success": true, "telemetry": telemetry.status() }),
+                Err(e) => serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to save consent: {}", e)
+                }),
+            }
+        }
+        _ => serde_json::json!({
+            "success": false,
+            "error": format!("Unknown telemetry command: {}", name)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(message: &str, context: Value) -> TelemetryReport {
+        TelemetryReport {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            release: "1.0.0".to_string(),
+            severity: ErrorSeverity::Error,
+            category: "database".to_string(),
+            message: message.to_string(),
+            location: None,
+            context: serde_json::from_value(context).unwrap(),
+            stack_trace: None,
+        }
+    }
+
+    #[test]
+    fn test_parses_sentry_dsn() {
+        let target = SentryTarget::from_dsn("https://abc123@o1.ingest.sentry.io/42").unwrap();
+        assert_eq!(target.store_url, "https://o1.ingest.sentry.io/api/42/store/");
+        assert_eq!(target.public_key, "abc123");
+        assert!(SentryTarget::from_dsn("not a dsn").is_none());
+    }
+
+    #[test]
+    fn test_scrubs_pii_fields_and_emails() {
+        let fields = vec!["email".to_string(), "path".to_string()];
+        let scrubbed = scrub(
+            report(
+                "Duplicate user alice@example.com",
+                serde_json::json!({
+                    "email": "alice@example.com",
+                    "path": "/home/alice/photos",
+                    "api_token": "secret",
+                    "note": "contact bob@example.org",
+                    "id": 7
+                }),
+            ),
+            &fields,
+        );
+        assert_eq!(scrubbed.message, "Duplicate user [email]");
+        assert_eq!(scrubbed.context["email"], "[scrubbed]");
+        assert_eq!(scrubbed.context["path"], "[scrubbed]");
+        assert_eq!(scrubbed.context["api_token"], "[redacted]");
+        assert_eq!(scrubbed.context["note"], "contact [email]");
+        assert_eq!(scrubbed.context["id"], 7);
+    }
+
+    #[test]
+    fn test_sampling_bounds() {
+        assert!(sampled(1.0));
+        assert!(!(0..100).any(|_| sampled(0.0)));
+    }
+}
@@ -0,0 +1,467 @@
+//! Update checks and self-update, `[updater]` (`updater` feature)
+//!
+//! The checker reads the latest release from GitHub (`github_repo`) or from a
+//! JSON feed (`feed_url`) at startup and every `check_interval_hours`, and
+//! compares its version with `PACKAGE_VERSION` from `build_config.rs`. A newer
+//! one is announced once with an `update.available` event. `update.install`,
+//! or `auto_install`, downloads this platform's asset, checks its minisign
+//! signature (`<asset>.minisig`) against `public_key` and replaces the
+//! running executable; the new version runs from the next start on.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::{error, info, warn};
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+use crate::infrastructure::commands::command_result;
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::infrastructure::secrets::SecretsManager;
+use crate::model::core::AppConfig;
+
+const USER_AGENT: &str = concat!("rustwebui-app/", env!("CARGO_PKG_VERSION"));
+const HTTP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+/// Largest download accepted, feed or executable
+const MAX_DOWNLOAD: u64 = 512 * 1024 * 1024;
+const SIGNATURE_SUFFIX: &str = ".minisig";
+
+/// A file attached to a release
+#[derive(Debug, Clone, Serialize)]
+pub struct Asset {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Release {
+    pub version: String,
+    pub notes: Option<String>,
+    /// Release page for people
+    pub url: Option<String>,
+    pub published_at: Option<String>,
+    #[serde(skip)]
+    pub assets: Vec<Asset>,
+}
+
+impl Release {
+    fn semver(&self) -> Option<semver::Version> {
+        parse_version(&self.version)
+    }
+
+    /// This platform's download and its signature, by `pattern` in the asset name
+    pub fn platform_asset(&self, pattern: &str) -> Option<(&Asset, Option<&Asset>)> {
+        let binary = self
+            .assets
+            .iter()
+            .find(|asset| asset.name.contains(pattern) && !asset.name.ends_with(SIGNATURE_SUFFIX))?;
+        let signature = self
+            .assets
+            .iter()
+            .find(|asset| asset.name == format!("{}{}", binary.name, SIGNATURE_SUFFIX));
+        Some((binary, signature))
+    }
+}
+
+/// `1.2.0`, also written `v1.2.0`
+fn parse_version(version: &str) -> Option<semver::Version> {
+    semver::Version::parse(version.trim().trim_start_matches('v')).ok()
+}
+
+/// Whether `release` is newer than `current`, leaving out prereleases unless allowed
+pub fn is_newer(release: &Release, current: &str, include_prereleases: bool) -> bool {
+    match (release.semver(), parse_version(current)) {
+        (Some(latest), Some(current)) => (include_prereleases || latest.pre.is_empty()) && latest > current,
+        _ => false,
+    }
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).filter(|text| !text.is_empty()).map(str::to_string)
+}
+
+/// The newest release of a GitHub `releases` response, a list or `releases/latest`
+pub fn parse_github(body: &Value, include_prereleases: bool) -> Option<Release> {
+    let releases = match body.as_array() {
+        Some(releases) => releases.iter().collect(),
+        None => vec![body],
+    };
+    releases
+        .into_iter()
+        .filter(|release| !release["draft"].as_bool().unwrap_or(false))
+        .filter(|release| include_prereleases || !release["prerelease"].as_bool().unwrap_or(false))
+        .filter_map(|release| {
+            let version = string(release, "tag_name")?.trim_start_matches('v').to_string();
+            let assets = release["assets"]
+                .as_array()
+                .map(|assets| {
+                    assets
+                        .iter()
+                        .filter_map(|asset| Some(Asset { name: string(asset, "name")?, url: string(asset, "browser_download_url")? }))
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(Release {
+                version,
+                notes: string(release, "body"),
+                url: string(release, "html_url"),
+                published_at: string(release, "published_at"),
+                assets,
+            })
+        })
+        .filter(|release| release.semver().is_some())
+        .max_by(|a, b| a.semver().cmp(&b.semver()))
+}
+
+/// A custom feed: `version`, optional `notes`, `url` and `published_at`, and `assets` of `name` and `url`
+pub fn parse_feed(body: &Value) -> Result<Release, String> {
+    let version = string(body, "version").ok_or("The feed has no version")?;
+    if parse_version(&version).is_none() {
+        return Err(format!("The feed version {} is not a semantic version", version));
+    }
+    let assets = body["assets"]
+        .as_array()
+        .map(|assets| {
+            assets
+                .iter()
+                .filter_map(|asset| Some(Asset { name: string(asset, "name")?, url: string(asset, "url")? }))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Release {
+        version,
+        notes: string(body, "notes"),
+        url: string(body, "url"),
+        published_at: string(body, "published_at"),
+        assets,
+    })
+}
+
+/// Check `bytes` against a `.minisig` signature made with `public_key`
+pub fn verify_signature(public_key: &str, bytes: &[u8], signature: &str) -> Result<(), String> {
+    let key = minisign_verify::PublicKey::from_base64(public_key.trim())
+        .map_err(|e| format!("Invalid updater public key: {}", e))?;
+    let signature = minisign_verify::Signature::decode(signature).map_err(|e| format!("Invalid signature: {}", e))?;
+    key.verify(bytes, &signature, false)
+        .map_err(|e| format!("The download does not match its signature: {}", e))
+}
+
+enum Source {
+    GitHub(String),
+    Feed(String),
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateStatus {
+    pub current_version: String,
+    pub latest: Option<Release>,
+    pub update_available: bool,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// Version installed by this run, active after a restart
+    pub installed: Option<String>,
+}
+
+pub struct Updater {
+    agent: ureq::Agent,
+    source: Source,
+    include_prereleases: bool,
+    asset: String,
+    public_key: Option<String>,
+    status: Mutex<UpdateStatus>,
+    /// Last version `update.available` went out for
+    announced: Mutex<Option<String>>,
+    installing: AsyncMutex<()>,
+}
+
+impl Updater {
+    fn from_config(config: &AppConfig) -> Result<Self, String> {
+        let source = match (config.get_updater_feed_url(), config.get_updater_github_repo()) {
+            (Some(url), _) => Source::Feed(url.to_string()),
+            (None, Some(repo)) => Source::GitHub(repo.to_string()),
+            (None, None) => return Err("[updater] needs github_repo or feed_url".to_string()),
+        };
+        let public_key = match config.get_updater_public_key() {
+            Some(value) => Some(SecretsManager::global().resolve(value).map_err(|e| e.to_string())?),
+            None => {
+                warn!("[updater] has no public_key; updates are announced but not installed");
+                None
+            }
+        };
+        Ok(Self {
+            agent: ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT).user_agent(USER_AGENT).build(),
+            source,
+            include_prereleases: config.is_updater_include_prereleases(),
+            asset: config.get_updater_asset(),
+            public_key,
+            status: Mutex::new(UpdateStatus { current_version: crate::PACKAGE_VERSION.to_string(), ..Default::default() }),
+            announced: Mutex::new(None),
+            installing: AsyncMutex::new(()),
+        })
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>, String> {
+        let request = self.agent.get(url).set("Accept", "application/octet-stream, application/json");
+        let url = url.to_string();
+        tokio::task::spawn_blocking(move || {
+            let response = request.call().map_err(|e| e.to_string())?;
+            let mut bytes = Vec::new();
+            response
+                .into_reader()
+                .take(MAX_DOWNLOAD + 1)
+                .read_to_end(&mut bytes)
+                .map_err(|e| e.to_string())?;
+            if bytes.len() as u64 > MAX_DOWNLOAD {
+                return Err(format!("{} is larger than {} bytes", url, MAX_DOWNLOAD));
+            }
+            Ok(bytes)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+
+    async fn fetch_latest(&self) -> Result<Option<Release>, String> {
+        match &self.source {
+            Source::Feed(url) => {
+                let body: Value = serde_json::from_slice(&self.download(url).await?).map_err(|e| e.to_string())?;
+                parse_feed(&body).map(Some)
+            }
+            Source::GitHub(repo) => {
+                let url = format!("https://api.github.com/repos/{}/releases?per_page=20", repo);
+                let body: Value = serde_json::from_slice(&self.download(&url).await?).map_err(|e| e.to_string())?;
+                Ok(parse_github(&body, self.include_prereleases))
+            }
+        }
+    }
+
+    pub fn status(&self) -> UpdateStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Look for a newer release, announcing it the first time it is seen
+    pub async fn check(&self) -> UpdateStatus {
+        let result = self.fetch_latest().await;
+        let status = {
+            let mut status = self.status.lock().unwrap();
+            status.last_checked = Some(Utc::now());
+            match result {
+                Ok(latest) => {
+                    status.update_available = latest
+                        .as_ref()
+                        .is_some_and(|release| is_newer(release, &status.current_version, self.include_prereleases));
+                    status.latest = latest;
+                    status.last_error = None;
+                }
+                Err(e) => {
+                    warn!("Update check failed: {}", e);
+                    status.last_error = Some(e);
+                }
+            }
+            status.clone()
+        };
+
+        if let Some(release) = status.latest.as_ref().filter(|_| status.update_available) {
+            let first = {
+                let mut announced = self.announced.lock().unwrap();
+                let first = announced.as_deref() != Some(release.version.as_str());
+                *announced = Some(release.version.clone());
+                first
+            };
+            if first {
+                info!("Update available: {} -> {}", status.current_version, release.version);
+                let payload = serde_json::json!({
+                    "current_version": status.current_version,
+                    "version": release.version,
+                    "notes": release.notes,
+                    "url": release.url,
+                    "published_at": release.published_at,
+                    "installable": self.public_key.is_some() && release.platform_asset(&self.asset).is_some(),
+                });
+                if let Err(e) = EventBus::global().emit_simple(&AppEventType::UpdateAvailable.to_string(), payload).await {
+                    error!("Failed to emit update available event: {}", e);
+                }
+            }
+        }
+        status
+    }
+
+    /// Download, verify and install the newer release found by the last check
+    pub async fn install(&self) -> AppResult<String> {
+        let _installing = self
+            .installing
+            .try_lock()
+            .map_err(|_| AppError::new(ErrorCode::InvalidStateTransition, "An update is already being installed"))?;
+        let status = self.status();
+        let release = status
+            .latest
+            .filter(|_| status.update_available)
+            .ok_or_else(|| AppError::new(ErrorCode::InvalidStateTransition, "No newer version is available"))?;
+        if status.installed.as_ref() == Some(&release.version) {
+            return Ok(release.version);
+        }
+        let public_key = self.public_key.as_deref().ok_or_else(|| {
+            AppError::new(ErrorCode::ValidationFailed, "Updates are only installed with [updater] public_key set")
+        })?;
+        let (binary, signature) = release.platform_asset(&self.asset).ok_or_else(|| {
+            AppError::new(ErrorCode::EntityNotFound, format!("Release {} has no asset matching {}", release.version, self.asset))
+        })?;
+        let signature = signature.ok_or_else(|| {
+            AppError::new(ErrorCode::EntityNotFound, format!("{} has no {} signature", binary.name, SIGNATURE_SUFFIX))
+        })?;
+
+        let unavailable = |e: String| AppError::new(ErrorCode::ServiceUnavailable, e);
+        let bytes = self.download(&binary.url).await.map_err(unavailable)?;
+        let signature = self.download(&signature.url).await.map_err(unavailable)?;
+        verify_signature(public_key, &bytes, &String::from_utf8_lossy(&signature))
+            .map_err(|e| AppError::new(ErrorCode::ValidationFailed, e))?;
+
+        let version = release.version.clone();
+        tokio::task::spawn_blocking(move || replace_executable(&bytes, &version))
+            .await
+            .map_err(|e| AppError::new(ErrorCode::CommandFailed, e.to_string()))?
+            .map_err(|e| AppError::new(ErrorCode::CommandFailed, format!("Failed to install the update: {}", e)))?;
+
+        self.status.lock().unwrap().installed = Some(release.version.clone());
+        info!("Installed version {}; it runs from the next start", release.version);
+        let payload = serde_json::json!({ "version": release.version, "restart_required": true });
+        if let Err(e) = EventBus::global().emit_simple(&AppEventType::UpdateInstalled.to_string(), payload).await {
+            error!("Failed to emit update installed event: {}", e);
+        }
+        Ok(release.version)
+    }
+}
+
+/// Swap the running executable for `bytes`, staged as an executable file first
+fn replace_executable(bytes: &[u8], version: &str) -> std::io::Result<()> {
+    let staged = std::env::temp_dir().join(format!("{}-{}-{}", crate::EXECUTABLE_NAME, version, uuid::Uuid::new_v4().simple()));
+    let result = (|| {
+        let mut file = std::fs::File::create(&staged)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+        }
+        self_replace::self_replace(&staged)
+    })();
+    let _ = std::fs::remove_file(&staged);
+    result
+}
+
+static UPDATER: OnceLock<Arc<Updater>> = OnceLock::new();
+
+/// Start checking for updates in the background
+pub fn start(config: &AppConfig) {
+    if !config.is_updater_enabled() {
+        return;
+    }
+    let updater = match Updater::from_config(config) {
+        Ok(updater) => Arc::new(updater),
+        Err(e) => {
+            error!("Update checks are off: {}", e);
+            return;
+        }
+    };
+    let _ = UPDATER.set(updater.clone());
+    let interval = config.get_updater_check_interval_hours();
+    let auto_install = config.is_updater_auto_install();
+
+    tokio::spawn(async move {
+        loop {
+            let status = updater.check().await;
+            if auto_install && status.update_available && status.installed.is_none() && updater.public_key.is_some() {
+                if let Err(e) = updater.install().await {
+                    warn!("Automatic update failed: {}", e.message);
+                }
+            }
+            if interval == 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(interval * 3600)).await;
+        }
+    });
+    info!("Update checks started ({} h)", interval);
+}
+
+async fn run(name: &str) -> AppResult<Value> {
+    let updater = UPDATER
+        .get()
+        .ok_or_else(|| AppError::new(ErrorCode::ServiceUnavailable, "Update checks are disabled ([updater] enabled)"))?;
+    match name {
+        "update.status" => Ok(serde_json::json!({ "success": true, "status": updater.status() })),
+        "update.check" => Ok(serde_json::json!({ "success": true, "status": updater.check().await })),
+        "update.install" => {
+            let version = updater.install().await?;
+            Ok(serde_json::json!({ "success": true, "version": version, "restart_required": true }))
+        }
+        other => Err(AppError::new(ErrorCode::CommandFailed, format!("Unknown command: {}", other))),
+    }
+}
+
+pub async fn handle_command(name: &str, _payload: &Value) -> Value {
+    command_result(run(name).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLIC_KEY: &str = "RWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
+    /// Prehashed minisign signature of `new build`
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQBAgMEBQYHCOhh9EmXCdJ6r1eWT4Ydyhf1QcHEZDc3HdzV1b92shIARrp4+lbDsKWE+N93W/EfhzjdV2zy2txo4qVlbc9VFgo=
+trusted comment: timestamp:1700000000\tfile:rustwebui-app-linux-x86_64
+5srQSwCEVyT9WonpqbpMeVODoBnnpjSelB165Y1M/CWWKLYHIiuBjMQFbWQcKWqNi1JVsD6SrB6wyfBXro3/BA==
+";
+
+    #[test]
+    fn test_signature_is_checked() {
+        assert!(verify_signature(PUBLIC_KEY, b"new build", SIGNATURE).is_ok());
+        assert!(verify_signature(PUBLIC_KEY, b"new build!", SIGNATURE).is_err());
+        assert!(verify_signature("not a key", b"new build", SIGNATURE).is_err());
+    }
+
+    #[test]
+    fn test_github_releases_and_versions() {
+        let body = serde_json::json!([
+            { "tag_name": "v1.3.0-beta.1", "prerelease": true, "assets": [] },
+            { "tag_name": "v2.0.0", "draft": true, "assets": [] },
+            {
+                "tag_name": "v1.2.0",
+                "body": "Fixes",
+                "html_url": "https://github.com/o/r/releases/tag/v1.2.0",
+                "assets": [
+                    { "name": "app-linux-x86_64.minisig", "browser_download_url": "https://dl/sig" },
+                    { "name": "app-linux-x86_64", "browser_download_url": "https://dl/bin" },
+                    { "name": "app-windows-x86_64.exe", "browser_download_url": "https://dl/exe" },
+                ],
+            },
+        ]);
+        let release = parse_github(&body, false).unwrap();
+        assert_eq!((release.version.as_str(), release.notes.as_deref()), ("1.2.0", Some("Fixes")));
+        let (binary, signature) = release.platform_asset("linux-x86_64").unwrap();
+        assert_eq!((binary.url.as_str(), signature.unwrap().url.as_str()), ("https://dl/bin", "https://dl/sig"));
+        assert!(release.platform_asset("windows-x86_64").unwrap().1.is_none());
+        assert!(is_newer(&release, "1.0.0", false));
+        assert!(!is_newer(&release, "1.2.0", false));
+
+        let beta = parse_github(&body, true).unwrap();
+        assert_eq!(beta.version, "1.3.0-beta.1");
+        assert!(!is_newer(&beta, "1.0.0", false));
+        assert!(is_newer(&beta, "1.2.0", true));
+    }
+
+    #[test]
+    fn test_feed() {
+        let release = parse_feed(&serde_json::json!({
+            "version": "1.1.0",
+            "assets": [{ "name": "app-macos-aarch64", "url": "https://dl/mac" }],
+        }))
+        .unwrap();
+        assert_eq!(release.assets.len(), 1);
+        assert!(parse_feed(&serde_json::json!({ "version": "latest" })).is_err());
+        assert!(parse_feed(&serde_json::json!({})).is_err());
+    }
+}
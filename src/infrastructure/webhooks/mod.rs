@@ -0,0 +1,406 @@
+//! Outbound webhooks, `[webhooks]` (`webhooks` feature, on by default)
+//!
+//! `webhooks.register` stores a URL with the event names it wants (`name.*`
+//! patterns allowed) and a secret. Every matching EventBus event is queued in
+//! `webhook_deliveries` for each such webhook and POSTed as JSON by the
+//! dispatcher. Requests are signed: `X-Webhook-Signature` is `sha256=` and the
+//! hex HMAC-SHA256 of `<X-Webhook-Timestamp>.<body>` under the secret. A
+//! non-2xx answer or no answer is retried with backoff until `max_attempts`,
+//! then the delivery is marked failed. `webhooks.deliveries` reads the log.
+
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, KeyInit, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::sync::{Arc, OnceLock, RwLock};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Notify;
+use tracing::{debug, error, info, warn};
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+use crate::infrastructure::commands::command_result;
+use crate::infrastructure::database::database;
+use crate::infrastructure::event_bus::{event_matches, Event, EventBus};
+use crate::infrastructure::file_transfer::hex;
+use crate::model::core::{AppConfig, Database, Webhook, WebhookDelivery, WebhookDeliveryStatus};
+
+/// Deliveries attempted per round
+const BATCH_SIZE: usize = 50;
+/// Deliveries `webhooks.deliveries` returns without a `limit`, and at most
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+static WAKE: Notify = Notify::const_new();
+
+/// `sha256=<hex>` signature of `body` sent at `timestamp`
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("sha256={}", hex(&mac.finalize().into_bytes()))
+}
+
+/// Delay before the next attempt after `attempts` failed ones: 10 s, doubling
+fn retry_delay(attempts: i64) -> Duration {
+    Duration::seconds(10 << (attempts - 1).clamp(0, 10))
+}
+
+/// Sends the signed requests
+#[async_trait::async_trait]
+pub trait WebhookTransport: Send + Sync {
+    /// POST `body` to `url`; the response status, or why there was none
+    async fn post(&self, url: &str, headers: Vec<(&'static str, String)>, body: String) -> Result<u16, String>;
+}
+
+pub struct HttpTransport {
+    agent: ureq::Agent,
+}
+
+impl HttpTransport {
+    pub fn new(timeout: std::time::Duration) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new().timeout(timeout).build(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookTransport for HttpTransport {
+    async fn post(&self, url: &str, headers: Vec<(&'static str, String)>, body: String) -> Result<u16, String> {
+        let mut request = self.agent.post(url).set("Content-Type", "application/json");
+        for (name, value) in &headers {
+            request = request.set(name, value);
+        }
+        tokio::task::spawn_blocking(move || match request.send_string(&body) {
+            Ok(response) => Ok(response.status()),
+            Err(ureq::Error::Status(status, _)) => Ok(status),
+            Err(e) => Err(e.to_string()),
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+}
+
+pub struct Dispatcher {
+    db: Arc<Database>,
+    transport: Arc<dyn WebhookTransport>,
+    max_attempts: i64,
+    /// `webhooks` rows, reloaded after every change
+    webhooks: RwLock<Vec<Webhook>>,
+}
+
+impl Dispatcher {
+    pub fn new(db: Arc<Database>, transport: Arc<dyn WebhookTransport>, max_attempts: u32) -> Self {
+        Self {
+            db,
+            transport,
+            max_attempts: max_attempts.max(1) as i64,
+            webhooks: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn reload(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let webhooks = self.db.list_webhooks()?;
+        *self.webhooks.write().unwrap() = webhooks;
+        Ok(())
+    }
+
+    /// Queue `event` for every webhook that wants it; returns how many
+    pub fn enqueue(&self, event: &Event, now: DateTime<Utc>) -> Result<usize, Box<dyn std::error::Error>> {
+        let targets: Vec<String> = self
+            .webhooks
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|webhook| webhook.events.iter().any(|pattern| event_matches(pattern, &event.name)))
+            .map(|webhook| webhook.id.clone())
+            .collect();
+        for webhook_id in &targets {
+            self.db
+                .enqueue_webhook_delivery(webhook_id, &event.id, &event.name, &event.payload, &now)?;
+        }
+        Ok(targets.len())
+    }
+
+    /// Attempt the deliveries due by `now`; returns how many were delivered
+    pub async fn deliver_due(&self, now: DateTime<Utc>) -> Result<usize, Box<dyn std::error::Error>> {
+        let due = self.db.due_webhook_deliveries(&now, BATCH_SIZE)?;
+        let attempts = due.iter().map(|delivery| self.attempt(delivery));
+        let outcomes = futures::future::join_all(attempts).await;
+
+        let mut delivered = 0;
+        for (delivery, outcome) in due.iter().zip(outcomes) {
+            let (status, error) = match outcome {
+                Ok(status) if (200..300).contains(&status) => (Some(status), None),
+                Ok(status) => (Some(status), Some(format!("HTTP {}", status))),
+                Err(e) => (None, Some(e)),
+            };
+            let attempts = delivery.attempts + 1;
+            let retry_at = (error.is_some() && attempts < self.max_attempts).then(|| Utc::now() + retry_delay(attempts));
+            match &error {
+                None => delivered += 1,
+                Some(e) if retry_at.is_some() => debug!("Webhook delivery {} failed, retrying: {}", delivery.id, e),
+                Some(e) => warn!("Giving up webhook delivery {} after {} attempts: {}", delivery.id, attempts, e),
+            }
+            self.db
+                .record_webhook_attempt(&delivery.id, status, error.as_deref(), retry_at.as_ref(), &Utc::now())?;
+        }
+        Ok(delivered)
+    }
+
+    async fn attempt(&self, delivery: &WebhookDelivery) -> Result<u16, String> {
+        let webhook = self
+            .webhooks
+            .read()
+            .unwrap()
+            .iter()
+            .find(|webhook| webhook.id == delivery.webhook_id)
+            .cloned()
+            .ok_or("The webhook was removed")?;
+        let body = serde_json::json!({
+            "id": delivery.id,
+            "event": delivery.event,
+            "event_id": delivery.event_id,
+            "payload": delivery.payload,
+            "created_at": delivery.created_at,
+        })
+        .to_string();
+        let timestamp = Utc::now().timestamp();
+        let headers = vec![
+            ("X-Webhook-Id", delivery.id.clone()),
+            ("X-Webhook-Event", delivery.event.clone()),
+            ("X-Webhook-Timestamp", timestamp.to_string()),
+            ("X-Webhook-Signature", sign(&webhook.secret, timestamp, &body)),
+        ];
+        self.transport.post(&webhook.url, headers, body).await
+    }
+}
+
+fn dispatcher_slot() -> &'static OnceLock<Dispatcher> {
+    static DISPATCHER: OnceLock<Dispatcher> = OnceLock::new();
+    &DISPATCHER
+}
+
+/// Queue matching events and deliver them in the background
+pub fn start(db: Arc<Database>, config: &AppConfig) {
+    let transport = Arc::new(HttpTransport::new(std::time::Duration::from_secs(config.get_webhooks_timeout_secs())));
+    let dispatcher = dispatcher_slot().get_or_init(|| Dispatcher::new(db, transport, config.get_webhooks_max_attempts()));
+    if let Err(e) = dispatcher.reload() {
+        error!("Failed to load webhooks: {}", e);
+    }
+    let retention = Duration::days(config.get_webhooks_retention_days() as i64);
+
+    tokio::spawn(async move {
+        let mut events = EventBus::global().listen().await;
+        loop {
+            match events.recv().await {
+                Ok(event) => match dispatcher.enqueue(&event, Utc::now()) {
+                    Ok(0) => {}
+                    Ok(_) => WAKE.notify_one(),
+                    Err(e) => error!("Failed to queue {} for webhooks: {}", event.name, e),
+                },
+                Err(RecvError::Lagged(missed)) => warn!("{} events were not checked for webhooks", missed),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut last_cleanup = None;
+        loop {
+            if let Err(e) = dispatcher.deliver_due(Utc::now()).await {
+                error!("Webhook delivery failed: {}", e);
+            }
+            if last_cleanup.is_none_or(|at: DateTime<Utc>| Utc::now() - at > Duration::hours(1)) {
+                match dispatcher.db.delete_webhook_deliveries(&(Utc::now() - retention)) {
+                    Ok(0) => {}
+                    Ok(deleted) => debug!("Deleted {} old webhook deliveries", deleted),
+                    Err(e) => error!("Failed to clean up webhook deliveries: {}", e),
+                }
+                last_cleanup = Some(Utc::now());
+            }
+            tokio::select! {
+                _ = WAKE.notified() => {}
+                _ = tokio::time::sleep(std::time::Duration::from_secs(1)) => {}
+            }
+        }
+    });
+    info!("Webhook dispatcher started");
+}
+
+fn database_error(e: Box<dyn std::error::Error>) -> AppError {
+    AppError::new(ErrorCode::DatabaseError, e.to_string())
+}
+
+/// Let the dispatcher see a registration or removal
+fn reload() {
+    if let Some(dispatcher) = dispatcher_slot().get() {
+        if let Err(e) = dispatcher.reload() {
+            error!("Failed to reload webhooks: {}", e);
+        }
+    }
+}
+
+/// A webhook from a `webhooks.register` payload
+fn new_webhook(payload: &Value) -> AppResult<Webhook> {
+    let invalid = |message: &str| AppError::new(ErrorCode::ValidationFailed, message);
+    let url = payload.get("url").and_then(Value::as_str).unwrap_or_default().trim();
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(invalid("url must be an http:// or https:// URL"));
+    }
+    let events: Vec<String> = payload
+        .get("events")
+        .and_then(Value::as_array)
+        .map(|events| events.iter().filter_map(Value::as_str).map(str::trim).map(str::to_string).collect())
+        .unwrap_or_default();
+    if events.is_empty() || events.iter().any(String::is_empty) {
+        return Err(invalid("events must list event names or name.* patterns"));
+    }
+    let secret = match payload.get("secret").and_then(Value::as_str) {
+        Some(secret) if secret.len() < 16 => return Err(invalid("secret must be at least 16 characters long")),
+        Some(secret) => secret.to_string(),
+        None => format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple()),
+    };
+    Ok(Webhook {
+        id: uuid::Uuid::new_v4().to_string(),
+        url: url.to_string(),
+        events,
+        secret,
+        created_at: Utc::now(),
+    })
+}
+
+fn run(name: &str, payload: &Value) -> AppResult<Value> {
+    let db = database()?;
+    match name {
+        "webhooks.register" => {
+            let webhook = new_webhook(payload)?;
+            db.insert_webhook(&webhook).map_err(database_error)?;
+            reload();
+            info!("Registered webhook {} for {}", webhook.url, webhook.events.join(", "));
+            Ok(serde_json::json!({ "success": true, "webhook": webhook, "secret": webhook.secret }))
+        }
+        "webhooks.list" => Ok(serde_json::json!({ "success": true, "webhooks": db.list_webhooks().map_err(database_error)? })),
+        "webhooks.remove" => {
+            let id = payload.get("id").and_then(Value::as_str).unwrap_or_default();
+            if !db.delete_webhook(id).map_err(database_error)? {
+                return Err(AppError::new(ErrorCode::EntityNotFound, format!("Webhook {} not found", id)));
+            }
+            reload();
+            Ok(serde_json::json!({ "success": true }))
+        }
+        "webhooks.deliveries" => {
+            let status = match payload.get("status").and_then(Value::as_str) {
+                Some(status) => Some(WebhookDeliveryStatus::parse(status).ok_or_else(|| {
+                    AppError::new(ErrorCode::ValidationFailed, "status must be pending, delivered or failed")
+                })?),
+                None => None,
+            };
+            let limit = payload
+                .get("limit")
+                .and_then(Value::as_u64)
+                .map_or(DEFAULT_LIMIT, |limit| (limit as usize).clamp(1, MAX_LIMIT));
+            let webhook_id = payload.get("webhook_id").and_then(Value::as_str);
+            let deliveries = db.list_webhook_deliveries(webhook_id, status, limit).map_err(database_error)?;
+            Ok(serde_json::json!({ "success": true, "deliveries": deliveries }))
+        }
+        other => Err(AppError::new(ErrorCode::CommandFailed, format!("Unknown command: {}", other))),
+    }
+}
+
+pub fn handle_command(name: &str, payload: &Value) -> Value {
+    command_result(run(name, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Headers and body of a request
+    type SentRequest = (Vec<(&'static str, String)>, String);
+
+    /// Answers with the queued statuses and records what it was sent
+    struct TestTransport {
+        statuses: Mutex<Vec<Result<u16, String>>>,
+        requests: Mutex<Vec<SentRequest>>,
+    }
+
+    #[async_trait::async_trait]
+    impl WebhookTransport for TestTransport {
+        async fn post(&self, _url: &str, headers: Vec<(&'static str, String)>, body: String) -> Result<u16, String> {
+            self.requests.lock().unwrap().push((headers, body));
+            self.statuses.lock().unwrap().remove(0)
+        }
+    }
+
+    #[test]
+    fn test_signature() {
+        // printf '1700000000.{}' | openssl dgst -sha256 -hmac 0123456789abcdef
+        assert_eq!(
+            sign("0123456789abcdef", 1_700_000_000, "{}"),
+            "sha256=e4f8e2ecae2295b2ddb2f0b5584c8275e226c0ebe9b3b819e70156bb67122e3e"
+        );
+        assert_eq!(retry_delay(1), Duration::seconds(10));
+        assert_eq!(retry_delay(3), Duration::seconds(40));
+    }
+
+    #[tokio::test]
+    async fn test_deliveries_are_signed_retried_and_logged() {
+        let db = Arc::new(Database::new(":memory:").unwrap());
+        db.init().unwrap();
+        let transport = Arc::new(TestTransport {
+            statuses: Mutex::new(vec![Err("connection refused".to_string()), Ok(500), Ok(204), Ok(410)]),
+            requests: Mutex::new(Vec::new()),
+        });
+        let dispatcher = Dispatcher::new(db.clone(), transport.clone(), 2);
+        let webhook = new_webhook(&serde_json::json!({
+            "url": "https://example.com/hook",
+            "events": ["counter.*"],
+            "secret": "0123456789abcdef",
+        }))
+        .unwrap();
+        db.insert_webhook(&webhook).unwrap();
+        dispatcher.reload().unwrap();
+
+        let now = Utc::now();
+        let changed = Event::new("counter.changed".to_string(), serde_json::json!({ "value": 1 }), "backend".to_string());
+        let metrics = Event::new("system.metrics".to_string(), serde_json::json!({}), "backend".to_string());
+        assert_eq!(dispatcher.enqueue(&changed, now).unwrap(), 1);
+        assert_eq!(dispatcher.enqueue(&metrics, now).unwrap(), 0);
+        assert_eq!(dispatcher.enqueue(&changed, now).unwrap(), 1);
+
+        // Both fail once, then wait for their retry
+        assert_eq!(dispatcher.deliver_due(now).await.unwrap(), 0);
+        assert_eq!(dispatcher.deliver_due(now).await.unwrap(), 0);
+        let later = Utc::now() + Duration::seconds(11);
+        assert_eq!(dispatcher.deliver_due(later).await.unwrap(), 1);
+
+        let log = db.list_webhook_deliveries(Some(&webhook.id), None, 10).unwrap();
+        let outcomes: Vec<_> = log.iter().map(|delivery| (delivery.status, delivery.attempts, delivery.response_status)).collect();
+        assert_eq!(
+            outcomes,
+            vec![(WebhookDeliveryStatus::Failed, 2, Some(410)), (WebhookDeliveryStatus::Delivered, 2, Some(204))]
+        );
+        assert_eq!(log[0].last_error.as_deref(), Some("HTTP 410"));
+
+        // The same delivery id on every attempt, and a signature over the body
+        let requests = transport.requests.lock().unwrap();
+        let header = |index: usize, name: &str| requests[index].0.iter().find(|(key, _)| *key == name).unwrap().1.clone();
+        assert_eq!(header(0, "X-Webhook-Id"), header(2, "X-Webhook-Id"));
+        let timestamp: i64 = header(2, "X-Webhook-Timestamp").parse().unwrap();
+        assert_eq!(header(2, "X-Webhook-Signature"), sign("0123456789abcdef", timestamp, &requests[2].1));
+        assert_eq!(serde_json::from_str::<Value>(&requests[2].1).unwrap()["payload"]["value"], 1);
+
+        assert!(db.delete_webhook(&webhook.id).unwrap());
+        assert!(db.list_webhook_deliveries(None, None, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_registration_is_validated() {
+        let register = |payload: Value| new_webhook(&payload).map_err(|e| e.message);
+        assert!(register(serde_json::json!({ "url": "ftp://example.com", "events": ["*"] })).is_err());
+        assert!(register(serde_json::json!({ "url": "https://example.com", "events": [] })).is_err());
+        assert!(register(serde_json::json!({ "url": "https://example.com", "events": ["*"], "secret": "short" })).is_err());
+        let webhook = register(serde_json::json!({ "url": "https://example.com", "events": ["data.changed"] })).unwrap();
+        assert_eq!(webhook.secret.len(), 64);
+    }
+}
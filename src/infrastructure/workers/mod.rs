@@ -0,0 +1,214 @@
+//! Worker pools for CPU-bound and blocking command work, `[workers]`
+//!
+//! `run(kind, work)` executes `work` on tokio's blocking threads with at most
+//! the kind's limit running at once. Further calls wait for a free slot as
+//! futures, so a burst of heavy commands neither stalls the async reactor and
+//! the WebSocket loops nor piles up blocking threads. Each kind counts what is
+//! running, waiting and done for `/api/devtools/workers`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::Semaphore;
+use utoipa::ToSchema;
+use crate::model::core::AppConfig;
+
+/// `images.organize`
+pub const IMAGES: &str = "images";
+
+/// Checksums and scans of uploads: `file.upload_chunk`, `file.upload_end`
+pub const HASHING: &str = "hashing";
+
+/// Blocking scheduler jobs such as `database.vacuum` and `database.backup`
+pub const JOBS: &str = "jobs";
+
+/// Counters exposed through devtools
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkerPoolStats {
+    pub kind: String,
+    pub limit: usize,
+    pub running: usize,
+    /// Calls waiting for a free slot
+    pub queued: usize,
+    pub peak_queued: usize,
+    pub completed: u64,
+    pub panicked: u64,
+}
+
+struct Pool {
+    limit: usize,
+    slots: Arc<Semaphore>,
+    running: AtomicUsize,
+    queued: AtomicUsize,
+    peak_queued: AtomicUsize,
+    completed: AtomicU64,
+    panicked: AtomicU64,
+}
+
+/// Decrements its gauge when dropped, also when the caller gives up waiting
+struct Gauge<'a>(&'a AtomicUsize);
+
+impl<'a> Gauge<'a> {
+    fn enter(gauge: &'a AtomicUsize) -> (Self, usize) {
+        let now = gauge.fetch_add(1, Ordering::Relaxed) + 1;
+        (Self(gauge), now)
+    }
+}
+
+impl Drop for Gauge<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub struct Workers {
+    default_limit: usize,
+    limits: BTreeMap<String, usize>,
+    pools: Mutex<BTreeMap<String, Arc<Pool>>>,
+}
+
+impl Workers {
+    pub fn new(default_limit: usize, limits: BTreeMap<String, usize>) -> Self {
+        Self { default_limit: default_limit.max(1), limits, pools: Mutex::default() }
+    }
+
+    pub fn global() -> &'static Workers {
+        static WORKERS: OnceLock<Workers> = OnceLock::new();
+        WORKERS.get_or_init(|| {
+            let config = AppConfig::global();
+            Workers::new(config.get_worker_default_limit(), config.get_worker_limits())
+        })
+    }
+
+    fn pool(&self, kind: &str) -> Arc<Pool> {
+        let mut pools = self.pools.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let pool = pools.entry(kind.to_string()).or_insert_with(|| {
+            let limit = self.limits.get(kind).copied().unwrap_or(self.default_limit).max(1);
+            Arc::new(Pool {
+                limit,
+                slots: Arc::new(Semaphore::new(limit)),
+                running: AtomicUsize::new(0),
+                queued: AtomicUsize::new(0),
+                peak_queued: AtomicUsize::new(0),
+                completed: AtomicU64::new(0),
+                panicked: AtomicU64::new(0),
+            })
+        });
+        Arc::clone(pool)
+    }
+
+    /// Run `work` on a blocking thread once the `kind` pool has a free slot.
+    /// The slot is held until `work` returns, even if the caller stops waiting.
+    pub async fn run<T, F>(&self, kind: &str, work: F) -> Result<T, String>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool(kind);
+        let slot = match Arc::clone(&pool.slots).try_acquire_owned() {
+            Ok(slot) => slot,
+            Err(_) => {
+                let (_waiting, queued) = Gauge::enter(&pool.queued);
+                pool.peak_queued.fetch_max(queued, Ordering::Relaxed);
+                Arc::clone(&pool.slots).acquire_owned().await.map_err(|e| e.to_string())?
+            }
+        };
+
+        let worker = Arc::clone(&pool);
+        let result = tokio::task::spawn_blocking(move || {
+            let _slot = slot;
+            let _running = Gauge::enter(&worker.running);
+            let output = work();
+            worker.completed.fetch_add(1, Ordering::Relaxed);
+            output
+        })
+        .await;
+        result.map_err(|e| {
+            if e.is_panic() {
+                pool.panicked.fetch_add(1, Ordering::Relaxed);
+            }
+            format!("{} worker failed: {}", kind, e)
+        })
+    }
+
+    /// Every pool used so far
+    pub fn stats(&self) -> Vec<WorkerPoolStats> {
+        let pools = self.pools.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        pools
+            .iter()
+            .map(|(kind, pool)| WorkerPoolStats {
+                kind: kind.clone(),
+                limit: pool.limit,
+                running: pool.running.load(Ordering::Relaxed),
+                queued: pool.queued.load(Ordering::Relaxed),
+                peak_queued: pool.peak_queued.load(Ordering::Relaxed),
+                completed: pool.completed.load(Ordering::Relaxed),
+                panicked: pool.panicked.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+/// `Workers::global().run`
+pub async fn run<T, F>(kind: &str, work: F) -> Result<T, String>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    Workers::global().run(kind, work).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn stats(workers: &Workers, kind: &str) -> WorkerPoolStats {
+        workers.stats().into_iter().find(|stats| stats.kind == kind).unwrap()
+    }
+
+    /// Until the images pool has `(running, queued)` calls
+    async fn wait_for(workers: &Workers, expected: (usize, usize)) {
+        for _ in 0..200 {
+            let now = workers.stats().into_iter().find(|stats| stats.kind == IMAGES);
+            if now.is_some_and(|now| (now.running, now.queued) == expected) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("images pool never had {:?} running and queued calls", expected);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_limit_queues_further_calls() {
+        let workers = Arc::new(Workers::new(4, BTreeMap::from([(IMAGES.to_string(), 1)])));
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+
+        let first = tokio::spawn({
+            let workers = Arc::clone(&workers);
+            async move { workers.run(IMAGES, move || blocked.recv().is_ok()).await }
+        });
+        wait_for(&workers, (1, 0)).await;
+        let second = tokio::spawn({
+            let workers = Arc::clone(&workers);
+            async move { workers.run(IMAGES, || 2).await }
+        });
+        wait_for(&workers, (1, 1)).await;
+
+        release.send(()).unwrap();
+        assert_eq!(first.await.unwrap(), Ok(true));
+        assert_eq!(second.await.unwrap(), Ok(2));
+        let done = stats(&workers, IMAGES);
+        assert_eq!((done.limit, done.running, done.queued, done.peak_queued, done.completed), (1, 0, 0, 1, 2));
+    }
+
+    #[tokio::test]
+    async fn test_panics_are_counted_and_reported() {
+        let workers = Workers::new(2, BTreeMap::new());
+        let result = workers.run(HASHING, || -> u8 { panic!("bad input") }).await;
+        assert!(result.unwrap_err().starts_with("hashing worker failed"));
+        let after = stats(&workers, HASHING);
+        assert_eq!((after.limit, after.running, after.completed, after.panicked), (2, 0, 0, 1));
+    }
+}
@@ -1,257 +1,134 @@
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
-use tracing::{info, error};
+use tracing::{info, warn, error};
 use webui_rs::webui;
 
 // Import consolidated modules
+mod core;
+mod error_handling;
 mod model;
 mod infrastructure;
 mod viewmodel;
+mod plugins;
 mod tests;
 mod presentation;
 
-use model::core::{init_logging_with_config, AppConfig, Database};
+use model::core::{init_logging_with_config, AppConfig, Database, WindowState};
 
+use infrastructure::database::init_database;
 use infrastructure::event_bus::EventBus;
 use infrastructure::logging::error_logger;
 
 use viewmodel::websocket_handler::start_websocket_server;
 use viewmodel::handlers::*;
+use viewmodel::window_logger::{window_logger, MAIN_WINDOW_ID};
 
 // Build-time generated config
 include!(concat!(env!("OUT_DIR"), "/build_config.rs"));
 
-fn start_http_server(port: u16) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// DevTools token from `X-DevTools-Token` or `Authorization: Bearer <token>`
+fn devtools_token(request: &tiny_http::Request) -> Option<String> {
+    request.headers().iter().find_map(|header| {
+        let value = header.value.as_str();
+        if header.field.equiv("X-DevTools-Token") {
+            Some(value.to_string())
+        } else if header.field.equiv("Authorization") {
+            value.strip_prefix("Bearer ").map(str::to_string)
+        } else {
+            None
+        }
+    })
+}
+
+/// JSON answer to a request the DevTools access policy refused
+fn access_denied(denied: &presentation::devtools::AccessDenied) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let body = serde_json::json!({ "error": denied.message }).to_string();
+    tiny_http::Response::from_data(body)
+        .with_status_code(denied.status)
+        .with_header(
+            tiny_http::Header::from_bytes(
+                &b"Content-Type"[..],
+                b"application/json",
+            )
+            .unwrap(),
+        )
+}
+
+fn start_http_server(listener: std::net::TcpListener) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let frontend_path = std::path::PathBuf::from("frontend/dist");
-    let devtools_api = crate::presentation::devtools::DevToolsApi::new();
+    let port = listener.local_addr()?.port();
+    let devtools_api = crate::presentation::devtools::DevToolsApi::new(port);
+    let frontend_config = serde_json::to_string(&infrastructure::server::frontend_config()).unwrap_or_default();
+    let openapi_json = presentation::openapi::openapi_json();
+    let asyncapi_json = presentation::asyncapi::asyncapi_json();
+    let ws_port = infrastructure::server::frontend_config()
+        .map(|published| published.ws_port)
+        .unwrap_or_else(|| AppConfig::global().get_ws_port());
+    let ws_access = viewmodel::ws_access::WsAccessPolicy::global();
+    let inline_scripts: Vec<String> = devtools_api
+        .access()
+        .token_script()
+        .into_iter()
+        .chain(ws_access.token_script())
+        .collect();
+    let security_headers =
+        presentation::security_headers::SecurityHeaders::from_config(AppConfig::global(), ws_port, &inline_scripts);
+    let cors = presentation::cors::CorsPolicy::from_config(AppConfig::global(), port);
+    let hooks = presentation::hooks::HookEndpoint::from_config(AppConfig::global());
+    let bridge_script = presentation::bridge::BridgeConfig::new(AppConfig::global(), &frontend_config, ws_port).render();
+    let dev_proxy = presentation::dev_proxy::DevProxy::from_config(AppConfig::global());
+    let assets = presentation::assets::AssetManifest::load(&frontend_path);
+    let static_root = presentation::static_files::StaticRoot::new(&frontend_path, AppConfig::global().get_static_extensions());
+    let assets_json = serde_json::to_string(&assets).unwrap_or_default();
 
     info!("Starting HTTP server on port {} for frontend files", port);
-    info!(
-        "Serving files from: {}",
-        frontend_path
-            .canonicalize()
-            .unwrap_or(frontend_path.clone())
-            .display()
-    );
-
-    let server = tiny_http::Server::http(format!("0.0.0.0:{}", port))?;
+    match &dev_proxy {
+        Some(proxy) => info!("Proxying frontend requests to the dev server at {}", proxy.url()),
+        None => info!(
+            "Serving files from: {}",
+            frontend_path
+                .canonicalize()
+                .unwrap_or(frontend_path.clone())
+                .display()
+        ),
+    }
 
-    thread::spawn(move || {
-        info!("HTTP server listening on http://localhost:{}", port);
+    let server = tiny_http::Server::from_listener(listener, None)?;
+    let threads = AppConfig::global().get_http_threads();
 
-        for request in server.incoming_requests() {
+    // Run by each of the `[runtime] http_threads` threads, which take turns at the listener
+    let serve = move || {
+        for mut request in server.incoming_requests() {
             let url = request.url().to_string();
-            
-            // Handle WebUI JavaScript bridge request
-            if url == "/webui.js" {
-                // Serve a minimal WebUI JavaScript bridge
-                let webui_js_content = r#"
-// WebUI JavaScript Bridge for communication with Rust backend
-(function() {
-    console.log('WebUI JavaScript Bridge loaded');
-    
-    // Create a WebSocket connection to the backend
-    const wsProtocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
-    const wsUrl = wsProtocol + '//' + window.location.host + '/_webui_ws_connect';
-    
-    let ws = null;
-    let isConnected = false;
-    let reconnectAttempts = 0;
-    let lastError = null;
-    
-    function connect() {
-        try {
-            ws = new WebSocket(wsUrl);
-            
-            ws.onopen = function(event) {
-                console.log('WebUI WebSocket connected');
-                isConnected = true;
-                reconnectAttempts = 0;
-                lastError = null;
+            let scope = presentation::access_log::RequestScope::begin(&request);
+            let header = |request: &tiny_http::Request, name: &'static str| {
+                request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.equiv(name))
+                    .map(|header| header.value.to_string())
             };
-            
-            ws.onmessage = function(event) {
-                console.log('WebUI received message:', event.data);
-                // Handle incoming messages from backend
-                try {
-                    const data = JSON.parse(event.data);
-                    console.log('Parsed message:', data);
-                    
-                    // Check for function responses based on the name
-                    if (data.name === 'get_users') {
-                        // This is a response to get_users
-                        window.dispatchEvent(new CustomEvent('db_response', { detail: data.payload || data }));
-                        return;
-                    }
-                    
-                    if (data.name === 'get_db_stats') {
-                        // This is a response to get_db_stats
-                        window.dispatchEvent(new CustomEvent('stats_response', { detail: data.payload || data }));
-                        return;
-                    }
-                    
-                    // Check for db_response event
-                    if (data.name === 'db_response' || (data.payload && data.payload.success !== undefined)) {
-                        const payload = data.payload || data;
-                        window.dispatchEvent(new CustomEvent('db_response', { detail: payload }));
-                        return;
-                    }
-                    
-                    // Check for stats_response event
-                    if (data.name === 'stats_response' || (data.payload && data.payload.stats !== undefined)) {
-                        const payload = data.payload || data;
-                        window.dispatchEvent(new CustomEvent('stats_response', { detail: payload }));
-                        return;
-                    }
-                    
-                    // Trigger generic webui_message event
-                    window.dispatchEvent(new CustomEvent('webui_message', { detail: data }));
-                } catch(e) {
-                    console.error('Error parsing WebUI message:', e);
+            let origin = header(&request, "Origin");
+            let host = header(&request, "Host");
+            let accept = header(&request, "Accept");
+
+            // CORS preflight, answered the same way for every route
+            if *request.method() == tiny_http::Method::Options {
+                let response = cors.preflight(
+                    origin.as_deref(),
+                    header(&request, "Access-Control-Request-Method").as_deref(),
+                    header(&request, "Access-Control-Request-Headers").as_deref(),
+                );
+                if let Err(e) = scope.respond(request, security_headers.apply(response)) {
+                    error!(error = %e, "Error sending preflight response");
                 }
-            };
-            
-            ws.onclose = function(event) {
-                console.log('WebUI WebSocket disconnected');
-                isConnected = false;
-                reconnectAttempts++;
-                // Attempt to reconnect after delay
-                setTimeout(connect, 3000);
-            };
-            
-            ws.onerror = function(error) {
-                console.error('WebUI WebSocket error:', error);
-                lastError = { message: error.message || 'WebSocket error' };
-            };
-        } catch(e) {
-            console.error('Failed to create WebUI WebSocket connection:', e);
-        }
-    }
-    
-    // Initialize connection
-    connect();
-    
-    // Expose WebUI functions to global scope
-    window.WebUI = {
-        isConnected: function() {
-            return isConnected;
-        },
-        getConnectionState: function() {
-            let state = 'closed';
-            if (isConnected) {
-                state = 'ready';
-            } else if (ws && ws.readyState === 0) {
-                state = 'connecting';
-            } else if (ws && ws.readyState === 1) {
-                state = 'open';
-            } else if (reconnectAttempts > 0) {
-                state = 'reconnecting';
-            }
-            return {
-                state: state,
-                reconnectAttempts: reconnectAttempts
-            };
-        },
-        getReadyState: function() {
-            return ws ? ws.readyState : 3; // 3 = CLOSED
-        },
-        getLastError: function() {
-            return lastError;
-        },
-        send: function(data) {
-            if (ws && ws.readyState === WebSocket.OPEN) {
-                ws.send(JSON.stringify(data));
-                return true;
-            }
-            console.warn('WebUI WebSocket not connected');
-            return false;
-        },
-        onMessage: function(callback) {
-            window.addEventListener('webui_message', function(event) {
-                callback(event.detail);
-            });
-        }
-    };
-    
-    // Expose functions that frontend expects
-    window.getUsers = function() {
-        console.log('getUsers called');
-        if (ws && ws.readyState === WebSocket.OPEN) {
-            ws.send(JSON.stringify({
-                id: Math.random().toString(36).substring(2, 15),
-                name: 'get_users',
-                payload: {},
-                timestamp: Date.now(),
-                source: 'frontend'
-            }));
-        } else {
-            console.warn('WebSocket not connected');
-            // Dispatch empty response to prevent infinite loading
-            window.dispatchEvent(new CustomEvent('db_response', { 
-                detail: { success: false, error: 'WebSocket not connected', data: [] } 
-            }));
-        }
-    };
-    
-    window.getDbStats = function() {
-        console.log('getDbStats called');
-        if (ws && ws.readyState === WebSocket.OPEN) {
-            ws.send(JSON.stringify({
-                id: Math.random().toString(36).substring(2, 15),
-                name: 'get_db_stats',
-                payload: {},
-                timestamp: Date.now(),
-                source: 'frontend'
-            }));
-        } else {
-            console.warn('WebSocket not connected');
-            window.dispatchEvent(new CustomEvent('stats_response', { 
-                detail: { success: false, error: 'WebSocket not connected', stats: { users: 0, tables: [] } } 
-            }));
-        }
-    };
-    
-    // webui.call() - Send a call to Rust backend and expect response
-    window.webui = {
-        call: function(functionName, data) {
-            console.log('webui.call:', functionName, data);
-            // Send the function call through WebSocket
-            if (ws && ws.readyState === WebSocket.OPEN) {
-                ws.send(JSON.stringify({
-                    id: Math.random().toString(36).substring(2, 15),
-                    name: functionName,
-                    payload: data || {},
-                    timestamp: Date.now(),
-                    source: 'frontend'
-                }));
-                return true;
+                continue;
             }
-            console.warn('WebUI WebSocket not connected, cannot call:', functionName);
-            return false;
-        }
-    };
-    
-    // Bind function for UI elements (original WebUI behavior)
-    window.webui_bind = function(elementId, callback) {
-        const element = document.getElementById(elementId);
-        if (element) {
-            element.addEventListener('click', function() {
-                callback();
-            });
-        }
-    };
-    
-    // Return function for sending data back to backend
-    window.webui_return = function(id, data) {
-        window.WebUI.send({ id: id, data: data });
-    };
-    
-    console.log('WebUI bridge initialized with getUsers/getDbStats functions');
-})();
-"#;
 
-                let response = tiny_http::Response::from_data(webui_js_content)
+            // Handle WebUI JavaScript bridge request
+            if url == presentation::bridge::BRIDGE_PATH {
+                let response = tiny_http::Response::from_data(bridge_script.as_bytes())
                     .with_header(
                         tiny_http::Header::from_bytes(
                             &b"Content-Type"[..],
@@ -267,15 +144,323 @@ fn start_http_server(port: u16) -> Result<(), Box<dyn std::error::Error + Send +
                         .unwrap(),
                     );
 
-                if let Err(e) = request.respond(response) {
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
                     error!(error = %e, "Error sending WebUI JS response");
                 }
                 
                 continue; // Skip the rest of the processing
             }
 
+            // Ports and app details for the frontend
+            if url == "/config.json" {
+                let response = tiny_http::Response::from_data(frontend_config.clone())
+                    .with_header(
+                        tiny_http::Header::from_bytes(
+                            &b"Content-Type"[..],
+                            b"application/json",
+                        )
+                        .unwrap(),
+                    )
+                    .with_header(
+                        tiny_http::Header::from_bytes(
+                            &b"Cache-Control"[..],
+                            b"no-cache, no-store, must-revalidate",
+                        )
+                        .unwrap(),
+                    );
+
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending config response");
+                }
+
+                continue;
+            }
+
+            // Liveness / readiness probes; 503 when a dependency is down
+            if url == "/healthz" || url == "/readyz" {
+                let report = if url == "/healthz" {
+                    devtools_api.liveness()
+                } else {
+                    devtools_api.readiness()
+                };
+                let status = if report.is_healthy() { 200 } else { 503 };
+                let response = tiny_http::Response::from_data(serde_json::to_string(&report).unwrap_or_default())
+                    .with_status_code(status)
+                    .with_header(
+                        tiny_http::Header::from_bytes(
+                            &b"Content-Type"[..],
+                            b"application/json",
+                        )
+                        .unwrap(),
+                    );
+
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending health response");
+                }
+
+                continue;
+            }
+
+            // What this binary was built from
+            if url == presentation::openapi::VERSION_PATH {
+                let response = tiny_http::Response::from_data(serde_json::to_string(&BUILD_INFO).unwrap_or_default())
+                    .with_header(
+                        tiny_http::Header::from_bytes(
+                            &b"Content-Type"[..],
+                            b"application/json",
+                        )
+                        .unwrap(),
+                    );
+
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending version response");
+                }
+
+                continue;
+            }
+
+            // Command latency histograms for Prometheus, for whoever may call the DevTools
+            if url == infrastructure::command_metrics::METRICS_PATH {
+                let access = devtools_api
+                    .access()
+                    .check(request.remote_addr(), host.as_deref(), devtools_token(&request).as_deref());
+                let response = match access {
+                    Ok(()) => tiny_http::Response::from_data(infrastructure::command_metrics::CommandMetrics::global().prometheus())
+                        .with_header(
+                            tiny_http::Header::from_bytes(
+                                &b"Content-Type"[..],
+                                b"text/plain; version=0.0.4",
+                            )
+                            .unwrap(),
+                        ),
+                    Err(denied) => access_denied(&denied),
+                };
+
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending metrics response");
+                }
+
+                continue;
+            }
+
+            // OpenAPI document of the HTTP API
+            if url == presentation::openapi::OPENAPI_PATH {
+                let response = tiny_http::Response::from_data(openapi_json.clone())
+                    .with_header(
+                        tiny_http::Header::from_bytes(
+                            &b"Content-Type"[..],
+                            b"application/json",
+                        )
+                        .unwrap(),
+                    );
+
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending OpenAPI response");
+                }
+
+                continue;
+            }
+
+            // AsyncAPI document of the WebSocket protocol
+            if url == presentation::asyncapi::ASYNCAPI_PATH {
+                let response = tiny_http::Response::from_data(asyncapi_json.clone())
+                    .with_header(
+                        tiny_http::Header::from_bytes(
+                            &b"Content-Type"[..],
+                            b"application/json",
+                        )
+                        .unwrap(),
+                    );
+
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending AsyncAPI response");
+                }
+
+                continue;
+            }
+
+            // JSON Schemas of the WebSocket envelopes, event payloads and DTOs, for whoever may call the DevTools
+            if url == "/api/schemas" || url.starts_with("/api/schemas/") {
+                let access = devtools_api
+                    .access()
+                    .check(request.remote_addr(), host.as_deref(), devtools_token(&request).as_deref());
+                let schema = match url.strip_prefix("/api/schemas/") {
+                    Some(name) => infrastructure::schema::get(name),
+                    None => Some(infrastructure::schema::all()),
+                };
+                let response = match (access, schema) {
+                    (Err(denied), _) => access_denied(&denied),
+                    (Ok(()), Some(schema)) => tiny_http::Response::from_data(schema.to_string()).with_header(
+                        tiny_http::Header::from_bytes(&b"Content-Type"[..], b"application/schema+json").unwrap(),
+                    ),
+                    (Ok(()), None) => presentation::pages::not_found(accept.as_deref(), &url),
+                };
+
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending schema response");
+                }
+
+                continue;
+            }
+
+            let (route, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+
+            // Bundle names of the current frontend build
+            if route == presentation::assets::ASSETS_PATH {
+                let response = if assets.is_empty() {
+                    let body = serde_json::json!({ "error": "The frontend build has no asset manifest" }).to_string();
+                    tiny_http::Response::from_data(body).with_status_code(404)
+                } else {
+                    tiny_http::Response::from_data(assets_json.clone())
+                };
+                let response = response.with_header(
+                    tiny_http::Header::from_bytes(
+                        &b"Content-Type"[..],
+                        b"application/json",
+                    )
+                    .unwrap(),
+                );
+
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending assets response");
+                }
+
+                continue;
+            }
+
+            // Multipart file uploads, streamed to disk
+            if route == presentation::upload::UPLOAD_PATH {
+                let content_type = header(&request, "Content-Type");
+                let method = request.method().to_string();
+                let (status, body) = presentation::upload::handle_upload(
+                    &method,
+                    query,
+                    origin.as_deref().is_none_or(|origin| cors.allows(origin)),
+                    content_type.as_deref(),
+                    request.as_reader(),
+                );
+                let response = tiny_http::Response::from_data(body.to_string())
+                    .with_status_code(status)
+                    .with_header(
+                        tiny_http::Header::from_bytes(
+                            &b"Content-Type"[..],
+                            b"application/json",
+                        )
+                        .unwrap(),
+                    );
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending upload response");
+                }
+                continue;
+            }
+
+            // Triggers from external systems, emitted as hook.<name> events
+            if let Some(name) = route.strip_prefix(presentation::hooks::HOOKS_PATH) {
+                let secret = header(&request, "X-Hook-Secret").or_else(|| {
+                    header(&request, "Authorization").and_then(|value| value.strip_prefix("Bearer ").map(str::to_string))
+                });
+                let method = request.method().to_string();
+                let name = name.to_string();
+                let (status, body) = hooks.handle(&method, &name, secret.as_deref(), request.as_reader());
+                let response = tiny_http::Response::from_data(body.to_string())
+                    .with_status_code(status)
+                    .with_header(
+                        tiny_http::Header::from_bytes(
+                            &b"Content-Type"[..],
+                            b"application/json",
+                        )
+                        .unwrap(),
+                    );
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending hook response");
+                }
+                continue;
+            }
+
+            // Bundled Swagger UI for exploring the OpenAPI document
+            #[cfg(feature = "swagger-ui")]
+            if url == presentation::openapi::SWAGGER_UI_PATH.trim_end_matches('/') {
+                // Relative asset URLs need the trailing slash
+                let response = tiny_http::Response::empty(301).with_header(
+                    tiny_http::Header::from_bytes(
+                        &b"Location"[..],
+                        presentation::openapi::SWAGGER_UI_PATH.as_bytes(),
+                    )
+                    .unwrap(),
+                );
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending Swagger UI response");
+                }
+                continue;
+            }
+            #[cfg(feature = "swagger-ui")]
+            if let Some(file) = url.strip_prefix(presentation::openapi::SWAGGER_UI_PATH) {
+                let response = match presentation::openapi::swagger_ui_file(file) {
+                    Some((bytes, content_type)) => tiny_http::Response::from_data(bytes).with_header(
+                        tiny_http::Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).unwrap(),
+                    ),
+                    None => presentation::pages::not_found(accept.as_deref(), &url),
+                };
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending Swagger UI response");
+                }
+                continue;
+            }
+
+            // Landing page listing the DevTools routes
+            if route == presentation::pages::DEVTOOLS_PATH || route == presentation::pages::DEVTOOLS_PATH.trim_end_matches('/') {
+                let access = devtools_api.access();
+                let response = match access.check_local(request.remote_addr(), host.as_deref()) {
+                    Ok(()) => presentation::pages::devtools(access.token.is_some()),
+                    Err(denied) => presentation::pages::error(accept.as_deref(), denied.status, denied.message),
+                };
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending DevTools page");
+                }
+                continue;
+            }
+
             // Handle DevTools API requests
             if url.starts_with("/api/devtools/") {
+                if let Err(denied) = devtools_api
+                    .access()
+                    .check(request.remote_addr(), host.as_deref(), devtools_token(&request).as_deref())
+                {
+                    let response = access_denied(&denied);
+                    if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                        error!(error = %e, "Error sending DevTools API response");
+                    }
+                    continue;
+                }
+
+                if presentation::devtools::changes_state(route) && *request.method() != tiny_http::Method::Post {
+                    let body = serde_json::json!({ "error": "This DevTools route only accepts POST" }).to_string();
+                    let response = tiny_http::Response::from_data(body)
+                        .with_status_code(405)
+                        .with_header(tiny_http::Header::from_bytes(&b"Allow"[..], &b"POST"[..]).unwrap())
+                        .with_header(
+                            tiny_http::Header::from_bytes(
+                                &b"Content-Type"[..],
+                                b"application/json",
+                            )
+                            .unwrap(),
+                        );
+                    if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                        error!(error = %e, "Error sending DevTools API response");
+                    }
+                    continue;
+                }
+
+                // A stream rather than one JSON answer
+                if route == presentation::event_tap::TAP_PATH {
+                    let head = cors
+                        .apply(security_headers.apply(tiny_http::Response::empty(200)), origin.as_deref())
+                        .with_header(scope.header());
+                    presentation::event_tap::serve(request, query, head.headers());
+                    scope.streamed(200);
+                    continue;
+                }
+
                 let response_data = match url.as_str() {
                     "/api/devtools/metrics" => {
                         serde_json::to_string(&devtools_api.get_system_metrics()).unwrap_or_default()
@@ -286,6 +471,93 @@ fn start_http_server(port: u16) -> Result<(), Box<dyn std::error::Error + Send +
                     "/api/devtools/info" => {
                         serde_json::to_string(&devtools_api.execute_command("info", serde_json::json!({}))).unwrap_or_default()
                     }
+                    "/api/devtools/scheduler" => {
+                        serde_json::to_string(&devtools_api.execute_command("scheduler.list", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    "/api/devtools/crashes" => {
+                        serde_json::to_string(&devtools_api.execute_command("crash.list", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    path if path.starts_with("/api/devtools/crashes/") => {
+                        let file = path.trim_start_matches("/api/devtools/crashes/");
+                        serde_json::to_string(&devtools_api.execute_command("crash.read", serde_json::json!({ "file": file }))).unwrap_or_default()
+                    }
+                    "/api/devtools/cache" => {
+                        serde_json::to_string(&devtools_api.execute_command("cache", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    "/api/devtools/workers" => {
+                        serde_json::to_string(&devtools_api.execute_command("workers", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    "/api/devtools/db/analyze" => {
+                        serde_json::to_string(&devtools_api.execute_command("db.analyze", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    "/api/devtools/db/pool" => {
+                        serde_json::to_string(&devtools_api.execute_command("db.pool", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    "/api/devtools/runtime" => {
+                        serde_json::to_string(&devtools_api.execute_command("runtime", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    "/api/devtools/startup" => {
+                        serde_json::to_string(&devtools_api.execute_command("startup", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    _ if route == "/api/devtools/bench" => {
+                        // ?events=&users=&encodes=
+                        let args: serde_json::Map<String, serde_json::Value> = form_urlencoded::parse(query.as_bytes())
+                            .filter_map(|(key, value)| value.parse::<u64>().ok().map(|count| (key.into_owned(), count.into())))
+                            .collect();
+                        serde_json::to_string(&devtools_api.execute_command("bench", args.into())).unwrap_or_default()
+                    }
+                    "/api/devtools/circuit_breakers" => {
+                        serde_json::to_string(&devtools_api.execute_command("circuit_breakers", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    path if path.starts_with("/api/devtools/circuit_breakers/reset/") => {
+                        let name = path.trim_start_matches("/api/devtools/circuit_breakers/reset/");
+                        serde_json::to_string(&devtools_api.execute_command("circuit_breakers.reset", serde_json::json!({ "name": name }))).unwrap_or_default()
+                    }
+                    "/api/devtools/logging" => {
+                        serde_json::to_string(&devtools_api.execute_command("logging.get_filter", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    path if path.starts_with("/api/devtools/logging/level/") => {
+                        // /level/{level} sets the default level, /level/{target}/{level} one target
+                        let args = match path.trim_start_matches("/api/devtools/logging/level/").split_once('/') {
+                            Some((target, level)) => serde_json::json!({ "target": target, "level": level }),
+                            None => serde_json::json!({ "level": path.trim_start_matches("/api/devtools/logging/level/") }),
+                        };
+                        serde_json::to_string(&devtools_api.execute_command("logging.set_level", args)).unwrap_or_default()
+                    }
+                    _ if route == "/api/devtools/ringbuffer" => {
+                        // ?limit=
+                        let args: serde_json::Map<String, serde_json::Value> = form_urlencoded::parse(query.as_bytes())
+                            .filter_map(|(key, value)| value.parse::<u64>().ok().map(|count| (key.into_owned(), count.into())))
+                            .collect();
+                        serde_json::to_string(&devtools_api.execute_command("ringbuffer.dump", args.into())).unwrap_or_default()
+                    }
+                    _ if route == "/api/devtools/http/recent" => {
+                        // ?limit=
+                        let args: serde_json::Map<String, serde_json::Value> = form_urlencoded::parse(query.as_bytes())
+                            .filter_map(|(key, value)| value.parse::<u64>().ok().map(|count| (key.into_owned(), count.into())))
+                            .collect();
+                        serde_json::to_string(&devtools_api.execute_command("http.recent", args.into())).unwrap_or_default()
+                    }
+                    "/api/devtools/telemetry" => {
+                        serde_json::to_string(&devtools_api.execute_command("telemetry.status", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    "/api/devtools/events/taps" => {
+                        serde_json::to_string(&devtools_api.execute_command("events.tap.list", serde_json::json!({}))).unwrap_or_default()
+                    }
+                    _ if route.starts_with("/api/devtools/events/tap/") => {
+                        // /{id}/pause, /{id}/resume, /{id}/filter?names=&sources=
+                        let (tap, action) = route
+                            .trim_start_matches("/api/devtools/events/tap/")
+                            .split_once('/')
+                            .unwrap_or_default();
+                        let mut args = serde_json::to_value(infrastructure::event_bus::tap::TapFilter::from_query(query)).unwrap_or_default();
+                        args["tap"] = serde_json::json!(tap);
+                        serde_json::to_string(&devtools_api.execute_command(&format!("events.tap.{}", action), args)).unwrap_or_default()
+                    }
+                    path if path.starts_with("/api/devtools/scheduler/run/") => {
+                        let job = path.trim_start_matches("/api/devtools/scheduler/run/");
+                        serde_json::to_string(&devtools_api.execute_command("scheduler.run_now", serde_json::json!({ "job": job }))).unwrap_or_default()
+                    }
                     _ => {
                         serde_json::json!({ "error": "Unknown DevTools endpoint" }).to_string()
                     }
@@ -298,77 +570,291 @@ fn start_http_server(port: u16) -> Result<(), Box<dyn std::error::Error + Send +
                             b"application/json",
                         )
                         .unwrap(),
-                    )
-                    .with_header(
-                        tiny_http::Header::from_bytes(
-                            &b"Access-Control-Allow-Origin"[..],
-                            b"*",
-                        )
-                        .unwrap(),
                     );
 
-                if let Err(e) = request.respond(response) {
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
                     error!(error = %e, "Error sending DevTools API response");
                 }
 
                 continue;
             }
 
-            let path = if url == "/" {
-                frontend_path.join("index.html")
-            } else {
-                frontend_path.join(url.trim_start_matches('/'))
+            // Routes and assets contributed by plugins
+            if url.starts_with("/plugins/") {
+                let headers = request
+                    .headers()
+                    .iter()
+                    .map(|header| (header.field.to_string(), header.value.to_string()))
+                    .collect();
+                let mut body = Vec::new();
+                if let Err(e) = std::io::Read::read_to_end(request.as_reader(), &mut body) {
+                    error!(error = %e, "Error reading plugin request body");
+                }
+                let method = request.method().to_string();
+                let response = match plugins::host::handle_http(&method, &url, headers, body) {
+                    Some(response) => tiny_http::Response::from_data(response.body)
+                        .with_status_code(response.status)
+                        .with_header(
+                            tiny_http::Header::from_bytes(
+                                &b"Content-Type"[..],
+                                response.content_type.as_bytes(),
+                            )
+                            .unwrap(),
+                        ),
+                    None => presentation::pages::not_found(accept.as_deref(), &url),
+                };
+
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending plugin response");
+                }
+
+                continue;
+            }
+
+            // The devtools and WebSocket tokens for a page
+            let inject_tokens = |html: &str| {
+                let mut html = devtools_api.access().inject_token(html, host.as_deref());
+                if let Some(script) = ws_access.token_script() {
+                    html = presentation::devtools::inject_script(&html, &script);
+                }
+                html
+            };
+
+            // Everything else comes from the dev server when one is configured
+            if let Some(proxy) = &dev_proxy {
+                let response = match proxy.forward(&mut request) {
+                    Ok(mut upstream) => {
+                        if upstream.is_html() {
+                            upstream.rewrite_html(inject_tokens);
+                        }
+                        upstream.into_response()
+                    }
+                    Err(e) => {
+                        warn!(error = %e, url = %url, "Dev server request failed");
+                        presentation::pages::error(accept.as_deref(), 502, &format!("The dev server at {} is not reachable.", proxy.url()))
+                    }
+                };
+                if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
+                    error!(error = %e, "Error sending proxied response");
+                }
+                continue;
+            }
+
+            let path = match static_root.resolve(route) {
+                Ok(path) => path,
+                Err(rejected) => {
+                    warn!(url = %url, reason = ?rejected, "Refused static file request");
+                    let response = presentation::pages::error(accept.as_deref(), rejected.status(), rejected.message());
+                    let _ = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref()));
+                    continue;
+                }
             };
 
             info!("HTTP Request: {} -> {:?}", url, path);
 
             if path.exists() && path.is_file() {
-                match std::fs::read(&path) {
-                    Ok(content) => {
-                        let content_type = mime_guess::from_path(&path)
-                            .first_or_octet_stream()
-                            .to_string();
+                let content_type = mime_guess::from_path(&path)
+                    .first_or_octet_stream()
+                    .to_string();
+                let range = request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.equiv("Range"))
+                    .map(|header| header.value.to_string());
 
-                        let response = tiny_http::Response::from_data(content).with_header(
-                            tiny_http::Header::from_bytes(
-                                &b"Content-Type"[..],
-                                content_type.as_bytes(),
+                // index.html gets the devtools and WebSocket tokens, every other asset is streamed with range support
+                let response = if path == frontend_path.join("index.html") {
+                    std::fs::read(&path).map(|content| {
+                        let content = inject_tokens(&String::from_utf8_lossy(&content)).into_bytes();
+                        tiny_http::Response::from_data(content)
+                            .with_header(
+                                tiny_http::Header::from_bytes(
+                                    &b"Content-Type"[..],
+                                    content_type.as_bytes(),
+                                )
+                                .unwrap(),
                             )
-                            .unwrap(),
-                        );
+                            .boxed()
+                    })
+                } else {
+                    presentation::static_files::file_response(&path, &content_type, range.as_deref())
+                };
 
-                        if let Err(e) = request.respond(response) {
+                match response {
+                    Ok(mut response) => {
+                        if let Some(cache_control) = assets.cache_control(route) {
+                            response.add_header(
+                                tiny_http::Header::from_bytes(&b"Cache-Control"[..], cache_control.as_bytes()).unwrap(),
+                            );
+                        }
+                        if let Err(e) = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref())) {
                             error!(error = %e, "Error sending response");
                         }
                     }
                     Err(e) => {
                         error!(error = %e, file_path = ?path, "Error reading file");
-                        let response = tiny_http::Response::from_string(format!("Error: {}", e))
-                            .with_status_code(500);
-                        let _ = request.respond(response);
+                        let response = presentation::pages::error(accept.as_deref(), 500, &format!("{} could not be read.", url));
+                        let _ = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref()));
                     }
                 }
+            } else if (route == "/" || route == "/index.html") && !frontend_path.join("index.html").exists() {
+                // The frontend has not been built yet
+                let _ = scope.respond(request, cors.apply(security_headers.apply(presentation::pages::splash(accept.as_deref())), origin.as_deref()));
             } else {
-                let response = tiny_http::Response::from_string("Not Found").with_status_code(404);
-                let _ = request.respond(response);
+                let response = presentation::pages::not_found(accept.as_deref(), route);
+                let _ = scope.respond(request, cors.apply(security_headers.apply(response), origin.as_deref()));
             }
         }
-    });
+    };
+
+    let serve = Arc::new(serve);
+    for worker in 0..threads {
+        let serve = Arc::clone(&serve);
+        thread::Builder::new().name(format!("http-{}", worker)).spawn(move || serve())?;
+    }
+    info!("HTTP server listening on http://localhost:{} with {} thread(s)", port, threads);
 
     Ok(())
 }
 
-#[tokio::main]
-async fn main() {
-    // Load application configuration
-    let config = match AppConfig::load() {
+/// Apply the saved geometry of the main window, falling back to the configured size;
+/// returns the saved state, whose placement `restore_window_placement` finishes once shown
+fn restore_window_state(window: &webui::Window, config: &AppConfig, db: &Database) -> Option<WindowState> {
+    if config.should_remember_window_state() {
+        match db.load_window_state(MAIN_WINDOW_ID) {
+            Ok(Some(state)) => {
+                info!(
+                    "Restoring window state: {}x{} at {},{} (maximized: {})",
+                    state.width, state.height, state.x, state.y, state.maximized
+                );
+                window.set_size(state.width, state.height);
+                // WebUI only takes positions on the primary monitor's side; the page moves it elsewhere
+                if state.x >= 0 && state.y >= 0 {
+                    window.set_position(state.x as u32, state.y as u32);
+                }
+                return Some(state);
+            }
+            Ok(None) => {
+                info!("No saved window state, using configured defaults");
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to load saved window state");
+            }
+        }
+    }
+
+    if let Some((width, height)) = config.get_window_size() {
+        window.set_size(width, height);
+    }
+    None
+}
+
+/// Finish restoring the shown main window from the page, which knows the
+/// monitors: move it to a saved position left of or above the primary
+/// monitor, back onto the current monitor when the saved one is gone, and
+/// maximize it again
+fn restore_window_placement(window: &webui::Window, state: &WindowState) {
+    let script = format!(
+        r#"(async () => {{
+    const saved = {{ x: {x}, y: {y}, width: {width} }};
+    let screens = [window.screen];
+    try {{
+        const permission = await navigator.permissions.query({{ name: 'window-management' }});
+        if (permission.state === 'granted') screens = (await window.getScreenDetails()).screens;
+        else if (window.screen.isExtended) screens = null;
+    }} catch (e) {{
+        if (window.screen.isExtended) screens = null;
+    }}
+    // Without the bounds of every monitor, trust the saved position
+    const visible = !screens || screens.some((s) =>
+        saved.x < s.availLeft + s.availWidth && saved.x + saved.width > s.availLeft &&
+        saved.y >= s.availTop && saved.y < s.availTop + s.availHeight);
+    if (!visible) window.moveTo(window.screen.availLeft, window.screen.availTop);
+    else if (saved.x < 0 || saved.y < 0) window.moveTo(saved.x, saved.y);
+    if ({maximized}) {{
+        window.moveTo(window.screen.availLeft, window.screen.availTop);
+        window.resizeTo(window.screen.availWidth, window.screen.availHeight);
+    }}
+}})();"#,
+        x = state.x,
+        y = state.y,
+        width = state.width,
+        maximized = state.maximized,
+    );
+    let result = window.run_js(script);
+    if result.error {
+        warn!("Could not restore the window placement: {}", result.data);
+    }
+}
+
+/// IPC socket path from the config, or the platform default
+fn ipc_path(config: &AppConfig) -> std::path::PathBuf {
+    config
+        .get_ipc_path()
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(viewmodel::ipc::default_socket_path)
+}
+
+fn main() {
+    // Time each startup step for the boot report
+    let startup = infrastructure::startup::Profiler::start();
+
+    // Read before the runtime exists, since `[runtime]` sizes it
+    let loaded = AppConfig::load();
+    let runtime = match infrastructure::runtime::build(loaded.as_ref().unwrap_or(&AppConfig::default())) {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("\x1b[31m✗ Failed to start the async runtime: {}\x1b[0m", e);
+            std::process::exit(1);
+        }
+    };
+    runtime.block_on(run(loaded, startup));
+}
+
+async fn run(loaded: Result<AppConfig, Box<dyn std::error::Error>>, mut startup: infrastructure::startup::Profiler) {
+    // `app --ctl <command> [payload]` talks to the running instance and exits
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--ctl") {
+        let config = loaded.unwrap_or_default();
+        std::process::exit(viewmodel::ipc::run_ctl(&ipc_path(&config), &args[index + 1..]));
+    }
+
+    // `app --generate-client [dir]` writes the typed frontend client and exits
+    if let Some(index) = args.iter().position(|arg| arg == "--generate-client") {
+        let config = loaded.unwrap_or_default();
+        let plugins_dir = config
+            .is_plugins_enabled()
+            .then(|| std::path::Path::new(config.get_plugins_dir()));
+        let plugin_commands = plugins::host::plugin_commands(plugins_dir);
+        std::process::exit(viewmodel::client_codegen::run_generate(&args[index + 1..], &plugin_commands));
+    }
+
+    // `app --replay <file> [--replay-speed <factor>]` runs a recorded session again once started
+    let replay = args.iter().position(|arg| arg == "--replay").map(|index| {
+        let Some(path) = args.get(index + 1) else {
+            eprintln!("Usage: app --replay <session.jsonl> [--replay-speed <factor>]");
+            std::process::exit(2);
+        };
+        let speed = args
+            .iter()
+            .position(|arg| arg == "--replay-speed")
+            .and_then(|index| args.get(index + 1))
+            .map_or(Ok(1.0), |speed| speed.parse::<f64>())
+            .unwrap_or_else(|_| {
+                eprintln!("--replay-speed takes a number, e.g. 4 (0 = no pauses)");
+                std::process::exit(2);
+            });
+        (std::path::PathBuf::from(path), speed.max(0.0))
+    });
+
+    let config = match loaded {
         Ok(config) => {
             eprintln!("\x1b[32m✓ Configuration loaded successfully!\x1b[0m");
             eprintln!("\x1b[36m  Application: {} v{}\x1b[0m",
                 config.get_app_name(),
                 config.get_version()
             );
-            config
+            config.install_global()
         }
         Err(ref e) => {
             error_logger::log_error_with_severity(
@@ -382,9 +868,10 @@ async fn main() {
                 None,
             );
             eprintln!("\x1b[33m⚠ Failed to load configuration, using defaults\x1b[0m");
-            AppConfig::default()
+            AppConfig::default().install_global()
         }
     };
+    startup.mark("config.load");
 
     // Initialize logging system with config settings
     if let Err(ref e) = init_logging_with_config(
@@ -406,12 +893,22 @@ async fn main() {
         return;
     }
 
+    error_logger::setup_panic_hook();
+    startup.mark("logging.init");
+
     info!("=============================================");
     info!(
         "Starting: {} v{}",
         config.get_app_name(),
         config.get_version()
     );
+    info!(
+        "Build: {} ({}, {}, built {})",
+        BUILD_INFO.git_commit.unwrap_or("unknown commit"),
+        BUILD_INFO.target,
+        BUILD_INFO.profile,
+        BUILD_INFO.build_timestamp
+    );
     info!("=============================================");
 
     // Initialize event bus
@@ -428,15 +925,92 @@ async fn main() {
     ).await {
         error!(error = %e, "Failed to emit app start event");
     }
+    startup.mark("event_bus.init");
+
+    // Bind both servers up front so the chosen ports can be handed to the frontend
+    let bind = config.get_server_bind();
+    let fallback_attempts = config.get_port_fallback_attempts();
+    let (ws_listener, http_listener) = match (
+        infrastructure::server::bind_with_fallback(bind, config.get_ws_port(), fallback_attempts),
+        infrastructure::server::bind_with_fallback(bind, config.get_http_port(), fallback_attempts),
+    ) {
+        (Ok(ws), Ok(http)) => (ws, http),
+        (Err(e), _) | (_, Err(e)) => {
+            error!(error = %e, bind = bind, "Failed to bind server ports");
+            return;
+        }
+    };
+    let ws_port = ws_listener.local_addr().map(|addr| addr.port()).unwrap_or_default();
+    let http_port = http_listener.local_addr().map(|addr| addr.port()).unwrap_or_default();
+    infrastructure::server::publish_ports(config, http_port, ws_port);
 
     // Start WebSocket server in a separate task
     let event_bus_for_ws = event_bus.clone();
     tokio::spawn(async move {
-        if let Err(e) = start_websocket_server(event_bus_for_ws, 9000).await {
+        if let Err(e) = start_websocket_server(event_bus_for_ws, ws_listener).await {
             error!(error = %e, "Failed to start WebSocket server");
         }
     });
-    info!("WebSocket server started on ws://127.0.0.1:9000");
+    info!("WebSocket server started on ws://{}:{}", bind, ws_port);
+    if config.is_chaos_enabled() {
+        // Announce it in the log now rather than at the first injected fault
+        infrastructure::chaos::Chaos::global();
+    }
+    startup.mark("servers.bind");
+
+    // Built-in plugins, plus third-party commands from plugin libraries
+    let plugins_dir = config
+        .is_plugins_enabled()
+        .then(|| std::path::Path::new(config.get_plugins_dir()));
+    let hot_reload = plugins_dir.is_some() && config.is_plugin_hot_reload_enabled();
+    let loaded = plugins::host::load_plugins(plugins_dir, &config.get_plugin_config(), hot_reload).await;
+    info!("{} plugin(s) running", loaded);
+    if let Some(plugins_dir) = plugins_dir.filter(|_| hot_reload) {
+        if let Err(e) = plugins::host::watch_plugins(plugins_dir, config.get_plugin_config()) {
+            error!(error = %e, "Failed to watch plugins for hot reload");
+        }
+    }
+    startup.mark("plugins.load");
+
+    // Local command channel for `--ctl`
+    if config.is_ipc_enabled() {
+        let path = ipc_path(config);
+        tokio::spawn(async move {
+            if let Err(e) = viewmodel::ipc::serve(path).await {
+                error!(error = %e, "IPC server stopped");
+            }
+        });
+    }
+
+    // Advertise on the LAN and look for other instances
+    #[cfg(feature = "discovery")]
+    if config.is_discovery_enabled() {
+        if let Err(e) = infrastructure::discovery::discovery().start(config, ws_port, http_port) {
+            tracing::warn!(error = %e, "Peer discovery unavailable");
+        }
+    }
+
+    #[cfg(feature = "grpc")]
+    if config.is_grpc_enabled() {
+        match infrastructure::server::bind_with_fallback(bind, config.get_grpc_port(), fallback_attempts) {
+            Ok(listener) => {
+                tokio::spawn(async move {
+                    if let Err(e) = viewmodel::grpc::serve(listener).await {
+                        error!(error = %e, "gRPC server stopped");
+                    }
+                });
+            }
+            Err(e) => error!(error = %e, bind = bind, "Failed to bind gRPC port"),
+        }
+    }
+
+    if config.should_autostart_metrics() {
+        infrastructure::metrics::metrics_sampler().start(None);
+    }
+
+    // Record the commands of this run for --replay
+    infrastructure::recorder::start(config);
+    startup.mark("services.start");
 
     info!("Application starting...");
 
@@ -445,7 +1019,7 @@ async fn main() {
     info!("Database path: {}", db_path);
 
     // Initialize SQLite database
-    let db = match Database::new(db_path) {
+    let db = match Database::open(config) {
         Ok(db) => {
             info!("Database initialized successfully");
             if let Err(e) = db.init() {
@@ -481,20 +1055,78 @@ async fn main() {
 
     // Initialize database handlers with the database instance
     init_database(Arc::clone(&db));
+    startup.mark("database.open");
+
+    // Apply the feature flag overrides set at runtime
+    infrastructure::flags::start().await;
+
+    // Summarize high-frequency events
+    infrastructure::event_bus::batching::start(config);
+
+    // Drop cached queries when data changes
+    infrastructure::cache::start();
+
+    // Send subscribed frontends patches of the state documents they mirror
+    infrastructure::state_sync::start();
+
+    // Count feature usage per day
+    infrastructure::analytics::start(Arc::clone(&db), config);
+
+    // Publish the events database changes left in the outbox
+    infrastructure::outbox::start(Arc::clone(&db), config);
+
+    // POST matching events to registered webhooks
+    #[cfg(feature = "webhooks")]
+    infrastructure::webhooks::start(Arc::clone(&db), config);
+
+    // Bridge selected events to and from an external message broker
+    #[cfg(feature = "broker")]
+    if config.is_broker_enabled() {
+        infrastructure::broker::start(config);
+    }
+
+    // Keep the launch-at-login entry in line with the stored preference
+    infrastructure::os_integration::start();
+
+    // Look for new releases of the app
+    #[cfg(feature = "updater")]
+    infrastructure::updater::start(config);
+
+    // Register the global keyboard shortcuts of [hotkeys]
+    #[cfg(feature = "hotkeys")]
+    infrastructure::hotkeys::start(config);
+
+    // Background maintenance jobs (vacuum, backups, log rotation, snapshots)
+    if config.is_scheduler_enabled() {
+        let scheduler = infrastructure::scheduler::Scheduler::global();
+        match infrastructure::scheduler::jobs::register_builtin_jobs(scheduler, config) {
+            Ok(()) => scheduler.start(),
+            Err(e) => error!(error = %e, "Failed to register scheduled jobs"),
+        }
+    }
+    startup.mark("background.start");
 
     // Start HTTP server for frontend files
-    let http_port = 8080u16;
-    if let Err(e) = start_http_server(http_port) {
+    if let Err(e) = start_http_server(http_listener) {
         error!(error = %e, "Failed to start HTTP server");
         return;
     }
 
     // Give the server a moment to start
     thread::sleep(Duration::from_millis(100));
+    startup.mark("http.serve");
+
+    if let Some((path, speed)) = replay {
+        infrastructure::recorder::start_replay(path, speed);
+    }
 
     // Create a new window
     let mut my_window = webui::Window::new();
 
+    // Restore geometry from the previous session and keep tracking changes
+    window_logger().set_persistence_enabled(config.should_remember_window_state());
+    let restored = restore_window_state(&my_window, config, &db);
+
     // Set up UI event handlers
     setup_ui_handlers(&mut my_window);
     setup_counter_handlers(&mut my_window);
@@ -512,6 +1144,11 @@ async fn main() {
     let url = format!("http://localhost:{}", http_port);
     info!("Loading application UI from {}", url);
     my_window.show(&url);
+    if let Some(state) = &restored {
+        restore_window_placement(&my_window, state);
+    }
+    startup.mark("window.show");
+    startup.finish();
 
     // Emit UI ready event
     if let Err(e) = event_bus.emit_simple(
@@ -538,6 +1175,13 @@ async fn main() {
         error!(error = %e, "Failed to emit app shutdown event");
     }
 
+    plugins::host::shutdown_plugins().await;
+    infrastructure::analytics::flush();
+
+    // Let peers drop this instance right away instead of waiting for the record to expire
+    #[cfg(feature = "discovery")]
+    infrastructure::discovery::discovery().stop();
+
     info!("Application shutting down...");
     info!("=============================================");
 }
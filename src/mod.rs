@@ -1,6 +0,0 @@
-pub mod model;
-pub mod viewmodel;
-pub mod infrastructure;
-pub mod error_handling;
-pub mod core;
-pub mod presentation;
\ No newline at end of file
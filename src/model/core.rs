@@ -1,46 +1,519 @@
-use rusqlite::Connection;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::OnceLock;
 use tracing::{info, Level};
+use crate::infrastructure::database::changes;
 use tracing_subscriber::prelude::__tracing_subscriber_SubscriberExt;
 use tracing_subscriber::{fmt, EnvFilter, Layer};
 
 // Consolidated core functionality
 // Combines: config, logging, database, and other infrastructure modules
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AppConfig {
     pub app: AppSettings,
     pub database: DatabaseSettings,
     pub window: WindowSettings,
     pub logging: LoggingSettings,
+    #[serde(default)]
+    pub server: ServerSettings,
+    #[serde(default)]
+    pub ipc: IpcSettings,
+    #[serde(default)]
+    pub grpc: GrpcSettings,
+    #[serde(default)]
+    pub discovery: DiscoverySettings,
+    #[serde(default)]
+    pub plugins: PluginSettings,
+    #[serde(default)]
+    pub shell: ShellSettings,
+    #[serde(default)]
+    pub metrics: MetricsSettings,
+    #[serde(default)]
+    pub scheduler: SchedulerSettings,
+    #[serde(default)]
+    pub fs_watcher: FsWatcherSettings,
+    #[serde(default)]
+    pub file_transfer: FileTransferSettings,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerSettings,
+    #[serde(default)]
+    pub cache: CacheSettings,
+    #[serde(default)]
+    pub telemetry: TelemetrySettings,
+    #[serde(default)]
+    pub devtools: DevToolsSettings,
+    #[serde(default)]
+    pub security_headers: SecurityHeadersSettings,
+    #[serde(default)]
+    pub cors: CorsSettings,
+    #[serde(default)]
+    pub secrets: SecretsSettings,
+    #[serde(default)]
+    pub sessions: SessionSettings,
+    #[serde(default)]
+    pub jwt: JwtSettings,
+    #[serde(default)]
+    pub auth: AuthSettings,
+    #[serde(default)]
+    pub mail: MailSettings,
+    #[serde(default)]
+    pub counters: CounterSettings,
+    #[serde(default)]
+    pub outbox: OutboxSettings,
+    #[serde(default)]
+    pub broker: BrokerSettings,
+    #[serde(default)]
+    pub webhooks: WebhookSettings,
+    #[serde(default)]
+    pub hooks: HookSettings,
+    #[serde(default)]
+    pub updater: UpdaterSettings,
+    #[serde(default)]
+    pub hotkeys: HotkeySettings,
+    #[serde(default)]
+    pub pdf: PdfSettings,
+    #[serde(default)]
+    pub capture: CaptureSettings,
+    #[serde(default)]
+    pub i18n: I18nSettings,
+    #[serde(default)]
+    pub analytics: AnalyticsSettings,
+    #[serde(default)]
+    pub batching: BatchingSettings,
+    #[serde(default)]
+    pub workers: WorkerSettings,
+    #[serde(default)]
+    pub chaos: ChaosSettings,
+    #[serde(default)]
+    pub recorder: RecorderSettings,
+    #[serde(default)]
+    pub delivery: DeliverySettings,
+    #[serde(default)]
+    pub dev: DevSettings,
+    #[serde(default)]
+    pub runtime: RuntimeSettings,
+    /// Default state of each feature flag, see `infrastructure::flags`
+    #[serde(default)]
+    pub features: BTreeMap<String, bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AppSettings {
     pub name: String,
     pub version: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct DatabaseSettings {
     pub path: String,
     pub create_sample_data: Option<bool>,
+    /// Encrypt the file with SQLCipher; needs the `encrypted-db` feature
+    pub encrypted: Option<bool>,
+    /// Passphrase for an encrypted database, or a `secret:<name>` reference; taken from the OS keychain when unset
+    pub encryption_key: Option<String>,
+    /// `PRAGMA synchronous`: OFF, NORMAL, FULL or EXTRA
+    pub synchronous: Option<String>,
+    /// `PRAGMA cache_size`: pages when positive, KiB when negative
+    pub cache_size: Option<i64>,
+    /// `PRAGMA mmap_size` in bytes
+    pub mmap_size: Option<u64>,
+    pub busy_timeout_ms: Option<u64>,
+    /// Read-only connections next to the writer; 0 = reads share the writer
+    pub read_connections: Option<usize>,
+    /// Tables whose row changes publish no `data.changed`
+    pub change_events_ignore: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct WindowSettings {
     pub title: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub remember_state: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LoggingSettings {
     pub level: String,
     pub file: String,
     pub append: Option<bool>,
+    pub ring_buffer_file: Option<String>,
+    pub ring_buffer_records: Option<usize>,
+    pub ring_buffer_level: Option<String>,
+    pub access_log: Option<bool>,
+    pub access_log_recent: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ServerSettings {
+    pub bind: Option<String>,
+    pub http_port: Option<u16>,
+    pub ws_port: Option<u16>,
+    pub port_fallback_attempts: Option<u16>,
+    pub compression: Option<bool>,
+    pub compression_threshold: Option<usize>,
+    /// File extensions the frontend server hands out
+    pub static_extensions: Option<Vec<String>>,
+    pub ws_allowed_origins: Option<Vec<String>>,
+    pub ws_require_token: Option<bool>,
+    pub ws_max_message_bytes: Option<usize>,
+    pub ws_max_json_bytes: Option<usize>,
+    pub ws_max_json_depth: Option<usize>,
+    pub ws_handshake_timeout_secs: Option<u64>,
+    pub ws_idle_timeout_secs: Option<u64>,
+    pub ws_write_timeout_secs: Option<u64>,
+    pub ws_max_lifetime_secs: Option<u64>,
+    /// Upgrade path and the timeouts its connections use instead
+    pub ws_endpoints: Option<BTreeMap<String, WsEndpointSettings>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WsEndpointSettings {
+    pub idle_timeout_secs: Option<u64>,
+    pub write_timeout_secs: Option<u64>,
+    pub max_lifetime_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IpcSettings {
+    pub enabled: Option<bool>,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GrpcSettings {
+    pub enabled: Option<bool>,
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DiscoverySettings {
+    pub enabled: Option<bool>,
+    pub service_type: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PluginSettings {
+    pub enabled: Option<bool>,
+    pub dir: Option<String>,
+    pub hot_reload: Option<bool>,
+    /// Per-plugin settings, keyed by plugin id
+    pub config: Option<HashMap<String, HashMap<String, serde_json::Value>>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ShellSettings {
+    pub allowed_url_schemes: Option<Vec<String>>,
+    pub allowed_paths: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MetricsSettings {
+    pub autostart: Option<bool>,
+    pub sample_interval_secs: Option<u64>,
+    pub top_processes: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SchedulerSettings {
+    pub enabled: Option<bool>,
+    pub vacuum_schedule: Option<String>,
+    pub backup_schedule: Option<String>,
+    pub checkpoint_schedule: Option<String>,
+    pub backup_dir: Option<String>,
+    pub backup_keep: Option<usize>,
+    pub log_max_size_mb: Option<u64>,
+    pub log_keep: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FsWatcherSettings {
+    pub max_watchers: Option<usize>,
+    pub debounce_ms: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FileTransferSettings {
+    pub allowed_dirs: Option<Vec<String>>,
+    pub max_file_size_mb: Option<u64>,
+    pub chunk_size_kb: Option<usize>,
+    pub upload_timeout_secs: Option<u64>,
+    pub upload_dir: Option<String>,
+    pub allowed_extensions: Option<Vec<String>>,
+    pub scan_command: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CircuitBreakerSettings {
+    pub failure_threshold: Option<u32>,
+    pub open_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CacheSettings {
+    pub enabled: Option<bool>,
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TelemetrySettings {
+    pub enabled: Option<bool>,
+    pub sentry_dsn: Option<String>,
+    pub webhook_url: Option<String>,
+    pub sample_rate: Option<f64>,
+    pub require_consent: Option<bool>,
+    pub scrub_fields: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DevToolsSettings {
+    pub enabled: Option<bool>,
+    pub enabled_in_release: Option<bool>,
+    pub local_only: Option<bool>,
+    pub require_token: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SecurityHeadersSettings {
+    pub enabled: Option<bool>,
+    pub dev_mode: Option<bool>,
+    pub content_security_policy: Option<String>,
+    pub frame_options: Option<String>,
+    pub referrer_policy: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CorsSettings {
+    pub allowed_origins: Option<Vec<String>>,
+    pub allowed_methods: Option<Vec<String>>,
+    pub allowed_headers: Option<Vec<String>>,
+    pub max_age_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SecretsSettings {
+    pub service: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionSettings {
+    pub idle_timeout_mins: Option<u64>,
+    pub max_lifetime_hours: Option<u64>,
+    pub resume_buffer_events: Option<usize>,
+    pub resume_window_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeliverySettings {
+    /// Event names or `prefix.*` patterns delivered at least once
+    pub reliable_events: Option<Vec<String>>,
+    pub ack_timeout_ms: Option<u64>,
+    pub max_attempts: Option<u32>,
+    pub max_unacked: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DevSettings {
+    /// Frontend dev server that non-API requests are forwarded to
+    pub proxy_url: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RuntimeSettings {
+    /// Threads running async tasks; 0 = number of CPUs
+    pub worker_threads: Option<usize>,
+    /// Upper bound of the threads `spawn_blocking` work may start
+    pub max_blocking_threads: Option<usize>,
+    /// Threads answering HTTP requests
+    pub http_threads: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JwtSettings {
+    pub enabled: Option<bool>,
+    pub algorithm: Option<String>,
+    pub secret: Option<String>,
+    pub private_key: Option<String>,
+    pub public_key: Option<String>,
+    pub issuer: Option<String>,
+    pub ttl_mins: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuthSettings {
+    pub reset_token_ttl_mins: Option<u64>,
+    pub verification_token_ttl_hours: Option<u64>,
+    pub min_password_length: Option<usize>,
+    pub reset_link: Option<String>,
+    pub verification_link: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MailSettings {
+    pub transport: Option<String>,
+    pub from: Option<String>,
+    pub smtp_host: Option<String>,
+    pub smtp_port: Option<u16>,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub smtp_tls: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CounterSettings {
+    pub event_sourced: Option<bool>,
+    pub snapshot_every: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OutboxSettings {
+    pub poll_interval_ms: Option<u64>,
+    pub batch_size: Option<usize>,
+    pub retention_hours: Option<u64>,
+    pub webhook_url: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BrokerSettings {
+    pub enabled: Option<bool>,
+    /// `nats` or `mqtt`
+    pub kind: Option<String>,
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub client_id: Option<String>,
+    pub prefix: Option<String>,
+    /// EventBus events sent to the broker
+    pub publish: Option<Vec<String>>,
+    /// Broker subjects or topic filters emitted as events
+    pub subscribe: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WebhookSettings {
+    pub max_attempts: Option<u32>,
+    pub timeout_secs: Option<u64>,
+    pub retention_days: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HookSettings {
+    pub enabled: Option<bool>,
+    pub secret: Option<String>,
+    /// Hook names accepted by `POST /api/hooks/{name}`; empty accepts any
+    pub names: Option<Vec<String>>,
+    pub max_body_kb: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AnalyticsSettings {
+    pub enabled: Option<bool>,
+    pub retention_days: Option<u64>,
+    /// Where opted-in daily summaries are posted; needs the `telemetry` feature
+    pub upload_url: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BatchingSettings {
+    pub enabled: Option<bool>,
+    /// Event name or `prefix.*` pattern and how its events are summarized
+    pub topics: Option<BTreeMap<String, BatchTopicSettings>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchTopicSettings {
+    pub summary: Option<String>,
+    pub interval_ms: Option<u64>,
+    pub group_by: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WorkerSettings {
+    /// Concurrent calls of a kind without its own limit; 0 = number of CPUs
+    pub default_limit: Option<usize>,
+    pub limits: Option<BTreeMap<String, usize>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ChaosSettings {
+    /// Only honoured in debug builds
+    pub enabled: Option<bool>,
+    pub db_delay_probability: Option<f64>,
+    pub db_delay_ms: Option<u64>,
+    pub ws_drop_probability: Option<f64>,
+    pub handler_error_probability: Option<f64>,
+    /// Commands that may fail; empty for every command but `hello`
+    pub commands: Option<Vec<String>>,
+    /// Seed of the draws, for a run that can be repeated; 0 = a new one each run
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RecorderSettings {
+    pub enabled: Option<bool>,
+    /// Directory of the `session-<time>.jsonl` recordings
+    pub dir: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct I18nSettings {
+    /// Locale of sessions that did not choose one
+    pub default_locale: Option<String>,
+    /// Directory of `<locale>.json` message bundles
+    pub dir: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdaterSettings {
+    pub enabled: Option<bool>,
+    /// `owner/repo` whose GitHub releases are checked
+    pub github_repo: Option<String>,
+    /// A JSON release feed, used instead of GitHub when set
+    pub feed_url: Option<String>,
+    pub check_interval_hours: Option<u64>,
+    pub include_prereleases: Option<bool>,
+    /// Part of the asset name that picks this platform's download
+    pub asset: Option<String>,
+    /// minisign public key releases are signed with
+    pub public_key: Option<String>,
+    pub auto_install: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HotkeySettings {
+    pub enabled: Option<bool>,
+    /// Shortcut (e.g. `ctrl+shift+KeyH`) and what it does
+    pub bindings: Option<BTreeMap<String, HotkeyBinding>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    /// Sent along with `hotkey.pressed` for the frontend to act on
+    pub action: Option<String>,
+    /// Run through the command bus when the shortcut is pressed
+    pub command: Option<String>,
+    pub payload: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PdfSettings {
+    /// Chromium-based browser that prints, found on the PATH and in the usual install locations when unset
+    pub browser: Option<String>,
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CaptureSettings {
+    /// Where screenshots are saved
+    pub dir: Option<String>,
+    /// Longest side of the thumbnail returned with a screenshot, in pixels
+    pub thumbnail_size: Option<u32>,
+    /// How long a capture request waits for the user's confirmation
+    pub confirm_timeout_secs: Option<u64>,
 }
 
 impl Default for AppConfig {
@@ -53,19 +526,77 @@ impl Default for AppConfig {
             database: DatabaseSettings {
                 path: String::from("app.db"),
                 create_sample_data: Some(true),
+                encrypted: None,
+                encryption_key: None,
+                synchronous: None,
+                cache_size: None,
+                mmap_size: None,
+                busy_timeout_ms: None,
+                read_connections: None,
+                change_events_ignore: None,
             },
             window: WindowSettings {
                 title: String::from("Rust WebUI Application"),
+                width: None,
+                height: None,
+                remember_state: Some(true),
             },
             logging: LoggingSettings {
                 level: String::from("info"),
                 file: String::from("application.log"),
                 append: Some(true),
+                ring_buffer_file: None,
+                ring_buffer_records: None,
+                ring_buffer_level: None,
+                access_log: None,
+                access_log_recent: None,
             },
+            server: ServerSettings::default(),
+            ipc: IpcSettings::default(),
+            grpc: GrpcSettings::default(),
+            discovery: DiscoverySettings::default(),
+            plugins: PluginSettings::default(),
+            shell: ShellSettings::default(),
+            metrics: MetricsSettings::default(),
+            scheduler: SchedulerSettings::default(),
+            fs_watcher: FsWatcherSettings::default(),
+            file_transfer: FileTransferSettings::default(),
+            circuit_breaker: CircuitBreakerSettings::default(),
+            cache: CacheSettings::default(),
+            telemetry: TelemetrySettings::default(),
+            devtools: DevToolsSettings::default(),
+            security_headers: SecurityHeadersSettings::default(),
+            cors: CorsSettings::default(),
+            secrets: SecretsSettings::default(),
+            sessions: SessionSettings::default(),
+            jwt: JwtSettings::default(),
+            auth: AuthSettings::default(),
+            mail: MailSettings::default(),
+            counters: CounterSettings::default(),
+            outbox: OutboxSettings::default(),
+            broker: BrokerSettings::default(),
+            webhooks: WebhookSettings::default(),
+            hooks: HookSettings::default(),
+            updater: UpdaterSettings::default(),
+            hotkeys: HotkeySettings::default(),
+            pdf: PdfSettings::default(),
+            capture: CaptureSettings::default(),
+            i18n: I18nSettings::default(),
+            analytics: AnalyticsSettings::default(),
+            batching: BatchingSettings::default(),
+            workers: WorkerSettings::default(),
+            chaos: ChaosSettings::default(),
+            recorder: RecorderSettings::default(),
+            delivery: DeliverySettings::default(),
+            dev: DevSettings::default(),
+            runtime: RuntimeSettings::default(),
+            features: BTreeMap::new(),
         }
     }
 }
 
+static APP_CONFIG: OnceLock<AppConfig> = OnceLock::new();
+
 impl AppConfig {
     pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
         // Try to find config file
@@ -101,7 +632,7 @@ impl AppConfig {
         if let Some(content) = config_content {
             match toml::from_str(&content) {
                 Ok(config) => {
-                    println!("Loaded configuration from: {}", config_path);
+                    eprintln!("Loaded configuration from: {}", config_path);
                     return Ok(config);
                 }
                 Err(e) => {
@@ -115,6 +646,17 @@ impl AppConfig {
         Ok(AppConfig::default())
     }
 
+    /// Make this configuration available to code without direct access to it
+    /// (WebSocket commands, services). Only the first call takes effect.
+    pub fn install_global(self) -> &'static AppConfig {
+        APP_CONFIG.get_or_init(|| self)
+    }
+
+    /// Installed configuration, or the defaults if none was installed yet
+    pub fn global() -> &'static AppConfig {
+        APP_CONFIG.get_or_init(AppConfig::default)
+    }
+
     pub fn get_app_name(&self) -> &str {
         &self.app.name
     }
@@ -131,10 +673,64 @@ impl AppConfig {
         self.database.create_sample_data.unwrap_or(true)
     }
 
+    pub fn is_db_encrypted(&self) -> bool {
+        self.database.encrypted.unwrap_or(false)
+    }
+
+    #[cfg_attr(not(feature = "encrypted-db"), allow(dead_code))]
+    pub fn get_db_encryption_key(&self) -> Option<&str> {
+        self.database.encryption_key.as_deref().filter(|key| !key.is_empty())
+    }
+
+    /// SQLite's default, FULL, unless set to another valid mode
+    pub fn get_db_synchronous(&self) -> &str {
+        let mode = self.database.synchronous.as_deref().unwrap_or_default();
+        crate::infrastructure::database::maintenance::SYNCHRONOUS_MODES
+            .iter()
+            .find(|valid| valid.eq_ignore_ascii_case(mode))
+            .copied()
+            .unwrap_or("FULL")
+    }
+
+    /// 2 MiB, SQLite's default
+    pub fn get_db_cache_size(&self) -> i64 {
+        self.database.cache_size.unwrap_or(-2000)
+    }
+
+    pub fn get_db_mmap_size(&self) -> u64 {
+        self.database.mmap_size.unwrap_or(0)
+    }
+
+    pub fn get_db_busy_timeout_ms(&self) -> u64 {
+        self.database.busy_timeout_ms.unwrap_or(5000)
+    }
+
+    pub fn get_db_read_connections(&self) -> usize {
+        self.database.read_connections.unwrap_or(2).min(16)
+    }
+
+    pub fn get_db_change_events_ignore(&self) -> Vec<String> {
+        self.database
+            .change_events_ignore
+            .clone()
+            .unwrap_or_else(|| changes::DEFAULT_IGNORED.iter().map(|table| table.to_string()).collect())
+    }
+
     pub fn get_window_title(&self) -> &str {
         &self.window.title
     }
 
+    pub fn get_window_size(&self) -> Option<(u32, u32)> {
+        match (self.window.width, self.window.height) {
+            (Some(width), Some(height)) => Some((width, height)),
+            _ => None,
+        }
+    }
+
+    pub fn should_remember_window_state(&self) -> bool {
+        self.window.remember_state.unwrap_or(true)
+    }
+
     pub fn get_log_level(&self) -> &str {
         &self.logging.level
     }
@@ -146,231 +742,924 @@ impl AppConfig {
     pub fn is_append_log(&self) -> bool {
         self.logging.append.unwrap_or(true)
     }
-}
 
-// Global guard to ensure the tracing subscriber stays active
+    /// Memory-mapped file of the last log records; `None` when empty or unset
+    pub fn get_log_ring_buffer_file(&self) -> Option<&str> {
+        self.logging.ring_buffer_file.as_deref().filter(|file| !file.is_empty())
+    }
 
-pub fn init_logging_with_config(
-    log_file: Option<&str>,
-    log_level: &str,
-    _append: bool,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Configure log level
-    let _level = match log_level {
-        "trace" => Level::TRACE,
-        "debug" => Level::DEBUG,
-        "info" => Level::INFO,
-        "warn" => Level::WARN,
-        "error" => Level::ERROR,
-        _ => Level::INFO,
-    };
+    pub fn get_log_ring_buffer_records(&self) -> usize {
+        self.logging.ring_buffer_records.unwrap_or(4096).clamp(16, 1_000_000)
+    }
 
-    // Set up environment filter
-    let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new(format!("rustwebui_app={}", log_level)));
+    /// Filter of the ring buffer, independent of the console's
+    pub fn get_log_ring_buffer_level(&self) -> &str {
+        self.logging.ring_buffer_level.as_deref().unwrap_or("rustwebui_app=debug")
+    }
 
-    // Create subscriber with console logging (without timestamps)
-    let subscriber = tracing_subscriber::registry().with(env_filter).with(
-        fmt::layer()
-            .with_ansi(true) // ANSI colors for console
-            .with_target(true)
-            .with_line_number(true)
-            .without_time() // Remove timestamps
-            .boxed(),
-    );
+    /// Log every HTTP request at info level
+    pub fn is_access_log_enabled(&self) -> bool {
+        self.logging.access_log.unwrap_or(true)
+    }
 
-    // Set the global subscriber
-    tracing::subscriber::set_global_default(subscriber)
-        .map_err(|err| format!("Failed to set tracing subscriber: {}", err))?;
+    /// HTTP requests kept for `http.recent`
+    pub fn get_access_log_recent(&self) -> usize {
+        self.logging.access_log_recent.unwrap_or(200).min(10_000)
+    }
 
-    info!(
-        message = "Logging system initialized",
-        log_level = log_level,
-        log_file = log_file.unwrap_or("none")
-    );
+    pub fn get_shell_allowed_url_schemes(&self) -> Vec<String> {
+        self.shell
+            .allowed_url_schemes
+            .clone()
+            .unwrap_or_else(|| vec!["http".to_string(), "https".to_string(), "mailto".to_string()])
+    }
 
-    Ok(())
-}
+    pub fn get_shell_allowed_paths(&self) -> Vec<String> {
+        self.shell
+            .allowed_paths
+            .clone()
+            .unwrap_or_else(|| vec!["~".to_string()])
+    }
 
-pub struct Database {
-    connection: Arc<Mutex<Connection>>,
-}
+    pub fn should_autostart_metrics(&self) -> bool {
+        self.metrics.autostart.unwrap_or(true)
+    }
+
+    pub fn get_metrics_sample_interval_secs(&self) -> u64 {
+        self.metrics.sample_interval_secs.unwrap_or(2).max(1)
+    }
 
-impl Database {
-    pub fn new(db_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let conn = Connection::open(db_path)?;
+    pub fn get_metrics_top_processes(&self) -> usize {
+        self.metrics.top_processes.unwrap_or(5)
+    }
 
-        // Enable WAL mode for better concurrency
-        conn.execute_batch("PRAGMA journal_mode=WAL;")?;
+    pub fn is_scheduler_enabled(&self) -> bool {
+        self.scheduler.enabled.unwrap_or(true)
+    }
 
-        // Emit database connection event
-        if let Ok(bus) =
-            std::panic::catch_unwind(|| crate::infrastructure::event_bus::EventBus::global())
-        {
-            if let Err(e) = futures::executor::block_on(bus.emit_simple(
-                &crate::infrastructure::event_bus::AppEventType::DatabaseOperation.to_string(),
-                serde_json::json!({
-                    "operation": "connect",
-                    "database": db_path
-                }),
-            )) {
-                eprintln!("Failed to emit database connection event: {}", e);
-            }
+    pub fn get_vacuum_schedule(&self) -> &str {
+        self.scheduler.vacuum_schedule.as_deref().unwrap_or("0 0 3 * * Sun")
+    }
+
+    pub fn get_checkpoint_schedule(&self) -> &str {
+        self.scheduler.checkpoint_schedule.as_deref().unwrap_or("0 */15 * * * *")
+    }
+
+    pub fn get_backup_schedule(&self) -> &str {
+        self.scheduler.backup_schedule.as_deref().unwrap_or("0 30 2 * * *")
+    }
+
+    pub fn get_backup_dir(&self) -> &str {
+        self.scheduler.backup_dir.as_deref().unwrap_or("backups")
+    }
+
+    pub fn get_backup_keep(&self) -> usize {
+        self.scheduler.backup_keep.unwrap_or(7)
+    }
+
+    pub fn get_log_max_size_mb(&self) -> u64 {
+        self.scheduler.log_max_size_mb.unwrap_or(10)
+    }
+
+    pub fn get_log_keep(&self) -> usize {
+        self.scheduler.log_keep.unwrap_or(5)
+    }
+
+    pub fn get_server_bind(&self) -> &str {
+        self.server.bind.as_deref().unwrap_or("127.0.0.1")
+    }
+
+    pub fn get_http_port(&self) -> u16 {
+        self.server.http_port.unwrap_or(8080)
+    }
+
+    pub fn get_ws_port(&self) -> u16 {
+        self.server.ws_port.unwrap_or(9000)
+    }
+
+    pub fn get_port_fallback_attempts(&self) -> u16 {
+        self.server.port_fallback_attempts.unwrap_or(10)
+    }
+
+    pub fn is_ws_compression_enabled(&self) -> bool {
+        self.server.compression.unwrap_or(true)
+    }
+
+    pub fn get_ws_compression_threshold(&self) -> usize {
+        self.server.compression_threshold.unwrap_or(1024)
+    }
+
+    /// Lower-case, without the dot; `*` allows any
+    pub fn get_static_extensions(&self) -> Vec<String> {
+        match &self.server.static_extensions {
+            Some(extensions) => extensions.iter().map(|ext| ext.trim_start_matches('.').to_ascii_lowercase()).collect(),
+            None => [
+                "html", "js", "mjs", "css", "map", "json", "txt", "wasm",
+                "png", "jpg", "jpeg", "gif", "svg", "webp", "avif", "ico",
+                "woff", "woff2", "ttf", "otf", "mp3", "mp4", "ogg", "wav", "webm",
+            ]
+            .map(String::from)
+            .to_vec(),
         }
+    }
+
+    /// Page origins besides the app's own HTTP origin that may open the WebSocket
+    pub fn get_ws_allowed_origins(&self) -> Vec<String> {
+        self.server.ws_allowed_origins.clone().unwrap_or_default()
+    }
 
-        Ok(Database {
-            connection: Arc::new(Mutex::new(conn)),
+    pub fn is_ws_token_required(&self) -> bool {
+        self.server.ws_require_token.unwrap_or(false)
+    }
+
+    /// Largest WebSocket message accepted, never less than a file chunk frame
+    pub fn get_ws_max_message_bytes(&self) -> usize {
+        let chunk_frame = (self.get_file_transfer_chunk_size_kb() + 64) * 1024;
+        self.server.ws_max_message_bytes.unwrap_or(16 << 20).max(chunk_frame)
+    }
+
+    pub fn get_ws_max_json_bytes(&self) -> usize {
+        self.server.ws_max_json_bytes.unwrap_or(1 << 20).max(1024)
+    }
+
+    pub fn get_ws_max_json_depth(&self) -> usize {
+        self.server.ws_max_json_depth.unwrap_or(32).max(1)
+    }
+
+    pub fn get_ws_handshake_timeout_secs(&self) -> u64 {
+        self.server.ws_handshake_timeout_secs.unwrap_or(10).max(1)
+    }
+
+    /// 0 = idle connections are never closed
+    pub fn get_ws_idle_timeout_secs(&self) -> u64 {
+        self.server.ws_idle_timeout_secs.unwrap_or(300)
+    }
+
+    pub fn get_ws_write_timeout_secs(&self) -> u64 {
+        self.server.ws_write_timeout_secs.unwrap_or(10).max(1)
+    }
+
+    /// 0 = connections may stay open indefinitely
+    pub fn get_ws_max_lifetime_secs(&self) -> u64 {
+        self.server.ws_max_lifetime_secs.unwrap_or(0)
+    }
+
+    pub fn get_ws_endpoints(&self) -> BTreeMap<String, WsEndpointSettings> {
+        self.server.ws_endpoints.clone().unwrap_or_default()
+    }
+
+    pub fn is_ipc_enabled(&self) -> bool {
+        self.ipc.enabled.unwrap_or(true)
+    }
+
+    /// IPC socket (or pipe) path; `None` means the platform default
+    pub fn get_ipc_path(&self) -> Option<&str> {
+        self.ipc.path.as_deref().filter(|path| !path.is_empty())
+    }
+
+    pub fn is_plugins_enabled(&self) -> bool {
+        self.plugins.enabled.unwrap_or(true)
+    }
+
+    pub fn get_plugins_dir(&self) -> &str {
+        self.plugins.dir.as_deref().unwrap_or("plugins")
+    }
+
+    /// Reload rebuilt plugin libraries while running; on by default in debug builds
+    pub fn is_plugin_hot_reload_enabled(&self) -> bool {
+        self.plugins.hot_reload.unwrap_or(cfg!(debug_assertions))
+    }
+
+    pub fn get_plugin_config(&self) -> HashMap<String, HashMap<String, serde_json::Value>> {
+        self.plugins.config.clone().unwrap_or_default()
+    }
+
+    pub fn get_file_transfer_allowed_dirs(&self) -> Vec<String> {
+        self.file_transfer
+            .allowed_dirs
+            .clone()
+            .unwrap_or_else(|| vec!["~/Downloads".to_string()])
+    }
+
+    pub fn get_file_transfer_max_file_size_mb(&self) -> u64 {
+        self.file_transfer.max_file_size_mb.unwrap_or(100)
+    }
+
+    pub fn get_file_transfer_chunk_size_kb(&self) -> usize {
+        self.file_transfer.chunk_size_kb.unwrap_or(256).max(1)
+    }
+
+    pub fn get_file_transfer_upload_timeout_secs(&self) -> u64 {
+        self.file_transfer.upload_timeout_secs.unwrap_or(300)
+    }
+
+    pub fn get_file_transfer_upload_dir(&self) -> Option<String> {
+        self.file_transfer.upload_dir.clone().filter(|dir| !dir.is_empty())
+    }
+
+    pub fn get_file_transfer_allowed_extensions(&self) -> Vec<String> {
+        self.file_transfer
+            .allowed_extensions
+            .iter()
+            .flatten()
+            .map(|extension| extension.trim_start_matches('.').to_lowercase())
+            .collect()
+    }
+
+    pub fn get_file_transfer_scan_command(&self) -> Vec<String> {
+        self.file_transfer.scan_command.clone().unwrap_or_default()
+    }
+
+    pub fn get_fs_max_watchers(&self) -> usize {
+        self.fs_watcher.max_watchers.unwrap_or(8)
+    }
+
+    pub fn get_fs_debounce_ms(&self) -> u64 {
+        self.fs_watcher.debounce_ms.unwrap_or(300)
+    }
+
+    pub fn get_circuit_failure_threshold(&self) -> u32 {
+        self.circuit_breaker.failure_threshold.unwrap_or(5).max(1)
+    }
+
+    pub fn get_circuit_open_secs(&self) -> u64 {
+        self.circuit_breaker.open_secs.unwrap_or(30)
+    }
+
+    pub fn is_cache_enabled(&self) -> bool {
+        self.cache.enabled.unwrap_or(true)
+    }
+
+    pub fn get_cache_ttl_secs(&self) -> u64 {
+        self.cache.ttl_secs.unwrap_or(30)
+    }
+
+    /// Devtools routes are served in debug builds unless disabled, and in
+    /// release builds only with `enabled_in_release = true`
+    pub fn is_devtools_enabled(&self) -> bool {
+        self.devtools.enabled.unwrap_or(true)
+            && (cfg!(debug_assertions) || self.devtools.enabled_in_release.unwrap_or(false))
+    }
+
+    pub fn is_devtools_local_only(&self) -> bool {
+        self.devtools.local_only.unwrap_or(true)
+    }
+
+    pub fn is_devtools_token_required(&self) -> bool {
+        self.devtools.require_token.unwrap_or(true)
+    }
+
+    pub fn is_security_headers_enabled(&self) -> bool {
+        self.security_headers.enabled.unwrap_or(true)
+    }
+
+    pub fn is_security_headers_dev_mode(&self) -> bool {
+        self.security_headers.dev_mode.unwrap_or(false)
+    }
+
+    /// A policy replacing the generated one; `None` when unset or empty
+    pub fn get_security_headers_csp(&self) -> Option<String> {
+        self.security_headers
+            .content_security_policy
+            .clone()
+            .filter(|policy| !policy.trim().is_empty())
+    }
+
+    pub fn get_security_headers_frame_options(&self) -> String {
+        self.security_headers.frame_options.clone().unwrap_or_else(|| "DENY".to_string())
+    }
+
+    pub fn get_security_headers_referrer_policy(&self) -> String {
+        self.security_headers
+            .referrer_policy
+            .clone()
+            .unwrap_or_else(|| "no-referrer".to_string())
+    }
+
+    /// Origins besides the app's own pages; defaults to the rsbuild dev server
+    pub fn get_cors_allowed_origins(&self) -> Vec<String> {
+        self.cors
+            .allowed_origins
+            .clone()
+            .unwrap_or_else(|| vec!["http://localhost:3000".to_string(), "http://127.0.0.1:3000".to_string()])
+    }
+
+    pub fn get_cors_allowed_methods(&self) -> Vec<String> {
+        self.cors
+            .allowed_methods
+            .clone()
+            .unwrap_or_else(|| ["GET", "POST", "OPTIONS"].map(String::from).to_vec())
+    }
+
+    pub fn get_cors_allowed_headers(&self) -> Vec<String> {
+        self.cors
+            .allowed_headers
+            .clone()
+            .unwrap_or_else(|| ["Content-Type", "Authorization", "X-DevTools-Token"].map(String::from).to_vec())
+    }
+
+    pub fn get_cors_max_age_secs(&self) -> u64 {
+        self.cors.max_age_secs.unwrap_or(600)
+    }
+
+    pub fn get_secrets_service(&self) -> &str {
+        self.secrets.service.as_deref().filter(|service| !service.is_empty()).unwrap_or("rustwebui-app")
+    }
+
+    pub fn get_session_idle_timeout_mins(&self) -> u64 {
+        self.sessions.idle_timeout_mins.unwrap_or(1440).max(1)
+    }
+
+    pub fn get_session_max_lifetime_hours(&self) -> u64 {
+        self.sessions.max_lifetime_hours.unwrap_or(720).max(1)
+    }
+
+    /// Events kept per session for a client that reconnects; 0 = no resuming
+    pub fn get_session_resume_buffer_events(&self) -> usize {
+        self.sessions.resume_buffer_events.unwrap_or(256).min(10_000)
+    }
+
+    pub fn get_session_resume_window_secs(&self) -> u64 {
+        self.sessions.resume_window_secs.unwrap_or(120)
+    }
+
+    /// Events delivered at least once; the task outcomes unless configured
+    pub fn get_delivery_reliable_events(&self) -> Vec<String> {
+        self.delivery.reliable_events.clone().unwrap_or_else(|| {
+            ["task.completed", "task.failed", "task.cancelled"].map(String::from).to_vec()
         })
     }
 
-    pub fn init(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let conn = self.connection.lock().unwrap();
-
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS users (
-                id INTEGER PRIMARY KEY,
-                name TEXT NOT NULL,
-                email TEXT NOT NULL,
-                role TEXT NOT NULL
-            )",
-            [],
-        )?;
-
-        // Emit database initialization event
-        if let Ok(bus) =
-            std::panic::catch_unwind(|| crate::infrastructure::event_bus::EventBus::global())
-        {
-            if let Err(e) = futures::executor::block_on(bus.emit_simple(
-                &crate::infrastructure::event_bus::AppEventType::DatabaseOperation.to_string(),
-                serde_json::json!({
-                    "operation": "init_schema",
-                    "table": "users"
-                }),
-            )) {
-                eprintln!("Failed to emit database initialization event: {}", e);
-            }
-        }
+    pub fn get_delivery_ack_timeout_ms(&self) -> u64 {
+        self.delivery.ack_timeout_ms.unwrap_or(5000).max(100)
+    }
 
-        info!("Database schema initialized");
-        Ok(())
+    pub fn get_delivery_max_attempts(&self) -> u32 {
+        self.delivery.max_attempts.unwrap_or(5).max(1)
     }
 
-    pub fn insert_sample_data(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let conn = self.connection.lock().unwrap();
+    /// Unacknowledged events kept per session; the oldest are dropped beyond it
+    pub fn get_delivery_max_unacked(&self) -> usize {
+        self.delivery.max_unacked.unwrap_or(256).max(1)
+    }
 
-        // Insert sample users if table is empty
-        let count: i64 = conn.query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0))?;
+    pub fn get_dev_proxy_url(&self) -> Option<&str> {
+        self.dev.proxy_url.as_deref().map(str::trim).filter(|url| !url.is_empty())
+    }
 
-        if count == 0 {
-            let sample_users = [
-                ("John Doe", "john@example.com", "admin"),
-                ("Jane Smith", "jane@example.com", "editor"),
-                ("Bob Johnson", "bob@example.com", "user"),
-                ("Alice Brown", "alice@example.com", "user"),
-            ];
+    pub fn is_jwt_enabled(&self) -> bool {
+        self.jwt.enabled.unwrap_or(false)
+    }
 
-            for (name, email, role) in &sample_users {
-                conn.execute(
-                    "INSERT INTO users (name, email, role) VALUES (?1, ?2, ?3)",
-                    rusqlite::params![name, email, role],
-                )?;
-            }
+    pub fn get_jwt_algorithm(&self) -> &str {
+        self.jwt.algorithm.as_deref().unwrap_or("HS256")
+    }
 
-            // Emit sample data insertion event
-            if let Ok(bus) =
-                std::panic::catch_unwind(|| crate::infrastructure::event_bus::EventBus::global())
-            {
-                if let Err(e) = futures::executor::block_on(bus.emit_simple(
-                    &crate::infrastructure::event_bus::AppEventType::DataChanged.to_string(),
-                    serde_json::json!({
-                        "operation": "insert_sample_data",
-                        "table": "users",
-                        "count": sample_users.len()
-                    }),
-                )) {
-                    eprintln!("Failed to emit sample data insertion event: {}", e);
-                }
-            }
+    /// HS256 signing secret, plain or a `secret:<name>` reference; `None` when unset or empty
+    pub fn get_jwt_secret(&self) -> Option<&str> {
+        self.jwt.secret.as_deref().filter(|secret| !secret.is_empty())
+    }
 
-            info!("Sample data inserted into database");
-        } else {
-            info!("Sample data already exists, skipping insertion");
+    /// RS256 private key: a PEM file path or a `secret:<name>` reference
+    pub fn get_jwt_private_key(&self) -> Option<&str> {
+        self.jwt.private_key.as_deref().filter(|key| !key.is_empty())
+    }
+
+    /// RS256 public key: a PEM file path or a `secret:<name>` reference
+    pub fn get_jwt_public_key(&self) -> Option<&str> {
+        self.jwt.public_key.as_deref().filter(|key| !key.is_empty())
+    }
+
+    pub fn get_jwt_issuer(&self) -> &str {
+        self.jwt.issuer.as_deref().filter(|issuer| !issuer.is_empty()).unwrap_or("rustwebui-app")
+    }
+
+    pub fn get_jwt_ttl_mins(&self) -> u64 {
+        self.jwt.ttl_mins.unwrap_or(60).max(1)
+    }
+
+    pub fn get_auth_reset_token_ttl_mins(&self) -> u64 {
+        self.auth.reset_token_ttl_mins.unwrap_or(60).max(1)
+    }
+
+    pub fn get_auth_verification_token_ttl_hours(&self) -> u64 {
+        self.auth.verification_token_ttl_hours.unwrap_or(48).max(1)
+    }
+
+    pub fn get_auth_min_password_length(&self) -> usize {
+        self.auth.min_password_length.unwrap_or(10).max(1)
+    }
+
+    /// Link mailed for a password reset, `{token}` replaced; `None` mails the bare token
+    pub fn get_auth_reset_link(&self) -> Option<&str> {
+        self.auth.reset_link.as_deref().filter(|link| !link.is_empty())
+    }
+
+    /// Link mailed for an email verification, `{token}` replaced; `None` mails the bare token
+    pub fn get_auth_verification_link(&self) -> Option<&str> {
+        self.auth.verification_link.as_deref().filter(|link| !link.is_empty())
+    }
+
+    /// `log` writes mails to the log instead of sending them; `smtp` needs the `smtp` feature
+    pub fn get_mail_transport(&self) -> &str {
+        self.mail.transport.as_deref().unwrap_or("log")
+    }
+
+    #[cfg_attr(not(feature = "smtp"), allow(dead_code))]
+    pub fn get_mail_from(&self) -> &str {
+        self.mail.from.as_deref().filter(|from| !from.is_empty()).unwrap_or("Rust WebUI App <noreply@localhost>")
+    }
+
+    #[cfg_attr(not(feature = "smtp"), allow(dead_code))]
+    pub fn get_mail_smtp_host(&self) -> &str {
+        self.mail.smtp_host.as_deref().unwrap_or("localhost")
+    }
+
+    #[cfg_attr(not(feature = "smtp"), allow(dead_code))]
+    pub fn get_mail_smtp_port(&self) -> u16 {
+        self.mail.smtp_port.unwrap_or(587)
+    }
+
+    #[cfg_attr(not(feature = "smtp"), allow(dead_code))]
+    pub fn get_mail_smtp_username(&self) -> Option<&str> {
+        self.mail.smtp_username.as_deref().filter(|username| !username.is_empty())
+    }
+
+    /// Plain or a `secret:<name>` reference
+    #[cfg_attr(not(feature = "smtp"), allow(dead_code))]
+    pub fn get_mail_smtp_password(&self) -> Option<&str> {
+        self.mail.smtp_password.as_deref().filter(|password| !password.is_empty())
+    }
+
+    /// `starttls` (default), `tls` or `none`
+    #[cfg_attr(not(feature = "smtp"), allow(dead_code))]
+    pub fn get_mail_smtp_tls(&self) -> &str {
+        self.mail.smtp_tls.as_deref().unwrap_or("starttls")
+    }
+
+    /// Keep counters as event streams instead of rows of `counters`
+    pub fn is_counters_event_sourced(&self) -> bool {
+        self.counters.event_sourced.unwrap_or(false)
+    }
+
+    /// Events between snapshots of an event-sourced counter
+    pub fn get_counters_snapshot_every(&self) -> u64 {
+        self.counters.snapshot_every.unwrap_or(50).max(1)
+    }
+
+    /// How often the outbox relay looks for due events it was not woken for
+    pub fn get_outbox_poll_interval_ms(&self) -> u64 {
+        self.outbox.poll_interval_ms.unwrap_or(1000).max(10)
+    }
+
+    pub fn get_outbox_batch_size(&self) -> usize {
+        self.outbox.batch_size.unwrap_or(100).max(1)
+    }
+
+    /// How long published events stay in the outbox
+    pub fn get_outbox_retention_hours(&self) -> u64 {
+        self.outbox.retention_hours.unwrap_or(24)
+    }
+
+    /// Where the `outbox-webhook` feature POSTs every event
+    pub fn get_outbox_webhook_url(&self) -> Option<&str> {
+        self.outbox.webhook_url.as_deref().filter(|url| !url.is_empty())
+    }
+
+    /// Accept triggers on `POST /api/hooks/{name}`
+    pub fn is_hooks_enabled(&self) -> bool {
+        self.hooks.enabled.unwrap_or(false)
+    }
+
+    /// Shared secret of incoming hooks, plain or a `secret:<name>` reference
+    pub fn get_hooks_secret(&self) -> Option<&str> {
+        self.hooks.secret.as_deref().filter(|secret| !secret.is_empty())
+    }
+
+    pub fn get_hooks_names(&self) -> &[String] {
+        self.hooks.names.as_deref().unwrap_or_default()
+    }
+
+    /// Largest hook body accepted
+    pub fn get_hooks_max_body_kb(&self) -> usize {
+        self.hooks.max_body_kb.unwrap_or(64).max(1)
+    }
+
+    pub fn get_default_locale(&self) -> &str {
+        self.i18n.default_locale.as_deref().filter(|locale| !locale.is_empty()).unwrap_or("en")
+    }
+
+    /// Directory of extra message bundles, if any
+    pub fn get_i18n_dir(&self) -> Option<&str> {
+        self.i18n.dir.as_deref().filter(|dir| !dir.is_empty())
+    }
+
+    /// Count feature usage locally
+    pub fn is_analytics_enabled(&self) -> bool {
+        self.analytics.enabled.unwrap_or(true)
+    }
+
+    /// Days of usage counts kept
+    pub fn get_analytics_retention_days(&self) -> u64 {
+        self.analytics.retention_days.unwrap_or(90).max(1)
+    }
+
+    /// Endpoint of opted-in usage summaries, plain or a `secret:<name>` reference
+    pub fn get_analytics_upload_url(&self) -> Option<&str> {
+        self.analytics.upload_url.as_deref().filter(|url| !url.is_empty())
+    }
+
+    pub fn is_batching_enabled(&self) -> bool {
+        self.batching.enabled.unwrap_or(true)
+    }
+
+    /// Batched topics; `database.operation` summarized as `database.activity` every 2s unless configured
+    pub fn get_batching_topics(&self) -> BTreeMap<String, BatchTopicSettings> {
+        self.batching.topics.clone().unwrap_or_else(|| {
+            BTreeMap::from([(
+                "database.operation".to_string(),
+                BatchTopicSettings {
+                    summary: Some("database.activity".to_string()),
+                    interval_ms: Some(2000),
+                    group_by: Some("operation".to_string()),
+                },
+            )])
+        })
+    }
+
+    pub fn get_worker_default_limit(&self) -> usize {
+        match self.workers.default_limit {
+            Some(limit) if limit > 0 => limit,
+            _ => std::thread::available_parallelism().map_or(1, |cpus| cpus.get()),
+        }
+    }
+
+    /// Limits per worker kind; one `images` and one `jobs` call at a time unless configured
+    pub fn get_worker_limits(&self) -> BTreeMap<String, usize> {
+        self.workers
+            .limits
+            .clone()
+            .unwrap_or_else(|| BTreeMap::from([("images".to_string(), 1), ("jobs".to_string(), 1)]))
+    }
+
+    pub fn get_runtime_worker_threads(&self) -> usize {
+        match self.runtime.worker_threads {
+            Some(threads) if threads > 0 => threads.min(256),
+            _ => std::thread::available_parallelism().map_or(1, |cpus| cpus.get()),
         }
+    }
+
+    /// 512 by default, as tokio's own
+    pub fn get_runtime_max_blocking_threads(&self) -> usize {
+        self.runtime.max_blocking_threads.unwrap_or(512).clamp(1, 4096)
+    }
+
+    /// One by default: requests are answered in turn
+    pub fn get_http_threads(&self) -> usize {
+        self.runtime.http_threads.unwrap_or(1).clamp(1, 64)
+    }
+
+    /// Fault injection is for development: release builds ignore `[chaos]`
+    pub fn is_chaos_enabled(&self) -> bool {
+        self.chaos.enabled.unwrap_or(false) && cfg!(debug_assertions)
+    }
+
+    pub fn get_chaos_db_delay_probability(&self) -> f64 {
+        self.chaos.db_delay_probability.unwrap_or(0.0).clamp(0.0, 1.0)
+    }
+
+    pub fn get_chaos_db_delay_ms(&self) -> u64 {
+        self.chaos.db_delay_ms.unwrap_or(500)
+    }
+
+    pub fn get_chaos_ws_drop_probability(&self) -> f64 {
+        self.chaos.ws_drop_probability.unwrap_or(0.0).clamp(0.0, 1.0)
+    }
+
+    pub fn get_chaos_handler_error_probability(&self) -> f64 {
+        self.chaos.handler_error_probability.unwrap_or(0.0).clamp(0.0, 1.0)
+    }
+
+    pub fn get_chaos_commands(&self) -> Vec<String> {
+        self.chaos.commands.clone().unwrap_or_default()
+    }
+
+    pub fn get_chaos_seed(&self) -> Option<u64> {
+        self.chaos.seed.filter(|seed| *seed != 0)
+    }
+
+    pub fn is_recorder_enabled(&self) -> bool {
+        self.recorder.enabled.unwrap_or(false)
+    }
 
-        Ok(())
+    pub fn get_recorder_dir(&self) -> &str {
+        self.recorder.dir.as_deref().filter(|dir| !dir.is_empty()).unwrap_or("sessions")
     }
 
-    // Method to get all users with event emission
-    pub fn get_all_users(&self) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error>> {
-        let conn = self.connection.lock().unwrap();
+    /// Browser printing `document.export_pdf`, if configured
+    pub fn get_pdf_browser(&self) -> Option<&str> {
+        self.pdf.browser.as_deref().filter(|browser| !browser.is_empty())
+    }
+
+    /// 60 seconds by default
+    pub fn get_pdf_timeout_secs(&self) -> u64 {
+        self.pdf.timeout_secs.unwrap_or(60).max(1)
+    }
+
+    /// Feature flags as configured, before runtime overrides
+    pub fn get_feature_flags(&self) -> &BTreeMap<String, bool> {
+        &self.features
+    }
+}
+
+/// `[grpc]` settings, only read when built with the `grpc` feature
+#[cfg(feature = "grpc")]
+impl AppConfig {
+    pub fn is_grpc_enabled(&self) -> bool {
+        self.grpc.enabled.unwrap_or(true)
+    }
+
+    pub fn get_grpc_port(&self) -> u16 {
+        self.grpc.port.unwrap_or(50051)
+    }
+}
+
+/// `[discovery]` settings, only read when built with the `discovery` feature
+#[cfg(feature = "discovery")]
+impl AppConfig {
+    pub fn is_discovery_enabled(&self) -> bool {
+        self.discovery.enabled.unwrap_or(true)
+    }
+
+    pub fn get_discovery_service_type(&self) -> &str {
+        self.discovery
+            .service_type
+            .as_deref()
+            .filter(|ty| !ty.is_empty())
+            .unwrap_or("_rustwebui._tcp.local.")
+    }
+}
+
+/// `[telemetry]` settings, only read when built with the `telemetry` feature
+#[cfg(feature = "telemetry")]
+impl AppConfig {
+    pub fn is_telemetry_enabled(&self) -> bool {
+        self.telemetry.enabled.unwrap_or(false)
+    }
+
+    pub fn get_telemetry_sentry_dsn(&self) -> Option<&str> {
+        self.telemetry.sentry_dsn.as_deref().filter(|dsn| !dsn.is_empty())
+    }
+
+    pub fn get_telemetry_webhook_url(&self) -> Option<&str> {
+        self.telemetry.webhook_url.as_deref().filter(|url| !url.is_empty())
+    }
+
+    pub fn get_telemetry_sample_rate(&self) -> f64 {
+        self.telemetry.sample_rate.unwrap_or(1.0).clamp(0.0, 1.0)
+    }
+
+    pub fn is_telemetry_consent_required(&self) -> bool {
+        self.telemetry.require_consent.unwrap_or(true)
+    }
 
-        let mut stmt = conn.prepare("SELECT id, name, email, role FROM users")?;
-        let user_iter = stmt.query_map([], |row| {
-            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-        })?;
+    pub fn get_telemetry_scrub_fields(&self) -> Vec<String> {
+        self.telemetry.scrub_fields.clone().unwrap_or_else(|| {
+            ["email", "name", "username", "user", "ip", "path", "source", "destination"]
+                .iter()
+                .map(|field| field.to_string())
+                .collect()
+        })
+    }
+}
 
-        let mut users = Vec::new();
-        for user_result in user_iter {
-            let (id, name, email, role): (i32, String, String, String) = user_result?;
-            users.push(serde_json::json!({
-                "id": id,
-                "name": name,
-                "email": email,
-                "role": role
-            }));
+/// `[broker]` settings, only read when built with the `broker` feature
+#[cfg(feature = "broker")]
+impl AppConfig {
+    pub fn is_broker_enabled(&self) -> bool {
+        self.broker.enabled.unwrap_or(false)
+    }
+
+    pub fn get_broker_kind(&self) -> &str {
+        self.broker.kind.as_deref().unwrap_or("nats")
+    }
+
+    /// Defaults to the standard local port of the broker kind
+    pub fn get_broker_url(&self) -> &str {
+        match self.broker.url.as_deref().filter(|url| !url.is_empty()) {
+            Some(url) => url,
+            None if self.get_broker_kind() == "mqtt" => "mqtt://127.0.0.1:1883",
+            None => "nats://127.0.0.1:4222",
         }
+    }
+
+    pub fn get_broker_username(&self) -> Option<&str> {
+        self.broker.username.as_deref().filter(|username| !username.is_empty())
+    }
+
+    /// Plain or a `secret:<name>` reference
+    pub fn get_broker_password(&self) -> Option<&str> {
+        self.broker.password.as_deref().filter(|password| !password.is_empty())
+    }
+
+    /// MQTT client id; `None` lets the bridge make one up
+    pub fn get_broker_client_id(&self) -> Option<&str> {
+        self.broker.client_id.as_deref().filter(|id| !id.is_empty())
+    }
+
+    /// Prepended to the subjects events are published on
+    pub fn get_broker_prefix(&self) -> &str {
+        self.broker.prefix.as_deref().unwrap_or("rustwebui")
+    }
+
+    pub fn get_broker_publish(&self) -> Vec<String> {
+        self.broker
+            .publish
+            .clone()
+            .unwrap_or_else(|| vec!["data.changed".to_string(), "counter.changed".to_string()])
+    }
 
-        // Emit get users event
-        if let Ok(bus) =
-            std::panic::catch_unwind(|| crate::infrastructure::event_bus::EventBus::global())
-        {
-            if let Err(e) = futures::executor::block_on(bus.emit_simple(
-                &crate::infrastructure::event_bus::AppEventType::DatabaseOperation.to_string(),
-                serde_json::json!({
-                    "operation": "get_users",
-                    "count": users.len()
-                }),
-            )) {
-                eprintln!("Failed to emit get users event: {}", e);
+    pub fn get_broker_subscribe(&self) -> Vec<String> {
+        self.broker.subscribe.clone().unwrap_or_default()
+    }
+}
+
+/// `[webhooks]` settings, only read when built with the `webhooks` feature
+#[cfg(feature = "webhooks")]
+impl AppConfig {
+    /// Attempts per delivery before it is marked failed
+    pub fn get_webhooks_max_attempts(&self) -> u32 {
+        self.webhooks.max_attempts.unwrap_or(8).max(1)
+    }
+
+    pub fn get_webhooks_timeout_secs(&self) -> u64 {
+        self.webhooks.timeout_secs.unwrap_or(10).max(1)
+    }
+
+    /// How long finished deliveries stay in the log
+    pub fn get_webhooks_retention_days(&self) -> u64 {
+        self.webhooks.retention_days.unwrap_or(30)
+    }
+}
+
+/// `[updater]` settings, only read when built with the `updater` feature
+#[cfg(feature = "updater")]
+impl AppConfig {
+    pub fn is_updater_enabled(&self) -> bool {
+        self.updater.enabled.unwrap_or(false)
+    }
+
+    pub fn get_updater_github_repo(&self) -> Option<&str> {
+        self.updater.github_repo.as_deref().filter(|repo| !repo.is_empty())
+    }
+
+    pub fn get_updater_feed_url(&self) -> Option<&str> {
+        self.updater.feed_url.as_deref().filter(|url| !url.is_empty())
+    }
+
+    /// Hours between checks; 0 checks only at startup and on `update.check`
+    pub fn get_updater_check_interval_hours(&self) -> u64 {
+        self.updater.check_interval_hours.unwrap_or(24)
+    }
+
+    pub fn is_updater_include_prereleases(&self) -> bool {
+        self.updater.include_prereleases.unwrap_or(false)
+    }
+
+    /// `{os}` and `{arch}` are replaced with those of the running build
+    pub fn get_updater_asset(&self) -> String {
+        self.updater
+            .asset
+            .as_deref()
+            .filter(|asset| !asset.is_empty())
+            .unwrap_or("{os}-{arch}")
+            .replace("{os}", std::env::consts::OS)
+            .replace("{arch}", std::env::consts::ARCH)
+    }
+
+    /// Plain or a `secret:<name>` reference; updates are never installed without it
+    pub fn get_updater_public_key(&self) -> Option<&str> {
+        self.updater.public_key.as_deref().filter(|key| !key.is_empty())
+    }
+
+    pub fn is_updater_auto_install(&self) -> bool {
+        self.updater.auto_install.unwrap_or(false)
+    }
+}
+
+/// `[hotkeys]` settings, only read when built with the `hotkeys` feature
+#[cfg(feature = "hotkeys")]
+impl AppConfig {
+    pub fn is_hotkeys_enabled(&self) -> bool {
+        self.hotkeys.enabled.unwrap_or(false)
+    }
+
+    pub fn get_hotkey_bindings(&self) -> BTreeMap<String, HotkeyBinding> {
+        self.hotkeys.bindings.clone().unwrap_or_default()
+    }
+}
+
+/// `[capture]` settings, only read when built with the `capture` feature
+#[cfg(feature = "capture")]
+impl AppConfig {
+    pub fn get_capture_dir(&self) -> &str {
+        self.capture.dir.as_deref().filter(|dir| !dir.is_empty()).unwrap_or("captures")
+    }
+
+    pub fn get_capture_thumbnail_size(&self) -> u32 {
+        self.capture.thumbnail_size.unwrap_or(320).clamp(16, 2048)
+    }
+
+    pub fn get_capture_confirm_timeout_secs(&self) -> u64 {
+        self.capture.confirm_timeout_secs.unwrap_or(60).max(1)
+    }
+}
+
+// Global guard to ensure the tracing subscriber stays active
+
+pub fn init_logging_with_config(
+    log_file: Option<&str>,
+    log_level: &str,
+    append: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Configure log level
+    let _level = match log_level {
+        "trace" => Level::TRACE,
+        "debug" => Level::DEBUG,
+        "info" => Level::INFO,
+        "warn" => Level::WARN,
+        "error" => Level::ERROR,
+        _ => Level::INFO,
+    };
+
+    // Set up environment filter
+    let env_filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("rustwebui_app={}", log_level)));
+
+    // The log ring buffer, with a filter of its own
+    let config = AppConfig::global();
+    let ring_buffer = config.get_log_ring_buffer_file().and_then(|path| {
+        match crate::infrastructure::logging::ring_buffer::layer(Path::new(path), config.get_log_ring_buffer_records()) {
+            Ok(layer) => Some(layer.with_filter(EnvFilter::new(config.get_log_ring_buffer_level()))),
+            Err(e) => {
+                eprintln!("Failed to open the log ring buffer {}: {}", path, e);
+                None
             }
         }
+    });
 
-        Ok(users)
-    }
-
-    // Method to get database stats with event emission
-    pub fn get_db_stats(&self) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        let conn = self.connection.lock().unwrap();
-
-        // Get user count
-        let user_count: i64 = conn.query_row("SELECT COUNT(*) FROM users", [], |row| row.get(0))?;
-
-        // Get table names
-        let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type='table'")?;
-        let table_names: Vec<String> = stmt
-            .query_map([], |row| row.get(0))?
-            .collect::<std::result::Result<Vec<String>, _>>()?;
-
-        let stats = serde_json::json!({
-            "users": user_count,
-            "tables": table_names
-        });
-
-        // Emit get stats event
-        if let Ok(bus) =
-            std::panic::catch_unwind(|| crate::infrastructure::event_bus::EventBus::global())
-        {
-            if let Err(e) = futures::executor::block_on(bus.emit_simple(
-                &crate::infrastructure::event_bus::AppEventType::DatabaseOperation.to_string(),
-                serde_json::json!({
-                    "operation": "get_stats",
-                    "stats": &stats
-                }),
-            )) {
-                eprintln!("Failed to emit get stats event: {}", e);
+    // The `[logging] file` log, with the startup filter; empty disables it
+    let log_file = log_file.filter(|file| !file.is_empty());
+    let file_layer = log_file.and_then(|path| {
+        match crate::infrastructure::logging::log_file::writer(Path::new(path), append) {
+            Ok(writer) => Some(
+                fmt::layer()
+                    .with_ansi(false)
+                    .with_target(true)
+                    .with_writer(writer)
+                    .with_filter(
+                        EnvFilter::try_from_default_env()
+                            .unwrap_or_else(|_| EnvFilter::new(format!("rustwebui_app={}", log_level))),
+                    ),
+            ),
+            Err(e) => {
+                eprintln!("Failed to open the log file {}: {}", path, e);
+                None
             }
         }
+    });
 
-        Ok(stats)
-    }
+    // Create subscriber with console logging (without timestamps); the filter
+    // is reloadable so devtools can change levels at runtime (logging.set_level),
+    // and applies to the console only
+    let subscriber = tracing_subscriber::registry()
+        .with(
+            fmt::layer()
+                .with_ansi(true) // ANSI colors for console
+                .with_target(true)
+                .with_line_number(true)
+                .without_time() // Remove timestamps
+                .with_filter(crate::infrastructure::logging::log_filter::reloadable(env_filter)),
+        )
+        .with(ring_buffer)
+        .with(file_layer);
+
+    // Set the global subscriber
+    tracing::subscriber::set_global_default(subscriber)
+        .map_err(|err| format!("Failed to set tracing subscriber: {}", err))?;
+
+    info!(
+        message = "Logging system initialized",
+        log_level = log_level,
+        log_file = log_file.unwrap_or("none")
+    );
+
+    Ok(())
 }
+
+// The database moved to `infrastructure::database::sqlite`; these keep `model::core` imports working
+pub use crate::infrastructure::database::sqlite::{
+    AuthTokenPurpose, Database, OutboxMessage, UsageCount, UserChanges, UserQuery, UserSession, WindowState, USER_SORT_FIELDS,
+};
+#[cfg(feature = "webhooks")]
+pub use crate::infrastructure::database::sqlite::{Webhook, WebhookDelivery, WebhookDeliveryStatus};
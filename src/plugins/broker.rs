@@ -0,0 +1,202 @@
+//! Permission-checked facades handed to plugins through `PluginContext::access`
+//!
+//! Every refused call is logged and announced as a `plugin.access_denied`
+//! audit event before the error goes back to the plugin.
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{error, warn};
+use crate::infrastructure::event_bus::{AppEventType, EventBus};
+use crate::infrastructure::database::{database, SqliteSettingsRepository};
+use crate::infrastructure::secrets::{self, SecretsManager};
+use crate::infrastructure::settings;
+use crate::plugins::plugin_api::sandbox::resolve_path;
+use crate::plugins::{
+    AccessError, DatabaseAccess, FileSystemAccess, NetworkAccess, Permission, Permissions, PluginAccess,
+    SecretsAccess, SettingsAccess,
+};
+
+struct Broker {
+    plugin_id: String,
+    permissions: Permissions,
+}
+
+impl Broker {
+    /// Check `needed`, auditing the refusal
+    fn check(&self, needed: &Permission, operation: &str) -> Result<(), String> {
+        self.permissions.check(&self.plugin_id, needed).map_err(|denied| {
+            audit(&denied, operation);
+            denied.to_string()
+        })
+    }
+}
+
+fn audit(denied: &AccessError, operation: &str) {
+    warn!(plugin = %denied.plugin_id, "Refused {}: {}", operation, denied);
+    let payload = serde_json::json!({
+        "plugin": denied.plugin_id,
+        "operation": operation,
+        "permission": denied.permission,
+        "declared": !denied.not_declared,
+    });
+    let event = AppEventType::PluginAccessDenied.to_string();
+    if let Err(e) = futures::executor::block_on(EventBus::global().emit_simple(&event, payload)) {
+        error!("Failed to emit plugin access audit event: {}", e);
+    }
+}
+
+impl DatabaseAccess for Broker {
+    fn query(&self, sql: &str, params: &[Value]) -> Result<Vec<Value>, String> {
+        self.check(&Permission::DatabaseRead, "database.query")?;
+        // Read-only unless the plugin may write anyway
+        let read_only = !self.permissions.allows(&Permission::DatabaseWrite);
+        database().map_err(|e| e.message)?.query_json(sql, params, read_only).map_err(|e| e.to_string())
+    }
+
+    fn execute(&self, sql: &str, params: &[Value]) -> Result<usize, String> {
+        self.check(&Permission::DatabaseWrite, "database.execute")?;
+        database().map_err(|e| e.message)?.execute_json(sql, params).map_err(|e| e.to_string())
+    }
+}
+
+impl FileSystemAccess for Broker {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, String> {
+        let path = resolve_path(path);
+        self.check(&Permission::FsRead(path.clone()), "fs.read")?;
+        std::fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), String> {
+        let path = resolve_path(path);
+        self.check(&Permission::FsWrite(path.clone()), "fs.write")?;
+        std::fs::write(&path, contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn list(&self, path: &Path) -> Result<Vec<PathBuf>, String> {
+        let path = resolve_path(path);
+        self.check(&Permission::FsRead(path.clone()), "fs.list")?;
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(&path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        entries.sort();
+        Ok(entries)
+    }
+}
+
+impl NetworkAccess for Broker {
+    fn connect(&self, address: &str) -> Result<std::net::TcpStream, String> {
+        self.check(&Permission::Network, "network.connect")?;
+        std::net::TcpStream::connect(address).map_err(|e| format!("{}: {}", address, e))
+    }
+}
+
+impl Broker {
+    /// Keychain account of the plugin's own secret `name`
+    fn own_secret(&self, name: &str, operation: &str) -> Result<String, String> {
+        self.check(&Permission::Secrets, operation)?;
+        secrets::plugin_account(&self.plugin_id, name).map_err(|e| e.to_string())
+    }
+}
+
+impl SecretsAccess for Broker {
+    fn get(&self, name: &str) -> Result<Option<String>, String> {
+        let account = self.own_secret(name, "secrets.get")?;
+        SecretsManager::global().store().get(&account).map_err(|e| e.to_string())
+    }
+
+    fn set(&self, name: &str, value: &str) -> Result<(), String> {
+        let account = self.own_secret(name, "secrets.set")?;
+        SecretsManager::global().store().set(&account, value).map_err(|e| e.to_string())
+    }
+
+    fn delete(&self, name: &str) -> Result<bool, String> {
+        let account = self.own_secret(name, "secrets.delete")?;
+        SecretsManager::global().store().delete(&account).map_err(|e| e.to_string())
+    }
+
+    fn get_shared(&self, name: &str) -> Result<Option<String>, String> {
+        self.check(&Permission::SecretRead(name.to_string()), "secrets.get_shared")?;
+        SecretsManager::global().get(name).map_err(|e| e.to_string())
+    }
+}
+
+impl Broker {
+    /// The settings store, for a call allowed as `operation`
+    fn settings_store(&self, operation: &str) -> Result<SqliteSettingsRepository, String> {
+        self.check(&Permission::Settings, operation)?;
+        Ok(SqliteSettingsRepository::new(database().map_err(|e| e.message)?))
+    }
+}
+
+impl SettingsAccess for Broker {
+    fn get(&self, key: &str) -> Result<Option<Value>, String> {
+        let repo = self.settings_store("settings.get")?;
+        let namespace = settings::plugin_namespace(&self.plugin_id);
+        futures::executor::block_on(settings::get(&repo, &namespace, key, None))
+            .map(|setting| setting.map(|setting| setting.value))
+            .map_err(|e| e.to_string())
+    }
+
+    fn set(&self, key: &str, value: Value) -> Result<(), String> {
+        let repo = self.settings_store("settings.set")?;
+        let namespace = settings::plugin_namespace(&self.plugin_id);
+        futures::executor::block_on(settings::set(&repo, &namespace, key, value, None))
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn all(&self) -> Result<serde_json::Map<String, Value>, String> {
+        let repo = self.settings_store("settings.all")?;
+        let namespace = settings::plugin_namespace(&self.plugin_id);
+        futures::executor::block_on(settings::values(&repo, &namespace, None)).map_err(|e| e.to_string())
+    }
+}
+
+/// Facades for `plugin_id` limited to `permissions`
+pub fn plugin_access(plugin_id: &str, permissions: Permissions) -> PluginAccess {
+    let broker = Arc::new(Broker {
+        plugin_id: plugin_id.to_string(),
+        permissions,
+    });
+    PluginAccess {
+        database: broker.clone(),
+        fs: broker.clone(),
+        network: broker.clone(),
+        secrets: broker.clone(),
+        settings: broker,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    #[test]
+    fn test_facades_check_permissions() {
+        let dir = TempDir::new("rustwebui-broker");
+        std::fs::create_dir_all(dir.join("reports")).unwrap();
+        std::fs::write(dir.join("reports").join("q1.csv"), "a,b").unwrap();
+        std::fs::write(dir.join("secret.txt"), "secret").unwrap();
+
+        let permissions = Permissions::parse(&[format!("fs.read:{}", dir.join("reports").display())]).unwrap();
+        let access = plugin_access("reports", permissions);
+
+        assert_eq!(access.fs.read(&dir.join("reports").join("q1.csv")).unwrap(), b"a,b");
+        assert_eq!(access.fs.list(&dir.join("reports")).unwrap().len(), 1);
+        let escape = access.fs.read(&dir.join("reports").join("..").join("secret.txt")).unwrap_err();
+        assert!(escape.starts_with("Access denied"));
+        assert!(access.fs.write(&dir.join("reports").join("q2.csv"), b"c").unwrap_err().starts_with("Access denied"));
+        assert!(access.network.connect("127.0.0.1:9").unwrap_err().starts_with("Capability not found"));
+        assert!(access.database.query("SELECT 1", &[]).unwrap_err().starts_with("Capability not found"));
+        assert!(access.secrets.get("token").unwrap_err().starts_with("Capability not found"));
+        assert!(access.settings.get("theme").unwrap_err().starts_with("Capability not found"));
+
+        let permissions = Permissions::parse(&["secrets.read:api-token".to_string()]).unwrap();
+        let access = plugin_access("reports", permissions);
+        assert!(access.secrets.get_shared("database:app.db").unwrap_err().starts_with("Access denied"));
+        assert!(access.secrets.set("token", "x").unwrap_err().starts_with("Access denied"));
+    }
+}
@@ -0,0 +1,319 @@
+//! Plugin host - the app's plugin registry and the services handed to plugins
+//!
+//! At startup the built-in plugins and every plugin in `[plugins] dir` are
+//! loaded and initialized, dependencies first, with their
+//! `[plugins.config.<id>]` settings.
+//! Commands they provide are dispatched from the WebSocket command handler
+//! (and so also from IPC and gRPC) after the built-in commands, and their
+//! HTTP routes and static assets are served under `/plugins/<id>/`.
+//!
+//! With `[plugins] hot_reload`, libraries are loaded from shadow copies and
+//! the directory is watched: a rebuilt library (or edited `plugin.toml`)
+//! replaces the running plugin and emits `plugin.reloaded`.
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+use crate::error_handling::{AppError, ErrorCode, GlobalErrorHandler};
+use crate::infrastructure::event_bus::{Event, EventBus};
+use crate::infrastructure::fs_watcher::ChangeBatch;
+use crate::plugins::broker;
+use crate::plugins::plugins::builtin_plugins;
+use crate::plugins::{
+    AccessError, EventBusTrait, LoggerTrait, Permissions, PluginContext, PluginHttpRequest, PluginHttpResponse,
+    PluginRegistry, ReloadReport, MANIFEST_FILE,
+};
+
+/// Quiet period after the last change before reloading, so builds finish writing
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// `EventBusTrait` over the application event bus; events are tagged with the plugin id
+struct HostEventBus {
+    plugin_id: String,
+}
+
+#[async_trait::async_trait]
+impl EventBusTrait for HostEventBus {
+    async fn emit(&self, event: &str, payload: Value) -> Result<(), String> {
+        let source = format!("plugin:{}", self.plugin_id);
+        EventBus::global()
+            .emit(Event::new(event.to_string(), payload, source))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    fn subscribe(&self, event: &str, handler: Arc<dyn Fn(Value) + Send + Sync>) {
+        let result = EventBus::global().subscribe_as(&subscription_owner(&self.plugin_id), event, move |event| {
+            handler((*event.payload).clone());
+            Ok(())
+        });
+        if let Err(e) = result {
+            warn!("Plugin {} could not subscribe to {}: {}", self.plugin_id, event, e);
+        }
+    }
+}
+
+fn subscription_owner(plugin_id: &str) -> String {
+    format!("plugin:{}", plugin_id)
+}
+
+/// Drop the event subscriptions of a plugin that was shut down or unloaded
+fn release_subscriptions(plugin_id: &str) {
+    let removed = EventBus::global().unsubscribe_owner(&subscription_owner(plugin_id));
+    if removed > 0 {
+        debug!("Removed {} event subscriptions of plugin {}", removed, plugin_id);
+    }
+}
+
+/// `LoggerTrait` writing to the app log under the plugin's id
+struct HostLogger {
+    plugin_id: String,
+}
+
+impl LoggerTrait for HostLogger {
+    fn info(&self, message: &str) {
+        info!(plugin = %self.plugin_id, "{}", message);
+    }
+
+    fn warn(&self, message: &str) {
+        warn!(plugin = %self.plugin_id, "{}", message);
+    }
+
+    fn error(&self, message: &str) {
+        error!(plugin = %self.plugin_id, "{}", message);
+    }
+
+    fn debug(&self, message: &str) {
+        debug!(plugin = %self.plugin_id, "{}", message);
+    }
+}
+
+pub fn plugin_context(plugin_id: &str, config: HashMap<String, Value>, permissions: Permissions) -> PluginContext {
+    PluginContext::new(
+        config,
+        Arc::new(HostEventBus { plugin_id: plugin_id.to_string() }),
+        Arc::new(HostLogger { plugin_id: plugin_id.to_string() }),
+    )
+    .with_access(broker::plugin_access(plugin_id, permissions))
+}
+
+fn registry() -> &'static RwLock<PluginRegistry> {
+    static REGISTRY: OnceLock<RwLock<PluginRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(PluginRegistry::new()))
+}
+
+/// Register the built-in plugins and load the plugins in `dir`, then initialize
+/// them all, dependencies first; returns how many are running.
+/// With `hot_reload` libraries are loaded from shadow copies so they can be rebuilt
+pub async fn load_plugins(
+    dir: Option<&Path>,
+    config: &HashMap<String, HashMap<String, Value>>,
+    hot_reload: bool,
+) -> usize {
+    let mut loaded = PluginRegistry::new();
+    loaded.set_on_release(release_subscriptions);
+    if hot_reload {
+        loaded.set_shadow_dir(std::env::temp_dir().join(format!("rustwebui-plugins-{}", std::process::id())));
+    }
+    // Built-ins first, so a library cannot take over their commands
+    for plugin in builtin_plugins() {
+        let id = plugin.metadata().id.clone();
+        if let Err(e) = loaded.register(plugin) {
+            error!("Failed to register built-in plugin {}: {}", id, e);
+        }
+    }
+    for (path, result) in dir.map(|dir| loaded.load_dir(dir)).unwrap_or_default() {
+        match result {
+            Ok(id) => info!("Loaded plugin {} from {}", id, path.display()),
+            Err(e) => error!("Failed to load plugin: {}", e),
+        }
+    }
+
+    // Initialized before publishing, while the registry holds the only references
+    for (id, e) in loaded.initialize_each(config, plugin_context).await {
+        error!("Plugin {} failed to initialize and was disabled: {}", id, e);
+    }
+
+    let plugins = loaded.plugins();
+    let Ok(mut registry) = registry().write() else {
+        return 0;
+    };
+    *registry = loaded;
+    plugins.len()
+}
+
+/// Commands of the built-in plugins and the plugins in `dir` as (name,
+/// description), read from their capabilities without initializing them
+pub fn plugin_commands(dir: Option<&Path>) -> Vec<(String, String)> {
+    let mut registry = PluginRegistry::new();
+    for plugin in builtin_plugins() {
+        let _ = registry.register(plugin);
+    }
+    for (path, result) in dir.map(|dir| registry.load_dir(dir)).unwrap_or_default() {
+        if let Err(e) = result {
+            warn!("Skipping plugin {}: {}", path.display(), e);
+        }
+    }
+    registry.commands()
+}
+
+/// Reload plugin `id` from its library and emit `plugin.reloaded` or `plugin.reload_failed`
+pub async fn reload_plugin(id: &str, config: &HashMap<String, HashMap<String, Value>>) -> Result<ReloadReport, String> {
+    // Taken out of the global registry so no lock is held across plugin calls;
+    // commands arriving meanwhile are answered as unknown
+    let mut reloading = registry()
+        .write()
+        .map(|mut registry| std::mem::take(&mut *registry))
+        .map_err(|_| "Plugin registry lock poisoned".to_string())?;
+    let result = reloading.reload(id, config, plugin_context).await;
+    if let Ok(mut registry) = registry().write() {
+        *registry = reloading;
+    }
+
+    let (event, payload) = match &result {
+        Ok(report) => {
+            info!("Reloaded plugin {} from {}", report.id, report.path.display());
+            for (failed, e) in &report.failed {
+                error!("Plugin {} failed during reload of {}: {}", failed, id, e);
+            }
+            ("plugin.reloaded", serde_json::to_value(report).unwrap_or_default())
+        }
+        Err(e) => {
+            error!("{}", e);
+            ("plugin.reload_failed", serde_json::json!({ "id": id, "error": e }))
+        }
+    };
+    if let Err(e) = EventBus::global().emit_simple(event, payload).await {
+        debug!("Failed to emit {}: {}", event, e);
+    }
+    result
+}
+
+/// Watch `dir` and reload plugins whose library or `plugin.toml` changes
+pub fn watch_plugins(dir: &Path, config: HashMap<String, HashMap<String, Value>>) -> Result<(), String> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| match result {
+        Ok(event) => {
+            let _ = tx.send(event);
+        }
+        Err(e) => warn!("Plugin watcher error: {}", e),
+    })
+    .map_err(|e| e.to_string())?;
+    watcher
+        .watch(dir, RecursiveMode::Recursive)
+        .map_err(|e| format!("{}: {}", dir.display(), e))?;
+    info!("Watching {} to hot reload plugins", dir.display());
+    tokio::spawn(reload_changed(watcher, rx, config));
+    Ok(())
+}
+
+/// Whether a change to `path` can affect a loaded plugin
+fn is_plugin_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+        || path.file_name().is_some_and(|name| name == MANIFEST_FILE)
+}
+
+/// Collect changes until the directory is quiet, then reload the affected plugins
+async fn reload_changed(
+    // Dropping the watcher would stop the events
+    _watcher: RecommendedWatcher,
+    mut rx: mpsc::UnboundedReceiver<notify::Event>,
+    config: HashMap<String, HashMap<String, Value>>,
+) {
+    while let Some(first) = rx.recv().await {
+        let mut batch = ChangeBatch::default();
+        batch.record(&first);
+        while let Ok(Some(event)) = tokio::time::timeout(RELOAD_DEBOUNCE, rx.recv()).await {
+            batch.record(&event);
+        }
+
+        let changed: BTreeSet<String> = {
+            let Ok(registry) = registry().read() else {
+                continue;
+            };
+            batch
+                .created
+                .iter()
+                .chain(&batch.modified)
+                .filter(|path| is_plugin_file(path))
+                .filter_map(|path| registry.plugin_for_path(path))
+                .collect()
+        };
+        for id in changed {
+            let _ = reload_plugin(&id, &config).await;
+        }
+    }
+}
+
+/// Run `name` if a plugin provides it; `None` leaves the command to the caller
+pub async fn handle_command(name: &str, payload: &Value) -> Option<Value> {
+    let plugin = registry().read().ok()?.command_handler(name)?;
+    let plugin_id = plugin.metadata().id.clone();
+    Some(match plugin.handle_command(name, payload.clone()).await {
+        Ok(response) => response,
+        Err(e) => {
+            // Sandbox refusals keep their own codes
+            let code = match AccessError::classify(&e) {
+                Some(true) => ErrorCode::PluginCapabilityNotFound,
+                Some(false) => ErrorCode::AccessDenied,
+                None => ErrorCode::PluginError,
+            };
+            GlobalErrorHandler::to_json_response(
+                &AppError::new(code, e)
+                    .with_context("plugin", plugin_id)
+                    .with_context("command", name),
+            )
+        }
+    })
+}
+
+/// Serve `/plugins/<id>/...` from the plugin's routes and assets; `None`
+/// for other URLs and for paths the plugin does not serve
+pub fn handle_http(method: &str, url: &str, headers: Vec<(String, String)>, body: Vec<u8>) -> Option<PluginHttpResponse> {
+    let (path, query) = match url.split_once('?') {
+        Some((path, query)) => (path, Some(query.to_string())),
+        None => (url, None),
+    };
+    let rest = path.strip_prefix("/plugins/")?;
+    let (plugin_id, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let request = PluginHttpRequest {
+        method: method.to_uppercase(),
+        path: path.to_string(),
+        query,
+        headers,
+        body,
+    };
+    registry().read().ok()?.handle_http(plugin_id, request)
+}
+
+/// Shut every plugin down before exit
+pub async fn shutdown_plugins() {
+    let Ok(mut registry) = registry().write().map(|mut registry| std::mem::take(&mut *registry)) else {
+        return;
+    };
+    for (id, e) in registry.shutdown_all().await {
+        warn!("Plugin {} did not shut down cleanly: {}", id, e);
+    }
+}
+
+/// `plugins.list`: metadata of the loaded plugins, the order they were initialized in
+/// and the services they publish
+pub fn list() -> Value {
+    let Ok(registry) = registry().read() else {
+        return serde_json::json!({ "success": true, "plugins": [], "initialization_order": [], "services": {} });
+    };
+    serde_json::json!({
+        "success": true,
+        "plugins": registry.plugins(),
+        "initialization_order": registry.initialization_order(),
+        "services": registry.services().providers(),
+    })
+}
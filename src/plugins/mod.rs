@@ -3,7 +3,13 @@
 //! This module contains the plugin system and built-in plugins.
 //! Plugins extend the core functionality without modifying the core.
 
+// The plugin API is mostly called from plugin libraries, not from the app
+#![allow(dead_code)]
+
+pub mod broker;
+pub mod host;
 pub mod plugin_api;
+#[allow(clippy::module_inception)]
 pub mod plugins;
 
 pub use plugin_api::*;
@@ -0,0 +1,126 @@
+//! HTTP endpoints and static assets contributed by plugins
+//!
+//! Everything a plugin contributes is served under `/plugins/<id>/`:
+//! `HttpRoute { path: "status", .. }` answers `/plugins/<id>/status` and
+//! `StaticAssets { mount: "ui", .. }` serves `/plugins/<id>/ui/...` from its
+//! directory, with `index.html` for the mount itself.
+
+use std::path::{Component, Path, PathBuf};
+
+/// A request routed to a plugin's `HttpRoute`
+#[derive(Debug, Clone, Default)]
+pub struct PluginHttpRequest {
+    /// Upper-case method, e.g. `GET`
+    pub method: String,
+    /// Path below `/plugins/<id>/`, without leading or trailing slashes
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl PluginHttpRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginHttpResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+impl PluginHttpResponse {
+    pub fn new(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        Self { status, content_type: content_type.to_string(), body }
+    }
+
+    pub fn json(status: u16, value: &serde_json::Value) -> Self {
+        Self::new(status, "application/json", value.to_string().into_bytes())
+    }
+
+    pub fn text(status: u16, text: &str) -> Self {
+        Self::new(status, "text/plain; charset=utf-8", text.as_bytes().to_vec())
+    }
+}
+
+/// Handler of an `HttpRoute`; runs on the HTTP server thread
+pub type HttpHandler = std::sync::Arc<dyn Fn(PluginHttpRequest) -> PluginHttpResponse + Send + Sync>;
+
+/// `/a//b/` -> `a/b`
+pub(crate) fn normalize(path: &str) -> String {
+    path.split('/').filter(|segment| !segment.is_empty()).collect::<Vec<_>>().join("/")
+}
+
+/// `rest` below `mount`, `Some("")` for the mount itself
+pub(crate) fn strip_mount<'a>(path: &'a str, mount: &str) -> Option<&'a str> {
+    if mount.is_empty() {
+        return Some(path);
+    }
+    match path.strip_prefix(mount)? {
+        "" => Some(""),
+        rest => rest.strip_prefix('/'),
+    }
+}
+
+/// Read `relative` from `dir`; `None` when it does not exist or leaves `dir`
+pub(crate) fn serve_asset(dir: &Path, relative: &str) -> Option<PluginHttpResponse> {
+    let mut path = PathBuf::from(dir);
+    for component in Path::new(relative).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    if path.is_dir() {
+        path.push("index.html");
+    }
+
+    // Symlinks may still point outside the directory
+    let root = dir.canonicalize().ok()?;
+    let path = path.canonicalize().ok()?;
+    if !path.starts_with(&root) || !path.is_file() {
+        return None;
+    }
+
+    let body = std::fs::read(&path).ok()?;
+    let content_type = mime_guess::from_path(&path).first_or_octet_stream().to_string();
+    Some(PluginHttpResponse::new(200, &content_type, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    #[test]
+    fn test_strip_mount() {
+        assert_eq!(strip_mount("ui/app.js", "ui"), Some("app.js"));
+        assert_eq!(strip_mount("ui", "ui"), Some(""));
+        assert_eq!(strip_mount("uix/app.js", "ui"), None);
+        assert_eq!(strip_mount("app.js", ""), Some("app.js"));
+        assert_eq!(normalize("/ui//panel/"), "ui/panel");
+    }
+
+    #[test]
+    fn test_serve_asset_stays_in_dir() {
+        let root = TempDir::new("rustwebui-assets");
+        let dir = root.join("ui");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("index.html"), "<h1>panel</h1>").unwrap();
+        std::fs::write(root.join("secret.txt"), "secret").unwrap();
+
+        let index = serve_asset(&dir, "").unwrap();
+        assert_eq!(index.content_type, "text/html");
+        assert_eq!(index.body, b"<h1>panel</h1>");
+        assert!(serve_asset(&dir, "../secret.txt").is_none());
+        assert!(serve_asset(&dir, "/etc/passwd").is_none());
+        assert!(serve_asset(&dir, "missing.js").is_none());
+    }
+}
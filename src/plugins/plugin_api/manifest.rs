@@ -0,0 +1,290 @@
+//! `plugin.toml` - what a plugin directory contains and needs
+//!
+//! ```toml
+//! id = "hello"
+//! version = "1.0.0"
+//! library = "libhello.so"          # optional when the directory holds one library
+//! dependencies = ["storage"]       # plugins initialized before this one
+//! required_capabilities = ["storage.get"]
+//! required_services = ["cache"]     # services providers publish before this one initializes
+//! permissions = ["database.read", "fs.read:/srv/reports", "network"]
+//!
+//! [config.greeting]
+//! type = "string"
+//! default = "Hello"
+//! ```
+//!
+//! Config values come from `[plugins.config.<id>]` in `app.config.toml`,
+//! falling back to the schema defaults.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use super::sandbox::Permissions;
+
+pub const MANIFEST_FILE: &str = "plugin.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigType {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Array,
+    Table,
+}
+
+impl ConfigType {
+    fn name(self) -> &'static str {
+        match self {
+            ConfigType::String => "string",
+            ConfigType::Integer => "integer",
+            ConfigType::Float => "float",
+            ConfigType::Boolean => "boolean",
+            ConfigType::Array => "array",
+            ConfigType::Table => "table",
+        }
+    }
+
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            ConfigType::String => value.is_string(),
+            ConfigType::Integer => value.is_i64() || value.is_u64(),
+            ConfigType::Float => value.is_number(),
+            ConfigType::Boolean => value.is_boolean(),
+            ConfigType::Array => value.is_array(),
+            ConfigType::Table => value.is_object(),
+        }
+    }
+}
+
+/// One entry of the manifest's config schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigField {
+    #[serde(rename = "type")]
+    pub kind: ConfigType,
+    #[serde(default)]
+    pub required: bool,
+    pub default: Option<toml::Value>,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub version: String,
+    pub library: Option<String>,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Commands that other plugins must provide
+    #[serde(default)]
+    pub required_capabilities: Vec<String>,
+    /// Services whose providers must initialize first
+    #[serde(default)]
+    pub required_services: Vec<String>,
+    /// What the plugin may access through its context, see `sandbox`
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub config: BTreeMap<String, ConfigField>,
+}
+
+impl PluginManifest {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let manifest: Self = toml::from_str(text).map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE, e))?;
+        if manifest.id.trim().is_empty() || manifest.version.trim().is_empty() {
+            return Err(format!("{} needs a non-empty id and version", MANIFEST_FILE));
+        }
+        if manifest.dependencies.contains(&manifest.id) {
+            return Err(format!("Plugin {} depends on itself", manifest.id));
+        }
+        manifest.permissions()?;
+        Ok(manifest)
+    }
+
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(MANIFEST_FILE);
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn permissions(&self) -> Result<Permissions, String> {
+        Permissions::parse(&self.permissions).map_err(|e| format!("Plugin {}: {}", self.id, e))
+    }
+
+    /// The library to load: `library` if set, otherwise the only library in `dir`
+    pub fn library_path(&self, dir: &Path) -> Result<PathBuf, String> {
+        if let Some(library) = &self.library {
+            return Ok(dir.join(library));
+        }
+        let libraries: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map_err(|e| format!("{}: {}", dir.display(), e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION))
+            .collect();
+        match libraries.as_slice() {
+            [library] => Ok(library.clone()),
+            [] => Err(format!("Plugin {}: no library in {}", self.id, dir.display())),
+            _ => Err(format!("Plugin {}: several libraries in {}, set `library`", self.id, dir.display())),
+        }
+    }
+
+    /// Merge `overrides` over the schema defaults and check every value's type
+    pub fn resolve_config(&self, overrides: &HashMap<String, Value>) -> Result<HashMap<String, Value>, String> {
+        if let Some(unknown) = overrides.keys().find(|key| !self.config.contains_key(*key)) {
+            return Err(format!("Plugin {}: unknown config key `{}`", self.id, unknown));
+        }
+
+        let mut resolved = HashMap::new();
+        for (key, field) in &self.config {
+            let value = match overrides.get(key) {
+                Some(value) => value.clone(),
+                None => match &field.default {
+                    Some(default) => serde_json::to_value(default).map_err(|e| e.to_string())?,
+                    None if field.required => {
+                        return Err(format!("Plugin {}: config `{}` is required", self.id, key));
+                    }
+                    None => continue,
+                },
+            };
+            if !field.kind.matches(&value) {
+                return Err(format!("Plugin {}: config `{}` must be a {}", self.id, key, field.kind.name()));
+            }
+            resolved.insert(key.clone(), value);
+        }
+        Ok(resolved)
+    }
+}
+
+/// Order plugins so dependencies come first
+///
+/// Returns the initialization order and the plugins that cannot be
+/// initialized: missing dependencies, dependencies that cannot be initialized
+/// themselves, and cycles.
+pub fn resolve_order(dependencies: &BTreeMap<String, Vec<String>>) -> (Vec<String>, Vec<(String, String)>) {
+    let mut failed: BTreeMap<String, String> = BTreeMap::new();
+
+    // Missing dependencies, and everything depending on a failed plugin
+    loop {
+        let newly_failed: Vec<(String, String)> = dependencies
+            .iter()
+            .filter(|(id, _)| !failed.contains_key(*id))
+            .find_map(|(id, deps)| {
+                deps.iter().find_map(|dep| {
+                    if !dependencies.contains_key(dep) {
+                        Some((id.clone(), format!("missing dependency {}", dep)))
+                    } else if failed.contains_key(dep) {
+                        Some((id.clone(), format!("dependency {} cannot be initialized", dep)))
+                    } else {
+                        None
+                    }
+                })
+            })
+            .into_iter()
+            .collect();
+        if newly_failed.is_empty() {
+            break;
+        }
+        failed.extend(newly_failed);
+    }
+
+    // Kahn's algorithm over the rest
+    let mut order = Vec::new();
+    let mut pending: Vec<&String> = dependencies.keys().filter(|id| !failed.contains_key(*id)).collect();
+    while !pending.is_empty() {
+        let (ready, blocked): (Vec<&String>, Vec<&String>) = pending
+            .into_iter()
+            .partition(|id| dependencies[*id].iter().all(|dep| order.contains(dep)));
+        if ready.is_empty() {
+            let cycle = blocked.iter().map(|id| id.as_str()).collect::<Vec<_>>().join(", ");
+            for id in &blocked {
+                failed.insert((*id).clone(), format!("dependency cycle among {}", cycle));
+            }
+            break;
+        }
+        order.extend(ready.into_iter().cloned());
+        pending = blocked;
+    }
+
+    (order, failed.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        edges
+            .iter()
+            .map(|(id, deps)| (id.to_string(), deps.iter().map(|dep| dep.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_dependencies_come_first() {
+        let (order, failed) = resolve_order(&graph(&[("app", &["storage", "auth"]), ("auth", &["storage"]), ("storage", &[])]));
+        assert_eq!(order, vec!["storage", "auth", "app"]);
+        assert!(failed.is_empty());
+    }
+
+    #[test]
+    fn test_missing_and_cyclic_dependencies_fail() {
+        let (order, failed) = resolve_order(&graph(&[
+            ("a", &["b"]),
+            ("b", &["a"]),
+            ("needs_ghost", &["ghost"]),
+            ("downstream", &["needs_ghost"]),
+            ("ok", &[]),
+        ]));
+        assert_eq!(order, vec!["ok"]);
+        let failed: BTreeMap<String, String> = failed.into_iter().collect();
+        assert_eq!(failed["needs_ghost"], "missing dependency ghost");
+        assert_eq!(failed["downstream"], "dependency needs_ghost cannot be initialized");
+        assert_eq!(failed["a"], "dependency cycle among a, b");
+    }
+
+    #[test]
+    fn test_resolve_config() {
+        let manifest = PluginManifest::parse(
+            r#"
+            id = "hello"
+            version = "1.0.0"
+
+            [config.greeting]
+            type = "string"
+            default = "Hello"
+
+            [config.retries]
+            type = "integer"
+            required = true
+            "#,
+        )
+        .unwrap();
+
+        assert!(manifest.resolve_config(&HashMap::new()).unwrap_err().contains("`retries` is required"));
+
+        let overrides = HashMap::from([("retries".to_string(), serde_json::json!(3))]);
+        let config = manifest.resolve_config(&overrides).unwrap();
+        assert_eq!(config["greeting"], "Hello");
+        assert_eq!(config["retries"], 3);
+
+        let overrides = HashMap::from([("retries".to_string(), serde_json::json!("three"))]);
+        assert!(manifest.resolve_config(&overrides).is_err());
+        let overrides = HashMap::from([("colour".to_string(), serde_json::json!("red"))]);
+        assert!(manifest.resolve_config(&overrides).unwrap_err().contains("unknown config key"));
+    }
+
+    #[test]
+    fn test_manifest_rejects_unknown_permission() {
+        let error = PluginManifest::parse("id = \"a\"\nversion = \"1\"\npermissions = [\"shell\"]").unwrap_err();
+        assert_eq!(error, "Plugin a: Unknown permission shell");
+    }
+
+    #[test]
+    fn test_manifest_rejects_self_dependency() {
+        assert!(PluginManifest::parse("id = \"a\"\nversion = \"1\"\ndependencies = [\"a\"]").is_err());
+    }
+}
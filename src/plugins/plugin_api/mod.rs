@@ -3,8 +3,24 @@
 //! This module defines the traits and types that plugins must implement.
 //! It provides the foundation for the plugin-driven architecture.
 
+pub mod http;
+pub mod manifest;
+pub mod sandbox;
+pub mod services;
+
+pub use http::{HttpHandler, PluginHttpRequest, PluginHttpResponse};
+pub use manifest::{PluginManifest, MANIFEST_FILE};
+pub use sandbox::{
+    AccessError, DatabaseAccess, FileSystemAccess, NetworkAccess, Permission, PluginAccess, Permissions,
+    SecretsAccess, SettingsAccess,
+};
+pub use services::ServiceRegistry;
+
+use libloading::{Library, Symbol};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::mem::ManuallyDrop;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// Plugin metadata
@@ -19,7 +35,7 @@ pub struct PluginMetadata {
 }
 
 /// Plugin capability - what the plugin provides
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub enum PluginCapability {
     /// Provides UI commands (frontend -> backend)
     Command {
@@ -40,11 +56,35 @@ pub enum PluginCapability {
         schema: serde_json::Value,
     },
     
-    /// Provides services to other plugins
+    /// Provides services to other plugins, resolved with `PluginContext::get_service`
     Service {
         name: String,
         interface: Arc<dyn std::any::Any + Send + Sync>,
     },
+
+    /// Serves `method` requests to `/plugins/<id>/<path>`
+    HttpRoute {
+        path: String,
+        method: String,
+        handler: HttpHandler,
+    },
+
+    /// Serves the files in `dir` under `/plugins/<id>/<mount>/`; a relative
+    /// `dir` is resolved against the directory the plugin was loaded from
+    StaticAssets {
+        mount: String,
+        dir: PathBuf,
+    },
+}
+
+impl PluginCapability {
+    /// `Service` capability for `service`; consumers resolve it as a `T`
+    pub fn service<T: std::any::Any + Send + Sync>(name: &str, service: T) -> Self {
+        PluginCapability::Service {
+            name: name.to_string(),
+            interface: Arc::new(service),
+        }
+    }
 }
 
 /// Plugin trait - all plugins must implement this
@@ -75,6 +115,9 @@ pub struct PluginContext {
     pub config: HashMap<String, serde_json::Value>,
     pub event_bus: Arc<dyn EventBusTrait>,
     pub logger: Arc<dyn LoggerTrait>,
+    pub services: Arc<ServiceRegistry>,
+    /// The only way to app resources; checked against the manifest's permissions
+    pub access: PluginAccess,
 }
 
 impl PluginContext {
@@ -83,12 +126,53 @@ impl PluginContext {
         event_bus: Arc<dyn EventBusTrait>,
         logger: Arc<dyn LoggerTrait>,
     ) -> Self {
-        Self { config, event_bus, logger }
+        Self {
+            config,
+            event_bus,
+            logger,
+            services: Arc::new(ServiceRegistry::new()),
+            access: PluginAccess::none("unknown"),
+        }
+    }
+
+    pub fn with_access(mut self, access: PluginAccess) -> Self {
+        self.access = access;
+        self
+    }
+
+    pub fn with_services(mut self, services: Arc<ServiceRegistry>) -> Self {
+        self.services = services;
+        self
     }
     
     pub fn get_config(&self, key: &str) -> Option<&serde_json::Value> {
         self.config.get(key)
     }
+
+    pub fn database(&self) -> &dyn DatabaseAccess {
+        self.access.database.as_ref()
+    }
+
+    pub fn fs(&self) -> &dyn FileSystemAccess {
+        self.access.fs.as_ref()
+    }
+
+    pub fn network(&self) -> &dyn NetworkAccess {
+        self.access.network.as_ref()
+    }
+
+    pub fn secrets(&self) -> &dyn SecretsAccess {
+        self.access.secrets.as_ref()
+    }
+
+    pub fn settings(&self) -> &dyn SettingsAccess {
+        self.access.settings.as_ref()
+    }
+
+    /// Service `name` published by another plugin, as a `T`
+    pub fn get_service<T: std::any::Any + Send + Sync>(&self, name: &str) -> Result<Arc<T>, String> {
+        self.services.get::<T>(name)
+    }
 }
 
 /// Event bus trait for plugin communication
@@ -106,10 +190,93 @@ pub trait LoggerTrait: Send + Sync {
     fn debug(&self, message: &str);
 }
 
+/// Version of the dynamic plugin ABI; bumped whenever `Plugin`,
+/// `PluginCapability` or `PluginContext` change shape
+pub const PLUGIN_ABI_VERSION: u32 = 4;
+
+/// `plugin_abi_version` export of a plugin library
+pub type PluginAbiVersionFn = unsafe extern "C" fn() -> u32;
+
+/// `create_plugin` export of a plugin library; returns `Box::into_raw(Box::new(plugin))`
+/// of a `Box<dyn Plugin>`
+pub type CreatePluginFn = unsafe extern "C" fn() -> *mut Box<dyn Plugin>;
+
+/// A plugin created by a loaded library, which must outlive it
+struct DynamicPlugin {
+    plugin: ManuallyDrop<Box<dyn Plugin>>,
+    library: ManuallyDrop<Library>,
+    /// Copy the library was loaded from, removed once it is unloaded
+    shadow: Option<PathBuf>,
+}
+
+impl Drop for DynamicPlugin {
+    fn drop(&mut self) {
+        // The plugin's code lives in the library, so drop it first
+        unsafe {
+            ManuallyDrop::drop(&mut self.plugin);
+            ManuallyDrop::drop(&mut self.library);
+        }
+        if let Some(shadow) = &self.shadow {
+            let _ = std::fs::remove_file(shadow);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Plugin for DynamicPlugin {
+    fn metadata(&self) -> &PluginMetadata {
+        self.plugin.metadata()
+    }
+
+    fn capabilities(&self) -> Vec<PluginCapability> {
+        self.plugin.capabilities()
+    }
+
+    async fn initialize(&mut self, context: &PluginContext) -> Result<(), String> {
+        self.plugin.initialize(context).await
+    }
+
+    async fn shutdown(&mut self) -> Result<(), String> {
+        self.plugin.shutdown().await
+    }
+
+    async fn handle_command(&self, command: &str, payload: serde_json::Value) -> Result<serde_json::Value, String> {
+        self.plugin.handle_command(command, payload).await
+    }
+}
+
+/// Outcome of `PluginRegistry::reload`
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadReport {
+    /// Id of the reloaded plugin
+    pub id: String,
+    pub path: PathBuf,
+    /// Plugins depending on it that were shut down and initialized again
+    pub dependents: Vec<String>,
+    /// Plugins that failed to shut down or initialize again, with the reason
+    pub failed: Vec<(String, String)>,
+}
+
 /// Plugin registry - manages plugin lifecycle
 pub struct PluginRegistry {
     plugins: HashMap<String, Arc<dyn Plugin>>,
-    capabilities: HashMap<String, Arc<dyn Plugin>>,
+    /// Command name -> id of the plugin handling it
+    capabilities: HashMap<String, String>,
+    manifests: HashMap<String, PluginManifest>,
+    /// Initialized plugins, dependencies first
+    initialized: Vec<String>,
+    /// Plugin id -> its `HttpRoute` and `StaticAssets` capabilities
+    http: HashMap<String, Vec<PluginCapability>>,
+    /// Plugin id -> library or plugin directory it was loaded from
+    sources: HashMap<String, PathBuf>,
+    /// Where libraries are copied before loading, so they can be rebuilt in place
+    shadow_dir: Option<PathBuf>,
+    /// Service name -> id of the plugin providing it
+    service_owners: HashMap<String, String>,
+    /// Services of initialized plugins
+    services: Arc<ServiceRegistry>,
+    /// Called with a plugin's id once it is shut down or dropped
+    on_release: Option<fn(&str)>,
 }
 
 impl PluginRegistry {
@@ -117,50 +284,573 @@ impl PluginRegistry {
         Self {
             plugins: HashMap::new(),
             capabilities: HashMap::new(),
+            manifests: HashMap::new(),
+            initialized: Vec::new(),
+            http: HashMap::new(),
+            sources: HashMap::new(),
+            shadow_dir: None,
+            service_owners: HashMap::new(),
+            services: Arc::new(ServiceRegistry::new()),
+            on_release: None,
         }
     }
     
+    /// Add a plugin and its commands; fails if the id or a command is taken,
+    /// or if its dependencies form a cycle with registered plugins
     pub fn register(&mut self, plugin: Arc<dyn Plugin>) -> Result<(), String> {
         let metadata = plugin.metadata();
-        if self.plugins.contains_key(&metadata.id) {
-            return Err(format!("Plugin {} already registered", metadata.id));
+        let id = metadata.id.clone();
+        if self.plugins.contains_key(&id) {
+            return Err(format!("Plugin {} already registered", id));
         }
         
-        // Register capabilities
+        // Check capabilities before touching the registry
+        let capabilities = plugin.capabilities();
+        let commands: Vec<String> = capabilities
+            .iter()
+            .filter_map(|capability| match capability {
+                PluginCapability::Command { name, .. } => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        if let Some((name, owner)) = commands.iter().find_map(|name| self.capabilities.get(name).map(|owner| (name, owner))) {
+            return Err(format!("Command {} of plugin {} is already provided by {}", name, id, owner));
+        }
+        let services: Vec<String> = capabilities
+            .iter()
+            .filter_map(|capability| match capability {
+                PluginCapability::Service { name, .. } => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        if let Some((name, owner)) = services.iter().find_map(|name| self.service_owners.get(name).map(|owner| (name, owner))) {
+            return Err(format!("Service {} of plugin {} is already provided by {}", name, id, owner));
+        }
+        let http: Vec<PluginCapability> = capabilities
+            .into_iter()
+            .filter(|capability| {
+                matches!(capability, PluginCapability::HttpRoute { .. } | PluginCapability::StaticAssets { .. })
+            })
+            .collect();
+
+        self.plugins.insert(id.clone(), plugin);
+        for name in &services {
+            self.service_owners.insert(name.clone(), id.clone());
+        }
+        let (_, failed) = manifest::resolve_order(&self.dependency_graph());
+        if let Some((_, reason)) = failed.iter().find(|(failed_id, reason)| *failed_id == id && reason.starts_with("dependency cycle")) {
+            self.plugins.remove(&id);
+            self.manifests.remove(&id);
+            self.service_owners.retain(|_, owner| *owner != id);
+            return Err(format!("Plugin {}: {}", id, reason));
+        }
+        for name in commands {
+            self.capabilities.insert(name, id.clone());
+        }
+        if !http.is_empty() {
+            self.http.insert(id, http);
+        }
+        Ok(())
+    }
+
+    /// Register a plugin described by a `plugin.toml`; the ids must agree
+    pub fn register_with_manifest(&mut self, plugin: Arc<dyn Plugin>, manifest: PluginManifest) -> Result<(), String> {
+        let id = plugin.metadata().id.clone();
+        if id != manifest.id {
+            return Err(format!("Plugin library reports id {} but its manifest says {}", id, manifest.id));
+        }
+        if self.plugins.contains_key(&id) {
+            return Err(format!("Plugin {} already registered", id));
+        }
+        self.manifests.insert(id, manifest);
+        self.register(plugin)
+    }
+
+    /// Dependencies of every registered plugin, from its metadata and manifest;
+    /// the providers of its required services count as dependencies
+    fn dependency_graph(&self) -> BTreeMap<String, Vec<String>> {
+        self.plugins
+            .iter()
+            .map(|(id, plugin)| {
+                let mut dependencies = plugin.metadata().dependencies.clone();
+                if let Some(manifest) = self.manifests.get(id) {
+                    dependencies.extend(manifest.dependencies.iter().cloned());
+                    dependencies.extend(
+                        manifest
+                            .required_services
+                            .iter()
+                            .filter_map(|service| self.service_owners.get(service))
+                            .filter(|owner| *owner != id)
+                            .cloned(),
+                    );
+                }
+                dependencies.sort();
+                dependencies.dedup();
+                (id.clone(), dependencies)
+            })
+            .collect()
+    }
+
+    /// Load libraries from copies in `dir` instead of in place, so a loaded
+    /// library can be overwritten by a rebuild and loaded again
+    pub fn set_shadow_dir(&mut self, dir: PathBuf) {
+        self.shadow_dir = Some(dir);
+    }
+
+    /// Have the host drop what a plugin registered with it outside the
+    /// registry, such as event subscriptions, when the plugin shuts down or
+    /// is dropped. Their handlers may live in the plugin's library, so they
+    /// must be gone before it is unloaded.
+    pub fn set_on_release(&mut self, release: fn(&str)) {
+        self.on_release = Some(release);
+    }
+
+    fn release(&self, id: &str) {
+        if let Some(release) = self.on_release {
+            release(id);
+        }
+    }
+
+    /// Open a plugin library, through a shadow copy if enabled
+    fn open_library(&self, path: &Path) -> Result<DynamicPlugin, String> {
+        let Some(shadow_dir) = &self.shadow_dir else {
+            return Self::open_library_at(path, None);
+        };
+        let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let shadow = shadow_dir.join(format!("{}-{}", uuid::Uuid::new_v4().simple(), file_name));
+        std::fs::create_dir_all(shadow_dir)
+            .and_then(|_| std::fs::copy(path, &shadow))
+            .map_err(|e| format!("{}: cannot copy to {}: {}", path.display(), shadow.display(), e))?;
+        Self::open_library_at(&shadow, Some(path)).inspect_err(|_| {
+            let _ = std::fs::remove_file(&shadow);
+        })
+    }
+
+    /// Open the library at `path` and run the ABI handshake; `original` is the
+    /// library `path` is a shadow copy of
+    ///
+    /// Plugins run in-process with full trust and must be built with the same
+    /// compiler version and `plugin_api` as the app.
+    fn open_library_at(path: &Path, original: Option<&Path>) -> Result<DynamicPlugin, String> {
+        let shown = original.unwrap_or(path);
+        let describe = |e: libloading::Error| format!("{}: {}", shown.display(), e);
+        // SAFETY: loading runs the library's initializers; plugins are trusted code
+        let library = unsafe { Library::new(path) }.map_err(describe)?;
+
+        let abi_version = unsafe {
+            let abi_version: Symbol<PluginAbiVersionFn> = library.get(b"plugin_abi_version").map_err(describe)?;
+            abi_version()
+        };
+        if abi_version != PLUGIN_ABI_VERSION {
+            return Err(format!(
+                "{}: plugin ABI version {} is not supported (expected {})",
+                shown.display(),
+                abi_version,
+                PLUGIN_ABI_VERSION
+            ));
+        }
+
+        let raw = unsafe {
+            let create_plugin: Symbol<CreatePluginFn> = library.get(b"create_plugin").map_err(describe)?;
+            create_plugin()
+        };
+        if raw.is_null() {
+            return Err(format!("{}: create_plugin returned null", shown.display()));
+        }
+        // SAFETY: the ABI handshake guarantees `raw` came from `Box::into_raw` of a `Box<dyn Plugin>`
+        let plugin = unsafe { Box::from_raw(raw) };
+
+        Ok(DynamicPlugin {
+            plugin: ManuallyDrop::new(*plugin),
+            library: ManuallyDrop::new(library),
+            shadow: original.map(|_| path.to_path_buf()),
+        })
+    }
+
+    /// Load a `cdylib` plugin exporting `plugin_abi_version` and `create_plugin`,
+    /// register it and return its id
+    pub fn load_from_path(&mut self, path: &Path) -> Result<String, String> {
+        let plugin = self.open_library(path)?;
+        let id = plugin.metadata().id.clone();
+        self.register(Arc::new(plugin))?;
+        self.sources.insert(id.clone(), path.to_path_buf());
+        Ok(id)
+    }
+
+    /// Load the plugin in `dir` described by its `plugin.toml`
+    pub fn load_plugin_dir(&mut self, dir: &Path) -> Result<String, String> {
+        let manifest = PluginManifest::load(dir)?;
+        let plugin = self.open_library(&manifest.library_path(dir)?)?;
+        let id = manifest.id.clone();
+        self.register_with_manifest(Arc::new(plugin), manifest)?;
+        self.sources.insert(id.clone(), dir.to_path_buf());
+        Ok(id)
+    }
+
+    /// Load every plugin in `dir`: libraries directly inside it and
+    /// subdirectories with a `plugin.toml`. A missing directory loads nothing
+    pub fn load_dir(&mut self, dir: &Path) -> Vec<(PathBuf, Result<String, String>)> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.join(MANIFEST_FILE).is_file()
+                    || path.extension().is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+            })
+            .collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let result = if path.is_dir() {
+                    self.load_plugin_dir(&path)
+                } else {
+                    self.load_from_path(&path)
+                };
+                (path, result)
+            })
+            .collect()
+    }
+
+    /// Why `id` cannot be initialized now, given the plugins initialized so far
+    fn blocked_by(&self, id: &str, dependencies: &[String]) -> Option<String> {
+        if let Some(dependency) = dependencies.iter().find(|dependency| !self.initialized.contains(dependency)) {
+            return Some(format!("dependency {} failed to initialize", dependency));
+        }
+        let manifest = self.manifests.get(id)?;
+        if let Some(service) = manifest.required_services.iter().find(|service| !self.service_owners.contains_key(*service)) {
+            return Some(format!("requires service {}, which no plugin provides", service));
+        }
+        manifest
+            .required_capabilities
+            .iter()
+            .find(|capability| {
+                !self
+                    .capabilities
+                    .get(*capability)
+                    .is_some_and(|owner| owner != id && self.initialized.contains(owner))
+            })
+            .map(|capability| format!("requires capability {}, which no plugin initialized before it provides", capability))
+    }
+
+    /// Initialize every plugin not initialized yet, dependencies first, with
+    /// config from `config` (keyed by plugin id) checked against each
+    /// manifest's schema. `context_for` builds each context from the plugin's
+    /// config and the permissions its manifest declares.
+    ///
+    /// Plugins that cannot be initialized - missing or cyclic dependencies,
+    /// missing capabilities, invalid config or a failing `initialize` - are
+    /// unregistered and returned with the reason. Must run before the registry
+    /// hands out plugin references.
+    pub async fn initialize_each<F>(
+        &mut self,
+        config: &HashMap<String, HashMap<String, serde_json::Value>>,
+        context_for: F,
+    ) -> Vec<(String, String)>
+    where
+        F: Fn(&str, HashMap<String, serde_json::Value>, Permissions) -> PluginContext,
+    {
+        let graph = self.dependency_graph();
+        let (order, mut failed) = manifest::resolve_order(&graph);
+        let empty = HashMap::new();
+
+        for id in order {
+            if self.initialized.contains(&id) {
+                continue;
+            }
+            if let Some(reason) = self.blocked_by(&id, &graph[&id]) {
+                failed.push((id, reason));
+                continue;
+            }
+            let overrides = config.get(&id).unwrap_or(&empty);
+            // Plugins without a manifest get no permissions
+            let (plugin_config, permissions) = match self.manifests.get(&id) {
+                Some(manifest) => (manifest.resolve_config(overrides), manifest.permissions().unwrap_or_default()),
+                None => (Ok(overrides.clone()), Permissions::default()),
+            };
+            let result = match (plugin_config, self.plugins.get_mut(&id).and_then(Arc::get_mut)) {
+                (Err(e), _) => Err(e),
+                (Ok(_), None) => Err("Plugin is in use and cannot be initialized".to_string()),
+                (Ok(plugin_config), Some(plugin)) => {
+                    let context = context_for(&id, plugin_config, permissions).with_services(self.services.clone());
+                    plugin.initialize(&context).await
+                }
+            };
+            match result {
+                Ok(()) => {
+                    self.publish_services(&id);
+                    self.initialized.push(id);
+                }
+                Err(e) => failed.push((id, e)),
+            }
+        }
+
+        for (id, _) in &failed {
+            self.unregister(id);
+        }
+        failed
+    }
+
+    /// Drop a plugin and everything it registered
+    fn unregister(&mut self, id: &str) {
+        self.release(id);
+        self.plugins.remove(id);
+        self.manifests.remove(id);
+        self.capabilities.retain(|_, owner| owner != id);
+        self.service_owners.retain(|_, owner| owner != id);
+        self.http.remove(id);
+        self.sources.remove(id);
+    }
+
+    /// Make the services of the initialized plugin `id` available to the next ones
+    fn publish_services(&self, id: &str) {
+        let Some(plugin) = self.plugins.get(id) else {
+            return;
+        };
         for capability in plugin.capabilities() {
-            if let PluginCapability::Command { name, .. } = capability {
-                self.capabilities.insert(name, plugin.clone());
+            if let PluginCapability::Service { name, interface } = capability {
+                self.services.publish(&name, id, interface);
             }
         }
-        
-        self.plugins.insert(metadata.id.clone(), plugin);
-        Ok(())
     }
-    
+
+    /// Services published by initialized plugins; contexts passed to
+    /// `initialize_all` should be built `with_services` of it
+    pub fn services(&self) -> Arc<ServiceRegistry> {
+        self.services.clone()
+    }
+
+    /// Initialize every plugin with the same context; stops at the first failure
     pub async fn initialize_all(&mut self, context: &PluginContext) -> Result<(), String> {
-        for plugin in self.plugins.values_mut() {
-            let mut p = plugin.clone();
-            // In a real implementation, we'd need mutable access
-            // This is a simplified version
+        let (order, failed) = manifest::resolve_order(&self.dependency_graph());
+        if let Some((id, reason)) = failed.first() {
+            return Err(format!("Plugin {} cannot be initialized: {}", id, reason));
+        }
+        for id in order {
+            let plugin = self
+                .plugins
+                .get_mut(&id)
+                .and_then(Arc::get_mut)
+                .ok_or_else(|| format!("Plugin {} is in use and cannot be initialized", id))?;
+            plugin.initialize(context).await.map_err(|e| format!("Plugin {} failed to initialize: {}", id, e))?;
+            self.publish_services(&id);
+            self.initialized.push(id);
         }
         Ok(())
     }
-    
+
+    /// Shut down and unregister every plugin, dependents first; returns the plugins that failed
+    pub async fn shutdown_all(&mut self) -> Vec<(String, String)> {
+        let mut failed = Vec::new();
+        let mut order: Vec<String> = self.initialized.drain(..).rev().collect();
+        order.extend(self.plugins.keys().filter(|id| !order.contains(id)).cloned().collect::<Vec<_>>());
+        for id in order {
+            let Some(mut plugin) = self.plugins.remove(&id) else {
+                continue;
+            };
+            self.services.withdraw(&id);
+            let result = match Arc::get_mut(&mut plugin) {
+                Some(plugin) => plugin.shutdown().await,
+                None => Err("Plugin is still in use".to_string()),
+            };
+            self.release(&id);
+            if let Err(e) = result {
+                failed.push((id, e));
+            }
+        }
+        self.capabilities.clear();
+        self.manifests.clear();
+        self.service_owners.clear();
+        self.http.clear();
+        self.sources.clear();
+        failed
+    }
+
+    /// Withdraw the services of an initialized plugin and shut it down, keeping it registered
+    async fn shutdown_one(&mut self, id: &str) -> Result<(), String> {
+        self.services.withdraw(id);
+        self.initialized.retain(|initialized| initialized != id);
+        let result = match self.plugins.get_mut(id).and_then(Arc::get_mut) {
+            Some(plugin) => plugin.shutdown().await,
+            None => Err("Plugin is still in use".to_string()),
+        };
+        self.release(id);
+        result
+    }
+
+    /// Initialized plugins depending on `id`, directly or not, in initialization order
+    fn dependents(&self, id: &str) -> Vec<String> {
+        let graph = self.dependency_graph();
+        let mut affected = vec![id.to_string()];
+        loop {
+            let more: Vec<String> = graph
+                .iter()
+                .filter(|(candidate, dependencies)| {
+                    !affected.contains(candidate) && dependencies.iter().any(|dependency| affected.contains(dependency))
+                })
+                .map(|(candidate, _)| candidate.clone())
+                .collect();
+            if more.is_empty() {
+                break;
+            }
+            affected.extend(more);
+        }
+        self.initialized
+            .iter()
+            .filter(|initialized| *initialized != id && affected.contains(initialized))
+            .cloned()
+            .collect()
+    }
+
+    /// Plugin loaded from `path`: its library, or a file in its plugin directory
+    pub fn plugin_for_path(&self, path: &Path) -> Option<String> {
+        let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let path = canonical(path);
+        self.sources
+            .iter()
+            .find(|(_, source)| {
+                let source = canonical(source);
+                path == source || (source.is_dir() && path.starts_with(&source))
+            })
+            .map(|(id, _)| id.clone())
+    }
+
+    /// Load plugin `id` again from where it came from, replaying its
+    /// registrations. Its dependents are shut down first and initialized again
+    /// afterwards, so none of them keeps using the old library's services.
+    ///
+    /// Fails when the plugin was not loaded from a library or the new library
+    /// cannot be loaded; the old instance is gone either way.
+    pub async fn reload<F>(
+        &mut self,
+        id: &str,
+        config: &HashMap<String, HashMap<String, serde_json::Value>>,
+        context_for: F,
+    ) -> Result<ReloadReport, String>
+    where
+        F: Fn(&str, HashMap<String, serde_json::Value>, Permissions) -> PluginContext,
+    {
+        let path = self
+            .sources
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("Plugin {} was not loaded from a library", id))?;
+        let dependents = self.dependents(id);
+
+        let mut failed = Vec::new();
+        for dependent in dependents.iter().rev().chain(std::iter::once(&id.to_string())) {
+            if let Err(e) = self.shutdown_one(dependent).await {
+                failed.push((dependent.clone(), e));
+            }
+        }
+        self.unregister(id);
+
+        let loaded = if path.is_dir() {
+            self.load_plugin_dir(&path)
+        } else {
+            self.load_from_path(&path)
+        };
+        // Settles the dependents either way; without the plugin they are disabled
+        failed.extend(self.initialize_each(config, context_for).await);
+        let id = loaded.map_err(|e| format!("Plugin {} could not be reloaded: {}", id, e))?;
+
+        Ok(ReloadReport { id, path, dependents, failed })
+    }
+
     pub fn get_plugin(&self, id: &str) -> Option<Arc<dyn Plugin>> {
         self.plugins.get(id).cloned()
     }
+
+    pub fn get_manifest(&self, id: &str) -> Option<&PluginManifest> {
+        self.manifests.get(id)
+    }
+
+    pub fn plugins(&self) -> Vec<PluginMetadata> {
+        let mut plugins: Vec<PluginMetadata> = self.plugins.values().map(|plugin| plugin.metadata().clone()).collect();
+        plugins.sort_by(|a, b| a.id.cmp(&b.id));
+        plugins
+    }
+
+    /// Initialized plugin ids, dependencies first
+    pub fn initialization_order(&self) -> &[String] {
+        &self.initialized
+    }
+
+    /// Name and description of every command, sorted by name
+    pub fn commands(&self) -> Vec<(String, String)> {
+        let mut commands: Vec<(String, String)> = self
+            .plugins
+            .values()
+            .flat_map(|plugin| plugin.capabilities())
+            .filter_map(|capability| match capability {
+                PluginCapability::Command { name, description, .. } => Some((name, description)),
+                _ => None,
+            })
+            .collect();
+        commands.sort();
+        commands
+    }
+
+    /// Plugin providing `command`, if any
+    pub fn command_handler(&self, command: &str) -> Option<Arc<dyn Plugin>> {
+        self.capabilities.get(command).and_then(|id| self.get_plugin(id))
+    }
     
     pub async fn handle_command(
         &self,
         command: &str,
         payload: serde_json::Value,
     ) -> Result<serde_json::Value, String> {
-        if let Some(plugin) = self.capabilities.get(command) {
+        if let Some(plugin) = self.command_handler(command) {
             plugin.handle_command(command, payload).await
         } else {
             Err(format!("Unknown command: {}", command))
         }
     }
+
+    /// Serve a request to `/plugins/<id>/<request.path>` from the plugin's
+    /// routes, then its static assets; `None` when nothing matches
+    pub fn handle_http(&self, plugin_id: &str, mut request: PluginHttpRequest) -> Option<PluginHttpResponse> {
+        let contributions = self.http.get(plugin_id)?;
+        request.path = http::normalize(&request.path);
+
+        let mut allowed = Vec::new();
+        for capability in contributions {
+            if let PluginCapability::HttpRoute { path, method, handler } = capability {
+                if http::normalize(path) != request.path {
+                    continue;
+                }
+                if method.eq_ignore_ascii_case(&request.method) {
+                    return Some(handler(request));
+                }
+                allowed.push(method.to_uppercase());
+            }
+        }
+        if !allowed.is_empty() {
+            return Some(PluginHttpResponse::text(405, &format!("Method not allowed, use {}", allowed.join(", "))));
+        }
+
+        if request.method != "GET" && request.method != "HEAD" {
+            return None;
+        }
+        contributions.iter().find_map(|capability| {
+            let PluginCapability::StaticAssets { mount, dir } = capability else {
+                return None;
+            };
+            let rest = http::strip_mount(&request.path, &http::normalize(mount))?;
+            let root = self
+                .sources
+                .get(plugin_id)
+                .map(|source| if source.is_dir() { source.as_path() } else { source.parent().unwrap_or(source) });
+            let dir = match root {
+                Some(root) if dir.is_relative() => root.join(dir),
+                _ => dir.clone(),
+            };
+            http::serve_asset(&dir, rest)
+        })
+    }
 }
 
 impl Default for PluginRegistry {
@@ -168,3 +858,326 @@ impl Default for PluginRegistry {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    struct EchoPlugin {
+        metadata: PluginMetadata,
+        command: String,
+        extra: Vec<PluginCapability>,
+        /// Service resolved in `initialize`
+        uses_service: Option<String>,
+        /// Event subscribed to in `initialize`, counting its deliveries
+        subscribes_to: Option<(String, Arc<std::sync::atomic::AtomicUsize>)>,
+    }
+
+    impl EchoPlugin {
+        fn new(id: &str) -> Self {
+            Self::with_dependencies(id, "echo", &[])
+        }
+
+        fn with_dependencies(id: &str, command: &str, dependencies: &[&str]) -> Self {
+            Self {
+                metadata: PluginMetadata {
+                    id: id.to_string(),
+                    name: "Echo".to_string(),
+                    version: "1.0.0".to_string(),
+                    description: "Returns its payload".to_string(),
+                    author: "tests".to_string(),
+                    dependencies: dependencies.iter().map(|dependency| dependency.to_string()).collect(),
+                },
+                command: command.to_string(),
+                extra: Vec::new(),
+                uses_service: None,
+                subscribes_to: None,
+            }
+        }
+    }
+
+    fn context(_id: &str, config: HashMap<String, serde_json::Value>, _permissions: Permissions) -> PluginContext {
+        struct NoBus;
+        #[async_trait::async_trait]
+        impl EventBusTrait for NoBus {
+            async fn emit(&self, _event: &str, _payload: serde_json::Value) -> Result<(), String> {
+                Ok(())
+            }
+            fn subscribe(&self, _event: &str, _handler: Arc<dyn Fn(serde_json::Value) + Send + Sync>) {}
+        }
+        struct NoLog;
+        impl LoggerTrait for NoLog {
+            fn info(&self, _message: &str) {}
+            fn warn(&self, _message: &str) {}
+            fn error(&self, _message: &str) {}
+            fn debug(&self, _message: &str) {}
+        }
+        PluginContext::new(config, Arc::new(NoBus), Arc::new(NoLog))
+    }
+
+    #[async_trait::async_trait]
+    impl Plugin for EchoPlugin {
+        fn metadata(&self) -> &PluginMetadata {
+            &self.metadata
+        }
+
+        fn capabilities(&self) -> Vec<PluginCapability> {
+            let mut capabilities = vec![PluginCapability::Command {
+                name: self.command.clone(),
+                description: "Echo the payload".to_string(),
+                handler: Arc::new(|payload| Box::pin(async move { Ok(payload) })),
+            }];
+            capabilities.extend(self.extra.iter().cloned());
+            capabilities
+        }
+
+        async fn initialize(&mut self, context: &PluginContext) -> Result<(), String> {
+            if let Some(name) = &self.uses_service {
+                context.get_service::<String>(name)?;
+            }
+            if let Some((event, delivered)) = &self.subscribes_to {
+                let delivered = delivered.clone();
+                context.event_bus.subscribe(event, Arc::new(move |_| {
+                    delivered.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }));
+            }
+            Ok(())
+        }
+
+        async fn shutdown(&mut self) -> Result<(), String> {
+            Ok(())
+        }
+
+        async fn handle_command(&self, _command: &str, payload: serde_json::Value) -> Result<serde_json::Value, String> {
+            Ok(payload)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registered_command_is_dispatched() {
+        let mut registry = PluginRegistry::new();
+        registry.register(Arc::new(EchoPlugin::new("echo"))).unwrap();
+
+        let payload = serde_json::json!({ "x": 1 });
+        assert_eq!(registry.handle_command("echo", payload.clone()).await.unwrap(), payload);
+        assert!(registry.handle_command("missing", payload).await.is_err());
+        assert!(registry.shutdown_all().await.is_empty());
+        assert!(registry.plugins().is_empty());
+    }
+
+    #[test]
+    fn test_command_conflict_is_rejected() {
+        let mut registry = PluginRegistry::new();
+        registry.register(Arc::new(EchoPlugin::new("first"))).unwrap();
+        let error = registry.register(Arc::new(EchoPlugin::new("second"))).unwrap_err();
+        assert!(error.contains("already provided by first"));
+    }
+
+    #[test]
+    fn test_load_rejects_non_library() {
+        let dir = TempDir::new("rustwebui-plugins");
+        let path = dir.join(format!("broken.{}", std::env::consts::DLL_EXTENSION));
+        std::fs::write(&path, b"not a library").unwrap();
+
+        let mut registry = PluginRegistry::new();
+        let results = registry.load_dir(&dir);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_err());
+        assert!(registry.load_dir(&dir.join("missing")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_initialization_follows_dependencies() {
+        let mut registry = PluginRegistry::new();
+        registry.register(Arc::new(EchoPlugin::with_dependencies("app", "app.run", &["storage"]))).unwrap();
+        registry.register(Arc::new(EchoPlugin::with_dependencies("storage", "storage.get", &[]))).unwrap();
+        registry.register(Arc::new(EchoPlugin::with_dependencies("orphan", "orphan.run", &["ghost"]))).unwrap();
+
+        let failed = registry.initialize_each(&HashMap::new(), context).await;
+        assert_eq!(failed, vec![("orphan".to_string(), "missing dependency ghost".to_string())]);
+        assert_eq!(registry.initialization_order(), ["storage", "app"]);
+        assert!(registry.command_handler("orphan.run").is_none());
+    }
+
+    #[test]
+    fn test_dependency_cycle_is_rejected_on_register() {
+        let mut registry = PluginRegistry::new();
+        registry.register(Arc::new(EchoPlugin::with_dependencies("a", "a.run", &["b"]))).unwrap();
+        let error = registry
+            .register(Arc::new(EchoPlugin::with_dependencies("b", "b.run", &["a"])))
+            .unwrap_err();
+        assert_eq!(error, "Plugin b: dependency cycle among a, b");
+        assert!(registry.command_handler("b.run").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_manifest_requirements_are_checked() {
+        let manifest = PluginManifest::parse(
+            "id = \"app\"\nversion = \"1.0.0\"\nrequired_capabilities = [\"storage.get\"]",
+        )
+        .unwrap();
+        let mut registry = PluginRegistry::new();
+        registry
+            .register_with_manifest(Arc::new(EchoPlugin::with_dependencies("app", "app.run", &[])), manifest.clone())
+            .unwrap();
+        let failed = registry.initialize_each(&HashMap::new(), context).await;
+        assert!(failed[0].1.contains("requires capability storage.get"));
+
+        let error = PluginRegistry::new()
+            .register_with_manifest(Arc::new(EchoPlugin::new("other")), manifest)
+            .unwrap_err();
+        assert!(error.contains("manifest says app"));
+    }
+
+    #[test]
+    fn test_http_routes_and_assets() {
+        let root = TempDir::new("rustwebui-plugin-http");
+        std::fs::create_dir_all(root.join("ui")).unwrap();
+        std::fs::write(root.join("ui").join("panel.js"), "export {}").unwrap();
+
+        let mut plugin = EchoPlugin::new("web");
+        plugin.extra = vec![
+            PluginCapability::HttpRoute {
+                path: "/status".to_string(),
+                method: "get".to_string(),
+                handler: Arc::new(|request| PluginHttpResponse::json(200, &serde_json::json!({ "query": request.query }))),
+            },
+            PluginCapability::StaticAssets { mount: "ui".to_string(), dir: root.join("ui") },
+        ];
+        let mut registry = PluginRegistry::new();
+        registry.register(Arc::new(plugin)).unwrap();
+
+        let request = |method: &str, path: &str| PluginHttpRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: Some("verbose=1".to_string()),
+            ..Default::default()
+        };
+        let status = registry.handle_http("web", request("GET", "status/")).unwrap();
+        assert_eq!(status.body, br#"{"query":"verbose=1"}"#);
+        assert_eq!(registry.handle_http("web", request("POST", "status")).unwrap().status, 405);
+
+        let asset = registry.handle_http("web", request("GET", "ui/panel.js")).unwrap();
+        assert_eq!(asset.body, b"export {}");
+        assert!(registry.handle_http("web", request("GET", "ui/../ui/panel.js")).is_none());
+        assert!(registry.handle_http("web", request("GET", "missing")).is_none());
+        assert!(registry.handle_http("other", request("GET", "status")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_service_providers_initialize_before_consumers() {
+        let manifest = |id: &str| {
+            PluginManifest::parse(&format!("id = \"{}\"\nversion = \"1.0.0\"\nrequired_services = [\"motd\"]", id)).unwrap()
+        };
+        let mut consumer = EchoPlugin::with_dependencies("consumer", "consumer.run", &[]);
+        consumer.uses_service = Some("motd".to_string());
+        let mut provider = EchoPlugin::with_dependencies("provider", "provider.run", &[]);
+        provider.extra = vec![PluginCapability::service("motd", "Welcome".to_string())];
+
+        let mut registry = PluginRegistry::new();
+        // "consumer" sorts first, so only the service dependency puts "provider" ahead
+        registry.register_with_manifest(Arc::new(consumer), manifest("consumer")).unwrap();
+        registry.register(Arc::new(provider)).unwrap();
+        registry
+            .register_with_manifest(Arc::new(EchoPlugin::with_dependencies("lonely", "lonely.run", &[])), {
+                let mut lonely = manifest("lonely");
+                lonely.required_services = vec!["weather".to_string()];
+                lonely
+            })
+            .unwrap();
+
+        let failed = registry.initialize_each(&HashMap::new(), context).await;
+        assert_eq!(failed, vec![("lonely".to_string(), "requires service weather, which no plugin provides".to_string())]);
+        assert_eq!(registry.initialization_order(), ["provider", "consumer"]);
+        assert_eq!(*registry.services().get::<String>("motd").unwrap(), "Welcome");
+
+        let error = registry
+            .register(Arc::new({
+                let mut copy = EchoPlugin::with_dependencies("copy", "copy.run", &[]);
+                copy.extra = vec![PluginCapability::service("motd", 1u32)];
+                copy
+            }))
+            .unwrap_err();
+        assert!(error.contains("already provided by provider"));
+
+        assert!(registry.shutdown_all().await.is_empty());
+        assert!(registry.services().providers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reload_restarts_dependents() {
+        let mut registry = PluginRegistry::new();
+        registry.register(Arc::new(EchoPlugin::with_dependencies("storage", "storage.get", &[]))).unwrap();
+        registry.register(Arc::new(EchoPlugin::with_dependencies("app", "app.run", &["storage"]))).unwrap();
+        registry.register(Arc::new(EchoPlugin::with_dependencies("ui", "ui.run", &["app"]))).unwrap();
+        registry.register(Arc::new(EchoPlugin::with_dependencies("other", "other.run", &[]))).unwrap();
+        assert!(registry.initialize_each(&HashMap::new(), context).await.is_empty());
+        assert_eq!(registry.dependents("storage"), ["app", "ui"]);
+
+        let error = registry.reload("storage", &HashMap::new(), context).await.unwrap_err();
+        assert!(error.contains("not loaded from a library"));
+
+        // The rebuilt library is gone, so the plugin and its dependents are disabled
+        let dir = TempDir::new("rustwebui-missing");
+        let path = dir.join(format!("storage.{}", std::env::consts::DLL_EXTENSION));
+        registry.sources.insert("storage".to_string(), path.clone());
+        assert_eq!(registry.plugin_for_path(&path).as_deref(), Some("storage"));
+        let error = registry.reload("storage", &HashMap::new(), context).await.unwrap_err();
+        assert!(error.starts_with("Plugin storage could not be reloaded"));
+        assert_eq!(registry.initialization_order(), ["other"]);
+        assert!(registry.command_handler("ui.run").is_none());
+    }
+
+    /// Bus for `bus_context`, reachable from the registry's release hook
+    fn test_bus() -> &'static crate::infrastructure::event_bus::EventBus {
+        static BUS: std::sync::OnceLock<crate::infrastructure::event_bus::EventBus> = std::sync::OnceLock::new();
+        BUS.get_or_init(crate::infrastructure::event_bus::EventBus::new)
+    }
+
+    /// `context` whose subscriptions go to `test_bus()` under the plugin's id
+    fn bus_context(id: &str, config: HashMap<String, serde_json::Value>, permissions: Permissions) -> PluginContext {
+        struct OwnedBus(String);
+        #[async_trait::async_trait]
+        impl EventBusTrait for OwnedBus {
+            async fn emit(&self, _event: &str, _payload: serde_json::Value) -> Result<(), String> {
+                Ok(())
+            }
+            fn subscribe(&self, event: &str, handler: Arc<dyn Fn(serde_json::Value) + Send + Sync>) {
+                test_bus()
+                    .subscribe_as(&self.0, event, move |event| {
+                        handler((*event.payload).clone());
+                        Ok(())
+                    })
+                    .unwrap();
+            }
+        }
+        PluginContext { event_bus: Arc::new(OwnedBus(id.to_string())), ..context(id, config, permissions) }
+    }
+
+    #[tokio::test]
+    async fn test_reload_drops_the_old_subscriptions() {
+        let delivered = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut plugin = EchoPlugin::with_dependencies("listener", "listener.run", &[]);
+        plugin.subscribes_to = Some(("test.reload_tick".to_string(), delivered.clone()));
+        let mut registry = PluginRegistry::new();
+        registry.set_on_release(|id| {
+            test_bus().unsubscribe_owner(id);
+        });
+        registry.register(Arc::new(plugin)).unwrap();
+        assert!(registry.initialize_each(&HashMap::new(), bus_context).await.is_empty());
+
+        test_bus().emit_simple("test.reload_tick", serde_json::json!({})).await.unwrap();
+        assert_eq!(delivered.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // The old instance is gone even though the new library fails to load
+        let dir = TempDir::new("rustwebui-missing");
+        let path = dir.join(format!("listener.{}", std::env::consts::DLL_EXTENSION));
+        registry.sources.insert("listener".to_string(), path);
+        assert!(registry.reload("listener", &HashMap::new(), bus_context).await.is_err());
+
+        test_bus().emit_simple("test.reload_tick", serde_json::json!({})).await.unwrap();
+        assert_eq!(delivered.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(test_bus().unsubscribe_owner("listener"), 0);
+    }
+}
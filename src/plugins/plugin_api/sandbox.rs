@@ -0,0 +1,388 @@
+//! Permissions and the brokered access plugins get to app resources
+//!
+//! A manifest lists what its plugin needs:
+//!
+//! ```toml
+//! permissions = ["database.read", "fs.read:/srv/reports", "fs.write:data", "network", "secrets", "settings"]
+//! ```
+//!
+//! `PluginContext` hands out `DatabaseAccess`, `FileSystemAccess`,
+//! `NetworkAccess`, `SecretsAccess` and `SettingsAccess` facades that check
+//! every call against these permissions.
+//! A call a plugin has no permission of that kind for fails with a
+//! "Capability not found" error; one outside what it was granted fails with
+//! "Access denied". Returned unchanged from `handle_command`, these errors
+//! reach the frontend with the `PluginCapabilityNotFound` and `AccessDenied`
+//! codes.
+
+use serde_json::Value;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+pub const ACCESS_DENIED: &str = "Access denied";
+pub const CAPABILITY_NOT_FOUND: &str = "Capability not found";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Permission {
+    /// `database.read`: read-only queries
+    DatabaseRead,
+    /// `database.write`: any statement; implies `database.read`
+    DatabaseWrite,
+    /// `fs.read:<path>`: read files and list directories below `path`
+    FsRead(PathBuf),
+    /// `fs.write:<path>`: also create and overwrite files below `path`
+    FsWrite(PathBuf),
+    /// `network`: open outgoing connections
+    Network,
+    /// `secrets`: keep the plugin's own secrets in the OS keychain
+    Secrets,
+    /// `secrets.read:<name>`: read the application secret `name`
+    SecretRead(String),
+    /// `settings`: keep the plugin's configuration in the settings store
+    Settings,
+}
+
+impl Permission {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        match text.split_once(':') {
+            Some(("fs.read", path)) if !path.is_empty() => Ok(Permission::FsRead(PathBuf::from(path))),
+            Some(("fs.write", path)) if !path.is_empty() => Ok(Permission::FsWrite(PathBuf::from(path))),
+            Some(("secrets.read", name)) if !name.is_empty() => Ok(Permission::SecretRead(name.to_string())),
+            _ => match text {
+                "database.read" => Ok(Permission::DatabaseRead),
+                "database.write" => Ok(Permission::DatabaseWrite),
+                "network" => Ok(Permission::Network),
+                "secrets" => Ok(Permission::Secrets),
+                "settings" => Ok(Permission::Settings),
+                "fs.read" | "fs.write" => Err(format!("Permission {} needs a path, e.g. {}:/some/path", text, text)),
+                "secrets.read" => Err("Permission secrets.read needs a secret name, e.g. secrets.read:api-token".to_string()),
+                _ => Err(format!("Unknown permission {}", text)),
+            },
+        }
+    }
+
+    /// The kind of resource, which decides between "capability not found" and "access denied"
+    fn kind(&self) -> &'static str {
+        match self {
+            Permission::DatabaseRead | Permission::DatabaseWrite => "database",
+            Permission::FsRead(_) | Permission::FsWrite(_) => "fs",
+            Permission::Network => "network",
+            Permission::Secrets | Permission::SecretRead(_) => "secrets",
+            Permission::Settings => "settings",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Permission::DatabaseRead => write!(f, "database.read"),
+            Permission::DatabaseWrite => write!(f, "database.write"),
+            Permission::FsRead(path) => write!(f, "fs.read:{}", path.display()),
+            Permission::FsWrite(path) => write!(f, "fs.write:{}", path.display()),
+            Permission::Network => write!(f, "network"),
+            Permission::Secrets => write!(f, "secrets"),
+            Permission::Settings => write!(f, "settings"),
+            Permission::SecretRead(name) => write!(f, "secrets.read:{}", name),
+        }
+    }
+}
+
+/// Absolute form of `path` with `.` and `..` removed and symlinks in its
+/// existing part resolved. File operations must use this path, so what was
+/// checked is what gets opened.
+pub fn resolve_path(path: &Path) -> PathBuf {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().map(|dir| dir.join(path)).unwrap_or_else(|_| path.to_path_buf())
+    };
+    let mut normal = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::ParentDir => {
+                normal.pop();
+            }
+            Component::CurDir => {}
+            other => normal.push(other),
+        }
+    }
+
+    // Files about to be created do not exist yet: resolve the nearest existing ancestor
+    let mut existing = normal.as_path();
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => break,
+        }
+    }
+    let mut resolved = existing.canonicalize().unwrap_or_else(|_| existing.to_path_buf());
+    resolved.extend(missing.iter().rev());
+    resolved
+}
+
+/// What a plugin was granted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Permissions {
+    granted: Vec<Permission>,
+}
+
+impl Permissions {
+    pub fn parse(list: &[String]) -> Result<Self, String> {
+        let granted = list.iter().map(|text| Permission::parse(text)).collect::<Result<_, _>>()?;
+        Ok(Self { granted })
+    }
+
+    pub fn granted(&self) -> &[Permission] {
+        &self.granted
+    }
+
+    /// Whether any permission for the same kind of resource was granted
+    pub fn has_kind(&self, needed: &Permission) -> bool {
+        self.granted.iter().any(|granted| granted.kind() == needed.kind())
+    }
+
+    /// Whether `needed` is covered; file system paths are compared resolved
+    pub fn allows(&self, needed: &Permission) -> bool {
+        self.granted.iter().any(|granted| match (granted, needed) {
+            (Permission::DatabaseWrite, Permission::DatabaseRead) => true,
+            (Permission::FsRead(root) | Permission::FsWrite(root), Permission::FsRead(path))
+            | (Permission::FsWrite(root), Permission::FsWrite(path)) => resolve_path(path).starts_with(resolve_path(root)),
+            _ => granted == needed,
+        })
+    }
+
+    /// `Ok` when `needed` is allowed, otherwise the error to return to the plugin
+    pub fn check(&self, plugin_id: &str, needed: &Permission) -> Result<(), AccessError> {
+        if self.allows(needed) {
+            return Ok(());
+        }
+        Err(AccessError {
+            plugin_id: plugin_id.to_string(),
+            permission: needed.to_string(),
+            not_declared: !self.has_kind(needed),
+        })
+    }
+}
+
+/// A call refused by the sandbox
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessError {
+    pub plugin_id: String,
+    pub permission: String,
+    /// No permission of this kind at all, rather than one that does not cover the call
+    pub not_declared: bool,
+}
+
+impl AccessError {
+    /// Whether a plugin error message came from the sandbox, and which kind;
+    /// `Some(true)` for a capability the plugin never declared
+    pub fn classify(message: &str) -> Option<bool> {
+        if message.starts_with(CAPABILITY_NOT_FOUND) {
+            Some(true)
+        } else if message.starts_with(ACCESS_DENIED) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.not_declared {
+            write!(f, "{}: plugin {} does not declare {}", CAPABILITY_NOT_FOUND, self.plugin_id, self.permission)
+        } else {
+            write!(f, "{}: plugin {} is not granted {}", ACCESS_DENIED, self.plugin_id, self.permission)
+        }
+    }
+}
+
+impl From<AccessError> for String {
+    fn from(error: AccessError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Queries against the app database
+pub trait DatabaseAccess: Send + Sync {
+    /// Run a read-only statement; rows as JSON objects keyed by column
+    fn query(&self, sql: &str, params: &[Value]) -> Result<Vec<Value>, String>;
+    /// Run a statement that may change data; returns the number of changed rows
+    fn execute(&self, sql: &str, params: &[Value]) -> Result<usize, String>;
+}
+
+/// Files below the granted paths
+pub trait FileSystemAccess: Send + Sync {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, String>;
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), String>;
+    fn list(&self, path: &Path) -> Result<Vec<PathBuf>, String>;
+}
+
+/// Outgoing connections
+pub trait NetworkAccess: Send + Sync {
+    fn connect(&self, address: &str) -> Result<std::net::TcpStream, String>;
+}
+
+/// Secrets in the OS keychain
+pub trait SecretsAccess: Send + Sync {
+    /// The plugin's own secret `name`
+    fn get(&self, name: &str) -> Result<Option<String>, String>;
+    fn set(&self, name: &str, value: &str) -> Result<(), String>;
+    /// Whether there was a value to delete
+    fn delete(&self, name: &str) -> Result<bool, String>;
+    /// Application secret `name`, such as an API token the app was configured with
+    fn get_shared(&self, name: &str) -> Result<Option<String>, String>;
+}
+
+/// The plugin's own namespace of the settings store, `plugin.<id>`
+pub trait SettingsAccess: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<Value>, String>;
+    /// Store `value`; `null` removes the key
+    fn set(&self, key: &str, value: Value) -> Result<(), String>;
+    /// Every key with its value
+    fn all(&self) -> Result<serde_json::Map<String, Value>, String>;
+}
+
+/// Facades refusing every call; what a context gets without a host
+struct NoAccess {
+    plugin_id: String,
+}
+
+impl NoAccess {
+    fn refuse<T>(&self, needed: Permission) -> Result<T, String> {
+        Err(Permissions::default().check(&self.plugin_id, &needed).unwrap_err().into())
+    }
+}
+
+impl DatabaseAccess for NoAccess {
+    fn query(&self, _sql: &str, _params: &[Value]) -> Result<Vec<Value>, String> {
+        self.refuse(Permission::DatabaseRead)
+    }
+
+    fn execute(&self, _sql: &str, _params: &[Value]) -> Result<usize, String> {
+        self.refuse(Permission::DatabaseWrite)
+    }
+}
+
+impl FileSystemAccess for NoAccess {
+    fn read(&self, path: &Path) -> Result<Vec<u8>, String> {
+        self.refuse(Permission::FsRead(path.to_path_buf()))
+    }
+
+    fn write(&self, path: &Path, _contents: &[u8]) -> Result<(), String> {
+        self.refuse(Permission::FsWrite(path.to_path_buf()))
+    }
+
+    fn list(&self, path: &Path) -> Result<Vec<PathBuf>, String> {
+        self.refuse(Permission::FsRead(path.to_path_buf()))
+    }
+}
+
+impl NetworkAccess for NoAccess {
+    fn connect(&self, _address: &str) -> Result<std::net::TcpStream, String> {
+        self.refuse(Permission::Network)
+    }
+}
+
+impl SecretsAccess for NoAccess {
+    fn get(&self, _name: &str) -> Result<Option<String>, String> {
+        self.refuse(Permission::Secrets)
+    }
+
+    fn set(&self, _name: &str, _value: &str) -> Result<(), String> {
+        self.refuse(Permission::Secrets)
+    }
+
+    fn delete(&self, _name: &str) -> Result<bool, String> {
+        self.refuse(Permission::Secrets)
+    }
+
+    fn get_shared(&self, name: &str) -> Result<Option<String>, String> {
+        self.refuse(Permission::SecretRead(name.to_string()))
+    }
+}
+
+impl SettingsAccess for NoAccess {
+    fn get(&self, _key: &str) -> Result<Option<Value>, String> {
+        self.refuse(Permission::Settings)
+    }
+
+    fn set(&self, _key: &str, _value: Value) -> Result<(), String> {
+        self.refuse(Permission::Settings)
+    }
+
+    fn all(&self) -> Result<serde_json::Map<String, Value>, String> {
+        self.refuse(Permission::Settings)
+    }
+}
+
+/// The facades a plugin reaches app resources through
+#[derive(Clone)]
+pub struct PluginAccess {
+    pub database: Arc<dyn DatabaseAccess>,
+    pub fs: Arc<dyn FileSystemAccess>,
+    pub network: Arc<dyn NetworkAccess>,
+    pub secrets: Arc<dyn SecretsAccess>,
+    pub settings: Arc<dyn SettingsAccess>,
+}
+
+impl PluginAccess {
+    /// Access that refuses everything
+    pub fn none(plugin_id: &str) -> Self {
+        let none = Arc::new(NoAccess { plugin_id: plugin_id.to_string() });
+        Self {
+            database: none.clone(),
+            fs: none.clone(),
+            network: none.clone(),
+            secrets: none.clone(),
+            settings: none,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_permissions() {
+        let permissions = Permissions::parse(&["database.write".to_string(), "fs.read:/srv/reports".to_string()]).unwrap();
+        assert_eq!(
+            permissions.granted(),
+            [Permission::DatabaseWrite, Permission::FsRead(PathBuf::from("/srv/reports"))]
+        );
+        assert!(Permission::parse("fs.read").unwrap_err().contains("needs a path"));
+        assert!(Permission::parse("shell").unwrap_err().contains("Unknown permission"));
+        assert_eq!(Permission::parse("fs.write:data").unwrap().to_string(), "fs.write:data");
+        assert_eq!(Permission::parse("secrets.read:api-token").unwrap(), Permission::SecretRead("api-token".to_string()));
+        assert!(Permission::parse("secrets.read").unwrap_err().contains("needs a secret name"));
+    }
+
+    #[test]
+    fn test_checks() {
+        let permissions = Permissions::parse(&["database.read".to_string(), "fs.read:/srv/reports".to_string()]).unwrap();
+        assert!(permissions.allows(&Permission::DatabaseRead));
+        assert!(permissions.allows(&Permission::FsRead(PathBuf::from("/srv/reports/2024/q1.csv"))));
+
+        let denied = permissions.check("reports", &Permission::DatabaseWrite).unwrap_err();
+        assert!(!denied.not_declared);
+        assert_eq!(AccessError::classify(&denied.to_string()), Some(false));
+
+        // `..` does not escape the granted directory
+        let escape = Permission::FsRead(PathBuf::from("/srv/reports/../secrets"));
+        assert!(!permissions.allows(&escape));
+        assert!(!permissions.allows(&Permission::FsWrite(PathBuf::from("/srv/reports/out.csv"))));
+
+        let undeclared = permissions.check("reports", &Permission::Network).unwrap_err();
+        assert_eq!(undeclared.to_string(), "Capability not found: plugin reports does not declare network");
+        assert_eq!(AccessError::classify(&undeclared.to_string()), Some(true));
+        assert_eq!(AccessError::classify("disk full"), None);
+    }
+}
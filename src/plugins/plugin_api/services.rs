@@ -0,0 +1,105 @@
+//! Services plugins provide to each other
+//!
+//! A plugin offers a service through a `PluginCapability::Service` and
+//! consumers resolve it by name and type with `PluginContext::get_service`.
+//! A service is published once its provider has initialized and withdrawn
+//! when it shuts down, so a consumer that lists it in `required_services`
+//! is initialized after the provider and can resolve it in `initialize`.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+struct ServiceEntry {
+    provider: String,
+    instance: Arc<dyn Any + Send + Sync>,
+}
+
+#[derive(Default)]
+pub struct ServiceRegistry {
+    services: RwLock<HashMap<String, ServiceEntry>>,
+}
+
+impl ServiceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve `name` as a `T`; fails if nothing provides it or it is another type
+    pub fn get<T: Any + Send + Sync>(&self, name: &str) -> Result<Arc<T>, String> {
+        let services = self.services.read().map_err(|_| "Service registry lock poisoned".to_string())?;
+        let entry = services.get(name).ok_or_else(|| format!("Service {} is not available", name))?;
+        entry.instance.clone().downcast::<T>().map_err(|_| {
+            format!(
+                "Service {} provided by {} is not a {}",
+                name,
+                entry.provider,
+                std::any::type_name::<T>()
+            )
+        })
+    }
+
+    /// Service name -> id of the plugin providing it
+    pub fn providers(&self) -> HashMap<String, String> {
+        self.services
+            .read()
+            .map(|services| {
+                services
+                    .iter()
+                    .map(|(name, entry)| (name.clone(), entry.provider.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn publish(&self, name: &str, provider: &str, instance: Arc<dyn Any + Send + Sync>) {
+        if let Ok(mut services) = self.services.write() {
+            services.insert(
+                name.to_string(),
+                ServiceEntry {
+                    provider: provider.to_string(),
+                    instance,
+                },
+            );
+        }
+    }
+
+    /// Remove every service `provider` published
+    pub(crate) fn withdraw(&self, provider: &str) {
+        if let Ok(mut services) = self.services.write() {
+            services.retain(|_, entry| entry.provider != provider);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    trait Greeter: Send + Sync {
+        fn greet(&self) -> String;
+    }
+
+    struct English;
+
+    impl Greeter for English {
+        fn greet(&self) -> String {
+            "Hello".to_string()
+        }
+    }
+
+    #[test]
+    fn test_typed_lookup() {
+        let registry = ServiceRegistry::new();
+        let greeter: Arc<dyn Greeter> = Arc::new(English);
+        registry.publish("greeter", "english", Arc::new(greeter));
+
+        assert_eq!(registry.get::<Arc<dyn Greeter>>("greeter").unwrap().greet(), "Hello");
+        let error = registry.get::<String>("greeter").err().unwrap();
+        assert!(error.contains("provided by english is not a"));
+        assert!(registry.get::<String>("missing").is_err());
+
+        registry.withdraw("english");
+        assert!(registry.providers().is_empty());
+    }
+}
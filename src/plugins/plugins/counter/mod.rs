@@ -0,0 +1 @@
+//! Counter plugin - not implemented yet; `counter.*` are built-in commands
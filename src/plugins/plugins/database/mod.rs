@@ -0,0 +1 @@
+//! Database plugin - not implemented yet; `get_users` and `get_db_stats` are built-in commands
@@ -1,8 +1,21 @@
 //! Built-in Plugins
 //! 
 //! These are the core plugins that provide essential functionality.
+//! `system_info` and `window_management` are complete `Plugin`
+//! implementations and double as reference plugins for third-party authors.
+
+use std::sync::Arc;
+use crate::plugins::Plugin;
 
 pub mod database;
 pub mod system_info;
 pub mod window_management;
 pub mod counter;
+
+/// The built-in plugins, registered before any plugin library is loaded
+pub fn builtin_plugins() -> Vec<Arc<dyn Plugin>> {
+    vec![
+        Arc::new(system_info::SystemInfoPlugin::new()),
+        Arc::new(window_management::WindowManagementPlugin::new()),
+    ]
+}
@@ -0,0 +1,93 @@
+//! System info plugin - `sysinfo.get`
+//!
+//! The smallest useful plugin: one stateless command, answered from the
+//! shared system information provider.
+
+use serde_json::Value;
+use std::sync::Arc;
+use crate::core::application::SystemInfoDto;
+use crate::core::domain::SystemInfoRepository;
+use crate::infrastructure::system_info::system_info_repository;
+use crate::plugins::{Plugin, PluginCapability, PluginContext, PluginMetadata};
+
+pub const PLUGIN_ID: &str = "system_info";
+
+pub struct SystemInfoPlugin {
+    metadata: PluginMetadata,
+}
+
+impl SystemInfoPlugin {
+    pub fn new() -> Self {
+        Self {
+            metadata: PluginMetadata {
+                id: PLUGIN_ID.to_string(),
+                name: "System Info".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                description: "Host operating system, CPU and memory information".to_string(),
+                author: "Rust WebUI".to_string(),
+                dependencies: Vec::new(),
+            },
+        }
+    }
+}
+
+impl Default for SystemInfoPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `sysinfo.get`: a snapshot of the host
+fn get() -> Result<Value, String> {
+    let info = system_info_repository().get_current().map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "success": true,
+        "data": SystemInfoDto::from(info),
+        "app_version": env!("CARGO_PKG_VERSION"),
+    }))
+}
+
+#[async_trait::async_trait]
+impl Plugin for SystemInfoPlugin {
+    fn metadata(&self) -> &PluginMetadata {
+        &self.metadata
+    }
+
+    fn capabilities(&self) -> Vec<PluginCapability> {
+        vec![PluginCapability::Command {
+            name: "sysinfo.get".to_string(),
+            description: "Operating system, CPU and memory of the host".to_string(),
+            handler: Arc::new(|_payload| Box::pin(async move { get() })),
+        }]
+    }
+
+    async fn initialize(&mut self, context: &PluginContext) -> Result<(), String> {
+        context.logger.debug("System info plugin ready");
+        Ok(())
+    }
+
+    async fn shutdown(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn handle_command(&self, command: &str, _payload: Value) -> Result<Value, String> {
+        match command {
+            "sysinfo.get" => get(),
+            _ => Err(format!("Unknown command: {}", command)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_returns_host_info() {
+        let plugin = SystemInfoPlugin::new();
+        let response = plugin.handle_command("sysinfo.get", Value::Null).await.unwrap();
+        assert_eq!(response["success"], true);
+        assert!(response["data"].is_object());
+        assert!(plugin.handle_command("sysinfo.other", Value::Null).await.is_err());
+    }
+}
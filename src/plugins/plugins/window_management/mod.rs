@@ -0,0 +1,194 @@
+//! Window management plugin - `window.open`, `window.close`, `window.set_title`
+//!
+//! Windows are addressed by their WebUI id, which `window.open` returns.
+//! Windows the plugin opened are closed again when it shuts down.
+
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use webui_rs::webui;
+use crate::infrastructure::server::frontend_config;
+use crate::plugins::{Plugin, PluginCapability, PluginContext, PluginMetadata};
+
+pub const PLUGIN_ID: &str = "window_management";
+
+/// Ids of the windows opened through `window.open`
+type OpenWindows = Arc<Mutex<BTreeSet<usize>>>;
+
+pub struct WindowManagementPlugin {
+    metadata: PluginMetadata,
+    opened: OpenWindows,
+}
+
+impl WindowManagementPlugin {
+    pub fn new() -> Self {
+        Self {
+            metadata: PluginMetadata {
+                id: PLUGIN_ID.to_string(),
+                name: "Window Management".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                description: "Open, close and retitle application windows".to_string(),
+                author: "Rust WebUI".to_string(),
+                dependencies: Vec::new(),
+            },
+            opened: OpenWindows::default(),
+        }
+    }
+}
+
+impl Default for WindowManagementPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// URL to show for a `window.open` payload: `url` as given, or `path` of the app's own UI
+fn target_url(payload: &Value) -> Result<String, String> {
+    if let Some(url) = payload.get("url").and_then(Value::as_str) {
+        return Ok(url.to_string());
+    }
+    let path = payload
+        .get("path")
+        .and_then(Value::as_str)
+        .ok_or("window.open needs a url or a path")?;
+    let port = frontend_config().map(|config| config.http_port).ok_or("The UI server is not running")?;
+    Ok(format!("http://localhost:{}/{}", port, path.trim_start_matches('/')))
+}
+
+fn window_id(payload: &Value) -> Result<usize, String> {
+    payload
+        .get("id")
+        .and_then(Value::as_u64)
+        .filter(|id| *id > 0)
+        .map(|id| id as usize)
+        .ok_or_else(|| "Missing or invalid window id".to_string())
+}
+
+/// A shown window by id
+fn shown_window(id: usize) -> Result<webui::Window, String> {
+    let window = webui::Window::from_id(id);
+    if window.is_shown() {
+        Ok(window)
+    } else {
+        Err(format!("Window {} is not open", id))
+    }
+}
+
+/// Script setting the page title; WebUI windows show the title of their page
+fn title_script(title: &str) -> String {
+    format!("document.title = {};", Value::from(title))
+}
+
+fn open(opened: &OpenWindows, payload: &Value) -> Result<Value, String> {
+    let url = target_url(payload)?;
+    let window = webui::Window::new();
+    if !window.show(&url) {
+        window.close();
+        return Err(format!("Could not show {}", url));
+    }
+    if let Some(title) = payload.get("title").and_then(Value::as_str) {
+        window.run_js(title_script(title));
+    }
+    if let Ok(mut opened) = opened.lock() {
+        opened.insert(window.id);
+    }
+    Ok(serde_json::json!({ "success": true, "id": window.id, "url": url }))
+}
+
+fn close(opened: &OpenWindows, payload: &Value) -> Result<Value, String> {
+    let id = window_id(payload)?;
+    shown_window(id)?.close();
+    if let Ok(mut opened) = opened.lock() {
+        opened.remove(&id);
+    }
+    Ok(serde_json::json!({ "success": true, "id": id }))
+}
+
+fn set_title(payload: &Value) -> Result<Value, String> {
+    let id = window_id(payload)?;
+    let title = payload.get("title").and_then(Value::as_str).ok_or("Missing title")?;
+    let result = shown_window(id)?.run_js(title_script(title));
+    if result.error {
+        return Err(format!("Could not set the title of window {}: {}", id, result.data));
+    }
+    Ok(serde_json::json!({ "success": true, "id": id, "title": title }))
+}
+
+#[async_trait::async_trait]
+impl Plugin for WindowManagementPlugin {
+    fn metadata(&self) -> &PluginMetadata {
+        &self.metadata
+    }
+
+    fn capabilities(&self) -> Vec<PluginCapability> {
+        let for_open = self.opened.clone();
+        let for_close = self.opened.clone();
+        vec![
+            PluginCapability::Command {
+                name: "window.open".to_string(),
+                description: "Open a window showing a URL or a path of the app UI".to_string(),
+                handler: Arc::new(move |payload| {
+                    let opened = for_open.clone();
+                    Box::pin(async move { open(&opened, &payload) })
+                }),
+            },
+            PluginCapability::Command {
+                name: "window.close".to_string(),
+                description: "Close a window by id".to_string(),
+                handler: Arc::new(move |payload| {
+                    let opened = for_close.clone();
+                    Box::pin(async move { close(&opened, &payload) })
+                }),
+            },
+            PluginCapability::Command {
+                name: "window.set_title".to_string(),
+                description: "Change the title of a window".to_string(),
+                handler: Arc::new(|payload| Box::pin(async move { set_title(&payload) })),
+            },
+        ]
+    }
+
+    async fn initialize(&mut self, context: &PluginContext) -> Result<(), String> {
+        context.logger.debug("Window management plugin ready");
+        Ok(())
+    }
+
+    async fn shutdown(&mut self) -> Result<(), String> {
+        let opened = std::mem::take(&mut *self.opened.lock().map_err(|_| "Window list lock poisoned".to_string())?);
+        for id in opened {
+            webui::Window::from_id(id).close();
+        }
+        Ok(())
+    }
+
+    async fn handle_command(&self, command: &str, payload: Value) -> Result<Value, String> {
+        match command {
+            "window.open" => open(&self.opened, &payload),
+            "window.close" => close(&self.opened, &payload),
+            "window.set_title" => set_title(&payload),
+            _ => Err(format!("Unknown command: {}", command)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_validation() {
+        let url = serde_json::json!({ "url": "https://example.com" });
+        assert_eq!(target_url(&url).unwrap(), "https://example.com");
+        assert!(target_url(&serde_json::json!({})).unwrap_err().contains("url or a path"));
+
+        assert_eq!(window_id(&serde_json::json!({ "id": 2 })).unwrap(), 2);
+        assert!(window_id(&serde_json::json!({ "id": 0 })).is_err());
+        assert!(window_id(&serde_json::json!({ "id": "main" })).is_err());
+        assert!(set_title(&serde_json::json!({ "id": 1 })).unwrap_err().contains("title"));
+    }
+
+    #[test]
+    fn test_title_is_escaped() {
+        assert_eq!(title_script("Say \"hi\"</script>"), "document.title = \"Say \\\"hi\\\"</script>\";");
+    }
+}
@@ -0,0 +1,198 @@
+//! Access log of the HTTP server, with an id per request
+//!
+//! Each request gets an id: the client's `X-Request-Id` when it is a
+//! plausible one, a fresh UUID otherwise. It is echoed in the response's
+//! `X-Request-Id`, set on a `http_request` tracing span around the handling,
+//! and made `request_context::current()` so the log ring buffer, error log
+//! entries and crash reports can name it. Once answered, the request is
+//! logged at info level with method, path, status, duration and body size
+//! (`[logging] access_log`), and kept among the last `access_log_recent`
+//! requests listed by `http.recent`.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::Read;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use tiny_http::{Header, Request, Response};
+use tracing::info;
+use utoipa::ToSchema;
+use crate::infrastructure::logging::request_context::{self, RequestGuard};
+use crate::model::core::AppConfig;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// One answered request
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AccessRecord {
+    pub request_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    /// Without the query string, which may carry tokens
+    pub path: String,
+    pub status: u16,
+    pub duration_ms: f64,
+    /// Body bytes sent; `None` for streams
+    pub bytes: Option<usize>,
+    pub remote: Option<String>,
+}
+
+pub struct AccessLog {
+    enabled: bool,
+    capacity: usize,
+    recent: Mutex<VecDeque<AccessRecord>>,
+}
+
+impl AccessLog {
+    pub fn new(enabled: bool, capacity: usize) -> Self {
+        Self { enabled, capacity, recent: Mutex::new(VecDeque::with_capacity(capacity)) }
+    }
+
+    pub fn global() -> &'static AccessLog {
+        static LOG: OnceLock<AccessLog> = OnceLock::new();
+        LOG.get_or_init(|| {
+            let config = AppConfig::global();
+            Self::new(config.is_access_log_enabled(), config.get_access_log_recent())
+        })
+    }
+
+    fn record(&self, record: AccessRecord) {
+        if self.enabled {
+            // request_id, method and path are fields of the `http_request` span
+            info!(
+                status = record.status,
+                duration_ms = record.duration_ms,
+                bytes = record.bytes,
+                "{} {} {}",
+                record.method,
+                record.path,
+                record.status
+            );
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        let mut recent = self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if recent.len() == self.capacity {
+            recent.pop_front();
+        }
+        recent.push_back(record);
+    }
+
+    /// The last `limit` requests, newest first
+    pub fn recent(&self, limit: Option<usize>) -> Vec<AccessRecord> {
+        let recent = self.recent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        recent.iter().rev().take(limit.unwrap_or(usize::MAX)).cloned().collect()
+    }
+}
+
+/// The client's id if it is one worth keeping: short, and safe in logs and headers
+fn given_id(value: &str) -> Option<&str> {
+    let valid = !value.is_empty()
+        && value.len() <= 64
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    valid.then_some(value)
+}
+
+/// A request being handled; answer it through `respond` so it is logged
+pub struct RequestScope {
+    id: String,
+    method: String,
+    path: String,
+    remote: Option<String>,
+    started: Instant,
+    _context: RequestGuard,
+    _span: tracing::span::EnteredSpan,
+}
+
+impl RequestScope {
+    pub fn begin(request: &Request) -> Self {
+        let id = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(REQUEST_ID_HEADER))
+            .and_then(|header| given_id(header.value.as_str()))
+            .map_or_else(|| uuid::Uuid::new_v4().simple().to_string(), str::to_string);
+        let method = request.method().to_string();
+        let path = request.url().split('?').next().unwrap_or_default().to_string();
+        let span = tracing::info_span!("http_request", request_id = %id, method = %method, path = %path).entered();
+        Self {
+            _context: request_context::enter(&id),
+            _span: span,
+            remote: request.remote_addr().map(|addr| addr.to_string()),
+            started: Instant::now(),
+            id,
+            method,
+            path,
+        }
+    }
+
+    /// `X-Request-Id` for the response
+    pub fn header(&self) -> Header {
+        Header::from_bytes(REQUEST_ID_HEADER.as_bytes(), self.id.as_bytes()).unwrap()
+    }
+
+    fn finish(self, status: u16, bytes: Option<usize>) {
+        AccessLog::global().record(AccessRecord {
+            request_id: self.id.clone(),
+            timestamp: Utc::now(),
+            method: self.method.clone(),
+            path: self.path.clone(),
+            status,
+            duration_ms: self.started.elapsed().as_secs_f64() * 1000.0,
+            bytes,
+            remote: self.remote.clone(),
+        });
+    }
+
+    /// Send `response` with the request id, and log it
+    pub fn respond<R: Read>(self, request: Request, response: Response<R>) -> std::io::Result<()> {
+        let response = response.with_header(self.header());
+        let status = response.status_code().0;
+        let bytes = response.data_length();
+        let result = request.respond(response);
+        self.finish(status, bytes);
+        result
+    }
+
+    /// Log a response that goes on as a stream, once its head is decided
+    pub fn streamed(self, status: u16) {
+        self.finish(status, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str) -> AccessRecord {
+        AccessRecord {
+            request_id: id.to_string(),
+            timestamp: Utc::now(),
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            status: 200,
+            duration_ms: 1.5,
+            bytes: Some(10),
+            remote: None,
+        }
+    }
+
+    #[test]
+    fn test_recent_requests_are_kept_newest_first() {
+        let log = AccessLog::new(false, 2);
+        for id in ["a", "b", "c"] {
+            log.record(record(id));
+        }
+        let ids: Vec<String> = log.recent(None).into_iter().map(|record| record.request_id).collect();
+        assert_eq!(ids, ["c", "b"]);
+        assert_eq!(log.recent(Some(1)).len(), 1);
+        assert!(AccessLog::new(false, 0).recent(None).is_empty());
+
+        assert_eq!(given_id("req-42_a.b"), Some("req-42_a.b"));
+        assert_eq!(given_id("bad id\r\nX-Injected: 1"), None);
+        assert_eq!(given_id(&"x".repeat(65)), None);
+        assert_eq!(given_id(""), None);
+    }
+}
@@ -0,0 +1,184 @@
+//! The asset manifest of the frontend build - served at `/api/assets`
+//!
+//! `rsbuild build` writes `frontend/dist/manifest.json` (`output.manifest`)
+//! listing the files it produced and which of them each entry point loads.
+//! It is read once at startup. Files listed in it whose names carry a content
+//! hash (`index.988e4988.js`) never change under that name, so they are
+//! served as immutable for a year; `index.html`, which names them, is always
+//! revalidated. The backend looks up the current bundle names here rather
+//! than guessing them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+pub const ASSETS_PATH: &str = "/api/assets";
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+const REVALIDATE: &str = "no-cache";
+
+/// What one entry point of the frontend loads
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct EntryAssets {
+    /// Scripts loaded up front, in order
+    pub js: Vec<String>,
+    pub css: Vec<String>,
+    /// Chunks loaded on demand
+    pub async_js: Vec<String>,
+    pub async_css: Vec<String>,
+    pub html: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct AssetManifest {
+    /// Entry points by name, e.g. `index`
+    pub entries: BTreeMap<String, EntryAssets>,
+    /// Every file of the build, as URL paths
+    pub files: Vec<String>,
+    /// Files served as immutable
+    #[serde(skip)]
+    hashed: HashSet<String>,
+}
+
+/// `manifest.json` as rsbuild writes it
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawManifest {
+    #[serde(default)]
+    all_files: Vec<String>,
+    #[serde(default)]
+    entries: BTreeMap<String, RawEntry>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawFiles {
+    #[serde(default)]
+    js: Vec<String>,
+    #[serde(default)]
+    css: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawEntry {
+    #[serde(default)]
+    initial: RawFiles,
+    #[serde(default, rename = "async")]
+    on_demand: RawFiles,
+    #[serde(default)]
+    html: Vec<String>,
+}
+
+/// Whether a file name carries a content hash: a dot-separated part of at least 8 hex digits
+fn is_content_hashed(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let parts: Vec<&str> = name.split('.').collect();
+    parts.len() > 2
+        && parts[1..parts.len() - 1]
+            .iter()
+            .any(|part| part.len() >= 8 && part.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+impl AssetManifest {
+    pub fn parse(json: &str) -> Result<Self, serde_json::Error> {
+        let raw: RawManifest = serde_json::from_str(json)?;
+        let entries = raw
+            .entries
+            .into_iter()
+            .map(|(name, entry)| {
+                let assets = EntryAssets {
+                    js: entry.initial.js,
+                    css: entry.initial.css,
+                    async_js: entry.on_demand.js,
+                    async_css: entry.on_demand.css,
+                    html: entry.html,
+                };
+                (name, assets)
+            })
+            .collect();
+        let hashed = raw.all_files.iter().filter(|file| is_content_hashed(file)).cloned().collect();
+        Ok(Self { entries, files: raw.all_files, hashed })
+    }
+
+    /// The manifest in `dist`; empty when the build wrote none
+    pub fn load(dist: &Path) -> Self {
+        let path = dist.join(MANIFEST_FILE);
+        let Ok(json) = std::fs::read_to_string(&path) else {
+            info!("No asset manifest at {}, hashed assets are not marked immutable", path.display());
+            return Self::default();
+        };
+        match Self::parse(&json) {
+            Ok(manifest) => {
+                info!("Asset manifest lists {} files, {} immutable", manifest.files.len(), manifest.hashed.len());
+                manifest
+            }
+            Err(e) => {
+                warn!(error = %e, path = %path.display(), "Ignoring unreadable asset manifest");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// `Cache-Control` for the file at URL path `path`, if it needs one
+    pub fn cache_control(&self, path: &str) -> Option<&'static str> {
+        if self.hashed.contains(path) {
+            Some(IMMUTABLE)
+        } else if path == "/" || path.ends_with(".html") {
+            Some(REVALIDATE)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"{
+        "allFiles": ["/static/js/index.988e4988.js", "/static/css/index.1a2b3c4d.css", "/static/js/async/42.deadbeef.js", "/favicon.ico", "/index.html"],
+        "entries": {
+            "index": {
+                "initial": { "js": ["/static/js/index.988e4988.js"], "css": ["/static/css/index.1a2b3c4d.css"] },
+                "async": { "js": ["/static/js/async/42.deadbeef.js"] },
+                "html": ["/index.html"]
+            }
+        },
+        "integrity": {}
+    }"#;
+
+    #[test]
+    fn test_rsbuild_manifest_is_read() {
+        let manifest = AssetManifest::parse(MANIFEST).unwrap();
+        let index = &manifest.entries["index"];
+        assert_eq!(index.js, ["/static/js/index.988e4988.js"]);
+        assert_eq!(index.css, ["/static/css/index.1a2b3c4d.css"]);
+        assert_eq!(index.async_js, ["/static/js/async/42.deadbeef.js"]);
+        assert!(index.async_css.is_empty());
+        assert_eq!(index.html, ["/index.html"]);
+        assert_eq!(manifest.files.len(), 5);
+        assert!(AssetManifest::parse("{\"allFiles\": 3}").is_err());
+    }
+
+    #[test]
+    fn test_only_hashed_files_of_the_build_are_immutable() {
+        let manifest = AssetManifest::parse(MANIFEST).unwrap();
+        assert_eq!(manifest.cache_control("/static/js/index.988e4988.js"), Some(IMMUTABLE));
+        assert_eq!(manifest.cache_control("/static/js/async/42.deadbeef.js"), Some(IMMUTABLE));
+        // Named like a build file, but not one
+        assert_eq!(manifest.cache_control("/static/js/index.00000000.js"), None);
+        assert_eq!(manifest.cache_control("/favicon.ico"), None);
+        assert_eq!(manifest.cache_control("/index.html"), Some(REVALIDATE));
+        assert_eq!(manifest.cache_control("/"), Some(REVALIDATE));
+
+        assert!(is_content_hashed("/static/js/lib-react.ab574441.js"));
+        assert!(!is_content_hashed("/static/js/winbox.min.js"));
+        assert!(!is_content_hashed("/deadbeef.js"));
+    }
+}
@@ -0,0 +1,275 @@
+//! AsyncAPI 3 document for the WebSocket protocol - served at `/api/asyncapi.json`
+//!
+//! Built from the JSON Schema registry (`infrastructure::schema`) and the
+//! command catalog: one `send` operation per command with its response as
+//! the reply, and one `receive` operation per event. The registry's `$defs`
+//! are collected under `components.schemas`.
+
+use serde_json::{Map, Value};
+use crate::infrastructure::event_bus::AppEventType;
+use crate::infrastructure::schema::{self, ERROR_RESPONSE, WS_MESSAGE};
+use crate::infrastructure::server::frontend_config;
+use crate::viewmodel::command_catalog::{self, CommandSpec};
+use crate::viewmodel::command_schemas;
+
+pub const ASYNCAPI_PATH: &str = "/api/asyncapi.json";
+
+/// Point `$ref`s into a schema's own `$defs` at `components.schemas`
+fn retarget(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                match field {
+                    Value::String(target) if key == "$ref" => {
+                        if let Some(name) = target.strip_prefix("#/$defs/") {
+                            *target = format!("#/components/schemas/{}", name);
+                        }
+                    }
+                    _ => retarget(field),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(retarget),
+        _ => {}
+    }
+}
+
+/// Add a registry schema and its `$defs` to `schemas`; returns a reference to it
+fn component(schema: &Value, schemas: &mut Map<String, Value>) -> Value {
+    let mut schema = schema.clone();
+    retarget(&mut schema);
+    let Value::Object(mut fields) = schema else {
+        return schema;
+    };
+    fields.remove("$schema");
+    if let Some(Value::Object(defs)) = fields.remove("$defs") {
+        schemas.extend(defs);
+    }
+    match fields.get("title").and_then(Value::as_str).map(str::to_string) {
+        Some(title) => {
+            schemas.insert(title.clone(), Value::Object(fields));
+            serde_json::json!({ "$ref": format!("#/components/schemas/{}", title) })
+        }
+        None => Value::Object(fields),
+    }
+}
+
+/// JSON Schema for the simple TypeScript types used by command parameters
+fn ts_type_schema(ts_type: &str) -> Value {
+    match ts_type {
+        "string" => serde_json::json!({ "type": "string" }),
+        "number" => serde_json::json!({ "type": "number" }),
+        "boolean" => serde_json::json!({ "type": "boolean" }),
+        "string[]" => serde_json::json!({ "type": "array", "items": { "type": "string" } }),
+        literals if literals.starts_with('\'') => {
+            let values: Vec<&str> = literals.split(" | ").map(|value| value.trim_matches('\'')).collect();
+            serde_json::json!({ "type": "string", "enum": values })
+        }
+        _ => serde_json::json!({}),
+    }
+}
+
+/// Parameters from the catalog, narrowed by the validation rules of `command_schemas`
+fn command_payload(command: &CommandSpec) -> Value {
+    let mut properties = Map::new();
+    for param in &command.params {
+        properties.insert(param.name.to_string(), ts_type_schema(param.ts_type));
+    }
+    if let Some(rules) = command_schemas::payload_schema(&command.name) {
+        for (name, constraints) in rules["properties"].as_object().into_iter().flatten() {
+            if let (Some(Value::Object(property)), Value::Object(constraints)) =
+                (properties.get_mut(name), constraints)
+            {
+                property.extend(constraints.clone());
+            }
+        }
+    }
+    let required: Vec<&str> = command.params.iter().filter(|param| !param.optional).map(|param| param.name).collect();
+    serde_json::json!({ "type": "object", "properties": properties, "required": required })
+}
+
+/// A message on the socket: the `WsMessage` envelope with a fixed `name` and its `payload`
+fn envelope(name: Option<&str>, payload: Value) -> Value {
+    let mut properties = Map::new();
+    if let Some(name) = name {
+        properties.insert("name".into(), serde_json::json!({ "const": name }));
+    }
+    properties.insert("payload".into(), payload);
+    serde_json::json!({
+        "allOf": [
+            { "$ref": "#/components/schemas/WsMessage" },
+            { "type": "object", "properties": properties },
+        ]
+    })
+}
+
+fn message_ref(channel: &str, key: &str) -> Value {
+    serde_json::json!({ "$ref": format!("#/channels/{}/messages/{}", channel, key) })
+}
+
+/// The whole document
+pub fn document() -> Value {
+    let registry = schema::all();
+    let mut schemas = Map::new();
+    let mut messages = Map::new();
+    let mut operations = Map::new();
+    let mut command_messages = Map::new();
+    let mut event_messages = Map::new();
+
+    component(&registry[WS_MESSAGE], &mut schemas);
+    let error_response = component(&registry[ERROR_RESPONSE], &mut schemas);
+    messages.insert(
+        "response".into(),
+        serde_json::json!({
+            "name": "response",
+            "summary": "Successful command response; `id` and `name` repeat the request",
+            "payload": envelope(None, serde_json::json!({
+                "type": "object",
+                "properties": { "success": { "const": true } },
+                "required": ["success"],
+            })),
+        }),
+    );
+    messages.insert(
+        ERROR_RESPONSE.into(),
+        serde_json::json!({
+            "name": ERROR_RESPONSE,
+            "summary": "Failed command response; `id` and `name` repeat the request",
+            "payload": envelope(None, error_response),
+        }),
+    );
+    let responses = serde_json::json!({
+        "response": { "$ref": "#/components/messages/response" },
+        ERROR_RESPONSE: { "$ref": format!("#/components/messages/{}", ERROR_RESPONSE) },
+    });
+
+    for command in command_catalog::commands() {
+        let key = format!("command.{}", command.name);
+        messages.insert(
+            key.clone(),
+            serde_json::json!({
+                "name": command.name,
+                "summary": command.description,
+                "payload": envelope(Some(&command.name), command_payload(&command)),
+            }),
+        );
+        command_messages.insert(key.clone(), serde_json::json!({ "$ref": format!("#/components/messages/{}", key) }));
+        operations.insert(
+            format!("call.{}", command.name),
+            serde_json::json!({
+                "action": "send",
+                "channel": { "$ref": "#/channels/commands" },
+                "summary": command.description,
+                "messages": [message_ref("commands", &key)],
+                "reply": {
+                    "channel": { "$ref": "#/channels/responses" },
+                    "messages": [message_ref("responses", "response"), message_ref("responses", ERROR_RESPONSE)],
+                },
+            }),
+        );
+    }
+
+    for event in AppEventType::ALL {
+        let name = event.to_string();
+        let key = format!("event.{}", name);
+        let payload = component(&registry["events"][&name], &mut schemas);
+        messages.insert(key.clone(), serde_json::json!({ "name": name, "payload": envelope(Some(&name), payload) }));
+        event_messages.insert(key.clone(), serde_json::json!({ "$ref": format!("#/components/messages/{}", key) }));
+        operations.insert(
+            format!("on.{}", name),
+            serde_json::json!({
+                "action": "receive",
+                "channel": { "$ref": "#/channels/events" },
+                "messages": [message_ref("events", &key)],
+            }),
+        );
+    }
+
+    for dto in registry["dtos"].as_object().into_iter().flat_map(|dtos| dtos.values()) {
+        component(dto, &mut schemas);
+    }
+
+    let mut document = serde_json::json!({
+        "asyncapi": "3.0.0",
+        "info": {
+            "title": "Rust WebUI WebSocket API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Commands, their responses and the application events, all sent as JSON \
+                `{ id, name, payload, timestamp, source }` messages over one WebSocket connection.",
+        },
+        "defaultContentType": "application/json",
+        "channels": {
+            "commands": {
+                "address": "/",
+                "description": "Commands sent by clients; `name` selects the command",
+                "messages": command_messages,
+            },
+            "responses": {
+                "address": "/",
+                "description": "One response per command, with the `id` of the request",
+                "messages": responses,
+            },
+            "events": {
+                "address": "/",
+                "description": "Event bus events broadcast to every client; `name` is the event",
+                "messages": event_messages,
+            },
+        },
+        "operations": operations,
+        "components": { "schemas": schemas, "messages": messages },
+    });
+    if let Some(config) = frontend_config() {
+        document["servers"] = serde_json::json!({
+            "local": { "host": format!("localhost:{}", config.ws_port), "protocol": "ws" },
+        });
+    }
+    document
+}
+
+/// The document served at `/api/asyncapi.json`
+pub fn asyncapi_json() -> String {
+    serde_json::to_string_pretty(&document()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every local `$ref` in `value` resolves within `document`
+    fn assert_refs_resolve(value: &Value, document: &Value) {
+        match value {
+            Value::Object(fields) => {
+                if let Some(Value::String(target)) = fields.get("$ref") {
+                    let pointer = target.strip_prefix('#').unwrap();
+                    assert!(document.pointer(pointer).is_some(), "unresolved {}", target);
+                }
+                fields.values().for_each(|field| assert_refs_resolve(field, document));
+            }
+            Value::Array(items) => items.iter().for_each(|item| assert_refs_resolve(item, document)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_document_is_self_consistent() {
+        let document = document();
+        assert_refs_resolve(&document, &document);
+
+        let create = &document["components"]["messages"]["command.counter.create"]["payload"]["allOf"][1];
+        assert_eq!(create["properties"]["name"]["const"], "counter.create");
+        let label = &create["properties"]["payload"]["properties"]["label"];
+        assert_eq!(label["type"], "string");
+        assert_eq!(label["maxLength"], 100);
+
+        assert_eq!(document["operations"]["on.counter.changed"]["action"], "receive");
+        assert_eq!(document["operations"]["call.counter.create"]["action"], "send");
+        assert!(document["components"]["schemas"]["Counter"].is_object());
+        assert!(document["components"]["schemas"]["UserDto"].is_object());
+    }
+
+    #[test]
+    fn test_ts_types() {
+        assert_eq!(ts_type_schema("'copy' | 'move'")["enum"], serde_json::json!(["copy", "move"]));
+        assert_eq!(ts_type_schema("User"), serde_json::json!({}));
+    }
+}
@@ -0,0 +1,118 @@
+//! `GET /webui.js` - the JavaScript bridge between the page and the backend
+//!
+//! The script is kept as `webui.js` next to this module and rendered once per
+//! run: each `__NAME__` placeholder in it is replaced with a value of this run
+//! as JSON (the published ports, the WebSocket port, the protocol version and
+//! the compression and features the bridge asks for in `hello`). The
+//! WebSocket token is deliberately not one of them: any page may load a
+//! script, so the bridge reads the token from `index.html` instead.
+
+use serde::Serialize;
+use crate::model::core::AppConfig;
+use crate::viewmodel::protocol;
+
+pub const BRIDGE_PATH: &str = "/webui.js";
+
+const TEMPLATE: &str = include_str!("webui.js");
+
+/// The values injected into the bridge
+#[derive(Debug, Clone, PartialEq)]
+pub struct BridgeConfig {
+    /// `/config.json` as published, exposed as `window.__APP_CONFIG__`
+    pub app_config_json: String,
+    pub ws_port: u16,
+    /// Whether the WebSocket refuses clients without the per-run token
+    pub token_required: bool,
+    pub protocol_version: u64,
+    /// Compression schemes offered when the browser can inflate
+    pub compression: Vec<&'static str>,
+    /// Features requested in `hello`
+    pub features: Vec<&'static str>,
+}
+
+impl BridgeConfig {
+    pub fn new(config: &AppConfig, app_config_json: &str, ws_port: u16) -> Self {
+        Self {
+            app_config_json: app_config_json.to_string(),
+            ws_port,
+            token_required: config.is_ws_token_required(),
+            protocol_version: protocol::PROTOCOL_VERSION,
+            compression: if config.is_ws_compression_enabled() { protocol::COMPRESSION.to_vec() } else { Vec::new() },
+            features: protocol::FEATURES.to_vec(),
+        }
+    }
+
+    /// The placeholders of the template and what replaces them
+    fn values(&self) -> [(&'static str, String); 6] {
+        let app_config = serde_json::from_str::<serde_json::Value>(&self.app_config_json).unwrap_or_default();
+        [
+            ("__APP_CONFIG_JSON__", json(&app_config)),
+            ("__WS_PORT__", json(&self.ws_port)),
+            ("__TOKEN_REQUIRED__", json(&self.token_required)),
+            ("__PROTOCOL_VERSION__", json(&self.protocol_version)),
+            ("__COMPRESSION__", json(&self.compression)),
+            ("__FEATURES__", json(&self.features)),
+        ]
+    }
+
+    /// The script to serve
+    pub fn render(&self) -> String {
+        self.values()
+            .iter()
+            .fold(TEMPLATE.to_string(), |script, (placeholder, value)| script.replace(placeholder, value))
+    }
+}
+
+/// `value` as a JavaScript literal; `<` is escaped so no value can end a `<script>` it is inlined in
+fn json(value: &impl Serialize) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "null".to_string()).replace('<', "\\u003c")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bridge() -> BridgeConfig {
+        BridgeConfig {
+            app_config_json: r#"{"http_port":8081,"ws_port":9001,"name":"</script>"}"#.to_string(),
+            ws_port: 9001,
+            token_required: true,
+            protocol_version: 3,
+            compression: vec!["deflate"],
+            features: vec!["events", "request_ids"],
+        }
+    }
+
+    #[test]
+    fn test_every_placeholder_is_rendered() {
+        let bridge = bridge();
+        for (placeholder, _) in bridge.values() {
+            assert!(TEMPLATE.contains(placeholder), "{} is not used by the template", placeholder);
+        }
+        let script = bridge.render();
+        for (placeholder, _) in bridge.values() {
+            assert!(!script.contains(placeholder), "{} was left in the script", placeholder);
+        }
+        assert!(script.contains(r#"window.__APP_CONFIG__ = {"http_port":8081,"name":"\u003c/script>","ws_port":9001};"#));
+        assert!(script.contains("':' + 9001 + wsToken"));
+        assert!(script.contains("const wsTokenRequired = true;"));
+        assert!(script.contains("protocol_version: 3,"));
+        assert!(script.contains(r#"compression: canInflate ? ["deflate"] : [],"#));
+        assert!(script.contains(r#"features: ["events","request_ids"],"#));
+    }
+
+    #[test]
+    fn test_values_follow_the_config() {
+        let mut config = AppConfig::default();
+        config.server.compression = Some(false);
+        let bridge = BridgeConfig::new(&config, "null", 9100);
+        assert!(!bridge.token_required && bridge.compression.is_empty());
+        assert_eq!(bridge.features, protocol::FEATURES);
+
+        let script = bridge.render();
+        assert!(script.contains("window.__APP_CONFIG__ = null;"));
+        assert!(script.contains("compression: canInflate ? [] : [],"));
+        // The token stays in index.html
+        assert!(!script.contains("window.__WS_TOKEN__ ="));
+    }
+}
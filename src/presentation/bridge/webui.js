@@ -0,0 +1,376 @@
+// WebUI JavaScript Bridge for communication with Rust backend
+//
+// Rendered by src/presentation/bridge: each __NAME__ placeholder is replaced
+// with a value of this run as JSON before the script is served.
+(function() {
+    console.log('WebUI JavaScript Bridge loaded');
+    
+    // Ports chosen by the backend for this run (see /config.json)
+    window.__APP_CONFIG__ = __APP_CONFIG_JSON__;
+
+    // Create a WebSocket connection to the backend
+    const wsProtocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
+    // The per-run token from index.html, when [server] ws_require_token is set; it is not
+    // part of this script, which any page could load
+    const wsTokenRequired = __TOKEN_REQUIRED__;
+    if (wsTokenRequired && !window.__WS_TOKEN__) {
+        console.warn('WebUI WebSocket needs the token from index.html; connections will be refused');
+    }
+    const wsToken = window.__WS_TOKEN__ ? '/?token=' + encodeURIComponent(window.__WS_TOKEN__) : '';
+    const wsUrl = wsProtocol + '//' + window.location.hostname + ':' + __WS_PORT__ + wsToken;
+    
+    let ws = null;
+    let isConnected = false;
+    let reconnectAttempts = 0;
+    let lastError = null;
+    // Settings agreed in the `hello` handshake; null until the backend answers
+    let protocol = null;
+    let protocolRejected = false;
+    // Session id from the last `hello`, to tell when the backend started a new session
+    const SESSION_KEY = 'webui_session';
+    // Resume token of the session, sent again on reconnect to keep the session
+    const RESUME_KEY = 'webui_resume_token';
+    // Id of the last event received, sent as resume_from on reconnect to get the events missed meanwhile
+    let lastEventId = null;
+    // Ids of requests sent, so their responses are not taken for events
+    const requestIds = new Set();
+    // Sequence numbers of reliable events already handled; they may be sent again until acknowledged
+    const seenSeqs = new Set();
+    // Deflated messages arrive as binary frames; inflating is async, so messages are handled in order through this chain
+    const canInflate = typeof DecompressionStream === 'function';
+    let inbox = Promise.resolve();
+
+    function sendRequest(message) {
+        if (message.id) {
+            requestIds.add(message.id);
+            // Messages that get no answer must not pile up
+            if (requestIds.size > 500) {
+                requestIds.delete(requestIds.values().next().value);
+            }
+        }
+        ws.send(JSON.stringify(message));
+    }
+
+    function readMessage(data) {
+        if (typeof data === 'string') {
+            return Promise.resolve(data);
+        }
+        const inflated = new Blob([data]).stream().pipeThrough(new DecompressionStream('deflate'));
+        return new Response(inflated).text();
+    }
+    
+    function handleMessage(text) {
+        console.log('WebUI received message:', text);
+        // Handle incoming messages from backend
+        try {
+            const data = JSON.parse(text);
+            console.log('Parsed message:', data);
+            
+            // Answer to the handshake: keep the agreed settings, or give up on a mismatch
+            if (data.name === 'hello') {
+                const payload = data.payload || {};
+                if (payload.success === false) {
+                    protocolRejected = true;
+                    lastError = payload.error || { message: 'Protocol rejected by backend' };
+                    console.error('WebUI protocol mismatch:', lastError.message);
+                } else {
+                    protocol = payload;
+                    if (payload.session) {
+                        // A new session numbers its reliable events from 1 again
+                        if (payload.session.id !== sessionStorage.getItem(SESSION_KEY)) {
+                            seenSeqs.clear();
+                        }
+                        sessionStorage.setItem(SESSION_KEY, payload.session.id);
+                    }
+                    // Only a new session comes with a token; a resumed one keeps the token already stored
+                    if (payload.resume_token) {
+                        sessionStorage.setItem(RESUME_KEY, payload.resume_token);
+                    }
+                    // Events were missed and cannot be replayed: state shown from before the gap is stale
+                    if (payload.resume && payload.resume.status === 'refresh') {
+                        console.warn('WebUI missed events while reconnecting:', payload.resume.reason);
+                        window.dispatchEvent(new CustomEvent('webui_refresh', { detail: payload.resume }));
+                    }
+                }
+                window.dispatchEvent(new CustomEvent('webui_protocol', { detail: payload }));
+                return;
+            }
+
+            // Reliable events are acknowledged every time, but handled once
+            if (typeof data.seq === 'number') {
+                if (ws && ws.readyState === WebSocket.OPEN) {
+                    ws.send(JSON.stringify({
+                        id: Math.random().toString(36).substring(2, 15),
+                        name: 'ack',
+                        payload: { seq: data.seq },
+                        timestamp: Date.now(),
+                        source: 'frontend'
+                    }));
+                }
+                if (seenSeqs.has(data.seq)) {
+                    return;
+                }
+                seenSeqs.add(data.seq);
+                if (seenSeqs.size > 1000) {
+                    seenSeqs.delete(seenSeqs.values().next().value);
+                }
+            }
+
+            if (!requestIds.delete(data.id) && data.id) {
+                lastEventId = data.id;
+            }
+            
+            // Check for function responses based on the name
+            if (data.name === 'get_users') {
+                // This is a response to get_users
+                window.dispatchEvent(new CustomEvent('db_response', { detail: data.payload || data }));
+                return;
+            }
+            
+            if (data.name === 'get_db_stats') {
+                // This is a response to get_db_stats
+                window.dispatchEvent(new CustomEvent('stats_response', { detail: data.payload || data }));
+                return;
+            }
+            
+            // Check for db_response event
+            if (data.name === 'db_response' || (data.payload && data.payload.success !== undefined)) {
+                const payload = data.payload || data;
+                window.dispatchEvent(new CustomEvent('db_response', { detail: payload }));
+                return;
+            }
+            
+            // Check for stats_response event
+            if (data.name === 'stats_response' || (data.payload && data.payload.stats !== undefined)) {
+                const payload = data.payload || data;
+                window.dispatchEvent(new CustomEvent('stats_response', { detail: payload }));
+                return;
+            }
+            
+            // Trigger generic webui_message event
+            window.dispatchEvent(new CustomEvent('webui_message', { detail: data }));
+        } catch(e) {
+            console.error('Error parsing WebUI message:', e);
+        }
+    }
+    
+    function connect() {
+        try {
+            ws = new WebSocket(wsUrl);
+            ws.binaryType = 'arraybuffer';
+            
+            ws.onopen = function(event) {
+                console.log('WebUI WebSocket connected');
+                isConnected = true;
+                reconnectAttempts = 0;
+                lastError = null;
+                protocol = null;
+                ws.send(JSON.stringify({
+                    id: Math.random().toString(36).substring(2, 15),
+                    name: 'hello',
+                    payload: {
+                        protocol_version: __PROTOCOL_VERSION__,
+                        formats: ['json'],
+                        compression: canInflate ? __COMPRESSION__ : [],
+                        features: __FEATURES__,
+                        resume_token: sessionStorage.getItem(RESUME_KEY) || undefined,
+                        resume_from: lastEventId || undefined,
+                        locale: navigator.language || undefined
+                    },
+                    timestamp: Date.now(),
+                    source: 'frontend'
+                }));
+            };
+            
+            ws.onmessage = function(event) {
+                inbox = inbox.then(function() {
+                    return readMessage(event.data);
+                }).then(handleMessage, function(e) {
+                    console.error('Error inflating WebUI message:', e);
+                });
+            };
+
+            ws.onclose = function(event) {
+                console.log('WebUI WebSocket disconnected');
+                isConnected = false;
+                // 1002: the backend does not speak our protocol, reconnecting will not help
+                if (protocolRejected || event.code === 1002) {
+                    console.error('WebUI WebSocket closed: unsupported protocol');
+                    return;
+                }
+                // 1008: the session expired or was revoked, the next connection starts a new one
+                if (event.code === 1008) {
+                    sessionStorage.removeItem(SESSION_KEY);
+                    sessionStorage.removeItem(RESUME_KEY);
+                }
+                reconnectAttempts++;
+                // Attempt to reconnect after delay
+                setTimeout(connect, 3000);
+            };
+            
+            ws.onerror = function(error) {
+                console.error('WebUI WebSocket error:', error);
+                lastError = { message: error.message || 'WebSocket error' };
+            };
+        } catch(e) {
+            console.error('Failed to create WebUI WebSocket connection:', e);
+        }
+    }
+    
+    // Initialize connection
+    connect();
+
+    // Report native window geometry so the backend can restore it on next start
+    let geometryTimer = null;
+    function reportWindowGeometry(action) {
+        if (!ws || ws.readyState !== WebSocket.OPEN) {
+            return;
+        }
+        sendRequest({
+            id: Math.random().toString(36).substring(2, 15),
+            name: 'window_state_change',
+            payload: {
+                id: 'main',
+                action: action,
+                x: window.screenX,
+                y: window.screenY,
+                width: window.outerWidth,
+                height: window.outerHeight,
+                maximized: window.outerWidth >= screen.availWidth && window.outerHeight >= screen.availHeight
+            },
+            timestamp: Date.now(),
+            source: 'frontend'
+        });
+    }
+
+    window.addEventListener('resize', function() {
+        clearTimeout(geometryTimer);
+        geometryTimer = setTimeout(function() {
+            reportWindowGeometry('resized');
+        }, 500);
+    });
+
+    window.addEventListener('beforeunload', function() {
+        reportWindowGeometry('moved');
+    });
+    
+    // Expose WebUI functions to global scope
+    window.WebUI = {
+        isConnected: function() {
+            return isConnected;
+        },
+        getConnectionState: function() {
+            let state = 'closed';
+            if (isConnected) {
+                state = 'ready';
+            } else if (ws && ws.readyState === 0) {
+                state = 'connecting';
+            } else if (ws && ws.readyState === 1) {
+                state = 'open';
+            } else if (reconnectAttempts > 0) {
+                state = 'reconnecting';
+            }
+            return {
+                state: state,
+                reconnectAttempts: reconnectAttempts
+            };
+        },
+        getReadyState: function() {
+            return ws ? ws.readyState : 3; // 3 = CLOSED
+        },
+        getLastError: function() {
+            return lastError;
+        },
+        getProtocol: function() {
+            return protocol;
+        },
+        send: function(data) {
+            if (ws && ws.readyState === WebSocket.OPEN) {
+                sendRequest(data);
+                return true;
+            }
+            console.warn('WebUI WebSocket not connected');
+            return false;
+        },
+        onMessage: function(callback) {
+            window.addEventListener('webui_message', function(event) {
+                callback(event.detail);
+            });
+        }
+    };
+    
+    // Expose functions that frontend expects
+    window.getUsers = function() {
+        console.log('getUsers called');
+        if (ws && ws.readyState === WebSocket.OPEN) {
+            sendRequest({
+                id: Math.random().toString(36).substring(2, 15),
+                name: 'get_users',
+                payload: {},
+                timestamp: Date.now(),
+                source: 'frontend'
+            });
+        } else {
+            console.warn('WebSocket not connected');
+            // Dispatch empty response to prevent infinite loading
+            window.dispatchEvent(new CustomEvent('db_response', { 
+                detail: { success: false, error: 'WebSocket not connected', data: [] } 
+            }));
+        }
+    };
+    
+    window.getDbStats = function() {
+        console.log('getDbStats called');
+        if (ws && ws.readyState === WebSocket.OPEN) {
+            sendRequest({
+                id: Math.random().toString(36).substring(2, 15),
+                name: 'get_db_stats',
+                payload: {},
+                timestamp: Date.now(),
+                source: 'frontend'
+            });
+        } else {
+            console.warn('WebSocket not connected');
+            window.dispatchEvent(new CustomEvent('stats_response', { 
+                detail: { success: false, error: 'WebSocket not connected', stats: { users: 0, tables: [] } } 
+            }));
+        }
+    };
+    
+    // webui.call() - Send a call to Rust backend and expect response
+    window.webui = {
+        call: function(functionName, data) {
+            console.log('webui.call:', functionName, data);
+            // Send the function call through WebSocket
+            if (ws && ws.readyState === WebSocket.OPEN) {
+                sendRequest({
+                    id: Math.random().toString(36).substring(2, 15),
+                    name: functionName,
+                    payload: data || {},
+                    timestamp: Date.now(),
+                    source: 'frontend'
+                });
+                return true;
+            }
+            console.warn('WebUI WebSocket not connected, cannot call:', functionName);
+            return false;
+        }
+    };
+    
+    // Bind function for UI elements (original WebUI behavior)
+    window.webui_bind = function(elementId, callback) {
+        const element = document.getElementById(elementId);
+        if (element) {
+            element.addEventListener('click', function() {
+                callback();
+            });
+        }
+    };
+    
+    // Return function for sending data back to backend
+    window.webui_return = function(id, data) {
+        window.WebUI.send({ id: id, data: data });
+    };
+    
+    console.log('WebUI bridge initialized with getUsers/getDbStats functions');
+})();
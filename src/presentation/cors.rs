@@ -0,0 +1,142 @@
+//! Cross-origin access to the HTTP API, from `[cors]`
+//!
+//! Pages served by the app itself are always allowed. Other origins, such as
+//! the rsbuild dev server, must be listed in `allowed_origins`; `"*"` allows
+//! any. Allowed origins get `Access-Control-Allow-Origin` echoed back on
+//! every response, and `OPTIONS` preflights are answered here before routing.
+
+use std::io::{self, Read};
+use tiny_http::{Header, Response, StatusCode};
+use crate::model::core::AppConfig;
+
+pub struct CorsPolicy {
+    /// `http://localhost:<port>` and `http://127.0.0.1:<port>` of the app's own pages
+    own_origins: Vec<String>,
+    allowed_origins: Vec<String>,
+    allowed_methods: Vec<String>,
+    allowed_headers: Vec<String>,
+    max_age_secs: u64,
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).unwrap()
+}
+
+impl CorsPolicy {
+    pub fn new(http_port: u16, allowed_origins: Vec<String>, allowed_methods: Vec<String>, allowed_headers: Vec<String>, max_age_secs: u64) -> Self {
+        Self {
+            own_origins: vec![format!("http://localhost:{}", http_port), format!("http://127.0.0.1:{}", http_port)],
+            allowed_origins: allowed_origins.into_iter().map(|origin| origin.trim_end_matches('/').to_string()).collect(),
+            allowed_methods: allowed_methods.into_iter().map(|method| method.to_uppercase()).collect(),
+            allowed_headers,
+            max_age_secs,
+        }
+    }
+
+    pub fn from_config(config: &AppConfig, http_port: u16) -> Self {
+        Self::new(
+            http_port,
+            config.get_cors_allowed_origins(),
+            config.get_cors_allowed_methods(),
+            config.get_cors_allowed_headers(),
+            config.get_cors_max_age_secs(),
+        )
+    }
+
+    fn allows_any(&self) -> bool {
+        self.allowed_origins.iter().any(|origin| origin == "*")
+    }
+
+    /// Whether a page from `origin` may use the API
+    pub fn allows(&self, origin: &str) -> bool {
+        self.own_origins.iter().chain(&self.allowed_origins).any(|allowed| allowed == origin) || self.allows_any()
+    }
+
+    /// Add the CORS headers for a request from `origin`, if it is allowed
+    pub fn apply<R: Read>(&self, mut response: Response<R>, origin: Option<&str>) -> Response<R> {
+        let Some(origin) = origin.filter(|origin| self.allows(origin)) else {
+            return response;
+        };
+        if self.allows_any() {
+            response.add_header(header("Access-Control-Allow-Origin", "*"));
+        } else {
+            response.add_header(header("Access-Control-Allow-Origin", origin));
+            response.add_header(header("Vary", "Origin"));
+        }
+        response
+    }
+
+    /// Answer an `OPTIONS` preflight: 204 with the allowed methods and headers, or 403
+    pub fn preflight(&self, origin: Option<&str>, method: Option<&str>, headers: Option<&str>) -> Response<io::Empty> {
+        let method_allowed = method.is_some_and(|method| self.allowed_methods.iter().any(|allowed| allowed == method));
+        let headers_allowed = headers
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|requested| !requested.is_empty())
+            .all(|requested| self.allowed_headers.iter().any(|allowed| allowed.eq_ignore_ascii_case(requested)));
+        if !origin.is_some_and(|origin| self.allows(origin)) || !method_allowed || !headers_allowed {
+            return Response::new_empty(StatusCode(403));
+        }
+
+        let response = Response::new_empty(StatusCode(204))
+            .with_header(header("Access-Control-Allow-Methods", &self.allowed_methods.join(", ")))
+            .with_header(header("Access-Control-Allow-Headers", &self.allowed_headers.join(", ")))
+            .with_header(header("Access-Control-Max-Age", &self.max_age_secs.to_string()));
+        self.apply(response, origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy_for(origins: &[&str]) -> CorsPolicy {
+        CorsPolicy::new(
+            8080,
+            origins.iter().map(|origin| origin.to_string()).collect(),
+            vec!["get".to_string(), "POST".to_string()],
+            vec!["Content-Type".to_string(), "X-DevTools-Token".to_string()],
+            600,
+        )
+    }
+
+    fn header_value<R: Read>(response: &Response<R>, field: &'static str) -> Option<String> {
+        response
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(field))
+            .map(|header| header.value.to_string())
+    }
+
+    #[test]
+    fn test_only_listed_origins_get_cors_headers() {
+        let policy = policy_for(&["http://localhost:3000/"]);
+        assert!(policy.allows("http://127.0.0.1:8080"));
+        assert!(policy.allows("http://localhost:3000"));
+        assert!(!policy.allows("https://example.com"));
+
+        let response = policy.apply(Response::empty(200), Some("http://localhost:3000"));
+        assert_eq!(header_value(&response, "Access-Control-Allow-Origin").as_deref(), Some("http://localhost:3000"));
+        let response = policy.apply(Response::empty(200), Some("https://example.com"));
+        assert_eq!(header_value(&response, "Access-Control-Allow-Origin"), None);
+
+        let any = policy_for(&["*"]);
+        let response = any.apply(Response::empty(200), Some("https://example.com"));
+        assert_eq!(header_value(&response, "Access-Control-Allow-Origin").as_deref(), Some("*"));
+    }
+
+    #[test]
+    fn test_preflight() {
+        let policy = policy_for(&["http://localhost:3000"]);
+        let origin = Some("http://localhost:3000");
+
+        let response = policy.preflight(origin, Some("GET"), Some("x-devtools-token, content-type"));
+        assert_eq!(response.status_code(), StatusCode(204));
+        assert_eq!(header_value(&response, "Access-Control-Allow-Methods").as_deref(), Some("GET, POST"));
+
+        assert_eq!(policy.preflight(origin, Some("DELETE"), None).status_code(), StatusCode(403));
+        assert_eq!(policy.preflight(origin, Some("GET"), Some("X-Other")).status_code(), StatusCode(403));
+        assert_eq!(policy.preflight(Some("https://example.com"), Some("GET"), None).status_code(), StatusCode(403));
+    }
+}
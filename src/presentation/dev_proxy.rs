@@ -0,0 +1,268 @@
+//! Forwarding frontend requests to the rsbuild dev server, from `[dev] proxy_url`
+//!
+//! With a proxy URL set, requests that no backend route answers go to the dev
+//! server instead of `frontend/dist`, so the page always reflects the sources
+//! being edited. The backend routes themselves (`/webui.js`, `/config.json`,
+//! `/api/...`) are still served from Rust. HTML pages get the same tokens
+//! injected as `index.html`, and a `<script src="/webui.js">` if they lack
+//! one. HMR keeps working because the rsbuild client opens its socket to the
+//! dev server directly, see `dev.client` in `frontend/rsbuild.config.dev.ts`.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use tiny_http::{Header, Request, Response, StatusCode};
+use crate::model::core::AppConfig;
+use crate::presentation::bridge::BRIDGE_PATH;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Headers that describe one connection rather than the message, never forwarded
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DevProxy {
+    host: String,
+    port: u16,
+    /// Path prefix of the dev server, without trailing slash
+    base: String,
+}
+
+/// An answer of the dev server, read in full
+#[derive(Debug)]
+pub struct Upstream {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl DevProxy {
+    /// The proxy for this run; `None` when `[dev] proxy_url` is unset or invalid
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        let url = config.get_dev_proxy_url()?;
+        match Self::parse(url) {
+            Ok(proxy) => Some(proxy),
+            Err(e) => {
+                tracing::warn!("Ignoring [dev] proxy_url {:?}: {}", url, e);
+                None
+            }
+        }
+    }
+
+    pub fn parse(url: &str) -> Result<Self, String> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| "only http:// dev servers are supported".to_string())?;
+        let (authority, base) = rest.split_once('/').map_or((rest, ""), |(authority, path)| (authority, path));
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse::<u16>().map_err(|_| format!("invalid port {:?}", port))?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err("missing host".to_string());
+        }
+        let base = base.trim_end_matches('/');
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            base: if base.is_empty() { String::new() } else { format!("/{}", base) },
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}:{}{}", self.host, self.port, self.base)
+    }
+
+    /// Send `request` on to the dev server and read its answer
+    pub fn forward(&self, request: &mut Request) -> io::Result<Upstream> {
+        let headers: Vec<(String, String)> = request
+            .headers()
+            .iter()
+            .map(|header| (header.field.to_string(), header.value.to_string()))
+            .collect();
+        let mut body = Vec::new();
+        request.as_reader().read_to_end(&mut body)?;
+        let method = request.method().to_string();
+        self.send(&method, request.url(), &headers, &body)
+    }
+
+    fn send(&self, method: &str, url: &str, headers: &[(String, String)], body: &[u8]) -> io::Result<Upstream> {
+        let addr = (self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} does not resolve", self.host)))?;
+        let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        stream.write_all(&self.request_head(method, url, headers, body.len()))?;
+        stream.write_all(body)?;
+        read_response(BufReader::new(stream), method == "HEAD")
+    }
+
+    fn request_head(&self, method: &str, url: &str, headers: &[(String, String)], body_len: usize) -> Vec<u8> {
+        let mut head = format!("{} {}{} HTTP/1.1\r\nHost: {}:{}\r\n", method, self.base, url, self.host, self.port);
+        for (field, value) in headers {
+            let lower = field.to_ascii_lowercase();
+            // Pages are rewritten, so they must come uncompressed
+            if HOP_BY_HOP.contains(&lower.as_str()) || matches!(lower.as_str(), "host" | "content-length" | "accept-encoding") {
+                continue;
+            }
+            head.push_str(&format!("{}: {}\r\n", field, value));
+        }
+        if body_len > 0 {
+            head.push_str(&format!("Content-Length: {}\r\n", body_len));
+        }
+        head.push_str("Connection: close\r\n\r\n");
+        head.into_bytes()
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn read_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(invalid("dev server closed the connection"));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn read_response(mut reader: impl BufRead, head_only: bool) -> io::Result<Upstream> {
+    let status_line = read_line(&mut reader)?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| invalid("malformed status line"))?;
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((field, value)) = line.split_once(':') {
+            headers.push((field.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    };
+
+    let mut body = Vec::new();
+    if head_only || status == 204 || status == 304 || (100..200).contains(&status) {
+        // No body
+    } else if header("Transfer-Encoding").is_some_and(|value| value.eq_ignore_ascii_case("chunked")) {
+        loop {
+            let size = read_line(&mut reader)?;
+            let size = usize::from_str_radix(size.split(';').next().unwrap_or_default().trim(), 16)
+                .map_err(|_| invalid("malformed chunk size"))?;
+            if size == 0 {
+                break;
+            }
+            let start = body.len();
+            body.resize(start + size, 0);
+            reader.read_exact(&mut body[start..])?;
+            read_line(&mut reader)?;
+        }
+    } else if let Some(len) = header("Content-Length").and_then(|value| value.parse::<usize>().ok()) {
+        body.resize(len, 0);
+        reader.read_exact(&mut body)?;
+    } else {
+        reader.read_to_end(&mut body)?;
+    }
+
+    headers.retain(|(field, _)| {
+        let lower = field.to_ascii_lowercase();
+        !HOP_BY_HOP.contains(&lower.as_str()) && lower != "content-length"
+    });
+    Ok(Upstream { status, headers, body })
+}
+
+impl Upstream {
+    pub fn is_html(&self) -> bool {
+        self.headers
+            .iter()
+            .any(|(field, value)| field.eq_ignore_ascii_case("Content-Type") && value.starts_with("text/html"))
+    }
+
+    /// Rewrite an HTML page with `inject`, and make sure it loads the bridge
+    pub fn rewrite_html(&mut self, inject: impl FnOnce(&str) -> String) {
+        let mut html = inject(&String::from_utf8_lossy(&self.body));
+        if !html.contains(&format!("src=\"{}\"", BRIDGE_PATH)) {
+            let tag = format!("<script src=\"{}\"></script>", BRIDGE_PATH);
+            match html.rfind("</body>") {
+                Some(index) => html.insert_str(index, &tag),
+                None => html.push_str(&tag),
+            }
+        }
+        self.body = html.into_bytes();
+    }
+
+    pub fn into_response(self) -> Response<io::Cursor<Vec<u8>>> {
+        let headers = self
+            .headers
+            .iter()
+            .filter_map(|(field, value)| Header::from_bytes(field.as_bytes(), value.as_bytes()).ok())
+            .collect();
+        let len = self.body.len();
+        Response::new(StatusCode(self.status), headers, io::Cursor::new(self.body), Some(len), None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_url_is_parsed() {
+        let proxy = DevProxy::parse("http://localhost:3000/").unwrap();
+        assert_eq!((proxy.host.as_str(), proxy.port, proxy.base.as_str()), ("localhost", 3000, ""));
+        assert_eq!(DevProxy::parse("http://127.0.0.1/app/").unwrap().url(), "http://127.0.0.1:80/app");
+        assert!(DevProxy::parse("https://localhost:3000").is_err());
+        assert!(DevProxy::parse("http://localhost:dev").is_err());
+        assert!(DevProxy::parse("http://:3000").is_err());
+
+        let head = String::from_utf8(proxy.request_head(
+            "GET",
+            "/static/js/index.js",
+            &[("Host".to_string(), "localhost:8080".to_string()), ("Accept-Encoding".to_string(), "gzip".to_string()), ("Accept".to_string(), "*/*".to_string())],
+            0,
+        ))
+        .unwrap();
+        assert_eq!(head, "GET /static/js/index.js HTTP/1.1\r\nHost: localhost:3000\r\nAccept: */*\r\nConnection: close\r\n\r\n");
+    }
+
+    #[test]
+    fn test_chunked_page_gets_the_bridge() {
+        let raw = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nTransfer-Encoding: chunked\r\nConnection: keep-alive\r\n\r\n\
+                   6\r\n<html>\r\nD\r\n<body></body>\r\n8\r\n</html>\n\r\n0\r\n\r\n";
+        let mut upstream = read_response(raw.as_bytes(), false).unwrap();
+        assert_eq!(upstream.status, 200);
+        assert_eq!(upstream.headers, [("Content-Type".to_string(), "text/html".to_string())]);
+        assert!(upstream.is_html());
+
+        upstream.rewrite_html(|html| html.replace("<body>", "<body><p>token</p>"));
+        assert_eq!(
+            String::from_utf8(upstream.body).unwrap(),
+            "<html><body><p>token</p><script src=\"/webui.js\"></script></body></html>\n"
+        );
+
+        let not_modified = read_response("HTTP/1.1 304 Not Modified\r\nETag: \"1\"\r\n\r\n".as_bytes(), false).unwrap();
+        assert!(not_modified.body.is_empty());
+    }
+}
@@ -2,9 +2,37 @@
 
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+use tracing::info;
+use utoipa::ToSchema;
+use crate::core::application::SystemInfoDto;
+use crate::core::domain::{DatabaseStatsRepository, SystemInfoRepository};
+use crate::error_handling::{circuit_breaker_stats, reset_circuit_breaker, CircuitBreakerStats};
+use crate::infrastructure::bench;
+use crate::infrastructure::cache::{CacheStats, QueryCache};
+use crate::infrastructure::command_metrics::{CommandMetrics, CommandStats};
+use crate::infrastructure::crash_reporter;
+use crate::infrastructure::database::{database, database_stats_repository};
+use crate::infrastructure::event_bus::tap::TapFilter;
+use crate::infrastructure::event_bus::{EventBus, TopicStats};
+use crate::infrastructure::jwt;
+use crate::infrastructure::logging::{log_filter, ring_buffer};
+use crate::infrastructure::runtime;
+use crate::infrastructure::scheduler::Scheduler;
+use crate::infrastructure::startup;
+use crate::infrastructure::system_info::system_info_repository;
+use crate::infrastructure::workers::{WorkerPoolStats, Workers};
+use crate::model::core::AppConfig;
+use crate::presentation::access_log::AccessLog;
+use crate::presentation::health::{self, HealthReport};
+
+fn bytes_to_mb(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
 
 /// System metrics snapshot
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SystemMetrics {
     pub timestamp: DateTime<Utc>,
     pub uptime_secs: u64,
@@ -12,39 +40,52 @@ pub struct SystemMetrics {
     pub connections: ConnectionMetrics,
     pub database: DatabaseMetrics,
     pub events: EventMetrics,
+    /// Calls of each command, slowest first
+    pub commands: Vec<CommandStats>,
+    pub host: Option<SystemInfoDto>,
+    pub circuit_breakers: Vec<CircuitBreakerStats>,
+    pub cache: CacheStats,
+    pub workers: Vec<WorkerPoolStats>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct MemoryMetrics {
     pub process_memory_mb: f64,
     pub available_system_mb: f64,
+    pub total_system_mb: f64,
+    pub used_system_mb: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ConnectionMetrics {
     pub websocket_active: usize,
     pub http_requests_total: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DatabaseMetrics {
     pub tables: Vec<TableStats>,
     pub total_records: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TableStats {
     pub name: String,
     pub row_count: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct EventMetrics {
     pub total_emitted: u64,
     pub recent_events: Vec<RecentEvent>,
+    pub topics: Vec<TopicStats>,
+    /// Receivers of the broadcast channel, one per connected client and bridge
+    pub listeners: usize,
+    /// Event taps streaming to devtools clients
+    pub open_taps: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct RecentEvent {
     pub id: String,
     pub name: String,
@@ -52,18 +93,174 @@ pub struct RecentEvent {
     pub source: String,
 }
 
+/// Why a devtools request was refused, with the HTTP status to answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessDenied {
+    pub status: u16,
+    pub message: &'static str,
+}
+
+/// Who may call the `/api/devtools/*` routes, from the `[devtools]` settings
+#[derive(Debug, Clone)]
+pub struct AccessPolicy {
+    pub enabled: bool,
+    pub local_only: bool,
+    /// Generated per run; `None` when `require_token = false`
+    pub token: Option<String>,
+    /// Whether an admin's `[jwt]` API token is accepted in place of `token`
+    pub api_tokens: bool,
+    /// Port the HTTP server is bound to, which a local request's `Host` names
+    pub port: u16,
+}
+
+impl AccessPolicy {
+    pub fn from_config(config: &AppConfig, port: u16) -> Self {
+        Self {
+            enabled: config.is_devtools_enabled(),
+            local_only: config.is_devtools_local_only(),
+            token: config
+                .is_devtools_token_required()
+                .then(|| uuid::Uuid::new_v4().simple().to_string()),
+            api_tokens: config.is_jwt_enabled(),
+            port,
+        }
+    }
+
+    pub fn check(&self, remote: Option<&SocketAddr>, host: Option<&str>, token: Option<&str>) -> Result<(), AccessDenied> {
+        self.check_local(remote, host)?;
+        if self.token.is_some() && !token.is_some_and(|given| self.accepts(given)) {
+            return Err(AccessDenied { status: 401, message: "Missing or invalid DevTools token" });
+        }
+        Ok(())
+    }
+
+    /// Whether `given` is this run's DevTools token, or an admin's API token with `[jwt]` enabled
+    pub fn accepts(&self, given: &str) -> bool {
+        let devtools_token = self.token.as_ref().filter(|_| self.enabled);
+        devtools_token.is_some_and(|expected| constant_time_eq(given.as_bytes(), expected.as_bytes()))
+            || (self.api_tokens && is_admin_token(given))
+    }
+
+    /// `check` without the token, for the landing page, which lists routes but no data
+    pub fn check_local(&self, remote: Option<&SocketAddr>, host: Option<&str>) -> Result<(), AccessDenied> {
+        if !self.enabled {
+            return Err(AccessDenied { status: 404, message: "DevTools are disabled" });
+        }
+        if self.local_only && !remote.is_some_and(|addr| addr.ip().is_loopback()) {
+            return Err(AccessDenied { status: 403, message: "DevTools only accept local connections" });
+        }
+        if !self.is_local_host(host) {
+            return Err(AccessDenied { status: 403, message: "DevTools only answer to localhost" });
+        }
+        Ok(())
+    }
+
+    /// Whether a request's `Host` names this server by a loopback name. A
+    /// page that rebinds its own domain to 127.0.0.1 reaches us over
+    /// loopback, but its requests still carry that domain.
+    fn is_local_host(&self, host: Option<&str>) -> bool {
+        if !self.local_only {
+            return true;
+        }
+        let Some(host) = host else { return false };
+        let (name, port) = match host.rsplit_once(':') {
+            Some((name, port)) if !port.ends_with(']') => (name, port.parse::<u16>().ok()),
+            _ => (host, Some(80)),
+        };
+        matches!(name.to_ascii_lowercase().as_str(), "localhost" | "127.0.0.1" | "[::1]") && port == Some(self.port)
+    }
+
+    /// The inline script `inject_token` adds, for allowing it in the CSP
+    pub fn token_script(&self) -> Option<String> {
+        match (&self.token, self.enabled) {
+            (Some(token), true) => Some(format!("window.__DEVTOOLS_TOKEN__ = \"{}\";", token)),
+            _ => None,
+        }
+    }
+
+    /// Hand the token to the frontend as `window.__DEVTOOLS_TOKEN__`.
+    /// It goes into index.html rather than a script so other origins can't
+    /// read it, and only when the page was asked for by a loopback `Host`.
+    pub fn inject_token(&self, html: &str, host: Option<&str>) -> String {
+        match self.token_script() {
+            Some(script) if self.is_local_host(host) => inject_script(html, &script),
+            _ => html.to_string(),
+        }
+    }
+}
+
+/// `/api/devtools/*` routes that change state, which only answer POST so
+/// that a link or an `<img>` cannot trigger them
+const STATE_CHANGING_ROUTES: &[&str] = &[
+    "/api/devtools/scheduler/run/",
+    "/api/devtools/circuit_breakers/reset/",
+    "/api/devtools/logging/level/",
+    "/api/devtools/events/tap/",
+];
+
+pub fn changes_state(route: &str) -> bool {
+    STATE_CHANGING_ROUTES.iter().any(|prefix| route.starts_with(prefix))
+}
+
+/// Add an inline `<script>` to the end of the page's `<head>`
+pub fn inject_script(html: &str, script: &str) -> String {
+    let script = format!("<script>{}</script>", script);
+    match html.find("</head>") {
+        Some(index) => format!("{}{}{}", &html[..index], script, &html[index..]),
+        None => format!("{}{}", script, html),
+    }
+}
+
+fn is_admin_token(given: &str) -> bool {
+    jwt::JwtAuth::global().is_ok_and(|auth| auth.authorize(given, jwt::ADMIN).is_ok())
+}
+
+/// The access policy of the running DevTools, set once the HTTP server starts
+static ACCESS: OnceLock<AccessPolicy> = OnceLock::new();
+
+/// Whether `given` proves admin rights for commands outside `/api/devtools/*`:
+/// see `AccessPolicy::accepts`; only an API token works before the HTTP server starts
+pub fn accepts_admin(given: &str) -> bool {
+    match ACCESS.get() {
+        Some(access) => access.accepts(given),
+        None => is_admin_token(given),
+    }
+}
+
+/// This run's DevTools token, once the HTTP server started
+#[cfg(test)]
+pub fn token() -> Option<&'static str> {
+    ACCESS.get().and_then(|access| access.token.as_deref())
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
 /// DevTools API handler
 pub struct DevToolsApi {
     start_time: DateTime<Utc>,
+    access: AccessPolicy,
 }
 
 impl DevToolsApi {
-    pub fn new() -> Self {
+    pub fn new(port: u16) -> Self {
+        let access = AccessPolicy::from_config(AppConfig::global(), port);
+        if access.enabled && access.token.is_some() {
+            info!("DevTools require a token; it is injected into index.html as window.__DEVTOOLS_TOKEN__");
+        }
+        // There is one HTTP server per run
+        let _ = ACCESS.set(access.clone());
         Self {
             start_time: Utc::now(),
+            access,
         }
     }
 
+    pub fn access(&self) -> &AccessPolicy {
+        &self.access
+    }
+
     pub fn get_system_metrics(&self) -> SystemMetrics {
         let uptime = Utc::now().signed_duration_since(self.start_time).num_seconds() as u64;
 
@@ -74,34 +271,41 @@ impl DevToolsApi {
             connections: self.get_connection_metrics(),
             database: self.get_database_metrics(),
             events: self.get_event_metrics(),
+            commands: CommandMetrics::global().stats(),
+            host: self.get_host_info(),
+            circuit_breakers: circuit_breaker_stats(),
+            cache: QueryCache::global().stats(),
+            workers: Workers::global().stats(),
         }
     }
 
     fn get_memory_metrics(&self) -> MemoryMetrics {
-        #[cfg(target_os = "linux")]
-        {
-            if let Ok(contents) = std::fs::read_to_string("/proc/meminfo") {
-                for line in contents.lines() {
-                    if line.starts_with("MemAvailable:") {
-                        let parts: Vec<&str> = line.split_whitespace().collect();
-                        if parts.len() >= 2 {
-                            if let Ok(kb) = parts[1].parse::<f64>() {
-                                return MemoryMetrics {
-                                    process_memory_mb: 0.0,
-                                    available_system_mb: kb / 1024.0,
-                                };
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        MemoryMetrics {
-            process_memory_mb: 0.0,
-            available_system_mb: 0.0,
+        let repository = system_info_repository();
+        let process_memory_mb = repository
+            .process_memory_bytes()
+            .map(bytes_to_mb)
+            .unwrap_or(0.0);
+
+        match repository.get_current() {
+            Ok(info) => MemoryMetrics {
+                process_memory_mb,
+                available_system_mb: bytes_to_mb(info.total_memory_bytes.saturating_sub(info.used_memory_bytes)),
+                total_system_mb: bytes_to_mb(info.total_memory_bytes),
+                used_system_mb: bytes_to_mb(info.used_memory_bytes),
+            },
+            Err(_) => MemoryMetrics {
+                process_memory_mb,
+                available_system_mb: 0.0,
+                total_system_mb: 0.0,
+                used_system_mb: 0.0,
+            },
         }
     }
 
+    fn get_host_info(&self) -> Option<SystemInfoDto> {
+        system_info_repository().get_current().ok().map(SystemInfoDto::from)
+    }
+
     fn get_connection_metrics(&self) -> ConnectionMetrics {
         ConnectionMetrics {
             websocket_active: 0,
@@ -110,23 +314,17 @@ impl DevToolsApi {
     }
 
     fn get_database_metrics(&self) -> DatabaseMetrics {
-        use crate::viewmodel::handlers::DATABASE;
-        
         let mut tables = Vec::new();
         let mut total_records = 0i64;
         
-        if let Ok(db_guard) = DATABASE.lock() {
-            if let Some(ref db) = *db_guard {
-                if let Ok(stats) = db.get_db_stats() {
-                    if let Some(users) = stats.get("users").and_then(|v| v.as_i64()) {
-                        tables.push(TableStats {
-                            name: "users".to_string(),
-                            row_count: users,
-                        });
-                        total_records += users;
-                    }
-                }
+        if let Ok(repository) = database_stats_repository() {
+            if let Ok(stats) = futures::executor::block_on(repository.get_stats()) {
+                tables.push(TableStats {
+                    name: "users".to_string(),
+                    row_count: stats.users_count,
+                });
             }
+            total_records = futures::executor::block_on(repository.get_total_records()).unwrap_or_default();
         }
 
         DatabaseMetrics {
@@ -136,31 +334,182 @@ impl DevToolsApi {
     }
 
     fn get_event_metrics(&self) -> EventMetrics {
+        let bus = EventBus::global();
         EventMetrics {
-            total_emitted: 0,
-            recent_events: Vec::new(),
+            total_emitted: bus.total_emitted(),
+            recent_events: bus
+                .recent_events()
+                .into_iter()
+                .rev()
+                .map(|record| RecentEvent {
+                    id: record.event.id,
+                    name: record.event.name,
+                    timestamp: record.timestamp,
+                    source: record.event.source,
+                })
+                .collect(),
+            topics: bus.topic_stats(),
+            listeners: bus.listener_count(),
+            open_taps: bus.taps().list().len(),
         }
     }
 
-    pub fn execute_command(&self, command: &str, _args: serde_json::Value) -> serde_json::Value {
+    /// Liveness report for `/healthz`
+    pub fn liveness(&self) -> HealthReport {
+        health::liveness(self.start_time)
+    }
+
+    /// Readiness report for `/readyz` and the `health` command
+    pub fn readiness(&self) -> HealthReport {
+        health::readiness(self.start_time)
+    }
+
+    pub fn execute_command(&self, command: &str, args: serde_json::Value) -> serde_json::Value {
         match command {
             "ping" => serde_json::json!({ "pong": true, "timestamp": Utc::now() }),
-            "health" => serde_json::json!({ 
-                "status": "healthy", 
-                "uptime_secs": Utc::now().signed_duration_since(self.start_time).num_seconds(),
-                "version": env!("CARGO_PKG_VERSION"),
-            }),
-            "info" => serde_json::json!({
-                "rust_version": std::env!("CARGO_PKG_VERSION"),
-                "debug": cfg!(debug_assertions),
-            }),
+            "health" => serde_json::to_value(self.readiness()).unwrap_or_default(),
+            "info" => {
+                let mut info = serde_json::to_value(crate::BUILD_INFO).unwrap_or_default();
+                info["rust_version"] = serde_json::json!(std::env!("CARGO_PKG_VERSION"));
+                info["debug"] = serde_json::json!(cfg!(debug_assertions));
+                info
+            }
+            "scheduler.list" => serde_json::json!({ "jobs": Scheduler::global().list() }),
+            "scheduler.run_now" => {
+                let job = args.get("job").and_then(|v| v.as_str()).unwrap_or_default();
+                match Scheduler::global().trigger(job) {
+                    Ok(()) => serde_json::json!({ "triggered": job }),
+                    Err(e) => serde_json::json!({ "error": e.to_string() }),
+                }
+            }
+            "crash.list" | "crash.read" => crash_reporter::handle_command(command, &args),
+            "logging.get_filter" | "logging.set_level" => log_filter::handle_command(command, &args),
+            "ringbuffer.dump" => ring_buffer::handle_command(&args),
+            "http.recent" => {
+                let limit = args.get("limit").and_then(|v| v.as_u64()).map(|limit| limit as usize);
+                serde_json::json!({ "requests": AccessLog::global().recent(limit) })
+            }
+            #[cfg(feature = "telemetry")]
+            "telemetry.status" | "telemetry.set_consent" => {
+                crate::infrastructure::telemetry::handle_command(command, &args)
+            }
+            #[cfg(not(feature = "telemetry"))]
+            "telemetry.status" | "telemetry.set_consent" => {
+                serde_json::json!({ "success": false, "error": "Built without the telemetry feature" })
+            }
+            "cache" => serde_json::json!({ "cache": QueryCache::global().stats() }),
+            "db.analyze" => match database() {
+                Ok(db) => match db.analyze() {
+                    Ok(analysis) => serde_json::json!({ "database": analysis }),
+                    Err(e) => serde_json::json!({ "error": e.to_string() }),
+                },
+                Err(e) => serde_json::json!({ "error": e.message }),
+            },
+            "db.pool" => match database() {
+                Ok(db) => serde_json::json!({ "pool": db.pool_stats() }),
+                Err(e) => serde_json::json!({ "error": e.message }),
+            },
+            "workers" => serde_json::json!({ "workers": Workers::global().stats() }),
+            "runtime" => match runtime::metrics() {
+                Some(metrics) => serde_json::json!({ "runtime": metrics }),
+                None => serde_json::json!({ "error": "The runtime was not built from the config" }),
+            },
+            "startup" => match startup::report() {
+                Some(report) => serde_json::json!({ "startup": report }),
+                None => serde_json::json!({ "error": "Startup has not finished" }),
+            },
+            "bench" => serde_json::json!({ "bench": bench::run(&args) }),
+            "circuit_breakers" => serde_json::json!({ "circuit_breakers": circuit_breaker_stats() }),
+            "circuit_breakers.reset" => {
+                let name = args.get("name").and_then(|v| v.as_str()).unwrap_or_default();
+                if reset_circuit_breaker(name) {
+                    serde_json::json!({ "reset": name })
+                } else {
+                    serde_json::json!({ "error": format!("Unknown circuit breaker: {}", name) })
+                }
+            }
+            "events.tap.list" => serde_json::json!({ "taps": EventBus::global().taps().list() }),
+            "events.tap.pause" | "events.tap.resume" | "events.tap.filter" => {
+                let bus = EventBus::global();
+                let taps = bus.taps();
+                let tap = args.get("tap").and_then(|v| v.as_str()).unwrap_or_default();
+                let updated = match command {
+                    "events.tap.filter" => taps.set_filter(tap, serde_json::from_value::<TapFilter>(args.clone()).unwrap_or_default()),
+                    _ => taps.set_paused(tap, command == "events.tap.pause"),
+                };
+                match updated {
+                    Some(info) => serde_json::json!({ "tap": info }),
+                    None => serde_json::json!({ "error": format!("Unknown tap: {}", tap) }),
+                }
+            }
             _ => serde_json::json!({ "error": format!("Unknown command: {}", command) }),
         }
     }
 }
 
-impl Default for DevToolsApi {
-    fn default() -> Self {
-        Self::new()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> AccessPolicy {
+        AccessPolicy {
+            enabled: true,
+            local_only: true,
+            token: Some("secret".to_string()),
+            api_tokens: false,
+            port: 8080,
+        }
+    }
+
+    #[test]
+    fn test_access_requires_local_address_and_token() {
+        let local: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let remote: SocketAddr = "192.168.1.20:50000".parse().unwrap();
+        let policy = policy();
+
+        let host = Some("localhost:8080");
+
+        assert!(policy.check(Some(&local), host, Some("secret")).is_ok());
+        assert_eq!(policy.check(Some(&remote), host, Some("secret")).unwrap_err().status, 403);
+        assert_eq!(policy.check(Some(&local), host, Some("wrong")).unwrap_err().status, 401);
+        assert_eq!(policy.check(Some(&local), host, None).unwrap_err().status, 401);
+
+        let disabled = AccessPolicy { enabled: false, ..policy };
+        assert_eq!(disabled.check(Some(&local), host, Some("secret")).unwrap_err().status, 404);
+    }
+
+    #[test]
+    fn test_access_requires_loopback_host() {
+        let local: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let policy = policy();
+        for host in ["localhost:8080", "127.0.0.1:8080", "[::1]:8080", "LOCALHOST:8080"] {
+            assert!(policy.check_local(Some(&local), Some(host)).is_ok(), "{}", host);
+        }
+        for host in ["rebound.example.com:8080", "localhost:8081", "localhost", "[::1]"] {
+            assert_eq!(policy.check_local(Some(&local), Some(host)).unwrap_err().status, 403, "{}", host);
+        }
+        assert_eq!(policy.check_local(Some(&local), None).unwrap_err().status, 403);
+        assert!(AccessPolicy { port: 80, ..policy }.check_local(Some(&local), Some("[::1]")).is_ok());
+    }
+
+    #[test]
+    fn test_injects_token_into_head() {
+        let host = Some("localhost:8080");
+        let html = policy().inject_token("<html><head><title>App</title></head><body></body></html>", host);
+        assert!(html.contains("<script>window.__DEVTOOLS_TOKEN__ = \"secret\";</script></head>"));
+        assert_eq!(policy().inject_token("<head></head>", Some("rebound.example.com:8080")), "<head></head>");
+
+        let without_token = AccessPolicy { token: None, ..policy() };
+        assert_eq!(without_token.inject_token("<head></head>", host), "<head></head>");
+    }
+
+    #[test]
+    fn test_state_changing_routes() {
+        assert!(changes_state("/api/devtools/scheduler/run/database.vacuum"));
+        assert!(changes_state("/api/devtools/circuit_breakers/reset/webhooks"));
+        assert!(changes_state("/api/devtools/events/tap/1/pause"));
+        assert!(!changes_state("/api/devtools/scheduler"));
+        assert!(!changes_state("/api/devtools/circuit_breakers"));
+        assert!(!changes_state("/api/devtools/logging"));
     }
 }
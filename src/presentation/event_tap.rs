@@ -0,0 +1,74 @@
+//! `GET /api/devtools/events/tap` - the event bus as server-sent events
+//!
+//! The stream opens with an `event: tap` message naming the tap, which the
+//! `POST /api/devtools/events/tap/{id}/…` routes pause, resume and refilter. Every
+//! event after that is one `data:` line of `TappedEvent` JSON with its `seq`
+//! as the message id. Each stream runs on a thread of its own until the
+//! client disconnects.
+
+use serde::Serialize;
+use std::io::{self, Write};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, info};
+use crate::infrastructure::event_bus::tap::{TapFilter, TappedEvent};
+use crate::infrastructure::event_bus::EventBus;
+
+pub const TAP_PATH: &str = "/api/devtools/events/tap";
+
+/// A quiet stream sends a comment this often, to find out the client has left
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// Open a tap filtered by `query` and stream it in answer to `request`,
+/// sending `headers` (CORS, security) with the response
+pub fn serve(request: tiny_http::Request, query: &str, headers: &[tiny_http::Header]) {
+    let bus = EventBus::global();
+    let (id, events) = bus.taps().open(TapFilter::from_query(query));
+    info!("DevTools event tap {} opened", id);
+    let headers = headers.to_vec();
+    thread::spawn(move || {
+        if let Err(e) = stream(request.into_writer(), &id, &headers, events) {
+            debug!("DevTools event tap {} ended: {}", id, e);
+        }
+        bus.taps().close(&id);
+        info!("DevTools event tap {} closed", id);
+    });
+}
+
+fn stream(
+    mut writer: impl Write,
+    id: &str,
+    headers: &[tiny_http::Header],
+    events: Receiver<TappedEvent>,
+) -> io::Result<()> {
+    writer.write_all(b"HTTP/1.1 200 OK\r\n")?;
+    for header in headers {
+        write!(writer, "{}\r\n", header)?;
+    }
+    writer.write_all(b"Content-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n")?;
+    writer.write_all(frame(Some("tap"), None, &serde_json::json!({ "tap": id })).as_bytes())?;
+    writer.flush()?;
+    loop {
+        match events.recv_timeout(KEEPALIVE) {
+            Ok(event) => writer.write_all(frame(None, Some(event.seq), &event).as_bytes())?,
+            Err(RecvTimeoutError::Timeout) => writer.write_all(b": keepalive\n\n")?,
+            // Closed from elsewhere
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        writer.flush()?;
+    }
+}
+
+/// One server-sent event; JSON never spans lines, so `data` is a single line
+fn frame(event: Option<&str>, id: Option<u64>, data: &impl Serialize) -> String {
+    let mut frame = String::new();
+    if let Some(event) = event {
+        frame.push_str(&format!("event: {}\n", event));
+    }
+    if let Some(id) = id {
+        frame.push_str(&format!("id: {}\n", id));
+    }
+    frame.push_str(&format!("data: {}\n\n", serde_json::to_string(data).unwrap_or_default()));
+    frame
+}
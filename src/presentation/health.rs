@@ -0,0 +1,147 @@
+//! Health checks - Liveness (`/healthz`) and readiness (`/readyz`)
+//!
+//! Liveness only checks in-process state (the event bus). Readiness also
+//! probes the database with a query and connects to the WebSocket listener.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, Ipv6Addr, TcpStream};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+use crate::infrastructure::database::database;
+use crate::infrastructure::event_bus::EventBus;
+use crate::viewmodel::websocket_handler;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Up,
+    Down,
+}
+
+/// Result of probing one dependency
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DependencyCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub latency_ms: f64,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthReport {
+    /// `healthy` when every check is up, `unhealthy` otherwise
+    pub status: String,
+    pub uptime_secs: i64,
+    pub version: String,
+    pub timestamp: DateTime<Utc>,
+    pub checks: Vec<DependencyCheck>,
+}
+
+impl HealthReport {
+    fn new(start_time: DateTime<Utc>, checks: Vec<DependencyCheck>) -> Self {
+        let healthy = checks.iter().all(|check| check.status == CheckStatus::Up);
+        Self {
+            status: if healthy { "healthy" } else { "unhealthy" }.to_string(),
+            uptime_secs: Utc::now().signed_duration_since(start_time).num_seconds(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: Utc::now(),
+            checks,
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(|check| check.status == CheckStatus::Up)
+    }
+}
+
+/// Run `probe` and time it; `Ok` carries a short description of what was seen
+fn timed(name: &str, probe: impl FnOnce() -> Result<String, String>) -> DependencyCheck {
+    let started = Instant::now();
+    let result = probe();
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    let (status, detail) = match result {
+        Ok(detail) => (CheckStatus::Up, detail),
+        Err(detail) => (CheckStatus::Down, detail),
+    };
+    DependencyCheck {
+        name: name.to_string(),
+        status,
+        latency_ms,
+        detail,
+    }
+}
+
+fn check_database() -> DependencyCheck {
+    timed("database", || {
+        let db = database().map_err(|e| e.message)?;
+        db.ping().map_err(|e| e.message)?;
+        Ok("SELECT 1 succeeded".to_string())
+    })
+}
+
+fn check_websocket() -> DependencyCheck {
+    timed("websocket", || {
+        let mut addr = websocket_handler::listen_addr().ok_or_else(|| "WebSocket server is not listening".to_string())?;
+        // Bound to all interfaces: probe through loopback
+        if addr.ip().is_unspecified() {
+            addr.set_ip(if addr.is_ipv4() { Ipv4Addr::LOCALHOST.into() } else { Ipv6Addr::LOCALHOST.into() });
+        }
+        TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+            .map_err(|e| format!("Cannot connect to {}: {}", addr, e))?;
+        Ok(format!("Accepting connections on {}", addr))
+    })
+}
+
+fn check_event_bus() -> DependencyCheck {
+    timed("event_bus", || {
+        let bus = EventBus::global();
+        bus.check()?;
+        Ok(format!(
+            "{} listeners, {} events emitted",
+            bus.listener_count(),
+            bus.total_emitted()
+        ))
+    })
+}
+
+/// Is the process able to do work at all
+pub fn liveness(start_time: DateTime<Utc>) -> HealthReport {
+    HealthReport::new(start_time, vec![check_event_bus()])
+}
+
+/// Are all dependencies reachable
+pub fn readiness(start_time: DateTime<Utc>) -> HealthReport {
+    HealthReport::new(
+        start_time,
+        vec![check_database(), check_websocket(), check_event_bus()],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_check_makes_report_unhealthy() {
+        let up = timed("event_bus", || Ok("ok".to_string()));
+        let down = timed("database", || Err("Database not initialized".to_string()));
+        assert_eq!(up.status, CheckStatus::Up);
+        assert_eq!(down.detail, "Database not initialized");
+
+        let report = HealthReport::new(Utc::now(), vec![up.clone()]);
+        assert!(report.is_healthy());
+        assert_eq!(report.status, "healthy");
+
+        let report = HealthReport::new(Utc::now(), vec![up, down]);
+        assert!(!report.is_healthy());
+        assert_eq!(report.status, "unhealthy");
+    }
+
+    #[test]
+    fn test_event_bus_check_passes() {
+        assert_eq!(check_event_bus().status, CheckStatus::Up);
+    }
+}
@@ -0,0 +1,162 @@
+//! `POST /api/hooks/{name}` - let external systems trigger the running app
+//!
+//! A CI job, a cron script or another service posts to a hook with the shared
+//! secret of `[hooks]` and the body becomes a `hook.<name>` event on the
+//! EventBus, with source `hook`. Frontends, plugins and backend listeners
+//! react to it like to any other event. A JSON body is the event payload as
+//! is; any other body is passed as a string, an empty one as `null`. While
+//! the `hooks` feature flag is off, accepted hooks are answered with 503.
+
+use serde_json::Value;
+use std::io::Read;
+use tracing::{error, info, warn};
+use crate::infrastructure::event_bus::{Event, EventBus};
+use crate::infrastructure::flags::{self, FeatureFlags};
+use crate::infrastructure::secrets::SecretsManager;
+use crate::model::core::AppConfig;
+use crate::presentation::devtools::constant_time_eq;
+
+pub const HOOKS_PATH: &str = "/api/hooks/";
+
+/// Longest hook name
+const MAX_NAME_LEN: usize = 64;
+
+fn failure(status: u16, error: impl ToString) -> (u16, Value) {
+    (status, serde_json::json!({ "success": false, "error": error.to_string() }))
+}
+
+pub struct HookEndpoint {
+    /// `None` when hooks are disabled or have no usable secret
+    secret: Option<String>,
+    names: Vec<String>,
+    max_body: usize,
+}
+
+impl HookEndpoint {
+    pub fn new(secret: Option<String>, names: Vec<String>, max_body: usize) -> Self {
+        Self { secret, names, max_body }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        let secret = if !config.is_hooks_enabled() {
+            None
+        } else {
+            match config.get_hooks_secret().map(|value| SecretsManager::global().resolve(value)) {
+                Some(Ok(secret)) if !secret.is_empty() => {
+                    info!("Incoming hooks are accepted on {}<name>", HOOKS_PATH);
+                    Some(secret)
+                }
+                Some(Ok(_)) | None => {
+                    error!("[hooks] is enabled without a secret; incoming hooks stay off");
+                    None
+                }
+                Some(Err(e)) => {
+                    error!("Failed to read the [hooks] secret, incoming hooks stay off: {}", e);
+                    None
+                }
+            }
+        };
+        Self::new(secret, config.get_hooks_names().to_vec(), config.get_hooks_max_body_kb() * 1024)
+    }
+
+    /// Check a hook request and turn its body into the event to emit
+    pub fn accept(&self, method: &str, name: &str, given_secret: Option<&str>, body: impl Read) -> Result<Event, (u16, Value)> {
+        let Some(secret) = &self.secret else {
+            return Err(failure(404, "Not Found"));
+        };
+        if method != "POST" {
+            return Err(failure(405, "Use POST"));
+        }
+        if !given_secret.is_some_and(|given| constant_time_eq(given.as_bytes(), secret.as_bytes())) {
+            return Err(failure(401, "Missing or wrong hook secret"));
+        }
+        if name.is_empty()
+            || name.len() > MAX_NAME_LEN
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(failure(400, "Hook names use letters, digits, '-', '_' and '.'"));
+        }
+        if !self.names.is_empty() && !self.names.iter().any(|allowed| allowed == name) {
+            return Err(failure(404, format!("Unknown hook: {}", name)));
+        }
+
+        // One byte past the limit tells a body that is too large
+        let mut bytes = Vec::new();
+        if let Err(e) = body.take(self.max_body as u64 + 1).read_to_end(&mut bytes) {
+            return Err(failure(400, format!("Failed to read the body: {}", e)));
+        }
+        if bytes.len() > self.max_body {
+            return Err(failure(413, format!("Hook bodies are limited to {} bytes", self.max_body)));
+        }
+        let payload = if bytes.iter().all(u8::is_ascii_whitespace) {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()))
+        };
+        Ok(Event::new(format!("hook.{}", name), payload, "hook".to_string()))
+    }
+
+    /// Answer a hook request with a status and a JSON body
+    pub fn handle(&self, method: &str, name: &str, given_secret: Option<&str>, body: impl Read) -> (u16, Value) {
+        let event = match self.accept(method, name, given_secret, body) {
+            Ok(event) => event,
+            Err(refused) => {
+                if refused.0 != 404 {
+                    warn!("Hook {} refused: {}", name, refused.1["error"].as_str().unwrap_or_default());
+                }
+                return refused;
+            }
+        };
+        if !FeatureFlags::global().is_enabled(flags::HOOKS) {
+            return failure(503, "Hooks are turned off by the hooks feature flag");
+        }
+        let event_id = event.id.clone();
+        let event_name = event.name.clone();
+        match futures::executor::block_on(EventBus::global().emit(event)) {
+            Ok(()) => {
+                info!("Hook {} emitted {}", name, event_name);
+                (202, serde_json::json!({ "success": true, "event": event_name, "event_id": event_id }))
+            }
+            Err(e) => failure(500, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(names: &[&str]) -> HookEndpoint {
+        HookEndpoint::new(Some("s3cret".to_string()), names.iter().map(|name| name.to_string()).collect(), 16)
+    }
+
+    fn status(result: Result<Event, (u16, Value)>) -> u16 {
+        result.err().map_or(202, |(status, _)| status)
+    }
+
+    #[test]
+    fn test_checks_secret_name_and_size() {
+        let hooks = endpoint(&[]);
+        let disabled = HookEndpoint::new(None, Vec::new(), 16);
+        assert_eq!(status(disabled.accept("POST", "deploy", Some("s3cret"), &b""[..])), 404);
+        assert_eq!(status(hooks.accept("GET", "deploy", Some("s3cret"), &b""[..])), 405);
+        assert_eq!(status(hooks.accept("POST", "deploy", None, &b""[..])), 401);
+        assert_eq!(status(hooks.accept("POST", "deploy", Some("s3creT"), &b""[..])), 401);
+        assert_eq!(status(hooks.accept("POST", "a/b", Some("s3cret"), &b""[..])), 400);
+        assert_eq!(status(hooks.accept("POST", "deploy", Some("s3cret"), &[b'x'; 17][..])), 413);
+        assert_eq!(status(endpoint(&["backup"]).accept("POST", "deploy", Some("s3cret"), &b""[..])), 404);
+    }
+
+    #[test]
+    fn test_body_becomes_event_payload() {
+        let hooks = endpoint(&["deploy"]);
+        let event = hooks.accept("POST", "deploy", Some("s3cret"), &br#"{"ref":"main"}"#[..]).unwrap();
+        assert_eq!((event.name.as_str(), event.source.as_str()), ("hook.deploy", "hook"));
+        assert_eq!(*event.payload, serde_json::json!({ "ref": "main" }));
+        let event = hooks.accept("POST", "deploy", Some("s3cret"), &b"done"[..]).unwrap();
+        assert_eq!(*event.payload, "done");
+        let event = hooks.accept("POST", "deploy", Some("s3cret"), &b" \n"[..]).unwrap();
+        assert!(event.payload.is_null());
+    }
+}
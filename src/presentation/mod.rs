@@ -1,3 +1,17 @@
 //! Presentation Layer Module
 
+pub mod access_log;
+pub mod assets;
+pub mod asyncapi;
+pub mod bridge;
+pub mod cors;
+pub mod dev_proxy;
 pub mod devtools;
+pub mod event_tap;
+pub mod health;
+pub mod hooks;
+pub mod openapi;
+pub mod pages;
+pub mod security_headers;
+pub mod static_files;
+pub mod upload;
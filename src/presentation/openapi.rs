@@ -0,0 +1,409 @@
+//! OpenAPI 3 document for the HTTP API - served at `/api/openapi.json`
+//!
+//! The routes themselves are matched by hand in the HTTP server loop, so the
+//! operations are described on the empty functions below. The application
+//! DTOs are published as schemas even where no HTTP route returns them yet.
+//! With the `swagger-ui` feature a bundled Swagger UI is served at `/api/docs/`.
+
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use crate::core::application::{CounterDto, DatabaseStatsDto, SystemInfoDto, UserDto};
+use crate::error_handling::{CircuitBreakerStats, CircuitState};
+use crate::infrastructure::bench::{BenchReport, EventThroughput};
+use crate::infrastructure::cache::CacheStats;
+use crate::infrastructure::command_metrics::CommandStats;
+use crate::infrastructure::event_bus::TopicStats;
+use crate::infrastructure::file_transfer::CompletedUpload;
+use crate::infrastructure::database::maintenance::{DatabaseAnalysis, DatabaseTuning, ObjectPages};
+use crate::infrastructure::database::read_pool::{ContentionStats, PoolStats};
+use crate::infrastructure::runtime::{RuntimeMetrics, RuntimeTuning, WorkerMetrics};
+use crate::infrastructure::workers::WorkerPoolStats;
+use crate::infrastructure::serialization::serialization::FormatComparison;
+use crate::infrastructure::server::FrontendConfig;
+use crate::infrastructure::startup::{StartupPhase, StartupReport};
+use crate::presentation::access_log::AccessRecord;
+use crate::presentation::assets::{AssetManifest, EntryAssets};
+use crate::presentation::devtools::{
+    ConnectionMetrics, DatabaseMetrics, EventMetrics, MemoryMetrics, RecentEvent, SystemMetrics, TableStats,
+};
+use crate::presentation::health::{CheckStatus, DependencyCheck, HealthReport};
+use crate::presentation::upload::UploadResponse;
+use crate::BuildInfo;
+
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+pub const VERSION_PATH: &str = "/api/version";
+#[cfg(feature = "swagger-ui")]
+pub const SWAGGER_UI_PATH: &str = "/api/docs/";
+
+#[utoipa::path(get, path = "/config.json", tag = "app",
+    responses((status = 200, description = "Ports and app details for the frontend", body = FrontendConfig)))]
+#[allow(dead_code)]
+fn config_json() {}
+
+#[utoipa::path(get, path = "/api/version", tag = "app",
+    responses((status = 200, description = "Version, git commit, build time, cargo features, target and profile of the binary", body = BuildInfo)))]
+#[allow(dead_code)]
+fn version() {}
+
+#[utoipa::path(get, path = "/healthz", tag = "health",
+    responses(
+        (status = 200, description = "In-process state is healthy", body = HealthReport),
+        (status = 503, description = "A check is down", body = HealthReport),
+    ))]
+#[allow(dead_code)]
+fn healthz() {}
+
+#[utoipa::path(get, path = "/metrics", tag = "health", security(("devtools_token" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "Prometheus text format: `command_duration_seconds` histograms and `command_errors_total` counters by command", content_type = "text/plain", body = String),
+        (status = 401, description = "Missing or wrong devtools token"),
+        (status = 403, description = "Request from a non-loopback address"),
+        (status = 404, description = "DevTools are disabled"),
+    ))]
+#[allow(dead_code)]
+fn metrics() {}
+
+#[utoipa::path(get, path = "/readyz", tag = "health",
+    responses(
+        (status = 200, description = "Database and WebSocket listener are reachable", body = HealthReport),
+        (status = 503, description = "A dependency is down", body = HealthReport),
+    ))]
+#[allow(dead_code)]
+fn readyz() {}
+
+#[utoipa::path(post, path = "/api/upload", tag = "files",
+    params(
+        ("directory" = Option<String>, Query, description = "Destination within `[file_transfer] allowed_dirs`, `upload_dir` by default"),
+        ("overwrite" = Option<bool>, Query, description = "Replace existing files"),
+    ),
+    request_body(content = Object, content_type = "multipart/form-data", description = "One or more file fields"),
+    responses(
+        (status = 200, description = "The stored files", body = UploadResponse),
+        (status = 400, description = "Malformed body or file name, or no file fields"),
+        (status = 403, description = "Destination outside the allowed directories, or a request from another origin"),
+        (status = 409, description = "A file exists and `overwrite` is not set"),
+        (status = 413, description = "A file exceeds `max_file_size_mb`"),
+        (status = 415, description = "Not a multipart body, or a file extension outside `allowed_extensions`"),
+        (status = 422, description = "Rejected by an upload scanner"),
+    ))]
+#[allow(dead_code)]
+fn upload() {}
+
+#[utoipa::path(post, path = "/api/hooks/{name}", tag = "hooks", security(("hook_secret" = []), ("bearer" = [])),
+    params(("name" = String, Path, description = "Hook name; the event is `hook.<name>`")),
+    request_body(content = Object, description = "Event payload; bodies that are not JSON are passed as a string"),
+    responses(
+        (status = 202, description = "`event` and `event_id` of the emitted event", body = Object),
+        (status = 400, description = "Malformed hook name"),
+        (status = 401, description = "Missing or wrong hook secret"),
+        (status = 404, description = "Hooks are disabled, or the name is not in `[hooks] names`"),
+        (status = 413, description = "The body exceeds `max_body_kb`"),
+        (status = 503, description = "The `hooks` feature flag is off"),
+    ))]
+#[allow(dead_code)]
+fn hook() {}
+
+#[utoipa::path(get, path = "/api/assets", tag = "app",
+    responses(
+        (status = 200, description = "Files of the frontend build and the bundles each entry point loads, from the rsbuild asset manifest", body = AssetManifest),
+        (status = 404, description = "The frontend was built without a manifest"),
+    ))]
+#[allow(dead_code)]
+fn assets() {}
+
+#[utoipa::path(get, path = "/api/asyncapi.json", tag = "schemas",
+    responses((status = 200, description = "AsyncAPI 3 document of the WebSocket commands, responses and events", body = Object)))]
+#[allow(dead_code)]
+fn asyncapi() {}
+
+#[utoipa::path(get, path = "/api/schemas", tag = "schemas", security(("devtools_token" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "`ws_message`, `events` by event name and `dtos` by type name", body = Object,
+            content_type = "application/schema+json"),
+        (status = 401, description = "Missing or wrong devtools token"),
+        (status = 403, description = "Request from a non-loopback address"),
+        (status = 404, description = "DevTools are disabled"),
+    ))]
+#[allow(dead_code)]
+fn schemas() {}
+
+#[utoipa::path(get, path = "/api/schemas/{name}", tag = "schemas", security(("devtools_token" = []), ("bearer" = [])),
+    params(("name" = String, Path, description = "`ws_message`, an event name such as `counter.changed`, or a DTO name")),
+    responses(
+        (status = 200, description = "One self-contained JSON Schema", body = Object, content_type = "application/schema+json"),
+        (status = 401, description = "Missing or wrong devtools token"),
+        (status = 403, description = "Request from a non-loopback address"),
+        (status = 404, description = "No schema with that name, or DevTools are disabled"),
+    ))]
+#[allow(dead_code)]
+fn schema() {}
+
+#[utoipa::path(get, path = "/api/devtools/metrics", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses(
+        (status = 200, description = "System metrics snapshot", body = SystemMetrics),
+        (status = 401, description = "Missing or wrong devtools token"),
+        (status = 403, description = "Request from a non-loopback address"),
+    ))]
+#[allow(dead_code)]
+fn devtools_metrics() {}
+
+#[utoipa::path(get, path = "/api/devtools/health", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "Readiness report, always with status 200", body = HealthReport)))]
+#[allow(dead_code)]
+fn devtools_health() {}
+
+#[utoipa::path(get, path = "/api/devtools/info", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "The `BuildInfo` fields with `rust_version` (the app version) and `debug`", body = Object)))]
+#[allow(dead_code)]
+fn devtools_info() {}
+
+#[utoipa::path(get, path = "/api/devtools/scheduler", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "`jobs`: the scheduled jobs and their last runs", body = Object)))]
+#[allow(dead_code)]
+fn devtools_scheduler() {}
+
+#[utoipa::path(post, path = "/api/devtools/scheduler/run/{job}", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    params(("job" = String, Path, description = "Job name")),
+    responses((status = 200, description = "`triggered` with the job name, or `error`", body = Object)))]
+#[allow(dead_code)]
+fn devtools_scheduler_run() {}
+
+#[utoipa::path(get, path = "/api/devtools/crashes", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "Crash reports on disk, newest first", body = Object)))]
+#[allow(dead_code)]
+fn devtools_crashes() {}
+
+#[utoipa::path(get, path = "/api/devtools/crashes/{file}", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    params(("file" = String, Path, description = "Crash report file name")),
+    responses((status = 200, description = "One crash report", body = Object)))]
+#[allow(dead_code)]
+fn devtools_crash() {}
+
+#[utoipa::path(get, path = "/api/devtools/circuit_breakers", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "`circuit_breakers`: one entry per breaker", body = Object)))]
+#[allow(dead_code)]
+fn devtools_circuit_breakers() {}
+
+#[utoipa::path(post, path = "/api/devtools/circuit_breakers/reset/{name}", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    params(("name" = String, Path, description = "Circuit breaker name")),
+    responses((status = 200, description = "`reset` with the breaker name, or `error`", body = Object)))]
+#[allow(dead_code)]
+fn devtools_circuit_breaker_reset() {}
+
+#[utoipa::path(get, path = "/api/devtools/cache", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "`cache`: query cache counters", body = Object)))]
+#[allow(dead_code)]
+fn devtools_cache() {}
+
+#[utoipa::path(get, path = "/api/devtools/workers", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "`workers`: one entry per worker pool used so far", body = Object)))]
+#[allow(dead_code)]
+fn devtools_workers() {}
+
+#[utoipa::path(get, path = "/api/devtools/db/analyze", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "`database`: page counts, fragmentation, the PRAGMAs in effect and the pages of each table and index", body = Object)))]
+#[allow(dead_code)]
+fn devtools_db_analyze() {}
+
+#[utoipa::path(get, path = "/api/devtools/db/pool", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "`pool`: the read-only connections, and how often and how long reads and writes waited for a connection", body = Object)))]
+#[allow(dead_code)]
+fn devtools_db_pool() {}
+
+#[utoipa::path(get, path = "/api/devtools/runtime", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "`runtime`: the thread counts from `[runtime]`, live tasks and queue depths", body = Object)))]
+#[allow(dead_code)]
+fn devtools_runtime() {}
+
+#[utoipa::path(get, path = "/api/devtools/startup", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "`startup`: how long each startup phase took", body = Object)))]
+#[allow(dead_code)]
+fn devtools_startup() {}
+
+#[utoipa::path(get, path = "/api/devtools/bench", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "`bench`: event emit throughput and serialization format timings", body = Object)))]
+#[allow(dead_code)]
+fn devtools_bench() {}
+
+#[utoipa::path(get, path = "/api/devtools/logging", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "The active log filter", body = Object)))]
+#[allow(dead_code)]
+fn devtools_logging() {}
+
+#[utoipa::path(post, path = "/api/devtools/logging/level/{level}", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    params(("level" = String, Path, description = "Default level: trace, debug, info, warn or error")),
+    responses((status = 200, description = "The new log filter", body = Object)))]
+#[allow(dead_code)]
+fn devtools_logging_level() {}
+
+#[utoipa::path(post, path = "/api/devtools/logging/level/{target}/{level}", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    params(
+        ("target" = String, Path, description = "Module path, e.g. `rustwebui_app::viewmodel`"),
+        ("level" = String, Path, description = "trace, debug, info, warn or error"),
+    ),
+    responses((status = 200, description = "The new log filter", body = Object)))]
+#[allow(dead_code)]
+fn devtools_logging_target_level() {}
+
+#[utoipa::path(get, path = "/api/devtools/ringbuffer", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    params(("limit" = Option<u64>, Query, description = "Only the newest `limit` records")),
+    responses((status = 200, description = "`records`: the log ring buffer, oldest first", body = Object)))]
+#[allow(dead_code)]
+fn devtools_ringbuffer() {}
+
+#[utoipa::path(get, path = "/api/devtools/http/recent", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    params(("limit" = Option<u64>, Query, description = "Only the newest `limit` requests")),
+    responses((status = 200, description = "`requests`: the last requests answered over HTTP, newest first", body = Object)))]
+#[allow(dead_code)]
+fn devtools_http_recent() {}
+
+#[utoipa::path(get, path = "/api/devtools/telemetry", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "Error reporting consent and queue state", body = Object)))]
+#[allow(dead_code)]
+fn devtools_telemetry() {}
+
+#[utoipa::path(get, path = "/api/devtools/events/tap", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    params(
+        ("names" = Option<String>, Query, description = "Comma-separated event name patterns, e.g. `user.*,database.operation`"),
+        ("sources" = Option<String>, Query, description = "Comma-separated event sources, e.g. `backend`"),
+    ),
+    responses((status = 200, description = "Server-sent events: `event: tap` with the tap id, then one message per published event with its payload size and handler latencies", content_type = "text/event-stream", body = String)))]
+#[allow(dead_code)]
+fn devtools_events_tap() {}
+
+#[utoipa::path(get, path = "/api/devtools/events/taps", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    responses((status = 200, description = "`taps`: open event taps with their filter, state and counts", body = Object)))]
+#[allow(dead_code)]
+fn devtools_events_taps() {}
+
+#[utoipa::path(post, path = "/api/devtools/events/tap/{id}/{action}", tag = "devtools", security(("devtools_token" = []), ("bearer" = [])),
+    params(
+        ("id" = String, Path, description = "Tap id from the stream's `event: tap` message"),
+        ("action" = String, Path, description = "`pause`, `resume`, or `filter` with the `names` and `sources` query parameters"),
+    ),
+    responses((status = 200, description = "`tap`: the tap after the change", body = Object)))]
+#[allow(dead_code)]
+fn devtools_events_tap_action() {}
+
+/// The two ways of sending the devtools token, plus the header of the hook secret
+struct DevToolsToken;
+
+impl Modify for DevToolsToken {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "devtools_token",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-DevTools-Token"))),
+        );
+        components.add_security_scheme(
+            "hook_secret",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Hook-Secret"))),
+        );
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Rust WebUI HTTP API", description = "HTTP endpoints of the app. Commands and events go over the WebSocket API."),
+    paths(
+        config_json, version, assets, healthz, readyz, metrics, upload, hook, asyncapi, schemas, schema,
+        devtools_metrics, devtools_health, devtools_info, devtools_scheduler, devtools_scheduler_run,
+        devtools_crashes, devtools_crash, devtools_circuit_breakers, devtools_circuit_breaker_reset, devtools_cache, devtools_workers, devtools_runtime, devtools_db_analyze, devtools_db_pool, devtools_startup, devtools_bench,
+        devtools_logging, devtools_logging_level, devtools_logging_target_level, devtools_ringbuffer, devtools_http_recent, devtools_telemetry,
+        devtools_events_tap, devtools_events_taps, devtools_events_tap_action,
+    ),
+    components(schemas(
+        UserDto, CounterDto, DatabaseStatsDto, SystemInfoDto,
+        FrontendConfig, BuildInfo, AssetManifest, EntryAssets, HealthReport, DependencyCheck, CheckStatus,
+        SystemMetrics, MemoryMetrics, ConnectionMetrics, DatabaseMetrics, TableStats, EventMetrics, RecentEvent, TopicStats, CommandStats,
+        CircuitBreakerStats, CircuitState, CacheStats, WorkerPoolStats, RuntimeMetrics, RuntimeTuning, WorkerMetrics, DatabaseAnalysis, DatabaseTuning, ObjectPages, PoolStats, ContentionStats, StartupReport, StartupPhase, BenchReport, EventThroughput, FormatComparison, UploadResponse, CompletedUpload, AccessRecord,
+    )),
+    modifiers(&DevToolsToken),
+)]
+struct ApiDoc;
+
+/// A GET route of the document, for listing on a page
+pub struct Route {
+    pub path: String,
+    /// What its `200` answer is
+    pub description: String,
+}
+
+/// The GET routes tagged `tag`, by path
+pub fn routes_tagged(tag: &str) -> Vec<Route> {
+    ApiDoc::openapi()
+        .paths
+        .paths
+        .into_iter()
+        .filter_map(|(path, item)| {
+            let operation = item.get?;
+            if !operation.tags.as_ref().is_some_and(|tags| tags.iter().any(|t| t == tag)) {
+                return None;
+            }
+            let description = match operation.responses.responses.get("200") {
+                Some(utoipa::openapi::RefOr::T(response)) => response.description.clone(),
+                _ => String::new(),
+            };
+            Some(Route { path, description })
+        })
+        .collect()
+}
+
+/// The document served at `/api/openapi.json`
+pub fn openapi_json() -> String {
+    let mut doc = ApiDoc::openapi();
+    doc.info.version = env!("CARGO_PKG_VERSION").to_string();
+    doc.to_pretty_json().unwrap_or_default()
+}
+
+/// A file of the bundled Swagger UI by its path below `/api/docs/`, with its content type
+#[cfg(feature = "swagger-ui")]
+pub fn swagger_ui_file(path: &str) -> Option<(Vec<u8>, String)> {
+    let config = std::sync::Arc::new(utoipa_swagger_ui::Config::from(OPENAPI_PATH));
+    match utoipa_swagger_ui::serve(path, config) {
+        Ok(file) => file.map(|file| (file.bytes.into_owned(), file.content_type)),
+        Err(e) => {
+            tracing::error!(error = %e, path, "Error serving Swagger UI");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_covers_routes_and_dtos() {
+        let doc: serde_json::Value = serde_json::from_str(&openapi_json()).unwrap();
+
+        assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+        assert_eq!(doc["info"]["version"], env!("CARGO_PKG_VERSION"));
+        for path in ["/healthz", "/readyz", "/config.json", "/api/version", "/api/devtools/metrics"] {
+            assert!(doc["paths"][path]["get"].is_object(), "missing {}", path);
+        }
+        assert!(doc["paths"]["/api/devtools/logging/level/{target}/{level}"]["post"].is_object());
+        assert!(doc["paths"]["/api/devtools/scheduler/run/{job}"]["get"].is_null());
+        for schema in ["UserDto", "CounterDto", "DatabaseStatsDto", "SystemInfoDto", "HealthReport", "SystemMetrics"] {
+            assert!(doc["components"]["schemas"][schema].is_object(), "missing {}", schema);
+        }
+        assert_eq!(doc["components"]["securitySchemes"]["devtools_token"]["name"], "X-DevTools-Token");
+        assert!(doc["paths"]["/healthz"]["get"]["security"].is_null());
+        assert!(doc["paths"]["/api/upload"]["post"]["requestBody"]["content"]["multipart/form-data"].is_object());
+        assert_eq!(doc["components"]["securitySchemes"]["hook_secret"]["name"], "X-Hook-Secret");
+    }
+
+    #[cfg(feature = "swagger-ui")]
+    #[test]
+    fn test_swagger_ui_points_at_document() {
+        let (_, content_type) = swagger_ui_file("").unwrap();
+        assert_eq!(content_type, "text/html");
+        let (initializer, _) = swagger_ui_file("swagger-initializer.js").unwrap();
+        assert!(String::from_utf8(initializer).unwrap().contains(OPENAPI_PATH));
+        assert!(swagger_ui_file("missing.js").is_none());
+    }
+}
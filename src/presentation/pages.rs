@@ -0,0 +1,187 @@
+//! HTML pages generated by the backend itself, from the askama templates in `templates/`
+//!
+//! Everything a browser may land on that isn't the frontend: error pages in
+//! place of bare "Not Found" text, the splash shown while `frontend/dist` has
+//! not been built, and the devtools landing page at `/api/devtools/`. All
+//! extend `layout.html` and are escaped by askama; none runs scripts, so the
+//! CSP needs no exception for them.
+//!
+//! Errors are negotiated: a request whose `Accept` header prefers JSON (as
+//! `fetch` calls with `Accept: application/json` do) gets an `ApiResponse`
+//! instead of the page. Neither ever carries an IO error, which may name
+//! paths on disk; those go to the log only.
+
+use askama::Template;
+use std::io::Cursor;
+use tiny_http::{Header, Response, StatusCode};
+use crate::core::application::ApiResponse;
+use crate::model::core::AppConfig;
+use crate::presentation::openapi;
+
+pub const DEVTOOLS_PATH: &str = "/api/devtools/";
+
+/// How often the splash page reloads
+const SPLASH_RETRY_SECS: u64 = 2;
+
+#[derive(Template)]
+#[template(path = "error.html")]
+struct ErrorPage<'a> {
+    app_name: &'a str,
+    status: u16,
+    reason: &'a str,
+    message: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "splash.html")]
+struct SplashPage<'a> {
+    app_name: &'a str,
+    version: &'a str,
+    retry_secs: u64,
+}
+
+#[derive(Template)]
+#[template(path = "devtools.html")]
+struct DevToolsPage<'a> {
+    app_name: &'a str,
+    token_required: bool,
+    routes: Vec<openapi::Route>,
+}
+
+fn html(page: &impl Template, status: u16) -> Response<Cursor<Vec<u8>>> {
+    let body = page.render().unwrap_or_else(|e| {
+        tracing::error!(error = %e, "Failed to render page");
+        StatusCode(status).default_reason_phrase().to_string()
+    });
+    Response::from_data(body)
+        .with_status_code(status)
+        .with_header(Header::from_bytes(&b"Content-Type"[..], &b"text/html; charset=utf-8"[..]).unwrap())
+}
+
+/// How much `accept` (an `Accept` header) wants one of `types`: the best
+/// quality given, and how early the winning range comes as the tie-breaker
+fn preference(accept: &str, types: impl Fn(&str) -> bool) -> (f32, std::cmp::Reverse<usize>) {
+    accept
+        .split(',')
+        .enumerate()
+        .filter_map(|(position, range)| {
+            let mut params = range.split(';');
+            let media_type = params.next()?.trim().to_ascii_lowercase();
+            if !types(&media_type) {
+                return None;
+            }
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some((quality, std::cmp::Reverse(position)))
+        })
+        .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .unwrap_or((0.0, std::cmp::Reverse(usize::MAX)))
+}
+
+/// Whether a request with this `Accept` header wants JSON rather than a page
+pub fn prefers_json(accept: Option<&str>) -> bool {
+    let Some(accept) = accept else {
+        return false;
+    };
+    let json = preference(accept, |media_type| media_type == "application/json" || media_type.ends_with("+json"));
+    let html = preference(accept, |media_type| media_type == "text/html" || media_type == "application/xhtml+xml");
+    json.0 > 0.0 && json > html
+}
+
+fn json_error(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    let body = serde_json::to_string(&ApiResponse::<()>::error(message.to_string())).unwrap_or_default();
+    Response::from_data(body)
+        .with_status_code(status)
+        .with_header(Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap())
+}
+
+/// An error for `status`, e.g. `404` with "There is nothing at /missing.",
+/// as a page or as JSON depending on `accept`
+pub fn error(accept: Option<&str>, status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    if prefers_json(accept) {
+        return json_error(status, message);
+    }
+    let page = ErrorPage {
+        app_name: AppConfig::global().get_app_name(),
+        status,
+        reason: StatusCode(status).default_reason_phrase(),
+        message,
+    };
+    html(&page, status)
+}
+
+pub fn not_found(accept: Option<&str>, path: &str) -> Response<Cursor<Vec<u8>>> {
+    error(accept, 404, &format!("There is nothing at {}.", path))
+}
+
+/// Shown for the app page while the frontend has not been built
+pub fn splash(accept: Option<&str>) -> Response<Cursor<Vec<u8>>> {
+    let response = if prefers_json(accept) {
+        json_error(503, "The frontend has not been built yet.")
+    } else {
+        let config = AppConfig::global();
+        let page = SplashPage {
+            app_name: config.get_app_name(),
+            version: config.get_version(),
+            retry_secs: SPLASH_RETRY_SECS,
+        };
+        html(&page, 503)
+    };
+    response.with_header(Header::from_bytes(&b"Retry-After"[..], SPLASH_RETRY_SECS.to_string().as_bytes()).unwrap())
+}
+
+/// The devtools routes with what they return
+pub fn devtools(token_required: bool) -> Response<Cursor<Vec<u8>>> {
+    let page = DevToolsPage {
+        app_name: AppConfig::global().get_app_name(),
+        token_required,
+        routes: openapi::routes_tagged("devtools"),
+    };
+    html(&page, 200)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages_are_escaped_and_complete() {
+        let page = ErrorPage { app_name: "Demo", status: 404, reason: "Not Found", message: "Nothing at /<script>" };
+        let html = page.render().unwrap();
+        assert!(html.contains("<title>404 Not Found - Demo</title>"));
+        assert!(html.contains("Nothing at /&#60;script&#62;"));
+
+        let page = SplashPage { app_name: "Demo", version: "1.2.3", retry_secs: 2 };
+        let html = page.render().unwrap();
+        assert!(html.contains(r#"<meta http-equiv="refresh" content="2">"#));
+        assert!(html.contains("v1.2.3"));
+
+        let routes = openapi::routes_tagged("devtools");
+        assert!(routes.iter().any(|route| route.path == "/api/devtools/metrics"));
+        let page = DevToolsPage { app_name: "Demo", token_required: true, routes };
+        let html = page.render().unwrap();
+        assert!(html.contains("<code>/api/devtools/metrics</code>"));
+        assert!(html.contains("X-DevTools-Token"));
+        assert!(!html.contains("<script"));
+    }
+
+    #[test]
+    fn test_json_is_sent_only_when_preferred() {
+        assert!(prefers_json(Some("application/json")));
+        assert!(prefers_json(Some("application/problem+json, text/plain")));
+        assert!(prefers_json(Some("text/html;q=0.5, application/json")));
+        assert!(prefers_json(Some("application/json, text/html")));
+        // What browsers send when navigating
+        assert!(!prefers_json(Some("text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")));
+        assert!(!prefers_json(Some("text/html, application/json")));
+        assert!(!prefers_json(Some("application/json;q=0")));
+        assert!(!prefers_json(Some("*/*")));
+        assert!(!prefers_json(None));
+
+        let body = json_error(404, "There is nothing at /missing.").into_reader().into_inner();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, serde_json::json!({ "success": false, "data": null, "error": "There is nothing at /missing.", "message": null }));
+    }
+}
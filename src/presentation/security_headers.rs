@@ -0,0 +1,113 @@
+//! Security headers added to every HTTP response, from `[security_headers]`
+//!
+//! The generated Content-Security-Policy fits the production React bundle:
+//! scripts only from the app itself plus the hashes of the inline scripts
+//! it injects (the devtools token), styles also inline since `index.html`
+//! and React `style` props use them, and WebSocket connections only to the
+//! command port. `dev_mode` loosens it for development builds that need
+//! eval (source maps, the devtools console) and a dev server on another port.
+
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::net::IpAddr;
+use tiny_http::{Header, Response};
+use crate::model::core::AppConfig;
+
+pub struct SecurityHeaders {
+    headers: Vec<Header>,
+}
+
+/// CSP source for an inline script, so it runs without `'unsafe-inline'`
+fn script_hash(script: &str) -> String {
+    let digest = Sha256::digest(script.as_bytes());
+    format!("'sha256-{}'", base64::engine::general_purpose::STANDARD.encode(digest))
+}
+
+/// The policy for a page whose WebSocket listens on `bind:ws_port`
+pub fn content_security_policy(dev_mode: bool, bind: &str, ws_port: u16, inline_scripts: &[String]) -> String {
+    let mut script_src = vec!["'self'".to_string()];
+    let mut connect_src = vec!["'self'".to_string()];
+    if dev_mode {
+        script_src.extend(["'unsafe-inline'", "'unsafe-eval'"].map(String::from));
+        connect_src.extend(["ws:", "wss:", "http://localhost:*", "http://127.0.0.1:*"].map(String::from));
+    } else {
+        script_src.extend(inline_scripts.iter().map(|script| script_hash(script)));
+        connect_src.push(format!("ws://localhost:{}", ws_port));
+        connect_src.push(format!("ws://127.0.0.1:{}", ws_port));
+        // Served to other machines, the page connects to whatever host name it was loaded from
+        if !bind.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback()) && bind != "localhost" {
+            connect_src.push(format!("ws://*:{}", ws_port));
+        }
+    }
+
+    [
+        "default-src 'self'".to_string(),
+        format!("script-src {}", script_src.join(" ")),
+        "style-src 'self' 'unsafe-inline'".to_string(),
+        "img-src 'self' data: blob:".to_string(),
+        "media-src 'self' blob:".to_string(),
+        "font-src 'self' data:".to_string(),
+        format!("connect-src {}", connect_src.join(" ")),
+        "object-src 'none'".to_string(),
+        "base-uri 'self'".to_string(),
+        "form-action 'self'".to_string(),
+        "frame-ancestors 'none'".to_string(),
+    ]
+    .join("; ")
+}
+
+impl SecurityHeaders {
+    /// Headers for this run; none when `[security_headers] enabled = false`
+    pub fn from_config(config: &AppConfig, ws_port: u16, inline_scripts: &[String]) -> Self {
+        if !config.is_security_headers_enabled() {
+            return Self { headers: Vec::new() };
+        }
+        let policy = config.get_security_headers_csp().unwrap_or_else(|| {
+            content_security_policy(
+                // Dev server bundles need eval
+                config.is_security_headers_dev_mode() || config.get_dev_proxy_url().is_some(),
+                config.get_server_bind(),
+                ws_port,
+                inline_scripts,
+            )
+        });
+        let headers = [
+            ("Content-Security-Policy", policy),
+            ("X-Content-Type-Options", "nosniff".to_string()),
+            ("X-Frame-Options", config.get_security_headers_frame_options()),
+            ("Referrer-Policy", config.get_security_headers_referrer_policy()),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| Header::from_bytes(field.as_bytes(), value.as_bytes()).ok())
+        .collect();
+        Self { headers }
+    }
+
+    pub fn apply<R: Read>(&self, mut response: Response<R>) -> Response<R> {
+        for header in &self.headers {
+            response.add_header(header.clone());
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_allows_app_scripts_and_ws_port() {
+        let script = "window.__DEVTOOLS_TOKEN__ = \"abc\";".to_string();
+        let policy = content_security_policy(false, "127.0.0.1", 9000, &[script]);
+
+        let directive = |name: &str| policy.split("; ").find(|d| d.starts_with(name)).unwrap().to_string();
+        assert!(directive("script-src").starts_with("script-src 'self' 'sha256-"));
+        assert!(!policy.contains("unsafe-eval"));
+        assert_eq!(directive("connect-src"), "connect-src 'self' ws://localhost:9000 ws://127.0.0.1:9000");
+        assert!(content_security_policy(false, "0.0.0.0", 9000, &[]).contains("ws://*:9000"));
+
+        let dev = content_security_policy(true, "127.0.0.1", 9000, &[]);
+        assert!(dev.contains("'unsafe-eval'") && dev.contains("connect-src 'self' ws: wss:"));
+    }
+}
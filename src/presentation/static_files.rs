@@ -0,0 +1,278 @@
+//! Frontend asset responses with byte range support
+//!
+//! Assets are streamed from disk and advertise `Accept-Ranges: bytes`, so
+//! `<video>` and `<audio>` can seek and interrupted downloads can resume.
+//! One `Range` is answered with `206 Partial Content`; a malformed header is
+//! ignored and the whole file sent, as RFC 9110 asks. Multiple ranges are
+//! not supported and answered with `416`, like ranges past the end.
+//!
+//! URL paths are mapped onto `frontend/dist` by `StaticRoot`, which only
+//! hands out files inside it: `..`, hidden segments, backslashes and encoded
+//! separators are refused before touching the disk, and a file reached
+//! through a symlink must still lie in the root once resolved. Only the
+//! extensions of `[server] static_extensions` are served.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tiny_http::{Header, Response, StatusCode};
+
+/// Why a URL path does not name a file that may be served
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejected {
+    /// Traversal, encoded separators, hidden or malformed segments
+    BadPath,
+    /// An extension outside `static_extensions`
+    Extension,
+}
+
+impl Rejected {
+    pub fn status(self) -> u16 {
+        match self {
+            Rejected::BadPath => 400,
+            Rejected::Extension => 403,
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            Rejected::BadPath => "The path is not allowed.",
+            Rejected::Extension => "Files of this type are not served.",
+        }
+    }
+}
+
+/// Decode `%XX` escapes; `None` for malformed ones or a result that isn't UTF-8
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// The directory frontend files are served from
+pub struct StaticRoot {
+    root: PathBuf,
+    /// Lower-case, without the dot; `*` allows any
+    extensions: Vec<String>,
+}
+
+impl StaticRoot {
+    pub fn new(root: impl Into<PathBuf>, extensions: Vec<String>) -> Self {
+        Self { root: root.into(), extensions }
+    }
+
+    /// The file below the root that `url_path` (without query) names, `/`
+    /// being `index.html`. It need not exist; if it does, it must lie within
+    /// the root once symlinks are resolved and have an allowed extension.
+    pub fn resolve(&self, url_path: &str) -> Result<PathBuf, Rejected> {
+        // Encoded separators only serve to smuggle traversal past checks
+        let lower = url_path.to_ascii_lowercase();
+        if lower.contains("%2f") || lower.contains("%5c") || lower.contains("%00") {
+            return Err(Rejected::BadPath);
+        }
+        let decoded = percent_decode(url_path).ok_or(Rejected::BadPath)?;
+        // Still escaped after decoding: encoded twice
+        if decoded.contains('\\') || decoded.contains('\0') || decoded.contains('%') {
+            return Err(Rejected::BadPath);
+        }
+
+        let relative = match decoded.trim_start_matches('/') {
+            "" => "index.html",
+            relative => relative,
+        };
+        let mut path = self.root.clone();
+        for segment in relative.split('/').filter(|segment| !segment.is_empty()) {
+            // `.`, `..` and dotfiles such as `.env`
+            let hidden = segment.starts_with('.') && segment != ".well-known";
+            if hidden || segment.contains(':') {
+                return Err(Rejected::BadPath);
+            }
+            path.push(segment);
+        }
+
+        // Missing files are simply not found
+        let (Ok(root), Ok(file)) = (self.root.canonicalize(), path.canonicalize()) else {
+            return Ok(path);
+        };
+        // A symlink inside the root may still point outside it
+        if !file.starts_with(&root) {
+            return Err(Rejected::BadPath);
+        }
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        if !self.extensions.iter().any(|allowed| allowed == "*" || *allowed == extension) {
+            return Err(Rejected::Extension);
+        }
+        Ok(path)
+    }
+}
+
+/// What a `Range` header asks of a file of a given length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    Full,
+    /// Inclusive, as in `Content-Range`
+    Partial { start: u64, end: u64 },
+    Unsatisfiable,
+}
+
+pub fn parse_range(header: Option<&str>, len: u64) -> ByteRange {
+    let Some(spec) = header.and_then(|header| header.trim().strip_prefix("bytes=")) else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Unsatisfiable;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+    let parse = |value: &str| value.trim().parse::<u64>().ok();
+
+    let (start, end) = match (start.trim().is_empty(), end.trim().is_empty()) {
+        // `bytes=-500`: the last 500 bytes
+        (true, false) => match parse(end) {
+            Some(0) => return ByteRange::Unsatisfiable,
+            Some(suffix) => (len.saturating_sub(suffix), len.saturating_sub(1)),
+            None => return ByteRange::Full,
+        },
+        // `bytes=500-`: from byte 500 on
+        (false, true) => match parse(start) {
+            Some(start) => (start, len.saturating_sub(1)),
+            None => return ByteRange::Full,
+        },
+        (false, false) => match (parse(start), parse(end)) {
+            (Some(start), Some(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+            _ => return ByteRange::Full,
+        },
+        (true, true) => return ByteRange::Full,
+    };
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial { start, end }
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).unwrap()
+}
+
+/// Stream the file at `path`, or the part of it `range` (a `Range` header) asks for
+pub fn file_response(path: &Path, content_type: &str, range: Option<&str>) -> io::Result<Response<Box<dyn Read + Send>>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut headers = vec![header("Content-Type", content_type), header("Accept-Ranges", "bytes")];
+
+    let (status, body, body_len): (u16, Box<dyn Read + Send>, u64) = match parse_range(range, len) {
+        ByteRange::Full => (200, Box::new(file), len),
+        ByteRange::Partial { start, end } => {
+            file.seek(SeekFrom::Start(start))?;
+            headers.push(header("Content-Range", &format!("bytes {}-{}/{}", start, end, len)));
+            (206, Box::new(file.take(end - start + 1)), end - start + 1)
+        }
+        ByteRange::Unsatisfiable => {
+            headers.push(header("Content-Range", &format!("bytes */{}", len)));
+            (416, Box::new(io::empty()), 0)
+        }
+    };
+    // Keep `Content-Length` on large files too, for progress and resuming
+    Ok(Response::new(StatusCode(status), headers, body, Some(body_len as usize), None).with_chunked_threshold(usize::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range(None, 100), ByteRange::Full);
+        assert_eq!(parse_range(Some("bytes=0-9"), 100), ByteRange::Partial { start: 0, end: 9 });
+        assert_eq!(parse_range(Some("bytes=90-"), 100), ByteRange::Partial { start: 90, end: 99 });
+        assert_eq!(parse_range(Some("bytes=-10"), 100), ByteRange::Partial { start: 90, end: 99 });
+        assert_eq!(parse_range(Some("bytes=-500"), 100), ByteRange::Partial { start: 0, end: 99 });
+        assert_eq!(parse_range(Some("bytes=50-500"), 100), ByteRange::Partial { start: 50, end: 99 });
+
+        assert_eq!(parse_range(Some("bytes=100-"), 100), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range(Some("bytes=-0"), 100), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range(Some("bytes=0-1, 5-9"), 100), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range(Some("bytes=0-"), 0), ByteRange::Unsatisfiable);
+
+        assert_eq!(parse_range(Some("bytes=9-0"), 100), ByteRange::Full);
+        assert_eq!(parse_range(Some("items=0-9"), 100), ByteRange::Full);
+        assert_eq!(parse_range(Some("bytes=a-b"), 100), ByteRange::Full);
+    }
+
+    fn root(dir: &Path) -> StaticRoot {
+        StaticRoot::new(dir, vec!["html".to_string(), "js".to_string()])
+    }
+
+    #[test]
+    fn test_malicious_paths_are_rejected() {
+        let root = root(Path::new("frontend/dist"));
+        assert_eq!(root.resolve("/"), Ok(PathBuf::from("frontend/dist/index.html")));
+        assert_eq!(root.resolve("/static/js/index.js"), Ok(PathBuf::from("frontend/dist/static/js/index.js")));
+        assert_eq!(root.resolve("//static//app%20v2.js"), Ok(PathBuf::from("frontend/dist/static/app v2.js")));
+        assert_eq!(root.resolve("/.well-known/security.html"), Ok(PathBuf::from("frontend/dist/.well-known/security.html")));
+
+        for path in [
+            "/../../etc/passwd.html",
+            "/static/../../secret.js",
+            "/%2e%2e/%2e%2e/etc/passwd.html",
+            "/%2E%2E%2Fapp.config.js",
+            "/static%2f..%2f..%2fsecret.js",
+            "/..%5c..%5csecret.js",
+            "/..\\..\\secret.js",
+            "/%252e%252e/secret.js",
+            "/index.html%00.js",
+            "/.env.js",
+            "/.git/config.html",
+            "/C:/Windows/win.ini.html",
+            "/%zz.js",
+            "/%c0%ae%c0%ae/secret.js",
+        ] {
+            assert_eq!(root.resolve(path), Err(Rejected::BadPath), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_only_allowed_files_within_the_root_are_served() {
+        let base = TempDir::new("static-root");
+        let dist = base.join("dist");
+        std::fs::create_dir_all(&dist).unwrap();
+        for file in ["page.html", "app.config.toml", "LICENSE", "PAGE.HTML"] {
+            std::fs::write(dist.join(file), "x").unwrap();
+        }
+
+        let root = root(&dist);
+        assert_eq!(root.resolve("/page.html"), Ok(dist.join("page.html")));
+        assert_eq!(root.resolve("/PAGE.HTML"), Ok(dist.join("PAGE.HTML")));
+        assert_eq!(root.resolve("/app.config.toml"), Err(Rejected::Extension));
+        assert_eq!(root.resolve("/LICENSE"), Err(Rejected::Extension));
+        assert!(StaticRoot::new(&dist, vec!["*".to_string()]).resolve("/LICENSE").is_ok());
+        // Missing files are left to answer 404, whatever their type
+        assert_eq!(root.resolve("/missing.toml"), Ok(dist.join("missing.toml")));
+
+        #[cfg(unix)]
+        {
+            std::fs::write(base.join("secret.html"), "secret").unwrap();
+            std::os::unix::fs::symlink(base.join("secret.html"), dist.join("leak.html")).unwrap();
+            std::os::unix::fs::symlink(dist.join("page.html"), dist.join("alias.html")).unwrap();
+            assert_eq!(root.resolve("/alias.html"), Ok(dist.join("alias.html")));
+            assert_eq!(root.resolve("/leak.html"), Err(Rejected::BadPath));
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! `POST /api/upload` - store files sent as `multipart/form-data`
+//!
+//! Every file field of the body is streamed to disk under the same rules as
+//! WebSocket uploads (`[file_transfer]`). The `directory` query parameter
+//! picks the destination within `allowed_dirs`, `upload_dir` by default, and
+//! `overwrite=true` replaces existing files. Requests from a page whose
+//! origin `[cors]` does not allow are refused, so other web pages cannot post
+//! files into the app.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Read;
+use utoipa::ToSchema;
+use crate::infrastructure::file_transfer::{self, multipart, CompletedUpload, TransferPolicy};
+use crate::model::core::AppConfig;
+
+pub const UPLOAD_PATH: &str = "/api/upload";
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UploadResponse {
+    pub success: bool,
+    pub files: Vec<CompletedUpload>,
+}
+
+fn failure(status: u16, error: impl ToString) -> (u16, Value) {
+    (status, serde_json::json!({ "success": false, "error": error.to_string() }))
+}
+
+/// Answer an upload request with a status and a JSON body. `origin_allowed` is false
+/// for requests from pages of origins `[cors]` does not allow; requests without an
+/// `Origin` come from local tools and are allowed.
+pub fn handle_upload(
+    method: &str,
+    query: &str,
+    origin_allowed: bool,
+    content_type: Option<&str>,
+    body: impl Read,
+) -> (u16, Value) {
+    if method != "POST" {
+        return failure(405, "Use POST with a multipart/form-data body");
+    }
+    if !origin_allowed {
+        return failure(403, "Uploads are only accepted from the app");
+    }
+    let Some(boundary) = content_type.and_then(multipart::boundary) else {
+        return failure(415, "Expected a multipart/form-data body");
+    };
+
+    let mut directory = None;
+    let mut overwrite = false;
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "directory" => directory = Some(value.into_owned()),
+            "overwrite" => overwrite = value == "true",
+            _ => {}
+        }
+    }
+
+    let policy = TransferPolicy::from_config(AppConfig::global());
+    match file_transfer::receive_multipart(&policy, body, &boundary, directory.as_deref(), overwrite) {
+        Ok(files) if files.is_empty() => failure(400, "The body has no file fields"),
+        Ok(files) => {
+            for file in &files {
+                futures::executor::block_on(file_transfer::emit_uploaded(file, "http"));
+            }
+            (200, serde_json::to_value(UploadResponse { success: true, files }).unwrap_or_default())
+        }
+        Err(e) => {
+            tracing::warn!("Upload rejected: {}", e);
+            failure(e.status(), e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refuses_other_origins_and_bodies() {
+        let body: &[u8] = b"";
+        assert_eq!(handle_upload("GET", "", true, None, body).0, 405);
+        assert_eq!(handle_upload("POST", "", false, None, body).0, 403);
+        assert_eq!(handle_upload("POST", "", true, Some("application/json"), body).0, 415);
+    }
+}
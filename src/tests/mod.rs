@@ -5,3 +5,7 @@
 
 mod test_domain_entities;
 mod test_domain_errors;
+mod test_server_roundtrip;
+
+#[cfg(test)]
+pub mod testkit;
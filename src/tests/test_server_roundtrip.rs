@@ -0,0 +1,114 @@
+//! Tests for command round-trips through the real servers
+//!
+//! Note: Uses the shared in-process app from `testkit`.
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::tests::testkit::{TestApp, WsClient};
+    use crate::viewmodel::protocol;
+
+    #[tokio::test]
+    async fn test_created_user_is_listed() {
+        let app = TestApp::shared();
+        let mut client = app.client().await;
+
+        let created = client
+            .call("create_user", json!({ "name": "Round Trip", "email": "round.trip@example.com", "role": "editor" }))
+            .await;
+        assert_eq!(created["success"], true, "{}", created);
+        let id = created["data"]["id"].clone();
+
+        let listed = client.call("get_users", json!({})).await;
+        let users = listed["data"].as_array().expect("get_users returns a list");
+        let user = users.iter().find(|user| user["id"] == id).expect("the new user is listed");
+        assert_eq!((user["name"].as_str(), user["role"].as_str()), (Some("Round Trip"), Some("editor")));
+        assert!(app.db.get_user(id.as_i64().unwrap()).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_payload_is_refused() {
+        let mut client = TestApp::shared().client().await;
+
+        let refused = client.call("create_user", json!({ "name": "", "email": "not an email" })).await;
+        assert_eq!(refused["success"], false);
+        assert_eq!(refused["error"]["code"], "ValidationFailed");
+    }
+
+    #[tokio::test]
+    async fn test_deeply_nested_message_is_refused() {
+        let mut client = TestApp::shared().client().await;
+
+        let nested = (0..64).fold(json!(1), |inner, _| json!([inner]));
+        client.send("get_users", nested).await;
+        let refused = client.next_event("backend.error").await;
+        assert_eq!(refused.payload["error"]["context"]["error_type"], "MESSAGE_TOO_DEEP", "{}", refused.payload);
+
+        // The connection is still usable
+        assert_eq!(client.call("get_users", json!({})).await["success"], true);
+    }
+
+    #[test]
+    fn test_http_server_answers_probes() {
+        let app = TestApp::shared();
+        let (status, body) = app.http_get("/healthz");
+        assert_eq!(status, 200, "{}", body);
+        assert_eq!(app.http_get("/api/no-such-route").0, 404);
+    }
+
+    #[test]
+    fn test_schemas_need_the_devtools_token() {
+        let app = TestApp::shared();
+        assert_eq!(app.http_get("/api/schemas").0, 401);
+        let (status, body) = app.devtools_get("/api/schemas/ws_message");
+        assert_eq!(status, 200, "{}", body);
+        assert_eq!(app.devtools_get("/api/schemas/no_such_schema").0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_command_latencies_are_exported() {
+        let app = TestApp::shared();
+        let mut client = app.client().await;
+        client.call("get_db_stats", json!({})).await;
+
+        assert_eq!(app.http_get("/metrics").0, 401);
+        let (status, body) = app.devtools_get("/metrics");
+        assert_eq!(status, 200, "{}", body);
+        assert!(body.contains("# TYPE command_duration_seconds histogram"), "{}", body);
+        assert!(body.contains("command_duration_seconds_bucket{command=\"get_db_stats\",le=\"+Inf\"}"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_reconnecting_client_gets_missed_events() {
+        let app = TestApp::shared();
+        let version = protocol::PROTOCOL_VERSION;
+        let mut first = WsClient::connect(&format!("ws://{}", app.ws_addr)).await;
+        let hello = first.call(protocol::HELLO, json!({ "protocol_version": version })).await;
+        let (session, resume_token) = (hello["session"]["id"].clone(), hello["resume_token"].clone());
+        first.call("ui.ready", json!({})).await;
+        let seen = first.next_event("backend.connected").await;
+        drop(first);
+
+        // Emitted while the first client is away
+        let mut other = app.client().await;
+        other.call("ui.ready", json!({})).await;
+        let missed = other.next_event("backend.connected").await;
+
+        // The session id alone does not continue the session
+        let mut guessing = WsClient::connect(&format!("ws://{}", app.ws_addr)).await;
+        let answer = guessing.call(protocol::HELLO, json!({ "protocol_version": version, "resume_token": session, "resume_from": seen.id })).await;
+        assert_ne!(answer["session"]["id"], session, "{}", answer);
+        assert_eq!(answer["resume"], json!({ "status": "refresh", "reason": "session_ended" }));
+
+        let mut resumed = WsClient::connect(&format!("ws://{}", app.ws_addr)).await;
+        let answer = resumed.call(protocol::HELLO, json!({ "protocol_version": version, "resume_token": resume_token, "resume_from": seen.id })).await;
+        assert_eq!(answer["session"]["id"], session, "{}", answer);
+        assert!(answer.get("resume_token").is_none(), "{}", answer);
+        assert_eq!(answer["resume"]["status"], "replayed", "{}", answer);
+        assert_eq!(resumed.next_event("backend.connected").await.id, missed.id);
+
+        let mut unknown = WsClient::connect(&format!("ws://{}", app.ws_addr)).await;
+        let answer = unknown.call(protocol::HELLO, json!({ "protocol_version": version, "resume_token": resume_token, "resume_from": "no-such-event" })).await;
+        assert_eq!(answer["resume"], json!({ "status": "refresh", "reason": "events_lost" }));
+    }
+}
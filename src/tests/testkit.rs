@@ -0,0 +1,214 @@
+//! In-process app for integration tests
+//!
+//! `TestApp::shared()` boots the HTTP and WebSocket servers once per test
+//! binary, on ephemeral ports and over an in-memory database, and keeps them
+//! on a runtime of their own so they outlive the `#[tokio::test]` that first
+//! asked for them. `WsClient` talks to the socket the way the frontend does,
+//! so a test can send a command and assert on its response and on the events
+//! it causes.
+//!
+//! The servers share the process-wide state of the app (`AppConfig`, the
+//! EventBus, the database handle), so tests should not depend on each other's
+//! rows or events beyond what they create themselves.
+//!
+//! `TempDir` is the scratch directory of a single test, for module tests
+//! that need real files.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tungstenite::Message;
+use crate::infrastructure::database::init_database;
+use crate::infrastructure::event_bus::EventBus;
+use crate::infrastructure::server::publish_ports;
+use crate::model::core::{AppConfig, Database};
+use crate::viewmodel::protocol;
+use crate::viewmodel::websocket_handler::{start_websocket_server, WebSocketEvent};
+
+/// How long a client waits for a response or an event
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct TestApp {
+    pub http_addr: SocketAddr,
+    pub ws_addr: SocketAddr,
+    pub db: Arc<Database>,
+}
+
+impl TestApp {
+    /// The servers of this test binary, started on first use
+    pub fn shared() -> &'static TestApp {
+        static APP: OnceLock<TestApp> = OnceLock::new();
+        APP.get_or_init(|| Self::boot().expect("Failed to boot the test app"))
+    }
+
+    fn boot() -> Result<TestApp, Box<dyn std::error::Error>> {
+        let db = Arc::new(Database::new(":memory:")?);
+        db.init()?;
+        init_database(Arc::clone(&db));
+
+        let ws_listener = TcpListener::bind("127.0.0.1:0")?;
+        let http_listener = TcpListener::bind("127.0.0.1:0")?;
+        let ws_addr = ws_listener.local_addr()?;
+        let http_addr = http_listener.local_addr()?;
+        publish_ports(AppConfig::global(), http_addr.port(), ws_addr.port());
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("testkit-server")
+            .enable_all()
+            .build()?;
+        std::thread::spawn(move || {
+            runtime.block_on(async move {
+                if let Err(e) = start_websocket_server(EventBus::global(), ws_listener).await {
+                    panic!("Test WebSocket server stopped: {}", e);
+                }
+            })
+        });
+        crate::start_http_server(http_listener).map_err(|e| e.to_string())?;
+
+        Ok(TestApp { http_addr, ws_addr, db })
+    }
+
+    /// A client that has said `hello`, so the server is sending it events
+    pub async fn client(&self) -> WsClient {
+        let mut client = WsClient::connect(&format!("ws://{}", self.ws_addr)).await;
+        let hello = client.call(protocol::HELLO, serde_json::json!({ "protocol_version": protocol::PROTOCOL_VERSION })).await;
+        assert!(hello.get("error").is_none(), "hello was refused: {}", hello);
+        client
+    }
+
+    /// Status and body of a plain `GET path`
+    pub fn http_get(&self, path: &str) -> (u16, String) {
+        self.get(path, "")
+    }
+
+    /// Status and body of `GET path` with this run's DevTools token
+    pub fn devtools_get(&self, path: &str) -> (u16, String) {
+        let token = crate::presentation::devtools::token().expect("The test app requires a DevTools token");
+        self.get(path, &format!("X-DevTools-Token: {}\r\n", token))
+    }
+
+    fn get(&self, path: &str, headers: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(self.http_addr).expect("Failed to connect to the test HTTP server");
+        stream.set_read_timeout(Some(RECEIVE_TIMEOUT)).ok();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n{}Connection: close\r\n\r\n", path, self.http_addr, headers)
+            .expect("Failed to send the request");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).expect("Failed to read the response");
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap_or((response.as_str(), ""));
+        let status = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .unwrap_or_default();
+        (status, body.to_string())
+    }
+}
+
+/// WebSocket client speaking the app's JSON messages
+pub struct WsClient {
+    socket: WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
+    next_id: u64,
+    /// Messages received while waiting for something else
+    unread: VecDeque<WebSocketEvent>,
+}
+
+impl WsClient {
+    pub async fn connect(url: &str) -> WsClient {
+        let (socket, _) = connect_async(url).await.unwrap_or_else(|e| panic!("Failed to connect to {}: {}", url, e));
+        WsClient { socket, next_id: 0, unread: VecDeque::new() }
+    }
+
+    /// Send `name` as the frontend would and return the id of the message
+    pub async fn send(&mut self, name: &str, payload: Value) -> String {
+        self.next_id += 1;
+        let id = format!("test-{}", self.next_id);
+        let message = WebSocketEvent {
+            id: id.clone(),
+            name: name.to_string(),
+            payload,
+            timestamp: 0,
+            source: "frontend".to_string(),
+            mutation_id: None,
+        };
+        let text = serde_json::to_string(&message).expect("Failed to serialize the message");
+        self.socket.send(Message::Text(text.into())).await.unwrap_or_else(|e| panic!("Failed to send {}: {}", name, e));
+        id
+    }
+
+    /// Send command `name` and wait for its response payload
+    pub async fn call(&mut self, name: &str, payload: Value) -> Value {
+        let id = self.send(name, payload).await;
+        self.wait_for(|message| message.id == id, &format!("the response to {}", name)).await.payload
+    }
+
+    /// Wait for the next event called `name`
+    pub async fn next_event(&mut self, name: &str) -> WebSocketEvent {
+        self.wait_for(|message| message.name == name && !message.id.starts_with("test-"), &format!("event {}", name)).await
+    }
+
+    async fn wait_for(&mut self, wanted: impl Fn(&WebSocketEvent) -> bool, what: &str) -> WebSocketEvent {
+        if let Some(index) = self.unread.iter().position(&wanted) {
+            return self.unread.remove(index).expect("index found by position");
+        }
+        let received = tokio::time::timeout(RECEIVE_TIMEOUT, async {
+            loop {
+                let message = match self.socket.next().await {
+                    Some(Ok(Message::Text(text))) => serde_json::from_str::<WebSocketEvent>(&text)
+                        .unwrap_or_else(|e| panic!("Unexpected message {}: {}", text, e)),
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => panic!("WebSocket failed while waiting for {}: {}", what, e),
+                    None => panic!("WebSocket closed while waiting for {}", what),
+                };
+                if wanted(&message) {
+                    return message;
+                }
+                self.unread.push_back(message);
+            }
+        })
+        .await;
+        received.unwrap_or_else(|_| panic!("No {} within {:?}", what, RECEIVE_TIMEOUT))
+    }
+}
+
+/// Fresh directory under the system temp dir, deleted with everything in it on drop
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create `<temp>/<prefix>-<uuid>`, so parallel tests never share a directory
+    pub fn new(prefix: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("{}-{}", prefix, uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&path).unwrap_or_else(|e| panic!("Failed to create {}: {}", path.display(), e));
+        TempDir { path }
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,255 @@
+//! Typed TypeScript client generated from the command catalog
+//!
+//! `app --generate-client [dir]` writes `client.ts` (by default to
+//! `frontend/src/generated`): one function per command with its request and
+//! response interfaces, and typed listeners for the application events.
+//! Plugin commands the catalog does not describe are included untyped.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use crate::viewmodel::command_catalog::{self, CommandSpec, EventSpec, FieldSpec, TypeSpec};
+
+pub const CLIENT_FILE: &str = "client.ts";
+pub const DEFAULT_CLIENT_DIR: &str = "frontend/src/generated";
+
+const HEADER: &str = "\
+// Generated by `app --generate-client` from the backend command catalog.
+// Do not edit; run `bun run generate:client` after changing commands or events.
+
+import type { RecoveryAction } from '../core/error-handling/app-error';
+import { EventBus } from '../models/event-bus';
+import { requestFromBackend } from '../view-models/communication-bridge';
+";
+
+const RUNTIME: &str = "\
+/** Error envelope of a failed command */
+export interface CommandErrorInfo {
+  code: string;
+  code_value: number;
+  message: string;
+  user_message: string;
+  recovery: RecoveryAction;
+  id: string;
+  timestamp: string;
+  context: Record<string, unknown>;
+}
+
+export class CommandError extends Error {
+  constructor(readonly command: string, readonly info: CommandErrorInfo) {
+    super(info.message);
+    this.name = 'CommandError';
+  }
+}
+
+/**
+ * Send `name` and resolve with its response; failures reject with a `CommandError`.
+ * With a `mutationId`, the backend also reports it as `command.accepted`, then `command.applied` or `command.rejected`.
+ */
+export async function call<T>(name: string, payload: object = {}, mutationId?: string): Promise<T> {
+  const response = await requestFromBackend(name, payload, mutationId);
+  if (response?.success === false) {
+    throw new CommandError(name, response.error as CommandErrorInfo);
+  }
+  return response as T;
+}
+
+/** Listen to a backend event; returns the unsubscribe function */
+export function onEvent<K extends keyof EventPayloads>(
+  name: K,
+  handler: (payload: EventPayloads[K]) => void,
+): () => void {
+  return EventBus.subscribe(name, (event) => handler(event.payload as unknown as EventPayloads[K]));
+}
+";
+
+/// `counter.create` -> `CounterCreate`
+fn pascal_case(name: &str) -> String {
+    name.split(['.', '_', '-'])
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// `counter.create` -> `counterCreate`
+fn camel_case(name: &str) -> String {
+    let pascal = pascal_case(name);
+    let mut chars = pascal.chars();
+    chars
+        .next()
+        .map(|first| first.to_lowercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn write_fields(out: &mut String, fields: &[FieldSpec]) {
+    for field in fields {
+        let optional = if field.optional { "?" } else { "" };
+        let _ = writeln!(out, "  {}{}: {};", field.name, optional, field.ts_type);
+    }
+}
+
+fn write_interface(out: &mut String, name: &str, leading: &str, fields: &[FieldSpec]) {
+    let _ = writeln!(out, "export interface {} {{", name);
+    out.push_str(leading);
+    write_fields(out, fields);
+    out.push_str("}\n\n");
+}
+
+fn write_command(out: &mut String, command: &CommandSpec) {
+    let base = pascal_case(&command.name);
+    let function = camel_case(&command.name);
+    let response = format!("{}Response", base);
+
+    if command.untyped {
+        let _ = writeln!(out, "/** {} */", command.description);
+        let _ = writeln!(
+            out,
+            "export const {} = (request: Record<string, unknown> = {{}}): Promise<Record<string, unknown>> =>\n  call('{}', request);\n",
+            function, command.name
+        );
+        return;
+    }
+
+    let request = format!("{}Request", base);
+    if !command.params.is_empty() {
+        write_interface(out, &request, "", &command.params);
+    }
+    write_interface(out, &response, "  success: true;\n", &command.returns);
+
+    let _ = writeln!(out, "/** {} */", command.description);
+    let signature = if command.params.is_empty() {
+        String::new()
+    } else if command.params.iter().all(|param| param.optional) {
+        format!("request: {} = {{}}", request)
+    } else {
+        format!("request: {}", request)
+    };
+    let arguments = if command.params.is_empty() { "" } else { ", request" };
+    let _ = writeln!(
+        out,
+        "export const {} = ({}): Promise<{}> => call('{}'{});\n",
+        function, signature, response, command.name, arguments
+    );
+}
+
+fn event_type_name(event: &EventSpec) -> String {
+    format!("{}Event", pascal_case(&event.name))
+}
+
+fn write_event(out: &mut String, event: &EventSpec) {
+    let name = event_type_name(event);
+    match event.payload_type {
+        Some(type_name) => {
+            let _ = writeln!(out, "export type {} = {};\n", name, type_name);
+        }
+        None if event.payload.is_empty() => {
+            let _ = writeln!(out, "export type {} = Record<string, unknown>;\n", name);
+        }
+        None => write_interface(out, &name, "", &event.payload),
+    }
+}
+
+/// The whole `client.ts`
+pub fn render(types: &[TypeSpec], commands: &[CommandSpec], events: &[EventSpec]) -> String {
+    let mut out = String::from(HEADER);
+    out.push('\n');
+    for spec in types {
+        write_interface(&mut out, spec.name, "", &spec.fields);
+    }
+    out.push_str(RUNTIME);
+
+    out.push_str("\n// Commands\n\n");
+    for command in commands {
+        write_command(&mut out, command);
+    }
+
+    out.push_str("// Events\n\n");
+    for event in events {
+        write_event(&mut out, event);
+    }
+    out.push_str("export interface EventPayloads {\n");
+    for event in events {
+        let _ = writeln!(out, "  '{}': {};", event.name, event_type_name(event));
+    }
+    out.push_str("}\n\n");
+    for event in events {
+        let _ = writeln!(
+            out,
+            "export const on{} = (handler: (payload: {}) => void): (() => void) =>\n  onEvent('{}', handler);\n",
+            pascal_case(&event.name),
+            event_type_name(event),
+            event.name
+        );
+    }
+    // One trailing newline
+    let trimmed = out.trim_end().len();
+    out.truncate(trimmed);
+    out.push('\n');
+    out
+}
+
+/// The catalog plus `plugin_commands` (name, description) it does not describe
+pub fn client_source(plugin_commands: &[(String, String)]) -> String {
+    let mut commands = command_catalog::commands();
+    for (name, description) in plugin_commands {
+        if !commands.iter().any(|command| command.name == *name) {
+            commands.push(CommandSpec::untyped(name, description));
+        }
+    }
+    render(&command_catalog::types(), &commands, &command_catalog::events())
+}
+
+/// Write `client.ts` into `dir`; returns the file written
+pub fn write_client(dir: &Path, plugin_commands: &[(String, String)]) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(CLIENT_FILE);
+    std::fs::write(&path, client_source(plugin_commands))?;
+    Ok(path)
+}
+
+/// `app --generate-client [dir]`: write the client and return the process exit code
+pub fn run_generate(args: &[String], plugin_commands: &[(String, String)]) -> i32 {
+    let dir = args.first().map(PathBuf::from).unwrap_or_else(|| PathBuf::from(DEFAULT_CLIENT_DIR));
+    match write_client(&dir, plugin_commands) {
+        Ok(path) => {
+            println!("Wrote {}", path.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("Failed to write {}: {}", dir.join(CLIENT_FILE).display(), e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(pascal_case("counter.create"), "CounterCreate");
+        assert_eq!(camel_case("get_system_info"), "getSystemInfo");
+        assert_eq!(camel_case("window.set_title"), "windowSetTitle");
+    }
+
+    #[test]
+    fn test_rendered_client() {
+        let source = client_source(&[("reports.export".to_string(), "Export a report".to_string())]);
+
+        assert!(source.contains("export interface CounterCreateRequest {\n  label: string;\n  id?: string;\n}"));
+        assert!(source.contains(
+            "export const counterCreate = (request: CounterCreateRequest): Promise<CounterCreateResponse> => call('counter.create', request);"
+        ));
+        assert!(source.contains("export const counterList = (): Promise<CounterListResponse> => call('counter.list');"));
+        assert!(source.contains("export const reportsExport = (request: Record<string, unknown> = {})"));
+        assert!(source.contains("  'counter.changed': CounterChangedEvent;"));
+        assert!(source.contains("export type TaskCompletedEvent = Task;"));
+        assert!(source.ends_with(";\n"));
+    }
+}
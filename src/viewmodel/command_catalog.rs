@@ -0,0 +1,917 @@
+//! Typed catalog of the WebSocket commands and application events
+//!
+//! `command_schemas` says what a payload must satisfy; this catalog says
+//! what each command takes and answers, and what each `AppEventType`
+//! carries, in TypeScript terms. `client_codegen` turns it into the typed
+//! frontend client, so a command registered in `WebSocketHandler::commands`
+//! belongs here as well.
+
+use crate::infrastructure::event_bus::AppEventType;
+
+/// A field of a request, response or event payload; `ts_type` is TypeScript
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub ts_type: &'static str,
+    pub optional: bool,
+}
+
+/// An interface shared between several commands or events
+#[derive(Debug, Clone)]
+pub struct TypeSpec {
+    pub name: &'static str,
+    pub fields: Vec<FieldSpec>,
+}
+
+impl TypeSpec {
+    fn new(name: &'static str) -> Self {
+        Self { name, fields: Vec::new() }
+    }
+
+    fn field(mut self, name: &'static str, ts_type: &'static str) -> Self {
+        self.fields.push(FieldSpec { name, ts_type, optional: false });
+        self
+    }
+
+    fn optional(mut self, name: &'static str, ts_type: &'static str) -> Self {
+        self.fields.push(FieldSpec { name, ts_type, optional: true });
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandSpec {
+    pub name: String,
+    pub description: String,
+    pub params: Vec<FieldSpec>,
+    /// Fields of a successful response besides `success`
+    pub returns: Vec<FieldSpec>,
+    /// Payload and response are unknown, as for plugin commands
+    pub untyped: bool,
+}
+
+impl CommandSpec {
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            params: Vec::new(),
+            returns: Vec::new(),
+            untyped: false,
+        }
+    }
+
+    pub fn untyped(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self { untyped: true, ..Self::new(name, description) }
+    }
+
+    fn param(mut self, name: &'static str, ts_type: &'static str) -> Self {
+        self.params.push(FieldSpec { name, ts_type, optional: false });
+        self
+    }
+
+    fn optional(mut self, name: &'static str, ts_type: &'static str) -> Self {
+        self.params.push(FieldSpec { name, ts_type, optional: true });
+        self
+    }
+
+    fn returns(mut self, name: &'static str, ts_type: &'static str) -> Self {
+        self.returns.push(FieldSpec { name, ts_type, optional: false });
+        self
+    }
+
+    /// A response field only some successful responses carry
+    fn may_return(mut self, name: &'static str, ts_type: &'static str) -> Self {
+        self.returns.push(FieldSpec { name, ts_type, optional: true });
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventSpec {
+    pub name: String,
+    /// Name of a `TypeSpec` the payload is, instead of its own fields
+    pub payload_type: Option<&'static str>,
+    pub payload: Vec<FieldSpec>,
+}
+
+impl EventSpec {
+    fn new(event: AppEventType) -> Self {
+        Self { name: event.to_string(), payload_type: None, payload: Vec::new() }
+    }
+
+    fn of(mut self, type_name: &'static str) -> Self {
+        self.payload_type = Some(type_name);
+        self
+    }
+
+    fn field(mut self, name: &'static str, ts_type: &'static str) -> Self {
+        self.payload.push(FieldSpec { name, ts_type, optional: false });
+        self
+    }
+
+    fn optional(mut self, name: &'static str, ts_type: &'static str) -> Self {
+        self.payload.push(FieldSpec { name, ts_type, optional: true });
+        self
+    }
+}
+
+const ORGANIZE_MODE: &str = "'copy' | 'move'";
+const USER_ROLE: &str = "'admin' | 'user' | 'editor' | 'viewer'";
+const WINDOW_ACTION: &str =
+    "'created' | 'focused' | 'blurred' | 'minimized' | 'restored' | 'maximized' | 'resized' | 'moved' | 'closed'";
+const COUNTER_EVENT: &str = "'CounterCreated' | 'CounterIncremented' | 'CounterDecremented' | 'CounterReset' | 'CounterSet'";
+const DELIVERY_STATUS: &str = "'pending' | 'delivered' | 'failed'";
+const TASK_STATUS: &str = "'running' | 'completed' | 'failed' | 'cancelled'";
+const STATE_DOCUMENT: &str = "'users' | 'counters' | 'system'";
+
+/// Interfaces referenced by name from commands and events
+pub fn types() -> Vec<TypeSpec> {
+    vec![
+        TypeSpec::new("ProtocolOffer")
+            .field("version", "string")
+            .field("protocol_versions", "number[]")
+            .field("formats", "string[]")
+            .field("compression", "string[]")
+            .field("features", "string[]")
+            .field("build_features", "string[]")
+            .field("locales", "string[]"),
+        TypeSpec::new("FileUpload")
+            .field("upload_id", "string")
+            .field("path", "string")
+            .field("size", "number")
+            .field("chunk_size", "number"),
+        TypeSpec::new("UploadedFile")
+            .field("path", "string")
+            .field("size", "number")
+            .field("sha256", "string"),
+        TypeSpec::new("User")
+            .field("id", "number")
+            .field("name", "string")
+            .field("email", "string")
+            .field("role", "string")
+            .field("version", "number"),
+        TypeSpec::new("Counter")
+            .field("id", "string")
+            .field("value", "number")
+            .field("label", "string")
+            .field("created_at", "string")
+            .field("updated_at", "string")
+            .field("version", "number"),
+        TypeSpec::new("CounterEvent")
+            .field("sequence", "number")
+            .field("recorded_at", "string")
+            .field("type", COUNTER_EVENT)
+            .optional("label", "string")
+            .optional("value", "number"),
+        TypeSpec::new("Task")
+            .field("id", "string")
+            .field("name", "string")
+            .field("status", TASK_STATUS)
+            .field("progress", "number")
+            .field("message", "string | null")
+            .field("started_at", "string")
+            .field("finished_at", "string | null")
+            .field("result", "unknown")
+            .field("error", "string | null"),
+        TypeSpec::new("SystemInfo")
+            .field("platform", "string")
+            .field("arch", "string")
+            .field("os_name", "string | null")
+            .field("os_version", "string | null")
+            .field("kernel_version", "string | null")
+            .field("hostname", "string | null")
+            .field("cpu_model", "string | null")
+            .field("cpu_cores", "number")
+            .field("physical_cores", "number | null")
+            .field("total_memory_bytes", "number")
+            .field("used_memory_bytes", "number")
+            .field("memory_usage_percent", "number")
+            .field("uptime_secs", "number"),
+        TypeSpec::new("MetricsSample")
+            .field("timestamp", "string")
+            .field("cpu_percent", "number")
+            .field("per_core_percent", "number[]")
+            .field("total_memory_bytes", "number")
+            .field("used_memory_bytes", "number")
+            .field("total_swap_bytes", "number")
+            .field("used_swap_bytes", "number")
+            .field("app_process", "Record<string, unknown> | null")
+            .field("top_processes", "Record<string, unknown>[]"),
+        TypeSpec::new("JobRun")
+            .field("job", "string")
+            .field("trigger", "'schedule' | 'manual'")
+            .field("started_at", "string")
+            .field("finished_at", "string")
+            .field("attempts", "number")
+            .field("success", "boolean")
+            .field("message", "string | null")
+            .field("error", "string | null"),
+        TypeSpec::new("DialogFilter").field("name", "string").field("extensions", "string[]"),
+        TypeSpec::new("Watch").field("id", "string").field("path", "string"),
+        TypeSpec::new("PluginMetadata")
+            .field("id", "string")
+            .field("name", "string")
+            .field("version", "string")
+            .field("description", "string")
+            .field("author", "string")
+            .field("dependencies", "string[]"),
+        TypeSpec::new("ApiTokenClaims")
+            .field("sub", "string")
+            .field("name", "string")
+            .field("role", USER_ROLE)
+            .field("iss", "string")
+            .field("iat", "number")
+            .field("exp", "number"),
+        TypeSpec::new("Setting")
+            .field("namespace", "string")
+            .field("key", "string")
+            .field("value", "unknown")
+            .field("user_id", "number | null")
+            .field("updated_at", "string"),
+        TypeSpec::new("HistoryEntry")
+            .field("id", "number")
+            .field("label", "string")
+            .field("at", "string")
+            .field("operation", "{ kind: string } & Record<string, unknown>"),
+        TypeSpec::new("OutboxMessage")
+            .field("id", "string")
+            .field("event", "string")
+            .field("payload", "Record<string, unknown>")
+            .field("created_at", "string")
+            .field("attempts", "number")
+            .field("last_error", "string | null"),
+        TypeSpec::new("Webhook")
+            .field("id", "string")
+            .field("url", "string")
+            .field("events", "string[]")
+            .field("created_at", "string"),
+        TypeSpec::new("WebhookDelivery")
+            .field("id", "string")
+            .field("webhook_id", "string")
+            .field("event_id", "string")
+            .field("event", "string")
+            .field("payload", "unknown")
+            .field("status", DELIVERY_STATUS)
+            .field("attempts", "number")
+            .field("response_status", "number | null")
+            .field("last_error", "string | null")
+            .field("created_at", "string")
+            .field("next_attempt_at", "string | null")
+            .field("delivered_at", "string | null"),
+        TypeSpec::new("Release")
+            .field("version", "string")
+            .field("notes", "string | null")
+            .field("url", "string | null")
+            .field("published_at", "string | null"),
+        TypeSpec::new("UpdateStatus")
+            .field("current_version", "string")
+            .field("latest", "Release | null")
+            .field("update_available", "boolean")
+            .field("last_checked", "string | null")
+            .field("last_error", "string | null")
+            .field("installed", "string | null"),
+        TypeSpec::new("UsageDay")
+            .field("day", "string")
+            .field("total", "number")
+            .field("commands", "Record<string, number>")
+            .field("windows", "Record<string, number>"),
+        TypeSpec::new("UsageUpload")
+            .field("opted_in", "boolean")
+            .field("available", "boolean")
+            .field("uploaded_through", "string | null"),
+        TypeSpec::new("SessionResume")
+            .field("status", "'replayed' | 'refresh'")
+            .optional("events", "number")
+            .optional("reason", "'events_lost' | 'buffer_expired' | 'session_ended' | 'resume_disabled'"),
+        TypeSpec::new("UserSession")
+            .field("id", "string")
+            .field("client", "string | null")
+            .field("created_at", "string")
+            .field("last_seen_at", "string")
+            .field("expires_at", "string")
+            .field("locale", "string | null"),
+        TypeSpec::new("JsonPatchOperation")
+            .field("op", "'add' | 'remove' | 'replace' | 'move' | 'copy' | 'test'")
+            .field("path", "string")
+            .optional("from", "string")
+            .optional("value", "unknown"),
+        TypeSpec::new("ClientProfile")
+            .field("id", "string")
+            .field("name", "string | null")
+            .field("kind", "string")
+            .field("metadata", "unknown")
+            .field("session", "string | null")
+            .field("address", "string | null")
+            .field("joined_at", "string"),
+        TypeSpec::new("HotkeyBinding")
+            .field("accelerator", "string")
+            .field("action", "string | null")
+            .field("command", "string | null")
+            .field("payload", "Record<string, unknown>")
+            .field("source", "'config' | 'runtime'"),
+        TypeSpec::new("LaunchAtLogin")
+            .field("enabled", "boolean")
+            .field("preference", "boolean | null")
+            .field("supported", "boolean")
+            .field("location", "string | null"),
+    ]
+}
+
+/// Every built-in command: the connection-level `hello`, `session.current`, `session.set_locale`, `settings.watch`, `settings.unwatch`,
+/// `state.subscribe`, `state.unsubscribe`, `presence.register`, `file.download`, `watch_path`, `unwatch` and
+/// `list_watches`, then the order of
+/// `WebSocketHandler::commands`
+pub fn commands() -> Vec<CommandSpec> {
+    let dialog = |name: &str, description: &str| {
+        CommandSpec::new(name, description)
+            .optional("title", "string")
+            .optional("directory", "string")
+            .optional("filters", "DialogFilter[]")
+            .returns("cancelled", "boolean")
+            .returns("paths", "string[]")
+    };
+    let counter_change = |name: &str, description: &str| {
+        CommandSpec::new(name, description)
+            .optional("id", "string")
+            .returns("counter", "Counter")
+    };
+    let shell = |name: &str, description: &str, param: &'static str| {
+        CommandSpec::new(name, description).param(param, "string").returns("target", "string")
+    };
+
+    vec![
+        CommandSpec::new("hello", "Negotiate the protocol version, format, compression and features of this connection")
+            .param("protocol_version", "number")
+            .optional("min_protocol_version", "number")
+            .optional("formats", "string[]")
+            .optional("compression", "string[]")
+            .optional("features", "string[]")
+            .optional("resume_token", "string")
+            .optional("resume_from", "string")
+            .optional("locale", "string")
+            .returns("protocol_version", "number")
+            .returns("format", "string")
+            .returns("compression", "string | null")
+            .returns("features", "string[]")
+            .returns("ignored_features", "string[]")
+            .returns("server", "ProtocolOffer")
+            .returns("locale", "string")
+            .may_return("session", "UserSession")
+            .may_return("resume_token", "string")
+            .may_return("resume", "SessionResume"),
+        CommandSpec::new("session.current", "The session of this connection").returns("session", "UserSession | null"),
+        CommandSpec::new("session.set_locale", "Choose the locale of this session's messages; a missing or `null` locale restores the default")
+            .optional("locale", "string")
+            .returns("locale", "string")
+            .returns("session", "UserSession"),
+        CommandSpec::new("settings.watch", "Current values of a settings namespace; its `settings.changed` events follow")
+            .param("namespace", "string")
+            .optional("user_id", "number")
+            .returns("values", "Record<string, unknown>"),
+        CommandSpec::new("settings.unwatch", "Stop receiving `settings.changed` events of a namespace")
+            .param("namespace", "string")
+            .returns("watching", "boolean"),
+        CommandSpec::new("state.subscribe", "A state document and its version; its `state.patch` events follow")
+            .param("document", STATE_DOCUMENT)
+            .returns("document", STATE_DOCUMENT)
+            .returns("version", "number")
+            .returns("state", "unknown"),
+        CommandSpec::new("state.unsubscribe", "Stop receiving `state.patch` events of a document")
+            .param("document", STATE_DOCUMENT)
+            .returns("subscribed", "boolean"),
+        CommandSpec::new("presence.register", "Make this connection present; announced as `presence.joined`, and `presence.left` once it closes")
+            .optional("name", "string")
+            .optional("kind", "string")
+            .optional("metadata", "unknown")
+            .returns("client", "ClientProfile"),
+        CommandSpec::new(
+            "file.download",
+            "Stream a file from `[file_transfer] allowed_dirs` as `file.download_chunk` binary frames",
+        )
+        .param("path", "string")
+        .returns("download_id", "string")
+        .returns("path", "string")
+        .returns("size", "number")
+        .returns("chunk_size", "number"),
+        CommandSpec::new("watch_path", "Watch a directory; its changes arrive as `fs.changed` events on this connection only")
+            .param("path", "string")
+            .returns("watch", "Watch"),
+        CommandSpec::new("unwatch", "Stop a watch of this connection by id or path")
+            .optional("id", "string")
+            .optional("path", "string")
+            .returns("watch", "Watch"),
+        CommandSpec::new("list_watches", "Watches of this connection").returns("watches", "Watch[]"),
+        CommandSpec::new("get_users", "Users, all of them by id unless filtered, sorted or paged")
+            .optional("role", USER_ROLE)
+            .optional("search", "string")
+            .optional("sort", "'id' | 'name' | 'email' | 'role'")
+            .optional("order", "'asc' | 'desc'")
+            .optional("limit", "number")
+            .optional("offset", "number")
+            .returns("data", "User[]"),
+        CommandSpec::new("update_user", "Update a user; `version` must match the stored one")
+            .param("id", "number")
+            .param("version", "number")
+            .optional("name", "string")
+            .optional("email", "string")
+            .optional("role", USER_ROLE)
+            .returns("data", "User"),
+        CommandSpec::new("create_user", "Add a user; the role defaults to `user`")
+            .param("name", "string")
+            .param("email", "string")
+            .optional("role", USER_ROLE)
+            .returns("data", "User"),
+        CommandSpec::new("delete_user", "Delete a user; `version` must match the stored one")
+            .param("id", "number")
+            .param("version", "number")
+            .returns("data", "User"),
+        CommandSpec::new("get_db_stats", "Database statistics")
+            .returns("stats", "{ users: number; tables: string[] }"),
+        CommandSpec::new("presence.list", "Clients that registered with `presence.register`, longest present first")
+            .returns("clients", "ClientProfile[]"),
+        CommandSpec::new("ui.ready", "Tell the backend the UI is up; answered by a `backend.connected` event")
+            .returns("message", "string"),
+        CommandSpec::new("window.state.change", "Report a change of a native window")
+            .param("id", "string")
+            .param("action", WINDOW_ACTION)
+            .optional("windowTitle", "string")
+            .optional("x", "number")
+            .optional("y", "number")
+            .optional("width", "number")
+            .optional("height", "number")
+            .optional("maximized", "boolean")
+            .returns("message", "string"),
+        CommandSpec::new("get_system_info", "Operating system, CPU and memory of the host")
+            .returns("data", "SystemInfo")
+            .returns("app_version", "string"),
+        CommandSpec::new("clipboard.read_text", "Read the clipboard as text").returns("text", "string"),
+        CommandSpec::new("clipboard.write_text", "Write text to the clipboard")
+            .param("text", "string")
+            .returns("length", "number"),
+        dialog("dialog.open_file", "Native file picker").optional("multiple", "boolean"),
+        dialog("dialog.open_folder", "Native folder picker").optional("multiple", "boolean"),
+        dialog("dialog.save_file", "Native save dialog").optional("file_name", "string"),
+        CommandSpec::new("counter.create", "Create a named counter")
+            .param("label", "string")
+            .optional("id", "string")
+            .returns("counter", "Counter"),
+        CommandSpec::new("counter.list", "All stored counters").returns("counters", "Counter[]"),
+        CommandSpec::new("counter.events", "Stored events of an event-sourced counter, oldest first")
+            .optional("id", "string")
+            .returns("events", "CounterEvent[]"),
+        counter_change("counter.increment", "Increment a counter"),
+        counter_change("counter.decrement", "Decrement a counter"),
+        counter_change("counter.reset", "Reset a counter to zero"),
+        CommandSpec::new("task.list", "Background tasks, newest first").returns("tasks", "Task[]"),
+        CommandSpec::new("task.get", "A background task by id")
+            .param("id", "string")
+            .returns("task", "Task"),
+        CommandSpec::new("task.cancel", "Cancel a running background task")
+            .param("id", "string")
+            .returns("id", "string"),
+        CommandSpec::new("images.organize", "Sort images into YYYY/MM folders by capture date")
+            .param("source", "string")
+            .optional("destination", "string")
+            .optional("mode", ORGANIZE_MODE)
+            .optional("dry_run", "boolean")
+            .optional("recursive", "boolean")
+            .optional("background", "boolean")
+            .may_return("report", "Record<string, unknown>")
+            .may_return("task_id", "string"),
+        CommandSpec::new("metrics.start", "Start the live metrics sampler")
+            .optional("interval_secs", "number")
+            .returns("started", "boolean")
+            .returns("status", "Record<string, unknown>"),
+        CommandSpec::new("metrics.stop", "Stop the live metrics sampler")
+            .returns("stopped", "boolean")
+            .returns("status", "Record<string, unknown>"),
+        CommandSpec::new("metrics.status", "State of the live metrics sampler")
+            .returns("status", "Record<string, unknown>"),
+        CommandSpec::new("scheduler.list", "Registered background jobs").returns("jobs", "Record<string, unknown>[]"),
+        CommandSpec::new("scheduler.history", "Recent job runs")
+            .optional("job", "string")
+            .optional("limit", "number")
+            .returns("runs", "JobRun[]"),
+        CommandSpec::new("scheduler.run_now", "Run a job immediately")
+            .param("job", "string")
+            .returns("run", "JobRun"),
+        shell("shell.open_url", "Open a URL in the default browser", "url"),
+        shell("shell.open_path", "Open a file with its default application", "path"),
+        shell("shell.reveal", "Show a file in the system file manager", "path"),
+        CommandSpec::new("peers.list", "Other instances found on the LAN")
+            .returns("advertising", "boolean")
+            .returns("instance_id", "string")
+            .returns("peers", "Record<string, unknown>[]"),
+        CommandSpec::new("telemetry.status", "Error reporting state").returns("telemetry", "Record<string, unknown>"),
+        CommandSpec::new("telemetry.set_consent", "Grant or revoke consent to error reporting")
+            .param("granted", "boolean")
+            .returns("telemetry", "Record<string, unknown>"),
+        CommandSpec::new("db.rekey", "Re-encrypt the database under a new keychain key").optional("admin_token", "string"),
+        CommandSpec::new("session.list", "Unexpired sessions, most recently active first")
+            .optional("admin_token", "string")
+            .returns("sessions", "UserSession[]"),
+        CommandSpec::new("session.revoke", "End a session and close its connection")
+            .optional("admin_token", "string")
+            .param("id", "string"),
+        CommandSpec::new("token.issue", "Sign an API token (JWT) for a user, for gRPC and the DevTools API")
+            .optional("admin_token", "string")
+            .param("user_id", "number")
+            .optional("ttl_mins", "number")
+            .returns("token", "string")
+            .returns("token_type", "'Bearer'")
+            .returns("expires_at", "string")
+            .returns("claims", "ApiTokenClaims"),
+        CommandSpec::new("auth.request_password_reset", "Mail a password reset token; succeeds for unknown addresses too")
+            .param("email", "string"),
+        CommandSpec::new("auth.reset_password", "Set a new password with a mailed reset token")
+            .param("token", "string")
+            .param("password", "string")
+            .returns("user_id", "number"),
+        CommandSpec::new("auth.request_email_verification", "Mail an email verification token to a user")
+            .param("user_id", "number"),
+        CommandSpec::new("auth.verify_email", "Mark a user's email verified with a mailed token")
+            .param("token", "string")
+            .returns("user_id", "number"),
+        CommandSpec::new("settings.get", "One setting, or every value of a namespace; a user's values fall back to global ones")
+            .param("namespace", "string")
+            .optional("key", "string")
+            .optional("user_id", "number")
+            .may_return("value", "unknown")
+            .may_return("setting", "Setting | null")
+            .may_return("values", "Record<string, unknown>"),
+        CommandSpec::new("settings.set", "Store a setting, globally or for a user; a missing or `null` value removes it")
+            .param("namespace", "string")
+            .param("key", "string")
+            .optional("value", "unknown")
+            .optional("user_id", "number")
+            .returns("setting", "Setting | null"),
+        CommandSpec::new("flags.get_all", "Every feature flag and the runtime overrides behind them, for everyone or a user")
+            .optional("user_id", "number")
+            .returns("flags", "Record<string, boolean>")
+            .returns("overrides", "Record<string, boolean>"),
+        CommandSpec::new("flags.set", "Override a feature flag, globally or for a user; a missing or `null` value removes the override")
+            .param("name", "string")
+            .optional("enabled", "boolean | null")
+            .optional("user_id", "number")
+            .returns("name", "string")
+            .returns("enabled", "boolean"),
+        CommandSpec::new("undo", "Revert the newest user or counter change")
+            .returns("entry", "HistoryEntry")
+            .returns("result", "Record<string, unknown>"),
+        CommandSpec::new("redo", "Apply the newest undone change again")
+            .returns("entry", "HistoryEntry")
+            .returns("result", "Record<string, unknown>"),
+        CommandSpec::new("history.list", "Journaled changes, newest first, and those `redo` would apply")
+            .returns("undo", "HistoryEntry[]")
+            .returns("redo", "HistoryEntry[]"),
+        CommandSpec::new("outbox.status", "Database events not yet published, and the oldest of them")
+            .returns("pending", "number")
+            .returns("failing", "number")
+            .returns("oldest", "OutboxMessage | null"),
+        CommandSpec::new("analytics.summary", "This installation's own usage counts per day and whether they are uploaded")
+            .optional("days", "number")
+            .returns("enabled", "boolean")
+            .returns("days", "UsageDay[]")
+            .returns("upload", "UsageUpload"),
+        CommandSpec::new("broker.status", "State of the bridge to an external message broker")
+            .returns("running", "boolean")
+            .returns("kind", "string | null")
+            .returns("url", "string | null")
+            .returns("connected", "boolean")
+            .returns("publish", "string[] | null")
+            .returns("subscribe", "string[] | null")
+            .returns("published", "number")
+            .returns("received", "number")
+            .returns("last_error", "string | null"),
+        CommandSpec::new("webhooks.register", "Send matching events to a URL as signed POST requests")
+            .optional("admin_token", "string")
+            .param("url", "string")
+            .param("events", "string[]")
+            .optional("secret", "string")
+            .returns("webhook", "Webhook")
+            .returns("secret", "string"),
+        CommandSpec::new("webhooks.list", "Registered webhooks")
+            .optional("admin_token", "string")
+            .returns("webhooks", "Webhook[]"),
+        CommandSpec::new("webhooks.remove", "Remove a webhook and its delivery log")
+            .optional("admin_token", "string")
+            .param("id", "string"),
+        CommandSpec::new("webhooks.deliveries", "Webhook deliveries, newest first")
+            .optional("admin_token", "string")
+            .optional("webhook_id", "string")
+            .optional("status", DELIVERY_STATUS)
+            .optional("limit", "number")
+            .returns("deliveries", "WebhookDelivery[]"),
+        CommandSpec::new("collab.sync", "What a shared document holds beyond a state vector, as a base64 Yjs update")
+            .param("document", "string")
+            .optional("state_vector", "string")
+            .returns("document", "string")
+            .returns("update", "string")
+            .returns("state_vector", "string"),
+        CommandSpec::new("collab.update", "Merge a base64 Yjs update into a shared document; announced as `collab.update`")
+            .param("document", "string")
+            .param("update", "string")
+            .optional("origin", "string")
+            .returns("document", "string")
+            .returns("state_vector", "string"),
+        CommandSpec::new("hotkeys.list", "Global keyboard shortcuts registered with the OS").returns("bindings", "HotkeyBinding[]"),
+        CommandSpec::new("hotkeys.register", "Register a global keyboard shortcut; each press emits `hotkey.pressed`")
+            .param("accelerator", "string")
+            .optional("action", "string")
+            .optional("command", "string")
+            .optional("payload", "Record<string, unknown>")
+            .returns("binding", "HotkeyBinding"),
+        CommandSpec::new("hotkeys.unregister", "Release a global keyboard shortcut")
+            .param("accelerator", "string")
+            .returns("binding", "HotkeyBinding"),
+        CommandSpec::new("capture.window", "Ask the user to allow a screenshot of a window; announced as `capture.requested`")
+            .optional("title", "string")
+            .returns("request_id", "string")
+            .returns("kind", "'window' | 'screen'")
+            .returns("target", "string")
+            .returns("expires_in_secs", "number"),
+        CommandSpec::new("capture.screen", "Ask the user to allow a screenshot of a monitor; announced as `capture.requested`")
+            .optional("monitor", "string")
+            .returns("request_id", "string")
+            .returns("kind", "'window' | 'screen'")
+            .returns("target", "string")
+            .returns("expires_in_secs", "number"),
+        CommandSpec::new("capture.confirm", "Allow or refuse a capture request; allowing saves the screenshot")
+            .param("request_id", "string")
+            .param("allow", "boolean")
+            .returns("request_id", "string")
+            .returns("kind", "'window' | 'screen'")
+            .returns("target", "string")
+            .returns("allowed", "boolean")
+            .may_return("path", "string")
+            .may_return("width", "number")
+            .may_return("height", "number")
+            .may_return("thumbnail", "string"),
+        CommandSpec::new("sound.play", "Play a built-in UI sound at the stored volume, unless muted")
+            .param("name", "'click' | 'success' | 'error' | 'notification'")
+            .optional("volume", "number")
+            .returns("played", "boolean"),
+        CommandSpec::new("sound.list", "Built-in UI sounds, the volume and whether they are muted")
+            .returns("sounds", "string[]")
+            .returns("volume", "number")
+            .returns("muted", "boolean"),
+        CommandSpec::new("sound.set_volume", "Store the volume of UI sounds, from 0 to 1")
+            .param("volume", "number")
+            .returns("sounds", "string[]")
+            .returns("volume", "number")
+            .returns("muted", "boolean"),
+        CommandSpec::new("sound.set_muted", "Mute or unmute every UI sound")
+            .param("muted", "boolean")
+            .returns("sounds", "string[]")
+            .returns("volume", "number")
+            .returns("muted", "boolean"),
+        CommandSpec::new("os.integration", "OS integration settings: whether the app launches at login")
+            .returns("platform", "string")
+            .returns("launch_at_login", "LaunchAtLogin"),
+        CommandSpec::new("os.set_launch_at_login", "Add or remove the login entry and remember the choice")
+            .param("enabled", "boolean")
+            .returns("platform", "string")
+            .returns("launch_at_login", "LaunchAtLogin"),
+        CommandSpec::new("document.export_pdf", "Print HTML to a PDF the user picks a place for; announced as `document.exported`")
+            .param("html", "string")
+            .optional("base_url", "string")
+            .optional("file_name", "string")
+            .optional("title", "string")
+            .returns("cancelled", "boolean")
+            .may_return("export_id", "string")
+            .may_return("path", "string"),
+        CommandSpec::new("update.status", "Result of the last update check").returns("status", "UpdateStatus"),
+        CommandSpec::new("update.check", "Look for a newer release now").returns("status", "UpdateStatus"),
+        CommandSpec::new("update.install", "Download, verify and install the newer release; it runs after a restart")
+            .optional("admin_token", "string")
+            .returns("version", "string")
+            .returns("restart_required", "boolean"),
+        CommandSpec::new("plugins.list", "Loaded plugins, their initialization order and services")
+            .returns("plugins", "PluginMetadata[]")
+            .returns("initialization_order", "string[]")
+            .returns("services", "Record<string, string>"),
+        CommandSpec::new("schemas.get", "JSON Schemas of the WebSocket envelope, event payloads and DTOs")
+            .optional("name", "string")
+            .may_return("schemas", "Record<string, unknown>")
+            .may_return("name", "string")
+            .may_return("schema", "Record<string, unknown>"),
+        CommandSpec::new("file.upload_begin", "Start an upload; the data follows as `file.upload_chunk` binary frames")
+            .param("name", "string")
+            .param("size", "number")
+            .optional("directory", "string")
+            .optional("overwrite", "boolean")
+            .optional("sha256", "string")
+            .returns("upload", "FileUpload"),
+        CommandSpec::new("file.upload_end", "Verify a fully received upload and move it into place")
+            .param("upload_id", "string")
+            .optional("overwrite", "boolean")
+            .returns("file", "UploadedFile"),
+        CommandSpec::new("sysinfo.get", "Host details from the system_info plugin")
+            .returns("data", "SystemInfo")
+            .returns("app_version", "string"),
+        CommandSpec::new("window.open", "Open a window showing a URL or a path of the app UI")
+            .optional("url", "string")
+            .optional("path", "string")
+            .optional("title", "string")
+            .returns("id", "number")
+            .returns("url", "string"),
+        CommandSpec::new("window.close", "Close a window by id")
+            .param("id", "number")
+            .returns("id", "number"),
+        CommandSpec::new("window.set_title", "Change the title of a window")
+            .param("id", "number")
+            .param("title", "string")
+            .returns("id", "number")
+            .returns("title", "string"),
+    ]
+}
+
+/// Payload of every `AppEventType`
+pub fn events() -> Vec<EventSpec> {
+    AppEventType::ALL
+        .iter()
+        .map(|event| {
+            let spec = EventSpec::new(*event);
+            match event {
+                AppEventType::UserLogin | AppEventType::UserLogout => spec.optional("user_id", "number"),
+                AppEventType::DataChanged => spec
+                    .field("operation", "string")
+                    .field("table", "string")
+                    .optional("count", "number")
+                    .optional("user", "User"),
+                AppEventType::CounterIncremented => spec.field("id", "string").field("value", "number"),
+                AppEventType::CounterChanged => spec
+                    .field("operation", "'increment' | 'decrement' | 'reset' | 'create' | 'set' | 'restore' | 'delete'")
+                    .field("counter", "Counter"),
+                AppEventType::DatabaseOperation => spec.field("operation", "string").optional("count", "number"),
+                AppEventType::DatabaseActivity => spec
+                    .field("topic", "string")
+                    .field("count", "number")
+                    .field("counts", "Record<string, number>")
+                    .field("from", "string")
+                    .field("to", "string"),
+                AppEventType::SystemHealthCheck => spec.optional("type", "string").optional("system", "SystemInfo"),
+                AppEventType::FrontendConnected | AppEventType::FrontendDisconnected => spec,
+                AppEventType::WindowStateChanged => spec
+                    .field("id", "string")
+                    .field("action", WINDOW_ACTION)
+                    .optional("title", "string"),
+                AppEventType::ClipboardAccessed => spec
+                    .field("operation", "'read' | 'write'")
+                    .field("length", "number")
+                    .field("success", "boolean")
+                    .field("error", "string | null"),
+                AppEventType::PluginAccessDenied => spec
+                    .field("plugin", "string")
+                    .field("operation", "string")
+                    .field("permission", "string")
+                    .field("declared", "boolean"),
+                AppEventType::SystemMetrics => spec.of("MetricsSample"),
+                AppEventType::JobCompleted => spec.of("JobRun"),
+                AppEventType::FsChanged => spec
+                    .field("watch_id", "string")
+                    .field("path", "string")
+                    .field("created", "string[]")
+                    .field("modified", "string[]")
+                    .field("removed", "string[]"),
+                AppEventType::ImagesOrganizeProgress => spec
+                    .field("processed", "number")
+                    .field("total", "number")
+                    .field("current", "string")
+                    .field("dry_run", "boolean"),
+                AppEventType::FileTransferProgress => spec
+                    .field("transfer_id", "string")
+                    .field("direction", "'upload' | 'download'")
+                    .field("name", "string")
+                    .field("bytes", "number")
+                    .field("total", "number"),
+                AppEventType::FileUploaded => spec
+                    .field("path", "string")
+                    .field("name", "string")
+                    .field("size", "number")
+                    .field("sha256", "string")
+                    .field("transport", "'websocket' | 'http'"),
+                AppEventType::SettingsChanged => spec
+                    .field("namespace", "string")
+                    .field("key", "string")
+                    .field("value", "unknown")
+                    .field("user_id", "number | null"),
+                AppEventType::TaskProgress => spec
+                    .field("id", "string")
+                    .field("name", "string")
+                    .field("progress", "number")
+                    .field("message", "string | null"),
+                AppEventType::TaskCompleted | AppEventType::TaskFailed | AppEventType::TaskCancelled => spec.of("Task"),
+                AppEventType::UpdateAvailable => spec
+                    .field("current_version", "string")
+                    .field("version", "string")
+                    .field("notes", "string | null")
+                    .field("url", "string | null")
+                    .field("published_at", "string | null")
+                    .field("installable", "boolean"),
+                AppEventType::UpdateInstalled => spec.field("version", "string").field("restart_required", "boolean"),
+                AppEventType::FlagsChanged => spec
+                    .field("name", "string")
+                    .field("enabled", "boolean")
+                    .field("override", "boolean | null")
+                    .field("user_id", "number | null"),
+                AppEventType::ChaosInjected => spec
+                    .field("fault", "'db_delay' | 'ws_drop' | 'handler_error'")
+                    .optional("command", "string")
+                    .optional("delay_ms", "number")
+                    .optional("bytes", "number"),
+                AppEventType::StatePatch => spec
+                    .field("document", STATE_DOCUMENT)
+                    .field("version", "number")
+                    .field("patch", "JsonPatchOperation[]"),
+                AppEventType::CommandAccepted => spec.field("mutation_id", "string").field("command", "string"),
+                AppEventType::CommandApplied => spec
+                    .field("mutation_id", "string")
+                    .field("command", "string")
+                    .field("result", "Record<string, unknown>")
+                    .optional("document", STATE_DOCUMENT)
+                    .optional("version", "number")
+                    .optional("state", "unknown"),
+                AppEventType::CommandRejected => spec
+                    .field("mutation_id", "string")
+                    .field("command", "string")
+                    .field("error", "CommandErrorInfo")
+                    .optional("document", STATE_DOCUMENT)
+                    .optional("version", "number")
+                    .optional("state", "unknown"),
+                AppEventType::PresenceJoined | AppEventType::PresenceLeft => spec.of("ClientProfile"),
+                AppEventType::CollabUpdate => spec
+                    .field("document", "string")
+                    .field("update", "string")
+                    .optional("origin", "string | null"),
+                AppEventType::HotkeyPressed => spec
+                    .field("accelerator", "string")
+                    .field("action", "string | null")
+                    .field("command", "string | null")
+                    .field("result", "Record<string, unknown> | null"),
+                AppEventType::DocumentExported => spec
+                    .field("export_id", "string")
+                    .field("path", "string")
+                    .field("success", "boolean")
+                    .field("bytes", "number | null")
+                    .field("error", "string | null")
+                    .field("duration_ms", "number"),
+                AppEventType::CaptureRequested => spec
+                    .field("request_id", "string")
+                    .field("kind", "'window' | 'screen'")
+                    .field("target", "string")
+                    .field("expires_in_secs", "number"),
+                AppEventType::CaptureCompleted => spec
+                    .field("request_id", "string")
+                    .field("kind", "'window' | 'screen'")
+                    .field("target", "string")
+                    .field("allowed", "boolean")
+                    .optional("path", "string")
+                    .optional("width", "number")
+                    .optional("height", "number")
+                    .optional("error", "string"),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::viewmodel::command_schemas;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_names_are_unique() {
+        let commands = commands();
+        let names: HashSet<&str> = commands.iter().map(|command| command.name.as_str()).collect();
+        assert_eq!(names.len(), commands.len());
+        let types: HashSet<&str> = types().iter().map(|spec| spec.name).collect();
+        assert!(types.contains("Counter"));
+        assert_eq!(events().len(), AppEventType::ALL.len());
+    }
+
+    #[test]
+    fn test_required_params_match_schemas() {
+        for command in commands() {
+            // Only `Required` rules fail for an empty payload
+            let required: Vec<String> = match command_schemas::validate(&command.name, &serde_json::json!({})) {
+                Ok(()) => continue,
+                Err(e) => e.context["errors"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|error| error["field"].as_str().unwrap().to_string())
+                    .collect(),
+            };
+            let declared: Vec<String> = command
+                .params
+                .iter()
+                .filter(|param| !param.optional)
+                .map(|param| param.name.to_string())
+                .collect();
+            assert_eq!(required, declared, "{}", command.name);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Payload rules for WebSocket commands
+//!
+//! Commands listed here are validated before they reach their handler;
+//! commands without a schema are passed through unchanged.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use crate::core::application::{PayloadSchema, Rule};
+use crate::error_handling::AppResult;
+use crate::infrastructure::state_sync;
+use crate::model::core::USER_SORT_FIELDS;
+
+const USER_ROLES: &[&str] = &["admin", "user", "editor", "viewer"];
+const ORGANIZE_MODES: &[&str] = &["copy", "move"];
+const DELIVERY_STATUSES: &[&str] = &["pending", "delivered", "failed"];
+const SORT_ORDERS: &[&str] = &["asc", "desc"];
+
+fn schemas() -> &'static HashMap<&'static str, PayloadSchema> {
+    static SCHEMAS: OnceLock<HashMap<&'static str, PayloadSchema>> = OnceLock::new();
+    SCHEMAS.get_or_init(|| {
+        let task_id = PayloadSchema::new().field("id", [Rule::Required]);
+        let path = PayloadSchema::new().field("path", [Rule::Required]);
+        let settings_namespace = PayloadSchema::new()
+            .field("namespace", [Rule::Required, Rule::Length { min: 1, max: 128 }])
+            .field("user_id", [Rule::Integer]);
+        let state_document = PayloadSchema::new().field("document", [Rule::Required, Rule::OneOf(state_sync::DOCUMENTS)]);
+        let collab_document = PayloadSchema::new().field("document", [Rule::Required, Rule::Length { min: 1, max: 128 }]);
+
+        HashMap::from([
+            (
+                "get_users",
+                PayloadSchema::new()
+                    .field("role", [Rule::OneOf(USER_ROLES)])
+                    .field("search", [Rule::Length { min: 0, max: 100 }])
+                    .field("sort", [Rule::OneOf(USER_SORT_FIELDS)])
+                    .field("order", [Rule::OneOf(SORT_ORDERS)])
+                    .field("limit", [Rule::Integer])
+                    .field("offset", [Rule::Integer]),
+            ),
+            (
+                "update_user",
+                PayloadSchema::new()
+                    .field("id", [Rule::Required, Rule::Integer])
+                    .field("version", [Rule::Required, Rule::Integer])
+                    .field("name", [Rule::Length { min: 1, max: 100 }])
+                    .field("email", [Rule::Email])
+                    .field("role", [Rule::OneOf(USER_ROLES)]),
+            ),
+            (
+                "create_user",
+                PayloadSchema::new()
+                    .field("name", [Rule::Required, Rule::Length { min: 1, max: 100 }])
+                    .field("email", [Rule::Required, Rule::Email])
+                    .field("role", [Rule::OneOf(USER_ROLES)]),
+            ),
+            (
+                "delete_user",
+                PayloadSchema::new()
+                    .field("id", [Rule::Required, Rule::Integer])
+                    .field("version", [Rule::Required, Rule::Integer]),
+            ),
+            (
+                "counter.create",
+                PayloadSchema::new().field("label", [Rule::Required, Rule::Length { min: 1, max: 100 }]),
+            ),
+            (
+                "hello",
+                PayloadSchema::new()
+                    .field("protocol_version", [Rule::Required, Rule::Integer])
+                    .field("min_protocol_version", [Rule::Integer])
+                    .field("session", [Rule::Length { min: 1, max: 64 }])
+                    .field("resume_from", [Rule::Length { min: 1, max: 64 }])
+                    .field("locale", [Rule::Length { min: 2, max: 35 }]),
+            ),
+            ("session.set_locale", PayloadSchema::new().field("locale", [Rule::Length { min: 2, max: 35 }])),
+            (
+                "file.upload_begin",
+                PayloadSchema::new()
+                    .field("name", [Rule::Required, Rule::Length { min: 1, max: 255 }])
+                    .field("size", [Rule::Required, Rule::Integer])
+                    .field("overwrite", [Rule::Boolean])
+                    .field("sha256", [Rule::Length { min: 64, max: 64 }]),
+            ),
+            (
+                "file.upload_chunk",
+                PayloadSchema::new()
+                    .field("upload_id", [Rule::Required])
+                    .field("offset", [Rule::Required, Rule::Integer])
+                    .field("sha256", [Rule::Required, Rule::Length { min: 64, max: 64 }]),
+            ),
+            (
+                "file.upload_end",
+                PayloadSchema::new()
+                    .field("upload_id", [Rule::Required])
+                    .field("overwrite", [Rule::Boolean]),
+            ),
+            ("file.download", path.clone()),
+            ("shell.open_url", PayloadSchema::new().field("url", [Rule::Required])),
+            ("shell.open_path", path.clone()),
+            ("shell.reveal", path.clone()),
+            ("watch_path", path),
+            ("metrics.start", PayloadSchema::new().field("interval_secs", [Rule::Integer])),
+            ("scheduler.run_now", PayloadSchema::new().field("job", [Rule::Required])),
+            ("settings.get", settings_namespace.clone().field("key", [Rule::Length { min: 1, max: 128 }])),
+            (
+                "settings.set",
+                settings_namespace
+                    .clone()
+                    .field("key", [Rule::Required, Rule::Length { min: 1, max: 128 }]),
+            ),
+            ("settings.watch", settings_namespace.clone()),
+            ("settings.unwatch", settings_namespace),
+            ("state.subscribe", state_document.clone()),
+            ("state.unsubscribe", state_document),
+            (
+                "presence.register",
+                PayloadSchema::new()
+                    .field("name", [Rule::Length { min: 1, max: 128 }])
+                    .field("kind", [Rule::Length { min: 1, max: 64 }]),
+            ),
+            (
+                "hotkeys.register",
+                PayloadSchema::new()
+                    .field("accelerator", [Rule::Required, Rule::Length { min: 1, max: 64 }])
+                    .field("action", [Rule::Length { min: 1, max: 64 }])
+                    .field("command", [Rule::Length { min: 1, max: 128 }]),
+            ),
+            (
+                "hotkeys.unregister",
+                PayloadSchema::new().field("accelerator", [Rule::Required, Rule::Length { min: 1, max: 64 }]),
+            ),
+            (
+                "document.export_pdf",
+                PayloadSchema::new()
+                    .field("html", [Rule::Required])
+                    .field("base_url", [Rule::Length { min: 1, max: 2048 }])
+                    .field("file_name", [Rule::Length { min: 1, max: 255 }])
+                    .field("title", [Rule::Length { min: 1, max: 128 }]),
+            ),
+            ("capture.window", PayloadSchema::new().field("title", [Rule::Length { min: 1, max: 256 }])),
+            ("capture.screen", PayloadSchema::new().field("monitor", [Rule::Length { min: 1, max: 128 }])),
+            (
+                "capture.confirm",
+                PayloadSchema::new()
+                    .field("request_id", [Rule::Required, Rule::Length { min: 1, max: 64 }])
+                    .field("allow", [Rule::Required, Rule::Boolean]),
+            ),
+            (
+                "sound.play",
+                PayloadSchema::new()
+                    .field("name", [Rule::Required, Rule::Length { min: 1, max: 64 }])
+                    .field("volume", [Rule::Range { min: 0.0, max: 1.0 }]),
+            ),
+            ("sound.set_volume", PayloadSchema::new().field("volume", [Rule::Required, Rule::Range { min: 0.0, max: 1.0 }])),
+            ("sound.set_muted", PayloadSchema::new().field("muted", [Rule::Required, Rule::Boolean])),
+            ("os.set_launch_at_login", PayloadSchema::new().field("enabled", [Rule::Required, Rule::Boolean])),
+            ("collab.sync", collab_document.clone()),
+            (
+                "collab.update",
+                collab_document.field("update", [Rule::Required]).field("origin", [Rule::Length { min: 1, max: 128 }]),
+            ),
+            ("flags.get_all", PayloadSchema::new().field("user_id", [Rule::Integer])),
+            ("analytics.summary", PayloadSchema::new().field("days", [Rule::Integer])),
+            (
+                "flags.set",
+                PayloadSchema::new()
+                    .field("name", [Rule::Required, Rule::Length { min: 1, max: 128 }])
+                    .field("enabled", [Rule::Boolean])
+                    .field("user_id", [Rule::Integer]),
+            ),
+            ("session.revoke", PayloadSchema::new().field("id", [Rule::Required])),
+            (
+                "token.issue",
+                PayloadSchema::new()
+                    .field("user_id", [Rule::Required, Rule::Integer])
+                    .field("ttl_mins", [Rule::Integer]),
+            ),
+            (
+                "auth.request_password_reset",
+                PayloadSchema::new().field("email", [Rule::Required, Rule::Email]),
+            ),
+            (
+                "auth.reset_password",
+                PayloadSchema::new()
+                    .field("token", [Rule::Required, Rule::Length { min: 64, max: 64 }])
+                    .field("password", [Rule::Required, Rule::Length { min: 1, max: 1024 }]),
+            ),
+            (
+                "auth.request_email_verification",
+                PayloadSchema::new().field("user_id", [Rule::Required, Rule::Integer]),
+            ),
+            (
+                "auth.verify_email",
+                PayloadSchema::new().field("token", [Rule::Required, Rule::Length { min: 64, max: 64 }]),
+            ),
+            (
+                "images.organize",
+                PayloadSchema::new()
+                    .field("source", [Rule::Required])
+                    .field("mode", [Rule::OneOf(ORGANIZE_MODES)])
+                    .field("dry_run", [Rule::Boolean])
+                    .field("recursive", [Rule::Boolean])
+                    .field("background", [Rule::Boolean]),
+            ),
+            ("task.get", task_id.clone()),
+            ("task.cancel", task_id),
+            (
+                "telemetry.set_consent",
+                PayloadSchema::new().field("granted", [Rule::Required, Rule::Boolean]),
+            ),
+            (
+                "webhooks.register",
+                PayloadSchema::new()
+                    .field("url", [Rule::Required, Rule::Length { min: 1, max: 2048 }])
+                    .field("events", [Rule::Required])
+                    .field("secret", [Rule::Length { min: 16, max: 256 }]),
+            ),
+            ("webhooks.remove", PayloadSchema::new().field("id", [Rule::Required])),
+            (
+                "webhooks.deliveries",
+                PayloadSchema::new()
+                    .field("status", [Rule::OneOf(DELIVERY_STATUSES)])
+                    .field("limit", [Rule::Integer]),
+            ),
+        ])
+    })
+}
+
+/// The rules declared for `command` as a JSON Schema, if any
+pub fn payload_schema(command: &str) -> Option<Value> {
+    schemas().get(command).map(PayloadSchema::json_schema)
+}
+
+/// Check `payload` against the schema declared for `command`, if any
+pub fn validate(command: &str, payload: &Value) -> AppResult<()> {
+    match schemas().get(command) {
+        Some(schema) => schema.validate(command, payload),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,178 @@
+//! Commands registered with a typed request and response
+//!
+//! `CommandRegistry::register` ties a command name to an async handler that
+//! takes its request struct and returns its response struct. The registry
+//! deserializes the payload into the request, so a field of the wrong type
+//! is answered with `ValidationFailed` before the handler runs, serializes
+//! the response as `{ "success": true, ...its fields }`, and turns an
+//! `AppError` into the standard error response. The rules of
+//! `command_schemas` still apply first, as to every command.
+//!
+//! Feature modules that predate the registry answer several commands from
+//! one `handle_command(name, payload)` with a complete response;
+//! `register_module` routes their commands to it, so that every command
+//! `WebSocketHandler::dispatch` answers is found here. New commands should
+//! use `register` and types of their own.
+
+use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tracing::warn;
+use crate::error_handling::{AppError, AppResult, ErrorCode, GlobalErrorHandler};
+
+type Handler = Arc<dyn Fn(Value) -> BoxFuture<'static, AppResult<Value>> + Send + Sync>;
+
+/// Request of a command without parameters
+#[derive(Debug, Default, Deserialize)]
+pub struct NoParams {}
+
+#[derive(Default)]
+pub struct CommandRegistry {
+    handlers: HashMap<&'static str, Handler>,
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `name` with `handler`
+    pub fn register<Req, Resp, F, Fut>(mut self, name: &'static str, handler: F) -> Self
+    where
+        Req: DeserializeOwned + Send + 'static,
+        Resp: Serialize + 'static,
+        F: Fn(Req) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = AppResult<Resp>> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        let call: Handler = Arc::new(move |payload: Value| {
+            let handler = Arc::clone(&handler);
+            Box::pin(async move {
+                let request = request::<Req>(name, payload)?;
+                let response = handler(request).await?;
+                success(name, &response)
+            })
+        });
+        assert!(self.handlers.insert(name, call).is_none(), "{} is registered twice", name);
+        self
+    }
+
+    /// Answer each of `names` with `handler`, which gets the name and the raw
+    /// payload and returns the whole response, `success` included
+    pub fn register_module<F, Fut>(mut self, names: &[&'static str], handler: F) -> Self
+    where
+        F: Fn(&'static str, Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Value> + Send + 'static,
+    {
+        let handler = Arc::new(handler);
+        for &name in names {
+            let handler = Arc::clone(&handler);
+            let call: Handler = Arc::new(move |payload: Value| {
+                let response = handler(name, payload);
+                Box::pin(async move { Ok(response.await) })
+            });
+            assert!(self.handlers.insert(name, call).is_none(), "{} is registered twice", name);
+        }
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn handles(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+
+    /// The response to `name`, or `None` if it is not registered here
+    pub async fn call(&self, name: &str, payload: &Value) -> Option<Value> {
+        let handler = self.handlers.get(name)?;
+        Some(match handler(payload.clone()).await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("{} failed: {}", name, e.summary());
+                GlobalErrorHandler::to_json_response(&e)
+            }
+        })
+    }
+}
+
+fn request<Req: DeserializeOwned>(name: &str, payload: Value) -> AppResult<Req> {
+    // No payload at all is an empty one
+    let payload = if payload.is_null() { Value::Object(Default::default()) } else { payload };
+    serde_json::from_value(payload).map_err(|e| {
+        AppError::new(ErrorCode::ValidationFailed, format!("Invalid {} payload: {}", name, e)).with_context("command", name)
+    })
+}
+
+fn success(name: &str, response: &impl Serialize) -> AppResult<Value> {
+    match serde_json::to_value(response) {
+        Ok(Value::Object(mut fields)) => {
+            fields.insert("success".to_string(), Value::Bool(true));
+            Ok(Value::Object(fields))
+        }
+        Ok(_) => Err(AppError::new(ErrorCode::HandlerError, format!("{} answered something other than an object", name))),
+        Err(e) => Err(AppError::new(ErrorCode::SerializationError, format!("Failed to serialize the {} response", name))
+            .with_cause(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Add {
+        a: i64,
+        b: i64,
+    }
+
+    #[derive(Serialize)]
+    struct Sum {
+        sum: i64,
+    }
+
+    async fn add(request: Add) -> AppResult<Sum> {
+        if request.b == 0 {
+            return Err(AppError::new(ErrorCode::BusinessRuleViolation, "b must not be 0"));
+        }
+        Ok(Sum { sum: request.a + request.b })
+    }
+
+    #[tokio::test]
+    async fn test_payloads_and_responses_are_typed() {
+        let registry = CommandRegistry::new()
+            .register("add", add)
+            .register("ping", |_: NoParams| async { Ok(Sum { sum: 0 }) });
+
+        let response = registry.call("add", &serde_json::json!({ "a": 2, "b": 3 })).await.unwrap();
+        assert_eq!(response, serde_json::json!({ "success": true, "sum": 5 }));
+        assert_eq!(registry.call("ping", &Value::Null).await.unwrap()["success"], true);
+        assert!(registry.call("missing", &Value::Null).await.is_none());
+
+        let invalid = registry.call("add", &serde_json::json!({ "a": "2", "b": 3 })).await.unwrap();
+        assert_eq!(invalid["error"]["code"], "ValidationFailed");
+        assert_eq!(invalid["error"]["context"]["command"], "add");
+        assert!(invalid["error"]["message"].as_str().unwrap().starts_with("Invalid add payload: invalid type: string"));
+
+        let refused = registry.call("add", &serde_json::json!({ "a": 2, "b": 0 })).await.unwrap();
+        assert_eq!(refused["success"], false);
+        assert_eq!(refused["error"]["code"], "BusinessRuleViolation");
+    }
+
+    #[tokio::test]
+    async fn test_module_handlers_answer_as_they_are() {
+        let registry = CommandRegistry::new().register_module(&["echo.name", "echo.payload"], |name, payload| async move {
+            match name {
+                "echo.name" => serde_json::json!({ "success": true, "name": name }),
+                _ => serde_json::json!({ "success": false, "error": payload }),
+            }
+        });
+        assert!(registry.handles("echo.payload") && !registry.handles("echo"));
+        let response = registry.call("echo.name", &Value::Null).await.unwrap();
+        assert_eq!(response, serde_json::json!({ "success": true, "name": "echo.name" }));
+        let failed = registry.call("echo.payload", &serde_json::json!("bad")).await.unwrap();
+        assert_eq!(failed, serde_json::json!({ "success": false, "error": "bad" }));
+    }
+}
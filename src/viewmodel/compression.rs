@@ -0,0 +1,64 @@
+//! Message compression for the WebSocket channel
+//!
+//! tungstenite 0.26 has no permessage-deflate and fails the connection on
+//! any frame with RSV1 set, so the extension cannot be offered in the
+//! upgrade. Compression is negotiated in `hello` instead: a session that
+//! agreed on `deflate` gets messages of at least
+//! `[server] compression_threshold` bytes as binary frames holding the
+//! zlib-compressed JSON, while smaller ones stay text frames. Binary frames
+//! received on such a session are inflated the same way.
+//!
+//! File-transfer frames share the binary channel. A zlib stream starts with
+//! its 0x78 header byte, never with their `WUBF` magic, so either side can
+//! tell the two apart by the first bytes.
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{self, Read, Write};
+
+pub const DEFLATE: &str = "deflate";
+
+/// Largest message an inflated frame may grow to, as tungstenite's `max_message_size`
+const MAX_INFLATED_SIZE: u64 = 64 << 20;
+
+pub fn deflate(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::with_capacity(data.len() / 4), Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+pub fn inflate(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut inflated = Vec::new();
+    ZlibDecoder::new(data).take(MAX_INFLATED_SIZE + 1).read_to_end(&mut inflated)?;
+    if inflated.len() as u64 > MAX_INFLATED_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Inflated message too large"));
+    }
+    Ok(inflated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let json = serde_json::json!({ "data": vec!["user@example.com"; 200] }).to_string();
+        let compressed = deflate(json.as_bytes()).unwrap();
+        assert!(compressed.len() < json.len() / 10);
+        assert_eq!(inflate(&compressed).unwrap(), json.as_bytes());
+        assert!(inflate(b"{\"plain\": true}").is_err());
+    }
+
+    #[test]
+    fn test_never_confused_with_file_frames() {
+        use crate::infrastructure::file_transfer::{encode_frame, is_frame};
+
+        for json in ["", "WUBF", "WUBF\u{0}\u{0}\u{0}\u{2}{}", &"x".repeat(10_000)] {
+            assert!(!is_frame(&deflate(json.as_bytes()).unwrap()), "{:?}", json);
+        }
+        let frame = encode_frame(&serde_json::json!({ "transfer_id": "t1", "offset": 0 }), &deflate(b"{}").unwrap());
+        assert!(is_frame(&frame));
+        assert!(inflate(&frame).is_err());
+    }
+}
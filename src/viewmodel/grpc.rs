@@ -0,0 +1,278 @@
+//! gRPC transport (`--features grpc`)
+//!
+//! Serves `proto/app.proto` with tonic. Every RPC goes through the same
+//! validation and dispatch as the WebSocket handler, and failures carry the
+//! envelope's error code in the `app-error-code` metadata entry. With
+//! `[jwt] enabled = true` calls need an `authorization: Bearer <token>` entry
+//! whose role allows the RPC: reads for any role, `UpdateUser` and `Execute`
+//! for editors, and `Execute` of the WebSocket handler's `ADMIN_COMMANDS`
+//! (`token.issue`, `db.rekey`, `update.install`, `webhooks.*`, ...) for admins.
+
+use serde_json::Value;
+use std::pin::Pin;
+use tokio_stream::wrappers::{BroadcastStream, TcpListenerStream};
+use tokio_stream::{Stream, StreamExt};
+use tonic::metadata::MetadataValue;
+use tonic::{Code, Request, Response, Status};
+use tracing::{debug, info};
+use crate::infrastructure::event_bus::EventBus;
+use crate::infrastructure::jwt::{self, Claims, JwtError};
+use crate::model::core::AppConfig;
+use crate::viewmodel::websocket_handler::{WebSocketHandler, ADMIN_COMMANDS};
+
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("rustwebui.v1");
+}
+
+use proto::app_server::{App, AppServer};
+
+/// Map an error envelope (`{ success: false, error: { code, message } }`) to a status
+fn status_from_envelope(response: &Value) -> Status {
+    let error = &response["error"];
+    let code_name = error["code"].as_str().unwrap_or("Unknown");
+    let message = error["message"].as_str().unwrap_or("Command failed");
+    let code = match code_name {
+        "ValidationFailed" | "SerializationError" => Code::InvalidArgument,
+        "EntityNotFound" | "PluginNotFound" | "PluginCapabilityNotFound" => Code::NotFound,
+        "ConflictError" => Code::Aborted,
+        "AccessDenied" => Code::PermissionDenied,
+        "BusinessRuleViolation" | "InvalidStateTransition" => Code::FailedPrecondition,
+        "ServiceUnavailable" | "ConnectionFailed" => Code::Unavailable,
+        "Timeout" => Code::DeadlineExceeded,
+        "HandlerError" if message.starts_with("Unknown function") => Code::Unimplemented,
+        _ => Code::Internal,
+    };
+    let mut status = Status::new(code, message);
+    if let Ok(value) = MetadataValue::try_from(code_name) {
+        status.metadata_mut().insert("app-error-code", value);
+    }
+    status
+}
+
+fn status_from_jwt(error: JwtError) -> Status {
+    match error {
+        JwtError::Missing | JwtError::Invalid(_) => Status::unauthenticated(error.to_string()),
+        JwtError::Forbidden(_) => Status::permission_denied(error.to_string()),
+        other => Status::unavailable(other.to_string()),
+    }
+}
+
+/// Verify the bearer token when `[jwt]` is enabled and keep its claims for the RPC
+#[allow(clippy::result_large_err)] // tonic's interceptors fail with `Status`
+fn check_token(mut request: Request<()>) -> Result<Request<()>, Status> {
+    if !AppConfig::global().is_jwt_enabled() {
+        return Ok(request);
+    }
+    let header = request.metadata().get("authorization").and_then(|value| value.to_str().ok());
+    let claims = jwt::authorize(header, jwt::VIEW).map_err(status_from_jwt)?;
+    debug!("gRPC call by user {} ({:?})", claims.sub, claims.role);
+    request.extensions_mut().insert(claims);
+    Ok(request)
+}
+
+/// Refuse the call unless the token's role allows `action`; calls without a token
+/// only get here when `[jwt]` is disabled
+#[allow(clippy::result_large_err)]
+fn require<T>(request: &Request<T>, action: &str) -> Result<(), Status> {
+    match request.extensions().get::<Claims>() {
+        Some(claims) if !claims.role.can_perform(action) => {
+            Err(status_from_jwt(JwtError::Forbidden(action.to_string())))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Run a command and keep the response only when it succeeded
+async fn call(name: &str, payload: Value) -> Result<Value, Status> {
+    debug!("gRPC command: {}", name);
+    let response = WebSocketHandler::handle_function_call(name, &payload)
+        .await
+        .unwrap_or_else(|| serde_json::json!({ "success": true }));
+    if response.get("success").and_then(Value::as_bool) == Some(false) {
+        return Err(status_from_envelope(&response));
+    }
+    Ok(response)
+}
+
+fn user_from_json(user: &Value) -> proto::User {
+    let text = |key: &str| user[key].as_str().unwrap_or_default().to_string();
+    proto::User {
+        id: user["id"].as_i64().unwrap_or_default(),
+        name: text("name"),
+        email: text("email"),
+        role: text("role"),
+        version: user["version"].as_i64().unwrap_or_default(),
+    }
+}
+
+fn update_payload(request: &proto::UpdateUserRequest) -> Value {
+    let mut payload = serde_json::json!({ "id": request.id, "version": request.version });
+    for (key, value) in [("name", &request.name), ("email", &request.email), ("role", &request.role)] {
+        if !value.is_empty() {
+            payload[key] = Value::String(value.clone());
+        }
+    }
+    payload
+}
+
+#[derive(Debug, Default)]
+pub struct AppService;
+
+#[tonic::async_trait]
+impl App for AppService {
+    async fn list_users(
+        &self,
+        request: Request<proto::ListUsersRequest>,
+    ) -> Result<Response<proto::ListUsersResponse>, Status> {
+        require(&request, jwt::VIEW)?;
+        let response = call("get_users", serde_json::json!({})).await?;
+        let users = response["data"].as_array().map(|users| users.iter().map(user_from_json).collect());
+        Ok(Response::new(proto::ListUsersResponse {
+            users: users.unwrap_or_default(),
+        }))
+    }
+
+    async fn update_user(
+        &self,
+        request: Request<proto::UpdateUserRequest>,
+    ) -> Result<Response<proto::User>, Status> {
+        require(&request, jwt::EDIT)?;
+        let response = call("update_user", update_payload(request.get_ref())).await?;
+        Ok(Response::new(user_from_json(&response["data"])))
+    }
+
+    async fn get_db_stats(
+        &self,
+        request: Request<proto::GetDbStatsRequest>,
+    ) -> Result<Response<proto::DbStats>, Status> {
+        require(&request, jwt::VIEW)?;
+        let response = call("get_db_stats", serde_json::json!({})).await?;
+        let stats = &response["stats"];
+        Ok(Response::new(proto::DbStats {
+            users: stats["users"].as_i64().unwrap_or_default(),
+            tables: serde_json::from_value(stats["tables"].clone()).unwrap_or_default(),
+        }))
+    }
+
+    async fn execute(
+        &self,
+        request: Request<proto::CommandRequest>,
+    ) -> Result<Response<proto::CommandResponse>, Status> {
+        let name = request.get_ref().name.as_str();
+        let action = if ADMIN_COMMANDS.contains(&name) {
+            jwt::ADMIN
+        } else {
+            jwt::EDIT
+        };
+        require(&request, action)?;
+        let request = request.into_inner();
+        let payload = if request.payload_json.trim().is_empty() {
+            serde_json::json!({})
+        } else {
+            serde_json::from_str(&request.payload_json)
+                .map_err(|e| Status::invalid_argument(format!("Invalid payload_json: {}", e)))?
+        };
+        // Failures are returned as the JSON envelope, like over WebSocket
+        let response = WebSocketHandler::handle_function_call(&request.name, &payload)
+            .await
+            .unwrap_or_else(|| serde_json::json!({ "success": true }));
+        Ok(Response::new(proto::CommandResponse {
+            response_json: response.to_string(),
+        }))
+    }
+
+    type StreamEventsStream = Pin<Box<dyn Stream<Item = Result<proto::Event, Status>> + Send>>;
+
+    #[allow(clippy::result_large_err)]
+    async fn stream_events(
+        &self,
+        request: Request<proto::StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        require(&request, jwt::VIEW)?;
+        let prefix = request.into_inner().prefix;
+        let receiver = EventBus::global().listen().await;
+        // Lagged receivers skip the dropped events rather than ending the stream
+        let events = BroadcastStream::new(receiver).filter_map(move |event| {
+            let event = event.ok()?;
+            event.name.starts_with(&prefix).then(|| {
+                Ok(proto::Event {
+                    id: event.id,
+                    name: event.name,
+                    payload_json: event.payload.to_string(),
+                    source: event.source,
+                })
+            })
+        });
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+/// Serve the gRPC service until the transport fails
+pub async fn serve(listener: std::net::TcpListener) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    listener.set_nonblocking(true)?;
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+    info!("gRPC listening on {}", listener.local_addr()?);
+    tonic::transport::Server::builder()
+        .add_service(AppServer::with_interceptor(AppService, check_token))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::domain::UserRole;
+
+    #[test]
+    fn test_status_from_envelope() {
+        let envelope = |code: &str, message: &str| {
+            serde_json::json!({ "success": false, "error": { "code": code, "message": message } })
+        };
+        let status = status_from_envelope(&envelope("ValidationFailed", "bad email"));
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "bad email");
+        assert_eq!(status.metadata().get("app-error-code").unwrap(), "ValidationFailed");
+
+        assert_eq!(status_from_envelope(&envelope("ConflictError", "stale")).code(), Code::Aborted);
+        assert_eq!(
+            status_from_envelope(&envelope("HandlerError", "Unknown function: nope")).code(),
+            Code::Unimplemented
+        );
+        assert_eq!(status_from_envelope(&envelope("DatabaseError", "locked")).code(), Code::Internal);
+    }
+
+    #[test]
+    fn test_update_payload_skips_empty_fields() {
+        let request = proto::UpdateUserRequest {
+            id: 3,
+            version: 1,
+            role: "Admin".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(update_payload(&request), serde_json::json!({ "id": 3, "version": 1, "role": "Admin" }));
+    }
+
+    #[test]
+    fn test_require_checks_the_token_role() {
+        let auth = jwt::JwtAuth::hs256(b"test-secret", "tests", chrono::Duration::minutes(5));
+        let with_role = |role| {
+            let mut request = Request::new(());
+            request.extensions_mut().insert(auth.claims(1, "Test", role, None, chrono::Utc::now()).unwrap());
+            request
+        };
+        assert!(require(&Request::new(()), jwt::ADMIN).is_ok());
+        assert!(require(&with_role(UserRole::Viewer), jwt::VIEW).is_ok());
+        assert_eq!(require(&with_role(UserRole::Viewer), jwt::EDIT).unwrap_err().code(), Code::PermissionDenied);
+        assert!(require(&with_role(UserRole::Editor), jwt::EDIT).is_ok());
+        assert_eq!(require(&with_role(UserRole::Editor), jwt::ADMIN).unwrap_err().code(), Code::PermissionDenied);
+        assert!(require(&with_role(UserRole::Admin), jwt::ADMIN).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_command_is_unimplemented() {
+        let status = call("no.such.command", serde_json::json!({})).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unimplemented);
+    }
+}
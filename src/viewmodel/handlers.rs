@@ -1,63 +1,63 @@
-use lazy_static::lazy_static;
 use tracing::{info, error};
-use std::sync::{Arc, Mutex};
 use webui_rs::webui;
+use crate::core::domain::{DatabaseStatsRepository, DomainError, UserRepository};
+use crate::infrastructure::database::{database_stats_repository, user_repository};
 use crate::infrastructure::event_bus::{EventBus, AppEventType};
-use tokio;
 
 // Consolidated handlers module combining all previous handler modules
 // Combines: ui_handlers, counter_handlers, db_handlers, sysinfo_handlers, utils_handlers, advanced_handlers, enhanced_handlers
 
-// Shared database reference using lazy static
-lazy_static! {
-    pub static ref DATABASE: Arc<Mutex<Option<Arc<crate::model::core::Database>>>> =
-        Arc::new(Mutex::new(None));
-}
-
-pub fn init_database(db: Arc<crate::model::core::Database>) {
-    let mut db_guard = DATABASE.lock().unwrap();
-    *db_guard = Some(db);
+/// Send a counter result to the frontend as a `counter_response` DOM event
+fn dispatch_counter_response(
+    event: &webui::Event,
+    result: crate::core::domain::DomainResult<crate::core::domain::Counter>,
+) {
+    let response = match result {
+        Ok(counter) => serde_json::json!({ "success": true, "counter": counter }),
+        Err(e) => {
+            error!("Counter operation failed: {}", e);
+            serde_json::json!({ "success": false, "error": e.to_string() })
+        }
+    };
+    let js_code = format!(
+        "window.dispatchEvent(new CustomEvent('counter_response', {{ detail: {} }}))",
+        response
+    );
+    event.get_window().run_js(&js_code);
 }
 
 pub fn setup_ui_handlers(window: &mut webui::Window) {
+    use crate::infrastructure::counters::{apply, CounterOperation, DEFAULT_COUNTER_ID};
+
     // Setup basic UI handlers
     window.bind("increment_counter", |_event| {
         info!("Increment counter event received");
-        
+
+        let result = futures::executor::block_on(apply(DEFAULT_COUNTER_ID, CounterOperation::Increment));
+
         // Emit event through event bus
-        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
-            let bus_clone = bus.clone();
-            tokio::spawn(async move {
-                if let Err(e) = bus_clone.emit_simple(
+        if let Ok(counter) = &result {
+            if let Ok(bus) = std::panic::catch_unwind(EventBus::global) {
+                if let Err(e) = futures::executor::block_on(bus.emit_simple(
                     &AppEventType::CounterIncremented.to_string(),
                     serde_json::json!({
-                        "value": 1
+                        "id": counter.id,
+                        "value": counter.value
                     }),
-                ).await {
+                )) {
                     error!("Failed to emit counter incremented event: {}", e);
                 }
-            });
+            }
         }
-        
-        // Increment counter logic would go here
-        webui::exit(); // Exit for demo purposes
+
+        dispatch_counter_response(&_event, result);
     });
 
     window.bind("reset_counter", |_event| {
         info!("Reset counter event received");
-        
-        // Emit event through event bus
-        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
-            let bus_clone = bus.clone();
-            tokio::spawn(async move {
-                if let Err(e) = bus_clone.emit_simple(
-                    "counter.reset",
-                    serde_json::json!({}),
-                ).await {
-                    error!("Failed to emit counter reset event: {}", e);
-                }
-            });
-        }
+
+        let result = futures::executor::block_on(apply(DEFAULT_COUNTER_ID, CounterOperation::Reset));
+        dispatch_counter_response(&_event, result);
     });
 
     info!("UI handlers registered");
@@ -67,18 +67,19 @@ pub fn setup_counter_handlers(window: &mut webui::Window) {
     // Counter-specific handlers
     window.bind("get_counter_value", |_event| {
         info!("Get counter value event received");
-        
-        // Emit event through event bus
-        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
-            if let Err(e) = futures::executor::block_on(bus.emit_simple(
-                "counter.value.request",
-                serde_json::json!({
-                    "request_id": uuid::Uuid::new_v4().to_string()
-                }),
-            )) {
-                error!("Failed to emit counter value request event: {}", e);
-            }
-        }
+
+        let result = futures::executor::block_on(crate::infrastructure::counters::list()).map(|counters| {
+            counters
+                .into_iter()
+                .find(|c| c.id == crate::infrastructure::counters::DEFAULT_COUNTER_ID)
+                .unwrap_or_else(|| {
+                    crate::core::domain::Counter::new(
+                        crate::infrastructure::counters::DEFAULT_COUNTER_ID.to_string(),
+                        "Default".to_string(),
+                    )
+                })
+        });
+        dispatch_counter_response(&_event, result);
     });
 
     info!("Counter handlers registered");
@@ -100,174 +101,122 @@ pub fn setup_db_handlers(window: &mut webui::Window) {
     // Database handlers - these are called via webui.call() from frontend
     window.bind("get_users", |_event| {
         info!("Get users event received");
-        
-        // Get database instance
-        if let Ok(db_guard) = DATABASE.lock() {
-            if let Some(ref db) = *db_guard {
-                match db.get_all_users() {
-                    Ok(users) => {
-                        // Send response back to frontend via JavaScript
-                        let response = serde_json::json!({
-                            "success": true,
-                            "data": users
-                        });
-                        let js_code = format!(
-                            "window.dispatchEvent(new CustomEvent('db_response', {{ detail: {} }}))",
-                            response
-                        );
-                        _event.get_window().run_js(&js_code);
-                        
-                        // Emit event through event bus
-                        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
-                            if let Err(e) = futures::executor::block_on(bus.emit_simple(
-                                &AppEventType::DatabaseOperation.to_string(),
-                                serde_json::json!({
-                                    "operation": "get_users_success",
-                                    "count": users.len()
-                                }),
-                            )) {
-                                error!("Failed to emit database operation event: {}", e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to get users: {}", e);
-                        
-                        let response = serde_json::json!({
-                            "success": false,
-                            "error": e.to_string()
-                        });
-                        let js_code = format!(
-                            "window.dispatchEvent(new CustomEvent('db_response', {{ detail: {} }}))",
-                            response
-                        );
-                        _event.get_window().run_js(&js_code);
-                        
-                        // Emit error event through event bus
-                        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
-                        if let Err(err) = futures::executor::block_on(bus.emit_simple(
-                            &AppEventType::DatabaseOperation.to_string(),
-                            serde_json::json!({
-                                "operation": "get_users_error",
-                                "error": e.to_string()
-                            }),
-                        )) {
-                                error!("Failed to emit database error event: {}", err);
-                            }
-                        }
+
+        let result = user_repository()
+            .map_err(DomainError::from)
+            .and_then(|repository| futures::executor::block_on(repository.get_all()));
+        match result {
+            Ok(users) => {
+                // Send response back to frontend via JavaScript
+                let response = serde_json::json!({
+                    "success": true,
+                    "data": users
+                });
+                let js_code = format!(
+                    "window.dispatchEvent(new CustomEvent('db_response', {{ detail: {} }}))",
+                    response
+                );
+                _event.get_window().run_js(&js_code);
+
+                // Emit event through event bus
+                if let Ok(bus) = std::panic::catch_unwind(EventBus::global) {
+                    if let Err(e) = futures::executor::block_on(bus.emit_simple(
+                        &AppEventType::DatabaseOperation.to_string(),
+                        serde_json::json!({
+                            "operation": "get_users_success",
+                            "count": users.len()
+                        }),
+                    )) {
+                        error!("Failed to emit database operation event: {}", e);
                     }
                 }
-            } else {
-                error!("Database not initialized");
-                
+            }
+            Err(e) => {
+                error!("Failed to get users: {}", e);
+
                 let response = serde_json::json!({
                     "success": false,
-                    "error": "Database not initialized"
+                    "error": e.to_string()
                 });
                 let js_code = format!(
                     "window.dispatchEvent(new CustomEvent('db_response', {{ detail: {} }}))",
                     response
                 );
                 _event.get_window().run_js(&js_code);
-            }
-        } else {
-            error!("Failed to acquire database lock");
 
-            let response = serde_json::json!({
-                "success": false,
-                "error": "Failed to acquire database lock"
-            });
-            let js_code = format!(
-                "window.dispatchEvent(new CustomEvent('db_response', {{ detail: {} }}))",
-                response
-            );
-            _event.get_window().run_js(&js_code);
+                // Emit error event through event bus
+                if let Ok(bus) = std::panic::catch_unwind(EventBus::global) {
+                    if let Err(err) = futures::executor::block_on(bus.emit_simple(
+                        &AppEventType::DatabaseOperation.to_string(),
+                        serde_json::json!({
+                            "operation": "get_users_error",
+                            "error": e.to_string()
+                        }),
+                    )) {
+                        error!("Failed to emit database error event: {}", err);
+                    }
+                }
+            }
         }
     });
 
     window.bind("get_db_stats", |_event| {
         info!("Get DB stats event received");
-        
-        // Get database instance
-        if let Ok(db_guard) = DATABASE.lock() {
-            if let Some(ref db) = *db_guard {
-                match db.get_db_stats() {
-                    Ok(stats) => {
-                        let response = serde_json::json!({
-                            "success": true,
-                            "stats": stats
-                        });
-                        let js_code = format!(
-                            "window.dispatchEvent(new CustomEvent('stats_response', {{ detail: {} }}))",
-                            response
-                        );
-                        _event.get_window().run_js(&js_code);
-                        
-                        // Emit event through event bus
-                        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
-                            if let Err(e) = futures::executor::block_on(bus.emit_simple(
-                                "database.stats.response",
-                                serde_json::json!({
-                                    "operation": "get_stats_success",
-                                    "stats": &stats
-                                }),
-                            )) {
-                                error!("Failed to emit database stats response event: {}", e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to get database stats: {}", e);
-                        
-                        let response = serde_json::json!({
-                            "success": false,
-                            "error": e.to_string()
-                        });
-                        let js_code = format!(
-                            "window.dispatchEvent(new CustomEvent('stats_response', {{ detail: {} }}))",
-                            response
-                        );
-                        _event.get_window().run_js(&js_code);
-                        
-                        // Emit error event through event bus
-                        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
-                            if let Err(err) = futures::executor::block_on(bus.emit_simple(
-                                &AppEventType::DatabaseOperation.to_string(),
-                                serde_json::json!({
-                                    "operation": "get_stats_error",
-                                    "error": e.to_string()
-                                }),
-                            )) {
-                                error!("Failed to emit database stats error event: {}", err);
-                            }
-                        }
+
+        let result = database_stats_repository()
+            .map_err(DomainError::from)
+            .and_then(|repository| futures::executor::block_on(repository.get_stats()));
+        match result {
+            Ok(stats) => {
+                let response = serde_json::json!({
+                    "success": true,
+                    "stats": stats
+                });
+                let js_code = format!(
+                    "window.dispatchEvent(new CustomEvent('stats_response', {{ detail: {} }}))",
+                    response
+                );
+                _event.get_window().run_js(&js_code);
+
+                // Emit event through event bus
+                if let Ok(bus) = std::panic::catch_unwind(EventBus::global) {
+                    if let Err(e) = futures::executor::block_on(bus.emit_simple(
+                        "database.stats.response",
+                        serde_json::json!({
+                            "operation": "get_stats_success",
+                            "stats": &stats
+                        }),
+                    )) {
+                        error!("Failed to emit database stats response event: {}", e);
                     }
                 }
-            } else {
-                error!("Database not initialized");
-                
+            }
+            Err(e) => {
+                error!("Failed to get database stats: {}", e);
+
                 let response = serde_json::json!({
                     "success": false,
-                    "error": "Database not initialized"
+                    "error": e.to_string()
                 });
                 let js_code = format!(
                     "window.dispatchEvent(new CustomEvent('stats_response', {{ detail: {} }}))",
                     response
                 );
                 _event.get_window().run_js(&js_code);
-            }
-        } else {
-            error!("Failed to acquire database lock");
 
-            let response = serde_json::json!({
-                "success": false,
-                "error": "Failed to acquire database lock"
-            });
-            let js_code = format!(
-                "window.dispatchEvent(new CustomEvent('stats_response', {{ detail: {} }}))",
-                response
-            );
-            _event.get_window().run_js(&js_code);
+                // Emit error event through event bus
+                if let Ok(bus) = std::panic::catch_unwind(EventBus::global) {
+                    if let Err(err) = futures::executor::block_on(bus.emit_simple(
+                        &AppEventType::DatabaseOperation.to_string(),
+                        serde_json::json!({
+                            "operation": "get_stats_error",
+                            "error": e.to_string()
+                        }),
+                    )) {
+                        error!("Failed to emit database stats error event: {}", err);
+                    }
+                }
+            }
         }
     });
 
@@ -288,16 +237,7 @@ pub fn setup_sysinfo_handlers(window: &mut webui::Window) {
     window.bind("get_system_info", |_event| {
         info!("Get system info event received");
         
-        let sysinfo = serde_json::json!({
-            "platform": std::env::consts::OS,
-            "arch": std::env::consts::ARCH,
-            "family": std::env::consts::FAMILY,
-            "os_version": "Linux",
-            "app_version": "1.0.0",
-            "rust_version": env!("CARGO_PKG_VERSION"),
-            "memory_usage": 0,
-            "cpu_cores": 0
-        });
+        let sysinfo = crate::infrastructure::system_info::get_system_info_response();
         
         let js_code = format!(
             "window.dispatchEvent(new CustomEvent('sysinfo_response', {{ detail: {} }}))",
@@ -306,7 +246,7 @@ pub fn setup_sysinfo_handlers(window: &mut webui::Window) {
         _event.get_window().run_js(&js_code);
         
         // Emit event through event bus
-        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
+        if let Ok(bus) = std::panic::catch_unwind(EventBus::global) {
             if let Err(e) = futures::executor::block_on(bus.emit_simple(
                 &AppEventType::SystemHealthCheck.to_string(),
                 serde_json::json!({
@@ -325,12 +265,20 @@ pub fn setup_utils_handlers(window: &mut webui::Window) {
     // Utility handlers
     window.bind("open_folder", |_event| {
         info!("Open folder event received");
-        
+
+        let payload = match crate::infrastructure::shell::open_app_folder() {
+            Ok(path) => serde_json::json!({ "success": true, "path": path.display().to_string() }),
+            Err(e) => {
+                error!("Failed to open application folder: {}", e);
+                serde_json::json!({ "success": false, "error": e.to_string() })
+            }
+        };
+
         // Emit event through event bus
-        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
+        if let Ok(bus) = std::panic::catch_unwind(EventBus::global) {
             if let Err(e) = futures::executor::block_on(bus.emit_simple(
                 "utility.folder.open",
-                serde_json::json!({}),
+                payload,
             )) {
                 error!("Failed to emit open folder event: {}", e);
             }
@@ -339,16 +287,41 @@ pub fn setup_utils_handlers(window: &mut webui::Window) {
 
     window.bind("organize_images", |_event| {
         info!("Organize images event received");
-        
-        // Emit event through event bus
-        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
-            if let Err(e) = futures::executor::block_on(bus.emit_simple(
-                "utility.images.organize",
-                serde_json::json!({}),
-            )) {
-                error!("Failed to emit organize images event: {}", e);
-            }
-        }
+        let window_id = _event.window;
+
+        // Pick a folder and preview the result (dry run) without blocking the UI;
+        // the frontend applies it through the `images.organize` WebSocket command.
+        std::thread::spawn(move || {
+            use crate::infrastructure::dialog::{open_folder, DialogOptions};
+            use crate::infrastructure::image_organizer::{organize_with_events, OrganizeMode, OrganizeOptions};
+
+            let dialog = DialogOptions {
+                title: Some("Select a folder of images".to_string()),
+                ..Default::default()
+            };
+            let response = match futures::executor::block_on(open_folder(&dialog)).into_iter().next() {
+                Some(folder) => {
+                    let options = OrganizeOptions {
+                        source: folder.to_string_lossy().into_owned(),
+                        destination: None,
+                        mode: OrganizeMode::Copy,
+                        dry_run: true,
+                        recursive: true,
+                    };
+                    match organize_with_events(&options) {
+                        Ok(report) => serde_json::json!({ "success": true, "source": options.source, "report": report }),
+                        Err(e) => serde_json::json!({ "success": false, "error": e }),
+                    }
+                }
+                None => serde_json::json!({ "success": false, "cancelled": true }),
+            };
+
+            let js_code = format!(
+                "window.dispatchEvent(new CustomEvent('organize_images_response', {{ detail: {} }}))",
+                response
+            );
+            webui::Window::from_id(window_id).run_js(&js_code);
+        });
     });
 
     info!("Utility handlers registered");
@@ -360,7 +333,7 @@ pub fn setup_advanced_handlers(window: &mut webui::Window) {
         info!("Advanced operation event received");
         
         // Emit event through event bus
-        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
+        if let Ok(bus) = std::panic::catch_unwind(EventBus::global) {
             if let Err(e) = futures::executor::block_on(bus.emit_simple(
                 "advanced.operation",
                 serde_json::json!({}),
@@ -379,7 +352,7 @@ pub fn setup_enhanced_handlers(window: &mut webui::Window) {
         info!("Enhanced feature event received");
 
         // Emit event through event bus
-        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
+        if let Ok(bus) = std::panic::catch_unwind(EventBus::global) {
             if let Err(e) = futures::executor::block_on(bus.emit_simple(
                 "enhanced.feature",
                 serde_json::json!({}),
@@ -406,7 +379,7 @@ pub fn setup_window_tracking_handlers(window: &mut webui::Window) {
         info!("Window state change event received - will be handled by WebSocket");
 
         // Emit event through event bus to notify that a window state change occurred
-        if let Ok(bus) = std::panic::catch_unwind(|| EventBus::global()) {
+        if let Ok(bus) = std::panic::catch_unwind(EventBus::global) {
             if let Err(e) = futures::executor::block_on(bus.emit_simple(
                 "window.state.change.event",
                 serde_json::json!({
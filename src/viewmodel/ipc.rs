@@ -0,0 +1,299 @@
+//! Local IPC channel for scripting the running app (`app --ctl ...`)
+//!
+//! Newline-delimited JSON over a Unix domain socket (a named pipe on
+//! Windows). Each request line `{ "name": ..., "payload": ... }` is answered
+//! with one response line from the same command dispatch as the WebSocket
+//! handler; the extra `emit` command publishes `payload.event` on the event bus.
+//! A line longer than `MAX_LINE` is refused and ends the connection.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::{debug, info};
+use crate::error_handling::{AppError, ErrorCode, GlobalErrorHandler};
+use crate::infrastructure::event_bus::{Event, EventBus};
+use crate::viewmodel::websocket_handler::WebSocketHandler;
+
+/// Longest request line, like the largest JSON message over WebSocket
+const MAX_LINE: u64 = 1 << 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpcRequest {
+    pub name: String,
+    #[serde(default)]
+    pub payload: Value,
+}
+
+/// Socket (or pipe) used when `[ipc] path` is not set
+pub fn default_socket_path() -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from(r"\\.\pipe\rustwebui-app")
+    } else {
+        std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir)
+            .join("rustwebui-app.sock")
+    }
+}
+
+async fn emit(payload: &Value) -> Value {
+    let Some(event) = payload.get("event").and_then(Value::as_str).filter(|e| !e.is_empty()) else {
+        return GlobalErrorHandler::to_json_response(&AppError::new(
+            ErrorCode::ValidationFailed,
+            "emit needs a non-empty event name",
+        ));
+    };
+    let data = payload.get("payload").cloned().unwrap_or(Value::Null);
+    match EventBus::global()
+        .emit(Event::new(event.to_string(), data, "ipc".to_string()))
+        .await
+    {
+        Ok(()) => serde_json::json!({ "success": true, "emitted": event }),
+        Err(e) => GlobalErrorHandler::to_json_response(
+            &AppError::new(ErrorCode::CommandFailed, format!("Failed to emit {}: {}", event, e)),
+        ),
+    }
+}
+
+/// Answer one request line
+pub async fn handle_request(line: &str) -> Value {
+    let request = match serde_json::from_str::<IpcRequest>(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return GlobalErrorHandler::to_json_response(&AppError::new(
+                ErrorCode::SerializationError,
+                format!("Invalid IPC request: {}", e),
+            ))
+        }
+    };
+    debug!("IPC command: {}", request.name);
+    if request.name == "emit" {
+        return emit(&request.payload).await;
+    }
+    WebSocketHandler::handle_function_call(&request.name, &request.payload)
+        .await
+        .unwrap_or_else(|| serde_json::json!({ "success": true }))
+}
+
+async fn serve_connection<S: AsyncRead + AsyncWrite>(stream: S) -> io::Result<()> {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = tokio::io::BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        // One byte past the limit tells a line that is too long from one that just fits
+        let read = (&mut reader).take(MAX_LINE + 1).read_until(b'\n', &mut line).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        let too_long = line.last() != Some(&b'\n') && read as u64 > MAX_LINE;
+        let response = if too_long {
+            GlobalErrorHandler::to_json_response(&AppError::new(
+                ErrorCode::ValidationFailed,
+                format!("IPC request is longer than {} bytes", MAX_LINE),
+            ))
+        } else {
+            let text = std::str::from_utf8(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if text.trim().is_empty() {
+                continue;
+            }
+            handle_request(text.trim_end()).await
+        };
+        let mut response = response.to_string();
+        response.push('\n');
+        writer.write_all(response.as_bytes()).await?;
+        if too_long {
+            // The rest of the refused line cannot be told apart from the next request
+            return Ok(());
+        }
+    }
+}
+
+/// Accept IPC clients until the listener fails
+#[cfg(unix)]
+pub async fn serve(path: PathBuf) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::net::{UnixListener, UnixStream};
+
+    if path.exists() {
+        if UnixStream::connect(&path).await.is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("{} is used by another running instance", path.display()),
+            ));
+        }
+        // Left behind by a previous run that did not shut down cleanly
+        std::fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    info!("IPC listening on {}", path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(stream).await {
+                debug!("IPC connection closed: {}", e);
+            }
+        });
+    }
+}
+
+/// Accept IPC clients until the pipe fails
+#[cfg(windows)]
+pub async fn serve(path: PathBuf) -> io::Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let name = path.to_string_lossy().into_owned();
+    let mut server = ServerOptions::new().first_pipe_instance(true).create(&name)?;
+    info!("IPC listening on {}", name);
+
+    loop {
+        server.connect().await?;
+        let connected = server;
+        server = ServerOptions::new().create(&name)?;
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(connected).await {
+                debug!("IPC connection closed: {}", e);
+            }
+        });
+    }
+}
+
+/// Turn `--ctl` arguments into a request:
+/// `get-users`, `counter.increment '{"id":"a"}'`, `emit event.name '{"x":1}'`
+pub fn parse_ctl_args(args: &[String]) -> Result<IpcRequest, String> {
+    let parse_json = |text: Option<&String>| -> Result<Value, String> {
+        text.map(|text| serde_json::from_str(text).map_err(|e| format!("Invalid JSON '{}': {}", text, e)))
+            .unwrap_or(Ok(Value::Object(Default::default())))
+    };
+
+    let command = args
+        .first()
+        .ok_or_else(|| "Usage: --ctl <command> [json-payload] | --ctl emit <event> [json-payload]".to_string())?;
+    if command == "emit" {
+        let event = args.get(1).ok_or_else(|| "Usage: --ctl emit <event> [json-payload]".to_string())?;
+        return Ok(IpcRequest {
+            name: "emit".to_string(),
+            payload: serde_json::json!({ "event": event, "payload": parse_json(args.get(2))? }),
+        });
+    }
+    Ok(IpcRequest {
+        name: command.replace('-', "_"),
+        payload: parse_json(args.get(1))?,
+    })
+}
+
+/// Send one request to the running app and return its response
+pub fn send_request(path: &std::path::Path, request: &IpcRequest) -> io::Result<Value> {
+    #[cfg(unix)]
+    let stream = std::os::unix::net::UnixStream::connect(path)?;
+    #[cfg(windows)]
+    let stream = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+
+    let mut writer = stream.try_clone()?;
+    writer.write_all(format!("{}\n", serde_json::to_string(request)?).as_bytes())?;
+    writer.flush()?;
+
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    serde_json::from_str(&line).map_err(io::Error::from)
+}
+
+/// `app --ctl ...`: print the response and return the process exit code
+pub fn run_ctl(path: &std::path::Path, args: &[String]) -> i32 {
+    let request = match parse_ctl_args(args) {
+        Ok(request) => request,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    match send_request(path, &request) {
+        Ok(response) => {
+            println!("{}", serde_json::to_string_pretty(&response).unwrap_or_default());
+            if response.get("success").and_then(Value::as_bool) == Some(false) { 1 } else { 0 }
+        }
+        Err(e) => {
+            eprintln!("Cannot reach the running app at {}: {}", path.display(), e);
+            3
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::testkit::TempDir;
+
+    fn args(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_ctl_args() {
+        let request = parse_ctl_args(&args(&["get-users"])).unwrap();
+        assert_eq!(request.name, "get_users");
+        assert_eq!(request.payload, serde_json::json!({}));
+
+        let request = parse_ctl_args(&args(&["emit", "demo.ping", r#"{"x":1}"#])).unwrap();
+        assert_eq!(request.name, "emit");
+        assert_eq!(request.payload, serde_json::json!({ "event": "demo.ping", "payload": { "x": 1 } }));
+
+        assert!(parse_ctl_args(&args(&[])).is_err());
+        assert!(parse_ctl_args(&args(&["counter.increment", "{not json"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_errors_use_envelope() {
+        let response = handle_request("not json").await;
+        assert_eq!(response["success"], false);
+        assert_eq!(response["error"]["code"], "SerializationError");
+
+        let response = handle_request(r#"{"name":"emit","payload":{}}"#).await;
+        assert_eq!(response["error"]["code"], "ValidationFailed");
+
+        let response = handle_request(r#"{"name":"emit","payload":{"event":"ipc.test"}}"#).await;
+        assert_eq!(response, serde_json::json!({ "success": true, "emitted": "ipc.test" }));
+    }
+
+    #[tokio::test]
+    async fn test_overlong_line_is_refused() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(serve_connection(server));
+        let (reader, mut writer) = tokio::io::split(client);
+        tokio::spawn(async move {
+            let _ = writer.write_all(&vec![b' '; MAX_LINE as usize + 2]).await;
+        });
+
+        let mut response = String::new();
+        tokio::io::BufReader::new(reader).read_line(&mut response).await.unwrap();
+        let response: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["error"]["code"], "ValidationFailed");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_round_trip_over_socket() {
+        let dir = TempDir::new("rustwebui-ipc");
+        let path = dir.join("ctl.sock");
+        tokio::spawn(serve(path.clone()));
+        for _ in 0..50 {
+            if path.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        let request = parse_ctl_args(&args(&["emit", "ipc.roundtrip"])).unwrap();
+        let client_path = path.clone();
+        let response = tokio::task::spawn_blocking(move || send_request(&client_path, &request))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response["emitted"], "ipc.roundtrip");
+    }
+}
@@ -1,3 +1,19 @@
+pub mod client_codegen;
+pub mod command_catalog;
+pub mod command_schemas;
+pub mod commands;
+pub mod compression;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod handlers;
+pub mod ipc;
+pub mod protocol;
 pub mod websocket_handler;
-pub mod window_logger;
\ No newline at end of file
+pub mod window_logger;
+pub mod ws_access;
+pub mod ws_delivery;
+pub mod ws_fanout;
+pub mod ws_mutations;
+pub mod ws_parser;
+pub mod ws_resume;
+pub mod ws_timeouts;
@@ -0,0 +1,227 @@
+//! WebSocket protocol negotiation - the `hello` handshake
+//!
+//! A client opens with `hello { protocol_version, min_protocol_version?,
+//! formats?, compression?, features?, locale? }`. The backend answers with the
+//! version, format, compression and features both sides support, or with a
+//! `CommunicationError` and closes the connection when there is no common
+//! version or format. Clients that never say hello get `Session::default()`.
+
+use serde::Serialize;
+use serde_json::Value;
+use crate::error_handling::{AppError, AppResult, ErrorCode};
+use crate::infrastructure::i18n::Catalog;
+use crate::model::core::AppConfig;
+use crate::viewmodel::command_schemas;
+use crate::viewmodel::compression::DEFLATE;
+
+pub const HELLO: &str = "hello";
+
+/// Newest protocol version this backend speaks
+pub const PROTOCOL_VERSION: u64 = 1;
+/// Oldest protocol version this backend still accepts
+pub const MIN_PROTOCOL_VERSION: u64 = 1;
+
+/// Message formats the socket reads and writes
+pub const FORMATS: &[&str] = &["json"];
+/// Compression schemes applied to message payloads, see `compression`
+pub const COMPRESSION: &[&str] = &[DEFLATE];
+
+/// Per-connection behaviour a client can opt into
+pub const EVENTS: &str = "events";
+pub const FEATURES: &[&str] = &[EVENTS, "request_ids", "mutations"];
+
+/// Cargo features this build was compiled with, reported to clients
+fn build_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "discovery") {
+        features.push("discovery");
+    }
+    if cfg!(feature = "telemetry") {
+        features.push("telemetry");
+    }
+    if cfg!(feature = "grpc") {
+        features.push("grpc");
+    }
+    if cfg!(feature = "smtp") {
+        features.push("smtp");
+    }
+    if cfg!(feature = "outbox-webhook") {
+        features.push("outbox-webhook");
+    }
+    if cfg!(feature = "broker") {
+        features.push("broker");
+    }
+    if cfg!(feature = "webhooks") {
+        features.push("webhooks");
+    }
+    if cfg!(feature = "updater") {
+        features.push("updater");
+    }
+    if cfg!(feature = "collab") {
+        features.push("collab");
+    }
+    if cfg!(feature = "hotkeys") {
+        features.push("hotkeys");
+    }
+    if cfg!(feature = "capture") {
+        features.push("capture");
+    }
+    if cfg!(feature = "media") {
+        features.push("media");
+    }
+    features
+}
+
+/// `COMPRESSION`, unless `[server] compression` turns it off
+fn compression_schemes() -> &'static [&'static str] {
+    if AppConfig::global().is_ws_compression_enabled() {
+        COMPRESSION
+    } else {
+        &[]
+    }
+}
+
+/// What one connection agreed on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Session {
+    pub protocol_version: u64,
+    pub format: String,
+    pub compression: Option<String>,
+    pub features: Vec<String>,
+    /// Whether the client said hello; the defaults keep pre-handshake clients working
+    pub negotiated: bool,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            protocol_version: MIN_PROTOCOL_VERSION,
+            format: FORMATS[0].to_string(),
+            compression: None,
+            features: FEATURES.iter().map(|feature| feature.to_string()).collect(),
+            negotiated: false,
+        }
+    }
+}
+
+impl Session {
+    pub fn has(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+fn strings(payload: &Value, key: &str) -> Option<Vec<String>> {
+    payload
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect())
+}
+
+fn mismatch(message: String, payload: &Value) -> AppError {
+    AppError::new(ErrorCode::CommunicationError, message)
+        .with_context("protocol_versions", vec![MIN_PROTOCOL_VERSION, PROTOCOL_VERSION])
+        .with_context("formats", FORMATS)
+        .with_context("client", payload.clone())
+}
+
+/// Agree on a session for a `hello` payload
+pub fn negotiate(payload: &Value) -> AppResult<Session> {
+    command_schemas::validate(HELLO, payload)?;
+    let client_max = payload["protocol_version"].as_u64().unwrap_or_default();
+    let client_min = payload.get("min_protocol_version").and_then(Value::as_u64).unwrap_or(client_max);
+
+    let protocol_version = client_max.min(PROTOCOL_VERSION);
+    if protocol_version < client_min.max(MIN_PROTOCOL_VERSION) {
+        return Err(mismatch(
+            format!(
+                "No common protocol version: client speaks {}-{}, backend {}-{}",
+                client_min, client_max, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+            ),
+            payload,
+        ));
+    }
+
+    let formats = strings(payload, "formats").unwrap_or_else(|| vec![FORMATS[0].to_string()]);
+    let format = formats
+        .iter()
+        .find(|format| FORMATS.contains(&format.as_str()))
+        .cloned()
+        .ok_or_else(|| mismatch(format!("No common message format in {:?}", formats), payload))?;
+
+    let compression = strings(payload, "compression")
+        .unwrap_or_default()
+        .into_iter()
+        .find(|scheme| compression_schemes().contains(&scheme.as_str()));
+
+    let features = match strings(payload, "features") {
+        Some(requested) => requested.into_iter().filter(|f| FEATURES.contains(&f.as_str())).collect(),
+        None => Session::default().features,
+    };
+
+    Ok(Session { protocol_version, format, compression, features, negotiated: true })
+}
+
+/// Response to `hello`: the session, plus what the backend offers so the client can degrade
+pub fn hello_response(session: &Session, payload: &Value) -> Value {
+    let ignored: Vec<String> = strings(payload, "features")
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|feature| !session.has(feature))
+        .collect();
+    serde_json::json!({
+        "success": true,
+        "protocol_version": session.protocol_version,
+        "format": session.format,
+        "compression": session.compression,
+        "features": session.features,
+        "ignored_features": ignored,
+        "server": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "protocol_versions": [MIN_PROTOCOL_VERSION, PROTOCOL_VERSION],
+            "formats": FORMATS,
+            "compression": compression_schemes(),
+            "features": FEATURES,
+            "build_features": build_features(),
+            "locales": Catalog::global().locales(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiates_common_settings() {
+        let payload = serde_json::json!({
+            "protocol_version": PROTOCOL_VERSION + 1,
+            "min_protocol_version": 1,
+            "formats": ["msgpack", "json"],
+            "compression": ["zstd", "deflate"],
+            "features": ["request_ids", "time_travel"],
+        });
+        let session = negotiate(&payload).unwrap();
+        assert_eq!(session.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(session.format, "json");
+        assert_eq!(session.compression.as_deref(), Some(DEFLATE));
+        assert_eq!(session.features, vec!["request_ids"]);
+        assert!(!session.has(EVENTS));
+
+        let response = hello_response(&session, &payload);
+        assert_eq!(response["ignored_features"], serde_json::json!(["time_travel"]));
+    }
+
+    #[test]
+    fn test_defaults_and_mismatches() {
+        let session = negotiate(&serde_json::json!({ "protocol_version": 1 })).unwrap();
+        assert!(session.negotiated && session.has(EVENTS));
+        assert_eq!(session.compression, None);
+
+        let too_new = negotiate(&serde_json::json!({ "protocol_version": 9, "min_protocol_version": 5 })).unwrap_err();
+        assert_eq!(too_new.code, ErrorCode::CommunicationError);
+        let no_format = negotiate(&serde_json::json!({ "protocol_version": 1, "formats": ["cbor"] })).unwrap_err();
+        assert!(no_format.message.contains("format"));
+        let missing = negotiate(&serde_json::json!({})).unwrap_err();
+        assert_eq!(missing.code, ErrorCode::ValidationFailed);
+    }
+}
@@ -1,16 +1,50 @@
-use std::sync::Arc;
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
-use tokio::sync::{mpsc, Notify};
-use tokio_tungstenite::{accept_async, tungstenite::Result};
+use tokio::sync::{broadcast, mpsc, Notify};
+use tokio_tungstenite::{accept_hdr_async_with_config, tungstenite::Result};
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::protocol::WebSocketConfig;
 use futures_util::{SinkExt, StreamExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::time::timeout;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tracing::{info, error, debug, warn, trace};
+use crate::core::domain::DatabaseStatsRepository;
+use crate::error_handling::{circuit_breaker, guards, AppError, AppResult, ErrorCode, GlobalErrorHandler};
+use crate::infrastructure::analytics::Analytics;
+use crate::infrastructure::cache::{self, QueryCache};
+use crate::infrastructure::chaos::Chaos;
+use crate::infrastructure::command_metrics::CommandMetrics;
+use crate::infrastructure::database::query::Order;
+use crate::infrastructure::database::SqliteDatabaseStatsRepository;
 use crate::infrastructure::event_bus::{EventBus, Event};
-use crate::viewmodel::handlers::DATABASE;
+use crate::infrastructure::file_transfer;
+use crate::infrastructure::fs_watcher;
+use crate::infrastructure::flags::FeatureFlags;
+use crate::infrastructure::history::{self, Operation, UserRecord};
+use crate::infrastructure::i18n::{self, Catalog};
+use crate::infrastructure::metrics::metrics_sampler;
+use crate::infrastructure::presence::{self, ClientProfile, Presence};
+use crate::infrastructure::recorder;
+use crate::infrastructure::sessions::{OpenedSession, SessionManager};
+use crate::infrastructure::settings;
+use crate::infrastructure::state_sync;
+use crate::model::core::{AppConfig, Database, UserChanges, UserQuery, UserSession};
+use crate::presentation::devtools;
+use crate::viewmodel::command_schemas;
+use crate::viewmodel::commands::{CommandRegistry, NoParams};
+use crate::viewmodel::compression;
+use crate::viewmodel::protocol::{self, Session};
 use crate::viewmodel::window_logger::window_logger;
+use crate::viewmodel::ws_access::WsAccessPolicy;
+use crate::viewmodel::ws_delivery::{self, ConnectionOutbox};
+use crate::viewmodel::ws_fanout;
+use crate::viewmodel::ws_mutations;
+use crate::viewmodel::ws_parser::{self, MessageLimits};
+use crate::viewmodel::ws_resume::{ConnectionReplay, Resume, ResumeBuffers};
+use crate::viewmodel::ws_timeouts::{self, TimeoutPolicy};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebSocketEvent {
@@ -19,15 +53,9 @@ pub struct WebSocketEvent {
     pub payload: Value,
     pub timestamp: u64,
     pub source: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WebSocketError {
-    pub id: String,
-    pub error_type: String,
-    pub message: String,
-    pub details: Option<Value>,
-    pub timestamp: u64,
+    /// Client-chosen id making a command a mutation, see `ws_mutations`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mutation_id: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -95,6 +123,36 @@ impl std::fmt::Display for ConnectionError {
     }
 }
 
+/// Why the server ends a connection after answering a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloseReason {
+    /// The client's protocol cannot be served; reconnecting as is will not help
+    UnsupportedProtocol,
+    /// The session expired or was revoked; reconnect without it for a new one
+    SessionEnded,
+    /// Open for `max_lifetime`; reconnecting starts a fresh connection
+    LifetimeReached,
+}
+
+impl CloseReason {
+    fn code(self) -> tungstenite::protocol::frame::coding::CloseCode {
+        use tungstenite::protocol::frame::coding::CloseCode;
+        match self {
+            CloseReason::UnsupportedProtocol => CloseCode::Protocol,
+            CloseReason::SessionEnded => CloseCode::Policy,
+            CloseReason::LifetimeReached => CloseCode::Again,
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            CloseReason::UnsupportedProtocol => "Unsupported protocol",
+            CloseReason::SessionEnded => "Session ended",
+            CloseReason::LifetimeReached => "Connection lifetime reached",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct StateTransition {
@@ -113,6 +171,12 @@ pub struct ConnectionStats {
     pub bytes_received: u64,
     pub errors_count: u64,
     pub reconnects: u64,
+    /// Messages sent deflated, and their size before and after compression
+    pub compressed_messages: u64,
+    pub compression_bytes_in: u64,
+    pub compression_bytes_out: u64,
+    /// Messages sent uncompressed on a compressed session for being below the threshold
+    pub uncompressed_messages: u64,
     pub state_history: Vec<StateTransition>,
     pub created_at: Instant,
 }
@@ -126,6 +190,10 @@ impl Default for ConnectionStats {
             bytes_received: 0,
             errors_count: 0,
             reconnects: 0,
+            compressed_messages: 0,
+            compression_bytes_in: 0,
+            compression_bytes_out: 0,
+            uncompressed_messages: 0,
             state_history: Vec::new(),
             created_at: Instant::now(),
         }
@@ -137,6 +205,138 @@ pub struct WebSocketHandler {
     connection_notify: Arc<Notify>,
 }
 
+/// Address the WebSocket listener is bound to, once it is accepting connections
+static LISTEN_ADDR: OnceLock<SocketAddr> = OnceLock::new();
+
+/// Bound WebSocket address, used by the readiness check
+pub fn listen_addr() -> Option<SocketAddr> {
+    LISTEN_ADDR.get().copied()
+}
+
+/// Commands answered by the connection itself rather than the command bus
+pub(crate) const CONNECTION_COMMANDS: &[&str] = &[
+    protocol::HELLO,
+    "session.current",
+    "session.set_locale",
+    "settings.watch",
+    "settings.unwatch",
+    "state.subscribe",
+    "state.unsubscribe",
+    "presence.register",
+    file_transfer::DOWNLOAD,
+    "watch_path",
+    "unwatch",
+    "list_watches",
+    ws_delivery::ACK,
+];
+
+/// Commands a WebSocket client may only send with `admin_token` in the payload: the
+/// DevTools token or an admin's API token. IPC clients are local and trusted, and
+/// gRPC checks the caller's token itself.
+pub(crate) const ADMIN_COMMANDS: &[&str] = &[
+    "session.list",
+    "session.revoke",
+    "token.issue",
+    "db.rekey",
+    "update.install",
+    "webhooks.register",
+    "webhooks.list",
+    "webhooks.remove",
+    "webhooks.deliveries",
+];
+
+/// Limits on incoming messages, from `[server]` in the config
+fn message_limits() -> &'static MessageLimits {
+    static LIMITS: OnceLock<MessageLimits> = OnceLock::new();
+    LIMITS.get_or_init(|| {
+        let config = AppConfig::global();
+        MessageLimits {
+            max_message_bytes: config.get_ws_max_message_bytes(),
+            max_json_bytes: config.get_ws_max_json_bytes(),
+            max_depth: config.get_ws_max_json_depth(),
+        }
+    })
+}
+
+/// Most users one `get_users` page holds
+const MAX_USERS_PAGE: usize = 1000;
+
+#[derive(Debug, Default, Deserialize)]
+struct GetUsersRequest {
+    role: Option<String>,
+    /// Part of the name or email
+    search: Option<String>,
+    sort: Option<String>,
+    /// `asc` or `desc`
+    order: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+impl GetUsersRequest {
+    fn query(self) -> UserQuery {
+        let text = |value: Option<String>| value.filter(|text| !text.is_empty());
+        UserQuery {
+            order: if self.order.as_deref() == Some("desc") { Order::Desc } else { Order::Asc },
+            role: text(self.role),
+            search: text(self.search),
+            sort: text(self.sort),
+            limit: self.limit.map(|limit| limit.clamp(1, MAX_USERS_PAGE)),
+            offset: self.offset.unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct UsersResponse {
+    data: Value,
+}
+
+#[derive(Deserialize)]
+struct CreateUserRequest {
+    name: String,
+    email: String,
+    role: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UpdateUserRequest {
+    id: i64,
+    version: i64,
+    #[serde(flatten)]
+    changes: UserChanges,
+}
+
+#[derive(Deserialize)]
+struct DeleteUserRequest {
+    id: i64,
+    version: i64,
+}
+
+#[derive(Serialize)]
+struct UserResponse {
+    data: Value,
+}
+
+#[derive(Serialize)]
+struct DbStatsResponse {
+    stats: Value,
+}
+
+#[derive(Serialize)]
+struct PresenceResponse {
+    clients: Vec<ClientProfile>,
+}
+
+#[derive(Serialize)]
+struct MessageResponse {
+    message: &'static str,
+}
+
+fn database_error(e: Box<dyn std::error::Error>) -> AppError {
+    AppError::new(ErrorCode::DatabaseError, e.to_string())
+}
+
 impl WebSocketHandler {
     pub fn new(event_bus: Arc<EventBus>) -> Self {
         Self {
@@ -145,9 +345,13 @@ impl WebSocketHandler {
         }
     }
 
-    pub async fn start_server(&self, addr: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let listener = TcpListener::bind(addr).await?;
-        info!("WebSocket server starting on {}", addr);
+    /// Accept connections on an already bound listener (see `server::bind_with_fallback`)
+    pub async fn start_server(&self, listener: std::net::TcpListener) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        listener.set_nonblocking(true)?;
+        let listener = TcpListener::from_std(listener)?;
+        let local = listener.local_addr()?;
+        info!("WebSocket server starting on {}", local);
+        let _ = LISTEN_ADDR.set(local);
 
         loop {
             match listener.accept().await {
@@ -190,6 +394,15 @@ impl WebSocketHandler {
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let mut stats = ConnectionStats::default();
         let mut state = ConnectionState::Initialized;
+        let mut session = Session::default();
+        let mut user_session: Option<UserSession> = None;
+        let mut watches = settings::Watches::default();
+        let mut fs_watches = fs_watcher::Owned::default();
+        let mut subscriptions = state_sync::Subscriptions::default();
+        let mut registration = presence::Registration::default();
+        let mut replay = ConnectionReplay::default();
+        let mut outbox = ConnectionOutbox::default();
+        let peer = stream.peer_addr().ok().map(|addr| addr.ip().to_string());
         
         info!("Accepting new TCP connection from {:?}", stream.peer_addr());
         Self::transition_state(&mut state, ConnectionState::TcpConnecting, &mut stats, Some("TCP connection started".to_string()));
@@ -207,9 +420,31 @@ impl WebSocketHandler {
         // Accept WebSocket handshake with timeout
         Self::transition_state(&mut state, ConnectionState::HandshakeInitiated, &mut stats, Some("WebSocket handshake started".to_string()));
         
+        // Refuse upgrades from other web pages before any message is read
+        let mut path = String::from("/");
+        // `ErrorResponse` is tungstenite's own type
+        #[allow(clippy::result_large_err)]
+        let check_access = |request: &Request, response: Response| -> std::result::Result<Response, ErrorResponse> {
+            path = request.uri().path().to_string();
+            let origin = request.headers().get("Origin").and_then(|value| value.to_str().ok());
+            match WsAccessPolicy::global().check(origin, request.uri().query()) {
+                Ok(()) => Ok(response),
+                Err(rejection) => {
+                    let mut refused = ErrorResponse::new(Some(rejection.message.to_string()));
+                    *refused.status_mut() = tungstenite::http::StatusCode::from_u16(rejection.status)
+                        .unwrap_or(tungstenite::http::StatusCode::FORBIDDEN);
+                    Err(refused)
+                }
+            }
+        };
+        let limits = message_limits();
+        let socket_config = WebSocketConfig::default()
+            .max_message_size(Some(limits.max_message_bytes))
+            .max_frame_size(Some(limits.max_message_bytes));
+        let policy = TimeoutPolicy::global();
         let ws_stream_result = timeout(
-            Duration::from_secs(10),
-            accept_async(stream)
+            policy.handshake(),
+            accept_hdr_async_with_config(stream, check_access, Some(socket_config))
         ).await;
 
         let ws_stream = match ws_stream_result {
@@ -218,6 +453,17 @@ impl WebSocketHandler {
                 info!("WebSocket handshake completed successfully, state: {:?}", state);
                 stream
             }
+            Ok(Err(tungstenite::Error::Protocol(tungstenite::error::ProtocolError::HandshakeIncomplete))) => {
+                // Connected and closed without a request, e.g. the /readyz probe
+                debug!("Connection closed before the WebSocket handshake");
+                Self::transition_state(&mut state, ConnectionState::Closed, &mut stats, Some("Closed before handshake".to_string()));
+                return Ok(());
+            }
+            Ok(Err(tungstenite::Error::Http(response))) => {
+                // Refused by `check_access`, which logged why
+                Self::transition_state(&mut state, ConnectionState::Closed, &mut stats, Some(format!("Upgrade refused with {}", response.status())));
+                return Ok(());
+            }
             Ok(Err(e)) => {
                 error!("WebSocket handshake failed: {}", e);
                 stats.errors_count += 1;
@@ -225,7 +471,7 @@ impl WebSocketHandler {
                 return Err(Box::new(e));
             }
             Err(_) => {
-                error!("WebSocket handshake timed out after 10 seconds");
+                error!("WebSocket handshake timed out after {} seconds", policy.handshake().as_secs());
                 stats.errors_count += 1;
                 Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::HandshakeTimeout), &mut stats, Some("Handshake timeout".to_string()));
                 return Err("Handshake timeout".into());
@@ -233,57 +479,23 @@ impl WebSocketHandler {
         };
 
         let (mut sink, mut stream) = ws_stream.split();
+        let timeouts = policy.for_path(&path);
+        debug!("Connection at {} runs with {:?}", path, timeouts);
 
-        // Channel for broadcasting events from event bus to this connection
-        let (tx, mut rx) = mpsc::unbounded_channel();
-
-        // Spawn a task to listen for events from the event bus and forward them to this connection
-        let event_bus_clone = event_bus.clone();
-        let event_forwarder_handle = tokio::spawn(async move {
-            let mut receiver = event_bus_clone.listen().await;
-            loop {
-                match receiver.recv().await {
-                    Ok(event) => {
-                        if event.source != "frontend" {
-                            let ws_event = WebSocketEvent {
-                                id: event.id,
-                                name: event.name,
-                                payload: event.payload,
-                                timestamp: std::time::SystemTime::now()
-                                    .duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap()
-                                    .as_millis() as u64,
-                                source: event.source,
-                            };
-
-                            match serde_json::to_string(&ws_event) {
-                                Ok(json_str) => {
-                                    if tx.send(tungstenite::Message::Text(json_str.into())).is_err() {
-                                        debug!("Event bus receiver dropped, stopping event forwarding");
-                                        break;
-                                    }
-                                }
-                                Err(e) => {
-                                    error!("Failed to serialize event to JSON: {}", e);
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Event bus receiver error: {}", e);
-                        break;
-                    }
-                }
-            }
-        });
+        // Event frames, serialized once for every connection
+        let mut events = ws_fanout::subscribe();
+        metrics_sampler().set_clients(ws_fanout::connections());
+        // Binary file frames of downloads; bounded so a fast disk waits for a slow client
+        let (frame_tx, mut frame_rx) = mpsc::channel::<Vec<u8>>(8);
 
         // Update state to authenticated (no authentication in this implementation, but showing the state flow)
         Self::transition_state(&mut state, ConnectionState::Authenticated, &mut stats, Some("Connection authenticated".to_string()));
         Self::transition_state(&mut state, ConnectionState::Ready, &mut stats, Some("Connection ready".to_string()));
 
         // Main message processing loop with comprehensive error handling
-        let idle_timeout_duration = Duration::from_secs(300);
         let mut last_activity = Instant::now();
+        let lifetime_end = timeouts.max_lifetime.map(|limit| tokio::time::Instant::now() + limit);
+        let mut revoked = SessionManager::global().subscribe_revoked();
 
         loop {
             // Update state to receiving before waiting for messages
@@ -298,13 +510,16 @@ impl WebSocketHandler {
                             stats.messages_received += 1;
                             stats.bytes_received += msg.len() as u64;
                             trace!("Received WebSocket message: {:?}", msg);
+                            if (msg.is_text() || msg.is_binary()) && Chaos::global().drop_ws_message(msg.len()) {
+                                continue;
+                            }
 
                             match msg {
                                 tungstenite::Message::Text(text) => {
                                     debug!("Processing text message: {} chars", text.len());
                                     Self::transition_state(&mut state, ConnectionState::Processing, &mut stats, Some("Processing text message".to_string()));
 
-                                    match serde_json::from_str::<WebSocketEvent>(&text) {
+                                    match ws_parser::parse::<WebSocketEvent>(text.as_bytes(), message_limits()) {
                                         Ok(ws_event) => {
                                             debug!("Received WebSocket event: {} from {}", ws_event.name, ws_event.source);
 
@@ -313,8 +528,30 @@ impl WebSocketHandler {
                                             let event_payload = ws_event.payload.clone();
                                             let event_id = ws_event.id.clone();
 
+                                            let mutation = ws_mutations::Mutation::of(&ws_event);
+                                            if let Some(mutation) = &mutation {
+                                                let accepted = mutation.accepted();
+                                                if let Err(e) = Self::send_event(&mut sink, &session, &mut stats, timeouts.write, &accepted).await {
+                                                    error!("Error sending mutation {}: {}", accepted.name, e);
+                                                    stats.errors_count += 1;
+                                                    Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::SendError(e.to_string())), &mut stats, Some(e.to_string()));
+                                                    break;
+                                                }
+                                            }
+
                                             // Handle the function call and send response if needed
-                                            let response = Self::handle_function_call(&event_name, &event_payload).await;
+                                            let (response, close) = Self::handle_message(&mut session, &mut user_session, &mut watches, &mut fs_watches, &mut subscriptions, &mut registration, &mut replay, &mut outbox, peer.as_deref(), &frame_tx, &event_name, &event_payload).await;
+
+                                            // The outcome of a mutation goes out before its response
+                                            if let Some(mutation) = &mutation {
+                                                let outcome = mutation.outcome(response.as_ref()).await;
+                                                if let Err(e) = Self::send_event(&mut sink, &session, &mut stats, timeouts.write, &outcome).await {
+                                                    error!("Error sending mutation {}: {}", outcome.name, e);
+                                                    stats.errors_count += 1;
+                                                    Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::SendError(e.to_string())), &mut stats, Some(e.to_string()));
+                                                    break;
+                                                }
+                                            }
 
                                             if let Some(resp) = response {
                                                 Self::transition_state(&mut state, ConnectionState::Sending, &mut stats, Some("Sending response".to_string()));
@@ -327,12 +564,13 @@ impl WebSocketHandler {
                                                         .unwrap()
                                                         .as_millis() as u64,
                                                     source: "backend".to_string(),
+                                                    mutation_id: ws_event.mutation_id.clone(),
                                                 };
 
                                                 match serde_json::to_string(&resp_event) {
                                                     Ok(json_str) => {
-                                                        stats.bytes_sent += json_str.len() as u64;
-                                                        if let Err(e) = sink.send(tungstenite::Message::Text(json_str.into())).await {
+                                                        let message = Self::outgoing(&session, &mut stats, json_str);
+                                                        if let Err(e) = ws_timeouts::write_within(timeouts.write, sink.send(message)).await {
                                                             error!("Error sending response: {}", e);
                                                             stats.errors_count += 1;
                                                             Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::SendError(e.to_string())), &mut stats, Some(e.to_string()));
@@ -350,15 +588,24 @@ impl WebSocketHandler {
                                                 Self::transition_state(&mut state, ConnectionState::Ready, &mut stats, Some("Response sent, ready".to_string()));
                                             }
 
-                                            // Emit the event to the event bus for other parts of the application
-                                            let event = Event::new(
-                                                ws_event.name,
-                                                ws_event.payload,
-                                                ws_event.source,
-                                            );
+                                            if let Some(reason) = close {
+                                                Self::close_with(&mut sink, timeouts.write, reason).await;
+                                                Self::transition_state(&mut state, ConnectionState::Closing, &mut stats, Some(reason.message().to_string()));
+                                                break;
+                                            }
+
+                                            // Emit the event to the event bus for other parts of the application; acks are
+                                            // bookkeeping of this connection only
+                                            if ws_event.name != ws_delivery::ACK {
+                                                let event = Event::new(
+                                                    ws_event.name,
+                                                    ws_event.payload,
+                                                    ws_event.source,
+                                                );
 
-                                            if let Err(e) = event_bus.emit(event).await {
-                                                error!("Error emitting event to event bus: {}", e);
+                                                if let Err(e) = event_bus.emit(event).await {
+                                                    error!("Error emitting event to event bus: {}", e);
+                                                }
                                             }
                                         }
                                         Err(parse_error) => {
@@ -366,23 +613,18 @@ impl WebSocketHandler {
                                             stats.errors_count += 1;
 
                                             // Send error response back to client
-                                            let error_response = WebSocketError {
-                                                id: "parse_error".to_string(),
-                                                error_type: "JSON_PARSE_ERROR".to_string(),
-                                                message: "Invalid JSON format".to_string(),
-                                                details: Some(serde_json::json!({
-                                                    "raw_message": text.chars().take(200).collect::<String>(),
-                                                    "parse_error": parse_error.to_string()
-                                                })),
-                                                timestamp: std::time::SystemTime::now()
-                                                    .duration_since(std::time::UNIX_EPOCH)
-                                                    .unwrap()
-                                                    .as_millis() as u64,
-                                            };
+                                            let error_response = Self::error_event(
+                                                "parse_error",
+                                                AppError::new(ErrorCode::SerializationError, "Invalid JSON format")
+                                                    .with_context("error_type", parse_error.error_type().unwrap_or("JSON_PARSE_ERROR"))
+                                                    .with_context("raw_message", text.chars().take(200).collect::<String>())
+                                                    .with_context("parse_error", parse_error.to_string())
+                                                    .with_context("protocol_version", session.protocol_version),
+                                            );
 
                                             match serde_json::to_string(&error_response) {
                                                 Ok(json_str) => {
-                                                    if let Err(e) = sink.send(tungstenite::Message::Text(json_str.into())).await {
+                                                    if let Err(e) = ws_timeouts::write_within(timeouts.write, sink.send(tungstenite::Message::Text(json_str.into()))).await {
                                                         error!("Error sending error response: {}", e);
                                                     }
                                                 }
@@ -397,12 +639,38 @@ impl WebSocketHandler {
                                     debug!("Processing binary message: {} bytes", data.len());
                                     stats.bytes_received += data.len() as u64;
                                     Self::transition_state(&mut state, ConnectionState::Processing, &mut stats, Some("Processing binary message".to_string()));
-                                    
-                                    // For now, treat binary data as a UTF-8 string if possible
-                                    match String::from_utf8(data.to_vec()) {
+
+                                    if file_transfer::is_frame(&data) {
+                                        let response = Self::handle_frame(&data).await;
+                                        match serde_json::to_string(&response) {
+                                            Ok(json_str) => {
+                                                let message = Self::outgoing(&session, &mut stats, json_str);
+                                                if let Err(e) = ws_timeouts::write_within(timeouts.write, sink.send(message)).await {
+                                                    error!("Error sending frame response: {}", e);
+                                                    stats.errors_count += 1;
+                                                    Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::SendError(e.to_string())), &mut stats, Some(e.to_string()));
+                                                    break;
+                                                }
+                                                stats.messages_sent += 1;
+                                            }
+                                            Err(e) => error!("Failed to serialize frame response: {}", e),
+                                        }
+                                        Self::transition_state(&mut state, ConnectionState::Ready, &mut stats, Some("Frame response sent".to_string()));
+                                        continue;
+                                    }
+
+                                    // Deflated JSON on a compressed session, UTF-8 JSON otherwise
+                                    let data = match session.compression.as_deref() {
+                                        Some(compression::DEFLATE) => compression::inflate(&data).unwrap_or_else(|e| {
+                                            debug!("Binary message is not deflated ({}), reading it as is", e);
+                                            data.to_vec()
+                                        }),
+                                        _ => data.to_vec(),
+                                    };
+                                    match String::from_utf8(data) {
                                         Ok(text) => {
                                             // Try to parse as JSON
-                                            match serde_json::from_str::<WebSocketEvent>(&text) {
+                                            match ws_parser::parse::<WebSocketEvent>(text.as_bytes(), message_limits()) {
                                                 Ok(ws_event) => {
                                                     debug!("Received WebSocket event from binary: {} from {}", ws_event.name, ws_event.source);
 
@@ -411,8 +679,30 @@ impl WebSocketHandler {
                                                     let event_payload = ws_event.payload.clone();
                                                     let event_id = ws_event.id.clone();
 
+                                                    let mutation = ws_mutations::Mutation::of(&ws_event);
+                                                    if let Some(mutation) = &mutation {
+                                                        let accepted = mutation.accepted();
+                                                        if let Err(e) = Self::send_event(&mut sink, &session, &mut stats, timeouts.write, &accepted).await {
+                                                            error!("Error sending mutation {}: {}", accepted.name, e);
+                                                            stats.errors_count += 1;
+                                                            Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::SendError(e.to_string())), &mut stats, Some(e.to_string()));
+                                                            break;
+                                                        }
+                                                    }
+
                                                     // Handle the function call and send response if needed
-                                                    let response = Self::handle_function_call(&event_name, &event_payload).await;
+                                                    let (response, close) = Self::handle_message(&mut session, &mut user_session, &mut watches, &mut fs_watches, &mut subscriptions, &mut registration, &mut replay, &mut outbox, peer.as_deref(), &frame_tx, &event_name, &event_payload).await;
+
+                                                    // The outcome of a mutation goes out before its response
+                                                    if let Some(mutation) = &mutation {
+                                                        let outcome = mutation.outcome(response.as_ref()).await;
+                                                        if let Err(e) = Self::send_event(&mut sink, &session, &mut stats, timeouts.write, &outcome).await {
+                                                            error!("Error sending mutation {}: {}", outcome.name, e);
+                                                            stats.errors_count += 1;
+                                                            Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::SendError(e.to_string())), &mut stats, Some(e.to_string()));
+                                                            break;
+                                                        }
+                                                    }
 
                                                     if let Some(resp) = response {
                                                         Self::transition_state(&mut state, ConnectionState::Sending, &mut stats, Some("Sending binary response".to_string()));
@@ -425,12 +715,13 @@ impl WebSocketHandler {
                                                                 .unwrap()
                                                                 .as_millis() as u64,
                                                             source: "backend".to_string(),
+                                                            mutation_id: ws_event.mutation_id.clone(),
                                                         };
 
                                                         match serde_json::to_string(&resp_event) {
                                                             Ok(json_str) => {
-                                                                stats.bytes_sent += json_str.len() as u64;
-                                                                if let Err(e) = sink.send(tungstenite::Message::Text(json_str.into())).await {
+                                                                let message = Self::outgoing(&session, &mut stats, json_str);
+                                                                if let Err(e) = ws_timeouts::write_within(timeouts.write, sink.send(message)).await {
                                                                     error!("Error sending response: {}", e);
                                                                     stats.errors_count += 1;
                                                                     Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::SendError(e.to_string())), &mut stats, Some(e.to_string()));
@@ -448,38 +739,42 @@ impl WebSocketHandler {
                                                         Self::transition_state(&mut state, ConnectionState::Ready, &mut stats, Some("Binary response sent".to_string()));
                                                     }
 
-                                                    // Emit the event to the event bus for other parts of the application
-                                                    let event = Event::new(
-                                                        ws_event.name,
-                                                        ws_event.payload,
-                                                        ws_event.source,
-                                                    );
+                                                    if let Some(reason) = close {
+                                                        Self::close_with(&mut sink, timeouts.write, reason).await;
+                                                        Self::transition_state(&mut state, ConnectionState::Closing, &mut stats, Some(reason.message().to_string()));
+                                                        break;
+                                                    }
+
+                                                    // Emit the event to the event bus for other parts of the application; acks are
+                                                    // bookkeeping of this connection only
+                                                    if ws_event.name != ws_delivery::ACK {
+                                                        let event = Event::new(
+                                                            ws_event.name,
+                                                            ws_event.payload,
+                                                            ws_event.source,
+                                                        );
 
-                                                    if let Err(e) = event_bus.emit(event).await {
-                                                        error!("Error emitting event to event bus: {}", e);
+                                                        if let Err(e) = event_bus.emit(event).await {
+                                                            error!("Error emitting event to event bus: {}", e);
+                                                        }
                                                     }
                                                 }
                                                 Err(parse_error) => {
                                                     error!("Failed to parse binary WebSocket message as JSON: {}", parse_error);
                                                     stats.errors_count += 1;
                                                     // Send error response back to client
-                                                    let error_response = WebSocketError {
-                                                        id: "binary_parse_error".to_string(),
-                                                        error_type: "BINARY_PARSE_ERROR".to_string(),
-                                                        message: "Invalid binary data format".to_string(),
-                                                        details: Some(serde_json::json!({
-                                                            "binary_length": text.len(),
-                                                            "parse_error": parse_error.to_string()
-                                                        })),
-                                                        timestamp: std::time::SystemTime::now()
-                                                            .duration_since(std::time::UNIX_EPOCH)
-                                                            .unwrap()
-                                                            .as_millis() as u64,
-                                                    };
+                                                    let error_response = Self::error_event(
+                                                        "binary_parse_error",
+                                                        AppError::new(ErrorCode::SerializationError, "Invalid binary data format")
+                                                            .with_context("error_type", parse_error.error_type().unwrap_or("BINARY_PARSE_ERROR"))
+                                                            .with_context("binary_length", text.len())
+                                                            .with_context("parse_error", parse_error.to_string())
+                                                            .with_context("protocol_version", session.protocol_version),
+                                                    );
 
                                                     match serde_json::to_string(&error_response) {
                                                         Ok(json_str) => {
-                                                            if let Err(e) = sink.send(tungstenite::Message::Text(json_str.into())).await {
+                                                            if let Err(e) = ws_timeouts::write_within(timeouts.write, sink.send(tungstenite::Message::Text(json_str.into()))).await {
                                                                 error!("Error sending binary error response: {}", e);
                                                             }
                                                         }
@@ -494,22 +789,16 @@ impl WebSocketHandler {
                                             error!("Binary data is not valid UTF-8: {}", utf8_error);
                                             stats.errors_count += 1;
                                             // Send error response back to client
-                                            let error_response = WebSocketError {
-                                                id: "utf8_error".to_string(),
-                                                error_type: "UTF8_DECODE_ERROR".to_string(),
-                                                message: "Binary data is not valid UTF-8".to_string(),
-                                                details: Some(serde_json::json!({
-                                                    "decode_error": utf8_error.to_string()
-                                                })),
-                                                timestamp: std::time::SystemTime::now()
-                                                    .duration_since(std::time::UNIX_EPOCH)
-                                                    .unwrap()
-                                                    .as_millis() as u64,
-                                            };
+                                            let error_response = Self::error_event(
+                                                "utf8_error",
+                                                AppError::new(ErrorCode::SerializationError, "Binary data is not valid UTF-8")
+                                                    .with_context("error_type", "UTF8_DECODE_ERROR")
+                                                    .with_context("decode_error", utf8_error.to_string()),
+                                            );
 
                                             match serde_json::to_string(&error_response) {
                                                 Ok(json_str) => {
-                                                    if let Err(e) = sink.send(tungstenite::Message::Text(json_str.into())).await {
+                                                    if let Err(e) = ws_timeouts::write_within(timeouts.write, sink.send(tungstenite::Message::Text(json_str.into()))).await {
                                                         error!("Error sending UTF-8 error response: {}", e);
                                                     }
                                                 }
@@ -525,7 +814,7 @@ impl WebSocketHandler {
                                     Self::transition_state(&mut state, ConnectionState::PingSent, &mut stats, Some("Received ping".to_string()));
                                     // Respond to ping with pong to keep connection alive
                                     Self::transition_state(&mut state, ConnectionState::Sending, &mut stats, Some("Sending pong".to_string()));
-                                    match sink.send(tungstenite::Message::Pong(data.into())).await {
+                                    match ws_timeouts::write_within(timeouts.write, sink.send(tungstenite::Message::Pong(data.into()))).await {
                                         Ok(_) => {
                                             trace!("Sent pong response");
                                             stats.messages_sent += 1;
@@ -562,22 +851,16 @@ impl WebSocketHandler {
                             Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::ProtocolError(e.to_string())), &mut stats, Some(e.to_string()));
                             
                             // Send protocol error to client
-                            let error_response = WebSocketError {
-                                id: "protocol_error".to_string(),
-                                error_type: "PROTOCOL_ERROR".to_string(),
-                                message: "WebSocket protocol error".to_string(),
-                                details: Some(serde_json::json!({
-                                    "error": e.to_string()
-                                })),
-                                timestamp: std::time::SystemTime::now()
-                                    .duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap()
-                                    .as_millis() as u64,
-                            };
+                            let error_response = Self::error_event(
+                                "protocol_error",
+                                AppError::new(ErrorCode::CommunicationError, "WebSocket protocol error")
+                                    .with_context("error_type", "PROTOCOL_ERROR")
+                                    .with_context("error", e.to_string()),
+                            );
 
                             match serde_json::to_string(&error_response) {
                                 Ok(json_str) => {
-                                    if let Err(close_e) = sink.send(tungstenite::Message::Text(json_str.into())).await {
+                                    if let Err(close_e) = ws_timeouts::write_within(timeouts.write, sink.send(tungstenite::Message::Text(json_str.into()))).await {
                                         error!("Error sending protocol error response: {}", close_e);
                                     }
                                 }
@@ -595,13 +878,32 @@ impl WebSocketHandler {
                         }
                     }
                 }
-                msg = rx.recv() => {
-                    match msg {
-                        Some(msg) => {
+                event = events.recv() => {
+                    match event {
+                        Ok(_) if !session.has(protocol::EVENTS) => {
+                            trace!("Client did not ask for events, not forwarding");
+                        }
+                        Ok(event) if !watches.forwards(&event.name, &event.payload) => {
+                            trace!("Client does not watch these settings, not forwarding");
+                        }
+                        Ok(event) if !fs_watches.forwards(&event.name, &event.payload) => {
+                            trace!("Client does not hold this file watch, not forwarding");
+                        }
+                        Ok(event) if !subscriptions.forwards(&event.name, &event.payload) => {
+                            trace!("Client did not subscribe to this state, not forwarding");
+                        }
+                        Ok(event) if outbox.covers(&event.name) => {
+                            trace!("Event is delivered from the session's outbox");
+                        }
+                        Ok(event) if replay.was_replayed(&event.id) => {
+                            trace!("Event went out with the replay, not forwarding again");
+                        }
+                        Ok(event) => {
                             trace!("Forwarding event bus message to WebSocket");
                             Self::transition_state(&mut state, ConnectionState::Sending, &mut stats, Some("Forwarding event".to_string()));
                             last_activity = Instant::now();
-                            match sink.send(msg).await {
+                            let msg = Self::outgoing_shared(&session, &mut stats, event.text(), || event.deflated());
+                            match ws_timeouts::write_within(timeouts.write, sink.send(msg)).await {
                                 Ok(_) => {
                                     trace!("Event bus message sent successfully");
                                     stats.messages_sent += 1;
@@ -615,23 +917,111 @@ impl WebSocketHandler {
                                 }
                             }
                         }
-                        None => {
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!("Connection fell behind, {} events were not forwarded", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
                             warn!("Event bus channel closed, continuing...");
                             Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::ChannelClosed), &mut stats, Some("Event channel closed".to_string()));
                         }
                     }
                 }
-                _ = tokio::time::sleep(idle_timeout_duration) => {
-                    let idle_duration = last_activity.elapsed();
-                    if idle_duration >= idle_timeout_duration {
-                        warn!("Connection idle for {} seconds, closing", idle_timeout_duration.as_secs());
+                Some(frame) = frame_rx.recv() => {
+                    last_activity = Instant::now();
+                    stats.bytes_sent += frame.len() as u64;
+                    if let Err(e) = ws_timeouts::write_within(timeouts.write, sink.send(tungstenite::Message::Binary(frame.into()))).await {
+                        error!("Error sending file frame: {}", e);
+                        stats.errors_count += 1;
+                        Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::SendError(e.to_string())), &mut stats, Some(e.to_string()));
+                        break;
+                    }
+                    stats.messages_sent += 1;
+                }
+                Ok(id) = revoked.recv() => {
+                    if user_session.as_ref().is_some_and(|current| current.id == id) {
+                        info!("Session {} was revoked, closing its connection", id);
+                        let notice = Self::error_event(
+                            "session_ended",
+                            AppError::new(ErrorCode::SessionExpired, "Session revoked").with_context("session", id),
+                        );
+                        if let Ok(json_str) = serde_json::to_string(&notice) {
+                            let message = Self::outgoing(&session, &mut stats, json_str);
+                            if ws_timeouts::write_within(timeouts.write, sink.send(message)).await.is_ok() {
+                                stats.messages_sent += 1;
+                            }
+                        }
+                        Self::close_with(&mut sink, timeouts.write, CloseReason::SessionEnded).await;
+                        Self::transition_state(&mut state, ConnectionState::Closing, &mut stats, Some("Session revoked".to_string()));
+                        break;
+                    }
+                }
+                _ = ws_timeouts::until(timeouts.idle.map(|idle| tokio::time::Instant::from_std(last_activity) + idle)) => {
+                    if let Some(idle) = timeouts.idle.filter(|&idle| last_activity.elapsed() >= idle) {
+                        warn!("Connection idle for {} seconds, closing", idle.as_secs());
                         stats.errors_count += 1;
                         Self::transition_state(&mut state, ConnectionState::Closing, &mut stats, Some("Idle timeout".to_string()));
                         break;
                     }
                 }
+                // Acknowledgements overdue; sent again below
+                _ = ws_timeouts::until(outbox.next_retry().map(tokio::time::Instant::from_std)) => {}
+                _ = ws_timeouts::until(lifetime_end) => {
+                    info!("Connection open for {:?}, closing so the client reconnects", timeouts.max_lifetime.unwrap_or_default());
+                    Self::close_with(&mut sink, timeouts.write, CloseReason::LifetimeReached).await;
+                    Self::transition_state(&mut state, ConnectionState::Closing, &mut stats, Some(CloseReason::LifetimeReached.message().to_string()));
+                    break;
+                }
             };
 
+            // Events a resuming client missed, right after the `hello` answer
+            let mut replay_failed = false;
+            for event in replay.take() {
+                if !session.has(protocol::EVENTS)
+                    || !watches.forwards(&event.name, &event.payload)
+                    || !fs_watches.forwards(&event.name, &event.payload)
+                    || !subscriptions.forwards(&event.name, &event.payload)
+                    || outbox.covers(&event.name)
+                {
+                    continue;
+                }
+                let msg = Self::outgoing_shared(&session, &mut stats, event.text(), || event.deflated());
+                if let Err(e) = ws_timeouts::write_within(timeouts.write, sink.send(msg)).await {
+                    error!("Error replaying missed events: {}", e);
+                    stats.errors_count += 1;
+                    Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::SendError(e.to_string())), &mut stats, Some(e.to_string()));
+                    replay_failed = true;
+                    break;
+                }
+                stats.messages_sent += 1;
+            }
+            if replay_failed {
+                break;
+            }
+
+            // Reliable events not yet sent on this connection or not acknowledged in time
+            let mut delivery_failed = false;
+            for (seq, event) in outbox.due() {
+                if !watches.forwards(&event.name, &event.payload)
+                    || !fs_watches.forwards(&event.name, &event.payload)
+                    || !subscriptions.forwards(&event.name, &event.payload)
+                {
+                    outbox.ack(seq);
+                    continue;
+                }
+                let msg = Self::outgoing(&session, &mut stats, event.sequenced(seq));
+                if let Err(e) = ws_timeouts::write_within(timeouts.write, sink.send(msg)).await {
+                    error!("Error delivering {} #{}: {}", event.name, seq, e);
+                    stats.errors_count += 1;
+                    Self::transition_state(&mut state, ConnectionState::Error(ConnectionError::SendError(e.to_string())), &mut stats, Some(e.to_string()));
+                    delivery_failed = true;
+                    break;
+                }
+                stats.messages_sent += 1;
+            }
+            if delivery_failed {
+                break;
+            }
+
             // Check if we should break out of the loop due to an error
             if matches!(state, ConnectionState::Error(_) | ConnectionState::Closing | ConnectionState::Closed | ConnectionState::Terminated) {
                 if !matches!(state, ConnectionState::Error(_)) {
@@ -644,16 +1034,24 @@ impl WebSocketHandler {
         info!("Closing WebSocket connection, final state: {:?}", state);
         info!("Connection stats: messages_sent={}, messages_received={}, bytes_sent={}, bytes_received={}, errors={}, uptime={:?}", 
             stats.messages_sent, stats.messages_received, stats.bytes_sent, stats.bytes_received, stats.errors_count, stats.created_at.elapsed());
+        if stats.compressed_messages > 0 {
+            info!("Compression stats: compressed={}, uncompressed={}, bytes_in={}, bytes_out={}",
+                stats.compressed_messages, stats.uncompressed_messages, stats.compression_bytes_in, stats.compression_bytes_out);
+        }
 
         // Attempt to send a close frame if we're not already in an error state
         if !matches!(state, ConnectionState::Error(_)) {
-            if let Err(e) = sink.close().await {
+            if let Err(e) = ws_timeouts::write_within(timeouts.write, sink.close()).await {
                 warn!("Error closing WebSocket sink: {}", e);
             }
         }
 
-        // Cancel the event forwarder task
-        event_forwarder_handle.abort();
+        // The session's events are kept for a while, in case the client comes back
+        replay.detach();
+        outbox.detach();
+        presence::leave(&mut registration).await;
+        drop(events);
+        metrics_sampler().set_clients(ws_fanout::connections());
 
         // Notify that connection is closing
         connection_notify.notify_waiters();
@@ -671,140 +1069,759 @@ impl WebSocketHandler {
         Ok(())
     }
 
-    async fn handle_function_call(name: &str, payload: &Value) -> Option<Value> {
-        match name {
-            "get_users" => {
-                match DATABASE.try_lock() {
-                    Ok(db_guard) => {
-                        if let Some(ref db) = *db_guard {
-                            match db.get_all_users() {
-                                Ok(users) => {
-                                    debug!("Successfully retrieved {} users", users.len());
-                                    Some(serde_json::json!({
-                                        "success": true,
-                                        "data": users
-                                    }))
-                                }
-                                Err(e) => {
-                                    error!("Error retrieving users: {}", e);
-                                    Some(serde_json::json!({
-                                        "success": true,
-                                        "data": [], // Return empty array instead of error to prevent UI issues
-                                        "error": e.to_string()
-                                    }))
-                                }
-                            }
-                        } else {
-                            error!("Database not available in get_users");
-                            Some(serde_json::json!({
-                                "success": true,
-                                "data": [],
-                                "error": "Database not available"
-                            }))
-                        }
-                    }
-                    Err(_) => {
-                        error!("Could not acquire database lock for get_users");
-                        Some(serde_json::json!({
-                            "success": true,
-                            "data": [],
-                            "error": "Database busy"
-                        }))
+    /// Answer a binary file frame (`file.upload_chunk`) with a regular response
+    async fn handle_frame(frame: &[u8]) -> WebSocketEvent {
+        let (header, data) = match file_transfer::decode_frame::<WebSocketEvent>(frame, message_limits()) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                warn!("Rejected binary frame: {}", e);
+                return Self::error_event(
+                    "frame_error",
+                    AppError::new(ErrorCode::SerializationError, e.to_string()).with_context("error_type", "FRAME_DECODE_ERROR"),
+                );
+            }
+        };
+        debug!("Received {} frame with {} bytes", header.name, data.len());
+
+        let payload = match header.name.as_str() {
+            file_transfer::UPLOAD_CHUNK => match command_schemas::validate(&header.name, &header.payload) {
+                Ok(()) => Self::normalize_failure(&header.name, file_transfer::handle_chunk(&header.payload, data).await),
+                Err(e) => GlobalErrorHandler::to_json_response(&e),
+            },
+            other => GlobalErrorHandler::to_json_response(
+                &AppError::new(ErrorCode::HandlerError, format!("Unknown binary frame: {}", other))
+                    .with_context("function", other),
+            ),
+        };
+        WebSocketEvent {
+            id: header.id,
+            name: header.name,
+            payload,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64,
+            source: "backend".to_string(),
+            mutation_id: None,
+        }
+    }
+
+    /// Frame for an outgoing JSON message: deflated when the session agreed on it and the
+    /// message reaches `[server] compression_threshold`, text otherwise
+    fn outgoing(session: &Session, stats: &mut ConnectionStats, json: String) -> tungstenite::Message {
+        let json = tungstenite::Utf8Bytes::from(json);
+        let text = json.clone();
+        Self::outgoing_shared(session, stats, json, || compression::deflate(text.as_bytes()).map(tungstenite::Bytes::from))
+    }
+
+    /// `outgoing` for a frame that may be shared with other connections, with
+    /// `deflate` giving its compressed form if the session needs it
+    fn outgoing_shared(
+        session: &Session,
+        stats: &mut ConnectionStats,
+        json: tungstenite::Utf8Bytes,
+        deflate: impl FnOnce() -> std::io::Result<tungstenite::Bytes>,
+    ) -> tungstenite::Message {
+        if session.compression.as_deref() == Some(compression::DEFLATE) {
+            if json.len() < AppConfig::global().get_ws_compression_threshold() {
+                stats.uncompressed_messages += 1;
+            } else {
+                match deflate() {
+                    Ok(compressed) => {
+                        stats.compressed_messages += 1;
+                        stats.compression_bytes_in += json.len() as u64;
+                        stats.compression_bytes_out += compressed.len() as u64;
+                        stats.bytes_sent += compressed.len() as u64;
+                        return tungstenite::Message::Binary(compressed);
                     }
+                    Err(e) => warn!("Failed to compress message, sending it as is: {}", e),
                 }
             }
-            "get_db_stats" => {
-                match DATABASE.try_lock() {
-                    Ok(db_guard) => {
-                        if let Some(ref db) = *db_guard {
-                            match db.get_db_stats() {
-                                Ok(stats) => {
-                                    debug!("Successfully retrieved database stats");
-                                    Some(serde_json::json!({
-                                        "success": true,
-                                        "stats": stats
-                                    }))
+        }
+        stats.bytes_sent += json.len() as u64;
+        tungstenite::Message::Text(json)
+    }
+
+    /// Send `event` on its own rather than as the response to a message
+    async fn send_event<S>(
+        sink: &mut S,
+        session: &Session,
+        stats: &mut ConnectionStats,
+        limit: Duration,
+        event: &WebSocketEvent,
+    ) -> tungstenite::Result<()>
+    where
+        S: futures_util::Sink<tungstenite::Message, Error = tungstenite::Error> + Unpin,
+    {
+        let json = serde_json::to_string(event).map_err(|e| tungstenite::Error::Io(std::io::Error::other(e)))?;
+        let message = Self::outgoing(session, stats, json);
+        ws_timeouts::write_within(limit, sink.send(message)).await?;
+        stats.messages_sent += 1;
+        Ok(())
+    }
+
+    /// Answer the commands bound to this connection (`hello`, `session.current`,
+    /// `session.set_locale`, `settings.watch`, `state.subscribe`, `presence.register`, `file.download`,
+    /// `watch_path`, `ack`) or pass the command on, then whether to close the connection
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_message(
+        session: &mut Session,
+        user_session: &mut Option<UserSession>,
+        watches: &mut settings::Watches,
+        fs_watches: &mut fs_watcher::Owned,
+        subscriptions: &mut state_sync::Subscriptions,
+        registration: &mut presence::Registration,
+        replay: &mut ConnectionReplay,
+        outbox: &mut ConnectionOutbox,
+        client: Option<&str>,
+        frames: &mpsc::Sender<Vec<u8>>,
+        name: &str,
+        payload: &Value,
+    ) -> (Option<Value>, Option<CloseReason>) {
+        if name == ws_delivery::ACK {
+            match payload.get("seq").and_then(Value::as_u64) {
+                Some(seq) if outbox.ack(seq) => trace!("Client acknowledged #{}", seq),
+                _ => debug!("Ignoring ack of nothing outstanding: {}", payload),
+            }
+            return (None, None);
+        }
+        recorder::record(client, name, payload);
+        if name == protocol::HELLO {
+            return match protocol::negotiate(payload) {
+                Ok(negotiated) => {
+                    info!(
+                        protocol_version = negotiated.protocol_version,
+                        format = %negotiated.format,
+                        features = ?negotiated.features,
+                        "Client protocol negotiated"
+                    );
+                    let mut response = protocol::hello_response(&negotiated, payload);
+                    *session = negotiated;
+                    let sessions = SessionManager::global();
+                    let resume_token = payload.get("resume_token").and_then(Value::as_str);
+                    // Saying hello again without a token keeps the connection's live session
+                    let current = user_session
+                        .take()
+                        .filter(|_| resume_token.is_none())
+                        .and_then(|mut current| sessions.touch(&mut current).unwrap_or(false).then_some(current));
+                    let opened = match current {
+                        Some(session) => Ok(OpenedSession { session, resume_token: None }),
+                        None => sessions.open(resume_token, client.map(String::from)),
+                    };
+                    match opened {
+                        Ok(OpenedSession { session: mut opened, resume_token: new_token }) => {
+                            // A resumed session keeps the locale it has
+                            let requested = payload.get("locale").and_then(Value::as_str).filter(|_| opened.locale.is_none());
+                            if let Some(requested) = requested {
+                                match Catalog::global().negotiate(requested) {
+                                    Some(locale) => {
+                                        if let Err(e) = sessions.set_locale(&mut opened, Some(locale)) {
+                                            warn!("Could not keep the session locale: {}", e);
+                                        }
+                                    }
+                                    None => debug!("No messages in {}, keeping the default locale", requested),
                                 }
-                                Err(e) => {
-                                    error!("Error retrieving database stats: {}", e);
-                                    Some(serde_json::json!({
-                                        "success": true,
-                                        "stats": { "users": 0, "tables": [] },
-                                        "error": e.to_string()
-                                    }))
+                            }
+                            if let Some(last_event_id) = payload.get("resume_from").and_then(Value::as_str) {
+                                // A session that could not be continued missed everything
+                                let resumed = if new_token.is_none() {
+                                    ResumeBuffers::global().resume(&opened.id, last_event_id)
+                                } else {
+                                    Resume::Refresh("session_ended")
+                                };
+                                response["resume"] = resumed.to_json();
+                                if let Resume::Replay(events) = resumed {
+                                    replay.queue(events);
                                 }
                             }
-                        } else {
-                            error!("Database not available in get_db_stats");
-                            Some(serde_json::json!({
-                                "success": true,
-                                "stats": { "users": 0, "tables": [] },
-                                "error": "Database not available"
-                            }))
+                            replay.attach(&opened.id);
+                            if session.has(protocol::EVENTS) {
+                                outbox.attach(&opened.id);
+                            }
+                            response["session"] = serde_json::to_value(&opened).unwrap_or_default();
+                            if let Some(token) = new_token {
+                                response["resume_token"] = token.into();
+                            }
+                            *user_session = Some(opened);
                         }
+                        Err(e) => warn!("Could not open a session: {}", e),
                     }
-                    Err(_) => {
-                        error!("Could not acquire database lock for get_db_stats");
-                        Some(serde_json::json!({
-                            "success": true,
-                            "stats": { "users": 0, "tables": [] },
-                            "error": "Database busy"
-                        }))
-                    }
+                    response["locale"] = Self::locale(user_session).into();
+                    (Some(response), None)
                 }
-            }
-            "ui.ready" => {
-                // Handle UI ready event from frontend
-                debug!("UI ready event received from frontend: {:?}", payload);
-                
-                // Emit backend connected event to notify frontend that backend is ready
-                let event_bus = EventBus::global();
-                if let Err(e) = event_bus.emit_simple(
-                    "backend.connected",
-                    serde_json::json!({
-                        "message": "Backend connected and ready"
-                    }),
-                ).await {
-                    error!(error = %e, "Failed to emit backend connected event");
+                Err(e) => {
+                    warn!("Rejecting client: {}", e.message);
+                    (Some(GlobalErrorHandler::to_json_response(&e)), Some(CloseReason::UnsupportedProtocol))
                 }
+            };
+        }
+        let locale = user_session.as_ref().and_then(|current| current.locale.clone());
+        i18n::scope(locale, Self::answer(user_session, watches, fs_watches, subscriptions, registration, client, frames, name, payload)).await
+    }
 
-                Some(serde_json::json!({
-                    "success": true,
-                    "message": "UI ready event processed, backend connected"
-                }))
+    /// Everything but `hello` for `handle_message`, in the locale of the session
+    #[allow(clippy::too_many_arguments)]
+    async fn answer(
+        user_session: &mut Option<UserSession>,
+        watches: &mut settings::Watches,
+        fs_watches: &mut fs_watcher::Owned,
+        subscriptions: &mut state_sync::Subscriptions,
+        registration: &mut presence::Registration,
+        client: Option<&str>,
+        frames: &mpsc::Sender<Vec<u8>>,
+        name: &str,
+        payload: &Value,
+    ) -> (Option<Value>, Option<CloseReason>) {
+        if let Err(e) = Self::track_session(user_session, client) {
+            return (Some(GlobalErrorHandler::to_json_response(&e)), Some(CloseReason::SessionEnded));
+        }
+        if name == "session.current" {
+            return (Some(serde_json::json!({ "success": true, "session": user_session })), None);
+        }
+        if name == "session.set_locale" {
+            let response = match command_schemas::validate(name, payload).and_then(|()| Self::set_locale(user_session, payload)) {
+                Ok(locale) => serde_json::json!({ "success": true, "locale": locale, "session": user_session }),
+                Err(e) => GlobalErrorHandler::to_json_response(&e),
+            };
+            return (Some(response), None);
+        }
+        if name == "settings.watch" || name == "settings.unwatch" {
+            let response = match command_schemas::validate(name, payload) {
+                Ok(()) => Self::normalize_failure(name, settings::handle_watch(name, payload, watches).await),
+                Err(e) => GlobalErrorHandler::to_json_response(&e),
+            };
+            return (Some(response), None);
+        }
+        if name == "state.subscribe" || name == "state.unsubscribe" {
+            let response = match command_schemas::validate(name, payload) {
+                Ok(()) => Self::normalize_failure(name, state_sync::handle_subscription(name, payload, subscriptions).await),
+                Err(e) => GlobalErrorHandler::to_json_response(&e),
+            };
+            return (Some(response), None);
+        }
+        if name == "presence.register" {
+            let response = match command_schemas::validate(name, payload) {
+                Ok(()) => {
+                    let session = user_session.as_ref().map(|current| current.id.as_str());
+                    presence::register(payload, registration, session, client).await
+                }
+                Err(e) => GlobalErrorHandler::to_json_response(&e),
+            };
+            return (Some(response), None);
+        }
+        if matches!(name, "watch_path" | "unwatch" | "list_watches") {
+            let response = match command_schemas::validate(name, payload) {
+                Ok(()) => Self::normalize_failure(name, fs_watcher::handle_command(name, payload, fs_watches)),
+                Err(e) => GlobalErrorHandler::to_json_response(&e),
+            };
+            return (Some(response), None);
+        }
+        if name == file_transfer::DOWNLOAD {
+            let response = match command_schemas::validate(name, payload) {
+                Ok(()) => Self::normalize_failure(name, file_transfer::start_download(payload, frames.clone())),
+                Err(e) => GlobalErrorHandler::to_json_response(&e),
+            };
+            return (Some(response), None);
+        }
+        if let Err(e) = Self::check_admin(name, payload) {
+            return (Some(GlobalErrorHandler::to_json_response(&e)), None);
+        }
+        (Self::handle_function_call(name, payload).await, None)
+    }
+
+    /// Refuse an `ADMIN_COMMANDS` entry whose payload carries no valid `admin_token`
+    fn check_admin(name: &str, payload: &Value) -> AppResult<()> {
+        if !ADMIN_COMMANDS.contains(&name) {
+            return Ok(());
+        }
+        let token = payload.get("admin_token").and_then(Value::as_str);
+        if token.is_some_and(devtools::accepts_admin) {
+            return Ok(());
+        }
+        warn!("Refused {} without a valid admin token", name);
+        Err(AppError::new(ErrorCode::AccessDenied, format!("{} needs the DevTools token or an admin's API token as admin_token", name))
+            .with_context("command", name))
+    }
+
+    /// Locale of the messages for `user_session`
+    fn locale(user_session: &Option<UserSession>) -> String {
+        let catalog = Catalog::global();
+        user_session
+            .as_ref()
+            .and_then(|current| current.locale.clone())
+            .unwrap_or_else(|| catalog.default_locale().to_string())
+    }
+
+    /// `session.set_locale { locale }`: keep an available locale with the session, or the default for `null`
+    fn set_locale(user_session: &mut Option<UserSession>, payload: &Value) -> AppResult<String> {
+        let catalog = Catalog::global();
+        let locale = match payload.get("locale").and_then(Value::as_str) {
+            Some(requested) => Some(catalog.negotiate(requested).ok_or_else(|| {
+                AppError::new(ErrorCode::ValidationFailed, format!("No messages in locale {}", requested))
+                    .with_context("locales", catalog.locales())
+            })?),
+            None => None,
+        };
+        let current = guards::require(user_session.as_mut(), ErrorCode::SessionExpired, "No session")?;
+        SessionManager::global()
+            .set_locale(current, locale)
+            .map_err(|e| AppError::new(ErrorCode::DatabaseError, "Could not keep the session locale").with_cause(e.to_string()))?;
+        Ok(Self::locale(user_session))
+    }
+
+    /// Open a session with the first message of a client that skipped `hello`, record
+    /// activity on later ones; fails once the session has expired or was revoked
+    fn track_session(user_session: &mut Option<UserSession>, client: Option<&str>) -> AppResult<()> {
+        let sessions = SessionManager::global();
+        let result = match user_session.as_mut() {
+            Some(current) => sessions.touch(current),
+            None => sessions.open(None, client.map(String::from)).map(|opened| {
+                *user_session = Some(opened.session);
+                true
+            }),
+        };
+        match result {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                let id = user_session.take().map(|ended| ended.id).unwrap_or_default();
+                Err(AppError::new(ErrorCode::SessionExpired, "Session expired").with_context("session", id))
             }
-            "window_state_change" | "window.state.change" => {
-                // Handle window state change events from frontend
-                debug!("Window state change received: {:?}", payload);
-
-                // Use the window logger to handle the state change
-                let logger = window_logger();
-                let payload_clone = payload.clone(); // Clone the payload to move into async block
-                tokio::spawn(async move {
-                    logger.log_window_state_change(&payload_clone).await;
+            Err(e) => {
+                // Sessions are bookkeeping; a database problem should not cut the client off
+                warn!("Could not record session activity: {}", e);
+                Ok(())
+            }
+        }
+    }
+
+    /// Send the close frame for `reason`, giving up after `limit`
+    async fn close_with<S>(sink: &mut S, limit: Duration, reason: CloseReason)
+    where
+        S: futures_util::Sink<tungstenite::Message, Error = tungstenite::Error> + Unpin,
+    {
+        let frame = tungstenite::protocol::CloseFrame {
+            code: reason.code(),
+            reason: reason.message().into(),
+        };
+        if ws_timeouts::write_within(limit, sink.send(tungstenite::Message::Close(Some(frame)))).await.is_err() {
+            debug!("Client went away before the close frame");
+        }
+    }
+
+    /// Every built-in command; plugin commands are looked up after these
+    fn commands() -> &'static CommandRegistry {
+        static COMMANDS: OnceLock<CommandRegistry> = OnceLock::new();
+        COMMANDS.get_or_init(|| {
+            let registry = CommandRegistry::new()
+                .register("get_users", Self::get_users)
+                .register("create_user", Self::create_user)
+                .register("update_user", Self::update_user)
+                .register("delete_user", Self::delete_user)
+                .register("get_db_stats", Self::get_db_stats)
+                .register("presence.list", |_: NoParams| async { Ok(PresenceResponse { clients: Presence::global().list() }) })
+                .register("ui.ready", Self::ui_ready)
+                .register("window_state_change", Self::window_state_change)
+                .register("window.state.change", Self::window_state_change)
+                .register_module(&["get_system_info"], |_, _| async { crate::infrastructure::system_info::get_system_info_response() })
+                .register_module(&["clipboard.read_text", "clipboard.write_text"], |name, payload| async move {
+                    crate::infrastructure::clipboard::handle_command(name, &payload).await
+                })
+                .register_module(&["dialog.open_file", "dialog.open_folder", "dialog.save_file"], |name, payload| async move {
+                    crate::infrastructure::dialog::handle_command(name, &payload).await
+                })
+                .register_module(
+                    &["counter.create", "counter.list", "counter.events", "counter.increment", "counter.decrement", "counter.reset"],
+                    |name, payload| async move { crate::infrastructure::counters::handle_command(name, &payload).await },
+                )
+                .register_module(&["task.list", "task.get", "task.cancel"], |name, payload| async move {
+                    crate::infrastructure::tasks::handle_command(name, &payload)
+                })
+                .register_module(&["images.organize"], |name, payload| async move {
+                    crate::infrastructure::image_organizer::handle_command(name, &payload).await
+                })
+                .register_module(&["metrics.start", "metrics.stop", "metrics.status"], |name, payload| async move {
+                    crate::infrastructure::metrics::handle_command(name, &payload)
+                })
+                .register_module(&["scheduler.list", "scheduler.history", "scheduler.run_now"], |name, payload| async move {
+                    crate::infrastructure::scheduler::handle_command(name, &payload).await
+                })
+                .register_module(&["shell.open_url", "shell.open_path", "shell.reveal"], |name, payload| async move {
+                    crate::infrastructure::shell::handle_command(name, &payload)
+                })
+                .register_module(&["session.list", "session.revoke"], |name, payload| async move {
+                    crate::infrastructure::sessions::handle_command(name, &payload)
+                })
+                .register_module(&["token.issue"], |name, payload| async move { crate::infrastructure::jwt::handle_command(name, &payload).await })
+                .register_module(
+                    &["auth.request_password_reset", "auth.reset_password", "auth.request_email_verification", "auth.verify_email"],
+                    |name, payload| async move { crate::infrastructure::auth::handle_command(name, &payload).await },
+                )
+                .register_module(&["settings.get", "settings.set"], |name, payload| async move { settings::handle_command(name, &payload).await })
+                .register_module(&["flags.get_all", "flags.set"], |name, payload| async move {
+                    crate::infrastructure::flags::handle_command(name, &payload).await
+                })
+                .register_module(&["undo", "redo", "history.list"], |name, payload| async move { history::handle_command(name, &payload).await })
+                .register_module(&["outbox.status"], |name, payload| async move { crate::infrastructure::outbox::handle_command(name, &payload) })
+                .register_module(&["analytics.summary"], |name, payload| async move {
+                    crate::infrastructure::analytics::handle_command(name, &payload).await
+                })
+                .register_module(&["os.integration", "os.set_launch_at_login"], |name, payload| async move {
+                    crate::infrastructure::os_integration::handle_command(name, &payload).await
+                })
+                .register_module(&["document.export_pdf"], |name, payload| async move {
+                    crate::infrastructure::pdf::handle_command(name, &payload).await
+                })
+                .register_module(&["plugins.list"], |_, _| async { crate::plugins::host::list() })
+                .register_module(&["schemas.get"], |name, payload| async move { crate::infrastructure::schema::handle_command(name, &payload) })
+                .register_module(&[file_transfer::UPLOAD_BEGIN, file_transfer::UPLOAD_END], |name, payload| async move {
+                    file_transfer::handle_command(name, &payload).await
                 });
 
-                // Return success response
-                Some(serde_json::json!({
-                    "success": true,
-                    "message": "Window state change logged"
-                }))
+            // Commands of features left out of the build still answer, with why they can't
+            let peers = &["peers.list"];
+            #[cfg(feature = "discovery")]
+            let registry = registry.register_module(peers, |name, payload| async move {
+                crate::infrastructure::discovery::handle_command(name, &payload)
+            });
+            #[cfg(not(feature = "discovery"))]
+            let registry = registry.register_module(peers, |_, _| Self::built_without("discovery"));
+
+            let telemetry = &["telemetry.status", "telemetry.set_consent"];
+            #[cfg(feature = "telemetry")]
+            let registry = registry.register_module(telemetry, |name, payload| async move {
+                crate::infrastructure::telemetry::handle_command(name, &payload)
+            });
+            #[cfg(not(feature = "telemetry"))]
+            let registry = registry.register_module(telemetry, |_, _| Self::built_without("telemetry"));
+
+            let rekey = &["db.rekey"];
+            #[cfg(feature = "encrypted-db")]
+            let registry = registry.register_module(rekey, |name, payload| async move {
+                crate::infrastructure::database::encryption::handle_command(name, &payload)
+            });
+            #[cfg(not(feature = "encrypted-db"))]
+            let registry = registry.register_module(rekey, |_, _| Self::built_without("encrypted-db"));
+
+            let broker = &["broker.status"];
+            #[cfg(feature = "broker")]
+            let registry = registry.register_module(broker, |name, payload| async move {
+                crate::infrastructure::broker::handle_command(name, &payload)
+            });
+            #[cfg(not(feature = "broker"))]
+            let registry = registry.register_module(broker, |_, _| Self::built_without("broker"));
+
+            let collab = &["collab.sync", "collab.update"];
+            #[cfg(feature = "collab")]
+            let registry = registry.register_module(collab, |name, payload| async move {
+                crate::infrastructure::collab::handle_command(name, &payload).await
+            });
+            #[cfg(not(feature = "collab"))]
+            let registry = registry.register_module(collab, |_, _| Self::built_without("collab"));
+
+            let hotkeys = &["hotkeys.list", "hotkeys.register", "hotkeys.unregister"];
+            #[cfg(feature = "hotkeys")]
+            let registry = registry.register_module(hotkeys, |name, payload| async move {
+                crate::infrastructure::hotkeys::handle_command(name, &payload).await
+            });
+            #[cfg(not(feature = "hotkeys"))]
+            let registry = registry.register_module(hotkeys, |_, _| Self::built_without("hotkeys"));
+
+            let capture = &["capture.window", "capture.screen", "capture.confirm"];
+            #[cfg(feature = "capture")]
+            let registry = registry.register_module(capture, |name, payload| async move {
+                crate::infrastructure::capture::handle_command(name, &payload).await
+            });
+            #[cfg(not(feature = "capture"))]
+            let registry = registry.register_module(capture, |_, _| Self::built_without("capture"));
+
+            let sound = &["sound.play", "sound.list", "sound.set_volume", "sound.set_muted"];
+            #[cfg(feature = "media")]
+            let registry = registry.register_module(sound, |name, payload| async move {
+                crate::infrastructure::media::handle_command(name, &payload).await
+            });
+            #[cfg(not(feature = "media"))]
+            let registry = registry.register_module(sound, |_, _| Self::built_without("media"));
+
+            let webhooks = &["webhooks.register", "webhooks.list", "webhooks.remove", "webhooks.deliveries"];
+            #[cfg(feature = "webhooks")]
+            let registry = registry.register_module(webhooks, |name, payload| async move {
+                crate::infrastructure::webhooks::handle_command(name, &payload)
+            });
+            #[cfg(not(feature = "webhooks"))]
+            let registry = registry.register_module(webhooks, |_, _| Self::built_without("webhooks"));
+
+            let update = &["update.status", "update.check", "update.install"];
+            #[cfg(feature = "updater")]
+            let registry = registry.register_module(update, |name, payload| async move {
+                crate::infrastructure::updater::handle_command(name, &payload).await
+            });
+            #[cfg(not(feature = "updater"))]
+            let registry = registry.register_module(update, |_, _| Self::built_without("updater"));
+
+            registry
+        })
+    }
+
+    #[cfg_attr(
+        all(
+            feature = "discovery",
+            feature = "telemetry",
+            feature = "encrypted-db",
+            feature = "broker",
+            feature = "collab",
+            feature = "hotkeys",
+            feature = "capture",
+            feature = "media",
+            feature = "webhooks",
+            feature = "updater"
+        ),
+        allow(dead_code)
+    )]
+    async fn built_without(feature: &'static str) -> Value {
+        serde_json::json!({
+            "success": false,
+            "error": format!("Built without the {} feature", feature)
+        })
+    }
+
+    /// Tell the frontend the backend is ready for it
+    async fn ui_ready(payload: Value) -> AppResult<MessageResponse> {
+        debug!("UI ready event received from frontend: {:?}", payload);
+        if let Err(e) = EventBus::global()
+            .emit_simple("backend.connected", serde_json::json!({ "message": "Backend connected and ready" }))
+            .await
+        {
+            error!(error = %e, "Failed to emit backend connected event");
+        }
+        Ok(MessageResponse { message: "UI ready event processed, backend connected" })
+    }
+
+    /// Logged in the background, the frontend doesn't wait for it
+    async fn window_state_change(payload: Value) -> AppResult<MessageResponse> {
+        debug!("Window state change received: {:?}", payload);
+        let logger = window_logger();
+        tokio::spawn(async move {
+            logger.log_window_state_change(&payload).await;
+        });
+        Ok(MessageResponse { message: "Window state change logged" })
+    }
+
+    async fn get_users(request: GetUsersRequest) -> AppResult<UsersResponse> {
+        let query = request.query();
+        let load = || circuit_breaker("database").call(|| Self::database()?.find_users(&query).map(Value::from).map_err(database_error));
+        // Only the full listing is cached
+        let data = if query == UserQuery::default() { QueryCache::global().get_or_load(cache::USERS, load)? } else { load()? };
+        debug!("Retrieved {} users", data.as_array().map_or(0, Vec::len));
+        Ok(UsersResponse { data })
+    }
+
+    /// `version` is the one the client last read
+    async fn update_user(request: UpdateUserRequest) -> AppResult<UserResponse> {
+        let UpdateUserRequest { id, version, changes } = request;
+        let (before, user) = circuit_breaker("database").call(|| {
+            let db = Self::database()?;
+            let before = db.get_user(id).map_err(database_error)?;
+            Ok((before, db.update_user(id, &changes, version)?))
+        })?;
+        Self::users_changed();
+        if let (Some(from), Some(to)) = (before.as_ref().and_then(UserRecord::from_value), UserRecord::from_value(&user)) {
+            if from != to {
+                history::record(format!("update_user {}", id), Operation::UpdateUser { from, to }).await;
             }
-            _ => {
-                warn!("Unknown function called: {}", name);
-                // For unknown function calls, return an error response
-                Some(serde_json::json!({
-                    "success": false,
-                    "error": format!("Unknown function: {}", name),
-                    "function": name
-                }))
+        }
+        Ok(UserResponse { data: user })
+    }
+
+    /// The role defaults to `user`
+    async fn create_user(request: CreateUserRequest) -> AppResult<UserResponse> {
+        let role = request.role.as_deref().unwrap_or("user");
+        let user = circuit_breaker("database").call(|| Self::database()?.create_user(None, &request.name, &request.email, role))?;
+        Self::users_changed();
+        if let Some(user) = UserRecord::from_value(&user) {
+            history::record(format!("create_user {}", user.id), Operation::CreateUser { user }).await;
+        }
+        Ok(UserResponse { data: user })
+    }
+
+    /// `version` is the one the client last read
+    async fn delete_user(request: DeleteUserRequest) -> AppResult<UserResponse> {
+        let DeleteUserRequest { id, version } = request;
+        let user = circuit_breaker("database").call(|| Self::database()?.delete_user(id, version))?;
+        Self::users_changed();
+        if let Some(user) = UserRecord::from_value(&user) {
+            history::record(format!("delete_user {}", id), Operation::DeleteUser { user }).await;
+        }
+        Ok(UserResponse { data: user })
+    }
+
+    /// Don't wait for the relay's data.changed: the caller may read right away
+    fn users_changed() {
+        QueryCache::global().invalidate();
+    }
+
+    async fn get_db_stats(_: NoParams) -> AppResult<DbStatsResponse> {
+        let stats = QueryCache::global().get_or_load(cache::DB_STATS, || {
+            circuit_breaker("database").call(|| {
+                let repository = SqliteDatabaseStatsRepository::new(Self::database()?);
+                let stats = futures::executor::block_on(repository.get_stats())?;
+                Ok(serde_json::json!({ "users": stats.users_count, "tables": stats.tables }))
+            })
+        })?;
+        Ok(DbStatsResponse { stats })
+    }
+
+    /// The shared database; the lock is only held long enough to clone the handle
+    fn database() -> AppResult<Arc<Database>> {
+        Chaos::global().delay_db();
+        crate::infrastructure::database::database()
+    }
+
+    /// Transport-level failure sent as a `backend.error` event
+    fn error_event(id: &str, error: AppError) -> WebSocketEvent {
+        WebSocketEvent {
+            id: id.to_string(),
+            name: "backend.error".to_string(),
+            payload: GlobalErrorHandler::to_json_response(&error),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64,
+            source: "backend".to_string(),
+            mutation_id: None,
+        }
+    }
+
+    /// Wrap handler responses of the form `{ success: false, error: "..." }`
+    /// in the standard error envelope; extra fields move into the error context
+    fn normalize_failure(name: &str, response: Value) -> Value {
+        if response.get("success").and_then(Value::as_bool) != Some(false) {
+            return response;
+        }
+        let Some(message) = response.get("error").and_then(Value::as_str) else {
+            return response;
+        };
+
+        let mut error = AppError::new(ErrorCode::CommandFailed, message).with_context("command", name);
+        if let Value::Object(fields) = &response {
+            for (key, value) in fields.iter().filter(|(key, _)| !matches!(key.as_str(), "success" | "error")) {
+                error = error.with_context(key.clone(), value.clone());
             }
         }
+        GlobalErrorHandler::to_json_response(&error)
+    }
+
+    /// Validate and run one command; shared with the IPC channel
+    pub(crate) async fn handle_function_call(name: &str, payload: &Value) -> Option<Value> {
+        let started = Instant::now();
+        let response = Self::run_function_call(name, payload).await;
+        if let Some(response) = &response {
+            let failed = response.get("success").and_then(Value::as_bool) == Some(false);
+            CommandMetrics::global().record(name, started.elapsed(), failed);
+        }
+        response
+    }
+
+    async fn run_function_call(name: &str, payload: &Value) -> Option<Value> {
+        if let Err(e) = command_schemas::validate(name, payload) {
+            debug!("Rejected {} payload: {}", name, e.message);
+            return Some(GlobalErrorHandler::to_json_response(&e));
+        }
+        if let Some(flag) = FeatureFlags::global().refusing(name) {
+            debug!("Refused {}: the {} feature flag is off", name, flag);
+            return Some(GlobalErrorHandler::to_json_response(
+                &AppError::new(ErrorCode::ServiceUnavailable, format!("{} is turned off by the {} feature flag", name, flag))
+                    .with_context("command", name)
+                    .with_context("flag", flag),
+            ));
+        }
+        Analytics::global().command(name);
+        if let Some(e) = Chaos::global().handler_error(name) {
+            return Some(GlobalErrorHandler::to_json_response(&e));
+        }
+
+        Self::dispatch(name, payload)
+            .await
+            .map(|response| Self::normalize_failure(name, response))
+    }
+
+    async fn dispatch(name: &str, payload: &Value) -> Option<Value> {
+        if let Some(response) = Self::commands().call(name, payload).await {
+            return Some(response);
+        }
+        if let Some(response) = crate::plugins::host::handle_command(name, payload).await {
+            return Some(response);
+        }
+        warn!("Unknown function called: {}", name);
+        Some(GlobalErrorHandler::to_json_response(
+            &AppError::new(ErrorCode::HandlerError, format!("Unknown function: {}", name)).with_context("function", name),
+        ))
     }
 }
 
-pub async fn start_websocket_server(event_bus: Arc<EventBus>, port: u16) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+pub async fn start_websocket_server(event_bus: Arc<EventBus>, listener: std::net::TcpListener) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let handler = WebSocketHandler::new(event_bus);
-    let addr = format!("127.0.0.1:{}", port);
-    handler.start_server(&addr).await
-}
\ No newline at end of file
+    handler.start_server(listener).await
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_failures_are_wrapped() {
+        let response = WebSocketHandler::normalize_failure(
+            "watch_path",
+            serde_json::json!({ "success": false, "error": "Not a directory: /nope", "path": "/nope" }),
+        );
+        assert_eq!(response["error"]["code"], "CommandFailed");
+        assert_eq!(response["error"]["message"], "Not a directory: /nope");
+        assert_eq!(response["error"]["context"]["command"], "watch_path");
+        assert_eq!(response["error"]["context"]["path"], "/nope");
+
+        let ok = serde_json::json!({ "success": true, "watches": [] });
+        assert_eq!(WebSocketHandler::normalize_failure("list_watches", ok.clone()), ok);
+    }
+
+    #[test]
+    fn test_admin_commands_need_a_token() {
+        assert!(WebSocketHandler::check_admin("session.current", &Value::Null).is_ok());
+        let refused = WebSocketHandler::check_admin("session.list", &serde_json::json!({ "admin_token": "guess" })).unwrap_err();
+        assert_eq!(refused.code, ErrorCode::AccessDenied);
+        assert_eq!(WebSocketHandler::check_admin("session.revoke", &serde_json::json!({ "id": "x" })).unwrap_err().code, ErrorCode::AccessDenied);
+        assert_eq!(WebSocketHandler::check_admin("token.issue", &serde_json::json!({ "user_id": 1 })).unwrap_err().code, ErrorCode::AccessDenied);
+    }
+
+    #[test]
+    fn test_rekey_needs_a_token() {
+        let refused = WebSocketHandler::check_admin("db.rekey", &serde_json::json!({})).unwrap_err();
+        assert_eq!(refused.code, ErrorCode::AccessDenied);
+        assert_eq!(refused.context["command"], "db.rekey");
+    }
+
+    #[test]
+    fn test_update_install_needs_a_token() {
+        assert!(WebSocketHandler::check_admin("update.check", &Value::Null).is_ok());
+        let refused = WebSocketHandler::check_admin("update.install", &serde_json::json!({ "admin_token": "" })).unwrap_err();
+        assert_eq!(refused.code, ErrorCode::AccessDenied);
+    }
+
+    #[test]
+    fn test_webhook_commands_need_a_token() {
+        let register = serde_json::json!({ "url": "https://example.com/hook", "events": ["*"] });
+        assert_eq!(WebSocketHandler::check_admin("webhooks.register", &register).unwrap_err().code, ErrorCode::AccessDenied);
+        for name in ["webhooks.list", "webhooks.remove", "webhooks.deliveries"] {
+            let refused = WebSocketHandler::check_admin(name, &serde_json::json!({ "admin_token": "guess" })).unwrap_err();
+            assert_eq!(refused.code, ErrorCode::AccessDenied, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_catalog_commands_are_registered() {
+        // Answered by the bundled system_info and window_management plugins
+        let plugin_commands = ["sysinfo.get", "window.open", "window.close", "window.set_title"];
+        for command in crate::viewmodel::command_catalog::commands() {
+            let name = command.name.as_str();
+            let answered = CONNECTION_COMMANDS.contains(&name) || plugin_commands.contains(&name);
+            assert!(answered || WebSocketHandler::commands().handles(name), "{}", name);
+        }
+    }
+}
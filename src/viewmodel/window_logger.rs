@@ -1,8 +1,15 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing::{info, warn, debug};
+use tracing::{info, warn, debug, error};
 use serde_json::Value;
+use crate::infrastructure::analytics::Analytics;
+use crate::infrastructure::database::database;
+use crate::model::core::WindowState;
+
+/// Identifier the bridge script uses when reporting the native application window
+pub const MAIN_WINDOW_ID: &str = "main";
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -12,21 +19,46 @@ pub struct WindowInfo {
     pub focused: bool,
     pub minimized: bool,
     pub maximized: bool,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
     pub created_at: u64,
     pub last_activity: u64,
 }
 
+impl WindowInfo {
+    /// Geometry snapshot suitable for persistence, if the size is known
+    pub fn to_state(&self) -> Option<WindowState> {
+        Some(WindowState {
+            window_id: self.id.clone(),
+            x: self.x.unwrap_or(0),
+            y: self.y.unwrap_or(0),
+            width: self.width?,
+            height: self.height?,
+            maximized: self.maximized,
+        })
+    }
+}
+
 pub struct WindowLogger {
     windows: Arc<Mutex<HashMap<String, WindowInfo>>>,
+    persist_state: AtomicBool,
 }
 
 impl WindowLogger {
     pub fn new() -> Self {
         Self {
             windows: Arc::new(Mutex::new(HashMap::new())),
+            persist_state: AtomicBool::new(false),
         }
     }
 
+    /// Enable or disable saving window geometry to the database
+    pub fn set_persistence_enabled(&self, enabled: bool) {
+        self.persist_state.store(enabled, Ordering::Relaxed);
+    }
+
     pub async fn register_window(&self, id: String, title: String) {
         let mut windows = self.windows.lock().await;
         let now = std::time::SystemTime::now()
@@ -40,6 +72,10 @@ impl WindowLogger {
             focused: false,
             minimized: false,
             maximized: false,
+            x: None,
+            y: None,
+            width: None,
+            height: None,
             created_at: now,
             last_activity: now,
         };
@@ -124,6 +160,40 @@ impl WindowLogger {
         }
     }
 
+    pub async fn window_geometry_changed(&self, id: &str, payload: &Value) {
+        let mut windows = self.windows.lock().await;
+        if !windows.contains_key(id) && id == MAIN_WINDOW_ID {
+            // The native window is never announced with a "created" action
+            drop(windows);
+            self.register_window(id.to_string(), String::from("Main Window")).await;
+            windows = self.windows.lock().await;
+        }
+
+        if let Some(window) = windows.get_mut(id) {
+            let get_i32 = |key: &str| payload.get(key).and_then(|v| v.as_i64()).map(|v| v as i32);
+            let get_u32 = |key: &str| payload.get(key).and_then(|v| v.as_u64()).map(|v| v as u32);
+
+            window.x = get_i32("x").or(window.x);
+            window.y = get_i32("y").or(window.y);
+            window.width = get_u32("width").or(window.width);
+            window.height = get_u32("height").or(window.height);
+            if let Some(maximized) = payload.get("maximized").and_then(|v| v.as_bool()) {
+                window.maximized = maximized;
+            }
+            window.last_activity = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64;
+
+            debug!(
+                "Window geometry changed: {} ({}) {:?}x{:?} at {:?},{:?}",
+                window.title, id, window.width, window.height, window.x, window.y
+            );
+        } else {
+            warn!("Attempted to update geometry of non-existent window: {}", id);
+        }
+    }
+
     pub async fn window_closed(&self, id: &str) {
         let mut windows = self.windows.lock().await;
         if let Some(window) = windows.get(id) {
@@ -161,9 +231,14 @@ impl WindowLogger {
                     "minimized" => self.window_minimized(window_id).await,
                     "restored" => self.window_restored(window_id).await,
                     "maximized" => self.window_maximized(window_id).await,
-                    "closed" => self.window_closed(window_id).await,
+                    "resized" | "moved" => self.window_geometry_changed(window_id, payload).await,
+                    "closed" => {
+                        self.persist_window_state(window_id).await;
+                        self.window_closed(window_id).await
+                    }
                     "created" => {
                         if let Some(title) = payload.get("windowTitle").and_then(|v| v.as_str()) {
+                            Analytics::global().window(title);
                             self.register_window(window_id.to_string(), title.to_string()).await;
                         }
                     },
@@ -171,6 +246,31 @@ impl WindowLogger {
                         debug!("Unknown window action: {}", action);
                     }
                 }
+
+                if matches!(action, "resized" | "moved" | "maximized" | "restored") {
+                    self.persist_window_state(window_id).await;
+                }
+            }
+        }
+    }
+
+    /// Save the current geometry of a window so it can be restored on the next start
+    async fn persist_window_state(&self, id: &str) {
+        if !self.persist_state.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let state = {
+            let windows = self.windows.lock().await;
+            match windows.get(id).and_then(|w| w.to_state()) {
+                Some(state) => state,
+                None => return,
+            }
+        };
+
+        if let Ok(db) = database() {
+            if let Err(e) = db.save_window_state(&state) {
+                error!("Failed to persist window state for {}: {}", id, e);
             }
         }
     }
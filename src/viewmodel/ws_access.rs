@@ -0,0 +1,112 @@
+//! Who may open the WebSocket, checked during the upgrade request
+//!
+//! Browsers send an `Origin` with every WebSocket upgrade but do not apply
+//! CORS to it, so without a check any web page could drive the backend.
+//! Upgrades from a page are only accepted from the app's own HTTP origin
+//! and `[server] ws_allowed_origins`. Clients without an `Origin` are local
+//! tools rather than pages; `ws_require_token` additionally makes every
+//! client pass the per-run token, injected into index.html as
+//! `window.__WS_TOKEN__`, as the `token` query parameter.
+
+use std::sync::OnceLock;
+use tracing::warn;
+use crate::infrastructure::server::frontend_config;
+use crate::model::core::AppConfig;
+use crate::presentation::devtools::constant_time_eq;
+
+/// Why an upgrade was refused, with the HTTP status to answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub status: u16,
+    pub message: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct WsAccessPolicy {
+    allowed_origins: Vec<String>,
+    /// Generated per run; `None` when `ws_require_token = false`
+    token: Option<String>,
+}
+
+impl WsAccessPolicy {
+    pub fn new(http_port: u16, extra_origins: Vec<String>, token: Option<String>) -> Self {
+        let own = [format!("http://localhost:{}", http_port), format!("http://127.0.0.1:{}", http_port)];
+        Self {
+            allowed_origins: own
+                .into_iter()
+                .chain(extra_origins.into_iter().map(|origin| origin.trim_end_matches('/').to_string()))
+                .collect(),
+            token,
+        }
+    }
+
+    pub fn from_config(config: &AppConfig, http_port: u16) -> Self {
+        Self::new(
+            http_port,
+            config.get_ws_allowed_origins(),
+            config
+                .is_ws_token_required()
+                .then(|| uuid::Uuid::new_v4().simple().to_string()),
+        )
+    }
+
+    /// The policy for this run, once the HTTP port is known
+    pub fn global() -> &'static WsAccessPolicy {
+        static POLICY: OnceLock<WsAccessPolicy> = OnceLock::new();
+        POLICY.get_or_init(|| {
+            let config = AppConfig::global();
+            let http_port = frontend_config().map_or_else(|| config.get_http_port(), |published| published.http_port);
+            Self::from_config(config, http_port)
+        })
+    }
+
+    /// Check an upgrade request's `Origin` header and URL query
+    pub fn check(&self, origin: Option<&str>, query: Option<&str>) -> Result<(), Rejection> {
+        if let Some(origin) = origin {
+            if !self.allowed_origins.iter().any(|allowed| allowed == origin) {
+                warn!("Refused WebSocket connection from origin {}", origin);
+                return Err(Rejection { status: 403, message: "Origin not allowed" });
+            }
+        }
+        if let Some(expected) = &self.token {
+            let token = form_urlencoded::parse(query.unwrap_or_default().as_bytes())
+                .find(|(key, _)| key == "token")
+                .map(|(_, value)| value.into_owned());
+            if !token.is_some_and(|given| constant_time_eq(given.as_bytes(), expected.as_bytes())) {
+                warn!("Refused WebSocket connection without a valid token");
+                return Err(Rejection { status: 401, message: "Missing or invalid token" });
+            }
+        }
+        Ok(())
+    }
+
+    /// The inline script handing the token to the page as `window.__WS_TOKEN__`
+    pub fn token_script(&self) -> Option<String> {
+        self.token
+            .as_ref()
+            .map(|token| format!("window.__WS_TOKEN__ = \"{}\";", token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_origin_and_token() {
+        let open = WsAccessPolicy::new(8080, vec!["http://localhost:3000/".to_string()], None);
+        assert_eq!(open.check(None, None), Ok(()));
+        assert_eq!(open.check(Some("http://127.0.0.1:8080"), None), Ok(()));
+        assert_eq!(open.check(Some("http://localhost:3000"), None), Ok(()));
+        assert_eq!(open.check(Some("http://localhost:8081"), None).unwrap_err().status, 403);
+        assert_eq!(open.check(Some("https://example.com"), None).unwrap_err().status, 403);
+
+        let locked = WsAccessPolicy::new(8080, Vec::new(), Some("secret".to_string()));
+        assert_eq!(locked.check(Some("http://localhost:8080"), Some("token=secret")), Ok(()));
+        assert_eq!(locked.check(None, Some("a=1&token=secret")), Ok(()));
+        assert_eq!(locked.check(None, None).unwrap_err().status, 401);
+        assert_eq!(locked.check(Some("http://localhost:8080"), Some("token=guess")).unwrap_err().status, 401);
+        assert_eq!(locked.check(Some("https://example.com"), Some("token=secret")).unwrap_err().status, 403);
+        assert!(locked.token_script().unwrap().contains("secret"));
+    }
+}
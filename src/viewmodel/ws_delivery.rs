@@ -0,0 +1,256 @@
+//! At-least-once delivery of critical events to WebSocket clients
+//!
+//! Events matching `[delivery] reliable_events` are not simply forwarded.
+//! Every session that said `hello` with the `events` feature gets them
+//! numbered in an outbox of its own, and each is sent with its `seq` and kept
+//! until the client answers `ack { seq }`. One not acknowledged within
+//! `ack_timeout_ms` is sent again, up to `max_attempts` times in all. An
+//! outbox outlives its connection for `[sessions] resume_window_secs`, so
+//! what was queued or in flight when the connection dropped goes out again
+//! once the session reconnects. A client may therefore see an event twice;
+//! the `webui.js` bridge drops repeats by `seq`. Connections without a session
+//! get these events like any other, unnumbered.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::warn;
+use crate::infrastructure::event_bus::event_matches;
+use crate::model::core::AppConfig;
+use crate::viewmodel::ws_fanout::OutgoingEvent;
+
+/// Connection command acknowledging `seq`
+pub const ACK: &str = "ack";
+
+struct Unacked {
+    event: Arc<OutgoingEvent>,
+    /// `None` until sent on the current connection
+    sent_at: Option<Instant>,
+    attempts: u32,
+}
+
+struct Outbox {
+    next_seq: u64,
+    unacked: BTreeMap<u64, Unacked>,
+    connections: usize,
+    detached_at: Option<Instant>,
+}
+
+pub struct Deliveries {
+    patterns: Vec<String>,
+    ack_timeout: Duration,
+    max_attempts: u32,
+    max_unacked: usize,
+    window: Duration,
+    outboxes: Mutex<HashMap<String, Outbox>>,
+}
+
+impl Deliveries {
+    pub fn new(patterns: Vec<String>, ack_timeout: Duration, max_attempts: u32, max_unacked: usize, window: Duration) -> Self {
+        Self { patterns, ack_timeout, max_attempts, max_unacked, window, outboxes: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn global() -> &'static Deliveries {
+        static DELIVERIES: OnceLock<Deliveries> = OnceLock::new();
+        DELIVERIES.get_or_init(|| {
+            let config = AppConfig::global();
+            Self::new(
+                config.get_delivery_reliable_events(),
+                Duration::from_millis(config.get_delivery_ack_timeout_ms()),
+                config.get_delivery_max_attempts(),
+                config.get_delivery_max_unacked(),
+                Duration::from_secs(config.get_session_resume_window_secs()),
+            )
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Outbox>> {
+        self.outboxes.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Whether events called `name` go through the outboxes
+    pub fn is_reliable(&self, name: &str) -> bool {
+        self.patterns.iter().any(|pattern| event_matches(pattern, name))
+    }
+
+    /// A connection of `session` opened; everything still unacknowledged is sent on it
+    pub fn attach(&self, session: &str) {
+        if self.patterns.is_empty() {
+            return;
+        }
+        let mut outboxes = self.lock();
+        let outbox = outboxes.entry(session.to_string()).or_insert_with(|| Outbox {
+            next_seq: 1,
+            unacked: BTreeMap::new(),
+            connections: 0,
+            detached_at: None,
+        });
+        outbox.connections += 1;
+        outbox.detached_at = None;
+        for unacked in outbox.unacked.values_mut() {
+            unacked.sent_at = None;
+        }
+    }
+
+    pub fn detach(&self, session: &str) {
+        let mut outboxes = self.lock();
+        if let Some(outbox) = outboxes.get_mut(session) {
+            outbox.connections = outbox.connections.saturating_sub(1);
+            if outbox.connections == 0 {
+                outbox.detached_at = Some(Instant::now());
+            }
+        }
+        self.prune(&mut outboxes);
+    }
+
+    /// Queue a frame sent to clients in every outbox, if it is reliable
+    pub fn record(&self, frame: &Arc<OutgoingEvent>) {
+        if !self.is_reliable(&frame.name) {
+            return;
+        }
+        let mut outboxes = self.lock();
+        self.prune(&mut outboxes);
+        for (session, outbox) in outboxes.iter_mut() {
+            if outbox.unacked.len() >= self.max_unacked {
+                if let Some((seq, dropped)) = outbox.unacked.pop_first() {
+                    warn!("Outbox of session {} is full, dropping {} #{}", session, dropped.event.name, seq);
+                }
+            }
+            outbox.unacked.insert(outbox.next_seq, Unacked { event: Arc::clone(frame), sent_at: None, attempts: 0 });
+            outbox.next_seq += 1;
+        }
+    }
+
+    /// Events of `session` to send now: those not sent on this connection yet
+    /// and those whose acknowledgement is overdue
+    pub fn due(&self, session: &str) -> Vec<(u64, Arc<OutgoingEvent>)> {
+        let mut outboxes = self.lock();
+        let Some(outbox) = outboxes.get_mut(session) else {
+            return Vec::new();
+        };
+        let now = Instant::now();
+        let mut due = Vec::new();
+        outbox.unacked.retain(|seq, unacked| {
+            if unacked.sent_at.is_some_and(|sent| now.duration_since(sent) < self.ack_timeout) {
+                return true;
+            }
+            if unacked.attempts >= self.max_attempts {
+                warn!("Session {} did not acknowledge {} #{} after {} attempts, giving up", session, unacked.event.name, seq, unacked.attempts);
+                return false;
+            }
+            unacked.attempts += 1;
+            unacked.sent_at = Some(now);
+            due.push((*seq, Arc::clone(&unacked.event)));
+            true
+        });
+        due
+    }
+
+    /// When the next acknowledgement of `session` is overdue
+    pub fn next_retry(&self, session: &str) -> Option<Instant> {
+        let outboxes = self.lock();
+        let outbox = outboxes.get(session)?;
+        outbox.unacked.values().filter_map(|unacked| unacked.sent_at).min().map(|sent| sent + self.ack_timeout)
+    }
+
+    pub fn ack(&self, session: &str, seq: u64) -> bool {
+        self.lock().get_mut(session).is_some_and(|outbox| outbox.unacked.remove(&seq).is_some())
+    }
+
+    /// Drop the outboxes of sessions gone for longer than the window
+    fn prune(&self, outboxes: &mut HashMap<String, Outbox>) {
+        outboxes.retain(|_, outbox| outbox.detached_at.is_none_or(|detached| detached.elapsed() < self.window));
+    }
+}
+
+/// The outbox a connection delivers from, once its session said `hello`
+#[derive(Default)]
+pub struct ConnectionOutbox {
+    session: Option<String>,
+}
+
+impl ConnectionOutbox {
+    pub fn attach(&mut self, session: &str) {
+        if self.session.as_deref() == Some(session) {
+            return;
+        }
+        self.detach();
+        Deliveries::global().attach(session);
+        self.session = Some(session.to_string());
+    }
+
+    pub fn detach(&mut self) {
+        if let Some(session) = self.session.take() {
+            Deliveries::global().detach(&session);
+        }
+    }
+
+    /// Whether an event reaches this connection through the outbox rather than as it is
+    pub fn covers(&self, name: &str) -> bool {
+        self.session.is_some() && Deliveries::global().is_reliable(name)
+    }
+
+    pub fn due(&self) -> Vec<(u64, Arc<OutgoingEvent>)> {
+        self.session.as_deref().map(|session| Deliveries::global().due(session)).unwrap_or_default()
+    }
+
+    pub fn next_retry(&self) -> Option<Instant> {
+        self.session.as_deref().and_then(|session| Deliveries::global().next_retry(session))
+    }
+
+    pub fn ack(&self, seq: u64) -> bool {
+        self.session.as_deref().is_some_and(|session| Deliveries::global().ack(session, seq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::event_bus::Event;
+
+    fn frame(name: &str) -> Arc<OutgoingEvent> {
+        Arc::new(OutgoingEvent::new(Event::new(name.to_string(), serde_json::Value::Null, "backend".to_string()), 0).unwrap())
+    }
+
+    fn deliveries(ack_timeout: Duration) -> Deliveries {
+        Deliveries::new(vec!["task.*".to_string()], ack_timeout, 2, 8, Duration::from_secs(60))
+    }
+
+    fn seqs(due: Vec<(u64, Arc<OutgoingEvent>)>) -> Vec<u64> {
+        due.into_iter().map(|(seq, _)| seq).collect()
+    }
+
+    #[test]
+    fn test_unacknowledged_events_are_sent_again_until_attempts_run_out() {
+        let deliveries = deliveries(Duration::ZERO);
+        deliveries.attach("s1");
+        deliveries.record(&frame("task.completed"));
+        deliveries.record(&frame("counter.changed"));
+        deliveries.record(&frame("task.failed"));
+
+        assert_eq!(seqs(deliveries.due("s1")), [1, 2]);
+        assert!(deliveries.ack("s1", 1));
+        assert!(!deliveries.ack("s1", 1));
+        // Overdue at once with no timeout; the second attempt is the last
+        assert_eq!(seqs(deliveries.due("s1")), [2]);
+        assert!(deliveries.due("s1").is_empty());
+        assert!(deliveries.next_retry("s1").is_none());
+    }
+
+    #[test]
+    fn test_outbox_waits_for_a_reconnect() {
+        let deliveries = deliveries(Duration::from_secs(60));
+        deliveries.attach("s1");
+        deliveries.record(&frame("task.completed"));
+        assert_eq!(seqs(deliveries.due("s1")), [1]);
+        assert!(deliveries.due("s1").is_empty());
+        assert!(deliveries.next_retry("s1").is_some());
+
+        // Lost with the connection, and queued while away
+        deliveries.detach("s1");
+        deliveries.record(&frame("task.cancelled"));
+        deliveries.attach("s1");
+        assert_eq!(seqs(deliveries.due("s1")), [1, 2]);
+        assert!(deliveries.due("s2").is_empty());
+    }
+}
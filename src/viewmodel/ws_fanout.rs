@@ -0,0 +1,140 @@
+//! Event frames shared by every WebSocket connection
+//!
+//! One task listens to the EventBus and serializes each event into its client
+//! frame once; connections receive the frame behind an `Arc` and send its
+//! bytes as they are. The deflated form is made once too, by the first
+//! connection that negotiated compression.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::io;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{error, warn};
+use tungstenite::{Bytes, Utf8Bytes};
+use crate::infrastructure::event_bus::{Event, EventBus};
+use crate::viewmodel::compression;
+use crate::viewmodel::ws_delivery::Deliveries;
+use crate::viewmodel::ws_resume::ResumeBuffers;
+
+/// Frames a connection may fall behind before it skips some
+const CAPACITY: usize = 256;
+
+/// Wire shape of `WebSocketEvent`, borrowing from the event
+#[derive(Serialize)]
+struct Frame<'a> {
+    id: &'a str,
+    name: &'a str,
+    payload: &'a Value,
+    timestamp: u64,
+    source: &'a str,
+}
+
+/// A bus event serialized for WebSocket clients
+pub struct OutgoingEvent {
+    pub id: String,
+    pub name: String,
+    pub payload: Arc<Value>,
+    text: Utf8Bytes,
+    deflated: OnceLock<Result<Bytes, String>>,
+}
+
+impl OutgoingEvent {
+    pub fn new(event: Event, timestamp: u64) -> serde_json::Result<Self> {
+        let text = serde_json::to_string(&Frame {
+            id: &event.id,
+            name: &event.name,
+            payload: &event.payload,
+            timestamp,
+            source: &event.source,
+        })?;
+        Ok(Self { id: event.id, name: event.name, payload: event.payload, text: text.into(), deflated: OnceLock::new() })
+    }
+
+    /// The JSON frame; clones share its bytes
+    pub fn text(&self) -> Utf8Bytes {
+        self.text.clone()
+    }
+
+    /// The JSON frame numbered with its `seq` in a session's outbox, see `ws_delivery`
+    pub fn sequenced(&self, seq: u64) -> String {
+        // The frame is a serialized struct, so it opens with `{` and has fields
+        format!("{{\"seq\":{},{}", seq, &self.text.as_str()[1..])
+    }
+
+    /// The zlib-compressed JSON frame
+    pub fn deflated(&self) -> io::Result<Bytes> {
+        self.deflated
+            .get_or_init(|| compression::deflate(self.text.as_bytes()).map(Bytes::from).map_err(|e| e.to_string()))
+            .clone()
+            .map_err(io::Error::other)
+    }
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+static FRAMES: OnceLock<broadcast::Sender<Arc<OutgoingEvent>>> = OnceLock::new();
+
+/// WebSocket connections receiving event frames. Unlike `EventBus::listener_count`
+/// this leaves out the fan-out task itself and other backend listeners.
+pub fn connections() -> usize {
+    FRAMES.get().map_or(0, broadcast::Sender::receiver_count)
+}
+
+/// Frames of every event not sent by a frontend, from now on
+pub fn subscribe() -> broadcast::Receiver<Arc<OutgoingEvent>> {
+    FRAMES
+        .get_or_init(|| {
+            let (frames, _) = broadcast::channel(CAPACITY);
+            let sender = frames.clone();
+            let mut events = futures::executor::block_on(EventBus::global().listen());
+            tokio::spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(event) if event.source == "frontend" => {}
+                        Ok(event) => match OutgoingEvent::new(event, now_millis()) {
+                            Ok(frame) => {
+                                let frame = Arc::new(frame);
+                                ResumeBuffers::global().record(&frame);
+                                Deliveries::global().record(&frame);
+                                // No connection listening is fine
+                                drop(sender.send(frame));
+                            }
+                            Err(e) => error!("Failed to serialize event to JSON: {}", e),
+                        },
+                        Err(RecvError::Lagged(skipped)) => warn!("WebSocket fan-out fell behind the event bus, skipped {} events", skipped),
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+            frames
+        })
+        .subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_matches_websocket_event() {
+        let event = Event::new("data.changed".to_string(), serde_json::json!({ "table": "users" }), "backend".to_string());
+        let id = event.id.clone();
+        let outgoing = OutgoingEvent::new(event, 42).unwrap();
+        let frame: crate::viewmodel::websocket_handler::WebSocketEvent = serde_json::from_str(outgoing.text().as_str()).unwrap();
+        assert_eq!((frame.id, frame.name.as_str(), frame.timestamp), (id, "data.changed", 42));
+        assert_eq!(frame.payload, serde_json::json!({ "table": "users" }));
+
+        let deflated = outgoing.deflated().unwrap();
+        assert_eq!(compression::inflate(&deflated).unwrap(), outgoing.text().as_bytes());
+        // Made once, then shared
+        assert_eq!(outgoing.deflated().unwrap().as_ptr(), deflated.as_ptr());
+
+        let sequenced: Value = serde_json::from_str(&outgoing.sequenced(7)).unwrap();
+        assert_eq!((sequenced["seq"].as_u64(), sequenced["name"].as_str()), (Some(7), Some("data.changed")));
+    }
+}
@@ -0,0 +1,132 @@
+//! Acknowledgements and outcomes of client mutations, for optimistic updates
+//!
+//! A command sent with a `mutation_id` in its envelope (any client-chosen,
+//! non-empty string) is a mutation. Before it runs, the connection gets
+//! `command.accepted { mutation_id, command }`; once it ran, and before its
+//! response, `command.applied { mutation_id, command, result }` or
+//! `command.rejected { mutation_id, command, error }`. When the command
+//! changes a state document (`users` for the user commands, `counters` for
+//! the counter ones), both outcomes also carry that `document` with its
+//! `version` and `state` as they are after the command, so a client showing
+//! the change ahead of time can drop its guess and take the real state, or
+//! roll back to it. Reporting a document keeps it current from then on, as
+//! `state.subscribe` does. The outcome frames have ids of their own; only
+//! the response has the id of the request, so it still settles the request.
+
+use serde_json::{json, Value};
+use tracing::warn;
+use crate::core::domain::clock;
+use crate::infrastructure::event_bus::AppEventType;
+use crate::infrastructure::state_sync::{self, StateStore};
+use crate::viewmodel::websocket_handler::WebSocketEvent;
+
+/// The state document a command changes, if any
+fn document_of(command: &str) -> Option<&'static str> {
+    match command {
+        "create_user" | "update_user" | "delete_user" => Some(state_sync::USERS),
+        "counter.create" | "counter.increment" | "counter.decrement" | "counter.reset" => Some(state_sync::COUNTERS),
+        _ => None,
+    }
+}
+
+/// A command sent with a `mutation_id`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mutation {
+    id: String,
+    command: String,
+}
+
+impl Mutation {
+    pub fn of(event: &WebSocketEvent) -> Option<Self> {
+        let id = event.mutation_id.as_deref().filter(|id| !id.is_empty())?;
+        Some(Self { id: id.to_string(), command: event.name.clone() })
+    }
+
+    pub fn accepted(&self) -> WebSocketEvent {
+        frame(AppEventType::CommandAccepted, json!({ "mutation_id": self.id, "command": self.command }))
+    }
+
+    /// `command.applied` or `command.rejected` for the command's `response`
+    pub async fn outcome(&self, response: Option<&Value>) -> WebSocketEvent {
+        let response = response.unwrap_or(&Value::Null);
+        let mut payload = outcome_payload(&self.id, &self.command, response);
+        if let Some(document) = document_of(&self.command) {
+            let store = StateStore::global();
+            // The command's own change becomes a version before it is reported
+            store.refresh(document).await;
+            match store.snapshot(document).await {
+                Ok((version, state)) => {
+                    payload["document"] = document.into();
+                    payload["version"] = version.into();
+                    payload["state"] = state;
+                }
+                Err(e) => warn!("Mutation {} goes out without the {} state: {}", self.id, document, e),
+            }
+        }
+        let event = if payload.get("error").is_some() { AppEventType::CommandRejected } else { AppEventType::CommandApplied };
+        frame(event, payload)
+    }
+}
+
+/// The outcome without the state: rejected when `response` says `success: false`
+fn outcome_payload(mutation_id: &str, command: &str, response: &Value) -> Value {
+    if response["success"] == false {
+        json!({ "mutation_id": mutation_id, "command": command, "error": response["error"] })
+    } else {
+        json!({ "mutation_id": mutation_id, "command": command, "result": response })
+    }
+}
+
+fn frame(event: AppEventType, payload: Value) -> WebSocketEvent {
+    WebSocketEvent {
+        id: clock::new_id(),
+        name: event.to_string(),
+        payload,
+        timestamp: clock::now_millis(),
+        source: "backend".to_string(),
+        mutation_id: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(name: &str, mutation_id: Option<&str>) -> WebSocketEvent {
+        WebSocketEvent {
+            id: "req-1".to_string(),
+            name: name.to_string(),
+            payload: json!({}),
+            timestamp: 0,
+            source: "frontend".to_string(),
+            mutation_id: mutation_id.map(String::from),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mutations_are_acknowledged_then_settled() {
+        assert_eq!(Mutation::of(&event("get_users", None)), None);
+        assert_eq!(Mutation::of(&event("get_users", Some(""))), None);
+
+        let mutation = Mutation::of(&event("echo", Some("m-1"))).unwrap();
+        let accepted = mutation.accepted();
+        assert_eq!((accepted.name, accepted.mutation_id), (AppEventType::CommandAccepted.to_string(), None));
+        assert_ne!(accepted.id, "req-1");
+        assert_eq!(accepted.payload, json!({ "mutation_id": "m-1", "command": "echo" }));
+
+        let applied = mutation.outcome(Some(&json!({ "success": true, "echo": 1 }))).await;
+        assert_eq!(applied.name, AppEventType::CommandApplied.to_string());
+        assert_eq!(applied.payload["result"]["echo"], 1);
+        assert!(applied.payload.get("document").is_none());
+        assert_eq!(mutation.outcome(None).await.name, AppEventType::CommandApplied.to_string());
+
+        let error = json!({ "code": "ValidationFailed", "message": "no" });
+        let rejected = mutation.outcome(Some(&json!({ "success": false, "error": error }))).await;
+        assert_eq!(rejected.name, AppEventType::CommandRejected.to_string());
+        assert_eq!(rejected.payload, json!({ "mutation_id": "m-1", "command": "echo", "error": error }));
+
+        assert_eq!(document_of("update_user"), Some(state_sync::USERS));
+        assert_eq!(document_of("counter.reset"), Some(state_sync::COUNTERS));
+        assert_eq!(document_of("counter.list"), None);
+    }
+}
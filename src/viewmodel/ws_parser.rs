@@ -0,0 +1,195 @@
+//! Parsing of incoming WebSocket messages within explicit limits
+//!
+//! Text messages, deflated binary messages and the JSON header of binary file
+//! frames all go through `parse`. JSON longer than `max_json_bytes` or nested
+//! deeper than `max_depth` arrays and objects is refused before serde sees it,
+//! so a client cannot make the backend allocate or recurse without bound. The
+//! socket itself refuses any message over `max_message_bytes`, which bounds
+//! file frames too.
+//!
+//! Depends on serde and thiserror only, so the targets in `fuzz/` compile this file as is.
+
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// First bytes of every binary file frame
+pub const FRAME_MAGIC: &[u8; 4] = b"WUBF";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageLimits {
+    /// Largest WebSocket message the socket accepts, file frames included
+    pub max_message_bytes: usize,
+    /// Largest JSON message or frame header
+    pub max_json_bytes: usize,
+    /// Deepest nesting of arrays and objects in a JSON message
+    pub max_depth: usize,
+}
+
+impl Default for MessageLimits {
+    fn default() -> Self {
+        Self {
+            max_message_bytes: 16 << 20,
+            max_json_bytes: 1 << 20,
+            max_depth: 32,
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("Message is {size} bytes, the limit is {limit}")]
+    TooLarge { size: usize, limit: usize },
+    #[error("Message is nested deeper than {limit} levels")]
+    TooDeep { limit: usize },
+    #[error("{0}")]
+    Invalid(String),
+}
+
+impl ParseError {
+    /// `error_type` of the error response for a refused message, `None` when
+    /// it is simply not valid
+    pub fn error_type(&self) -> Option<&'static str> {
+        match self {
+            ParseError::TooLarge { .. } => Some("MESSAGE_TOO_LARGE"),
+            ParseError::TooDeep { .. } => Some("MESSAGE_TOO_DEEP"),
+            ParseError::Invalid(_) => None,
+        }
+    }
+}
+
+/// Deserialize `json` if it is within `limits`
+pub fn parse<T: DeserializeOwned>(json: &[u8], limits: &MessageLimits) -> Result<T, ParseError> {
+    if json.len() > limits.max_json_bytes {
+        return Err(ParseError::TooLarge { size: json.len(), limit: limits.max_json_bytes });
+    }
+    check_depth(json, limits.max_depth)?;
+    serde_json::from_slice(json).map_err(|e| ParseError::Invalid(e.to_string()))
+}
+
+/// Scan for `[` and `{` outside strings without parsing, in one pass
+fn check_depth(json: &[u8], max_depth: usize) -> Result<(), ParseError> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for &byte in json {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max_depth {
+                    return Err(ParseError::TooDeep { limit: max_depth });
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Split a binary file frame into its JSON header and its data:
+/// `FRAME_MAGIC`, the header length as a big-endian u32, the header, the data
+pub fn split_frame(frame: &[u8]) -> Result<(&[u8], &[u8]), ParseError> {
+    let rest = frame
+        .strip_prefix(FRAME_MAGIC)
+        .ok_or_else(|| ParseError::Invalid("missing magic".to_string()))?;
+    if rest.len() < 4 {
+        return Err(ParseError::Invalid("truncated header length".to_string()));
+    }
+    let (length, rest) = rest.split_at(4);
+    let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+    if rest.len() < length {
+        return Err(ParseError::Invalid("truncated header".to_string()));
+    }
+    Ok(rest.split_at(length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::viewmodel::websocket_handler::WebSocketEvent;
+    use proptest::prelude::*;
+    use serde_json::Value;
+
+    fn nested(depth: usize) -> String {
+        format!("{}1{}", "[".repeat(depth), "]".repeat(depth))
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = MessageLimits { max_json_bytes: 64, max_depth: 4, ..MessageLimits::default() };
+        assert!(parse::<Value>(nested(4).as_bytes(), &limits).is_ok());
+        assert_eq!(parse::<Value>(nested(5).as_bytes(), &limits), Err(ParseError::TooDeep { limit: 4 }));
+        // Brackets inside strings, escaped quotes included, are not nesting
+        assert!(parse::<Value>(br#"{"a": "[[[[[\"{{{{{"}"#, &limits).is_ok());
+        let long = format!("\"{}\"", "x".repeat(70));
+        assert_eq!(parse::<Value>(long.as_bytes(), &limits), Err(ParseError::TooLarge { size: 72, limit: 64 }));
+        assert_eq!(ParseError::TooLarge { size: 72, limit: 64 }.error_type(), Some("MESSAGE_TOO_LARGE"));
+        assert_eq!(parse::<Value>(b"{", &limits).unwrap_err().error_type(), None);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_arbitrary_bytes_never_panic(bytes in proptest::collection::vec(any::<u8>(), 0..512)) {
+            let _ = parse::<Value>(&bytes, &MessageLimits::default());
+            if let Ok((header, _)) = split_frame(&bytes) {
+                let _ = parse::<Value>(header, &MessageLimits::default());
+            }
+        }
+
+        #[test]
+        fn prop_depth_limit_is_exact(depth in 0usize..64, max_depth in 1usize..48) {
+            let limits = MessageLimits { max_depth, ..MessageLimits::default() };
+            let parsed = parse::<Value>(nested(depth).as_bytes(), &limits);
+            prop_assert_eq!(parsed.is_ok(), depth <= max_depth);
+        }
+
+        #[test]
+        fn prop_frames_split_where_they_were_joined(
+            header in "[ -~]{0,64}",
+            data in proptest::collection::vec(any::<u8>(), 0..256),
+        ) {
+            let mut frame = FRAME_MAGIC.to_vec();
+            frame.extend_from_slice(&(header.len() as u32).to_be_bytes());
+            frame.extend_from_slice(header.as_bytes());
+            frame.extend_from_slice(&data);
+            let (split_header, split_data) = split_frame(&frame).unwrap();
+            prop_assert_eq!(split_header, header.as_bytes());
+            prop_assert_eq!(split_data, &data[..]);
+            prop_assert!(split_frame(&frame[..frame.len().min(7)]).is_err());
+        }
+
+        #[test]
+        fn prop_events_round_trip(
+            id in "[a-z0-9-]{0,36}",
+            name in "[a-z_.]{1,32}",
+            text in any::<String>(),
+            items in proptest::collection::vec(any::<i32>(), 0..16),
+            timestamp in any::<u64>(),
+        ) {
+            let event = WebSocketEvent {
+                id,
+                name,
+                payload: serde_json::json!({ "text": text, "items": items }),
+                timestamp,
+                source: "frontend".to_string(),
+                mutation_id: None,
+            };
+            let json = serde_json::to_vec(&event).unwrap();
+            let parsed: WebSocketEvent = parse(&json, &MessageLimits::default()).unwrap();
+            prop_assert_eq!(parsed.id, event.id);
+            prop_assert_eq!(parsed.name, event.name);
+            prop_assert_eq!(parsed.payload, event.payload);
+            prop_assert_eq!(parsed.timestamp, event.timestamp);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Events a client missed while it was reconnecting
+//!
+//! Every session that said `hello` gets a buffer of the last
+//! `[sessions] resume_buffer_events` event frames sent to clients. It keeps
+//! filling after the session's last connection closed, for
+//! `resume_window_secs`. A client reconnecting within that window with
+//! `hello { session, resume_from }`, `resume_from` being the id of the last
+//! event it received, is sent the events after it again, right after the
+//! `hello` answer. When that event has left the buffer, or the session or its
+//! buffer is gone, the answer tells the client to refresh its state instead.
+
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use crate::model::core::AppConfig;
+use crate::viewmodel::ws_fanout::OutgoingEvent;
+
+/// What a reconnecting client gets, as told in the `hello` answer
+pub enum Resume {
+    /// The events after the client's last one, oldest first
+    Replay(Vec<Arc<OutgoingEvent>>),
+    /// Events were lost; the client should load its state afresh
+    Refresh(&'static str),
+}
+
+impl Resume {
+    /// `resume` of the `hello` answer
+    pub fn to_json(&self) -> Value {
+        match self {
+            Resume::Replay(events) => serde_json::json!({ "status": "replayed", "events": events.len() }),
+            Resume::Refresh(reason) => serde_json::json!({ "status": "refresh", "reason": reason }),
+        }
+    }
+}
+
+struct Buffer {
+    events: VecDeque<Arc<OutgoingEvent>>,
+    connections: usize,
+    /// When the last connection of the session closed
+    detached_at: Option<Instant>,
+}
+
+pub struct ResumeBuffers {
+    capacity: usize,
+    window: Duration,
+    sessions: Mutex<HashMap<String, Buffer>>,
+}
+
+impl ResumeBuffers {
+    pub fn new(capacity: usize, window: Duration) -> Self {
+        Self { capacity, window, sessions: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn global() -> &'static ResumeBuffers {
+        static BUFFERS: OnceLock<ResumeBuffers> = OnceLock::new();
+        BUFFERS.get_or_init(|| {
+            let config = AppConfig::global();
+            Self::new(config.get_session_resume_buffer_events(), Duration::from_secs(config.get_session_resume_window_secs()))
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Buffer>> {
+        self.sessions.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// A connection of `session` opened; its buffer fills from now on
+    pub fn attach(&self, session: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut sessions = self.lock();
+        let buffer = sessions.entry(session.to_string()).or_insert_with(|| Buffer {
+            events: VecDeque::with_capacity(self.capacity),
+            connections: 0,
+            detached_at: None,
+        });
+        buffer.connections += 1;
+        buffer.detached_at = None;
+    }
+
+    /// A connection of `session` closed; after its last one the buffer is kept for the window
+    pub fn detach(&self, session: &str) {
+        let mut sessions = self.lock();
+        if let Some(buffer) = sessions.get_mut(session) {
+            buffer.connections = buffer.connections.saturating_sub(1);
+            if buffer.connections == 0 {
+                buffer.detached_at = Some(Instant::now());
+            }
+        }
+        self.prune(&mut sessions);
+    }
+
+    /// Keep a frame sent to clients in every buffer
+    pub fn record(&self, frame: &Arc<OutgoingEvent>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut sessions = self.lock();
+        self.prune(&mut sessions);
+        for buffer in sessions.values_mut() {
+            if buffer.events.len() == self.capacity {
+                buffer.events.pop_front();
+            }
+            buffer.events.push_back(Arc::clone(frame));
+        }
+    }
+
+    /// The events `session` was sent after `last_event_id`
+    pub fn resume(&self, session: &str, last_event_id: &str) -> Resume {
+        if self.capacity == 0 {
+            return Resume::Refresh("resume_disabled");
+        }
+        let mut sessions = self.lock();
+        self.prune(&mut sessions);
+        let Some(buffer) = sessions.get(session) else {
+            return Resume::Refresh("buffer_expired");
+        };
+        match buffer.events.iter().position(|event| event.id == last_event_id) {
+            Some(last) => Resume::Replay(buffer.events.iter().skip(last + 1).cloned().collect()),
+            None => Resume::Refresh("events_lost"),
+        }
+    }
+
+    /// Drop the buffers of sessions gone for longer than the window
+    fn prune(&self, sessions: &mut HashMap<String, Buffer>) {
+        sessions.retain(|_, buffer| buffer.detached_at.is_none_or(|detached| detached.elapsed() < self.window));
+    }
+}
+
+/// One connection's part in resuming: the session it fills a buffer for, the
+/// frames still to replay, and the ids replayed that live delivery must skip
+#[derive(Default)]
+pub struct ConnectionReplay {
+    attached: Option<String>,
+    pending: Vec<Arc<OutgoingEvent>>,
+    replayed: HashSet<String>,
+}
+
+impl ConnectionReplay {
+    /// Fill the buffer of `session` while this connection is open
+    pub fn attach(&mut self, session: &str) {
+        if self.attached.as_deref() == Some(session) {
+            return;
+        }
+        self.detach();
+        ResumeBuffers::global().attach(session);
+        self.attached = Some(session.to_string());
+    }
+
+    pub fn detach(&mut self) {
+        if let Some(session) = self.attached.take() {
+            ResumeBuffers::global().detach(&session);
+        }
+    }
+
+    pub fn queue(&mut self, events: Vec<Arc<OutgoingEvent>>) {
+        self.pending.extend(events);
+    }
+
+    /// The frames to replay now
+    pub fn take(&mut self) -> Vec<Arc<OutgoingEvent>> {
+        let pending = std::mem::take(&mut self.pending);
+        self.replayed.extend(pending.iter().map(|event| event.id.clone()));
+        pending
+    }
+
+    /// Whether a live event went out already as part of the replay
+    pub fn was_replayed(&mut self, id: &str) -> bool {
+        !self.replayed.is_empty() && self.replayed.remove(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::event_bus::Event;
+
+    fn frame(name: &str) -> Arc<OutgoingEvent> {
+        Arc::new(OutgoingEvent::new(Event::new(name.to_string(), Value::Null, "backend".to_string()), 0).unwrap())
+    }
+
+    fn names(resume: &Resume) -> Vec<&str> {
+        match resume {
+            Resume::Replay(events) => events.iter().map(|event| event.name.as_str()).collect(),
+            Resume::Refresh(reason) => vec![*reason],
+        }
+    }
+
+    #[test]
+    fn test_reconnecting_session_gets_the_events_after_its_last() {
+        let buffers = ResumeBuffers::new(3, Duration::from_secs(60));
+        buffers.record(&frame("before.attach"));
+        buffers.attach("s1");
+        let first = frame("one");
+        buffers.record(&first);
+        buffers.detach("s1");
+        // While the client is away
+        buffers.record(&frame("two"));
+        buffers.record(&frame("three"));
+
+        assert_eq!(names(&buffers.resume("s1", &first.id)), ["two", "three"]);
+        assert_eq!(names(&buffers.resume("s2", &first.id)), ["buffer_expired"]);
+
+        // The oldest events leave a full buffer
+        buffers.record(&frame("four"));
+        assert_eq!(names(&buffers.resume("s1", &first.id)), ["events_lost"]);
+    }
+
+    #[test]
+    fn test_buffers_of_departed_sessions_expire() {
+        let buffers = ResumeBuffers::new(8, Duration::ZERO);
+        buffers.attach("s1");
+        let event = frame("one");
+        buffers.record(&event);
+        assert_eq!(names(&buffers.resume("s1", &event.id)), Vec::<&str>::new());
+        buffers.detach("s1");
+        assert_eq!(names(&buffers.resume("s1", &event.id)), ["buffer_expired"]);
+
+        let disabled = ResumeBuffers::new(0, Duration::from_secs(60));
+        disabled.attach("s1");
+        assert_eq!(names(&disabled.resume("s1", &event.id)), ["resume_disabled"]);
+    }
+}
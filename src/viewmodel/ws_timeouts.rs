@@ -0,0 +1,147 @@
+//! How long a WebSocket connection may wait, per endpoint path
+//!
+//! Every connection gets `[server]`'s handshake, idle, write and lifetime
+//! limits. Any path under which clients connect can override the last three
+//! in `[server.ws_endpoints."/path"]`, e.g. to let a long-lived dashboard
+//! sit idle or to recycle tool connections hourly; the longest matching path
+//! wins. The handshake limit is the same for all, since the path is only
+//! known once the upgrade request has been read.
+
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::time::{timeout, Instant};
+use crate::model::core::{AppConfig, WsEndpointSettings};
+
+/// The limits a connection runs under once upgraded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionTimeouts {
+    /// Closed after this long without a message either way; `None` never
+    pub idle: Option<Duration>,
+    /// A message the client has not taken within this long ends the connection
+    pub write: Duration,
+    /// Closed with "try again later" this long after opening; `None` never
+    pub max_lifetime: Option<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimeoutPolicy {
+    handshake: Duration,
+    default: ConnectionTimeouts,
+    /// Path and overrides, longest path first
+    endpoints: Vec<(String, WsEndpointSettings)>,
+}
+
+impl TimeoutPolicy {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let mut endpoints: Vec<(String, WsEndpointSettings)> = config.get_ws_endpoints().into_iter().collect();
+        endpoints.sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+        Self {
+            handshake: Duration::from_secs(config.get_ws_handshake_timeout_secs()),
+            default: ConnectionTimeouts {
+                idle: unlimited_if_zero(config.get_ws_idle_timeout_secs()),
+                write: Duration::from_secs(config.get_ws_write_timeout_secs()),
+                max_lifetime: unlimited_if_zero(config.get_ws_max_lifetime_secs()),
+            },
+            endpoints,
+        }
+    }
+
+    pub fn global() -> &'static TimeoutPolicy {
+        static POLICY: OnceLock<TimeoutPolicy> = OnceLock::new();
+        POLICY.get_or_init(|| Self::from_config(AppConfig::global()))
+    }
+
+    pub fn handshake(&self) -> Duration {
+        self.handshake
+    }
+
+    /// The limits for a connection upgraded at `path`
+    pub fn for_path(&self, path: &str) -> ConnectionTimeouts {
+        let Some((_, endpoint)) = self.endpoints.iter().find(|(prefix, _)| path_matches(prefix, path)) else {
+            return self.default;
+        };
+        ConnectionTimeouts {
+            idle: endpoint.idle_timeout_secs.map_or(self.default.idle, unlimited_if_zero),
+            write: endpoint.write_timeout_secs.map_or(self.default.write, |secs| Duration::from_secs(secs.max(1))),
+            max_lifetime: endpoint.max_lifetime_secs.map_or(self.default.max_lifetime, unlimited_if_zero),
+        }
+    }
+}
+
+/// `0` turns a limit off
+fn unlimited_if_zero(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// `prefix` is `path` or one of its parent paths
+fn path_matches(prefix: &str, path: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+/// Completes at `deadline`, or never without one
+pub async fn until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Run a write, failing with `TimedOut` once it has taken longer than `limit`
+#[allow(clippy::result_large_err)]
+pub async fn write_within<T>(
+    limit: Duration,
+    write: impl Future<Output = tungstenite::Result<T>>,
+) -> tungstenite::Result<T> {
+    timeout(limit, write).await.unwrap_or_else(|_| {
+        Err(tungstenite::Error::Io(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            format!("client took no data for {} seconds", limit.as_secs()),
+        )))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_longest_matching_endpoint_overrides_the_defaults() {
+        let mut config = AppConfig::default();
+        config.server.ws_idle_timeout_secs = Some(300);
+        config.server.ws_max_lifetime_secs = Some(0);
+        config.server.ws_endpoints = Some(BTreeMap::from([
+            ("/tools".to_string(), WsEndpointSettings { max_lifetime_secs: Some(3600), ..Default::default() }),
+            (
+                "/tools/dashboard/".to_string(),
+                WsEndpointSettings { idle_timeout_secs: Some(0), write_timeout_secs: Some(0), ..Default::default() },
+            ),
+        ]));
+        let policy = TimeoutPolicy::from_config(&config);
+
+        let default = policy.for_path("/");
+        assert_eq!(default, ConnectionTimeouts { idle: Some(Duration::from_secs(300)), write: Duration::from_secs(10), max_lifetime: None });
+        assert_eq!(policy.for_path("/toolsmith"), default);
+
+        let tools = policy.for_path("/tools/ctl");
+        assert_eq!(tools.max_lifetime, Some(Duration::from_secs(3600)));
+        assert_eq!(tools.idle, default.idle);
+
+        // Overrides do not stack: the dashboard keeps the default lifetime
+        let dashboard = policy.for_path("/tools/dashboard");
+        assert_eq!(dashboard, ConnectionTimeouts { idle: None, write: Duration::from_secs(1), max_lifetime: None });
+        assert_eq!(policy.handshake(), Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_a_stalled_write_times_out() {
+        let stalled = write_within(Duration::from_millis(20), std::future::pending::<tungstenite::Result<()>>()).await;
+        assert!(matches!(stalled, Err(tungstenite::Error::Io(e)) if e.kind() == std::io::ErrorKind::TimedOut));
+        assert!(write_within(Duration::from_millis(20), async { Ok(()) }).await.is_ok());
+    }
+}